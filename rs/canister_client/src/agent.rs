@@ -646,6 +646,7 @@ mod tests {
                 sender: Blob(sender.get().into_vec()),
                 nonce: None,
                 ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                height: None,
             },
         };
         // Workaround because HttpQueryContent is not cloneable
@@ -698,6 +699,7 @@ mod tests {
                 sender: Blob(sender_id.get().into_vec()),
                 nonce: None,
                 ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                height: None,
             },
         };
         // Workaround because HttpQueryContent is not cloneable
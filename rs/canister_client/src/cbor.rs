@@ -187,6 +187,7 @@ impl Agent {
                 sender: self.sender_field.clone(),
                 nonce: None,
                 ingress_expiry: current_time_and_expiry_time().1.as_nanos_since_unix_epoch(),
+                height: None,
             },
         };
 
@@ -137,6 +137,7 @@ impl Execution {
                 allocated_bytes,
                 allocated_message_bytes,
                 instance_stats,
+                system_api_call_counters,
             },
             deltas,
             instance_or_system_api,
@@ -200,6 +201,7 @@ impl Execution {
                     allocated_message_bytes,
                     num_instructions_left,
                     instance_stats,
+                    system_api_call_counters,
                 };
                 self.sandbox_manager.controller.execution_finished(
                     protocol::ctlsvc::ExecutionFinishedRequest {
@@ -221,6 +223,7 @@ impl Execution {
                     allocated_bytes,
                     allocated_message_bytes,
                     instance_stats,
+                    system_api_call_counters,
                 };
 
                 self.sandbox_manager.controller.execution_finished(
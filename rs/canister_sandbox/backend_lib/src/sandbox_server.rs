@@ -44,9 +44,14 @@ impl SandboxService for SandboxServer {
         &self,
         req: OpenWasmSerializedRequest,
     ) -> rpc::Call<OpenWasmSerializedReply> {
-        let result = self
-            .manager
-            .open_wasm_serialized(req.wasm_id, &req.serialized_module)
+        let wasm_id = req.wasm_id;
+        let result = req
+            .serialized_module
+            .load()
+            .and_then(|serialized_module| {
+                self.manager
+                    .open_wasm_serialized(wasm_id, &serialized_module)
+            })
             .map(|_| ());
         rpc::Call::new_resolved(Ok(OpenWasmSerializedReply(result)))
     }
@@ -119,13 +124,22 @@ impl SandboxService for SandboxServer {
         &self,
         req: CreateExecutionStateSerializedRequest,
     ) -> rpc::Call<CreateExecutionStateSerializedReply> {
-        let result = self.manager.create_execution_state_serialized(
-            req.wasm_id,
-            req.serialized_module,
-            req.wasm_page_map,
-            req.next_wasm_memory_id,
-            req.canister_id,
-        );
+        let CreateExecutionStateSerializedRequest {
+            wasm_id,
+            serialized_module,
+            wasm_page_map,
+            next_wasm_memory_id,
+            canister_id,
+        } = req;
+        let result = serialized_module.load().and_then(|serialized_module| {
+            self.manager.create_execution_state_serialized(
+                wasm_id,
+                serialized_module,
+                wasm_page_map,
+                next_wasm_memory_id,
+                canister_id,
+            )
+        });
         rpc::Call::new_resolved(Ok(CreateExecutionStateSerializedReply(result)))
     }
 }
@@ -163,6 +177,7 @@ mod tests {
         methods::{FuncRef, WasmMethod},
         time::Time,
         ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions,
+        MAX_WASM64_MEMORY_IN_BYTES,
     };
     use mockall::*;
     use std::collections::{BTreeMap, BTreeSet};
@@ -181,6 +196,7 @@ mod tests {
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
+            wasm64_memory_limit: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
         }
     }
 
@@ -1,22 +1,24 @@
 //! This defines the RPC service methods offered by the sandbox process
 //! (used by the controller) as well as the expected replies.
 
-use std::{sync::Arc, time::Duration};
+use std::{fs::File, os::unix::io::FromRawFd, sync::Arc, time::Duration};
 
 use crate::fdenum::EnumerateInnerFileDescriptors;
 use crate::protocol::structs;
-use ic_embedders::{CompilationResult, SerializedModule, SerializedModuleBytes};
+use ic_embedders::{
+    read_persisted_module, CompilationResult, SerializedModule, SerializedModuleBytes,
+};
 use ic_interfaces::execution_environment::HypervisorResult;
 use ic_replicated_state::{
     page_map::{
-        CheckpointSerialization, MappingSerialization, PageAllocatorSerialization,
+        CheckpointSerialization, FileDescriptor, MappingSerialization, PageAllocatorSerialization,
         PageMapSerialization,
     },
     Global, NumWasmPages,
 };
 use ic_types::CanisterId;
 use ic_utils;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use super::{
     id::{ExecId, MemoryId, WasmId},
@@ -59,6 +61,68 @@ pub struct OpenWasmRequest {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OpenWasmReply(pub HypervisorResult<(CompilationResult, SerializedModule)>);
 
+/// A previously compiled module sent to the sandbox.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(bound = "T: Serialize + DeserializeOwned")]
+pub enum SerializedModuleSource<T> {
+    /// The module is part of the request. It is wrapped in an `Arc` only so
+    /// that we can cheaply create the request before sending it to the
+    /// sandbox.
+    InMemory(
+        #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
+        #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
+        Arc<T>,
+    ),
+    /// An entry of the on-disk compilation cache. The sandbox reads and
+    /// verifies the module itself, so that the replica never has to load it.
+    OnDisk(FileDescriptor),
+}
+
+impl<T> EnumerateInnerFileDescriptors for SerializedModuleSource<T> {
+    fn enumerate_fds<'a>(&'a mut self, fds: &mut Vec<&'a mut std::os::unix::io::RawFd>) {
+        match self {
+            SerializedModuleSource::InMemory(_) => {}
+            SerializedModuleSource::OnDisk(file_descriptor) => fds.push(&mut file_descriptor.fd),
+        }
+    }
+}
+
+impl SerializedModuleSource<SerializedModule> {
+    /// Returns the module, reading it from the on-disk compilation cache if
+    /// necessary. Must be called at most once by the sandbox because it takes
+    /// ownership of the received file descriptor.
+    pub fn load(self) -> HypervisorResult<Arc<SerializedModule>> {
+        match self {
+            SerializedModuleSource::InMemory(serialized_module) => Ok(serialized_module),
+            SerializedModuleSource::OnDisk(file_descriptor) => {
+                read_received_module(file_descriptor).map(Arc::new)
+            }
+        }
+    }
+}
+
+impl SerializedModuleSource<SerializedModuleBytes> {
+    /// Returns the serialized `wasmtime::Module`, reading it from the on-disk
+    /// compilation cache if necessary. Must be called at most once by the
+    /// sandbox because it takes ownership of the received file descriptor.
+    pub fn load(self) -> HypervisorResult<Arc<SerializedModuleBytes>> {
+        match self {
+            SerializedModuleSource::InMemory(bytes) => Ok(bytes),
+            SerializedModuleSource::OnDisk(file_descriptor) => {
+                read_received_module(file_descriptor)
+                    .map(|serialized_module| serialized_module.bytes)
+            }
+        }
+    }
+}
+
+fn read_received_module(file_descriptor: FileDescriptor) -> HypervisorResult<SerializedModule> {
+    // SAFETY: The file descriptor was installed by the transport when the
+    // request was received and is not owned by anything else.
+    let file = unsafe { File::from_raw_fd(file_descriptor.fd) };
+    read_persisted_module(file)
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OpenWasmSerializedRequest {
     /// Id used to later refer to this canister runner. Must be unique
@@ -66,12 +130,13 @@ pub struct OpenWasmSerializedRequest {
     pub wasm_id: WasmId,
 
     /// The serialization of a previously compiled `wasmtime::Module`.
-    /// This types in just an `Arc` reference to a vector of bytes and the only
-    /// reason it is `Arc` is so that we can cheaply create the
-    /// `OpenWasmSerializedRequest` before sending it to the sandbox.
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
-    pub serialized_module: Arc<SerializedModuleBytes>,
+    pub serialized_module: SerializedModuleSource<SerializedModuleBytes>,
+}
+
+impl EnumerateInnerFileDescriptors for OpenWasmSerializedRequest {
+    fn enumerate_fds<'a>(&'a mut self, fds: &mut Vec<&'a mut std::os::unix::io::RawFd>) {
+        self.serialized_module.enumerate_fds(fds);
+    }
 }
 
 /// Reply to an `OpenWasmRequest`.
@@ -127,6 +192,9 @@ impl EnumerateInnerFileDescriptors for CheckpointSerialization {
         if let Some(mapping) = self.mapping.as_mut() {
             mapping.enumerate_fds(fds)
         }
+        for overlay in self.overlays.iter_mut() {
+            overlay.enumerate_fds(fds)
+        }
     }
 }
 
@@ -266,12 +334,7 @@ pub struct CreateExecutionStateReply(pub HypervisorResult<CreateExecutionStateSu
 pub struct CreateExecutionStateSerializedRequest {
     pub wasm_id: WasmId,
     /// The serialization of a previously compiled `wasmtime::Module`.
-    /// This types in just an `Arc` reference to a vector of bytes and the only
-    /// reason it is `Arc` is so that we can cheaply create the
-    /// `CreateExecutionStateSerializedRequest` before sending it to the sandbox.
-    #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
-    #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
-    pub serialized_module: Arc<SerializedModule>,
+    pub serialized_module: SerializedModuleSource<SerializedModule>,
     pub wasm_page_map: PageMapSerialization,
     pub next_wasm_memory_id: MemoryId,
     pub canister_id: CanisterId,
@@ -279,6 +342,7 @@ pub struct CreateExecutionStateSerializedRequest {
 
 impl EnumerateInnerFileDescriptors for CreateExecutionStateSerializedRequest {
     fn enumerate_fds<'a>(&'a mut self, fds: &mut Vec<&'a mut std::os::unix::io::RawFd>) {
+        self.serialized_module.enumerate_fds(fds);
         self.wasm_page_map.enumerate_fds(fds);
     }
 }
@@ -316,12 +380,12 @@ pub enum Request {
 impl EnumerateInnerFileDescriptors for Request {
     fn enumerate_fds<'a>(&'a mut self, fds: &mut Vec<&'a mut std::os::unix::io::RawFd>) {
         match self {
+            Request::OpenWasmSerialized(request) => request.enumerate_fds(fds),
             Request::OpenMemory(request) => request.enumerate_fds(fds),
             Request::CreateExecutionState(request) => request.enumerate_fds(fds),
             Request::CreateExecutionStateSerialized(request) => request.enumerate_fds(fds),
             Request::Terminate(_)
             | Request::OpenWasm(_)
-            | Request::OpenWasmSerialized(_)
             | Request::CloseWasm(_)
             | Request::CloseMemory(_)
             | Request::StartExecution(_)
@@ -1,7 +1,7 @@
 use ic_canister_sandbox_common::controller_launcher_service::ControllerLauncherService;
 use ic_canister_sandbox_common::launcher_service::LauncherService;
 use ic_canister_sandbox_common::protocol::id::{ExecId, MemoryId, WasmId};
-use ic_canister_sandbox_common::protocol::sbxsvc::MemorySerialization;
+use ic_canister_sandbox_common::protocol::sbxsvc::{MemorySerialization, SerializedModuleSource};
 use ic_canister_sandbox_common::protocol::structs::{SandboxExecInput, SandboxExecOutput};
 use ic_canister_sandbox_common::sandbox_service::SandboxService;
 use ic_canister_sandbox_common::{protocol, rpc};
@@ -10,9 +10,11 @@ use ic_embedders::wasm_executor::{
     get_wasm_reserved_pages, CanisterStateChanges, PausedWasmExecution, SliceExecutionOutput,
     WasmExecutionResult, WasmExecutor,
 };
-use ic_embedders::{CompilationCache, CompilationResult, WasmExecutionInput};
+use ic_embedders::{
+    CachedModule, CompilationCache, CompilationResult, SerializedModule, WasmExecutionInput,
+};
 use ic_interfaces::execution_environment::{
-    HypervisorError, HypervisorResult, InstanceStats, WasmExecutionOutput,
+    HypervisorError, HypervisorResult, InstanceStats, SystemApiCallCounters, WasmExecutionOutput,
 };
 use ic_logger::{error, warn, ReplicaLogger};
 use ic_metrics::buckets::decimal_buckets_with_zero;
@@ -20,12 +22,15 @@ use ic_metrics::MetricsRegistry;
 use ic_replicated_state::canister_state::execution_state::{
     SandboxMemory, SandboxMemoryHandle, SandboxMemoryOwner, WasmBinary,
 };
+use ic_replicated_state::page_map::FileDescriptor;
 use ic_replicated_state::{EmbedderCache, ExecutionState, ExportedFunctions, Memory, PageMap};
 use ic_types::{CanisterId, NumBytes, NumInstructions};
 use ic_wasm_types::CanisterModule;
 use prometheus::{Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge};
 use std::collections::{HashMap, VecDeque};
 use std::convert::TryInto;
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
 use std::path::PathBuf;
 use std::process::ExitStatus;
 use std::sync::Weak;
@@ -516,6 +521,7 @@ impl WasmExecutor for SandboxedExecutionController {
                                 accessed_pages: 0,
                                 dirty_pages: 0,
                             },
+                            system_api_call_counters: SystemApiCallCounters::default(),
                         },
                         None,
                     ),
@@ -624,12 +630,76 @@ impl WasmExecutor for SandboxedExecutionController {
         let wasm_binary = WasmBinary::new(canister_module);
 
         // Steps 1, 2, 3, 4 are performed by the sandbox process.
-        let wasm_id = WasmId::new();
+        let mut wasm_id = WasmId::new();
         let wasm_page_map = PageMap::default();
-        let next_wasm_memory_id = MemoryId::new();
+        let mut next_wasm_memory_id = MemoryId::new();
+
+        let cached = match compilation_cache.get(&wasm_binary.binary) {
+            None => None,
+            Some(cached_module) => {
+                self.metrics.inc_cache_lookup(COMPILATION_CACHE_HIT);
+                let _deserialization_timer = self
+                    .metrics
+                    .sandboxed_execution_replica_create_exe_state_wait_deserialize_duration
+                    .start_timer();
+                sandbox_process.history.record(format!(
+                    "CreateExecutionStateSerialized(wasm_id={}, next_wasm_memory_id={})",
+                    wasm_id, next_wasm_memory_id
+                ));
+                let result = serialized_module_source(&cached_module, Arc::clone).and_then(
+                    |(serialized_module, _file)| {
+                        sandbox_process
+                            .sandbox_service
+                            .create_execution_state_serialized(
+                                protocol::sbxsvc::CreateExecutionStateSerializedRequest {
+                                    wasm_id,
+                                    serialized_module,
+                                    wasm_page_map: wasm_page_map.serialize(),
+                                    next_wasm_memory_id,
+                                    canister_id,
+                                },
+                            )
+                            .sync()
+                            .unwrap()
+                            .0
+                    },
+                );
+                match result {
+                    Err(err) if matches!(cached_module, CachedModule::OnDisk(_)) => {
+                        // The persisted module is unusable, so compile the
+                        // Wasm module again. Fresh ids are used in case the
+                        // sandbox has registered the old ones.
+                        warn!(
+                            self.logger,
+                            "Failed to load persisted module of canister {}: {}", canister_id, err
+                        );
+                        compilation_cache.remove(&wasm_binary.binary);
+                        wasm_id = WasmId::new();
+                        next_wasm_memory_id = MemoryId::new();
+                        None
+                    }
+                    result => {
+                        let sandbox_result = result?;
+                        self.metrics
+                            .sandboxed_execution_sandbox_create_exe_state_deserialize_total_duration
+                            .observe(sandbox_result.total_sandbox_time.as_secs_f64());
+                        self.metrics
+                            .sandboxed_execution_sandbox_create_exe_state_deserialize_duration
+                            .observe(sandbox_result.deserialization_time.as_secs_f64());
+                        Some((
+                            sandbox_result.wasm_memory_modifications,
+                            sandbox_result.exported_globals,
+                            cached_module,
+                            None,
+                        ))
+                    }
+                }
+            }
+        };
 
-        let (memory_modifications, exported_globals, serialized_module, compilation_result) =
-            match compilation_cache.get(&wasm_binary.binary) {
+        let (memory_modifications, exported_globals, cached_module, compilation_result) =
+            match cached {
+                Some(cached) => cached,
                 None => {
                     self.metrics.inc_cache_lookup(CACHE_MISS);
                     let _compilation_timer = self
@@ -657,47 +727,10 @@ impl WasmExecutor for SandboxedExecutionController {
                     (
                         reply.wasm_memory_modifications,
                         reply.exported_globals,
-                        serialized_module,
+                        CachedModule::InMemory(serialized_module),
                         Some(reply.compilation_result),
                     )
                 }
-                Some(serialized_module) => {
-                    self.metrics.inc_cache_lookup(COMPILATION_CACHE_HIT);
-                    let _deserialization_timer = self
-                        .metrics
-                        .sandboxed_execution_replica_create_exe_state_wait_deserialize_duration
-                        .start_timer();
-                    sandbox_process.history.record(format!(
-                        "CreateExecutionStateSerialized(wasm_id={}, next_wasm_memory_id={})",
-                        wasm_id, next_wasm_memory_id
-                    ));
-                    let sandbox_result = sandbox_process
-                        .sandbox_service
-                        .create_execution_state_serialized(
-                            protocol::sbxsvc::CreateExecutionStateSerializedRequest {
-                                wasm_id,
-                                serialized_module: Arc::clone(&serialized_module),
-                                wasm_page_map: wasm_page_map.serialize(),
-                                next_wasm_memory_id,
-                                canister_id,
-                            },
-                        )
-                        .sync()
-                        .unwrap()
-                        .0?;
-                    self.metrics
-                        .sandboxed_execution_sandbox_create_exe_state_deserialize_total_duration
-                        .observe(sandbox_result.total_sandbox_time.as_secs_f64());
-                    self.metrics
-                        .sandboxed_execution_sandbox_create_exe_state_deserialize_duration
-                        .observe(sandbox_result.deserialization_time.as_secs_f64());
-                    (
-                        sandbox_result.wasm_memory_modifications,
-                        sandbox_result.exported_globals,
-                        serialized_module,
-                        None,
-                    )
-                }
             };
         let _finish_timer = self
             .metrics
@@ -734,15 +767,15 @@ impl WasmExecutor for SandboxedExecutionController {
         let execution_state = ExecutionState::new(
             canister_root,
             wasm_binary,
-            ExportedFunctions::new(serialized_module.exported_functions.clone()),
+            ExportedFunctions::new(cached_module.exported_functions().clone()),
             wasm_memory,
             stable_memory,
             exported_globals,
-            serialized_module.wasm_metadata.clone(),
+            cached_module.wasm_metadata().clone(),
         );
         Ok((
             execution_state,
-            serialized_module.compilation_cost,
+            cached_module.compilation_cost(),
             compilation_result,
         ))
     }
@@ -1115,48 +1148,98 @@ fn open_wasm(
         }
     }
 
-    let wasm_id = WasmId::new();
-    match compilation_cache.get(&wasm_binary.binary) {
-        None => {
-            metrics.inc_cache_lookup(CACHE_MISS);
-            sandbox_process
-                .history
-                .record(format!("OpenWasm(wasm_id={})", wasm_id));
-            match sandbox_process
-                .sandbox_service
-                .open_wasm(protocol::sbxsvc::OpenWasmRequest {
-                    wasm_id,
-                    wasm_src: wasm_binary.binary.as_slice().to_vec(),
+    if let Some(cached_module) = compilation_cache.get(&wasm_binary.binary) {
+        metrics.inc_cache_lookup(COMPILATION_CACHE_HIT);
+        let wasm_id = WasmId::new();
+        sandbox_process
+            .history
+            .record(format!("OpenWasmSerialized(wasm_id={})", wasm_id));
+        match cached_module {
+            CachedModule::InMemory(serialized_module) => {
+                sandbox_process
+                    .sandbox_service
+                    .open_wasm_serialized(protocol::sbxsvc::OpenWasmSerializedRequest {
+                        wasm_id,
+                        serialized_module: SerializedModuleSource::InMemory(Arc::clone(
+                            &serialized_module.bytes,
+                        )),
+                    })
+                    .on_completion(|_| ());
+                cache_opened_wasm(&mut *embedder_cache, sandbox_process, wasm_id);
+                return Ok((wasm_id, None));
+            }
+            CachedModule::OnDisk(_) => {
+                // Unlike above, wait for the sandbox to read the persisted
+                // module: if it is unusable, the Wasm module is compiled
+                // again below.
+                let result = serialized_module_source(&cached_module, |serialized_module| {
+                    Arc::clone(&serialized_module.bytes)
                 })
-                .sync()
-                .unwrap()
-                .0
-            {
-                Ok((compilation_result, serialized_module)) => {
+                .and_then(|(serialized_module, _file)| {
+                    sandbox_process
+                        .sandbox_service
+                        .open_wasm_serialized(protocol::sbxsvc::OpenWasmSerializedRequest {
+                            wasm_id,
+                            serialized_module,
+                        })
+                        .sync()
+                        .unwrap()
+                        .0
+                });
+                if result.is_ok() {
                     cache_opened_wasm(&mut *embedder_cache, sandbox_process, wasm_id);
-                    compilation_cache.insert(&wasm_binary.binary, Arc::new(serialized_module));
-                    Ok((wasm_id, Some(compilation_result)))
-                }
-                Err(err) => {
-                    cache_errored_wasm(&mut *embedder_cache, err.clone());
-                    Err(err)
+                    return Ok((wasm_id, None));
                 }
+                compilation_cache.remove(&wasm_binary.binary);
             }
         }
-        Some(serialized_module) => {
-            metrics.inc_cache_lookup(COMPILATION_CACHE_HIT);
-            sandbox_process
-                .history
-                .record(format!("OpenWasmSerialized(wasm_id={})", wasm_id));
-            sandbox_process
-                .sandbox_service
-                .open_wasm_serialized(protocol::sbxsvc::OpenWasmSerializedRequest {
-                    wasm_id,
-                    serialized_module: Arc::clone(&serialized_module.bytes),
-                })
-                .on_completion(|_| ());
+    }
+
+    metrics.inc_cache_lookup(CACHE_MISS);
+    let wasm_id = WasmId::new();
+    sandbox_process
+        .history
+        .record(format!("OpenWasm(wasm_id={})", wasm_id));
+    match sandbox_process
+        .sandbox_service
+        .open_wasm(protocol::sbxsvc::OpenWasmRequest {
+            wasm_id,
+            wasm_src: wasm_binary.binary.as_slice().to_vec(),
+        })
+        .sync()
+        .unwrap()
+        .0
+    {
+        Ok((compilation_result, serialized_module)) => {
             cache_opened_wasm(&mut *embedder_cache, sandbox_process, wasm_id);
-            Ok((wasm_id, None))
+            compilation_cache.insert(&wasm_binary.binary, Arc::new(serialized_module));
+            Ok((wasm_id, Some(compilation_result)))
+        }
+        Err(err) => {
+            cache_errored_wasm(&mut *embedder_cache, err.clone());
+            Err(err)
+        }
+    }
+}
+
+/// Returns the source of a cached module to send to the sandbox along with the
+/// file of a persisted module, which has to stay open until the sandbox has
+/// replied to the request.
+fn serialized_module_source<T>(
+    cached_module: &CachedModule,
+    in_memory: impl FnOnce(&Arc<SerializedModule>) -> Arc<T>,
+) -> HypervisorResult<(SerializedModuleSource<T>, Option<File>)> {
+    match cached_module {
+        CachedModule::InMemory(serialized_module) => Ok((
+            SerializedModuleSource::InMemory(in_memory(serialized_module)),
+            None,
+        )),
+        CachedModule::OnDisk(on_disk_module) => {
+            let file = on_disk_module.open()?;
+            let file_descriptor = FileDescriptor {
+                fd: file.as_raw_fd(),
+            };
+            Ok((SerializedModuleSource::OnDisk(file_descriptor), Some(file)))
         }
     }
 }
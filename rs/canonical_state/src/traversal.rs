@@ -658,6 +658,7 @@ mod tests {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                max_wasm64_memory_size: NumBytes::from(0),
             },
             subnet_test_id(1) => SubnetTopology {
                 public_key: vec![5, 6, 7, 8],
@@ -665,6 +666,7 @@ mod tests {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                max_wasm64_memory_size: NumBytes::from(0),
             }
         };
         fn id_range(from: u64, to: u64) -> CanisterIdRange {
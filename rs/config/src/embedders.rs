@@ -31,6 +31,8 @@ const DEFAULT_WASMTIME_RAYON_COMPILATION_THREADS: usize = 10;
 pub struct FeatureFlags {
     pub rate_limiting_of_debug_prints: FlagStatus,
     pub module_sharing: FlagStatus,
+    /// Indicates whether Wasm modules with a 64-bit memory are accepted.
+    pub wasm64: FlagStatus,
 }
 
 impl Default for FeatureFlags {
@@ -38,6 +40,7 @@ impl Default for FeatureFlags {
         Self {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             module_sharing: FlagStatus::Enabled,
+            wasm64: FlagStatus::Disabled,
         }
     }
 }
@@ -8,8 +8,10 @@ use ic_types::{
     Cycles, NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
-const GB: u64 = 1024 * 1024 * 1024;
+const MB: u64 = 1024 * 1024;
+const GB: u64 = 1024 * MB;
 
 /// This is the upper limit on how much logical storage canisters can request to
 /// be store on a given subnet.
@@ -51,6 +53,14 @@ const INGRESS_HISTORY_MEMORY_CAPACITY: NumBytes = NumBytes::new(10 * GB);
 /// memory can succeed.
 pub(crate) const SUBNET_HEAP_DELTA_CAPACITY: NumBytes = NumBytes::new(150 * GB);
 
+/// The default upper limit on the estimated size of the query cache.
+const QUERY_CACHE_CAPACITY: NumBytes = NumBytes::new(200 * MB);
+
+/// The default upper limit on the disk space used by the persistent
+/// compilation cache. Serialized modules are typically a few MiB, so this is
+/// enough to hold the modules of several thousand canisters.
+const COMPILATION_CACHE_CAPACITY: NumBytes = NumBytes::new(10 * GB);
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default)]
 pub struct Config {
@@ -118,6 +128,29 @@ pub struct Config {
     /// Compiling a single WASM instruction should cost as much as executing
     /// this many instructions.
     pub cost_to_compile_wasm_instruction: NumInstructions,
+
+    /// Indicates whether the results of user queries are cached until the
+    /// state they were computed on changes.
+    pub query_caching: FlagStatus,
+
+    /// The maximum estimated size of the query cache in bytes.
+    pub query_cache_capacity: NumBytes,
+
+    /// Indicates whether canisters can install Wasm modules with a 64-bit
+    /// memory.
+    pub wasm64: FlagStatus,
+
+    /// Indicates whether compiled Wasm modules are persisted on disk so that
+    /// they don't have to be recompiled after a restart of the replica.
+    pub persistent_compilation_cache: FlagStatus,
+
+    /// The maximum disk space used by the persistent compilation cache in
+    /// bytes.
+    pub compilation_cache_capacity: NumBytes,
+
+    /// The directory of the persistent compilation cache. If not set, compiled
+    /// modules are only kept in memory.
+    pub compilation_cache_dir: Option<PathBuf>,
 }
 
 impl Default for Config {
@@ -147,6 +180,12 @@ impl Default for Config {
             deterministic_time_slicing: FlagStatus::Disabled,
             module_sharing: FlagStatus::Enabled,
             cost_to_compile_wasm_instruction: embedders::DEFAULT_COST_TO_COMPILE_WASM_INSTRUCTION,
+            query_caching: FlagStatus::Disabled,
+            query_cache_capacity: QUERY_CACHE_CAPACITY,
+            wasm64: FlagStatus::Disabled,
+            persistent_compilation_cache: FlagStatus::Disabled,
+            compilation_cache_capacity: COMPILATION_CACHE_CAPACITY,
+            compilation_cache_dir: None,
         }
     }
 }
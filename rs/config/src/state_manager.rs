@@ -1,14 +1,31 @@
+use crate::flag_status::FlagStatus;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Config {
     state_root: PathBuf,
+    /// If enabled, a checkpoint stores the pages of each page map that changed
+    /// since the previous checkpoint in a new overlay file instead of
+    /// rewriting the whole page map file. Overlay files are periodically
+    /// merged in the background.
+    ///
+    /// The manifest of a checkpoint does not depend on this setting, so
+    /// replicas of the same subnet may use different settings.
+    #[serde(default = "incremental_checkpoints_default")]
+    pub incremental_checkpoints: FlagStatus,
+}
+
+fn incremental_checkpoints_default() -> FlagStatus {
+    FlagStatus::Disabled
 }
 
 impl Config {
     pub fn new(state_root: PathBuf) -> Self {
-        Self { state_root }
+        Self {
+            state_root,
+            incremental_checkpoints: incremental_checkpoints_default(),
+        }
     }
 
     pub fn state_root(&self) -> PathBuf {
@@ -56,6 +56,14 @@ const MAX_INSTRUCTIONS_PER_ROUND: NumInstructions = NumInstructions::new(7 * B);
 // with roughly 100MB of state, so we set the limit to 40x.
 const MAX_INSTRUCTIONS_PER_INSTALL_CODE: NumInstructions = NumInstructions::new(40 * 5 * B);
 
+// Limit per `install_code` message if deterministic time slicing is enabled.
+// The execution is spread over many rounds in slices of at most
+// `MAX_INSTRUCTIONS_PER_SLICE`, so the limit does not affect the round
+// duration and can be much larger than `MAX_INSTRUCTIONS_PER_INSTALL_CODE`.
+// This allows canisters with large state to serialize it to stable memory
+// during upgrade.
+const MAX_INSTRUCTIONS_PER_INSTALL_CODE_WITH_DTS: NumInstructions = NumInstructions::new(1_000 * B);
+
 // The factor to bump the instruction limit for system subnets.
 const SYSTEM_SUBNET_FACTOR: u64 = 10;
 
@@ -125,6 +133,11 @@ pub struct SchedulerConfig {
     /// Maximum number of instructions an `install_code` message can consume.
     pub max_instructions_per_install_code: NumInstructions,
 
+    /// Maximum number of instructions an `install_code` message can consume
+    /// if deterministic time slicing is enabled. Such a message may run over
+    /// many rounds.
+    pub max_instructions_per_install_code_with_dts: NumInstructions,
+
     /// This specifies the upper limit on how much heap delta all the canisters
     /// together on the subnet can produce in between checkpoints. This is a
     /// soft limit in the sense, that we will continue to execute canisters as
@@ -170,6 +183,7 @@ impl SchedulerConfig {
             instruction_overhead_per_canister_for_finalization:
                 INSTRUCTION_OVERHEAD_PER_CANISTER_FOR_FINALIZATION,
             max_instructions_per_install_code: MAX_INSTRUCTIONS_PER_INSTALL_CODE,
+            max_instructions_per_install_code_with_dts: MAX_INSTRUCTIONS_PER_INSTALL_CODE_WITH_DTS,
             max_heap_delta_per_iteration: MAX_HEAP_DELTA_PER_ITERATION,
             max_message_duration_before_warn_in_seconds:
                 MAX_MESSAGE_DURATION_BEFORE_WARN_IN_SECONDS,
@@ -190,6 +204,8 @@ impl SchedulerConfig {
             instruction_overhead_per_canister_for_finalization:
                 INSTRUCTION_OVERHEAD_PER_CANISTER_FOR_FINALIZATION,
             max_instructions_per_install_code,
+            max_instructions_per_install_code_with_dts: MAX_INSTRUCTIONS_PER_INSTALL_CODE_WITH_DTS
+                * SYSTEM_SUBNET_FACTOR,
             max_heap_delta_per_iteration: MAX_HEAP_DELTA_PER_ITERATION * SYSTEM_SUBNET_FACTOR,
             max_message_duration_before_warn_in_seconds:
                 MAX_MESSAGE_DURATION_BEFORE_WARN_IN_SECONDS,
@@ -213,6 +229,7 @@ impl SchedulerConfig {
             instruction_overhead_per_canister_for_finalization:
                 INSTRUCTION_OVERHEAD_PER_CANISTER_FOR_FINALIZATION,
             max_instructions_per_install_code: MAX_INSTRUCTIONS_PER_INSTALL_CODE,
+            max_instructions_per_install_code_with_dts: MAX_INSTRUCTIONS_PER_INSTALL_CODE_WITH_DTS,
             max_heap_delta_per_iteration: MAX_HEAP_DELTA_PER_ITERATION,
             max_message_duration_before_warn_in_seconds:
                 MAX_MESSAGE_DURATION_BEFORE_WARN_IN_SECONDS,
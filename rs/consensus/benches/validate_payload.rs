@@ -38,6 +38,7 @@ use ic_test_utilities::{
     consensus::{fake::*, make_genesis, MockConsensusCache},
     crypto::temp_crypto_component_with_fake_registry,
     cycles_account_manager::CyclesAccountManagerBuilder,
+    query_stats::FakeQueryStatsPayloadBuilder,
     self_validating_payload_builder::FakeSelfValidatingPayloadBuilder,
     state::ReplicatedStateBuilder,
     state_manager::MockStateManager,
@@ -158,6 +159,7 @@ where
             Arc::new(FakeXNetPayloadBuilder::new()),
             Arc::new(FakeSelfValidatingPayloadBuilder::new()),
             Arc::new(FakeCanisterHttpPayloadBuilder::new()),
+            Arc::new(FakeQueryStatsPayloadBuilder::new()),
             metrics_registry,
             no_op_logger(),
         ));
@@ -306,6 +308,7 @@ fn validate_payload(
 
     payload_builder.validate_payload(
         Height::from(CERTIFIED_HEIGHT + 1),
+        node_test_id(0),
         payload,
         &past_payloads,
         &validation_context,
@@ -58,6 +58,7 @@ use ic_interfaces::{
     ecdsa::EcdsaPool,
     ingress_manager::IngressSelector,
     messaging::{MessageRouting, XNetPayloadBuilder},
+    query_stats::QueryStatsPayloadBuilder,
     registry::{self, LocalStoreCertifiedTimeReader, RegistryClient},
     self_validating_payload::SelfValidatingPayloadBuilder,
     time_source::TimeSource,
@@ -138,6 +139,7 @@ impl ConsensusImpl {
         xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
         self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
         canister_http_payload_builder: Arc<dyn CanisterHttpPayloadBuilder>,
+        query_stats_payload_builder: Arc<dyn QueryStatsPayloadBuilder>,
         dkg_pool: Arc<RwLock<dyn DkgPool>>,
         ecdsa_pool: Arc<RwLock<dyn EcdsaPool>>,
         dkg_key_manager: Arc<Mutex<DkgKeyManager>>,
@@ -157,6 +159,7 @@ impl ConsensusImpl {
             xnet_payload_builder,
             self_validating_payload_builder,
             canister_http_payload_builder,
+            query_stats_payload_builder,
             metrics_registry.clone(),
             logger.clone(),
         ));
@@ -624,6 +627,7 @@ pub fn setup(
     xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
     self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
     canister_http_payload_builder: Arc<dyn CanisterHttpPayloadBuilder>,
+    query_stats_payload_builder: Arc<dyn QueryStatsPayloadBuilder>,
     dkg_pool: Arc<RwLock<dyn DkgPool>>,
     ecdsa_pool: Arc<RwLock<dyn EcdsaPool>>,
     dkg_key_manager: Arc<Mutex<DkgKeyManager>>,
@@ -658,6 +662,7 @@ pub fn setup(
             xnet_payload_builder,
             self_validating_payload_builder,
             canister_http_payload_builder,
+            query_stats_payload_builder,
             dkg_pool,
             ecdsa_pool,
             dkg_key_manager,
@@ -687,6 +692,7 @@ mod tests {
         canister_http::FakeCanisterHttpPayloadBuilder,
         ingress_selector::FakeIngressSelector,
         message_routing::FakeMessageRouting,
+        query_stats::FakeQueryStatsPayloadBuilder,
         self_validating_payload_builder::FakeSelfValidatingPayloadBuilder,
         types::ids::{node_test_id, subnet_test_id},
         xnet_payload_builder::FakeXNetPayloadBuilder,
@@ -750,6 +756,7 @@ mod tests {
             Arc::new(FakeXNetPayloadBuilder::new()),
             Arc::new(FakeSelfValidatingPayloadBuilder::new()),
             Arc::new(FakeCanisterHttpPayloadBuilder::new()),
+            Arc::new(FakeQueryStatsPayloadBuilder::new()),
             dkg_pool,
             ecdsa_pool,
             Arc::new(Mutex::new(DkgKeyManager::new(
@@ -4,7 +4,7 @@ use crate::consensus::metrics::{
 use ic_interfaces::{
     canister_http::CanisterHttpPayloadBuilder, consensus::PayloadValidationError,
     ingress_manager::IngressSelector, messaging::XNetPayloadBuilder,
-    self_validating_payload::SelfValidatingPayloadBuilder,
+    query_stats::QueryStatsPayloadBuilder, self_validating_payload::SelfValidatingPayloadBuilder,
 };
 use ic_logger::{error, warn, ReplicaLogger};
 use ic_types::{
@@ -12,7 +12,7 @@ use ic_types::{
         BatchPayload, CanisterHttpPayload, IngressPayload, SelfValidatingPayload, ValidationContext,
     },
     consensus::Payload,
-    CountBytes, Height, NumBytes, Time,
+    CountBytes, Height, NodeId, NumBytes, Time,
 };
 use std::sync::Arc;
 
@@ -40,6 +40,7 @@ pub(crate) enum BatchPayloadSectionBuilder {
     XNet(Arc<dyn XNetPayloadBuilder>),
     SelfValidating(Arc<dyn SelfValidatingPayloadBuilder>),
     CanisterHttp(Arc<dyn CanisterHttpPayloadBuilder>),
+    QueryStats(Arc<dyn QueryStatsPayloadBuilder>),
 }
 
 impl BatchPayloadSectionBuilder {
@@ -194,12 +195,59 @@ impl BatchPayloadSectionBuilder {
                     }
                 }
             }
+            Self::QueryStats(builder) => {
+                let past_payloads = builder.filter_past_payloads(past_payloads);
+                let query_stats = match builder.get_query_stats_payload(
+                    validation_context,
+                    &past_payloads,
+                    max_size,
+                ) {
+                    Some(query_stats) => query_stats,
+                    None => return NumBytes::new(0),
+                };
+                let size = NumBytes::new(query_stats.count_bytes() as u64);
+
+                // Validate the payload as a safety measure
+                if let Err(err) = builder.validate_query_stats_payload(
+                    query_stats.proposer,
+                    &query_stats,
+                    validation_context,
+                    &past_payloads,
+                ) {
+                    error!(
+                        logger,
+                        "QueryStats payload did not pass validation, this is a bug, {:?} @{}",
+                        err,
+                        CRITICAL_ERROR_VALIDATION_NOT_PASSED
+                    );
+
+                    metrics.critical_error_validation_not_passed.inc();
+                    payload.query_stats = None;
+                    return NumBytes::new(0);
+                }
+
+                if size > max_size {
+                    error!(
+                        logger,
+                        "QueryStatsPayload is larger than byte limits, this is a bug, @{}",
+                        CRITICAL_ERROR_PAYLOAD_TOO_LARGE
+                    );
+
+                    metrics.cricital_error_payload_too_large.inc();
+                    payload.query_stats = None;
+                    return NumBytes::new(0);
+                }
+
+                payload.query_stats = Some(query_stats);
+                size
+            }
         }
     }
 
     /// Called to validate the payload.
     ///
     /// # Argument:
+    /// - `proposer`: The node that made the block containing the payload.
     /// - `payload`: The payload to verify.
     /// - `validation_context`: The [`ValidationContext`], under which to validate the payload.
     /// - `past_payloads`: All [`Payload`]s from the certified height to the tip.
@@ -210,6 +258,7 @@ impl BatchPayloadSectionBuilder {
     pub(crate) fn validate_payload(
         &self,
         height: Height,
+        proposer: NodeId,
         payload: &BatchPayload,
         validation_context: &ValidationContext,
         past_payloads: &[(Height, Time, Payload)],
@@ -249,6 +298,18 @@ impl BatchPayloadSectionBuilder {
                     &past_payloads,
                 )?)
             }
+            Self::QueryStats(builder) => match &payload.query_stats {
+                Some(query_stats) => {
+                    let past_payloads = builder.filter_past_payloads(past_payloads);
+                    Ok(builder.validate_query_stats_payload(
+                        proposer,
+                        query_stats,
+                        validation_context,
+                        &past_payloads,
+                    )?)
+                }
+                None => Ok(NumBytes::new(0)),
+            },
         }
     }
 }
@@ -944,6 +944,7 @@ impl Validator {
 
         let parent = get_notarized_parent(pool_reader, proposal)?;
         self.verify_signature(pool_reader, proposal)?;
+        let proposal_signer = proposal.signature.signer;
 
         // Ensure registry_version, certified_height and time are non-decreasing.
         let proposal = proposal.as_ref();
@@ -979,6 +980,7 @@ impl Validator {
         self.payload_builder
             .validate_payload(
                 proposal.height,
+                proposal_signer,
                 &proposal.payload,
                 &payloads,
                 &proposal.context,
@@ -1947,12 +1949,12 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .withf(move |_, _, payloads, _| {
+                .withf(move |_, _, _, payloads, _| {
                     // Assert that payloads are from blocks between:
                     // `certified_height` and the current height (`prior_height`)
                     payloads.len() as u64 == (prior_height - certified_height).get()
                 })
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2060,7 +2062,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2157,7 +2159,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2279,7 +2281,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2355,7 +2357,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2719,7 +2721,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| {
+                .returning(|_, _, _, _, _| {
                     Err(ValidationError::Transient(
                         PayloadTransientError::XNetPayloadValidationError(
                             XNetTransientValidationError::StateNotCommittedYet(Height::from(0)),
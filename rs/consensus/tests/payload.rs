@@ -15,6 +15,7 @@ use ic_test_utilities::{
     crypto::CryptoReturningOk,
     ingress_selector::FakeIngressSelector,
     message_routing::FakeMessageRouting,
+    query_stats::FakeQueryStatsPayloadBuilder,
     self_validating_payload_builder::FakeSelfValidatingPayloadBuilder,
     state::get_initial_state,
     state_manager::MockStateManager,
@@ -53,6 +54,8 @@ fn consensus_produces_expected_batches() {
         let canister_http_payload_builder = FakeCanisterHttpPayloadBuilder::new();
         let canister_http_payload_builder = Arc::new(canister_http_payload_builder);
 
+        let query_stats_payload_builder = Arc::new(FakeQueryStatsPayloadBuilder::new());
+
         let mut state_manager = MockStateManager::new();
         state_manager.expect_remove_states_below().return_const(());
         state_manager
@@ -139,6 +142,7 @@ fn consensus_produces_expected_batches() {
             Arc::clone(&xnet_payload_builder) as Arc<_>,
             Arc::clone(&self_validating_payload_builder) as Arc<_>,
             Arc::clone(&canister_http_payload_builder) as Arc<_>,
+            Arc::clone(&query_stats_payload_builder) as Arc<_>,
             Arc::clone(&dkg_pool) as Arc<_>,
             Arc::clone(&ecdsa_pool) as Arc<_>,
             dkg_key_manager.clone(),
@@ -320,6 +320,47 @@ impl CyclesAccountManager {
         )
     }
 
+    /// Returns the largest number of instructions, up to `num_instructions`,
+    /// whose execution cost can be withdrawn from the canister's balance
+    /// without dropping below its freezing threshold.
+    pub fn affordable_execution_instructions(
+        &self,
+        system_state: &SystemState,
+        canister_current_memory_usage: NumBytes,
+        canister_compute_allocation: ComputeAllocation,
+        num_instructions: NumInstructions,
+        subnet_size: usize,
+    ) -> NumInstructions {
+        let threshold = self.freeze_threshold_cycles(
+            system_state.freeze_threshold,
+            system_state.memory_allocation,
+            canister_current_memory_usage,
+            canister_compute_allocation,
+            subnet_size,
+        );
+        let cycles_available = if system_state.balance() > threshold {
+            system_state.balance() - threshold
+        } else {
+            Cycles::zero()
+        };
+        if self.execution_cost(num_instructions, subnet_size) <= cycles_available {
+            return num_instructions;
+        }
+
+        // The execution cost grows monotonically with the number of
+        // instructions, so a binary search finds the largest affordable one.
+        let (mut low, mut high) = (0, num_instructions.get());
+        while low < high {
+            let mid = low + (high - low + 1) / 2;
+            if self.execution_cost(NumInstructions::from(mid), subnet_size) <= cycles_available {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        NumInstructions::from(low)
+    }
+
     /// Refunds the corresponding cycles worth of the provided
     /// `num_instructions` to the canister's balance.
     pub fn refund_execution_cycles(
@@ -609,8 +650,8 @@ impl CyclesAccountManager {
         }
     }
 
-    /// Note that this function is made public only for the tests.
-    #[doc(hidden)]
+    /// Refunds the given cycles to the canister's balance, e.g. the execution
+    /// cycles withdrawn for a message whose execution got aborted.
     pub fn refund_cycles(&self, system_state: &mut SystemState, cycles: Cycles) {
         *system_state.balance_mut() += cycles;
         system_state
@@ -647,6 +647,50 @@ fn withdraw_execution_cycles_consumes_cycles() {
     assert!(consumed_cycles_before < consumed_cycles_after);
 }
 
+#[test]
+fn affordable_execution_instructions_are_capped_by_balance() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let affordable = NumInstructions::from(1_000_000);
+    let balance = cycles_account_manager.execution_cost(affordable, SMALL_APP_SUBNET_MAX_SIZE);
+    let system_state = SystemStateBuilder::new()
+        .initial_cycles(balance)
+        .freeze_threshold(NumSeconds::from(0))
+        .build();
+
+    // The whole limit is affordable.
+    assert_eq!(
+        cycles_account_manager.affordable_execution_instructions(
+            &system_state,
+            NumBytes::from(0),
+            ComputeAllocation::default(),
+            NumInstructions::from(1_000),
+            SMALL_APP_SUBNET_MAX_SIZE,
+        ),
+        NumInstructions::from(1_000)
+    );
+
+    // Only a part of the limit is affordable.
+    let instructions = cycles_account_manager.affordable_execution_instructions(
+        &system_state,
+        NumBytes::from(0),
+        ComputeAllocation::default(),
+        NumInstructions::from(1_000_000_000),
+        SMALL_APP_SUBNET_MAX_SIZE,
+    );
+    assert!(instructions >= affordable);
+    assert!(
+        cycles_account_manager.execution_cost(instructions, SMALL_APP_SUBNET_MAX_SIZE) <= balance
+    );
+    assert!(
+        cycles_account_manager.execution_cost(
+            instructions + NumInstructions::from(10),
+            SMALL_APP_SUBNET_MAX_SIZE
+        ) > balance
+    );
+}
+
 #[test]
 fn withdraw_for_transfer_does_not_consume_cycles() {
     let mut system_state = SystemStateBuilder::new().build();
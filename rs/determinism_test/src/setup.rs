@@ -27,7 +27,9 @@ use ic_test_utilities::{consensus::fake::FakeVerifier, types::ids::subnet_test_i
 use ic_test_utilities_registry::{
     add_subnet_record, insert_initial_dkg_transcript, SubnetRecordBuilder,
 };
-use ic_types::{replica_config::ReplicaConfig, NodeId, PrincipalId, RegistryVersion, SubnetId};
+use ic_types::{
+    replica_config::ReplicaConfig, NodeId, PrincipalId, RegistryVersion, ReplicaVersion, SubnetId,
+};
 use std::sync::Arc;
 
 fn get_registry(
@@ -139,6 +141,7 @@ pub(crate) fn setup() -> (
         config.hypervisor.clone(),
         Arc::clone(&cycles_account_manager),
         Arc::clone(&state_manager) as Arc<_>,
+        &ReplicaVersion::default(),
     );
     let _metrics_runtime = MetricsRuntimeImpl::new_insecure(
         tokio::runtime::Handle::current(),
@@ -36,7 +36,7 @@ use ic_types::{
     messages::{MessageId, SignedIngress},
     replica_config::ReplicaConfig,
     time::UNIX_EPOCH,
-    CanisterId, NodeId, PrincipalId, Randomness, RegistryVersion, SubnetId,
+    CanisterId, NodeId, PrincipalId, Randomness, RegistryVersion, ReplicaVersion, SubnetId,
 };
 use slog::{Drain, Logger};
 use std::collections::BTreeMap;
@@ -208,6 +208,7 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
             cfg.hypervisor.clone(),
             Arc::clone(&cycles_account_manager),
             Arc::clone(&state_manager) as Arc<_>,
+            &ReplicaVersion::default(),
         )
         .into_parts();
 
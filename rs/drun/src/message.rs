@@ -174,6 +174,7 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
             method_payload: parse_octet_string(payload)?,
             ingress_expiry: current_time_and_expiry_time().1.as_nanos_since_unix_epoch(),
             nonce: Some(nonce.to_le_bytes().to_vec()),
+            height: None,
         })),
         ["create"] => parse_create(nonce),
         ["install", canister_id, wasm_file, payload] => {
@@ -442,6 +443,7 @@ mod tests {
             method_payload: vec![1, 2, 3],
             ingress_expiry,
             nonce: Some(nonce.to_le_bytes().to_vec()),
+            height: None,
         });
         assert_eq!(expected, parsed_message);
     }
@@ -1,17 +1,67 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
+    path::Path,
     sync::{Arc, RwLock},
 };
 
 use crate::SerializedModule;
-use ic_config::flag_status::FlagStatus;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
+use ic_logger::{warn, ReplicaLogger};
+use ic_replicated_state::canister_state::execution_state::WasmMetadata;
+use ic_types::{methods::WasmMethod, NumBytes, NumInstructions, ReplicaVersion};
 use ic_wasm_types::{CanisterModule, WasmHash};
 
+mod on_disk;
+
+use on_disk::OnDiskCache;
+pub use on_disk::{read_persisted_module, OnDiskModule};
+
+/// A module found in the `CompilationCache`.
+#[derive(Clone, Debug)]
+pub enum CachedModule {
+    /// The module was compiled by this run of the replica.
+    InMemory(Arc<SerializedModule>),
+    /// The module was persisted by an earlier run of the replica. The
+    /// serialized module has to be read with `read_persisted_module` by the
+    /// process that deserializes it.
+    OnDisk(Arc<OnDiskModule>),
+}
+
+impl CachedModule {
+    pub fn exported_functions(&self) -> &BTreeSet<WasmMethod> {
+        match self {
+            CachedModule::InMemory(serialized_module) => &serialized_module.exported_functions,
+            CachedModule::OnDisk(on_disk_module) => &on_disk_module.exported_functions,
+        }
+    }
+
+    pub fn wasm_metadata(&self) -> &WasmMetadata {
+        match self {
+            CachedModule::InMemory(serialized_module) => &serialized_module.wasm_metadata,
+            CachedModule::OnDisk(on_disk_module) => &on_disk_module.wasm_metadata,
+        }
+    }
+
+    pub fn compilation_cost(&self) -> NumInstructions {
+        match self {
+            CachedModule::InMemory(serialized_module) => serialized_module.compilation_cost,
+            CachedModule::OnDisk(on_disk_module) => on_disk_module.compilation_cost,
+        }
+    }
+}
+
 /// Stores the serialized modules of wasm code that has already been compiled so
 /// that it can be used again without recompiling.
+///
+/// If created with `new_persistent`, the serialized modules are also written
+/// to disk so that they survive restarts of the replica. Modules found on disk
+/// are not loaded into the memory of the replica: only their summary is read
+/// on the first lookup, and the process that deserializes the module, normally
+/// the sandbox process, reads the module itself.
 pub struct CompilationCache {
     enabled: FlagStatus,
-    cache: RwLock<HashMap<WasmHash, Arc<SerializedModule>>>,
+    cache: RwLock<HashMap<WasmHash, CachedModule>>,
+    on_disk: Option<OnDiskCache>,
 }
 
 impl CompilationCache {
@@ -19,6 +69,49 @@ impl CompilationCache {
         Self {
             enabled,
             cache: RwLock::new(HashMap::new()),
+            on_disk: None,
+        }
+    }
+
+    /// Creates a cache that persists the serialized modules in `dir`, using
+    /// at most `capacity` bytes of disk space. Modules persisted by other
+    /// replica versions are discarded. Falls back to an in-memory cache if the
+    /// directory cannot be used.
+    pub fn new_persistent(
+        enabled: FlagStatus,
+        dir: &Path,
+        capacity: NumBytes,
+        replica_version: &ReplicaVersion,
+        embedder_config: &EmbeddersConfig,
+        log: ReplicaLogger,
+    ) -> Self {
+        if enabled == FlagStatus::Disabled {
+            return Self::new(enabled);
+        }
+        // To be on the safe side, any change of the embedder config
+        // invalidates the persisted modules.
+        let on_disk = match OnDiskCache::new(
+            dir,
+            capacity,
+            replica_version,
+            &format!("{:?}", embedder_config),
+            log.clone(),
+        ) {
+            Ok(on_disk) => Some(on_disk),
+            Err(err) => {
+                warn!(
+                    log,
+                    "Failed to open compilation cache in {}: {}",
+                    dir.display(),
+                    err
+                );
+                None
+            }
+        };
+        Self {
+            enabled,
+            cache: RwLock::new(HashMap::new()),
+            on_disk,
         }
     }
 
@@ -28,27 +121,50 @@ impl CompilationCache {
         serialized_module: Arc<SerializedModule>,
     ) {
         if self.enabled == FlagStatus::Enabled {
+            let wasm_hash = WasmHash::from(canister_module);
+            if let Some(on_disk) = &self.on_disk {
+                on_disk.insert(&wasm_hash, &serialized_module);
+            }
             self.cache
                 .write()
                 .unwrap()
-                .insert(WasmHash::from(canister_module), serialized_module);
+                .insert(wasm_hash, CachedModule::InMemory(serialized_module));
         }
     }
 
-    pub fn get(&self, canister_module: &CanisterModule) -> Option<Arc<SerializedModule>> {
+    pub fn get(&self, canister_module: &CanisterModule) -> Option<CachedModule> {
         if self.enabled == FlagStatus::Enabled {
+            let wasm_hash = WasmHash::from(canister_module);
+            if let Some(cached_module) = self.cache.read().unwrap().get(&wasm_hash) {
+                return Some(cached_module.clone());
+            }
+            let cached_module =
+                CachedModule::OnDisk(Arc::new(self.on_disk.as_ref()?.get(&wasm_hash)?));
             self.cache
-                .read()
+                .write()
                 .unwrap()
-                .get(&WasmHash::from(canister_module))
-                .map(Arc::clone)
+                .insert(wasm_hash, cached_module.clone());
+            Some(cached_module)
         } else {
             None
         }
     }
 
+    /// Removes the module, e.g. because its persisted serialized module could
+    /// not be read. The module will be compiled again on its next use.
+    pub fn remove(&self, canister_module: &CanisterModule) {
+        let wasm_hash = WasmHash::from(canister_module);
+        if let Some(on_disk) = &self.on_disk {
+            on_disk.remove_entry(&wasm_hash);
+        }
+        self.cache.write().unwrap().remove(&wasm_hash);
+    }
+
     #[doc(hidden)]
     pub fn clear_for_testing(&self) {
-        self.cache.write().unwrap().clear()
+        self.cache.write().unwrap().clear();
+        if let Some(on_disk) = &self.on_disk {
+            on_disk.clear();
+        }
     }
 }
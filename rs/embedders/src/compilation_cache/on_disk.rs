@@ -0,0 +1,598 @@
+//! Persists serialized modules on disk so that they survive restarts and
+//! upgrades of the replica.
+//!
+//! The cache lives in a directory named after a fingerprint of everything
+//! that affects the compilation output: the replica version, the `wasmtime`
+//! version, and the embedder configuration. Directories with other
+//! fingerprints are deleted on startup because their entries can never be
+//! used again.
+//!
+//! Each entry is a file named after the hash of the uninstrumented Wasm
+//! module. It contains a magic header followed by two checksummed sections:
+//! a small summary with the parts of the `SerializedModule` that the replica
+//! needs to create an execution state, and the `bincode` encoding of the
+//! `SerializedModule` itself. Only the file metadata is read on startup. The
+//! replica reads the summary on the first lookup of an entry, while the
+//! serialized module is read and verified by the process that deserializes
+//! it, normally the sandbox process. When the total size of the entries
+//! exceeds the capacity, the least recently used ones are evicted.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::TryFrom,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::UNIX_EPOCH,
+};
+
+use ic_crypto_sha::Sha256;
+use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
+use ic_logger::{warn, ReplicaLogger};
+use ic_replicated_state::canister_state::execution_state::WasmMetadata;
+use ic_types::{methods::WasmMethod, NumBytes, NumInstructions, ReplicaVersion};
+use ic_wasm_types::{WasmEngineError, WasmHash};
+use serde::{Deserialize, Serialize};
+
+use crate::SerializedModule;
+
+/// Must be bumped whenever the encoding of the entries changes.
+const FORMAT_VERSION: u32 = 2;
+
+const MAGIC: &[u8] = b"ICCM";
+const LENGTH_SIZE: usize = 8;
+const CHECKSUM_SIZE: usize = 32;
+const HEADER_SIZE: usize = MAGIC.len() + LENGTH_SIZE + CHECKSUM_SIZE;
+
+const TMP_EXTENSION: &str = "tmp";
+
+struct Entry {
+    size: u64,
+    last_used: u64,
+}
+
+/// The entries present on disk along with their total size.
+#[derive(Default)]
+struct Index {
+    entries: HashMap<WasmHash, Entry>,
+    total_size: u64,
+    // A logical clock used to find the least recently used entry.
+    clock: u64,
+}
+
+impl Index {
+    fn touch(&mut self, wasm_hash: &WasmHash) -> bool {
+        self.clock += 1;
+        let clock = self.clock;
+        match self.entries.get_mut(wasm_hash) {
+            Some(entry) => {
+                entry.last_used = clock;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, wasm_hash: WasmHash, size: u64) {
+        self.clock += 1;
+        let entry = Entry {
+            size,
+            last_used: self.clock,
+        };
+        if let Some(old) = self.entries.insert(wasm_hash, entry) {
+            self.total_size -= old.size;
+        }
+        self.total_size += size;
+    }
+
+    fn remove(&mut self, wasm_hash: &WasmHash) {
+        if let Some(entry) = self.entries.remove(wasm_hash) {
+            self.total_size -= entry.size;
+        }
+    }
+
+    fn least_recently_used(&self) -> Option<WasmHash> {
+        self.entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(wasm_hash, _)| wasm_hash.clone())
+    }
+}
+
+/// The parts of a `SerializedModule` that the replica needs to create an
+/// execution state. They are stored in front of the serialized module so that
+/// the replica doesn't have to read the latter.
+#[derive(Serialize, Deserialize)]
+struct Summary {
+    exported_functions: BTreeSet<WasmMethod>,
+    wasm_metadata: WasmMetadata,
+    compilation_cost: NumInstructions,
+}
+
+/// A serialized module persisted by an earlier run of the replica. Only its
+/// summary is kept in memory; the `SerializedModule` itself is read from the
+/// entry by the process that deserializes it, see `read_persisted_module`.
+#[derive(Debug)]
+pub struct OnDiskModule {
+    /// The file of the entry.
+    pub path: PathBuf,
+    /// List of functions exported by the canister.
+    pub exported_functions: BTreeSet<WasmMethod>,
+    /// The contents of the metadata custom section.
+    pub wasm_metadata: WasmMetadata,
+    /// Compiling the canister is equivalent to executing this many instructions.
+    pub compilation_cost: NumInstructions,
+}
+
+impl OnDiskModule {
+    /// Opens the entry so that it can be passed to the process that
+    /// deserializes the module.
+    pub fn open(&self) -> HypervisorResult<File> {
+        File::open(&self.path).map_err(|err| {
+            HypervisorError::WasmEngineError(WasmEngineError::FailedToDeserializeModule(format!(
+                "Failed to open compilation cache entry {}: {}",
+                self.path.display(),
+                err
+            )))
+        })
+    }
+}
+
+/// Reads and verifies the `SerializedModule` of an entry opened with
+/// `OnDiskModule::open`.
+pub fn read_persisted_module(mut file: File) -> HypervisorResult<SerializedModule> {
+    let mut bytes = vec![];
+    file.read_to_end(&mut bytes)
+        .map_err(|err| err.to_string())
+        .and_then(|_| {
+            let (_, rest) = read_section(strip_magic(&bytes)?)?;
+            let (payload, _) = read_section(rest)?;
+            bincode::deserialize(payload).map_err(|err| err.to_string())
+        })
+        .map_err(|err| {
+            HypervisorError::WasmEngineError(WasmEngineError::FailedToDeserializeModule(format!(
+                "Invalid compilation cache entry: {}",
+                err
+            )))
+        })
+}
+
+pub(crate) struct OnDiskCache {
+    dir: PathBuf,
+    capacity: NumBytes,
+    index: Mutex<Index>,
+    next_tmp_id: AtomicU64,
+    log: ReplicaLogger,
+}
+
+impl OnDiskCache {
+    /// Opens the cache in a subdirectory of `root` that corresponds to the
+    /// given replica version and embedder configuration, creating it if
+    /// necessary.
+    pub(crate) fn new(
+        root: &Path,
+        capacity: NumBytes,
+        replica_version: &ReplicaVersion,
+        embedder_config: &str,
+        log: ReplicaLogger,
+    ) -> std::io::Result<Self> {
+        let dir = root.join(hex::encode(fingerprint(replica_version, embedder_config)));
+        fs::create_dir_all(&dir)?;
+
+        for entry in fs::read_dir(root)? {
+            let path = entry?.path();
+            if path != dir {
+                let result = if path.is_dir() {
+                    fs::remove_dir_all(&path)
+                } else {
+                    fs::remove_file(&path)
+                };
+                if let Err(err) = result {
+                    warn!(
+                        log,
+                        "Failed to remove stale compilation cache {}: {}",
+                        path.display(),
+                        err
+                    );
+                }
+            }
+        }
+
+        let mut files = vec![];
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            match parse_entry_name(&path) {
+                Some(wasm_hash) => {
+                    let metadata = entry.metadata()?;
+                    let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+                    files.push((modified, wasm_hash, metadata.len()));
+                }
+                // Leftovers of interrupted writes.
+                None => {
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+        files.sort();
+
+        let mut index = Index::default();
+        for (_, wasm_hash, size) in files {
+            index.insert(wasm_hash, size);
+        }
+
+        let cache = Self {
+            dir,
+            capacity,
+            index: Mutex::new(index),
+            next_tmp_id: AtomicU64::new(0),
+            log,
+        };
+        // The capacity may have been lowered since the last run.
+        cache.evict(&mut cache.index.lock().unwrap());
+        Ok(cache)
+    }
+
+    /// Reads the summary of the given Wasm module from disk. Corrupted entries
+    /// are removed and reported as missing.
+    pub(crate) fn get(&self, wasm_hash: &WasmHash) -> Option<OnDiskModule> {
+        if !self.index.lock().unwrap().touch(wasm_hash) {
+            return None;
+        }
+        let path = self.entry_path(wasm_hash);
+        let result = File::open(&path)
+            .map_err(|err| err.to_string())
+            .and_then(read_summary);
+        match result {
+            Ok(summary) => Some(OnDiskModule {
+                path,
+                exported_functions: summary.exported_functions,
+                wasm_metadata: summary.wasm_metadata,
+                compilation_cost: summary.compilation_cost,
+            }),
+            Err(err) => {
+                warn!(
+                    self.log,
+                    "Dropping compilation cache entry {}: {}",
+                    path.display(),
+                    err
+                );
+                self.remove(&mut self.index.lock().unwrap(), wasm_hash);
+                None
+            }
+        }
+    }
+
+    /// Writes the serialized module of the given Wasm module to disk unless
+    /// it is already present, and evicts the least recently used entries if
+    /// the cache grows beyond its capacity.
+    pub(crate) fn insert(&self, wasm_hash: &WasmHash, serialized_module: &SerializedModule) {
+        // The module bytes are empty if module sharing is disabled, in which
+        // case the entry would be useless.
+        if serialized_module.bytes.is_empty()
+            || self.index.lock().unwrap().entries.contains_key(wasm_hash)
+        {
+            return;
+        }
+        let bytes = match encode(serialized_module) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                warn!(self.log, "Failed to encode serialized module: {}", err);
+                return;
+            }
+        };
+        let size = bytes.len() as u64;
+        if size > self.capacity.get() {
+            return;
+        }
+
+        // Write to a temporary file first, so that readers and restarts never
+        // observe a partially written entry.
+        let path = self.entry_path(wasm_hash);
+        let tmp_path = self.dir.join(format!(
+            "{}.{}",
+            self.next_tmp_id.fetch_add(1, Ordering::Relaxed),
+            TMP_EXTENSION
+        ));
+        if let Err(err) = fs::write(&tmp_path, &bytes).and_then(|()| fs::rename(&tmp_path, &path)) {
+            warn!(
+                self.log,
+                "Failed to write compilation cache entry {}: {}",
+                path.display(),
+                err
+            );
+            let _ = fs::remove_file(&tmp_path);
+            return;
+        }
+
+        let mut index = self.index.lock().unwrap();
+        index.insert(wasm_hash.clone(), size);
+        self.evict(&mut index);
+    }
+
+    /// Removes the entry of the given Wasm module, e.g. because the serialized
+    /// module could not be read.
+    pub(crate) fn remove_entry(&self, wasm_hash: &WasmHash) {
+        let mut index = self.index.lock().unwrap();
+        if index.entries.contains_key(wasm_hash) {
+            self.remove(&mut index, wasm_hash);
+        }
+    }
+
+    /// Removes all entries.
+    pub(crate) fn clear(&self) {
+        let mut index = self.index.lock().unwrap();
+        let wasm_hashes: Vec<_> = index.entries.keys().cloned().collect();
+        for wasm_hash in wasm_hashes {
+            self.remove(&mut index, &wasm_hash);
+        }
+    }
+
+    fn evict(&self, index: &mut Index) {
+        while index.total_size > self.capacity.get() {
+            match index.least_recently_used() {
+                Some(wasm_hash) => self.remove(index, &wasm_hash),
+                None => break,
+            }
+        }
+    }
+
+    fn remove(&self, index: &mut Index, wasm_hash: &WasmHash) {
+        index.remove(wasm_hash);
+        let path = self.entry_path(wasm_hash);
+        if let Err(err) = fs::remove_file(&path) {
+            warn!(
+                self.log,
+                "Failed to remove compilation cache entry {}: {}",
+                path.display(),
+                err
+            );
+        }
+    }
+
+    fn entry_path(&self, wasm_hash: &WasmHash) -> PathBuf {
+        self.dir.join(hex::encode(wasm_hash.to_vec()))
+    }
+}
+
+/// Returns a fingerprint of everything besides the Wasm module itself that
+/// determines the serialized module. A serialized module can only be
+/// deserialized by the version of `wasmtime` that produced it, so the version
+/// of `wasmtime-environ` is included as well. It is the same as the version of
+/// `wasmtime` because `wasmtime` depends on the exact version of it.
+fn fingerprint(replica_version: &ReplicaVersion, embedder_config: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.write(&FORMAT_VERSION.to_le_bytes());
+    for part in [
+        replica_version.as_ref(),
+        wasmtime_environ::VERSION,
+        embedder_config,
+    ] {
+        hasher.write(&(part.len() as u64).to_le_bytes());
+        hasher.write(part.as_bytes());
+    }
+    hasher.finish()
+}
+
+fn parse_entry_name(path: &Path) -> Option<WasmHash> {
+    let name = path.file_name()?.to_str()?;
+    let bytes = hex::decode(name).ok()?;
+    <[u8; 32]>::try_from(bytes.as_slice())
+        .ok()
+        .map(WasmHash::from)
+}
+
+/// Encodes an entry as `MAGIC | summary section | payload section`, where
+/// each section is `length | checksum | bytes`.
+fn encode(serialized_module: &SerializedModule) -> Result<Vec<u8>, String> {
+    let summary = bincode::serialize(&Summary {
+        exported_functions: serialized_module.exported_functions.clone(),
+        wasm_metadata: serialized_module.wasm_metadata.clone(),
+        compilation_cost: serialized_module.compilation_cost,
+    })
+    .map_err(|err| err.to_string())?;
+    let payload = bincode::serialize(serialized_module).map_err(|err| err.to_string())?;
+    let mut bytes = Vec::with_capacity(
+        MAGIC.len() + 2 * (LENGTH_SIZE + CHECKSUM_SIZE) + summary.len() + payload.len(),
+    );
+    bytes.extend_from_slice(MAGIC);
+    for section in [&summary, &payload] {
+        bytes.extend_from_slice(&(section.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&Sha256::hash(section));
+        bytes.extend_from_slice(section);
+    }
+    Ok(bytes)
+}
+
+/// Reads only the summary section of an entry.
+fn read_summary(mut file: File) -> Result<Summary, String> {
+    let mut bytes = vec![0; HEADER_SIZE];
+    file.read_exact(&mut bytes).map_err(|err| err.to_string())?;
+    let len = section_len(strip_magic(&bytes)?)?;
+    let file_len = file.metadata().map_err(|err| err.to_string())?.len();
+    if len > file_len.saturating_sub(HEADER_SIZE as u64) {
+        return Err("invalid summary length".to_string());
+    }
+    bytes.resize(HEADER_SIZE + len as usize, 0);
+    file.read_exact(&mut bytes[HEADER_SIZE..])
+        .map_err(|err| err.to_string())?;
+    let (summary, _) = read_section(strip_magic(&bytes)?)?;
+    bincode::deserialize(summary).map_err(|err| err.to_string())
+}
+
+fn strip_magic(bytes: &[u8]) -> Result<&[u8], String> {
+    match bytes.strip_prefix(MAGIC) {
+        Some(rest) => Ok(rest),
+        None => Err("invalid header".to_string()),
+    }
+}
+
+fn section_len(bytes: &[u8]) -> Result<u64, String> {
+    match bytes.get(..LENGTH_SIZE) {
+        Some(len) => Ok(u64::from_le_bytes(
+            <[u8; LENGTH_SIZE]>::try_from(len).unwrap(),
+        )),
+        None => Err("truncated section".to_string()),
+    }
+}
+
+/// Splits a section off the front of `bytes` and verifies its checksum.
+/// Returns the section and the bytes following it.
+fn read_section(bytes: &[u8]) -> Result<(&[u8], &[u8]), String> {
+    let len = section_len(bytes)?;
+    let rest = &bytes[LENGTH_SIZE..];
+    if rest.len() < CHECKSUM_SIZE || ((rest.len() - CHECKSUM_SIZE) as u64) < len {
+        return Err("truncated section".to_string());
+    }
+    let (checksum, rest) = rest.split_at(CHECKSUM_SIZE);
+    let (section, rest) = rest.split_at(len as usize);
+    if Sha256::hash(section).as_ref() != checksum {
+        return Err("checksum mismatch".to_string());
+    }
+    Ok((section, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SerializedModuleBytes;
+    use ic_logger::replica_logger::no_op_logger;
+    use std::sync::Arc;
+
+    fn serialized_module(bytes: Vec<u8>) -> SerializedModule {
+        SerializedModule {
+            bytes: Arc::new(SerializedModuleBytes::from_bytes_for_testing(bytes)),
+            exported_functions: Default::default(),
+            data_segments: Default::default(),
+            wasm_metadata: Default::default(),
+            compilation_cost: 42.into(),
+        }
+    }
+
+    fn wasm_hash(i: u8) -> WasmHash {
+        WasmHash::from([i; 32])
+    }
+
+    fn open_with_version(root: &Path, capacity: u64, version: &str, config: &str) -> OnDiskCache {
+        OnDiskCache::new(
+            root,
+            NumBytes::from(capacity),
+            &ReplicaVersion::try_from(version).unwrap(),
+            config,
+            no_op_logger(),
+        )
+        .unwrap()
+    }
+
+    fn open(root: &Path, capacity: u64, config: &str) -> OnDiskCache {
+        open_with_version(root, capacity, "1.0.0", config)
+    }
+
+    fn load(module: &OnDiskModule) -> HypervisorResult<SerializedModule> {
+        read_persisted_module(module.open()?)
+    }
+
+    #[test]
+    fn entries_survive_reopening() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = open(root.path(), 1 << 20, "config");
+        cache.insert(&wasm_hash(1), &serialized_module(vec![1, 2, 3]));
+        drop(cache);
+
+        let cache = open(root.path(), 1 << 20, "config");
+        let module = cache.get(&wasm_hash(1)).unwrap();
+        assert_eq!(module.compilation_cost, 42.into());
+        let serialized_module = load(&module).unwrap();
+        assert_eq!(serialized_module.bytes.as_slice(), &[1, 2, 3]);
+        assert_eq!(serialized_module.compilation_cost, 42.into());
+        assert!(cache.get(&wasm_hash(2)).is_none());
+    }
+
+    #[test]
+    fn changing_config_invalidates_entries() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = open(root.path(), 1 << 20, "config");
+        cache.insert(&wasm_hash(1), &serialized_module(vec![1, 2, 3]));
+        drop(cache);
+
+        let cache = open(root.path(), 1 << 20, "other config");
+        assert!(cache.get(&wasm_hash(1)).is_none());
+        assert_eq!(fs::read_dir(root.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn changing_replica_version_invalidates_entries() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = open_with_version(root.path(), 1 << 20, "1.0.0", "config");
+        cache.insert(&wasm_hash(1), &serialized_module(vec![1, 2, 3]));
+        drop(cache);
+
+        let cache = open_with_version(root.path(), 1 << 20, "1.0.1", "config");
+        assert!(cache.get(&wasm_hash(1)).is_none());
+        assert_eq!(fs::read_dir(root.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn entries_with_corrupted_summary_are_dropped() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = open(root.path(), 1 << 20, "config");
+        cache.insert(&wasm_hash(1), &serialized_module(vec![1, 2, 3]));
+
+        let path = cache.entry_path(&wasm_hash(1));
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[HEADER_SIZE] ^= 1;
+        fs::write(&path, bytes).unwrap();
+
+        assert!(cache.get(&wasm_hash(1)).is_none());
+        assert!(!path.exists());
+        assert_eq!(cache.index.lock().unwrap().total_size, 0);
+    }
+
+    #[test]
+    fn corrupted_modules_are_rejected_when_loaded() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = open(root.path(), 1 << 20, "config");
+        cache.insert(&wasm_hash(1), &serialized_module(vec![1, 2, 3]));
+
+        let path = cache.entry_path(&wasm_hash(1));
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+
+        // Only the summary is read by the lookup.
+        let module = cache.get(&wasm_hash(1)).unwrap();
+        assert!(load(&module).is_err());
+
+        cache.remove_entry(&wasm_hash(1));
+        assert!(!path.exists());
+        assert_eq!(cache.index.lock().unwrap().total_size, 0);
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted() {
+        let root = tempfile::tempdir().unwrap();
+        let entry_size = encode(&serialized_module(vec![0; 100])).unwrap().len() as u64;
+        let cache = open(root.path(), 2 * entry_size, "config");
+        cache.insert(&wasm_hash(1), &serialized_module(vec![1; 100]));
+        cache.insert(&wasm_hash(2), &serialized_module(vec![2; 100]));
+        assert!(cache.get(&wasm_hash(1)).is_some());
+        cache.insert(&wasm_hash(3), &serialized_module(vec![3; 100]));
+
+        assert!(cache.get(&wasm_hash(1)).is_some());
+        assert!(cache.get(&wasm_hash(2)).is_none());
+        assert!(cache.get(&wasm_hash(3)).is_some());
+        assert_eq!(cache.index.lock().unwrap().total_size, 2 * entry_size);
+    }
+
+    #[test]
+    fn empty_modules_are_not_persisted() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = open(root.path(), 1 << 20, "config");
+        cache.insert(&wasm_hash(1), &serialized_module(vec![]));
+        assert!(cache.get(&wasm_hash(1)).is_none());
+    }
+}
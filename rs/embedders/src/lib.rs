@@ -7,7 +7,7 @@ pub mod wasmtime_embedder;
 
 use std::{sync::Arc, time::Duration};
 
-pub use compilation_cache::CompilationCache;
+pub use compilation_cache::{read_persisted_module, CachedModule, CompilationCache, OnDiskModule};
 use ic_interfaces::execution_environment::AvailableMemory;
 use ic_replicated_state::{Global, NumWasmPages, PageIndex};
 use ic_sys::PageBytes;
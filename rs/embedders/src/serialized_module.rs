@@ -35,6 +35,16 @@ impl SerializedModuleBytes {
         Self(vec![])
     }
 
+    /// Returns true if the bytes were created by `empty`.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn from_bytes_for_testing(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
     /// It is guaranteed to be safe to deserialize this array into a `wasmtime::Module`.
     pub fn as_slice(&self) -> &[u8] {
         // Serializing a module always includes the header "wasmtime-aot", so
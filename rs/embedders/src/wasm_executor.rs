@@ -11,15 +11,17 @@ use serde::{Deserialize, Serialize};
 
 use crate::wasm_utils::compile;
 use crate::wasm_utils::instrumentation::Segments;
+use crate::{
+    read_persisted_module, CachedModule, CompilationCache, CompilationResult, SerializedModule,
+};
 use crate::{
     wasm_utils::decoding::decode_wasm, wasm_utils::validation::WasmImportsDetails,
     wasmtime_embedder::WasmtimeInstance, WasmExecutionInput, WasmtimeEmbedder,
 };
-use crate::{CompilationCache, CompilationResult, SerializedModule};
 use ic_config::flag_status::FlagStatus;
 use ic_interfaces::execution_environment::{
     AvailableMemory, HypervisorError, HypervisorResult, InstanceStats, OutOfInstructionsHandler,
-    SystemApi, WasmExecutionOutput,
+    SystemApi, SystemApiCallCounters, WasmExecutionOutput,
 };
 use ic_logger::{warn, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
@@ -214,6 +216,7 @@ impl WasmExecutor for WasmExecutorImpl {
                                 accessed_pages: 0,
                                 dirty_pages: 0,
                             },
+                            system_api_call_counters: SystemApiCallCounters::default(),
                         },
                         None,
                     ),
@@ -387,7 +390,22 @@ impl WasmExecutorImpl {
                 compilation_result: None,
             })
         } else {
-            match compilation_cache.get(&wasm_binary.binary) {
+            let serialized_module = match compilation_cache.get(&wasm_binary.binary) {
+                Some(CachedModule::InMemory(serialized_module)) => Some(serialized_module),
+                Some(CachedModule::OnDisk(on_disk_module)) => {
+                    match on_disk_module.open().and_then(read_persisted_module) {
+                        Ok(serialized_module) => Some(Arc::new(serialized_module)),
+                        Err(err) => {
+                            // Compile the module again instead.
+                            warn!(self.log, "Failed to load persisted module: {}", err);
+                            compilation_cache.remove(&wasm_binary.binary);
+                            None
+                        }
+                    }
+                }
+                None => None,
+            };
+            match serialized_module {
                 Some(serialized_module) => {
                     let module = self
                         .wasm_embedder
@@ -562,6 +580,7 @@ pub fn process(
 ) {
     let canister_id = sandbox_safe_system_state.canister_id();
     let modification_tracking = api_type.modification_tracking();
+    let wasm64_memory_limit = execution_parameters.wasm64_memory_limit;
     let system_api = SystemApiImpl::new(
         api_type,
         sandbox_safe_system_state,
@@ -601,6 +620,7 @@ pub fn process(
                         accessed_pages: 0,
                         dirty_pages: 0,
                     },
+                    system_api_call_counters: SystemApiCallCounters::default(),
                 },
                 None,
                 Err(system_api),
@@ -644,13 +664,20 @@ pub fn process(
         .take_execution_result(run_result.as_ref().err());
 
     let wasm_heap_size_after = instance.heap_size();
-    let wasm_heap_limit =
-        NumWasmPages::from(wasmtime_environ::WASM32_MAX_PAGES as usize) - wasm_reserved_pages;
+    let max_wasm_heap_size = if instance.is_memory64() {
+        NumWasmPages::from(
+            (wasm64_memory_limit.get() / wasmtime_environ::WASM_PAGE_SIZE as u64) as usize,
+        )
+    } else {
+        NumWasmPages::from(wasmtime_environ::WASM32_MAX_PAGES as usize)
+    };
+    let wasm_heap_limit = max_wasm_heap_size - wasm_reserved_pages;
 
     if wasm_heap_size_after > wasm_heap_limit {
         wasm_result = Err(HypervisorError::WasmReservedPages);
     }
 
+    let system_api_call_counters = instance.store_data().system_api.call_counters();
     let mut allocated_bytes = NumBytes::from(0);
     let mut allocated_message_bytes = NumBytes::from(0);
 
@@ -700,6 +727,7 @@ pub fn process(
             allocated_bytes,
             allocated_message_bytes,
             instance_stats,
+            system_api_call_counters,
         },
         wasm_state_changes,
         Ok(instance),
@@ -11,6 +11,7 @@ use crate::{serialized_module::SerializedModule, CompilationResult, WasmtimeEmbe
 pub mod decoding;
 pub mod errors;
 pub mod instrumentation;
+mod memory64;
 pub mod validation;
 mod wasm_module_builder;
 
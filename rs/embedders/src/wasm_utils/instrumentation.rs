@@ -93,8 +93,25 @@
 //! blocks to optimize for performance. The maximal overflow in that case is
 //! bound by the length of the longest execution path consisting of
 //! non-reentrant basic blocks.
-
-use super::{errors::into_parity_wasm_error, wasm_module_builder::WasmModuleBuilder};
+//!
+//! Modules with a 64-bit memory are instrumented the same way, except that
+//! memory sizes are 64-bit integers: the memory grow helper is imported as
+//!
+//! ```wasm
+//! (import "__" "update_available_memory_64" (func (;1;) ((param i64 i64) (result i64))))
+//! ```
+//!
+//! and the function handling bulk memory instructions takes and returns an
+//! `i64`. It traps if the size is too large to be a valid unsigned 63-bit
+//! integer, which no bulk memory operation can succeed with anyway. The size
+//! arguments of `memory.init`, `table.copy` and `table.init` remain 32-bit
+//! integers, so they are extended before and wrapped after the call.
+
+use super::{
+    errors::into_parity_wasm_error,
+    memory64::{lower_memory64, raise_memory64},
+    wasm_module_builder::WasmModuleBuilder,
+};
 use ic_replicated_state::canister_state::WASM_PAGE_SIZE_IN_BYTES;
 use ic_replicated_state::NumWasmPages;
 use ic_sys::{PageBytes, PageIndex, PAGE_SIZE};
@@ -119,6 +136,18 @@ enum InjectedImports {
     Count = 2,
 }
 
+const UPDATE_AVAILABLE_MEMORY_FN: &str = "update_available_memory";
+const UPDATE_AVAILABLE_MEMORY_64_FN: &str = "update_available_memory_64";
+
+// Returns the type of addresses and sizes of the Wasm memory.
+fn memory_value_type(is_memory64: bool) -> ValueType {
+    if is_memory64 {
+        ValueType::I64
+    } else {
+        ValueType::I32
+    }
+}
+
 // Gets the cost of an instruction.
 fn instruction_to_cost(i: &Instruction) -> u64 {
     match i {
@@ -139,14 +168,15 @@ fn instruction_to_cost(i: &Instruction) -> u64 {
 //     out of instructions.
 //   * `update_available_memory` which is called after a native `memory.grow` to
 //     check whether the canister has enough available memory according to its
-//     memory allocation.
+//     memory allocation. Modules with a 64-bit memory import
+//     `update_available_memory_64` instead.
 //
 // Note that these functions are injected as the first two imports, so that we
 // can increment all function indices unconditionally by two. (If they would be
 // added as the last two imports, we'd need to increment only non imported
 // functions, since imported functions precede all others in the function index
 // space, but this would be error-prone).
-fn inject_helper_functions(module: Module) -> Module {
+fn inject_helper_functions(module: Module, is_memory64: bool) -> Module {
     let mut builder = builder::from_module(module);
     let import_sig = builder.push_signature(builder::signature().build_sig());
 
@@ -159,17 +189,23 @@ fn inject_helper_functions(module: Module) -> Module {
             .build(),
     );
 
+    let memory_type = memory_value_type(is_memory64);
+    let update_available_memory_fn = if is_memory64 {
+        UPDATE_AVAILABLE_MEMORY_64_FN
+    } else {
+        UPDATE_AVAILABLE_MEMORY_FN
+    };
     let import_sig = builder.push_signature(
         builder::signature()
-            .with_param(ValueType::I32)
-            .with_param(ValueType::I32)
-            .with_result(ValueType::I32)
+            .with_param(memory_type)
+            .with_param(memory_type)
+            .with_result(memory_type)
             .build_sig(),
     );
     builder.push_import(
         builder::import()
             .module("__")
-            .field("update_available_memory")
+            .field(update_available_memory_fn)
             .external()
             .func(import_sig)
             .build(),
@@ -181,7 +217,7 @@ fn inject_helper_functions(module: Module) -> Module {
     // increase all other function indices unconditionally.
     let entries = module.import_section_mut().unwrap().entries_mut();
     let last = entries.pop().unwrap();
-    debug_assert!(last.module() == "__" && last.field() == "update_available_memory");
+    debug_assert!(last.module() == "__" && last.field() == update_available_memory_fn);
     entries.insert(0, last);
     let last = entries.pop().unwrap();
     debug_assert!(last.module() == "__" && last.field() == "out_of_instructions");
@@ -192,7 +228,7 @@ fn inject_helper_functions(module: Module) -> Module {
     );
     debug_assert!(
         entries[InjectedImports::UpdateAvailableMemoryFn as usize].field()
-            == "update_available_memory"
+            == update_available_memory_fn
     );
 
     // We lift all call references by 2
@@ -353,6 +389,7 @@ pub struct ExportModuleData {
     pub instructions_counter_ix: u32,
     pub decr_instruction_counter_fn: u32,
     pub start_fn_ix: Option<u32>,
+    pub is_memory64: bool,
 }
 
 /// Takes a Wasm binary and inserts the instructions metering and memory grow
@@ -364,10 +401,14 @@ pub(super) fn instrument(
     wasm: &BinaryEncodedWasm,
     cost_to_compile_wasm_instruction: NumInstructions,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
-    let module = parity_wasm::deserialize_buffer::<Module>(wasm.as_slice()).map_err(|err| {
-        WasmInstrumentationError::ParityDeserializeError(into_parity_wasm_error(err))
-    })?;
-    let mut module = inject_helper_functions(module);
+    let lowered_wasm =
+        lower_memory64(wasm.as_slice()).map_err(WasmInstrumentationError::InvalidMemorySection)?;
+    let is_memory64 = lowered_wasm.is_some();
+    let module = parity_wasm::deserialize_buffer::<Module>(
+        lowered_wasm.as_deref().unwrap_or_else(|| wasm.as_slice()),
+    )
+    .map_err(|err| WasmInstrumentationError::ParityDeserializeError(into_parity_wasm_error(err)))?;
+    let mut module = inject_helper_functions(module, is_memory64);
     module = export_table(module);
     module = export_memory(module);
     module = export_mutable_globals(module);
@@ -378,6 +419,7 @@ pub(super) fn instrument(
         instructions_counter_ix: num_globals,
         decr_instruction_counter_fn: num_functions,
         start_fn_ix: module.start_section(),
+        is_memory64,
     };
 
     if export_module_data.start_fn_ix.is_some() {
@@ -415,7 +457,7 @@ pub(super) fn instrument(
         if !func_types.is_empty() {
             let func_bodies = module.code_section_mut().unwrap().bodies_mut();
             for (func_ix, func_type) in func_types.into_iter().enumerate() {
-                inject_update_available_memory(&mut func_bodies[func_ix], &func_type);
+                inject_update_available_memory(&mut func_bodies[func_ix], &func_type, is_memory64);
             }
         }
     }
@@ -469,9 +511,12 @@ pub(super) fn instrument(
             })
             .unwrap_or(0)) as u64;
 
-    let result = parity_wasm::serialize(module).map_err(|err| {
+    let mut result = parity_wasm::serialize(module).map_err(|err| {
         WasmInstrumentationError::ParitySerializeError(into_parity_wasm_error(err))
     })?;
+    if is_memory64 {
+        result = raise_memory64(&result).map_err(WasmInstrumentationError::InvalidMemorySection)?;
+    }
     Ok(InstrumentationOutput {
         exported_functions,
         data,
@@ -491,33 +536,52 @@ pub fn export_additional_symbols(
     let mut mbuilder = WasmModuleBuilder::new(builder::from_module(module));
 
     // push function to decrement the instruction counter
+    let memory_type = memory_value_type(export_module_data.is_memory64);
+    let mut instructions = if export_module_data.is_memory64 {
+        vec![
+            // Trap if the amount is negative as a signed integer.
+            Instruction::GetLocal(0),
+            Instruction::I64Const(0),
+            Instruction::I64LtS,
+            Instruction::If(BlockType::NoResult),
+            Instruction::Unreachable,
+            Instruction::End,
+            // Subtract the parameter amount from the instruction counter
+            Instruction::GetGlobal(export_module_data.instructions_counter_ix),
+            Instruction::GetLocal(0),
+        ]
+    } else {
+        vec![
+            // Subtract the parameter amount from the instruction counter
+            Instruction::GetGlobal(export_module_data.instructions_counter_ix),
+            Instruction::GetLocal(0),
+            Instruction::I64ExtendUI32,
+        ]
+    };
+    instructions.extend_from_slice(&[
+        Instruction::I64Sub,
+        Instruction::SetGlobal(export_module_data.instructions_counter_ix),
+        // Call out_of_instructions() if `counter < 0`.
+        Instruction::GetGlobal(export_module_data.instructions_counter_ix),
+        Instruction::I64Const(0),
+        Instruction::I64LtS,
+        Instruction::If(BlockType::NoResult),
+        Instruction::Call(InjectedImports::OutOfInstructionsFn as u32),
+        Instruction::End,
+        // Return the original param so this function doesn't alter the stack
+        Instruction::GetLocal(0),
+        Instruction::End,
+    ]);
     mbuilder.push_function(
         builder::function()
             .with_signature(
                 builder::signature()
-                    .with_param(ValueType::I32) // amount to decrement by
-                    .with_result(ValueType::I32) // argument is returned so stack remains unchanged
+                    .with_param(memory_type) // amount to decrement by
+                    .with_result(memory_type) // argument is returned so stack remains unchanged
                     .build_sig(),
             )
             .body()
-            .with_instructions(Instructions::new(vec![
-                // Subtract the parameter amount from the instruction counter
-                Instruction::GetGlobal(export_module_data.instructions_counter_ix),
-                Instruction::GetLocal(0),
-                Instruction::I64ExtendUI32,
-                Instruction::I64Sub,
-                Instruction::SetGlobal(export_module_data.instructions_counter_ix),
-                // Call out_of_instructions() if `counter < 0`.
-                Instruction::GetGlobal(export_module_data.instructions_counter_ix),
-                Instruction::I64Const(0),
-                Instruction::I64LtS,
-                Instruction::If(BlockType::NoResult),
-                Instruction::Call(InjectedImports::OutOfInstructionsFn as u32),
-                Instruction::End,
-                // Return the original param so this function doesn't alter the stack
-                Instruction::GetLocal(0),
-                Instruction::End,
-            ]))
+            .with_instructions(Instructions::new(instructions))
             .build()
             .build(),
     );
@@ -641,9 +705,16 @@ fn inject_metering(code: &mut Instructions, export_data_module: &ExportModuleDat
                 }
             }
             InjectionPointCostDetail::DynamicCost => {
-                elems.extend_from_slice(&[Instruction::Call(
-                    export_data_module.decr_instruction_counter_fn,
-                )]);
+                let call = Instruction::Call(export_data_module.decr_instruction_counter_fn);
+                if export_data_module.is_memory64 && has_32_bit_size(&orig_elems[point.position]) {
+                    elems.extend_from_slice(&[
+                        Instruction::I64ExtendUI32,
+                        call,
+                        Instruction::I32WrapI64,
+                    ]);
+                } else {
+                    elems.push(call);
+                }
             }
         }
         last_injection_position = point.position;
@@ -652,11 +723,26 @@ fn inject_metering(code: &mut Instructions, export_data_module: &ExportModuleDat
     *code.elements_mut() = elems;
 }
 
+// Returns true if the size argument of a bulk memory instruction is a 32-bit
+// integer even in modules with a 64-bit memory.
+fn has_32_bit_size(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Bulk(BulkInstruction::MemoryInit(_))
+            | Instruction::Bulk(BulkInstruction::TableCopy)
+            | Instruction::Bulk(BulkInstruction::TableInit(_))
+    )
+}
+
 // Scans through a function and adds instrumentation after each `memory.grow`
 // instruction to make sure that there's enough available memory left to support
 // the requested extra memory. If no `memory.grow` instructions are present then
 // the function's code remains unchanged.
-fn inject_update_available_memory(func_body: &mut FuncBody, func_type: &FunctionType) {
+fn inject_update_available_memory(
+    func_body: &mut FuncBody,
+    func_type: &FunctionType,
+    is_memory64: bool,
+) {
     let mut injection_points: Vec<usize> = Vec::new();
     {
         let code = func_body.code();
@@ -674,7 +760,9 @@ fn inject_update_available_memory(func_body: &mut FuncBody, func_type: &Function
         // We inject a local to cache the argument to `memory.grow`.
         let n_locals: u32 = func_body.locals().iter().map(Local::count).sum();
         let memory_local_ix = func_type.params().len() as u32 + n_locals;
-        func_body.locals_mut().push(Local::new(1, ValueType::I32));
+        func_body
+            .locals_mut()
+            .push(Local::new(1, memory_value_type(is_memory64)));
         let code = func_body.code_mut();
         let orig_elems = code.elements_mut();
         let mut elems: Vec<Instruction> = Vec::new();
@@ -773,6 +861,10 @@ fn get_data(sections: &mut Vec<Section>) -> Segments {
                                     Instruction::I32Const(val),
                                     Instruction::End
                                ] => ((*val) as u32) as usize, // Convert via `u32` to avoid 64-bit sign-extension.
+                                [
+                                    Instruction::I64Const(val),
+                                    Instruction::End
+                               ] => (*val) as u64 as usize,
                                 _ => panic!(
                                     "complex initialization expressions for data segments are not supported!"
                                     ),
@@ -0,0 +1,298 @@
+//! Support for Wasm modules with a 64-bit memory, as defined by the
+//! [memory64 proposal](https://github.com/WebAssembly/memory64).
+//!
+//! `parity_wasm` cannot decode the limits of a 64-bit memory, although the
+//! proposal does not change the encoding of any other part of the module
+//! that validation and instrumentation look at. So before a module is handed
+//! to `parity_wasm`, the memories defined in its memory section are "lowered"
+//! to 32-bit memories, and after instrumentation they are "raised" back to
+//! 64-bit memories.
+//!
+//! Only the memory section is rewritten: importing a 64-bit memory is not
+//! supported.
+
+const HEADER_SIZE: usize = 8;
+const MEMORY_SECTION_ID: u8 = 5;
+
+const HAS_MAXIMUM_FLAG: u8 = 0x01;
+const MEMORY64_FLAG: u8 = 0x04;
+
+/// The limits of a memory defined in the memory section, in Wasm pages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryLimits {
+    flags: u8,
+    pub initial: u64,
+    pub maximum: Option<u64>,
+}
+
+impl MemoryLimits {
+    /// Returns true if this is a 64-bit memory.
+    pub fn is_64(&self) -> bool {
+        self.flags & MEMORY64_FLAG != 0
+    }
+
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.push(self.flags);
+        write_leb128(bytes, self.initial);
+        if let Some(maximum) = self.maximum {
+            write_leb128(bytes, maximum);
+        }
+    }
+}
+
+/// The memory section of a module: its position in the module along with the
+/// memories it defines.
+struct MemorySection {
+    start: usize,
+    end: usize,
+    memories: Vec<MemoryLimits>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| format!("unexpected end of module at offset {}", self.pos))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn leb128(&mut self) -> Result<u64, String> {
+        let mut result: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift == 63 && byte > 1 {
+                return Err(format!("integer too large at offset {}", self.pos - 1));
+            }
+            result |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+        }
+    }
+}
+
+fn write_leb128(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn find_memory_section(wasm: &[u8]) -> Result<Option<MemorySection>, String> {
+    if wasm.len() < HEADER_SIZE {
+        return Err("module is too short".to_string());
+    }
+    let mut reader = Reader {
+        bytes: wasm,
+        pos: HEADER_SIZE,
+    };
+    while reader.pos < wasm.len() {
+        let start = reader.pos;
+        let id = reader.byte()?;
+        let size = reader.leb128()? as usize;
+        let end = reader
+            .pos
+            .checked_add(size)
+            .filter(|end| *end <= wasm.len())
+            .ok_or_else(|| format!("section at offset {} is out of bounds", start))?;
+        if id == MEMORY_SECTION_ID {
+            let count = reader.leb128()?;
+            let mut memories = Vec::new();
+            for _ in 0..count {
+                let flags = reader.byte()?;
+                let initial = reader.leb128()?;
+                let maximum = if flags & HAS_MAXIMUM_FLAG != 0 {
+                    Some(reader.leb128()?)
+                } else {
+                    None
+                };
+                memories.push(MemoryLimits {
+                    flags,
+                    initial,
+                    maximum,
+                });
+            }
+            if reader.pos != end {
+                return Err(format!(
+                    "memory section at offset {} has an unexpected size",
+                    start
+                ));
+            }
+            return Ok(Some(MemorySection {
+                start,
+                end,
+                memories,
+            }));
+        }
+        reader.pos = end;
+    }
+    Ok(None)
+}
+
+/// Returns a copy of `wasm` in which the memory section is replaced by one
+/// defining `memories`.
+fn replace_memory_section(
+    wasm: &[u8],
+    section: &MemorySection,
+    memories: &[MemoryLimits],
+) -> Vec<u8> {
+    let mut payload = Vec::new();
+    write_leb128(&mut payload, memories.len() as u64);
+    for memory in memories {
+        memory.encode(&mut payload);
+    }
+    let mut result = Vec::with_capacity(wasm.len() + payload.len());
+    result.extend_from_slice(&wasm[..section.start]);
+    result.push(MEMORY_SECTION_ID);
+    write_leb128(&mut result, payload.len() as u64);
+    result.extend_from_slice(&payload);
+    result.extend_from_slice(&wasm[section.end..]);
+    result
+}
+
+/// Returns the limits of the memories defined in the memory section of
+/// `wasm`.
+pub fn memory_limits(wasm: &[u8]) -> Result<Vec<MemoryLimits>, String> {
+    Ok(find_memory_section(wasm)?
+        .map(|section| section.memories)
+        .unwrap_or_default())
+}
+
+/// If `wasm` defines a 64-bit memory, returns a copy of it in which all
+/// memories are 32-bit memories, so that it can be decoded by `parity_wasm`.
+/// Otherwise, returns `None`.
+///
+/// Maximum sizes that don't fit a 32-bit memory are capped, which does not
+/// matter in practice because the memory size is limited by the embedder
+/// anyway.
+pub fn lower_memory64(wasm: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let section = match find_memory_section(wasm)? {
+        Some(section) if section.memories.iter().any(MemoryLimits::is_64) => section,
+        _ => return Ok(None),
+    };
+    let max_pages = u32::MAX as u64;
+    let mut memories = section.memories.clone();
+    for memory in memories.iter_mut() {
+        if memory.initial > max_pages {
+            return Err(format!(
+                "initial memory size of {} pages is too large",
+                memory.initial
+            ));
+        }
+        memory.flags &= !MEMORY64_FLAG;
+        memory.maximum = memory.maximum.map(|maximum| maximum.min(max_pages));
+    }
+    Ok(Some(replace_memory_section(wasm, &section, &memories)))
+}
+
+/// Turns all memories defined in `wasm` into 64-bit memories. This reverts
+/// [`lower_memory64`] once the module has been processed by `parity_wasm`.
+pub fn raise_memory64(wasm: &[u8]) -> Result<Vec<u8>, String> {
+    match find_memory_section(wasm)? {
+        None => Ok(wasm.to_vec()),
+        Some(section) => {
+            let mut memories = section.memories.clone();
+            for memory in memories.iter_mut() {
+                memory.flags |= MEMORY64_FLAG;
+            }
+            Ok(replace_memory_section(wasm, &section, &memories))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+    // A module with a custom section followed by a memory section with a
+    // single memory with the given flags and limits.
+    fn module(flags: u8, limits: &[u64]) -> Vec<u8> {
+        let mut memory = vec![1, flags];
+        for limit in limits {
+            write_leb128(&mut memory, *limit);
+        }
+        let mut wasm = HEADER.to_vec();
+        wasm.extend_from_slice(&[0, 4, 3, b'a', b'b', b'c']);
+        wasm.push(MEMORY_SECTION_ID);
+        write_leb128(&mut wasm, memory.len() as u64);
+        wasm.extend_from_slice(&memory);
+        wasm
+    }
+
+    #[test]
+    fn leb128_roundtrip() {
+        for value in [0, 1, 127, 128, 65536, u32::MAX as u64, u64::MAX] {
+            let mut bytes = vec![];
+            write_leb128(&mut bytes, value);
+            let mut reader = Reader {
+                bytes: &bytes,
+                pos: 0,
+            };
+            assert_eq!(reader.leb128().unwrap(), value);
+            assert_eq!(reader.pos, bytes.len());
+        }
+    }
+
+    #[test]
+    fn reads_memory_limits() {
+        let limits = memory_limits(&module(0x05, &[1, 1 << 20])).unwrap();
+        assert_eq!(limits.len(), 1);
+        assert!(limits[0].is_64());
+        assert_eq!(limits[0].initial, 1);
+        assert_eq!(limits[0].maximum, Some(1 << 20));
+
+        let limits = memory_limits(&module(0x00, &[3])).unwrap();
+        assert!(!limits[0].is_64());
+        assert_eq!(limits[0].maximum, None);
+
+        assert!(memory_limits(HEADER).unwrap().is_empty());
+    }
+
+    #[test]
+    fn lowering_32_bit_memory_is_noop() {
+        assert_eq!(lower_memory64(&module(0x01, &[1, 2])).unwrap(), None);
+    }
+
+    #[test]
+    fn lower_and_raise_roundtrip() {
+        let wasm = module(0x05, &[1, 1 << 20]);
+        let lowered = lower_memory64(&wasm).unwrap().unwrap();
+        assert_eq!(lowered, module(0x01, &[1, 1 << 20]));
+        assert_eq!(raise_memory64(&lowered).unwrap(), wasm);
+    }
+
+    #[test]
+    fn lowering_caps_maximum() {
+        let wasm = module(0x05, &[1, 1 << 48]);
+        let lowered = lower_memory64(&wasm).unwrap().unwrap();
+        assert_eq!(lowered, module(0x01, &[1, u32::MAX as u64]));
+    }
+
+    #[test]
+    fn lowering_rejects_huge_initial_size() {
+        assert!(lower_memory64(&module(0x04, &[1 << 40])).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_memory_section() {
+        let mut wasm = module(0x05, &[1, 2]);
+        wasm.pop();
+        assert!(memory_limits(&wasm).is_err());
+    }
+}
@@ -1,13 +1,14 @@
 //! This module is responsible for validating the wasm binaries that are
 //! installed on the Internet Computer.
 
-use super::errors::into_parity_wasm_error;
+use super::{errors::into_parity_wasm_error, memory64};
 
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_replicated_state::canister_state::execution_state::{
     CustomSection, CustomSectionType, WasmMetadata,
 };
-use ic_types::{NumBytes, NumInstructions};
+use ic_replicated_state::canister_state::WASM_PAGE_SIZE_IN_BYTES;
+use ic_types::{NumBytes, NumInstructions, MAX_WASM64_MEMORY_IN_BYTES};
 use ic_wasm_types::{BinaryEncodedWasm, WasmValidationError};
 use parity_wasm::elements::{
     DataSegment, External, ImportCountType,
@@ -580,6 +581,128 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        // Variants of the System API calls above that take 64-bit addresses
+        // of the Wasm heap, for modules with a 64-bit memory.
+        (
+            "msg_caller_copy64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "msg_arg_data_copy64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "msg_method_name_copy64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "msg_reject_msg_copy64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "msg_reply_data_append64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "msg_reject64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "canister_self_copy64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "call_data_append64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "debug_print64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "trap64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "certified_data_set64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
+        (
+            "data_certificate_copy64",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64, ValueType::I64, ValueType::I64],
+                    return_type: vec![],
+                },
+            )],
+        ),
     ];
 
     valid_system_apis
@@ -855,7 +978,8 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
                 "Empty offset in data segment.".to_string(),
             )),
             Some(expr) => match expr.code() {
-                [Instruction::I32Const(_), Instruction::End] => Ok(()),
+                [Instruction::I32Const(_), Instruction::End]
+                | [Instruction::I64Const(_), Instruction::End] => Ok(()),
                 _ => Err(WasmValidationError::InvalidDataSection(
                     "Invalid offset expression in data segment.".to_string(),
                 )),
@@ -1047,6 +1171,34 @@ fn validate_code_section(module: &Module) -> Result<NumInstructions, WasmValidat
     Ok(max_function_size)
 }
 
+// Checks that a module with a 64-bit memory is allowed on this subnet and that
+// its initial memory size does not exceed `MAX_WASM64_MEMORY_IN_BYTES`.
+// Larger maximum sizes are fine because the embedder caps them anyway. The
+// tighter per-subnet limit is enforced during execution.
+fn validate_memory_section(
+    memories: &[memory64::MemoryLimits],
+    config: &EmbeddersConfig,
+) -> Result<(), WasmValidationError> {
+    if !memories.iter().any(memory64::MemoryLimits::is_64) {
+        return Ok(());
+    }
+    if config.feature_flags.wasm64 == FlagStatus::Disabled {
+        return Err(WasmValidationError::InvalidMemorySection(
+            "64-bit memories are not supported.".to_string(),
+        ));
+    }
+    let max_pages = MAX_WASM64_MEMORY_IN_BYTES / WASM_PAGE_SIZE_IN_BYTES as u64;
+    for memory in memories {
+        if memory.initial > max_pages {
+            return Err(WasmValidationError::InvalidMemorySection(format!(
+                "Initial memory size of {} pages exceeds the maximum of {} pages.",
+                memory.initial, max_pages
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Sets Wasmtime flags to ensure deterministic execution.
 pub fn ensure_determinism(config: &mut Config) {
     config
@@ -1058,6 +1210,7 @@ pub fn ensure_determinism(config: &mut Config) {
 fn can_compile(wasm: &BinaryEncodedWasm) -> Result<(), WasmValidationError> {
     let mut config = wasmtime::Config::default();
     ensure_determinism(&mut config);
+    config.wasm_memory64(true);
     let engine = wasmtime::Engine::new(&config).map_err(|_| {
         WasmValidationError::WasmtimeValidation(String::from("Failed to initialize Wasm engine"))
     })?;
@@ -1106,6 +1259,7 @@ pub struct WasmValidationDetails {
 /// * Data
 /// * Global
 /// * Function
+/// * Memory
 /// * CustomSections
 ///
 /// Additionally, it ensures that the wasm binary can actually compile.
@@ -1114,8 +1268,15 @@ pub(super) fn validate_wasm_binary(
     config: &EmbeddersConfig,
 ) -> Result<WasmValidationDetails, WasmValidationError> {
     can_compile(wasm)?;
-    let module = parity_wasm::deserialize_buffer::<Module>(wasm.as_slice())
-        .map_err(|err| WasmValidationError::ParityDeserializeError(into_parity_wasm_error(err)))?;
+    let memories = memory64::memory_limits(wasm.as_slice())
+        .map_err(WasmValidationError::InvalidMemorySection)?;
+    validate_memory_section(&memories, config)?;
+    let lowered_wasm = memory64::lower_memory64(wasm.as_slice())
+        .map_err(WasmValidationError::InvalidMemorySection)?;
+    let module = parity_wasm::deserialize_buffer::<Module>(
+        lowered_wasm.as_deref().unwrap_or_else(|| wasm.as_slice()),
+    )
+    .map_err(|err| WasmValidationError::ParityDeserializeError(into_parity_wasm_error(err)))?;
     let imports_details = validate_import_section(&module)?;
     let reserved_exports = validate_export_section(&module)?;
     validate_data_section(&module)?;
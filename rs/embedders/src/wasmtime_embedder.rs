@@ -23,7 +23,7 @@ use ic_replicated_state::{EmbedderCache, Global, NumWasmPages, PageIndex, PageMa
 use ic_sys::PAGE_SIZE;
 use ic_types::{
     methods::{FuncRef, WasmMethod},
-    CanisterId,
+    CanisterId, MAX_WASM64_MEMORY_IN_BYTES,
 };
 use ic_wasm_types::{BinaryEncodedWasm, WasmEngineError};
 use memory_tracker::{DirtyPageTracking, SigsegvMemoryTracker};
@@ -127,17 +127,19 @@ impl WasmtimeEmbedder {
         let mut config = wasmtime::Config::default();
         config.cranelift_opt_level(OptLevel::None);
         ensure_determinism(&mut config);
+        config.wasm_memory64(true);
         let raw_creator = MmapMemoryCreator {};
         let mem_creator = Arc::new(WasmtimeMemoryCreator::new(
             raw_creator,
             Arc::clone(&self.created_memories),
+            MAX_WASM64_MEMORY_IN_BYTES / wasmtime_environ::WASM_PAGE_SIZE as u64,
         ));
         config.with_host_memory(mem_creator);
 
         config
             // maximum size in bytes where a linear memory is considered
-            // static. setting this to maximum Wasm memory size will guarantee
-            // the memory is always static.
+            // static. setting this to maximum 32-bit Wasm memory size will
+            // guarantee that 32-bit memories are always static.
             .static_memory_maximum_size(
                 wasmtime_environ::WASM_PAGE_SIZE as u64 * wasmtime_environ::WASM32_MAX_PAGES as u64,
             )
@@ -582,11 +584,18 @@ impl<S: SystemApi> WasmtimeInstance<S> {
     }
 
     /// Returns the heap size.
-    /// Result is guaranteed to fit in a `u32`.
+    /// Result is guaranteed to fit in a `u32` unless the heap is a 64-bit
+    /// memory.
     pub fn heap_size(&mut self) -> NumWasmPages {
         NumWasmPages::from(self.memory().map_or(0, |mem| mem.size(&self.store)) as usize)
     }
 
+    /// Returns true if the heap is a 64-bit memory.
+    pub fn is_memory64(&mut self) -> bool {
+        self.memory()
+            .map_or(false, |mem| mem.ty(&self.store).is_64())
+    }
+
     /// Returns a list of exported globals.
     pub fn get_exported_globals(&mut self) -> Vec<Global> {
         let globals: Vec<_> = self
@@ -218,6 +218,40 @@ fn observe_execution_complexity<S: SystemApi>(
     Ok(())
 }
 
+/// Returns the part of the Wasm `heap` starting at the 64-bit address `addr`.
+fn heap_at<'a>(method_name: &str, heap: &'a mut [u8], addr: i64) -> HypervisorResult<&'a mut [u8]> {
+    let len = heap.len();
+    match usize::try_from(addr as u64) {
+        Ok(addr) if addr <= len => Ok(&mut heap[addr..]),
+        _ => Err(HypervisorError::ContractViolation(format!(
+            "{}: address {} exceeds the heap size {}",
+            method_name, addr as u64, len
+        ))),
+    }
+}
+
+/// Like `heap_at` but returns an empty slice for addresses out of bounds, for
+/// System API calls that must not fail because of invalid memory ranges.
+fn heap_at_or_empty(heap: &mut [u8], addr: i64) -> &mut [u8] {
+    let addr = usize::try_from(addr as u64).unwrap_or(usize::MAX);
+    let len = heap.len();
+    &mut heap[addr.min(len)..]
+}
+
+/// Converts an offset or size passed to a 64-bit System API call to `u32`.
+fn to_u32(method_name: &str, value: i64) -> HypervisorResult<u32> {
+    u32::try_from(value as u64).map_err(|_| {
+        HypervisorError::ContractViolation(format!(
+            "{}: value {} is out of bounds",
+            method_name, value as u64
+        ))
+    })
+}
+
+fn clamp_to_u32(value: i64) -> u32 {
+    u32::try_from(value as u64).unwrap_or(u32::MAX)
+}
+
 /// A helper to pass wasmtime counters to the System API
 fn ic0_performance_counter_helper<S: SystemApi>(
     log: &ReplicaLogger,
@@ -1208,6 +1242,377 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("__", "update_available_memory_64", {
+            move |mut caller: Caller<'_, StoreData<S>>,
+                  native_memory_grow_res: i64,
+                  additional_pages: i64| {
+                if native_memory_grow_res == -1 {
+                    return Ok(-1);
+                }
+                // A successful `memory.grow` never exceeds the maximum memory
+                // size, which is far below 2^32 pages.
+                match (
+                    i32::try_from(native_memory_grow_res),
+                    u32::try_from(additional_pages),
+                ) {
+                    (Ok(native_memory_grow_res), Ok(additional_pages)) => {
+                        with_system_api(&mut caller, |s| {
+                            s.update_available_memory(native_memory_grow_res, additional_pages)
+                        })
+                        .map(i64::from)
+                        .map_err(|e| process_err(caller, e))
+                    }
+                    _ => Err(process_err(caller, HypervisorError::OutOfMemory)),
+                }
+            }
+        })
+        .unwrap();
+
+    // The System API calls below take 64-bit addresses of the Wasm heap. They
+    // are implemented by the 32-bit System API calls on the part of the heap
+    // that starts at the given address, since the sizes and offsets into the
+    // copied data always fit into 32 bits.
+
+    linker
+        .func_wrap("ic0", "msg_caller_copy64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_CALLER_COPY,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: 0.into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    let heap = heap_at("ic0.msg_caller_copy64", memory, dst)?;
+                    let (offset, size) = (
+                        to_u32("ic0.msg_caller_copy64", offset)?,
+                        to_u32("ic0.msg_caller_copy64", size)?,
+                    );
+                    system_api.ic0_msg_caller_copy(0, offset, size, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_arg_data_copy64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::MSG_ARG_DATA_COPY,
+                    size as u32,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_ARG_DATA_COPY,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: 0.into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    let heap = heap_at("ic0.msg_arg_data_copy64", memory, dst)?;
+                    let (offset, size) = (
+                        to_u32("ic0.msg_arg_data_copy64", offset)?,
+                        to_u32("ic0.msg_arg_data_copy64", size)?,
+                    );
+                    system_api.ic0_msg_arg_data_copy(0, offset, size, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_method_name_copy64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::MSG_METHOD_NAME_COPY,
+                    size as u32,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_METHOD_NAME_COPY,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: 0.into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    let heap = heap_at("ic0.msg_method_name_copy64", memory, dst)?;
+                    let (offset, size) = (
+                        to_u32("ic0.msg_method_name_copy64", offset)?,
+                        to_u32("ic0.msg_method_name_copy64", size)?,
+                    );
+                    system_api.ic0_msg_method_name_copy(0, offset, size, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_reply_data_append64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::MSG_REPLY_DATA_APPEND,
+                    size as u32,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_REPLY_DATA_APPEND,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: (size as u64).into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    let heap = heap_at("ic0.msg_reply_data_append64", memory, src)?;
+                    system_api.ic0_msg_reply_data_append(
+                        0,
+                        to_u32("ic0.msg_reply_data_append64", size)?,
+                        heap,
+                    )
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_reject64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::MSG_REJECT,
+                    size as u32,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_REJECT,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: (size as u64).into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    let heap = heap_at("ic0.msg_reject64", memory, src)?;
+                    system_api.ic0_msg_reject(0, to_u32("ic0.msg_reject64", size)?, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_reject_msg_copy64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::MSG_REJECT_MSG_COPY,
+                    size as u32,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_REJECT_MSG_COPY,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: 0.into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    let heap = heap_at("ic0.msg_reject_msg_copy64", memory, dst)?;
+                    let (offset, size) = (
+                        to_u32("ic0.msg_reject_msg_copy64", offset)?,
+                        to_u32("ic0.msg_reject_msg_copy64", size)?,
+                    );
+                    system_api.ic0_msg_reject_msg_copy(0, offset, size, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "canister_self_copy64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::CANISTER_SELF_COPY,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: 0.into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    let heap = heap_at("ic0.canister_self_copy64", memory, dst)?;
+                    let (offset, size) = (
+                        to_u32("ic0.canister_self_copy64", offset)?,
+                        to_u32("ic0.canister_self_copy64", size)?,
+                    );
+                    system_api.ic0_canister_self_copy(0, offset, size, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "call_data_append64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::CALL_DATA_APPEND,
+                    size as u32,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::CALL_DATA_APPEND,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: (size as u64).into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    let heap = heap_at("ic0.call_data_append64", memory, src)?;
+                    system_api.ic0_call_data_append(
+                        0,
+                        to_u32("ic0.call_data_append64", size)?,
+                        heap,
+                    )
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "trap64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| -> Result<(), _> {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::TRAP,
+                    size as u32,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::TRAP,
+                        memory: (size as u64).into(),
+                        disk: (size as u64).into(),
+                        network: (size as u64).into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    system_api.ic0_trap(0, clamp_to_u32(size), heap_at_or_empty(memory, src))
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "certified_data_set64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::CERTIFIED_DATA_SET,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: 0.into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    let heap = heap_at("ic0.certified_data_set64", memory, src)?;
+                    system_api.ic0_certified_data_set(
+                        0,
+                        to_u32("ic0.certified_data_set64", size)?,
+                        heap,
+                    )
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "data_certificate_copy64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::DATA_CERTIFICATE_COPY,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: 0.into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    let heap = heap_at("ic0.data_certificate_copy64", memory, dst)?;
+                    let (offset, size) = (
+                        to_u32("ic0.data_certificate_copy64", offset)?,
+                        to_u32("ic0.data_certificate_copy64", size)?,
+                    );
+                    system_api.ic0_data_certificate_copy(0, offset, size, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "debug_print64", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::DEBUG_PRINT,
+                    size as u32,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::DEBUG_PRINT,
+                        memory: (size as u64).into(),
+                        disk: (size as u64).into(),
+                        network: (size as u64).into(),
+                    },
+                )?;
+                match (
+                    caller.data().system_api.subnet_type(),
+                    rate_limiting_of_debug_prints,
+                ) {
+                    (SubnetType::Application, FlagStatus::Enabled) => Ok(()),
+                    (SubnetType::VerifiedApplication, FlagStatus::Enabled) => Ok(()),
+                    (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => {
+                        with_memory_and_system_api(caller, |system_api, memory| {
+                            system_api.ic0_debug_print(
+                                0,
+                                clamp_to_u32(size),
+                                heap_at_or_empty(memory, src),
+                            )
+                        })
+                    }
+                }
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "canister_status", {
             move |mut caller: Caller<'_, StoreData<S>>| {
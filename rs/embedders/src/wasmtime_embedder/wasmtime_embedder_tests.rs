@@ -15,7 +15,7 @@ use ic_system_api::{
 use ic_test_utilities::{
     cycles_account_manager::CyclesAccountManagerBuilder, types::ids::canister_test_id,
 };
-use ic_types::{ComputeAllocation, NumBytes, NumInstructions};
+use ic_types::{ComputeAllocation, NumBytes, NumInstructions, MAX_WASM64_MEMORY_IN_BYTES};
 use ic_wasm_types::BinaryEncodedWasm;
 
 use lazy_static::lazy_static;
@@ -54,6 +54,7 @@ fn test_wasmtime_system_api() {
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
+            wasm64_memory_limit: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
         },
         *MAX_SUBNET_AVAILABLE_MEMORY,
         Memory::default(),
@@ -1,5 +1,5 @@
 use assert_matches::assert_matches;
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_embedders::{
     wasm_utils::{
         validate_and_instrument_for_testing,
@@ -19,10 +19,11 @@ fn wat2wasm(wat: &str) -> Result<BinaryEncodedWasm, wabt::Error> {
     features.enable_multi_value();
     wabt::wat2wasm_with_features(wat, features).map(BinaryEncodedWasm::new)
 }
-use ic_replicated_state::canister_state::execution_state::{
-    CustomSection, CustomSectionType, WasmMetadata,
+use ic_replicated_state::canister_state::{
+    execution_state::{CustomSection, CustomSectionType, WasmMetadata},
+    WASM_PAGE_SIZE_IN_BYTES,
 };
-use ic_types::{NumBytes, NumInstructions};
+use ic_types::{NumBytes, NumInstructions, MAX_WASM64_MEMORY_IN_BYTES};
 use maplit::btreemap;
 use parity_wasm::elements::{CustomSection as WasmCustomSection, Module, Section};
 
@@ -773,3 +774,73 @@ fn function_with_result_is_invalid() {
         ))
     );
 }
+
+// Returns a module that only defines a 64-bit memory of `initial` pages.
+// `wabt` does not support the memory64 proposal, so the module is encoded by
+// hand.
+fn wasm64_module(initial: u64) -> BinaryEncodedWasm {
+    let mut limits = vec![0x04];
+    let mut value = initial;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            limits.push(byte);
+            break;
+        }
+        limits.push(byte | 0x80);
+    }
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    wasm.extend_from_slice(&[5, limits.len() as u8 + 1, 1]);
+    wasm.extend_from_slice(&limits);
+    BinaryEncodedWasm::new(wasm)
+}
+
+fn wasm64_config() -> EmbeddersConfig {
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.wasm64 = FlagStatus::Enabled;
+    config
+}
+
+#[test]
+fn memory64_is_invalid_if_disabled() {
+    assert_matches!(
+        validate_wasm_binary(&wasm64_module(1), &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidMemorySection(_))
+    );
+}
+
+#[test]
+fn can_validate_memory64_if_enabled() {
+    assert_eq!(
+        validate_wasm_binary(&wasm64_module(1), &wasm64_config()),
+        Ok(WasmValidationDetails::default())
+    );
+}
+
+#[test]
+fn memory64_larger_than_max_size_is_invalid() {
+    let max_pages = MAX_WASM64_MEMORY_IN_BYTES / WASM_PAGE_SIZE_IN_BYTES as u64;
+    assert_eq!(
+        validate_wasm_binary(&wasm64_module(max_pages), &wasm64_config()),
+        Ok(WasmValidationDetails::default())
+    );
+    assert_matches!(
+        validate_wasm_binary(&wasm64_module(max_pages + 1), &wasm64_config()),
+        Err(WasmValidationError::InvalidMemorySection(_))
+    );
+}
+
+#[test]
+fn instrumented_memory64_module_keeps_64_bit_memory() {
+    let embedder = WasmtimeEmbedder::new(wasm64_config(), no_op_logger());
+    let (_, output) = validate_and_instrument_for_testing(&embedder, &wasm64_module(1)).unwrap();
+    let module = embedder.compile(&output.binary).unwrap();
+    assert!(module
+        .imports()
+        .any(|import| import.module() == "__" && import.name() == "update_available_memory_64"));
+    match module.get_export("memory") {
+        Some(wasmtime::ExternType::Memory(memory)) => assert!(memory.is_64()),
+        other => panic!("unexpected memory export {:?}", other),
+    }
+}
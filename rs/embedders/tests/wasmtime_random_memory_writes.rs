@@ -18,7 +18,7 @@ use ic_test_utilities::{
 };
 use ic_types::{
     methods::{FuncRef, WasmMethod},
-    ComputeAllocation, Cycles, NumBytes, NumInstructions, PrincipalId,
+    ComputeAllocation, Cycles, NumBytes, NumInstructions, PrincipalId, MAX_WASM64_MEMORY_IN_BYTES,
 };
 use ic_wasm_types::BinaryEncodedWasm;
 use lazy_static::lazy_static;
@@ -76,6 +76,7 @@ fn test_api_for_update(
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
+            wasm64_memory_limit: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
         },
         *MAX_SUBNET_AVAILABLE_MEMORY,
         Memory::default(),
@@ -21,7 +21,7 @@ use ic_test_utilities::{
 use ic_types::{
     messages::{CallbackId, Payload, RejectContext},
     methods::{Callback, WasmClosure},
-    Cycles, MemoryAllocation, NumBytes, NumInstructions, Time,
+    Cycles, MemoryAllocation, NumBytes, NumInstructions, Time, MAX_WASM64_MEMORY_IN_BYTES,
 };
 use ic_wasm_types::CanisterModule;
 use lazy_static::lazy_static;
@@ -130,6 +130,7 @@ where
         compute_allocation: canister_state.scheduler_state.compute_allocation,
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
+        wasm64_memory_limit: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
     };
 
     BenchmarkArgs {
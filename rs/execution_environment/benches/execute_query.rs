@@ -62,7 +62,7 @@ pub fn bench_execute_query(c: &mut Criterion) {
                 subnet_available_memory,
             };
             let instructions_before = round_limits.instructions;
-            let (_, _, result) = execute_non_replicated_query(
+            let (_, _, result, _) = execute_non_replicated_query(
                 NonReplicatedQueryKind::Pure { caller: sender },
                 "test",
                 &[],
@@ -13,7 +13,7 @@ use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
     CanisterInstallMode, CanisterStatusResultV2, CanisterStatusType, InstallCodeArgs,
-    Method as Ic00Method,
+    Method as Ic00Method, QueryStatsResult,
 };
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, IngressHistoryWriter, SubnetAvailableMemory,
@@ -406,6 +406,7 @@ impl CanisterManager {
         canister: &mut CanisterState,
     ) {
         // Note: At this point, the settings are validated.
+        canister.system_state.canister_version += 1;
         if let Some(controller) = settings.controller {
             // Remove all the other controllers and add the new one.
             canister.system_state.controllers.clear();
@@ -735,6 +736,28 @@ impl CanisterManager {
         }
         execution_parameters.compute_allocation = compute_allocation;
 
+        // With deterministic time slicing the instruction limit is so large
+        // that prepaying for all of it may exceed the balance of the canister.
+        // In that case the canister prepays only for the instructions it can
+        // afford, but at least for one slice, and the limit is lowered to
+        // match. Without deterministic time slicing the slice covers the
+        // entire message, so the limit never changes.
+        let affordable_instructions = self
+            .cycles_account_manager
+            .affordable_execution_instructions(
+                &canister.system_state,
+                memory_usage,
+                compute_allocation,
+                message_instruction_limit,
+                subnet_size,
+            );
+        let message_instruction_limit = affordable_instructions
+            .max(execution_parameters.instruction_limits.slice())
+            .min(message_instruction_limit);
+        execution_parameters
+            .instruction_limits
+            .update(message_instruction_limit);
+
         if let Err(err) = self.cycles_account_manager.withdraw_execution_cycles(
             &mut canister.system_state,
             memory_usage,
@@ -806,6 +829,10 @@ impl CanisterManager {
                     canister_layout_path,
                     config: self.config.clone(),
                     message,
+                    prepaid_execution_cycles: self
+                        .cycles_account_manager
+                        .execution_cost(message_instruction_limit, subnet_size),
+                    original_balance: canister.system_state.balance(),
                 };
                 DtsInstallCodeResult::Paused {
                     canister,
@@ -981,6 +1008,7 @@ impl CanisterManager {
         let compute_allocation = canister.scheduler_state.compute_allocation;
         let memory_allocation = canister.memory_allocation();
         let freeze_threshold = canister.system_state.freeze_threshold;
+        let total_query_stats = &canister.system_state.total_query_stats;
 
         Ok(CanisterStatusResultV2::new(
             canister.status(),
@@ -1003,6 +1031,12 @@ impl CanisterManager {
                     subnet_size,
                 )
                 .get(),
+            QueryStatsResult::new(
+                total_query_stats.num_calls,
+                total_query_stats.num_instructions,
+                total_query_stats.ingress_payload_size,
+                total_query_stats.egress_payload_size,
+            ),
         ))
     }
 
@@ -1668,6 +1702,7 @@ pub fn uninstall_canister(
 ) -> Vec<Response> {
     // Drop the canister's execution state.
     canister.execution_state = None;
+    canister.system_state.canister_version += 1;
 
     // Drop its certified data.
     canister.system_state.certified_data = Vec::new();
@@ -1821,6 +1856,8 @@ fn finish_install_code(
                 };
             }
 
+            new_canister.system_state.canister_version += 1;
+
             // Refund the left over execution cycles to the new canister and
             // replace the old canister with the new one.
             let old_wasm_hash = get_wasm_hash(&old_canister);
@@ -1847,8 +1884,6 @@ fn finish_install_code(
                 );
             }
 
-            // TODO(RUN-221): Copy parts of `old_canister_state` that could have changed
-            // externally into the new canister state in `result`.
             DtsInstallCodeResult::Finished {
                 canister: new_canister,
                 message,
@@ -1885,6 +1920,10 @@ fn finish_install_code(
 /// This struct saves PausedInstallCodeExecution (as opposed to PausedWasmExecution),
 /// which represents paused state of one of the subroutines of either (re)install
 /// or upgrade paths.
+///
+/// The routine works on a copy of the canister. The canister in the
+/// replicated state keeps its old code and does not execute any other
+/// messages until the execution finishes or gets aborted.
 #[derive(Debug)]
 pub(crate) struct PausedInstallCodeExecution {
     paused_routine: Box<dyn PausedInstallCodeRoutine>,
@@ -1893,6 +1932,11 @@ pub(crate) struct PausedInstallCodeExecution {
     canister_layout_path: PathBuf,
     config: CanisterMgrConfig,
     message: RequestOrIngress,
+    // The execution cycles withdrawn from the canister before the execution.
+    prepaid_execution_cycles: Cycles,
+    // The cycles balance of the canister when the execution was paused for
+    // the first time.
+    original_balance: Cycles,
 }
 
 impl PausedInstallCodeExecution {
@@ -1907,17 +1951,24 @@ impl PausedInstallCodeExecution {
             InstallCodeRoutineResult::Finished {
                 instructions_left,
                 result,
-            } => finish_install_code(
-                canister,
-                self.message,
-                self.message_instruction_limit,
-                instructions_left,
-                result,
-                self.mode,
-                self.canister_layout_path,
-                &self.config,
-                round,
-            ),
+            } => {
+                let original_balance = self.original_balance;
+                let result = result.map(|(mut new_canister, heap_delta)| {
+                    copy_external_changes(&canister, original_balance, &mut new_canister);
+                    (new_canister, heap_delta)
+                });
+                finish_install_code(
+                    canister,
+                    self.message,
+                    self.message_instruction_limit,
+                    instructions_left,
+                    result,
+                    self.mode,
+                    self.canister_layout_path,
+                    &self.config,
+                    round,
+                )
+            }
             InstallCodeRoutineResult::Paused { paused_execution } => {
                 let paused_execution = PausedInstallCodeExecution {
                     paused_routine: paused_execution,
@@ -1931,11 +1982,50 @@ impl PausedInstallCodeExecution {
         }
     }
 
-    pub fn abort(self) -> RequestOrIngress {
+    /// Aborts the paused execution and returns the original message. All
+    /// changes done by the execution are discarded and the execution cycles
+    /// withdrawn at the start are refunded, because the message is going to
+    /// be executed again from scratch.
+    pub fn abort(
+        self,
+        canister: &mut CanisterState,
+        cycles_account_manager: &CyclesAccountManager,
+    ) -> RequestOrIngress {
         self.paused_routine.abort();
+        cycles_account_manager
+            .refund_cycles(&mut canister.system_state, self.prepaid_execution_cycles);
         self.message
     }
 }
 
+/// Copies the parts of the canister that could have changed while the
+/// `install_code` execution was paused from `old_canister` into the result of
+/// the execution:
+/// - new messages could have been inducted into the queues,
+/// - ingress induction could have charged cycles,
+/// - the scheduler could have updated its state.
+fn copy_external_changes(
+    old_canister: &CanisterState,
+    original_balance: Cycles,
+    new_canister: &mut CanisterState,
+) {
+    new_canister
+        .system_state
+        .copy_queues_from(&old_canister.system_state);
+
+    let old_balance = old_canister.system_state.balance();
+    let new_balance = new_canister.system_state.balance_mut();
+    if old_balance < original_balance {
+        *new_balance -= original_balance - old_balance;
+    } else {
+        *new_balance += old_balance - original_balance;
+    }
+
+    // The compute allocation could have been changed by the execution.
+    let compute_allocation = new_canister.scheduler_state.compute_allocation;
+    new_canister.scheduler_state = old_canister.scheduler_state.clone();
+    new_canister.scheduler_state.compute_allocation = compute_allocation;
+}
+
 #[cfg(test)]
 pub(crate) mod tests;
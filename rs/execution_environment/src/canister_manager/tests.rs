@@ -58,7 +58,7 @@ use ic_types::{
     messages::{CallbackId, StopCanisterContext},
     nominal_cycles::NominalCycles,
     CanisterId, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions,
-    QueryAllocation, SubnetId, UserId,
+    QueryAllocation, ReplicaVersion, SubnetId, UserId, MAX_WASM64_MEMORY_IN_BYTES,
 };
 use ic_wasm_types::{CanisterModule, WasmValidationError};
 use lazy_static::lazy_static;
@@ -96,6 +96,7 @@ lazy_static! {
         compute_allocation: ComputeAllocation::default(),
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
+        wasm64_memory_limit: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
     };
 }
 
@@ -200,6 +201,7 @@ impl CanisterManagerBuilder {
             subnet_type,
             no_op_logger(),
             Arc::clone(&cycles_account_manager),
+            &ReplicaVersion::default(),
         );
         let hypervisor = Arc::new(hypervisor);
         CanisterManager::new(
@@ -9,6 +9,7 @@ use crate::execution::common::{validate_canister, validate_method};
 use crate::execution_environment::RoundLimits;
 use crate::{Hypervisor, NonReplicatedQueryKind};
 use ic_error_types::UserError;
+use ic_interfaces::execution_environment::SystemApiCallCounters;
 use ic_replicated_state::{CallOrigin, CanisterState, NetworkTopology};
 use ic_system_api::{ApiType, ExecutionParameters};
 use ic_types::ingress::WasmResult;
@@ -16,6 +17,9 @@ use ic_types::methods::{FuncRef, WasmMethod};
 use ic_types::{Cycles, NumInstructions, Time};
 
 // Execute non replicated query.
+//
+// Besides the canister and the result, returns the number of instructions
+// left and the counters of the system API calls made by the query.
 #[allow(clippy::too_many_arguments)]
 pub fn execute_non_replicated_query(
     query_kind: NonReplicatedQueryKind,
@@ -32,6 +36,7 @@ pub fn execute_non_replicated_query(
     CanisterState,
     NumInstructions,
     Result<Option<WasmResult>, UserError>,
+    SystemApiCallCounters,
 ) {
     // Validate that the canister is running.
     if let Err(err) = validate_canister(&canister) {
@@ -39,6 +44,7 @@ pub fn execute_non_replicated_query(
             canister,
             execution_parameters.instruction_limits.message(),
             Err(err),
+            SystemApiCallCounters::default(),
         );
     }
 
@@ -52,6 +58,7 @@ pub fn execute_non_replicated_query(
             canister,
             execution_parameters.instruction_limits.message(),
             Err(err.into_user_error(&canister_id)),
+            SystemApiCallCounters::default(),
         );
     }
 
@@ -112,5 +119,10 @@ pub fn execute_non_replicated_query(
     let result = output
         .wasm_result
         .map_err(|err| err.into_user_error(&canister.canister_id()));
-    (canister, output.num_instructions_left, result)
+    (
+        canister,
+        output.num_instructions_left,
+        result,
+        output.system_api_call_counters,
+    )
 }
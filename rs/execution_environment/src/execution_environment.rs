@@ -956,8 +956,12 @@ impl ExecutionEnvironment {
             time,
         };
 
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            &network_topology,
+        );
 
         execute_call(
             canister,
@@ -986,8 +990,12 @@ impl ExecutionEnvironment {
             instruction_limits.slice(),
             instruction_limits.slice(),
         );
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            &network_topology,
+        );
         let (canister, result) = execute_heartbeat(
             canister,
             network_topology,
@@ -1041,6 +1049,7 @@ impl ExecutionEnvironment {
         canister: &CanisterState,
         instruction_limits: InstructionLimits,
         execution_mode: ExecutionMode,
+        network_topology: &NetworkTopology,
     ) -> ExecutionParameters {
         ExecutionParameters {
             instruction_limits,
@@ -1048,6 +1057,7 @@ impl ExecutionEnvironment {
             compute_allocation: canister.scheduler_state.compute_allocation,
             subnet_type: self.own_subnet_type,
             execution_mode,
+            wasm64_memory_limit: network_topology.get_max_wasm64_memory_size(&self.own_subnet_id),
         }
     }
 
@@ -1215,8 +1225,12 @@ impl ExecutionEnvironment {
         network_topology: Arc<NetworkTopology>,
         round_limits: &mut RoundLimits,
     ) -> ExecuteMessageResult {
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            &network_topology,
+        );
         let round = RoundContext {
             network_topology: &*network_topology,
             hypervisor: &self.hypervisor,
@@ -1307,8 +1321,12 @@ impl ExecutionEnvironment {
             self.config.max_instructions_for_message_acceptance_calls,
             self.config.max_instructions_for_message_acceptance_calls,
         );
-        let execution_parameters =
-            self.execution_parameters(canister_state, instruction_limits, execution_mode);
+        let execution_parameters = self.execution_parameters(
+            canister_state,
+            instruction_limits,
+            execution_mode,
+            &state.metadata.network_topology,
+        );
 
         // Letting the canister grow arbitrarily when executing the
         // query is fine as we do not persist state modifications.
@@ -1346,8 +1364,12 @@ impl ExecutionEnvironment {
             max_instructions_per_message,
             max_instructions_per_message,
         );
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::NonReplicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::NonReplicated,
+            &state.metadata.network_topology,
+        );
         let subnet_available_memory = subnet_memory_capacity(&self.config);
         let mut round_limits = RoundLimits {
             instructions: as_round_instructions(max_instructions_per_message),
@@ -1750,6 +1772,10 @@ impl ExecutionEnvironment {
             compute_allocation: ComputeAllocation::default(),
             subnet_type: state.metadata.own_subnet_type,
             execution_mode: ExecutionMode::Replicated,
+            wasm64_memory_limit: state
+                .metadata
+                .network_topology
+                .get_max_wasm64_memory_size(&state.metadata.own_subnet_id),
         };
 
         let dts_result = self.canister_manager.install_code_dts(
@@ -1946,7 +1972,7 @@ impl ExecutionEnvironment {
                         }
                         ExecutionTask::PausedInstallCode(id) => {
                             let paused = self.take_paused_install_code(id).unwrap();
-                            let message = paused.abort();
+                            let message = paused.abort(canister, &self.cycles_account_manager);
                             ExecutionTask::AbortedInstallCode(message)
                         }
                     })
@@ -2123,7 +2149,7 @@ pub fn execute_canister(
         NextExecution::StartNew | NextExecution::ContinueLong => {}
     }
 
-    match canister.system_state.task_queue.pop_front() {
+    let mut result = match canister.system_state.task_queue.pop_front() {
         Some(task) => match task {
             ExecutionTask::Heartbeat => {
                 let (canister, result) = exec_env.execute_canister_heartbeat(
@@ -2187,7 +2213,9 @@ pub fn execute_canister(
                 subnet_size,
             )
         }
-    }
+    };
+    result.canister.system_state.canister_version += 1;
+    result
 }
 
 fn get_master_ecdsa_public_key<'a>(
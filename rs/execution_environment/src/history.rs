@@ -288,5 +288,6 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         CanisterInstallCodeRateLimited => {
             "Canister is rate limited because it executed too many instructions in the previous install_code messages"
         }
+        CertifiedStateRemoved => "Certified State Removed",
     }
 }
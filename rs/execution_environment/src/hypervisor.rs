@@ -20,7 +20,8 @@ use ic_sys::PAGE_SIZE;
 use ic_system_api::ExecutionParameters;
 use ic_system_api::{sandbox_safe_system_state::SandboxSafeSystemState, ApiType};
 use ic_types::{
-    ingress::WasmResult, methods::FuncRef, CanisterId, NumBytes, NumInstructions, SubnetId, Time,
+    ingress::WasmResult, methods::FuncRef, CanisterId, NumBytes, NumInstructions, ReplicaVersion,
+    SubnetId, Time,
 };
 use ic_wasm_types::CanisterModule;
 use prometheus::{Histogram, IntCounterVec, IntGauge};
@@ -218,6 +219,7 @@ impl Hypervisor {
         own_subnet_type: SubnetType,
         log: ReplicaLogger,
         cycles_account_manager: Arc<CyclesAccountManager>,
+        replica_version: &ReplicaVersion,
     ) -> Self {
         let mut embedder_config = EmbeddersConfig::new();
         embedder_config.query_execution_threads = config.query_execution_threads;
@@ -225,6 +227,22 @@ impl Hypervisor {
             config.rate_limiting_of_debug_prints;
         embedder_config.feature_flags.module_sharing = config.module_sharing;
         embedder_config.cost_to_compile_wasm_instruction = config.cost_to_compile_wasm_instruction;
+        embedder_config.feature_flags.wasm64 = config.wasm64;
+
+        let compilation_cache = match (
+            config.persistent_compilation_cache,
+            &config.compilation_cache_dir,
+        ) {
+            (FlagStatus::Enabled, Some(dir)) => CompilationCache::new_persistent(
+                config.module_sharing,
+                dir,
+                config.compilation_cache_capacity,
+                replica_version,
+                &embedder_config,
+                log.clone(),
+            ),
+            _ => CompilationCache::new(config.module_sharing),
+        };
 
         let wasm_executor: Arc<dyn WasmExecutor> = match config.canister_sandboxing_flag {
             FlagStatus::Enabled => {
@@ -253,7 +271,7 @@ impl Hypervisor {
             own_subnet_type,
             log,
             cycles_account_manager,
-            compilation_cache: Arc::new(CompilationCache::new(config.module_sharing)),
+            compilation_cache: Arc::new(compilation_cache),
             deterministic_time_slicing: config.deterministic_time_slicing,
             cost_to_compile_wasm_instruction: config.cost_to_compile_wasm_instruction,
         }
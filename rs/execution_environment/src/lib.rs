@@ -10,6 +10,7 @@ mod hypervisor;
 mod ingress_filter;
 mod metrics;
 mod query_handler;
+mod query_stats;
 mod scheduler;
 mod types;
 pub mod util;
@@ -36,10 +37,11 @@ use ic_logger::ReplicaLogger;
 use ic_metrics::MetricsRegistry;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{CallOrigin, NetworkTopology, ReplicatedState};
-use ic_types::{messages::CallContextId, SubnetId};
+use ic_types::{messages::CallContextId, ReplicaVersion, SubnetId};
 use ingress_filter::IngressFilter;
 use query_handler::HttpQueryHandler;
 pub use query_handler::InternalHttpQueryHandler;
+pub use query_stats::{QueryStatsCollector, QueryStatsPayloadBuilderImpl};
 use scheduler::SchedulerImpl;
 use std::sync::{Arc, Mutex};
 use tower::limit::GlobalConcurrencyLimitLayer;
@@ -85,6 +87,7 @@ pub struct ExecutionServices {
     pub sync_query_handler: Arc<dyn QueryHandler<State = ReplicatedState>>,
     pub async_query_handler: QueryExecutionService,
     pub anonymous_query_handler: AnonymousQueryService,
+    pub query_stats_collector: Arc<QueryStatsCollector>,
     pub scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
 }
 
@@ -101,6 +104,7 @@ impl ExecutionServices {
         config: Config,
         cycles_account_manager: Arc<CyclesAccountManager>,
         state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        replica_version: &ReplicaVersion,
     ) -> ExecutionServices {
         let hypervisor = Arc::new(Hypervisor::new(
             config.clone(),
@@ -109,6 +113,7 @@ impl ExecutionServices {
             own_subnet_type,
             logger.clone(),
             Arc::clone(&cycles_account_manager),
+            replica_version,
         ));
 
         let ingress_history_writer = Arc::new(IngressHistoryWriterImpl::new(
@@ -130,6 +135,7 @@ impl ExecutionServices {
             config.clone(),
             Arc::clone(&cycles_account_manager),
         ));
+        let query_stats_collector = Arc::new(QueryStatsCollector::new(Arc::clone(&state_reader)));
         let sync_query_handler = Arc::new(InternalHttpQueryHandler::new(
             logger.clone(),
             hypervisor,
@@ -138,6 +144,7 @@ impl ExecutionServices {
             metrics_registry,
             scheduler_config.max_instructions_per_message,
             Arc::clone(&cycles_account_manager),
+            Arc::clone(&query_stats_collector),
         ));
         let threadpool = threadpool::Builder::new()
             .num_threads(config.query_execution_threads)
@@ -195,6 +202,7 @@ impl ExecutionServices {
             sync_query_handler,
             async_query_handler,
             anonymous_query_handler,
+            query_stats_collector,
             scheduler,
         }
     }
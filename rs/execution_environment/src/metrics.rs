@@ -209,6 +209,7 @@ fn instructions_buckets() -> Vec<f64> {
         buckets.push(config.max_instructions_per_message);
         buckets.push(config.max_instructions_per_round);
         buckets.push(config.max_instructions_per_install_code);
+        buckets.push(config.max_instructions_per_install_code_with_dts);
     }
     let mut buckets: Vec<NumInstructions> = decimal_buckets_with_zero(4, 10)
         .into_iter()
@@ -540,6 +541,7 @@ mod tests {
                     config.max_instructions_per_message.get(),
                     config.max_instructions_per_round.get(),
                     config.max_instructions_per_install_code.get(),
+                    config.max_instructions_per_install_code_with_dts.get(),
                 ]
             })
             .collect();
@@ -2,6 +2,7 @@
 //! query methods via query calls.
 
 mod query_allocations;
+mod query_cache;
 mod query_context;
 #[cfg(test)]
 mod tests;
@@ -10,26 +11,29 @@ use crate::execution_environment::subnet_memory_capacity;
 use crate::{
     hypervisor::Hypervisor,
     metrics::{MeasurementScope, QueryHandlerMetrics},
+    query_stats::QueryStatsCollector,
 };
-use ic_config::execution_environment::Config;
+use ic_config::{execution_environment::Config, flag_status::FlagStatus};
 use ic_crypto_tree_hash::{flatmap, Label, LabeledTree, LabeledTree::SubTree};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_interfaces::execution_environment::{QueryExecutionService, QueryHandler};
-use ic_interfaces_state_manager::StateReader;
+use ic_interfaces_state_manager::{StateManagerError, StateReader};
 use ic_logger::ReplicaLogger;
 use ic_metrics::MetricsRegistry;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::ReplicatedState;
 use ic_types::{
+    batch::QueryStats,
     ingress::WasmResult,
     messages::{
         Blob, Certificate, CertificateDelegation, HttpQueryResponse, HttpQueryResponseReply,
         UserQuery,
     },
-    CanisterId, NumInstructions,
+    CanisterId, Height, NumInstructions,
 };
 use query_allocations::QueryAllocationsUsed;
+use query_cache::QueryCache;
 use serde::Serialize;
 use std::{
     convert::Infallible,
@@ -49,11 +53,14 @@ fn into_cbor<R: Serialize>(r: &R) -> Vec<u8> {
     ser.into_inner()
 }
 
-fn get_latest_certified_state_and_data_certificate(
+/// Returns the certified state at `height`, or the latest certified state if
+/// `height` is `None`, together with the data certificate of `canister_id`.
+fn get_certified_state_and_data_certificate(
     state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
     certificate_delegation: Option<CertificateDelegation>,
     canister_id: CanisterId,
-) -> Option<(Arc<ReplicatedState>, Vec<u8>)> {
+    height: Option<Height>,
+) -> Result<(Arc<ReplicatedState>, Vec<u8>), UserError> {
     // The path to fetch the data certificate for the canister.
     let path = SubTree(flatmap! {
         label("canister") => SubTree(
@@ -68,8 +75,29 @@ fn get_latest_certified_state_and_data_certificate(
         label("time") => LabeledTree::Leaf(())
     });
 
-    state_reader
-        .read_certified_state(&path)
+    let certified_state = match height {
+        None => state_reader.read_certified_state(&path),
+        Some(height) => state_reader
+            .read_certified_state_at(height, &path)
+            .map_err(|err| match err {
+                StateManagerError::StateRemoved(_) => UserError::new(
+                    ErrorCode::CertifiedStateRemoved,
+                    format!(
+                        "Certified state at height {} is no longer available.",
+                        height
+                    ),
+                ),
+                StateManagerError::StateNotCommittedYet(_) => UserError::new(
+                    ErrorCode::CertifiedStateUnavailable,
+                    format!(
+                        "State at height {} is not committed yet. Please try again...",
+                        height
+                    ),
+                ),
+            })?,
+    };
+
+    certified_state
         .map(|(state, tree, cert)| {
             (
                 state,
@@ -80,6 +108,12 @@ fn get_latest_certified_state_and_data_certificate(
                 }),
             )
         })
+        .ok_or_else(|| {
+            UserError::new(
+                ErrorCode::CertifiedStateUnavailable,
+                "Certified state is not available yet. Please try again...",
+            )
+        })
 }
 
 fn label<T: Into<Label>>(t: T) -> Label {
@@ -95,6 +129,8 @@ pub struct InternalHttpQueryHandler {
     metrics: QueryHandlerMetrics,
     max_instructions_per_message: NumInstructions,
     cycles_account_manager: Arc<CyclesAccountManager>,
+    query_cache: Option<QueryCache>,
+    query_stats: Arc<QueryStatsCollector>,
 }
 
 #[derive(Clone)]
@@ -106,6 +142,7 @@ pub(crate) struct HttpQueryHandler {
 }
 
 impl InternalHttpQueryHandler {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         log: ReplicaLogger,
         hypervisor: Arc<Hypervisor>,
@@ -114,7 +151,15 @@ impl InternalHttpQueryHandler {
         metrics_registry: &MetricsRegistry,
         max_instructions_per_message: NumInstructions,
         cycles_account_manager: Arc<CyclesAccountManager>,
+        query_stats: Arc<QueryStatsCollector>,
     ) -> Self {
+        let query_cache = match config.query_caching {
+            FlagStatus::Enabled => Some(QueryCache::new(
+                metrics_registry,
+                config.query_cache_capacity,
+            )),
+            FlagStatus::Disabled => None,
+        };
         Self {
             log,
             hypervisor,
@@ -124,7 +169,42 @@ impl InternalHttpQueryHandler {
             metrics: QueryHandlerMetrics::new(metrics_registry),
             max_instructions_per_message,
             cycles_account_manager,
+            query_cache,
+            query_stats,
+        }
+    }
+
+    /// Records the statistics of a query call to `receiver` with an argument
+    /// of `ingress_payload_size` bytes that executed `num_instructions` and
+    /// produced `result`.
+    ///
+    /// Calls to canisters that don't exist are not recorded, so that they
+    /// can't be used to grow the collected statistics without bound.
+    fn record_query_stats(
+        &self,
+        state: &ReplicatedState,
+        receiver: CanisterId,
+        ingress_payload_size: usize,
+        num_instructions: NumInstructions,
+        result: &Result<WasmResult, UserError>,
+    ) {
+        if state.canister_state(&receiver).is_none() {
+            return;
         }
+        let egress_payload_size = match result {
+            Ok(WasmResult::Reply(reply)) => reply.len(),
+            Ok(WasmResult::Reject(message)) => message.len(),
+            Err(_) => 0,
+        };
+        self.query_stats.record(
+            receiver,
+            &QueryStats {
+                num_calls: 1,
+                num_instructions: num_instructions.get(),
+                ingress_payload_size: ingress_payload_size as u64,
+                egress_payload_size: egress_payload_size as u64,
+            },
+        );
     }
 }
 
@@ -137,12 +217,32 @@ impl QueryHandler for InternalHttpQueryHandler {
         state: Arc<ReplicatedState>,
         data_certificate: Vec<u8>,
     ) -> Result<WasmResult, UserError> {
+        let receiver = query.receiver;
+        let ingress_payload_size = query.method_payload.len();
+        let query_cache_key = match self.query_cache.as_ref() {
+            Some(query_cache) => {
+                let key = query_cache::EntryKey::from(&query);
+                if let Some(result) = query_cache.get_valid_result(&key, &state) {
+                    let result = Ok(result);
+                    self.record_query_stats(
+                        &state,
+                        receiver,
+                        ingress_payload_size,
+                        NumInstructions::from(0),
+                        &result,
+                    );
+                    return result;
+                }
+                Some(key)
+            }
+            None => None,
+        };
+        let cached_state = Arc::clone(&state);
+
         let measurement_scope = MeasurementScope::root(&self.metrics.query);
-        // Note that This assumes that the QueryHandler is always called with the
-        // "latest" state.  If and when we start supporting queries against older
-        // versions of the state, we will need the caller of the QueryHandler to
-        // still supply its view of the "latest" state to allow looking up the
-        // "current" time.
+        // Queries against older states (see `UserQuery::height`) supply an older
+        // batch time, which only shifts when the allocations used are purged
+        // next.
         self.query_allocations_used
             .write()
             .unwrap()
@@ -164,12 +264,32 @@ impl QueryHandler for InternalHttpQueryHandler {
             max_canister_memory_size,
             self.max_instructions_per_message,
         );
-        context.run(
+        let result = context.run(
             query,
             &self.metrics,
             Arc::clone(&self.cycles_account_manager),
             &measurement_scope,
-        )
+        );
+        self.record_query_stats(
+            &cached_state,
+            receiver,
+            ingress_payload_size,
+            context.total_instructions_executed(),
+            &result,
+        );
+
+        if let (Some(query_cache), Some(key), Ok(wasm_result)) =
+            (self.query_cache.as_ref(), query_cache_key, result.as_ref())
+        {
+            query_cache.push(
+                key,
+                &cached_state,
+                wasm_result,
+                context.system_api_call_counters(),
+                context.has_called_other_canisters(),
+            );
+        }
+        result
     }
 }
 
@@ -227,17 +347,13 @@ impl Service<(UserQuery, Option<CertificateDelegation>)> for HttpQueryHandler {
                 // We managed to upgrade the weak pointer, so the query was not cancelled.
                 // Canceling the query after this point will have to effect: the query will
                 // be executed anyway. That is fine because the execution will take O(ms).
-                let result = match get_latest_certified_state_and_data_certificate(
+                let result = get_certified_state_and_data_certificate(
                     state_reader,
                     certificate_delegation,
                     query.receiver,
-                ) {
-                    Some((state, cert)) => internal.query(query, state, cert),
-                    None => Err(UserError::new(
-                        ErrorCode::CertifiedStateUnavailable,
-                        "Certified state is not available yet. Please try again...",
-                    )),
-                };
+                    query.height,
+                )
+                .and_then(|(state, cert)| internal.query(query, state, cert));
 
                 let http_query_response = match result {
                     Ok(res) => match res {
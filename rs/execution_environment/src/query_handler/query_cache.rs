@@ -0,0 +1,267 @@
+use ic_ic00_types::CanisterStatusType;
+use ic_interfaces::execution_environment::SystemApiCallCounters;
+use ic_metrics::MetricsRegistry;
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    ingress::WasmResult, messages::UserQuery, CanisterId, Cycles, Height, NumBytes, Time, UserId,
+};
+use lru::LruCache;
+use prometheus::{IntCounter, IntGauge};
+use std::{mem::size_of, sync::Mutex};
+
+pub(crate) struct QueryCacheMetrics {
+    pub hits: IntCounter,
+    pub misses: IntCounter,
+    pub invalidated_entries: IntCounter,
+    pub evicted_entries: IntCounter,
+    pub entries: IntGauge,
+    pub size_bytes: IntGauge,
+}
+
+impl QueryCacheMetrics {
+    fn new(metrics_registry: &MetricsRegistry) -> Self {
+        Self {
+            hits: metrics_registry.int_counter(
+                "execution_query_cache_hits_total",
+                "The number of queries answered from the query cache",
+            ),
+            misses: metrics_registry.int_counter(
+                "execution_query_cache_misses_total",
+                "The number of queries not found in the query cache",
+            ),
+            invalidated_entries: metrics_registry.int_counter(
+                "execution_query_cache_invalidated_entries_total",
+                "The number of query cache entries dropped because the state \
+                they were computed on is no longer current",
+            ),
+            evicted_entries: metrics_registry.int_counter(
+                "execution_query_cache_evicted_entries_total",
+                "The number of query cache entries evicted to stay within \
+                the cache capacity",
+            ),
+            entries: metrics_registry.int_gauge(
+                "execution_query_cache_entries",
+                "The number of entries in the query cache",
+            ),
+            size_bytes: metrics_registry.int_gauge(
+                "execution_query_cache_size_bytes",
+                "The estimated size of the query cache in bytes",
+            ),
+        }
+    }
+}
+
+/// Identifies a query for caching purposes: everything in a `UserQuery`
+/// except for the fields that don't affect its result.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct EntryKey {
+    receiver: CanisterId,
+    source: UserId,
+    method_name: String,
+    method_payload: Vec<u8>,
+    height: Option<Height>,
+}
+
+impl From<&UserQuery> for EntryKey {
+    fn from(query: &UserQuery) -> Self {
+        Self {
+            receiver: query.receiver,
+            source: query.source,
+            method_name: query.method_name.clone(),
+            method_payload: query.method_payload.clone(),
+            height: query.height,
+        }
+    }
+}
+
+impl EntryKey {
+    fn size_bytes(&self) -> usize {
+        self.method_name.len() + self.method_payload.len()
+    }
+}
+
+/// The parts of the state a cached result depends on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct EntryEnv {
+    /// The batch time of the state the result was computed on.
+    batch_time: Time,
+    /// The version of the receiver the result was computed on.
+    canister_version: u64,
+    /// The cycles balance of the receiver, which is not covered by the
+    /// canister version.
+    cycles_balance: Cycles,
+}
+
+impl EntryEnv {
+    /// Returns the environment of a query to `receiver` executed against
+    /// `state`, or `None` if the receiver is not running.
+    fn new(receiver: &CanisterId, state: &ReplicatedState) -> Option<Self> {
+        let canister = state.canister_state(receiver)?;
+        if canister.status() != CanisterStatusType::Running {
+            return None;
+        }
+        Some(Self {
+            batch_time: state.metadata.batch_time,
+            canister_version: canister.system_state.canister_version,
+            cycles_balance: canister.system_state.balance(),
+        })
+    }
+}
+
+struct EntryValue {
+    env: EntryEnv,
+    /// Whether the result may change with the batch time even if the
+    /// receiver doesn't change: the query read `ic0.time` or the data
+    /// certificate, or called other canisters whose versions are not tracked.
+    depends_on_batch_time: bool,
+    result: WasmResult,
+}
+
+impl EntryValue {
+    fn is_valid(&self, env: &EntryEnv) -> bool {
+        self.env.canister_version == env.canister_version
+            && self.env.cycles_balance == env.cycles_balance
+            && (!self.depends_on_batch_time || self.env.batch_time == env.batch_time)
+    }
+
+    fn size_bytes(&self) -> usize {
+        match &self.result {
+            WasmResult::Reply(reply) => reply.len(),
+            WasmResult::Reject(message) => message.len(),
+        }
+    }
+}
+
+/// The cached entries and their estimated total size.
+struct Entries {
+    lru: LruCache<EntryKey, EntryValue>,
+    size_bytes: usize,
+}
+
+impl Entries {
+    fn pop(&mut self, key: &EntryKey) {
+        if let Some(value) = self.lru.pop(key) {
+            self.size_bytes -= entry_size_bytes(key, &value);
+        }
+    }
+}
+
+/// A bounded LRU cache of successful query results.
+///
+/// Entries are returned for as long as the receiver keeps the canister
+/// version and cycles balance they were computed on.  Entries of queries that
+/// read the time or the data certificate, or that called other canisters, are
+/// in addition only returned for states with the same batch time.  Stale
+/// entries are dropped on lookup or eventually evicted.
+pub(crate) struct QueryCache {
+    entries: Mutex<Entries>,
+    capacity: NumBytes,
+    pub(crate) metrics: QueryCacheMetrics,
+}
+
+impl QueryCache {
+    pub(crate) fn new(metrics_registry: &MetricsRegistry, capacity: NumBytes) -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                lru: LruCache::unbounded(),
+                size_bytes: 0,
+            }),
+            capacity,
+            metrics: QueryCacheMetrics::new(metrics_registry),
+        }
+    }
+
+    /// Returns the cached result of the query `key` if it is still valid for
+    /// `state`.
+    pub(crate) fn get_valid_result(
+        &self,
+        key: &EntryKey,
+        state: &ReplicatedState,
+    ) -> Option<WasmResult> {
+        let env = EntryEnv::new(&key.receiver, state);
+        let mut entries = self.entries.lock().unwrap();
+        // Whether the cached entry is valid and whether it was computed on an
+        // older state than `state`.
+        let cached = entries.lru.peek(key).map(|value| match &env {
+            Some(env) => (value.is_valid(env), value.env.batch_time < env.batch_time),
+            None => (false, true),
+        });
+        let result = match cached {
+            Some((true, _)) => entries.lru.get(key).map(|value| value.result.clone()),
+            // Only invalid entries computed on older states are dropped, so
+            // that queries against older states don't evict newer entries.
+            Some((false, true)) => {
+                entries.pop(key);
+                self.metrics.invalidated_entries.inc();
+                None
+            }
+            _ => None,
+        };
+
+        match result {
+            Some(_) => self.metrics.hits.inc(),
+            None => self.metrics.misses.inc(),
+        }
+        self.observe_size(&entries);
+        result
+    }
+
+    /// Caches the `result` of the query `key` executed against `state`,
+    /// evicting the least recently used entries if the cache grows beyond its
+    /// capacity.
+    ///
+    /// `system_api_call_counters` and `has_called_other_canisters` describe
+    /// the execution and determine whether the result depends on the batch
+    /// time.
+    pub(crate) fn push(
+        &self,
+        key: EntryKey,
+        state: &ReplicatedState,
+        result: &WasmResult,
+        system_api_call_counters: &SystemApiCallCounters,
+        has_called_other_canisters: bool,
+    ) {
+        let env = match EntryEnv::new(&key.receiver, state) {
+            Some(env) => env,
+            None => return,
+        };
+        let value = EntryValue {
+            env,
+            depends_on_batch_time: system_api_call_counters.time > 0
+                || system_api_call_counters.data_certificate_copy > 0
+                || has_called_other_canisters,
+            result: result.clone(),
+        };
+        let entry_size = entry_size_bytes(&key, &value);
+        if entry_size as u64 > self.capacity.get() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.pop(&key);
+        entries.lru.put(key, value);
+        entries.size_bytes += entry_size;
+
+        while entries.size_bytes as u64 > self.capacity.get() {
+            match entries.lru.pop_lru() {
+                Some((evicted_key, evicted_value)) => {
+                    entries.size_bytes -= entry_size_bytes(&evicted_key, &evicted_value);
+                    self.metrics.evicted_entries.inc();
+                }
+                None => break,
+            }
+        }
+        self.observe_size(&entries);
+    }
+
+    fn observe_size(&self, entries: &Entries) {
+        self.metrics.entries.set(entries.lru.len() as i64);
+        self.metrics.size_bytes.set(entries.size_bytes as i64);
+    }
+}
+
+/// Returns the estimated memory used by a cache entry, including the fixed
+/// overhead of the key and the value.
+fn entry_size_bytes(key: &EntryKey, value: &EntryValue) -> usize {
+    size_of::<(EntryKey, EntryValue)>() + key.size_bytes() + value.size_bytes()
+}
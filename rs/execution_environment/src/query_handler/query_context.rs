@@ -49,7 +49,9 @@ use ic_config::flag_status::FlagStatus;
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
-use ic_interfaces::execution_environment::{ExecutionMode, HypervisorError, SubnetAvailableMemory};
+use ic_interfaces::execution_environment::{
+    ExecutionMode, HypervisorError, SubnetAvailableMemory, SystemApiCallCounters,
+};
 use ic_logger::{debug, error, fatal, warn, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
@@ -117,6 +119,13 @@ pub(super) struct QueryContext<'a> {
     max_canister_memory_size: NumBytes,
     max_instructions_per_message: NumInstructions,
     round_limits: RoundLimits,
+    // The number of instructions executed by all the calls in the context so
+    // far.
+    total_instructions_executed: NumInstructions,
+    // The system API calls made by the queries executed in the context so far.
+    system_api_call_counters: SystemApiCallCounters,
+    // Whether the query sent requests to other canisters.
+    has_called_other_canisters: bool,
 }
 
 impl<'a> QueryContext<'a> {
@@ -151,9 +160,29 @@ impl<'a> QueryContext<'a> {
             max_canister_memory_size,
             max_instructions_per_message,
             round_limits,
+            total_instructions_executed: NumInstructions::from(0),
+            system_api_call_counters: SystemApiCallCounters::default(),
+            has_called_other_canisters: false,
         }
     }
 
+    /// Returns the number of instructions executed by all the calls in the
+    /// context so far.
+    pub(super) fn total_instructions_executed(&self) -> NumInstructions {
+        self.total_instructions_executed
+    }
+
+    /// Returns the system API calls made by the queries executed in the
+    /// context so far.
+    pub(super) fn system_api_call_counters(&self) -> &SystemApiCallCounters {
+        &self.system_api_call_counters
+    }
+
+    /// Returns whether the query sent requests to other canisters.
+    pub(super) fn has_called_other_canisters(&self) -> bool {
+        self.has_called_other_canisters
+    }
+
     /// Executes the given Query sent by an end user.
     ///
     /// - If it produces a response return the response.
@@ -271,6 +300,7 @@ impl<'a> QueryContext<'a> {
                 )),
 
                 EnqueueRequestsResult::MessagesEnqueued => {
+                    self.has_called_other_canisters = true;
                     self.canisters.insert(canister.canister_id(), canister);
                     self.run_loop(canister_id, metrics, measurement_scope)
                 }
@@ -418,19 +448,23 @@ impl<'a> QueryContext<'a> {
             InstructionLimits::new(FlagStatus::Disabled, instruction_limit, instruction_limit);
         let execution_parameters = self.execution_parameters(&canister, instruction_limits);
 
-        let (canister, instructions_left, result) = execute_non_replicated_query(
-            query_kind,
-            method_name,
-            method_payload,
-            canister,
-            Some(self.data_certificate.clone()),
-            self.state.time(),
-            execution_parameters,
-            &self.network_topology,
-            self.hypervisor,
-            &mut self.round_limits,
-        );
+        let (canister, instructions_left, result, system_api_call_counters) =
+            execute_non_replicated_query(
+                query_kind,
+                method_name,
+                method_payload,
+                canister,
+                Some(self.data_certificate.clone()),
+                self.state.time(),
+                execution_parameters,
+                &self.network_topology,
+                self.hypervisor,
+                &mut self.round_limits,
+            );
         let instructions_executed = instruction_limit - instructions_left;
+        self.total_instructions_executed += instructions_executed;
+        self.system_api_call_counters
+            .saturating_accumulate(&system_api_call_counters);
         measurement_scope.add(instructions_executed, NumMessages::from(1));
         self.query_allocations_used
             .write()
@@ -571,6 +605,7 @@ impl<'a> QueryContext<'a> {
             .on_canister_result(call_context_id, result);
 
         let instructions_executed = instruction_limit - instructions_left;
+        self.total_instructions_executed += instructions_executed;
         measurement_scope.add(instructions_executed, NumMessages::from(1));
         self.query_allocations_used
             .write()
@@ -994,6 +1029,9 @@ impl<'a> QueryContext<'a> {
             compute_allocation: canister.scheduler_state.compute_allocation,
             subnet_type: self.own_subnet_type,
             execution_mode: ExecutionMode::NonReplicated,
+            wasm64_memory_limit: self
+                .network_topology
+                .get_max_wasm64_memory_size(&self.state.metadata.own_subnet_id),
         }
     }
 }
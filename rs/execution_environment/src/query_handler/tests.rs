@@ -1,13 +1,16 @@
+use super::get_certified_state_and_data_certificate;
 use crate::InternalHttpQueryHandler;
 use ic_base_types::NumSeconds;
 use ic_error_types::{ErrorCode, UserError};
+use ic_interfaces_state_manager::StateManagerError;
 use ic_registry_subnet_type::SubnetType;
 use ic_test_utilities::{
     execution_environment::ExecutionTestBuilder,
-    types::ids::user_test_id,
+    state_manager::MockStateManager,
+    types::ids::{canister_test_id, user_test_id},
     universal_canister::{call_args, wasm},
 };
-use ic_types::{ingress::WasmResult, messages::UserQuery, Cycles};
+use ic_types::{batch::QueryStatsEpoch, ingress::WasmResult, messages::UserQuery, Cycles, Height};
 use std::sync::Arc;
 
 const CYCLES_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
@@ -58,6 +61,7 @@ fn query_metrics_are_reported() {
                 .build(),
             ingress_expiry: 0,
             nonce: None,
+            height: None,
         },
         Arc::new(test.state().clone()),
         vec![],
@@ -217,6 +221,7 @@ fn query_call_with_side_effects() {
                 .build(),
             ingress_expiry: 0,
             nonce: None,
+            height: None,
         },
         Arc::new(test.state().clone()),
         vec![],
@@ -251,6 +256,7 @@ fn query_calls_disabled_for_application_subnet() {
                 .build(),
             ingress_expiry: 0,
             nonce: None,
+            height: None,
         },
         Arc::new(test.state().clone()),
         vec![],
@@ -297,6 +303,7 @@ fn query_compiled_once() {
             method_payload: wasm().reply().build(),
             ingress_expiry: 0,
             nonce: None,
+            height: None,
         },
         Arc::new(test.state().clone()),
         vec![],
@@ -317,6 +324,7 @@ fn query_compiled_once() {
             method_payload: wasm().reply().build(),
             ingress_expiry: 0,
             nonce: None,
+            height: None,
         },
         Arc::new(test.state().clone()),
         vec![],
@@ -359,6 +367,7 @@ fn queries_to_frozen_canisters_are_rejected() {
             method_payload: wasm().reply().build(),
             ingress_expiry: 0,
             nonce: None,
+            height: None,
         },
         Arc::new(test.state().clone()),
         vec![],
@@ -384,9 +393,172 @@ fn queries_to_frozen_canisters_are_rejected() {
             method_payload: wasm().reply().build(),
             ingress_expiry: 0,
             nonce: None,
+            height: None,
         },
         Arc::new(test.state().clone()),
         vec![],
     );
     assert!(result.is_ok());
 }
+
+#[test]
+fn query_cache_returns_results_until_receiver_changes() {
+    let mut test = ExecutionTestBuilder::new().with_query_caching().build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let query = UserQuery {
+        source: user_test_id(2),
+        receiver: canister_id,
+        method_name: "query".to_string(),
+        method_payload: wasm().reply_data(b"42").build(),
+        ingress_expiry: 0,
+        nonce: None,
+        height: None,
+    };
+
+    for _ in 0..2 {
+        let output = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+        assert_eq!(output, Ok(WasmResult::Reply(b"42".to_vec())));
+    }
+    {
+        let query_handler = downcast_query_handler(test.query_handler());
+        let metrics = &query_handler.query_cache.as_ref().unwrap().metrics;
+        assert_eq!(1, metrics.misses.get());
+        assert_eq!(1, metrics.hits.get());
+        assert_eq!(1, metrics.entries.get());
+        // Only the first query was executed.
+        assert_eq!(1, query_handler.metrics.query.duration.get_sample_count());
+    }
+
+    // A later batch time alone doesn't invalidate the entry.
+    test.state_mut().metadata.batch_time += std::time::Duration::from_secs(1);
+    let output = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+    assert_eq!(output, Ok(WasmResult::Reply(b"42".to_vec())));
+    {
+        let query_handler = downcast_query_handler(test.query_handler());
+        let metrics = &query_handler.query_cache.as_ref().unwrap().metrics;
+        assert_eq!(1, metrics.misses.get());
+        assert_eq!(2, metrics.hits.get());
+        assert_eq!(0, metrics.invalidated_entries.get());
+    }
+
+    // Executing an update on the receiver bumps its version, which
+    // invalidates the entry.
+    test.ingress(canister_id, "update", wasm().reply().build())
+        .unwrap();
+    test.state_mut().metadata.batch_time += std::time::Duration::from_secs(1);
+    let output = test.query(query, Arc::new(test.state().clone()), vec![]);
+    assert_eq!(output, Ok(WasmResult::Reply(b"42".to_vec())));
+
+    let query_handler = downcast_query_handler(test.query_handler());
+    let metrics = &query_handler.query_cache.as_ref().unwrap().metrics;
+    assert_eq!(2, metrics.misses.get());
+    assert_eq!(2, metrics.hits.get());
+    assert_eq!(1, metrics.invalidated_entries.get());
+    assert_eq!(1, metrics.entries.get());
+    assert_eq!(2, query_handler.metrics.query.duration.get_sample_count());
+}
+
+#[test]
+fn query_cache_entries_of_queries_reading_time_expire_with_batch_time() {
+    let mut test = ExecutionTestBuilder::new().with_query_caching().build();
+    let wat = r#"
+        (module
+            (import "ic0" "time" (func $time (result i64)))
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_query read_time")
+                (drop (call $time))
+                (call $msg_reply)
+            )
+            (memory 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let query = UserQuery {
+        source: user_test_id(2),
+        receiver: canister_id,
+        method_name: "read_time".to_string(),
+        method_payload: vec![],
+        ingress_expiry: 0,
+        nonce: None,
+        height: None,
+    };
+
+    for _ in 0..2 {
+        let output = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+        assert_eq!(output, Ok(WasmResult::Reply(vec![])));
+    }
+    test.state_mut().metadata.batch_time += std::time::Duration::from_secs(1);
+    let output = test.query(query, Arc::new(test.state().clone()), vec![]);
+    assert_eq!(output, Ok(WasmResult::Reply(vec![])));
+
+    let query_handler = downcast_query_handler(test.query_handler());
+    let metrics = &query_handler.query_cache.as_ref().unwrap().metrics;
+    assert_eq!(2, metrics.misses.get());
+    assert_eq!(1, metrics.hits.get());
+    assert_eq!(1, metrics.invalidated_entries.get());
+    assert_eq!(2, query_handler.metrics.query.duration.get_sample_count());
+}
+
+#[test]
+fn query_stats_are_recorded_for_receiver() {
+    let mut test = ExecutionTestBuilder::new().with_query_caching().build();
+    let canister_id = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let method_payload = wasm().reply_data(b"42").build();
+    let query = UserQuery {
+        source: user_test_id(2),
+        receiver: canister_id,
+        method_name: "query".to_string(),
+        method_payload: method_payload.clone(),
+        ingress_expiry: 0,
+        nonce: None,
+        height: None,
+    };
+
+    // The second query is answered from the cache.
+    for _ in 0..2 {
+        let output = test.query(query.clone(), Arc::new(test.state().clone()), vec![]);
+        assert_eq!(output, Ok(WasmResult::Reply(b"42".to_vec())));
+    }
+    // Queries to canisters that don't exist are not recorded.
+    let output = test.query(
+        UserQuery {
+            receiver: canister_test_id(1_000),
+            ..query
+        },
+        Arc::new(test.state().clone()),
+        vec![],
+    );
+    assert!(output.is_err());
+
+    let canister_stats = test
+        .query_stats_collector()
+        .epoch_stats(QueryStatsEpoch::from(0));
+    assert_eq!(canister_stats.len(), 1);
+    assert_eq!(canister_stats[0].canister_id, canister_id);
+    let stats = &canister_stats[0].stats;
+    assert_eq!(stats.num_calls, 2);
+    assert!(stats.num_instructions > 0);
+    assert_eq!(stats.ingress_payload_size, 2 * method_payload.len() as u64);
+    assert_eq!(stats.egress_payload_size, 2 * 2);
+}
+
+#[test]
+fn queries_at_heights_without_a_retained_hash_tree_are_rejected() {
+    // The state manager refuses to read states whose hash tree is gone rather
+    // than rehashing them, and the query is rejected without falling back to
+    // another state.
+    let mut state_manager = MockStateManager::new();
+    state_manager
+        .expect_read_certified_state_at()
+        .times(1)
+        .returning(|height, _| Err(StateManagerError::StateRemoved(height)));
+    state_manager.expect_read_certified_state().never();
+
+    let err = get_certified_state_and_data_certificate(
+        Arc::new(state_manager),
+        None,
+        canister_test_id(0),
+        Some(Height::from(1)),
+    )
+    .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CertifiedStateRemoved);
+}
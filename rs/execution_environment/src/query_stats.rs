@@ -0,0 +1,499 @@
+//! Collection of per-canister query statistics and their aggregation through
+//! consensus.
+//!
+//! Every node records statistics about the queries it executes, grouped by
+//! the [`QueryStatsEpoch`] of its latest certified height. Once an epoch is
+//! over, the node reports its statistics for that epoch in the next block it
+//! makes. The reports are kept in the replicated state until every node has
+//! reported the epoch or the next epoch is reported, and are then aggregated
+//! into the per-canister totals (see `ReplicatedState::apply_query_stats`).
+
+use ic_base_types::NodeId;
+use ic_interfaces::{
+    query_stats::{
+        InvalidQueryStatsPayload, QueryStatsPayloadBuilder, QueryStatsPayloadValidationError,
+        QueryStatsTransientValidationError,
+    },
+    validation::ValidationError,
+};
+use ic_interfaces_state_manager::StateReader;
+use ic_logger::{warn, ReplicaLogger};
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    batch::{
+        epoch_from_height, CanisterQueryStats, QueryStats, QueryStatsEpoch, QueryStatsPayload,
+        ValidationContext,
+    },
+    CanisterId, CountBytes, NumBytes,
+};
+use std::{collections::BTreeMap, sync::Arc, sync::Mutex};
+
+/// Collects the statistics of the queries executed by this node.
+pub struct QueryStatsCollector {
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    epochs: Mutex<BTreeMap<QueryStatsEpoch, BTreeMap<CanisterId, QueryStats>>>,
+}
+
+impl QueryStatsCollector {
+    pub fn new(state_reader: Arc<dyn StateReader<State = ReplicatedState>>) -> Self {
+        Self {
+            state_reader,
+            epochs: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Adds `stats` to the statistics of `canister_id` in the current epoch.
+    pub fn record(&self, canister_id: CanisterId, stats: &QueryStats) {
+        let epoch = epoch_from_height(self.state_reader.latest_certified_height());
+        let mut epochs = self.epochs.lock().unwrap();
+        epochs
+            .entry(epoch)
+            .or_default()
+            .entry(canister_id)
+            .or_default()
+            .saturating_accumulate(stats);
+
+        // Only the statistics of the previous epoch can still be reported.
+        let oldest_reportable_epoch = QueryStatsEpoch::from(epoch.get().saturating_sub(1));
+        let reportable_epochs = epochs.split_off(&oldest_reportable_epoch);
+        *epochs = reportable_epochs;
+    }
+
+    /// Returns the statistics collected during `epoch`, sorted by canister id.
+    pub fn epoch_stats(&self, epoch: QueryStatsEpoch) -> Vec<CanisterQueryStats> {
+        self.epochs
+            .lock()
+            .unwrap()
+            .get(&epoch)
+            .map(|canister_stats| {
+                canister_stats
+                    .iter()
+                    .map(|(canister_id, stats)| CanisterQueryStats {
+                        canister_id: *canister_id,
+                        stats: stats.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Builds and validates the [`QueryStatsPayload`]s through which nodes report
+/// the statistics collected by their [`QueryStatsCollector`].
+pub struct QueryStatsPayloadBuilderImpl {
+    collector: Arc<QueryStatsCollector>,
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    node_id: NodeId,
+    log: ReplicaLogger,
+}
+
+impl QueryStatsPayloadBuilderImpl {
+    pub fn new(
+        collector: Arc<QueryStatsCollector>,
+        state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        node_id: NodeId,
+        log: ReplicaLogger,
+    ) -> Self {
+        Self {
+            collector,
+            state_reader,
+            node_id,
+            log,
+        }
+    }
+
+    /// Returns whether `proposer` has already reported `epoch` (or a later
+    /// one), either in the state at the certified height or in one of the
+    /// `past_payloads` above it.
+    fn already_reported(
+        &self,
+        proposer: NodeId,
+        epoch: QueryStatsEpoch,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+    ) -> Result<bool, QueryStatsTransientValidationError> {
+        if past_payloads
+            .iter()
+            .any(|payload| payload.proposer == proposer && payload.epoch >= epoch)
+        {
+            return Ok(true);
+        }
+
+        let state = self
+            .state_reader
+            .get_state_at(validation_context.certified_height)
+            .map_err(|err| {
+                QueryStatsTransientValidationError::GetStateFailed(
+                    validation_context.certified_height,
+                    err,
+                )
+            })?;
+        Ok(state
+            .get_ref()
+            .metadata
+            .last_reported_query_stats_epochs
+            .get(&proposer)
+            .map_or(false, |last_reported_epoch| *last_reported_epoch >= epoch))
+    }
+}
+
+impl QueryStatsPayloadBuilder for QueryStatsPayloadBuilderImpl {
+    /// Reports the statistics of the epoch preceding the one of the certified
+    /// height. Canisters that don't fit into `byte_limit` are left out of the
+    /// report, as each epoch is reported at most once.
+    fn get_query_stats_payload(
+        &self,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+        byte_limit: NumBytes,
+    ) -> Option<QueryStatsPayload> {
+        let current_epoch = epoch_from_height(validation_context.certified_height);
+        if current_epoch.get() == 0 {
+            return None;
+        }
+        let epoch = QueryStatsEpoch::from(current_epoch.get() - 1);
+
+        match self.already_reported(self.node_id, epoch, validation_context, past_payloads) {
+            Ok(false) => {}
+            Ok(true) => return None,
+            Err(err) => {
+                warn!(self.log, "Failed to build query stats payload: {:?}", err);
+                return None;
+            }
+        }
+
+        let mut payload = QueryStatsPayload {
+            epoch,
+            proposer: self.node_id,
+            canister_stats: vec![],
+        };
+        let mut size = payload.count_bytes();
+        for entry in self.collector.epoch_stats(epoch) {
+            size += entry.count_bytes();
+            if size as u64 > byte_limit.get() {
+                break;
+            }
+            payload.canister_stats.push(entry);
+        }
+
+        if payload.canister_stats.is_empty() {
+            None
+        } else {
+            Some(payload)
+        }
+    }
+
+    fn validate_query_stats_payload(
+        &self,
+        proposer: NodeId,
+        payload: &QueryStatsPayload,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+    ) -> Result<NumBytes, QueryStatsPayloadValidationError> {
+        if payload.proposer != proposer {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::ProposerMismatch {
+                    proposer: payload.proposer,
+                    block_maker: proposer,
+                },
+            ));
+        }
+
+        let current_epoch = epoch_from_height(validation_context.certified_height);
+        if payload.epoch >= current_epoch {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::EpochNotFinished {
+                    epoch: payload.epoch,
+                    certified_height: validation_context.certified_height,
+                },
+            ));
+        }
+        if payload.epoch.get() + 1 < current_epoch.get() {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::EpochTooOld {
+                    epoch: payload.epoch,
+                    certified_height: validation_context.certified_height,
+                },
+            ));
+        }
+
+        if !payload
+            .canister_stats
+            .windows(2)
+            .all(|pair| pair[0].canister_id < pair[1].canister_id)
+        {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::UnsortedCanisterStats,
+            ));
+        }
+
+        if self
+            .already_reported(proposer, payload.epoch, validation_context, past_payloads)
+            .map_err(ValidationError::Transient)?
+        {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::AlreadyReported {
+                    proposer,
+                    epoch: payload.epoch,
+                },
+            ));
+        }
+
+        Ok(NumBytes::new(payload.count_bytes() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use ic_interfaces_state_manager::Labeled;
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_test_utilities::{
+        mock_time,
+        state::ReplicatedStateBuilder,
+        state_manager::MockStateManager,
+        types::ids::{canister_test_id, node_test_id},
+    };
+    use ic_types::{batch::QUERY_STATS_EPOCH_LENGTH, Height, RegistryVersion};
+
+    const CERTIFIED_HEIGHT: Height = Height::new(QUERY_STATS_EPOCH_LENGTH + 1);
+
+    fn validation_context() -> ValidationContext {
+        ValidationContext {
+            registry_version: RegistryVersion::from(1),
+            certified_height: CERTIFIED_HEIGHT,
+            time: mock_time(),
+        }
+    }
+
+    fn stats(num_calls: u64) -> QueryStats {
+        QueryStats {
+            num_calls,
+            num_instructions: 100 * num_calls,
+            ingress_payload_size: 10 * num_calls,
+            egress_payload_size: 20 * num_calls,
+        }
+    }
+
+    /// Sets up a payload builder for node 1 with a state reader whose latest
+    /// certified height is `latest_certified_height` and whose state at
+    /// `CERTIFIED_HEIGHT` is `state`.
+    fn setup(
+        latest_certified_height: Height,
+        state: ReplicatedState,
+    ) -> (Arc<QueryStatsCollector>, QueryStatsPayloadBuilderImpl) {
+        let mut state_manager = MockStateManager::new();
+        state_manager
+            .expect_latest_certified_height()
+            .return_const(latest_certified_height);
+        state_manager
+            .expect_get_state_at()
+            .return_const(Ok(Labeled::new(CERTIFIED_HEIGHT, Arc::new(state))));
+        let state_reader: Arc<dyn StateReader<State = ReplicatedState>> = Arc::new(state_manager);
+        let collector = Arc::new(QueryStatsCollector::new(Arc::clone(&state_reader)));
+        let builder = QueryStatsPayloadBuilderImpl::new(
+            Arc::clone(&collector),
+            state_reader,
+            node_test_id(1),
+            no_op_logger(),
+        );
+        (collector, builder)
+    }
+
+    #[test]
+    fn collector_accumulates_stats_per_canister() {
+        let (collector, _) = setup(Height::new(0), ReplicatedStateBuilder::new().build());
+        collector.record(canister_test_id(2), &stats(1));
+        collector.record(canister_test_id(1), &stats(1));
+        collector.record(canister_test_id(2), &stats(2));
+
+        assert_eq!(
+            collector.epoch_stats(QueryStatsEpoch::from(0)),
+            vec![
+                CanisterQueryStats {
+                    canister_id: canister_test_id(1),
+                    stats: stats(1),
+                },
+                CanisterQueryStats {
+                    canister_id: canister_test_id(2),
+                    stats: stats(3),
+                },
+            ]
+        );
+        assert!(collector.epoch_stats(QueryStatsEpoch::from(1)).is_empty());
+    }
+
+    #[test]
+    fn built_payload_reports_previous_epoch_and_is_valid() {
+        let (collector, builder) = setup(Height::new(0), ReplicatedStateBuilder::new().build());
+        collector.record(canister_test_id(1), &stats(1));
+
+        let payload = builder
+            .get_query_stats_payload(&validation_context(), &[], NumBytes::new(1 << 20))
+            .unwrap();
+        assert_eq!(payload.epoch, QueryStatsEpoch::from(0));
+        assert_eq!(payload.proposer, node_test_id(1));
+        assert_eq!(payload.canister_stats.len(), 1);
+        assert_eq!(
+            builder
+                .validate_query_stats_payload(node_test_id(1), &payload, &validation_context(), &[])
+                .unwrap(),
+            NumBytes::new(payload.count_bytes() as u64)
+        );
+
+        // The epoch is not reported again once it is in a past payload.
+        assert_eq!(
+            builder.get_query_stats_payload(
+                &validation_context(),
+                &[&payload],
+                NumBytes::new(1 << 20)
+            ),
+            None
+        );
+        assert_matches!(
+            builder.validate_query_stats_payload(
+                node_test_id(1),
+                &payload,
+                &validation_context(),
+                &[&payload]
+            ),
+            Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::AlreadyReported { .. }
+            ))
+        );
+    }
+
+    #[test]
+    fn payload_reported_in_state_is_invalid() {
+        let mut state = ReplicatedStateBuilder::new().build();
+        state
+            .metadata
+            .last_reported_query_stats_epochs
+            .insert(node_test_id(1), QueryStatsEpoch::from(0));
+        let (collector, builder) = setup(Height::new(0), state);
+        collector.record(canister_test_id(1), &stats(1));
+
+        assert_eq!(
+            builder.get_query_stats_payload(&validation_context(), &[], NumBytes::new(1 << 20)),
+            None
+        );
+        let payload = QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(0),
+            proposer: node_test_id(1),
+            canister_stats: collector.epoch_stats(QueryStatsEpoch::from(0)),
+        };
+        assert_matches!(
+            builder.validate_query_stats_payload(
+                node_test_id(1),
+                &payload,
+                &validation_context(),
+                &[]
+            ),
+            Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::AlreadyReported { .. }
+            ))
+        );
+    }
+
+    #[test]
+    fn invalid_payloads_are_rejected() {
+        let (_, builder) = setup(Height::new(0), ReplicatedStateBuilder::new().build());
+        let entry = |canister_id| CanisterQueryStats {
+            canister_id: canister_test_id(canister_id),
+            stats: stats(1),
+        };
+        let payload = QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(0),
+            proposer: node_test_id(1),
+            canister_stats: vec![entry(1), entry(2)],
+        };
+
+        assert_matches!(
+            builder.validate_query_stats_payload(
+                node_test_id(2),
+                &payload,
+                &validation_context(),
+                &[]
+            ),
+            Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::ProposerMismatch { .. }
+            ))
+        );
+        assert_matches!(
+            builder.validate_query_stats_payload(
+                node_test_id(1),
+                &QueryStatsPayload {
+                    epoch: QueryStatsEpoch::from(1),
+                    ..payload.clone()
+                },
+                &validation_context(),
+                &[]
+            ),
+            Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::EpochNotFinished { .. }
+            ))
+        );
+        assert_matches!(
+            builder.validate_query_stats_payload(
+                node_test_id(1),
+                &payload,
+                &ValidationContext {
+                    certified_height: Height::new(2 * QUERY_STATS_EPOCH_LENGTH),
+                    ..validation_context()
+                },
+                &[]
+            ),
+            Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::EpochTooOld { .. }
+            ))
+        );
+        assert_matches!(
+            builder.validate_query_stats_payload(
+                node_test_id(1),
+                &QueryStatsPayload {
+                    canister_stats: vec![entry(2), entry(1)],
+                    ..payload
+                },
+                &validation_context(),
+                &[]
+            ),
+            Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::UnsortedCanisterStats
+            ))
+        );
+    }
+
+    #[test]
+    fn payload_is_truncated_to_byte_limit() {
+        let (collector, builder) = setup(Height::new(0), ReplicatedStateBuilder::new().build());
+        for i in 0..10 {
+            collector.record(canister_test_id(i), &stats(1));
+        }
+        let empty_payload_size = QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(0),
+            proposer: node_test_id(1),
+            canister_stats: vec![],
+        }
+        .count_bytes();
+        let entry_size = std::mem::size_of::<CanisterQueryStats>();
+
+        let payload = builder
+            .get_query_stats_payload(
+                &validation_context(),
+                &[],
+                NumBytes::new((empty_payload_size + 3 * entry_size) as u64),
+            )
+            .unwrap();
+        assert_eq!(payload.canister_stats.len(), 3);
+
+        assert_eq!(
+            builder.get_query_stats_payload(
+                &validation_context(),
+                &[],
+                NumBytes::new(empty_payload_size as u64)
+            ),
+            None
+        );
+    }
+}
@@ -316,7 +316,7 @@ impl SchedulerImpl {
             }
             let instruction_limits = InstructionLimits::new(
                 self.deterministic_time_slicing,
-                self.config.max_instructions_per_install_code,
+                install_code_instruction_limit(&self.config, self.deterministic_time_slicing),
                 self.config.max_instructions_per_slice,
             );
             let instructions_before = round_limits.instructions;
@@ -368,8 +368,11 @@ impl SchedulerImpl {
                 break;
             }
             if let Some(msg) = state.pop_subnet_input() {
-                let max_instructions_per_message =
-                    get_instructions_limit_for_subnet_message(&self.config, &msg);
+                let max_instructions_per_message = get_instructions_limit_for_subnet_message(
+                    &self.config,
+                    self.deterministic_time_slicing,
+                    &msg,
+                );
                 let instruction_limits = InstructionLimits::new(
                     self.deterministic_time_slicing,
                     max_instructions_per_message,
@@ -443,6 +446,13 @@ impl SchedulerImpl {
                 .round_inner_heartbeat_overhead_duration
                 .start_timer();
             for canister in state.canisters_iter_mut() {
+                match canister.next_execution() {
+                    NextExecution::None | NextExecution::StartNew => {}
+                    // A heartbeat must not run before a long execution
+                    // finishes. In case of `install_code` the canister is
+                    // locked until then.
+                    NextExecution::ContinueLong | NextExecution::ContinueInstallCode => continue,
+                }
                 if canister.exports_heartbeat_method() {
                     canister
                         .system_state
@@ -1695,6 +1705,19 @@ fn can_execute_msg(
         .unwrap_or(true)
 }
 
+/// Returns the instruction limit of an `install_code` message. With
+/// deterministic time slicing the execution is spread over many rounds, so
+/// the limit is much larger.
+fn install_code_instruction_limit(
+    config: &SchedulerConfig,
+    deterministic_time_slicing: FlagStatus,
+) -> NumInstructions {
+    match deterministic_time_slicing {
+        FlagStatus::Enabled => config.max_instructions_per_install_code_with_dts,
+        FlagStatus::Disabled => config.max_instructions_per_install_code,
+    }
+}
+
 /// Based on the type of the subnet message to execute, figure out its
 /// instruction limit.
 ///
@@ -1702,6 +1725,7 @@ fn can_execute_msg(
 /// (de)-serialize a large state and thus consume a lot of instructions.
 fn get_instructions_limit_for_subnet_message(
     config: &SchedulerConfig,
+    deterministic_time_slicing: FlagStatus,
     msg: &CanisterInputMessage,
 ) -> NumInstructions {
     let (method_name, payload, sender) = match &msg {
@@ -1746,7 +1770,7 @@ fn get_instructions_limit_for_subnet_message(
                 Err(_) => config.max_instructions_per_message,
                 Ok(args) => match InstallCodeContext::try_from((sender, args)) {
                     Err(_) => config.max_instructions_per_message,
-                    Ok(_) => config.max_instructions_per_install_code,
+                    Ok(_) => install_code_instruction_limit(config, deterministic_time_slicing),
                 },
             },
         },
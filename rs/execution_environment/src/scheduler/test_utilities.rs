@@ -24,7 +24,7 @@ use ic_error_types::UserError;
 use ic_ic00_types::{CanisterInstallMode, InstallCodeArgs, Method, Payload};
 use ic_interfaces::execution_environment::{
     ExecutionRoundType, HypervisorError, HypervisorResult, IngressHistoryWriter, InstanceStats,
-    Scheduler, WasmExecutionOutput,
+    Scheduler, SystemApiCallCounters, WasmExecutionOutput,
 };
 use ic_logger::{replica_logger::no_op_logger, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
@@ -892,6 +892,7 @@ impl TestWasmExecutorCore {
                     accessed_pages: 0,
                     dirty_pages: 0,
                 },
+                system_api_call_counters: SystemApiCallCounters::default(),
             };
             self.schedule
                 .push((thread_id, self.round, canister_id, instructions_to_execute));
@@ -930,6 +931,7 @@ impl TestWasmExecutorCore {
             allocated_message_bytes: NumBytes::from(0),
             num_instructions_left: instructions_left,
             instance_stats,
+            system_api_call_counters: SystemApiCallCounters::default(),
         };
         self.schedule
             .push((thread_id, self.round, canister_id, instructions_to_execute));
@@ -1,5 +1,6 @@
 use assert_matches::assert_matches;
 use candid::{Decode, Encode};
+use ic_base_types::NumSeconds;
 use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_execution_environment::CompilationCostHandling;
@@ -4222,6 +4223,180 @@ fn dts_abort_works_in_install_code() {
     assert_eq!(result, WasmResult::Reply(EmptyBlob::encode()));
 }
 
+#[test]
+fn dts_abort_of_install_code_refunds_execution_cycles() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_instruction_limit(1_000_000)
+        .with_slice_instruction_limit(1_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let mut features = wabt::Features::new();
+    features.enable_bulk_memory();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000_000));
+    let original_balance = test.canister_state(canister_id).system_state.balance();
+    let payload = InstallCodeArgs {
+        mode: CanisterInstallMode::Install,
+        canister_id: canister_id.get(),
+        wasm_module: wat2wasm_with_features(DTS_INSTALL_WAT, features).unwrap(),
+        arg: vec![],
+        compute_allocation: None,
+        memory_allocation: None,
+        query_allocation: None,
+    };
+    test.subnet_message_raw(Method::InstallCode, payload.encode());
+    test.execute_slice(canister_id);
+    assert_eq!(
+        test.canister_state(canister_id).next_execution(),
+        NextExecution::ContinueInstallCode
+    );
+    assert!(test.canister_state(canister_id).system_state.balance() < original_balance);
+
+    test.abort_paused_executions();
+
+    assert_eq!(
+        test.canister_state(canister_id).system_state.balance(),
+        original_balance
+    );
+    assert!(test.canister_state(canister_id).execution_state.is_none());
+}
+
+#[test]
+fn dts_upgrade_keeps_messages_inducted_while_paused() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_instruction_limit(1_000_000)
+        .with_slice_instruction_limit(1_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let mut features = wabt::Features::new();
+    features.enable_bulk_memory();
+    let wasm_module = wat2wasm_with_features(DTS_INSTALL_WAT, features).unwrap();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000_000));
+    let payload = InstallCodeArgs {
+        mode: CanisterInstallMode::Install,
+        canister_id: canister_id.get(),
+        wasm_module: wasm_module.clone(),
+        arg: vec![],
+        compute_allocation: None,
+        memory_allocation: None,
+        query_allocation: None,
+    };
+    test.subnet_message_raw(Method::InstallCode, payload.encode());
+    while test.canister_state(canister_id).next_execution() == NextExecution::ContinueInstallCode {
+        test.execute_slice(canister_id);
+    }
+
+    let payload = InstallCodeArgs {
+        mode: CanisterInstallMode::Upgrade,
+        canister_id: canister_id.get(),
+        wasm_module,
+        arg: vec![],
+        compute_allocation: None,
+        memory_allocation: None,
+        query_allocation: None,
+    };
+    let upgrade_id = test.subnet_message_raw(Method::InstallCode, payload.encode());
+    assert_eq!(
+        test.canister_state(canister_id).next_execution(),
+        NextExecution::ContinueInstallCode
+    );
+
+    // The canister is locked while the upgrade is in progress, so the
+    // message is only enqueued.
+    let (ingress_id, _) = test.ingress_raw(canister_id, "read", vec![]);
+    test.execute_slice(canister_id);
+    assert_eq!(
+        test.ingress_status(ingress_id.clone()),
+        IngressStatus::Unknown
+    );
+
+    while test.canister_state(canister_id).next_execution() == NextExecution::ContinueInstallCode {
+        test.execute_slice(canister_id);
+    }
+    let result = check_ingress_status(test.ingress_status(upgrade_id)).unwrap();
+    assert_eq!(result, WasmResult::Reply(EmptyBlob::encode()));
+
+    // The message inducted during the upgrade survives it.
+    assert_eq!(
+        test.canister_state(canister_id).next_execution(),
+        NextExecution::StartNew
+    );
+    test.execute_slice(canister_id);
+    let result = check_ingress_status(test.ingress_status(ingress_id)).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![34; 10]));
+}
+
+#[test]
+fn dts_upgrade_of_canister_with_low_balance_prepays_affordable_instructions() {
+    let install_code_instruction_limit = 1_000_000_000;
+    let mut test = ExecutionTestBuilder::new()
+        .with_install_code_instruction_limit(install_code_instruction_limit)
+        .with_slice_instruction_limit(1_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let mut features = wabt::Features::new();
+    features.enable_bulk_memory();
+    let wasm_module = wat2wasm_with_features(DTS_INSTALL_WAT, features).unwrap();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000_000));
+    let payload = InstallCodeArgs {
+        mode: CanisterInstallMode::Install,
+        canister_id: canister_id.get(),
+        wasm_module: wasm_module.clone(),
+        arg: vec![],
+        compute_allocation: None,
+        memory_allocation: None,
+        query_allocation: None,
+    };
+    test.subnet_message_raw(Method::InstallCode, payload.encode());
+    while test.canister_state(canister_id).next_execution() == NextExecution::ContinueInstallCode {
+        test.execute_slice(canister_id);
+    }
+
+    // The canister can pay for the upgrade itself, but not for the entire
+    // instruction limit of `install_code`.
+    let subnet_size = test.subnet_size();
+    let balance = test
+        .cycles_account_manager()
+        .execution_cost(NumInstructions::from(10_000_000), subnet_size);
+    assert!(
+        balance
+            < test.cycles_account_manager().execution_cost(
+                NumInstructions::from(install_code_instruction_limit),
+                subnet_size
+            )
+    );
+    let canister = test.canister_state_mut(canister_id);
+    canister.system_state.freeze_threshold = NumSeconds::from(0);
+    *canister.system_state.balance_mut() = balance;
+
+    let payload = InstallCodeArgs {
+        mode: CanisterInstallMode::Upgrade,
+        canister_id: canister_id.get(),
+        wasm_module,
+        arg: vec![],
+        compute_allocation: None,
+        memory_allocation: None,
+        query_allocation: None,
+    };
+    let upgrade_id = test.subnet_message_raw(Method::InstallCode, payload.encode());
+    assert_eq!(
+        test.canister_state(canister_id).next_execution(),
+        NextExecution::ContinueInstallCode
+    );
+    while test.canister_state(canister_id).next_execution() == NextExecution::ContinueInstallCode {
+        test.execute_slice(canister_id);
+    }
+    let result = check_ingress_status(test.ingress_status(upgrade_id)).unwrap();
+    assert_eq!(result, WasmResult::Reply(EmptyBlob::encode()));
+
+    // Only the executed instructions are paid for in the end.
+    let balance_after = test.canister_state(canister_id).system_state.balance();
+    assert!(balance_after < balance);
+    assert!(balance_after > Cycles::zero());
+}
+
 #[test]
 fn dts_concurrent_subnet_available_change() {
     let mut test = ExecutionTestBuilder::new()
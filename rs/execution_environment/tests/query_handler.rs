@@ -12,7 +12,7 @@ use ic_test_utilities::{
     types::ids::{subnet_test_id, user_test_id},
     with_test_replica_logger,
 };
-use ic_types::{messages::UserQuery, CanisterId, SubnetId};
+use ic_types::{messages::UserQuery, CanisterId, ReplicaVersion, SubnetId};
 use maplit::btreemap;
 use std::{convert::TryFrom, path::Path, sync::Arc};
 
@@ -50,6 +50,7 @@ async fn query_non_existent() {
             Config::default(),
             cycles_account_manager,
             state_manager,
+            &ReplicaVersion::default(),
         );
 
         let receiver = CanisterId::from(1234);
@@ -61,6 +62,7 @@ async fn query_non_existent() {
                 method_payload: b"Hello".to_vec(),
                 ingress_expiry: 0,
                 nonce: None,
+                height: None,
             },
             Arc::new(state),
             vec![],
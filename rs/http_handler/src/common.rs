@@ -89,6 +89,7 @@ pub(crate) fn make_response(user_error: UserError) -> Response<Body> {
         C::CanisterWasmEngineError => StatusCode::INTERNAL_SERVER_ERROR,
         C::CanisterInstructionLimitExceeded => StatusCode::INTERNAL_SERVER_ERROR,
         C::CanisterInstallCodeRateLimited => StatusCode::TOO_MANY_REQUESTS,
+        C::CertifiedStateRemoved => StatusCode::GONE,
     };
     make_plaintext_response(status, user_error.description().to_string())
 }
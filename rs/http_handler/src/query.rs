@@ -1,4 +1,8 @@
 //! Module that deals with requests to /api/v2/canister/.../query
+//!
+//! A query may specify the `height` of a certified state that is still
+//! retained by the state manager, in which case it's executed against that
+//! state instead of the latest certified one.
 
 use crate::{
     body::BodyReceiverLayer,
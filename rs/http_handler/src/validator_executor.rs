@@ -141,6 +141,7 @@ mod tests {
                 sender: Blob(vec![4]), // the anonymous user.
                 nonce: None,
                 ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                height: None,
             },
         };
         let request = HttpRequestEnvelope::<HttpQueryContent> {
@@ -38,6 +38,29 @@ pub struct InstanceStats {
     pub dirty_pages: usize,
 }
 
+/// The number of calls to the system API functions whose results depend on
+/// more than the state of the executing canister.
+///
+/// Used by the query cache to decide which results remain valid once the
+/// batch time of the state changes.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemApiCallCounters {
+    /// The number of calls to `ic0.time`.
+    pub time: usize,
+    /// The number of calls to `ic0.data_certificate_copy`.
+    pub data_certificate_copy: usize,
+}
+
+impl SystemApiCallCounters {
+    /// Adds the counters of another execution to these counters.
+    pub fn saturating_accumulate(&mut self, rhs: &Self) {
+        self.time = self.time.saturating_add(rhs.time);
+        self.data_certificate_copy = self
+            .data_certificate_copy
+            .saturating_add(rhs.data_certificate_copy);
+    }
+}
+
 /// Errors that can be returned when fetching the available memory on a subnet.
 #[derive(Debug)]
 pub enum SubnetAvailableMemoryError {
@@ -661,7 +684,7 @@ pub trait SystemApi {
         heap: &[u8],
     ) -> HypervisorResult<()>;
 
-    fn ic0_time(&self) -> HypervisorResult<Time>;
+    fn ic0_time(&mut self) -> HypervisorResult<Time>;
 
     /// The canister can query the "performance counter", which is
     /// a deterministic monotonically increasing integer approximating
@@ -813,7 +836,7 @@ pub trait SystemApi {
     /// (i.e. data_certificate_present returns 1).
     /// Traps if data_certificate_present returns 0.
     fn ic0_data_certificate_copy(
-        &self,
+        &mut self,
         dst: u32,
         offset: u32,
         size: u32,
@@ -916,6 +939,7 @@ pub struct WasmExecutionOutput {
     pub allocated_bytes: NumBytes,
     pub allocated_message_bytes: NumBytes,
     pub instance_stats: InstanceStats,
+    pub system_api_call_counters: SystemApiCallCounters,
 }
 
 impl fmt::Display for WasmExecutionOutput {
@@ -0,0 +1,94 @@
+//! The query stats public interface.
+use crate::validation::ValidationError;
+use ic_base_types::NodeId;
+use ic_interfaces_state_manager::StateManagerError;
+use ic_types::{
+    batch::{QueryStatsEpoch, QueryStatsPayload, ValidationContext},
+    consensus::Payload,
+    Height, NumBytes, Time,
+};
+
+/// A [`QueryStatsPayload`] error from which it is not possible to recover.
+#[derive(Debug)]
+pub enum InvalidQueryStatsPayload {
+    /// The payload reports the statistics of a node other than the block maker.
+    ProposerMismatch {
+        proposer: NodeId,
+        block_maker: NodeId,
+    },
+    /// The epoch has not finished yet at the certified height.
+    EpochNotFinished {
+        epoch: QueryStatsEpoch,
+        certified_height: Height,
+    },
+    /// The epoch is older than the one preceding the epoch of the certified
+    /// height, so its reports may have been aggregated already.
+    EpochTooOld {
+        epoch: QueryStatsEpoch,
+        certified_height: Height,
+    },
+    /// The proposer has already reported this epoch, or a later one.
+    AlreadyReported {
+        proposer: NodeId,
+        epoch: QueryStatsEpoch,
+    },
+    /// The canister statistics are not sorted by canister id or contain
+    /// duplicates.
+    UnsortedCanisterStats,
+}
+
+/// A [`QueryStatsPayload`] error from which it may be possible to recover.
+#[derive(Debug)]
+pub enum QueryStatsTransientValidationError {
+    GetStateFailed(Height, StateManagerError),
+}
+
+/// A [`QueryStatsPayload`] error that results from payload validation.
+pub type QueryStatsPayloadValidationError =
+    ValidationError<InvalidQueryStatsPayload, QueryStatsTransientValidationError>;
+
+pub trait QueryStatsPayloadBuilder: Send + Sync {
+    /// Produces a [`QueryStatsPayload`] of maximum byte size `byte_limit`
+    /// with the statistics this node collected during the latest epoch that
+    /// finished at the certified height of `validation_context`, or `None` if
+    /// there is nothing to report.
+    ///
+    /// `past_payloads` are the [`QueryStatsPayload`]s from all blocks above
+    /// the certified height, in descending block height order.
+    fn get_query_stats_payload(
+        &self,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+        byte_limit: NumBytes,
+    ) -> Option<QueryStatsPayload>;
+
+    /// Checks whether the [`QueryStatsPayload`] of a block made by `proposer`
+    /// is valid given `validation_context` and `past_payloads`.
+    ///
+    /// If valid, returns the payload's byte size; else returns a permanent or
+    /// transient [`ValidationError`].
+    fn validate_query_stats_payload(
+        &self,
+        proposer: NodeId,
+        payload: &QueryStatsPayload,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+    ) -> Result<NumBytes, QueryStatsPayloadValidationError>;
+
+    /// Extracts the sequence of past [`QueryStatsPayload`]s from `past_payloads`.
+    fn filter_past_payloads<'a>(
+        &self,
+        past_payloads: &'a [(Height, Time, Payload)],
+    ) -> Vec<&'a QueryStatsPayload> {
+        past_payloads
+            .iter()
+            .filter_map(|(_, _, payload)| {
+                if payload.is_summary() {
+                    None
+                } else {
+                    payload.as_ref().as_data().batch.query_stats.as_ref()
+                }
+            })
+            .collect()
+    }
+}
@@ -392,4 +392,25 @@ pub trait StateReader: Send + Sync {
         &self,
         paths: &LabeledTree<()>,
     ) -> Option<(Arc<Self::State>, MixedHashTree, Certification)>;
+
+    /// Same as `read_certified_state`, but reads the certified state at the
+    /// specified `height` instead of the latest one.  Only the most recent
+    /// states whose hash trees are still retained in memory can be read, as
+    /// rehashing an older state on every call would be too expensive.
+    ///
+    /// Returns `Ok(None)` if the state at `height` is not certified yet.
+    ///
+    /// # Errors
+    ///
+    /// * If the state at `height` or its hash tree was already removed from
+    ///   memory, the `StateRemoved` error is returned.
+    ///
+    /// * If the state at `height` is not committed yet, the
+    ///   `StateNotCommittedYet` error is returned.
+    #[allow(clippy::type_complexity)]
+    fn read_certified_state_at(
+        &self,
+        height: Height,
+        paths: &LabeledTree<()>,
+    ) -> StateManagerResult<Option<(Arc<Self::State>, MixedHashTree, Certification)>>;
 }
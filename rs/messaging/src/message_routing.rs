@@ -33,7 +33,7 @@ use ic_types::{
     batch::Batch,
     registry::RegistryClientError,
     xnet::{StreamHeader, StreamIndex},
-    Height, NodeId, NumBytes, RegistryVersion, SubnetId,
+    Height, NodeId, NumBytes, RegistryVersion, SubnetId, MAX_WASM64_MEMORY_IN_BYTES,
 };
 use ic_utils::thread::JoinOnDrop;
 #[cfg(test)]
@@ -492,6 +492,8 @@ impl BatchProcessorImpl {
             let subnet_type = self.get_subnet_type(*subnet_id, registry_version);
             let subnet_features = self.get_subnet_features(*subnet_id, registry_version);
             let ecdsa_keys_held = self.get_ecdsa_keys_held(*subnet_id, registry_version);
+            let max_wasm64_memory_size =
+                self.get_max_wasm64_memory_size(*subnet_id, registry_version);
             subnets.insert(
                 *subnet_id,
                 SubnetTopology {
@@ -500,6 +502,7 @@ impl BatchProcessorImpl {
                     subnet_type,
                     subnet_features,
                     ecdsa_keys_held,
+                    max_wasm64_memory_size,
                 },
             );
         }
@@ -612,6 +615,21 @@ impl BatchProcessorImpl {
         record.max_number_of_canisters
     }
 
+    fn get_max_wasm64_memory_size(
+        &self,
+        subnet_id: SubnetId,
+        registry_version: RegistryVersion,
+    ) -> NumBytes {
+        let record = self.get_subnet_record(subnet_id, registry_version);
+        // A value of 0 means that the subnet uses the default limit, which is
+        // also the upper bound of what a subnet may configure.
+        let max_wasm64_memory_size = match record.max_wasm64_memory_size {
+            0 => MAX_WASM64_MEMORY_IN_BYTES,
+            size => size.min(MAX_WASM64_MEMORY_IN_BYTES),
+        };
+        NumBytes::new(max_wasm64_memory_size)
+    }
+
     fn get_max_ecdsa_queue_size(
         &self,
         subnet_id: SubnetId,
@@ -41,9 +41,15 @@ impl<'a> DemuxImpl<'a> {
 }
 
 impl<'a> Demux for DemuxImpl<'a> {
-    fn process_payload(&self, state: ReplicatedState, payload: BatchPayload) -> ReplicatedState {
+    fn process_payload(
+        &self,
+        state: ReplicatedState,
+        mut payload: BatchPayload,
+    ) -> ReplicatedState {
         trace!(self.log, "Processing Payload");
 
+        let query_stats = payload.query_stats.take();
+
         let (signed_ingress_msgs, certified_stream_slices, bitcoin_adapter_responses) =
             payload.into_messages().unwrap_or_else(|err| {
                 unreachable!(
@@ -83,6 +89,10 @@ impl<'a> Demux for DemuxImpl<'a> {
             });
         }
 
+        if let Some(query_stats) = query_stats {
+            state.apply_query_stats(&query_stats);
+        }
+
         state
     }
 }
@@ -20,7 +20,7 @@ use ic_test_utilities::{
 };
 use ic_types::crypto::canister_threshold_sig::MasterEcdsaPublicKey;
 use ic_types::messages::SignedIngress;
-use ic_types::{Height, PrincipalId, SubnetId};
+use ic_types::{Height, NumBytes, PrincipalId, SubnetId};
 use mockall::{mock, predicate::*, Sequence};
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -109,6 +109,7 @@ fn test_fixture(provided_batch: &Batch) -> StateMachineTestFixture {
             subnet_type: SubnetType::Application,
             subnet_features: SubnetFeatures::default(),
             ecdsa_keys_held: BTreeSet::new(),
+            max_wasm64_memory_size: NumBytes::from(0),
         },
     );
 
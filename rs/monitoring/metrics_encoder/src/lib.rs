@@ -99,4 +99,40 @@ impl<W: io::Write> MetricsEncoder<W> {
     pub fn encode_gauge(&mut self, name: &str, value: f64, help: &str) -> io::Result<()> {
         self.encode_single_value("gauge", name, value, help)
     }
+
+    /// Encodes the metadata and the values of a gauge that is partitioned
+    /// by labels, e.g. `name{topic="Governance",status="Open"} 3`.
+    ///
+    /// SAMPLES is a list of (labels, value) pairs, where labels is a list of
+    /// (label name, label value) pairs. Label values are escaped as required
+    /// by the text format.
+    pub fn encode_labeled_gauge<'a>(
+        &mut self,
+        name: &str,
+        samples: impl Iterator<Item = (Vec<(&'a str, String)>, f64)>,
+        help: &str,
+    ) -> io::Result<()> {
+        self.encode_header(name, help, "gauge")?;
+        for (labels, value) in samples {
+            let labels = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape_label_value(value)))
+                .collect::<Vec<_>>()
+                .join(",");
+            writeln!(
+                self.writer,
+                "{}{{{}}} {} {}",
+                name, labels, value, self.now_millis
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Escapes backslashes, double quotes and line feeds in a label value.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
 }
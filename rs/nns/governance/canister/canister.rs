@@ -16,6 +16,7 @@ use ic_nns_governance::{
 use rand::rngs::StdRng;
 use rand_core::{RngCore, SeedableRng};
 use std::boxed::Box;
+use std::collections::BTreeMap;
 use std::time::SystemTime;
 
 use prost::Message;
@@ -24,9 +25,10 @@ use async_trait::async_trait;
 use candid::candid_method;
 use dfn_candid::{candid, candid_one};
 use dfn_core::{
-    api::{arg_data, call_with_callbacks, caller, now},
+    api::{arg_data, call_with_callbacks, caller, now, performance_counter, time_nanos},
     over, over_async, println,
 };
+use dfn_http_metrics::{HttpRequest, HttpResponse};
 use dfn_protobuf::protobuf;
 
 use ic_base_types::{CanisterId, PrincipalId};
@@ -37,9 +39,6 @@ use ic_nns_common::{
     types::{NeuronId, ProposalId},
 };
 
-use ic_nervous_system_common::stable_mem_utils::{
-    BufferedStableMemReader, BufferedStableMemWriter,
-};
 use ic_nns_constants::{CYCLES_MINTING_CANISTER_ID, LEDGER_CANISTER_ID};
 use ic_nns_governance::pb::v1::{
     ListNodeProvidersResponse, MostRecentMonthlyNodeProviderRewards, NodeProvider, RewardEvent,
@@ -54,26 +53,23 @@ use ic_nns_governance::{
             claim_or_refresh::{By, MemoAndController},
             ClaimOrRefresh, Command, RegisterVote,
         },
-        manage_neuron_response, ClaimOrRefreshNeuronFromAccount,
-        ClaimOrRefreshNeuronFromAccountResponse, ExecuteNnsFunction, Governance as GovernanceProto,
-        GovernanceError, ListKnownNeuronsResponse, ListNeurons, ListNeuronsResponse,
-        ListProposalInfo, ListProposalInfoResponse, ManageNeuron, ManageNeuronResponse, Neuron,
-        NeuronInfo, NnsFunction, Proposal, ProposalInfo, Vote,
+        manage_neuron_response,
+        proposal::Action,
+        ClaimOrRefreshNeuronFromAccount, ClaimOrRefreshNeuronFromAccountResponse,
+        ExecuteNnsFunction, Governance as GovernanceProto, GovernanceError,
+        ListKnownNeuronsResponse, ListNeurons, ListNeuronsResponse, ListProposalInfo,
+        ListProposalInfoResponse, ManageNeuron, ManageNeuronResponse, Neuron, NeuronInfo,
+        NnsFunction, Proposal, ProposalInfo, ProposalRewardStatus, ProposalStatus, Topic, Vote,
     },
+    storage,
 };
 
 use dfn_core::api::reject_message;
 use ic_nervous_system_common::ledger::LedgerCanister;
 use ic_nns_common::access_control::check_caller_is_gtc;
 use ic_nns_governance::governance::HeapGrowthPotential;
-
-/// Size of the buffer for stable memory reads and writes.
-///
-/// Smaller buffer size means more stable_write and stable_read calls. With
-/// 100MiB buffer size, when the heap is near full, we need ~40 system calls.
-/// Larger buffer size means we may not be able to serialize the heap fully in
-/// some cases.
-const STABLE_MEM_BUFFER_SIZE: u32 = 100 * 1024 * 1024; // 100MiB
+use ic_sns_swap::pb::v1::SettleCommunityFundParticipation;
+use serde_bytes::ByteBuf;
 
 pub(crate) const LOG_PREFIX: &str = "[Governance] ";
 
@@ -100,6 +96,35 @@ fn governance_mut() -> &'static mut Governance {
     unsafe { GOVERNANCE.as_mut().expect("Canister not initialized!") }
 }
 
+/// Statistics about the most recent heartbeats, exported as metrics.
+///
+/// These are not persisted across upgrades.
+struct HeartbeatMetrics {
+    /// When the most recent heartbeat started, in nanoseconds since the Unix
+    /// epoch.
+    latest_start_timestamp_nanos: u64,
+    /// The number of instructions executed by the most recent heartbeat up
+    /// to its first await point.
+    latest_instructions: u64,
+    /// How long the most recent completed run of the periodic tasks took,
+    /// including the time spent waiting for calls to other canisters.
+    latest_periodic_tasks_duration_nanos: u64,
+}
+
+static mut HEARTBEAT_METRICS: HeartbeatMetrics = HeartbeatMetrics {
+    latest_start_timestamp_nanos: 0,
+    latest_instructions: 0,
+    latest_periodic_tasks_duration_nanos: 0,
+};
+
+fn heartbeat_metrics() -> &'static HeartbeatMetrics {
+    unsafe { &HEARTBEAT_METRICS }
+}
+
+fn heartbeat_metrics_mut() -> &'static mut HeartbeatMetrics {
+    unsafe { &mut HEARTBEAT_METRICS }
+}
+
 struct CanisterEnv {
     rng: StdRng,
     time_warp: TimeWarp,
@@ -296,6 +321,10 @@ fn canister_init_(init_payload: GovernanceProto) {
         init_payload.neurons.len()
     );
 
+    init_payload
+        .validate_neurons()
+        .expect("Error initializing the governance canister.");
+
     unsafe {
         assert!(
             GOVERNANCE.is_none(),
@@ -318,14 +347,11 @@ fn canister_init_(init_payload: GovernanceProto) {
 fn canister_pre_upgrade() {
     println!("{}Executing pre upgrade", LOG_PREFIX);
 
-    let mut writer = BufferedStableMemWriter::new(STABLE_MEM_BUFFER_SIZE);
-
-    governance()
-        .proto
-        .encode(&mut writer)
-        .expect("Error. Couldn't serialize canister pre-upgrade.");
-
-    writer.flush(); // or `drop(writer)`
+    // The neurons that have not been moved to stable memory yet are saved
+    // with the proto, and moved by the next version.
+    let governance = governance_mut();
+    governance.proto.neurons = governance.neuron_store.take_pending_neurons();
+    storage::save_governance_proto(&storage::upgrades_memory(), &governance.proto);
 }
 
 #[export_name = "canister_post_upgrade"]
@@ -333,9 +359,11 @@ fn canister_post_upgrade() {
     dfn_core::printer::hook();
     println!("{}Executing post upgrade", LOG_PREFIX);
 
-    let reader = BufferedStableMemReader::new(STABLE_MEM_BUFFER_SIZE);
-
-    match GovernanceProto::decode(reader) {
+    // The state saved by versions that predate the memory manager must be
+    // read before the memory manager is initialized, which overwrites it.
+    let proto = storage::load_legacy_governance_proto(&storage::raw_memory())
+        .unwrap_or_else(|| storage::load_governance_proto(&storage::upgrades_memory()));
+    match proto {
         Err(err) => {
             println!(
                 "Error deserializing canister state post-upgrade. \
@@ -345,6 +373,13 @@ fn canister_post_upgrade() {
             Err(err)
         }
         Ok(proto) => {
+            if !proto.neurons.is_empty() {
+                println!(
+                    "{}Moving {} neurons from the upgrade state to stable memory",
+                    LOG_PREFIX,
+                    proto.neurons.len()
+                );
+            }
             canister_init_(proto);
             Ok(())
         }
@@ -522,6 +557,24 @@ async fn manage_neuron_(manage_neuron: ManageNeuron) -> ManageNeuronResponse {
         .await
 }
 
+/// Settles the community fund participation in an SNS token swap opened by
+/// an OpenSnsTokenSwap proposal. Only callable by the swap canister targeted
+/// by that proposal.
+#[export_name = "canister_update settle_community_fund_participation"]
+fn settle_community_fund_participation() {
+    println!("{}settle_community_fund_participation", LOG_PREFIX);
+    over_async(candid_one, settle_community_fund_participation_)
+}
+
+#[candid_method(update, rename = "settle_community_fund_participation")]
+async fn settle_community_fund_participation_(
+    request: SettleCommunityFundParticipation,
+) -> Result<(), GovernanceError> {
+    governance_mut()
+        .settle_community_fund_participation(caller(), &request)
+        .await
+}
+
 /// Returns the full neuron corresponding to the neuron id or subaccount.
 #[export_name = "canister_query get_full_neuron_by_id_or_subaccount"]
 fn get_full_neuron_by_id_or_subaccount() {
@@ -725,10 +778,19 @@ fn get_network_economics_parameters_() -> NetworkEconomics {
 
 #[export_name = "canister_heartbeat"]
 fn canister_heartbeat() {
-    let future = governance_mut().run_periodic_tasks();
+    let start_timestamp_nanos = time_nanos();
+    heartbeat_metrics_mut().latest_start_timestamp_nanos = start_timestamp_nanos;
+    let future = async move {
+        governance_mut().run_periodic_tasks().await;
+        heartbeat_metrics_mut().latest_periodic_tasks_duration_nanos =
+            time_nanos().saturating_sub(start_timestamp_nanos);
+    };
 
     // canister_heartbeat must be synchronous, so we cannot .await the future
     dfn_core::api::futures::spawn(future);
+
+    // Counter 0 is the number of instructions executed in the current message.
+    heartbeat_metrics_mut().latest_instructions = performance_counter(0);
 }
 
 // Protobuf interface.
@@ -818,7 +880,7 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
 
     w.encode_gauge(
         "governance_stable_memory_size_bytes",
-        (dfn_core::api::stable_memory_size_in_pages() * 65536) as f64,
+        (dfn_core::stable::stable64_size() * 65536) as f64,
         "Size of the stable memory allocated by this canister measured in bytes.",
     )?;
     w.encode_gauge(
@@ -833,7 +895,7 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
     )?;
     w.encode_gauge(
         "governance_neurons_total",
-        governance.proto.neurons.len() as f64,
+        governance.neuron_store.len() as f64,
         "Total number of neurons.",
     )?;
     w.encode_gauge(
@@ -862,6 +924,45 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
         "Total number of e8s distributed in the latest reward event.",
     )?;
 
+    let mut proposals_by_topic_and_status: BTreeMap<(i32, i32), u64> = BTreeMap::new();
+    for proposal in governance.proto.proposals.values() {
+        *proposals_by_topic_and_status
+            .entry((proposal.topic() as i32, proposal.status() as i32))
+            .or_default() += 1;
+    }
+    w.encode_labeled_gauge(
+        "governance_proposals_count",
+        proposals_by_topic_and_status
+            .into_iter()
+            .map(|((topic, status), count)| {
+                (
+                    vec![
+                        ("topic", enum_name(topic, Topic::from_i32)),
+                        ("status", enum_name(status, ProposalStatus::from_i32)),
+                    ],
+                    count as f64,
+                )
+            }),
+        "Number of proposals that haven't been gc'd, grouped by topic and status.",
+    )?;
+
+    let heartbeat_metrics = heartbeat_metrics();
+    w.encode_gauge(
+        "governance_latest_heartbeat_timestamp_seconds",
+        (heartbeat_metrics.latest_start_timestamp_nanos / 1_000_000_000) as f64,
+        "Timestamp of the latest heartbeat, in seconds since the Unix epoch.",
+    )?;
+    w.encode_gauge(
+        "governance_latest_heartbeat_instructions",
+        heartbeat_metrics.latest_instructions as f64,
+        "Number of instructions executed by the latest heartbeat up to its first await point.",
+    )?;
+    w.encode_gauge(
+        "governance_latest_periodic_tasks_duration_seconds",
+        heartbeat_metrics.latest_periodic_tasks_duration_nanos as f64 / 1e9,
+        "How long the latest completed run of the periodic tasks took, in seconds.",
+    )?;
+
     let total_voting_power = match governance.proto.proposals.iter().next_back() {
         Some((_, proposal)) => match &proposal.latest_tally {
             Some(tally) => tally.total as f64,
@@ -975,9 +1076,206 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
     Ok(())
 }
 
+/// Serves `/api/proposals/<id>`, which returns the proposal with the given ID
+/// as JSON. Payloads of `ExecuteNnsFunction` proposals are candid-decoded and
+/// rendered as text, so that they can be inspected without custom tooling.
+fn serve_api(path: &str, _req: &HttpRequest) -> Option<HttpResponse> {
+    let id = path.strip_prefix("/api/proposals/")?;
+    let id = match id.parse::<u64>() {
+        Ok(id) => id,
+        Err(_) => return Some(text_response(400, format!("Invalid proposal ID: {}", id))),
+    };
+    let proposal_info = match governance().get_proposal_info(&caller(), ProposalIdProto { id }) {
+        Some(proposal_info) => proposal_info,
+        None => return Some(text_response(404, format!("Proposal {} not found", id))),
+    };
+    Some(proposal_response(&proposal_info))
+}
+
+fn proposal_response(proposal_info: &ProposalInfo) -> HttpResponse {
+    match serde_json::to_vec(&ProposalJson::from(proposal_info)) {
+        Ok(body) => HttpResponse {
+            status_code: 200,
+            headers: vec![
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("Content-Length".to_string(), body.len().to_string()),
+            ],
+            body: ByteBuf::from(body),
+            streaming_strategy: None,
+        },
+        Err(err) => text_response(500, format!("Failed to encode proposal: {}", err)),
+    }
+}
+
+/// The JSON representation of a proposal served by `/api/proposals/<id>`.
+/// Enums are rendered by name, and the action of the proposal by its kind and
+/// a text rendering of its payload.
+#[derive(serde::Serialize)]
+struct ProposalJson {
+    id: Option<u64>,
+    proposer: Option<u64>,
+    title: Option<String>,
+    summary: String,
+    url: String,
+    action: Option<ActionJson>,
+    topic: String,
+    status: String,
+    reward_status: String,
+    reject_cost_e8s: u64,
+    proposal_timestamp_seconds: u64,
+    decided_timestamp_seconds: u64,
+    executed_timestamp_seconds: u64,
+    failed_timestamp_seconds: u64,
+    deadline_timestamp_seconds: Option<u64>,
+    failure_reason: Option<String>,
+    latest_tally: Option<TallyJson>,
+}
+
+#[derive(serde::Serialize)]
+struct ActionJson {
+    kind: String,
+    /// The NNS function of an `ExecuteNnsFunction` proposal.
+    nns_function: Option<String>,
+    payload: String,
+}
+
+#[derive(serde::Serialize)]
+struct TallyJson {
+    timestamp_seconds: u64,
+    yes: u64,
+    no: u64,
+    total: u64,
+}
+
+impl From<&ProposalInfo> for ProposalJson {
+    fn from(proposal_info: &ProposalInfo) -> Self {
+        let proposal = proposal_info.proposal.as_ref();
+        Self {
+            id: proposal_info.id.as_ref().map(|id| id.id),
+            proposer: proposal_info.proposer.as_ref().map(|id| id.id),
+            title: proposal.and_then(|proposal| proposal.title.clone()),
+            summary: proposal
+                .map(|proposal| proposal.summary.clone())
+                .unwrap_or_default(),
+            url: proposal
+                .map(|proposal| proposal.url.clone())
+                .unwrap_or_default(),
+            action: proposal
+                .and_then(|proposal| proposal.action.as_ref())
+                .map(|action| ActionJson::new(action, &proposal_info.payload_text_rendering)),
+            topic: enum_name(proposal_info.topic, Topic::from_i32),
+            status: enum_name(proposal_info.status, ProposalStatus::from_i32),
+            reward_status: enum_name(proposal_info.reward_status, ProposalRewardStatus::from_i32),
+            reject_cost_e8s: proposal_info.reject_cost_e8s,
+            proposal_timestamp_seconds: proposal_info.proposal_timestamp_seconds,
+            decided_timestamp_seconds: proposal_info.decided_timestamp_seconds,
+            executed_timestamp_seconds: proposal_info.executed_timestamp_seconds,
+            failed_timestamp_seconds: proposal_info.failed_timestamp_seconds,
+            deadline_timestamp_seconds: proposal_info.deadline_timestamp_seconds,
+            failure_reason: proposal_info
+                .failure_reason
+                .as_ref()
+                .map(|err| err.to_string()),
+            latest_tally: proposal_info.latest_tally.as_ref().map(|tally| TallyJson {
+                timestamp_seconds: tally.timestamp_seconds,
+                yes: tally.yes,
+                no: tally.no,
+                total: tally.total,
+            }),
+        }
+    }
+}
+
+impl ActionJson {
+    /// The opaque payload of an `ExecuteNnsFunction` proposal is rendered by
+    /// the text rendering stored at submission if there is one, or else by
+    /// the candid text form of the payload if it can be decoded, or else by
+    /// the hex encoding of the payload. Other actions are rendered with their
+    /// `Debug` representation.
+    fn new(action: &Action, payload_text_rendering: &Option<String>) -> Self {
+        let (kind, nns_function, payload) = match action {
+            Action::ManageNeuron(payload) => ("ManageNeuron", None, format!("{:?}", payload)),
+            Action::ManageNetworkEconomics(payload) => {
+                ("ManageNetworkEconomics", None, format!("{:?}", payload))
+            }
+            Action::Motion(payload) => ("Motion", None, payload.motion_text.clone()),
+            Action::ExecuteNnsFunction(execute) => {
+                let payload = payload_text_rendering
+                    .clone()
+                    .or_else(|| {
+                        candid::IDLArgs::from_bytes(&execute.payload)
+                            .ok()
+                            .map(|args| args.to_string())
+                    })
+                    .unwrap_or_else(|| {
+                        execute
+                            .payload
+                            .iter()
+                            .map(|byte| format!("{:02x}", byte))
+                            .collect()
+                    });
+                (
+                    "ExecuteNnsFunction",
+                    Some(enum_name(execute.nns_function, NnsFunction::from_i32)),
+                    payload,
+                )
+            }
+            Action::ApproveGenesisKyc(payload) => {
+                ("ApproveGenesisKyc", None, format!("{:?}", payload))
+            }
+            Action::AddOrRemoveNodeProvider(payload) => {
+                ("AddOrRemoveNodeProvider", None, format!("{:?}", payload))
+            }
+            Action::RewardNodeProvider(payload) => {
+                ("RewardNodeProvider", None, format!("{:?}", payload))
+            }
+            Action::SetDefaultFollowees(payload) => {
+                ("SetDefaultFollowees", None, format!("{:?}", payload))
+            }
+            Action::RewardNodeProviders(payload) => {
+                ("RewardNodeProviders", None, format!("{:?}", payload))
+            }
+            Action::RegisterKnownNeuron(payload) => {
+                ("RegisterKnownNeuron", None, format!("{:?}", payload))
+            }
+            Action::SetSnsTokenSwapOpenTimeWindow(payload) => (
+                "SetSnsTokenSwapOpenTimeWindow",
+                None,
+                format!("{:?}", payload),
+            ),
+            Action::OpenSnsTokenSwap(payload) => {
+                ("OpenSnsTokenSwap", None, format!("{:?}", payload))
+            }
+        };
+        Self {
+            kind: kind.to_string(),
+            nns_function,
+            payload,
+        }
+    }
+}
+
+/// Returns the name of a protobuf enum value, or "Unknown" for values that
+/// this version of the canister doesn't know about.
+fn enum_name<T: std::fmt::Debug>(value: i32, from_i32: impl FnOnce(i32) -> Option<T>) -> String {
+    match from_i32(value) {
+        Some(value) => format!("{:?}", value),
+        None => "Unknown".to_string(),
+    }
+}
+
+fn text_response(status_code: u16, body: String) -> HttpResponse {
+    HttpResponse {
+        status_code,
+        headers: vec![],
+        body: ByteBuf::from(body),
+        streaming_strategy: None,
+    }
+}
+
 #[export_name = "canister_query http_request"]
 fn http_request() {
-    dfn_http_metrics::serve_metrics(encode_metrics);
+    dfn_http_metrics::serve_metrics_and(encode_metrics, serve_api);
 }
 
 // This makes this Candid service self-describing, so that for example Candid
@@ -1042,3 +1340,45 @@ fn test_set_time_warp() {
     assert!(delta_s >= 1000, "delta_s = {}", delta_s);
     assert!(delta_s < 1005, "delta_s = {}", delta_s);
 }
+
+#[test]
+fn test_proposal_response_renders_execute_nns_function_payload_as_json() {
+    let proposal_info = ProposalInfo {
+        id: Some(ProposalIdProto { id: 42 }),
+        proposal: Some(Proposal {
+            title: Some("Bless version".to_string()),
+            action: Some(Action::ExecuteNnsFunction(ExecuteNnsFunction {
+                nns_function: NnsFunction::BlessReplicaVersion as i32,
+                payload: candid::encode_one("hello").unwrap(),
+            })),
+            ..Default::default()
+        }),
+        topic: Topic::ReplicaVersionManagement as i32,
+        status: ProposalStatus::Open as i32,
+        ..Default::default()
+    };
+
+    let response = proposal_response(&proposal_info);
+
+    assert_eq!(response.status_code, 200);
+    assert!(response
+        .headers
+        .contains(&("Content-Type".to_string(), "application/json".to_string())));
+    let json: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+    assert_eq!(json["id"], 42);
+    assert_eq!(json["title"], "Bless version");
+    assert_eq!(json["topic"], "ReplicaVersionManagement");
+    assert_eq!(json["status"], "Open");
+    assert_eq!(json["action"]["kind"], "ExecuteNnsFunction");
+    assert_eq!(json["action"]["nns_function"], "BlessReplicaVersion");
+    assert_eq!(json["action"]["payload"], "(\"hello\")");
+}
+
+#[test]
+fn test_enum_name_of_unknown_value() {
+    assert_eq!(
+        enum_name(Topic::Governance as i32, Topic::from_i32),
+        "Governance"
+    );
+    assert_eq!(enum_name(i32::MAX, Topic::from_i32), "Unknown");
+}
@@ -214,6 +214,17 @@ pub struct Neuron {
     /// If set, the neuron belongs to the "known neurons". It has been given a name and maybe a description.
     #[prost(message, optional, tag = "18")]
     pub known_neuron_data: ::core::option::Option<KnownNeuronData>,
+    /// The maturity of the neuron that has been staked, in "e8s equivalent".
+    ///
+    /// Staked maturity counts towards the voting power of the neuron, like
+    /// its stake, but is not minted. Once the neuron is dissolved, its staked
+    /// maturity is moved back to `maturity_e8s_equivalent`.
+    #[prost(uint64, optional, tag = "20")]
+    pub staked_maturity_e8s_equivalent: ::core::option::Option<u64>,
+    /// If set to true, the voting rewards of the neuron are added to its
+    /// staked maturity instead of its maturity.
+    #[prost(bool, optional, tag = "21")]
+    pub auto_stake_maturity: ::core::option::Option<bool>,
     /// At any time, at most one of `when_dissolved` and
     /// `dissolve_delay` are specified.
     ///
@@ -438,6 +449,29 @@ pub struct SetSnsTokenSwapOpenTimeWindow {
     #[prost(message, optional, tag = "2")]
     pub request: ::core::option::Option<::ic_sns_swap::pb::v1::SetOpenTimeWindowRequest>,
 }
+/// Calls the open Candid method on a swap canister, drawing a contribution
+/// from the maturity of community fund neurons.
+///
+/// The contribution of each community fund neuron is proportional to its
+/// maturity. The ICP is only minted once the swap commits; if the swap
+/// aborts, the maturity is given back to the neurons (see
+/// settle_community_fund_participation).
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenSnsTokenSwap {
+    /// The swap canister to send the request to.
+    #[prost(message, optional, tag = "1")]
+    pub target_swap_canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The window during which the swap accepts participants.
+    #[prost(message, optional, tag = "2")]
+    pub open_time_window: ::core::option::Option<::ic_sns_swap::pb::v1::TimeWindow>,
+    /// The total amount of ICP to be drawn from the maturity of community
+    /// fund neurons. If the community fund neurons hold less maturity in
+    /// total, all of it is drawn.
+    #[prost(uint64, optional, tag = "3")]
+    pub community_fund_investment_e8s: ::core::option::Option<u64>,
+}
 /// A proposal is the immutable input of a proposal submission. This contains
 /// all the information from the original proposal submission.
 ///
@@ -466,7 +500,7 @@ pub struct Proposal {
     /// take.
     #[prost(
         oneof = "proposal::Action",
-        tags = "10, 12, 13, 14, 15, 16, 17, 18, 19, 21, 22"
+        tags = "10, 12, 13, 14, 15, 16, 17, 18, 19, 21, 22, 23"
     )]
     pub action: ::core::option::Option<proposal::Action>,
 }
@@ -542,6 +576,10 @@ pub mod proposal {
         /// Call the set_open_time_window on a swap canister.
         #[prost(message, tag = "22")]
         SetSnsTokenSwapOpenTimeWindow(super::SetSnsTokenSwapOpenTimeWindow),
+        /// Call the open method on a swap canister, with a contribution from the
+        /// community fund.
+        #[prost(message, tag = "23")]
+        OpenSnsTokenSwap(super::OpenSnsTokenSwap),
     }
 }
 /// Empty message to use in oneof fields that represent empty
@@ -569,7 +607,7 @@ pub struct ManageNeuron {
     pub neuron_id_or_subaccount: ::core::option::Option<manage_neuron::NeuronIdOrSubaccount>,
     #[prost(
         oneof = "manage_neuron::Command",
-        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 13, 14"
+        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 13, 14, 15"
     )]
     pub command: ::core::option::Option<manage_neuron::Command>,
 }
@@ -632,6 +670,14 @@ pub mod manage_neuron {
     #[cfg_attr(feature = "test", derive(comparable::Comparable))]
     #[derive(Clone, PartialEq, ::prost::Message)]
     pub struct LeaveCommunityFund {}
+    /// Set whether the voting rewards of the neuron are automatically staked.
+    #[derive(candid::CandidType, candid::Deserialize)]
+    #[cfg_attr(feature = "test", derive(comparable::Comparable))]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct ChangeAutoStakeMaturity {
+        #[prost(bool, tag = "1")]
+        pub requested_setting_for_auto_stake_maturity: bool,
+    }
     /// Commands that only configure a given neuron, but do not interact
     /// with the outside world. They all require the caller to be the
     /// controller of the neuron.
@@ -639,7 +685,7 @@ pub mod manage_neuron {
     #[cfg_attr(feature = "test", derive(comparable::Comparable))]
     #[derive(Clone, PartialEq, ::prost::Message)]
     pub struct Configure {
-        #[prost(oneof = "configure::Operation", tags = "1, 2, 3, 4, 5, 6, 7, 8")]
+        #[prost(oneof = "configure::Operation", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9")]
         pub operation: ::core::option::Option<configure::Operation>,
     }
     /// Nested message and enum types in `Configure`.
@@ -664,6 +710,8 @@ pub mod manage_neuron {
             JoinCommunityFund(super::JoinCommunityFund),
             #[prost(message, tag = "8")]
             LeaveCommunityFund(super::LeaveCommunityFund),
+            #[prost(message, tag = "9")]
+            ChangeAutoStakeMaturity(super::ChangeAutoStakeMaturity),
         }
     }
     /// Disburse this neuron's stake: transfer the staked ICP to the
@@ -742,6 +790,20 @@ pub mod manage_neuron {
         #[prost(uint32, tag = "1")]
         pub percentage_to_merge: u32,
     }
+    /// Stake the maturity of a neuron.
+    /// The caller can choose a percentage of the current maturity to stake.
+    /// Unlike MergeMaturity, this does not mint any ICP: the staked maturity
+    /// only counts towards the voting power of the neuron until the neuron
+    /// is dissolved.
+    #[derive(candid::CandidType, candid::Deserialize)]
+    #[cfg_attr(feature = "test", derive(comparable::Comparable))]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct StakeMaturity {
+        /// The percentage of maturity to stake, from 1 to 100 (inclusive).
+        /// If not specified, all of the maturity is staked.
+        #[prost(uint32, optional, tag = "1")]
+        pub percentage_to_stake: ::core::option::Option<u32>,
+    }
     /// Disburse a portion of this neuron's stake into another neuron.
     /// This allows to split a neuron but with a new dissolve delay
     /// and owned by someone else.
@@ -883,6 +945,8 @@ pub mod manage_neuron {
         MergeMaturity(MergeMaturity),
         #[prost(message, tag = "14")]
         Merge(Merge),
+        #[prost(message, tag = "15")]
+        StakeMaturity(StakeMaturity),
     }
 }
 /// The response of the ManageNeuron command
@@ -894,7 +958,7 @@ pub mod manage_neuron {
 pub struct ManageNeuronResponse {
     #[prost(
         oneof = "manage_neuron_response::Command",
-        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12"
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13"
     )]
     pub command: ::core::option::Option<manage_neuron_response::Command>,
 }
@@ -932,6 +996,17 @@ pub mod manage_neuron_response {
     #[derive(candid::CandidType, candid::Deserialize)]
     #[cfg_attr(feature = "test", derive(comparable::Comparable))]
     #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct StakeMaturityResponse {
+        /// The maturity of the neuron that is left after staking.
+        #[prost(uint64, tag = "1")]
+        pub maturity_e8s: u64,
+        /// The staked maturity of the neuron after staking.
+        #[prost(uint64, tag = "2")]
+        pub staked_maturity_e8s: u64,
+    }
+    #[derive(candid::CandidType, candid::Deserialize)]
+    #[cfg_attr(feature = "test", derive(comparable::Comparable))]
+    #[derive(Clone, PartialEq, ::prost::Message)]
     pub struct FollowResponse {}
     #[derive(candid::CandidType, candid::Deserialize)]
     #[cfg_attr(feature = "test", derive(comparable::Comparable))]
@@ -1000,6 +1075,8 @@ pub mod manage_neuron_response {
         MergeMaturity(MergeMaturityResponse),
         #[prost(message, tag = "12")]
         Merge(MergeResponse),
+        #[prost(message, tag = "13")]
+        StakeMaturity(StakeMaturityResponse),
     }
 }
 #[derive(candid::CandidType, candid::Deserialize)]
@@ -1170,6 +1247,20 @@ pub struct ProposalData {
     /// Wait-for-quiet state that needs to be saved in stable memory.
     #[prost(message, optional, tag = "16")]
     pub wait_for_quiet_state: ::core::option::Option<WaitForQuietState>,
+    /// The contributions drawn from the maturity of community fund neurons
+    /// when an OpenSnsTokenSwap proposal is executed. Cleared once the
+    /// participation has been settled, i.e. once the ICP has been minted or the
+    /// maturity has been given back to the neurons.
+    #[prost(message, repeated, tag = "17")]
+    pub cf_participants: ::prost::alloc::vec::Vec<::ic_sns_swap::pb::v1::CfParticipant>,
+    /// A human-readable rendering of the payload of an `ExecuteNnsFunction`
+    /// proposal, computed when the proposal is submitted by decoding the
+    /// payload into the argument type of the called canister method.
+    ///
+    /// Not set for other proposals, or for proposals submitted before payloads
+    /// were rendered.
+    #[prost(string, optional, tag = "18")]
+    pub payload_text_rendering: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Stores data relevant to the "wait for quiet" implementation.
 #[derive(candid::CandidType, candid::Deserialize)]
@@ -1234,6 +1325,9 @@ pub struct ProposalInfo {
     pub reward_status: i32,
     #[prost(uint64, optional, tag = "19")]
     pub deadline_timestamp_seconds: ::core::option::Option<u64>,
+    /// See \[ProposalData::payload_text_rendering\].
+    #[prost(string, optional, tag = "20")]
+    pub payload_text_rendering: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Network economics contains the parameters for several operations related
 /// to the economy of the network. When submitting a NetworkEconomics proposal
@@ -1356,7 +1450,9 @@ pub struct KnownNeuronData {
 #[cfg_attr(feature = "test", derive(comparable::Comparable), compare_default)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Governance {
-    /// Current set of neurons.
+    /// The initial set of neurons. Once the canister is running, neurons live
+    /// in stable memory rather than here, so this is only populated in the init
+    /// payload and in the state saved by versions that predate the neuron store.
     #[prost(map = "fixed64, message", tag = "1")]
     pub neurons: ::std::collections::HashMap<u64, Neuron>,
     /// Proposals.
@@ -1732,9 +1828,8 @@ pub enum Topic {
     Kyc = 9,
     /// Topic for proposals to reward node providers.
     NodeProviderRewards = 10,
-    /// Currently, the only type of proposal in this topic is
-    /// SetSnsTokenSwapOpenTimeWindow. It is not expected that more types of proposals will
-    /// be added to this topic.
+    /// Proposals that open and configure SNS token swaps, i.e.
+    /// SetSnsTokenSwapOpenTimeWindow and OpenSnsTokenSwap.
     SnsDecentralizationSale = 11,
 }
 /// Every neuron is in one of three states.
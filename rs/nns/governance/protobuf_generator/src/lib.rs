@@ -41,6 +41,14 @@ pub fn generate_prost_files(proto: ProtoPaths<'_>, out: &Path) {
         ]
         .join(" "),
     );
+    config.type_attribute(
+        "ic_nns_governance.pb.v1.OpenSnsTokenSwap",
+        [
+            "#[derive(candid::CandidType, candid::Deserialize)]",
+            "#[cfg_attr(feature = \"test\", derive(comparable::Comparable))]",
+        ]
+        .join(" "),
+    );
     config.type_attribute(
         "ic_nns_governance.pb.v1.Empty",
         [
@@ -145,6 +153,14 @@ pub fn generate_prost_files(proto: ProtoPaths<'_>, out: &Path) {
         ]
         .join(" "),
     );
+    config.type_attribute(
+        "ic_nns_governance.pb.v1.ManageNeuron.ChangeAutoStakeMaturity",
+        [
+            "#[derive(candid::CandidType, candid::Deserialize)]",
+            "#[cfg_attr(feature = \"test\", derive(comparable::Comparable))]",
+        ]
+        .join(" "),
+    );
     config.type_attribute(
         "ic_nns_governance.pb.v1.ManageNeuron.SetDissolveTimestamp",
         [
@@ -241,6 +257,14 @@ pub fn generate_prost_files(proto: ProtoPaths<'_>, out: &Path) {
         ]
         .join(" "),
     );
+    config.type_attribute(
+        "ic_nns_governance.pb.v1.ManageNeuron.StakeMaturity",
+        [
+            "#[derive(candid::CandidType, candid::Deserialize)]",
+            "#[cfg_attr(feature = \"test\", derive(comparable::Comparable))]",
+        ]
+        .join(" "),
+    );
     config.type_attribute(
         "ic_nns_governance.pb.v1.ManageNeuron.Split",
         [
@@ -369,6 +393,14 @@ pub fn generate_prost_files(proto: ProtoPaths<'_>, out: &Path) {
         ]
         .join(" "),
     );
+    config.type_attribute(
+        "ic_nns_governance.pb.v1.ManageNeuronResponse.StakeMaturityResponse",
+        [
+            "#[derive(candid::CandidType, candid::Deserialize)]",
+            "#[cfg_attr(feature = \"test\", derive(comparable::Comparable))]",
+        ]
+        .join(" "),
+    );
     config.type_attribute(
         "ic_nns_governance.pb.v1.ManageNeuronResponse.FollowResponse",
         [
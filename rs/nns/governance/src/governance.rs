@@ -22,11 +22,11 @@ use crate::pb::v1::{
     proposal,
     reward_node_provider::RewardMode,
     Ballot, BallotInfo, ExecuteNnsFunction, Governance as GovernanceProto, GovernanceError,
-    KnownNeuron, KnownNeuronData, ListKnownNeuronsResponse, ListNeurons, ListNeuronsResponse,
-    ListProposalInfo, ListProposalInfoResponse, ManageNeuron, ManageNeuronResponse,
+    KnownNeuron, ListKnownNeuronsResponse, ListNeurons, ListNeuronsResponse, ListProposalInfo,
+    ListProposalInfoResponse, ManageNeuron, ManageNeuronResponse,
     MostRecentMonthlyNodeProviderRewards, NetworkEconomics, Neuron, NeuronInfo, NeuronState,
-    NnsFunction, NodeProvider, Proposal, ProposalData, ProposalInfo, ProposalRewardStatus,
-    ProposalStatus, RewardEvent, RewardNodeProvider, RewardNodeProviders,
+    NnsFunction, NodeProvider, OpenSnsTokenSwap, Proposal, ProposalData, ProposalInfo,
+    ProposalRewardStatus, ProposalStatus, RewardEvent, RewardNodeProvider, RewardNodeProviders,
     SetSnsTokenSwapOpenTimeWindow, Tally, Topic, UpdateNodeProvider, Vote,
 };
 
@@ -48,8 +48,11 @@ use registry_canister::mutations::do_add_node_operator::AddNodeOperatorPayload;
 #[cfg(target_arch = "wasm32")]
 use dfn_core::println;
 
+use crate::neuron_store::NeuronStore;
+use crate::nns_function_payload::render_nns_function_payload;
 use crate::pb::v1::governance::GovernanceCachedMetrics;
 use crate::pb::v1::manage_neuron_response::MergeMaturityResponse;
+use crate::pb::v1::manage_neuron_response::StakeMaturityResponse;
 use crate::pb::v1::proposal::Action;
 use crate::pb::v1::reward_node_provider::RewardToAccount;
 use crate::pb::v1::WaitForQuietState;
@@ -163,6 +166,15 @@ const NODE_PROVIDER_REWARD_PERIOD_SECONDS: u64 = 2629800;
 
 const VALID_MATURITY_MODULATION_BASIS_POINTS_RANGE: RangeInclusive<i32> = -500..=500;
 
+/// The maximum number of neurons moved from the heap to stable memory by
+/// `Governance::new` and by each call to `run_periodic_tasks`, so that the
+/// migration of all neurons stays within the instruction limits.
+const MAX_NEURONS_TO_MIGRATE_PER_CALL: usize = 5_000;
+
+/// The maximum number of neurons whose staked maturity is moved back to their
+/// maturity by each call to `run_periodic_tasks`.
+const MAX_NEURONS_TO_UNSTAKE_MATURITY_PER_CALL: usize = 100;
+
 // The default values for network economics (until we initialize it).
 // Can't implement Default since it conflicts with Prost's.
 impl NetworkEconomics {
@@ -364,6 +376,12 @@ impl ManageNeuronResponse {
         }
     }
 
+    pub fn stake_maturity_response(response: StakeMaturityResponse) -> Self {
+        ManageNeuronResponse {
+            command: Some(manage_neuron_response::Command::StakeMaturity(response)),
+        }
+    }
+
     pub fn follow_response() -> Self {
         ManageNeuronResponse {
             command: Some(manage_neuron_response::Command::Follow(
@@ -563,16 +581,23 @@ impl Neuron {
         self.joined_community_fund_timestamp_seconds.is_some()
     }
 
+    /// Returns true if the voting rewards of this neuron are staked.
+    fn is_auto_stake_maturity_enabled(&self) -> bool {
+        self.auto_stake_maturity.unwrap_or(false)
+    }
+
     /// Return the voting power of this neuron.
     ///
-    /// The voting power is the stake of the neuron modified by a
-    /// bonus of up to 100% depending on the dissolve delay, with
-    /// the maximum bonus of 100% received at an 8 year dissolve
-    /// delay. The voting power is further modified by the age of
-    /// the neuron giving up to 25% bonus after four years.
+    /// The voting power is the stake of the neuron, including its
+    /// staked maturity, modified by a bonus of up to 100% depending
+    /// on the dissolve delay, with the maximum bonus of 100% received
+    /// at an 8 year dissolve delay. The voting power is further
+    /// modified by the age of the neuron giving up to 25% bonus after
+    /// four years.
     pub fn voting_power(&self, now_seconds: u64) -> u64 {
         // We compute the stake adjustments in u128.
-        let stake = self.stake_e8s() as u128;
+        let stake =
+            self.stake_e8s() as u128 + self.staked_maturity_e8s_equivalent.unwrap_or(0) as u128;
         // Dissolve delay is capped to eight years, but we cap it
         // again here to make sure, e.g., if this changes in the
         // future.
@@ -998,6 +1023,14 @@ impl Neuron {
             manage_neuron::configure::Operation::LeaveCommunityFund(_) => {
                 self.leave_community_fund()
             }
+            manage_neuron::configure::Operation::ChangeAutoStakeMaturity(change) => {
+                self.auto_stake_maturity = if change.requested_setting_for_auto_stake_maturity {
+                    Some(true)
+                } else {
+                    None
+                };
+                Ok(())
+            }
         }
     }
 
@@ -1144,9 +1177,8 @@ impl Proposal {
                 | proposal::Action::RewardNodeProviders(_) => Topic::NodeProviderRewards,
                 proposal::Action::SetDefaultFollowees(_)
                 | proposal::Action::RegisterKnownNeuron(_) => Topic::Governance,
-                proposal::Action::SetSnsTokenSwapOpenTimeWindow(_) => {
-                    Topic::SnsDecentralizationSale
-                }
+                proposal::Action::SetSnsTokenSwapOpenTimeWindow(_)
+                | proposal::Action::OpenSnsTokenSwap(_) => Topic::SnsDecentralizationSale,
             }
         } else {
             Topic::Unspecified
@@ -1179,7 +1211,7 @@ impl Action {
 }
 
 impl ProposalData {
-    pub(crate) fn topic(&self) -> Topic {
+    pub fn topic(&self) -> Topic {
         if let Some(proposal) = &self.proposal {
             proposal.topic()
         } else {
@@ -1570,163 +1602,41 @@ impl Tally {
 }
 
 impl GovernanceProto {
-    /// From the `neurons` part of this `Governance` struct, build the
-    /// index (per topic) from followee to set of followers. The
-    /// neurons themselves map followers (the neuron ID) to a set of
-    /// followees (per topic).
-    pub fn build_topic_followee_index(&self) -> BTreeMap<Topic, BTreeMap<u64, BTreeSet<u64>>> {
-        let mut topic_followee_index = BTreeMap::new();
-        for neuron in self.neurons.values() {
-            GovernanceProto::add_neuron_to_topic_followee_index(&mut topic_followee_index, neuron);
-        }
-        topic_followee_index
-    }
-
-    pub fn add_neuron_to_topic_followee_index(
-        index: &mut BTreeMap<Topic, BTreeMap<u64, BTreeSet<u64>>>,
-        neuron: &Neuron,
-    ) {
-        for (itopic, followees) in neuron.followees.iter() {
-            // Note: if there are topics in the data (e.g.,
-            // file) that the Governance struct was
-            // (re-)constructed from that are no longer
-            // defined in the `enum Topic`, the entries are
-            // not put into the topic_followee_index.
-            //
-            // This is okay, as the topics are only changed on
-            // upgrades, and the index is rebuilt on upgrade.
-            if let Some(topic) = Topic::from_i32(*itopic) {
-                let followee_index = index.entry(topic).or_insert_with(BTreeMap::new);
-                for followee in followees.followees.iter() {
-                    followee_index
-                        .entry(followee.id)
-                        .or_insert_with(BTreeSet::new)
-                        .insert(neuron.id.as_ref().expect("Neuron must have an id").id);
-                }
-            }
-        }
-    }
-
-    pub fn remove_neuron_from_topic_followee_index(
-        index: &mut BTreeMap<Topic, BTreeMap<u64, BTreeSet<u64>>>,
-        neuron: &Neuron,
-    ) {
-        for (itopic, followees) in neuron.followees.iter() {
-            if let Some(topic) = Topic::from_i32(*itopic) {
-                if let Some(followee_index) = index.get_mut(&topic) {
-                    for followee in followees.followees.iter() {
-                        if let Some(followee_set) = followee_index.get_mut(&followee.id) {
-                            followee_set
-                                .remove(&neuron.id.as_ref().expect("Neuron must have an id").id);
-                            if followee_set.is_empty() {
-                                followee_index.remove(&followee.id);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    /// Update `index` to map all the given Neuron's hot keys and controller to
-    /// `neuron_id`
-    pub fn add_neuron_to_principal_to_neuron_ids_index(
-        index: &mut BTreeMap<PrincipalId, HashSet<u64>>,
-        neuron_id: u64,
-        neuron: &Neuron,
-    ) {
-        let principals = neuron.hot_keys.iter().chain(neuron.controller.iter());
-
-        for principal in principals {
-            Self::add_neuron_to_principal_in_principal_to_neuron_ids_index(
-                index, neuron_id, principal,
-            );
-        }
-    }
-
-    pub fn add_neuron_to_principal_in_principal_to_neuron_ids_index(
-        index: &mut BTreeMap<PrincipalId, HashSet<u64>>,
-        neuron_id: u64,
-        principal: &PrincipalId,
-    ) {
-        let neuron_ids = index.entry(*principal).or_insert_with(HashSet::new);
-        neuron_ids.insert(neuron_id);
-    }
-
-    /// Update `index` to remove the neuron from the list of neurons mapped to
-    /// principals.
-    pub fn remove_neuron_from_principal_to_neuron_ids_index(
-        index: &mut BTreeMap<PrincipalId, HashSet<u64>>,
-        neuron_id: u64,
-        neuron: &Neuron,
-    ) {
-        let principals = neuron.hot_keys.iter().chain(neuron.controller.iter());
-
-        for principal in principals {
-            Self::remove_neuron_from_principal_in_principal_to_neuron_ids_index(
-                index, neuron_id, principal,
-            );
-        }
-    }
-
-    pub fn remove_neuron_from_principal_in_principal_to_neuron_ids_index(
-        index: &mut BTreeMap<PrincipalId, HashSet<u64>>,
-        neuron_id: u64,
-        principal: &PrincipalId,
-    ) {
-        let neuron_ids = index.get_mut(principal);
-        // Shouldn't fail if the index is broken, so just continue.
-        if neuron_ids.is_none() {
-            return;
-        }
-        let neuron_ids = neuron_ids.unwrap();
-        neuron_ids.retain(|nid| *nid != neuron_id);
-        // If there are no neurons left, remove the entry from the index.
-        if neuron_ids.is_empty() {
-            index.remove(principal);
-        }
-    }
-
-    pub fn build_principal_to_neuron_ids_index(&self) -> BTreeMap<PrincipalId, HashSet<u64>> {
-        let mut index = BTreeMap::new();
-
-        for (id, neuron) in self.neurons.iter() {
-            Self::add_neuron_to_principal_to_neuron_ids_index(&mut index, *id, neuron);
-        }
-
-        index
-    }
-
-    pub fn build_known_neuron_name_index(&self) -> HashSet<String> {
-        self.neurons
-            .iter()
-            .filter(|(_id, neuron)| neuron.known_neuron_data.is_some())
-            .map(|(_id, neuron)| neuron.known_neuron_data.as_ref().unwrap().name.clone())
-            .collect()
-    }
-
-    // Returns whether the proposed default following is valid by making
-    // sure that the refered to neurons exist.
-    fn validate_default_followees(
-        &self,
-        proposed: &HashMap<i32, Followees>,
-    ) -> Result<(), GovernanceError> {
-        for followees in proposed.values() {
-            for followee in &followees.followees {
-                if !self.neurons.contains_key(&followee.id) {
-                    return Err(GovernanceError::new_with_message(
-                        ErrorType::NotFound,
-                        "One or more of the neurons proposed to become\
-                         the new default followees don't exist.",
-                    ));
-                }
+    /// Validates the neurons in `neurons`, which are only set in the initial
+    /// state of the canister and in the state saved by versions that predate
+    /// the `NeuronStore`.
+    pub fn validate_neurons(&self) -> Result<(), GovernanceError> {
+        // Make sure that subaccounts are not repeated across neurons.
+        let mut subaccounts = HashSet::new();
+        for n in self.neurons.values() {
+            // For now expect that neurons have pre-assigned ids, since
+            // we add them only at genesis.
+            let _ =
+                n.id.as_ref()
+                    .expect("Currently neurons must have been pre-assigned an id.");
+            let subaccount = Subaccount(n.account.clone().as_slice().try_into().map_err(|_| {
+                GovernanceError::new_with_message(
+                    ErrorType::PreconditionFailed,
+                    "Invalid subaccount",
+                )
+            })?);
+            if !subaccounts.insert(subaccount) {
+                return Err(GovernanceError::new_with_message(
+                    ErrorType::PreconditionFailed,
+                    "There are two neurons with the same subaccount",
+                ));
             }
         }
         Ok(())
     }
 
-    /// Iterate over all neurons and compute `GovernanceCachedMetrics`
-    pub fn compute_cached_metrics(&self, now: u64, icp_supply: Tokens) -> GovernanceCachedMetrics {
+    /// Iterate over `neurons` and compute `GovernanceCachedMetrics`
+    pub fn compute_cached_metrics(
+        &self,
+        neurons: impl Iterator<Item = Neuron>,
+        now: u64,
+        icp_supply: Tokens,
+    ) -> GovernanceCachedMetrics {
         let mut metrics = GovernanceCachedMetrics {
             timestamp_seconds: now,
             total_supply_icp: icp_supply.get_tokens(),
@@ -1739,7 +1649,7 @@ impl GovernanceProto {
             0
         };
 
-        for (_, neuron) in self.neurons.iter() {
+        for neuron in neurons {
             metrics.total_staked_e8s += neuron.stake_e8s();
 
             if neuron.joined_community_fund_timestamp_seconds.unwrap_or(0) > 0 {
@@ -1922,10 +1832,14 @@ impl LedgerUpdateLock {
 /// IC's governance system.
 pub struct Governance {
     /// The Governance Protobuf which contains all persistent state of
-    /// the IC's governance system. Needs to be stored and retrieved
-    /// on upgrades.
+    /// the IC's governance system, except for the neurons. Needs to be
+    /// stored and retrieved on upgrades.
     pub proto: GovernanceProto,
 
+    /// The neurons and the indexes over them, which live in stable memory
+    /// and therefore survive upgrades without being serialized.
+    pub neuron_store: NeuronStore,
+
     /// Implementation of Environment to make unit testing easier.
     pub env: Box<dyn Environment>,
 
@@ -1935,27 +1849,6 @@ pub struct Governance {
     /// Implementation of the interface with the CMC canister.
     cmc: Box<dyn CMC>,
 
-    /// Cached data structure that (for each topic) maps a followee to
-    /// the set of followers. This is the inverse of the mapping from
-    /// neuron (follower) to followees, in the neurons. This is a
-    /// cached index and will be removed and recreated when the state
-    /// is saved and restored.
-    ///
-    /// Topic -> (neuron ID of followee) -> set of followers.
-    pub topic_followee_index: BTreeMap<Topic, BTreeMap<u64, BTreeSet<u64>>>,
-
-    /// Maps Principals to the Neuron IDs of all Neurons that have this
-    /// Principal as their controller or as one of their hot keys
-    ///
-    /// This is a cached index and will be removed and recreated when the state
-    /// is saved and restored.
-    pub principal_to_neuron_ids_index: BTreeMap<PrincipalId, HashSet<u64>>,
-
-    /// Set of all names given to Known Neurons, to prevent duplication.
-    ///
-    /// This set is cached and will be removed and recreated when the state is saved and restored.
-    pub known_neuron_name_set: HashSet<String>,
-
     /// Timestamp, in seconds since the unix epoch, until which no proposal
     /// needs to be processed.
     closest_proposal_deadline_timestamp_seconds: u64,
@@ -1998,22 +1891,25 @@ impl Governance {
             })
         }
 
-        let mut gov = Self {
+        // The neurons in the proto are either those of the initial state or,
+        // when upgrading from a version that kept the neurons on the heap,
+        // those that were saved in pre_upgrade. Either way, they are moved to
+        // stable memory in batches, the first one right away and the
+        // following ones in `run_periodic_tasks`.
+        let mut neuron_store = NeuronStore::init();
+        neuron_store.add_pending_neurons(std::mem::take(&mut proto.neurons));
+        neuron_store.migrate_pending_neurons(MAX_NEURONS_TO_MIGRATE_PER_CALL);
+
+        Self {
             proto,
+            neuron_store,
             env,
             ledger,
             cmc,
-            topic_followee_index: BTreeMap::new(),
-            principal_to_neuron_ids_index: BTreeMap::new(),
-            known_neuron_name_set: HashSet::new(),
             closest_proposal_deadline_timestamp_seconds: 0,
             latest_gc_timestamp_seconds: 0,
             latest_gc_num_proposals: 0,
-        };
-
-        gov.initialize_indices();
-
-        gov
+        }
     }
 
     /// Validates that the underlying protobuf is well formed.
@@ -2025,41 +1921,29 @@ impl Governance {
             ));
         }
 
-        // Make sure that subaccounts are not repeated across neurons.
-        let mut subaccounts = HashSet::new();
-        for n in self.proto.neurons.values() {
-            // For now expect that neurons have pre-assigned ids, since
-            // we add them only at genesis.
-            let _ =
-                n.id.as_ref()
-                    .expect("Currently neurons must have been pre-assigned an id.");
-            let subaccount = Subaccount(n.account.clone().as_slice().try_into().map_err(|_| {
-                GovernanceError::new_with_message(
-                    ErrorType::PreconditionFailed,
-                    "Invalid subaccount",
-                )
-            })?);
-            if !subaccounts.insert(subaccount) {
-                return Err(GovernanceError::new_with_message(
-                    ErrorType::PreconditionFailed,
-                    "There are two neurons with the same subaccount",
-                ));
-            }
-        }
-
-        self.proto
-            .validate_default_followees(&self.proto.default_followees)?;
+        self.validate_default_followees(&self.proto.default_followees)?;
 
         Ok(())
     }
 
-    /// Initializes the indices.
-    /// Must be called after the state has been externally changed (e.g. by
-    /// setting a new proto).
-    fn initialize_indices(&mut self) {
-        self.topic_followee_index = self.proto.build_topic_followee_index();
-        self.principal_to_neuron_ids_index = self.proto.build_principal_to_neuron_ids_index();
-        self.known_neuron_name_set = self.proto.build_known_neuron_name_index();
+    // Returns whether the proposed default following is valid by making
+    // sure that the refered to neurons exist.
+    fn validate_default_followees(
+        &self,
+        proposed: &HashMap<i32, Followees>,
+    ) -> Result<(), GovernanceError> {
+        for followees in proposed.values() {
+            for followee in &followees.followees {
+                if !self.neuron_store.contains(followee.id) {
+                    return Err(GovernanceError::new_with_message(
+                        ErrorType::NotFound,
+                        "One or more of the neurons proposed to become\
+                         the new default followees don't exist.",
+                    ));
+                }
+            }
+        }
+        Ok(())
     }
 
     fn transaction_fee(&self) -> u64 {
@@ -2071,7 +1955,7 @@ impl Governance {
         let mut id = self.env.random_u64();
         // Don't allow IDs that are already in use. In addition, zero
         // is an invalid ID as it can be confused with an unset ID.
-        while self.proto.neurons.contains_key(&id) || id == 0 {
+        while self.neuron_store.contains(id) || id == 0 {
             id = self.env.random_u64();
         }
         NeuronId { id }
@@ -2097,21 +1981,15 @@ impl Governance {
         })
     }
 
-    pub fn get_neuron(&self, nid: &NeuronId) -> Result<&Neuron, GovernanceError> {
-        self.proto
-            .neurons
-            .get(&nid.id)
+    /// Returns a copy of the neuron with the given ID. Changes to the neuron
+    /// must be written back with `self.neuron_store.upsert`.
+    pub fn get_neuron(&self, nid: &NeuronId) -> Result<Neuron, GovernanceError> {
+        self.neuron_store
+            .get(nid.id)
             .ok_or_else(|| Self::neuron_not_found_error(nid))
     }
 
-    pub fn get_neuron_mut(&mut self, nid: &NeuronId) -> Result<&mut Neuron, GovernanceError> {
-        self.proto
-            .neurons
-            .get_mut(&nid.id)
-            .ok_or_else(|| Self::neuron_not_found_error(nid))
-    }
-
-    fn find_neuron(&self, find_by: &NeuronIdOrSubaccount) -> Result<&Neuron, GovernanceError> {
+    fn find_neuron(&self, find_by: &NeuronIdOrSubaccount) -> Result<Neuron, GovernanceError> {
         match find_by {
             NeuronIdOrSubaccount::NeuronId(nid) => self.get_neuron(nid),
             NeuronIdOrSubaccount::Subaccount(sid) => self
@@ -2192,12 +2070,11 @@ impl Governance {
         }
     }
 
-    /// Add a neuron to the list of neurons and update
-    /// `principal_to_neuron_ids_index`
+    /// Add a neuron to the neuron store, which also updates its indexes.
     ///
     /// Fails under the following conditions:
     /// - the maximum number of neurons has been reached, or
-    /// - the given `neuron_id` already exists in `self.proto.neurons`, or
+    /// - the given `neuron_id` already exists in `self.neuron_store`, or
     /// - the neuron's controller `PrincipalId` is not self-authenticating.
     fn add_neuron(&mut self, neuron_id: u64, neuron: Neuron) -> Result<(), GovernanceError> {
         if neuron_id == 0 {
@@ -2222,13 +2099,13 @@ impl Governance {
         // New neurons are not allowed when the heap is too large.
         self.check_heap_can_grow()?;
 
-        if self.proto.neurons.len() + 1 > MAX_NUMBER_OF_NEURONS {
+        if self.neuron_store.len() + 1 > MAX_NUMBER_OF_NEURONS {
             return Err(GovernanceError::new_with_message(
                 ErrorType::PreconditionFailed,
                 "Cannot add neuron. Max number of neurons reached.",
             ));
         }
-        if self.proto.neurons.contains_key(&neuron_id) {
+        if self.neuron_store.contains(neuron_id) {
             return Err(GovernanceError::new_with_message(
                 ErrorType::PreconditionFailed,
                 format!(
@@ -2245,28 +2122,16 @@ impl Governance {
             ));
         }
 
-        GovernanceProto::add_neuron_to_principal_to_neuron_ids_index(
-            &mut self.principal_to_neuron_ids_index,
-            neuron_id,
-            &neuron,
-        );
-
-        GovernanceProto::add_neuron_to_topic_followee_index(
-            &mut self.topic_followee_index,
-            &neuron,
-        );
-
-        self.proto.neurons.insert(neuron_id, neuron);
+        self.neuron_store.upsert(neuron)?;
 
         Ok(())
     }
 
-    /// Remove a neuron from the list of neurons and update
-    /// `principal_to_neuron_ids_index`
+    /// Remove a neuron from the neuron store, which also updates its indexes.
     ///
-    /// Fail if the given `neuron_id` doesn't exist in `self.proto.neurons`
-    fn remove_neuron(&mut self, neuron_id: u64, neuron: Neuron) -> Result<(), GovernanceError> {
-        if !self.proto.neurons.contains_key(&neuron_id) {
+    /// Fail if the given `neuron_id` doesn't exist in `self.neuron_store`
+    fn remove_neuron(&mut self, neuron_id: u64) -> Result<(), GovernanceError> {
+        if self.neuron_store.remove(neuron_id).is_none() {
             return Err(GovernanceError::new_with_message(
                 ErrorType::NotFound,
                 format!(
@@ -2276,45 +2141,29 @@ impl Governance {
             ));
         }
 
-        GovernanceProto::remove_neuron_from_principal_to_neuron_ids_index(
-            &mut self.principal_to_neuron_ids_index,
-            neuron_id,
-            &neuron,
-        );
-
-        GovernanceProto::remove_neuron_from_topic_followee_index(
-            &mut self.topic_followee_index,
-            &neuron,
-        );
-
-        self.proto.neurons.remove(&neuron_id);
-
         Ok(())
     }
 
     /// Return the Neuron IDs of all Neurons that have `principal` as their
     /// controller or as one of their hot keys.
     pub fn get_neuron_ids_by_principal(&self, principal: &PrincipalId) -> Vec<u64> {
-        self.principal_to_neuron_ids_index
-            .get(principal)
-            .map(|ids| ids.iter().copied().collect())
-            .unwrap_or_default()
+        self.neuron_store
+            .neuron_ids_by_principal(principal)
+            .into_iter()
+            .collect()
     }
 
     /// Return the union of `followees` with the set of Neuron IDs of all
     /// Neurons that directly follow the `followees` w.r.t. the
     /// topic `NeuronManagement`.
     pub fn get_managed_neuron_ids_for(&self, followees: &[u64]) -> Vec<u64> {
-        // Tap into the `topic_followee_index` for followers of level zero neurons.
+        // Tap into the followees index for followers of level zero neurons.
         let mut managed: HashSet<u64> = followees.iter().copied().collect();
         for followee in followees {
-            if let Some(followers) = self
-                .topic_followee_index
-                .get(&Topic::NeuronManagement)
-                .and_then(|m| m.get(followee))
-            {
-                managed.extend(followers)
-            }
+            managed.extend(
+                self.neuron_store
+                    .followers(Topic::NeuronManagement, *followee),
+            );
         }
 
         managed.iter().copied().collect()
@@ -2340,9 +2189,8 @@ impl Governance {
         ListNeuronsResponse {
             neuron_infos: requested_list()
                 .filter_map(|x| {
-                    self.proto
-                        .neurons
-                        .get(x)
+                    self.neuron_store
+                        .get(*x)
                         .map(|y| (*x, y.get_neuron_info(now)))
                 })
                 .collect(),
@@ -2352,43 +2200,23 @@ impl Governance {
         }
     }
 
-    /// Returns a neuron, given a subaccount.
-    ///
-    /// Currently we just do linear search on the neurons. We tried an index at
-    /// some point, but the index was too big, took too long to build and
-    /// ultimately lowered our max possible number of neurons, so we
-    /// "downgraded" to linear search.
-    ///
-    /// Consider changing this if getting a neuron by subaccount ever gets in a
-    /// hot path.
-    pub fn get_neuron_by_subaccount(&self, subaccount: &Subaccount) -> Option<&Neuron> {
-        self.proto.neurons.values().find(|&n| {
-            if let Ok(s) = &&Subaccount::try_from(&n.account[..]) {
-                return s == subaccount;
-            }
-            false
-        })
-    }
-
-    pub fn get_neuron_by_subaccount_mut(&mut self, subaccount: &Subaccount) -> Option<&mut Neuron> {
-        self.proto.neurons.values_mut().find(|n| {
-            if let Ok(s) = &&Subaccount::try_from(&n.account[..]) {
-                return s == subaccount;
-            }
-            false
-        })
+    /// Returns a copy of the neuron with the given subaccount.
+    pub fn get_neuron_by_subaccount(&self, subaccount: &Subaccount) -> Option<Neuron> {
+        self.neuron_store
+            .neuron_id_by_subaccount(subaccount)
+            .and_then(|id| self.neuron_store.get(id))
     }
 
     /// Returns a list of known neurons, neurons that have been given a name.
     pub fn list_known_neurons(&self) -> ListKnownNeuronsResponse {
         let known_neurons: Vec<KnownNeuron> = self
-            .proto
-            .neurons
-            .iter()
-            .filter(|(_id, neuron)| neuron.known_neuron_data.is_some())
-            .map(|(id, neuron)| KnownNeuron {
-                id: Some(NeuronId { id: *id }),
-                known_neuron_data: neuron.known_neuron_data.clone(),
+            .neuron_store
+            .known_neuron_ids()
+            .into_iter()
+            .filter_map(|id| self.neuron_store.get(id))
+            .map(|neuron| KnownNeuron {
+                id: neuron.id.clone(),
+                known_neuron_data: neuron.known_neuron_data,
             })
             .collect();
         ListKnownNeuronsResponse { known_neurons }
@@ -2397,7 +2225,7 @@ impl Governance {
     /// Claim the neurons supplied by the GTC on behalf of `new_controller`
     ///
     /// For each neuron ID in `neuron_ids`, check that the corresponding neuron
-    /// exists in `self.proto.neurons` and the neuron's controller is the GTC.
+    /// exists in `self.neuron_store` and the neuron's controller is the GTC.
     /// If the neuron is in the expected state, set the neuron's controller to
     /// `new_controller` and set other fields (e.g.
     /// `created_timestamp_seconds`).
@@ -2412,7 +2240,7 @@ impl Governance {
         }
 
         let ids_are_valid = neuron_ids.iter().all(|id| {
-            if let Some(neuron) = self.proto.neurons.get(&id.id) {
+            if let Some(neuron) = self.neuron_store.get(id.id) {
                 neuron.controller.as_ref() == Some(GENESIS_TOKEN_CANISTER_ID.get_ref())
             } else {
                 false
@@ -2428,21 +2256,10 @@ impl Governance {
         }
 
         for neuron_id in neuron_ids {
-            let neuron = self.proto.neurons.get_mut(&neuron_id.id).unwrap();
-            let old_controller = neuron.controller.expect("Neuron must have a controller");
+            let mut neuron = self.neuron_store.get(neuron_id.id).unwrap();
             neuron.controller = Some(new_controller);
             neuron.created_timestamp_seconds = self.env.now();
-            GovernanceProto::remove_neuron_from_principal_in_principal_to_neuron_ids_index(
-                &mut self.principal_to_neuron_ids_index,
-                neuron_id.id,
-                &old_controller,
-            );
-
-            GovernanceProto::add_neuron_to_principal_in_principal_to_neuron_ids_index(
-                &mut self.principal_to_neuron_ids_index,
-                neuron_id.id,
-                &new_controller,
-            );
+            self.neuron_store.upsert(neuron)?;
         }
 
         Ok(())
@@ -2497,11 +2314,11 @@ impl Governance {
             )
             .await?;
 
-        let donor_neuron = donor_neuron.clone();
-        self.remove_neuron(donor_neuron_id.id, donor_neuron)?;
+        self.remove_neuron(donor_neuron_id.id)?;
 
-        let recipient_neuron = self.get_neuron_mut(recipient_neuron_id)?;
+        let mut recipient_neuron = self.get_neuron(recipient_neuron_id)?;
         recipient_neuron.cached_neuron_stake_e8s += transfer_amount_doms;
+        self.neuron_store.upsert(recipient_neuron)?;
         Ok(())
     }
 
@@ -2532,7 +2349,7 @@ impl Governance {
         disburse: &manage_neuron::Disburse,
     ) -> Result<u64, GovernanceError> {
         let transaction_fee_e8s = self.transaction_fee();
-        let neuron = self.proto.neurons.get_mut(&id.id).ok_or_else(|| {
+        let neuron = self.neuron_store.get(id.id).ok_or_else(|| {
             GovernanceError::new_with_message(
                 ErrorType::NotFound,
                 format!("Neuron not found in governance canister: {}", id.id),
@@ -2639,10 +2456,9 @@ impl Governance {
                 .await?;
         }
 
-        let neuron = self
-            .proto
-            .neurons
-            .get_mut(&id.id)
+        let mut neuron = self
+            .neuron_store
+            .get(id.id)
             .expect("Expected the parent neuron to exist");
 
         // Update the stake and the fees to reflect the burning above.
@@ -2652,6 +2468,7 @@ impl Governance {
             neuron.cached_neuron_stake_e8s = 0;
         }
         neuron.neuron_fees_e8s = 0;
+        self.neuron_store.upsert(neuron)?;
 
         // Transfer 2 - Disburse to the chosen account. This may fail if the
         // user told us to disburse more than they had in their account (but
@@ -2670,7 +2487,12 @@ impl Governance {
 
         let to_deduct = disburse_amount_e8s + transaction_fee_e8s;
         // The transfer was successful we can change the stake of the neuron.
+        let mut neuron = self
+            .neuron_store
+            .get(id.id)
+            .expect("Expected the parent neuron to exist");
         neuron.cached_neuron_stake_e8s = neuron.cached_neuron_stake_e8s.saturating_sub(to_deduct);
+        self.neuron_store.upsert(neuron)?;
 
         Ok(block_height)
     }
@@ -2713,9 +2535,9 @@ impl Governance {
 
         let transaction_fee_e8s = self.transaction_fee();
 
-        // Get the neuron and clone to appease the borrow checker.
-        // We'll get a mutable reference when we need to change it later.
-        let parent_neuron = self.get_neuron(id)?.clone();
+        // Get a copy of the neuron. We'll get it again when we need to change
+        // it later.
+        let parent_neuron = self.get_neuron(id)?;
 
         if parent_neuron.state(self.env.now()) == NeuronState::Spawning {
             return Err(GovernanceError::new_with_message(
@@ -2775,10 +2597,9 @@ impl Governance {
 
         // Make sure there isn't already a neuron with the same sub-account.
         if self
-            .proto
-            .neurons
-            .values()
-            .any(|n| n.account == to_subaccount.0)
+            .neuron_store
+            .neuron_id_by_subaccount(&to_subaccount)
+            .is_some()
         {
             return Err(GovernanceError::new_with_message(
                 ErrorType::PreconditionFailed,
@@ -2827,6 +2648,8 @@ impl Governance {
                 .joined_community_fund_timestamp_seconds,
             known_neuron_data: None,
             spawn_at_timestamp_seconds: None,
+            staked_maturity_e8s_equivalent: None,
+            auto_stake_maturity: parent_neuron.auto_stake_maturity,
         };
 
         // Add the child neuron to the set of neurons undergoing ledger updates.
@@ -2836,7 +2659,7 @@ impl Governance {
         // acquiring the lock. Indeed, in case there is already a pending
         // command, we return without state rollback. If we had already created
         // the embryo, it would not be garbage collected.
-        self.add_neuron(child_nid.id, child_neuron.clone())?;
+        self.add_neuron(child_nid.id, child_neuron)?;
 
         // Do the transfer.
 
@@ -2857,7 +2680,7 @@ impl Governance {
             // If we've got an error, we assume the transfer didn't happen for
             // some reason. The only state to cleanup is to delete the child
             // neuron, since we haven't mutated the parent yet.
-            self.remove_neuron(child_nid.id, child_neuron)?;
+            self.remove_neuron(child_nid.id)?;
             println!(
                 "Neuron stake transfer of split_neuron: {:?} \
                      failed with error: {:?}. Neuron can't be staked.",
@@ -2866,18 +2689,20 @@ impl Governance {
             return Err(error);
         }
 
-        // Get the neuron again, but this time a mutable reference.
+        // Get the neuron again, as it may have changed during the transfer.
         // Expect it to exist, since we acquired a lock above.
-        let parent_neuron = self.get_neuron_mut(id).expect("Neuron not found");
+        let mut parent_neuron = self.get_neuron(id).expect("Neuron not found");
 
         // Update the state of the parent and child neurons.
         parent_neuron.cached_neuron_stake_e8s -= split.amount_e8s;
+        self.neuron_store.upsert(parent_neuron)?;
 
-        let child_neuron = self
-            .get_neuron_mut(&child_nid)
+        let mut child_neuron = self
+            .get_neuron(&child_nid)
             .expect("Expected the child neuron to exist");
 
         child_neuron.cached_neuron_stake_e8s = staked_amount;
+        self.neuron_store.upsert(child_neuron)?;
         Ok(child_nid)
     }
 
@@ -2937,8 +2762,7 @@ impl Governance {
             ));
         }
 
-        // Get the neuron and clone to appease the borrow checker.
-        let target_neuron = self.get_neuron(id)?.clone();
+        let target_neuron = self.get_neuron(id)?;
         if !target_neuron.is_controlled_by(caller) {
             return Err(GovernanceError::new_with_message(
                 ErrorType::NotAuthorized,
@@ -2946,7 +2770,7 @@ impl Governance {
             ));
         }
 
-        let source_neuron = self.get_neuron(source_id)?.clone();
+        let source_neuron = self.get_neuron(source_id)?;
         if !source_neuron.is_controlled_by(caller) {
             return Err(GovernanceError::new_with_message(
                 ErrorType::NotAuthorized,
@@ -3061,8 +2885,8 @@ impl Governance {
             subtract_fees = true;
         }
 
-        let source_neuron_mut = self
-            .get_neuron_mut(source_id)
+        let mut source_neuron_mut = self
+            .get_neuron(source_id)
             .expect("Expected the source neuron to exist");
 
         if subtract_fees {
@@ -3125,6 +2949,7 @@ impl Governance {
             if source_neuron_mut.aging_since_timestamp_seconds != u64::MAX {
                 source_neuron_mut.aging_since_timestamp_seconds = now;
             }
+            self.neuron_store.upsert(source_neuron_mut)?;
 
             let _block_height: u64 = self
                 .ledger
@@ -3137,29 +2962,36 @@ impl Governance {
                 )
                 .await
                 .map_err(|err| {
-                    let source_neuron_mut = self
-                        .proto
-                        .neurons
-                        .get_mut(&source_id.id)
+                    let mut source_neuron_mut = self
+                        .neuron_store
+                        .get(source_id.id)
                         .expect("Expected the source neuron to exist");
                     source_neuron_mut.cached_neuron_stake_e8s += source_stake_e8s;
                     source_neuron_mut.aging_since_timestamp_seconds = source_age_timestamp_seconds;
+                    upsert_neuron_or_log(&mut self.neuron_store, source_neuron_mut);
                     err
                 })?;
+        } else {
+            self.neuron_store.upsert(source_neuron_mut)?;
         }
 
         // Lookup the neuron again, since it may have changed since the
         // (potential) call to the Ledger canister above.
-        let source_neuron_mut = self
-            .get_neuron_mut(source_id)
+        let mut source_neuron_mut = self
+            .get_neuron(source_id)
             .expect("Expected the source neuron to exist");
 
-        // Set source maturity to zero
+        // Set source maturity and staked maturity to zero
         let source_maturity = source_neuron_mut.maturity_e8s_equivalent;
         source_neuron_mut.maturity_e8s_equivalent = 0;
+        let source_staked_maturity = source_neuron_mut
+            .staked_maturity_e8s_equivalent
+            .take()
+            .unwrap_or(0);
+        self.neuron_store.upsert(source_neuron_mut)?;
 
         let mut target_neuron_mut = self
-            .get_neuron_mut(id)
+            .get_neuron(id)
             .expect("Expected the target neuron to exist");
 
         let target_dissolve_delay = target_neuron_mut.dissolve_delay_seconds(now);
@@ -3195,8 +3027,17 @@ impl Governance {
         target_neuron_mut.cached_neuron_stake_e8s = new_stake_e8s;
         target_neuron_mut.aging_since_timestamp_seconds = now.saturating_sub(new_age_seconds);
 
-        // Move maturity from source neuron to target
+        // Move maturity and staked maturity from source neuron to target
         target_neuron_mut.maturity_e8s_equivalent += source_maturity;
+        if source_staked_maturity > 0 {
+            target_neuron_mut.staked_maturity_e8s_equivalent = Some(
+                target_neuron_mut
+                    .staked_maturity_e8s_equivalent
+                    .unwrap_or(0)
+                    .saturating_add(source_staked_maturity),
+            );
+        }
+        self.neuron_store.upsert(target_neuron_mut)?;
 
         println!(
             "{}Merged neuron {} into {} at {:?}",
@@ -3228,7 +3069,7 @@ impl Governance {
         // New neurons are not allowed when the heap is too large.
         self.check_heap_can_grow()?;
 
-        let parent_neuron = self.get_neuron(id)?.clone();
+        let parent_neuron = self.get_neuron(id)?;
 
         if parent_neuron.state(self.env.now()) == NeuronState::Spawning {
             return Err(GovernanceError::new_with_message(
@@ -3295,10 +3136,9 @@ impl Governance {
 
         // Make sure there isn't already a neuron with the same sub-account.
         if self
-            .proto
-            .neurons
-            .values()
-            .any(|n| n.account == to_subaccount.0)
+            .neuron_store
+            .neuron_id_by_subaccount(&to_subaccount)
+            .is_some()
         {
             return Err(GovernanceError::new_with_message(
                 ErrorType::PreconditionFailed,
@@ -3334,15 +3174,18 @@ impl Governance {
             // considered part of the community fund.
             joined_community_fund_timestamp_seconds: None,
             known_neuron_data: None,
+            staked_maturity_e8s_equivalent: None,
+            auto_stake_maturity: None,
         };
 
         self.add_neuron(child_nid.id, child_neuron)?;
 
-        // Get the neurons again, but this time mutable references.
-        let parent_neuron = self.get_neuron_mut(id).expect("Neuron not found");
+        // Get the parent neuron again, this time to change it.
+        let mut parent_neuron = self.get_neuron(id).expect("Neuron not found");
 
         // Reset the parent's maturity.
         parent_neuron.maturity_e8s_equivalent -= maturity_to_spawn;
+        self.neuron_store.upsert(parent_neuron)?;
 
         Ok(child_nid)
     }
@@ -3365,7 +3208,7 @@ impl Governance {
         caller: &PrincipalId,
         merge_maturity: &manage_neuron::MergeMaturity,
     ) -> Result<MergeMaturityResponse, GovernanceError> {
-        let neuron = self.get_neuron(id)?.clone();
+        let neuron = self.get_neuron(id)?;
 
         if neuron.state(self.env.now()) == NeuronState::Spawning {
             return Err(GovernanceError::new_with_message(
@@ -3444,9 +3287,7 @@ impl Governance {
             .await?;
 
         // Adjust the maturity, stake and age of the neuron
-        let neuron = self
-            .get_neuron_mut(nid)
-            .expect("Expected the neuron to exist");
+        let mut neuron = self.get_neuron(nid).expect("Expected the neuron to exist");
 
         neuron.maturity_e8s_equivalent = neuron
             .maturity_e8s_equivalent
@@ -3456,6 +3297,7 @@ impl Governance {
             .saturating_add(maturity_to_merge);
         neuron.update_stake(new_stake, now);
         let new_stake_e8s = neuron.cached_neuron_stake_e8s;
+        self.neuron_store.upsert(neuron)?;
 
         Ok(MergeMaturityResponse {
             merged_maturity_e8s: maturity_to_merge,
@@ -3463,6 +3305,76 @@ impl Governance {
         })
     }
 
+    /// Stakes the maturity of a neuron.
+    ///
+    /// This method allows a neuron controller to stake the currently
+    /// existing maturity of a neuron. The caller can choose a percentage
+    /// of maturity to stake, which defaults to all of it. Unlike merging
+    /// maturity, staking maturity does not mint any ICP: the staked
+    /// maturity counts towards the voting power of the neuron and is
+    /// moved back to the neuron's maturity once the neuron is dissolved.
+    ///
+    /// Pre-conditions:
+    /// - The neuron is controlled by `caller`
+    /// - The neuron is not in spawning state.
+    /// - The neuron is not undergoing ledger updates.
+    /// - The percentage to stake is between 1 and 100 (inclusive).
+    pub fn stake_maturity_of_neuron(
+        &mut self,
+        id: &NeuronId,
+        caller: &PrincipalId,
+        stake_maturity: &manage_neuron::StakeMaturity,
+    ) -> Result<StakeMaturityResponse, GovernanceError> {
+        let mut neuron = self.get_neuron(id)?;
+
+        if neuron.state(self.env.now()) == NeuronState::Spawning {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                "Can't perform operation on neuron: Neuron is spawning.",
+            ));
+        }
+
+        if !neuron.is_controlled_by(caller) {
+            return Err(GovernanceError::new(ErrorType::NotAuthorized));
+        }
+
+        // The maturity must not change while the neuron is, e.g., merging
+        // or spawning maturity.
+        if self.proto.in_flight_commands.contains_key(&id.id) {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::LedgerUpdateOngoing,
+                "Neuron has an ongoing ledger update.",
+            ));
+        }
+
+        let percentage_to_stake = stake_maturity.percentage_to_stake.unwrap_or(100);
+        if percentage_to_stake > 100 || percentage_to_stake == 0 {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                "The percentage of maturity to stake must be a value between 0 (exclusive) and 100 (inclusive)."));
+        }
+
+        // Compute in u128 so that the multiplication cannot overflow.
+        let maturity_to_stake =
+            (neuron.maturity_e8s_equivalent as u128 * percentage_to_stake as u128 / 100) as u64;
+
+        neuron.maturity_e8s_equivalent = neuron
+            .maturity_e8s_equivalent
+            .saturating_sub(maturity_to_stake);
+        let staked_maturity_e8s = neuron
+            .staked_maturity_e8s_equivalent
+            .unwrap_or(0)
+            .saturating_add(maturity_to_stake);
+        neuron.staked_maturity_e8s_equivalent = Some(staked_maturity_e8s);
+        let maturity_e8s = neuron.maturity_e8s_equivalent;
+        self.neuron_store.upsert(neuron)?;
+
+        Ok(StakeMaturityResponse {
+            maturity_e8s,
+            staked_maturity_e8s,
+        })
+    }
+
     /// Disburse part of the stake of a neuron into a new neuron, possibly
     /// owned by someone else and with a different dissolve delay.
     ///
@@ -3501,7 +3413,7 @@ impl Governance {
         let creation_timestamp_seconds = self.env.now();
         let transaction_fee_e8s = self.transaction_fee();
 
-        let parent_neuron = self.get_neuron(id)?.clone();
+        let parent_neuron = self.get_neuron(id)?;
         let parent_nid = parent_neuron.id.as_ref().expect("Neurons must have an id");
 
         if parent_neuron.state(self.env.now()) == NeuronState::Spawning {
@@ -3617,10 +3529,9 @@ impl Governance {
 
         // Make sure there isn't already a neuron with the same sub-account.
         if self
-            .proto
-            .neurons
-            .values()
-            .any(|n| n.account == to_subaccount.0)
+            .neuron_store
+            .neuron_id_by_subaccount(&to_subaccount)
+            .is_some()
         {
             return Err(GovernanceError::new_with_message(
                 ErrorType::PreconditionFailed,
@@ -3669,9 +3580,11 @@ impl Governance {
             joined_community_fund_timestamp_seconds: None,
             known_neuron_data: None,
             spawn_at_timestamp_seconds: None,
+            staked_maturity_e8s_equivalent: None,
+            auto_stake_maturity: None,
         };
 
-        self.add_neuron(child_nid.id, child_neuron.clone())?;
+        self.add_neuron(child_nid.id, child_neuron)?;
 
         // Add the child neuron to the set of neurons undergoing ledger updates.
         let _child_lock = self.lock_neuron_for_command(child_nid.id, in_flight_command.clone())?;
@@ -3697,7 +3610,7 @@ impl Governance {
             // If we've got an error, we assume the transfer didn't happen for
             // some reason. The only state to cleanup is to delete the child
             // neuron, since we haven't mutated the parent yet.
-            self.remove_neuron(child_nid.id, child_neuron)?;
+            self.remove_neuron(child_nid.id)?;
             println!(
                 "Neuron minting transfer of to neuron: {:?}\
                                   failed with error: {:?}. Neuron can't be staked.",
@@ -3706,17 +3619,19 @@ impl Governance {
             return Err(error);
         }
 
-        // Get the neurons again, but this time mutable references.
-        let parent_neuron = self.get_neuron_mut(id).expect("Neuron not found");
+        // Get the neurons again, as they may have changed during the transfer.
+        let mut parent_neuron = self.get_neuron(id).expect("Neuron not found");
 
         // Update the state of the parent and child neurons.
         parent_neuron.cached_neuron_stake_e8s -= disburse_to_neuron.amount_e8s;
+        self.neuron_store.upsert(parent_neuron)?;
 
-        let child_neuron = self
-            .get_neuron_mut(&child_nid)
+        let mut child_neuron = self
+            .get_neuron(&child_nid)
             .expect("Expected the child neuron to exist");
 
         child_neuron.cached_neuron_stake_e8s = staked_amount;
+        self.neuron_store.upsert(child_neuron)?;
         Ok(child_nid)
     }
 
@@ -3771,9 +3686,8 @@ impl Governance {
     /// neuron is accessible to any caller.
     pub fn get_neuron_info(&self, id: &NeuronId) -> Result<NeuronInfo, GovernanceError> {
         let neuron = self
-            .proto
-            .neurons
-            .get(&id.id)
+            .neuron_store
+            .get(id.id)
             .ok_or_else(|| GovernanceError::new(ErrorType::NotFound))?;
         let now = self.env.now();
         Ok(neuron.get_neuron_info(now))
@@ -3810,7 +3724,7 @@ impl Governance {
             let authorized = &mut false;
             if let Some(followees) = neuron.neuron_managers() {
                 for f in followees.iter() {
-                    if let Some(f_neuron) = self.proto.neurons.get(&f.id) {
+                    if let Some(f_neuron) = self.neuron_store.get(f.id) {
                         if f_neuron.is_authorized_to_vote(caller) {
                             *authorized = true;
                             break;
@@ -3822,7 +3736,7 @@ impl Governance {
                 return Err(GovernanceError::new(ErrorType::NotAuthorized));
             }
         }
-        Ok(neuron.clone())
+        Ok(neuron)
     }
 
     /// Returns the complete neuron data for a given neuron `id` after
@@ -3866,13 +3780,9 @@ impl Governance {
         match proposal_data {
             None => None,
             Some(pd) => {
-                let empty = HashSet::<u64>::new();
-                let caller_neurons: &HashSet<u64> = self
-                    .principal_to_neuron_ids_index
-                    .get(caller)
-                    .unwrap_or(&empty);
+                let caller_neurons = self.neuron_store.neuron_ids_by_principal(caller);
                 let now = self.env.now();
-                Some(self.proposal_data_to_info(pd, caller_neurons, now, false))
+                Some(self.proposal_data_to_info(pd, &caller_neurons, now, false))
             }
         }
     }
@@ -3888,14 +3798,10 @@ impl Governance {
     /// retrieve dropped payloads by calling `get_proposal_info` for
     /// each proposal of interest.
     pub fn get_pending_proposals(&self, caller: &PrincipalId) -> Vec<ProposalInfo> {
-        let empty = HashSet::<u64>::new();
-        let caller_neurons: &HashSet<u64> = self
-            .principal_to_neuron_ids_index
-            .get(caller)
-            .unwrap_or(&empty);
+        let caller_neurons = self.neuron_store.neuron_ids_by_principal(caller);
         let now = self.env.now();
         self.get_pending_proposals_data()
-            .map(|data| self.proposal_data_to_info(data, caller_neurons, now, true))
+            .map(|data| self.proposal_data_to_info(data, &caller_neurons, now, true))
             .collect()
     }
 
@@ -3977,6 +3883,7 @@ impl Governance {
             deadline_timestamp_seconds: Some(
                 data.get_deadline_timestamp_seconds(voting_period_seconds),
             ),
+            payload_text_rendering: data.payload_text_rendering.clone(),
         }
     }
 
@@ -3993,7 +3900,7 @@ impl Governance {
             if let Some(mgr_ids) = self
                 .find_neuron(managed_id)
                 .ok()
-                .and_then(|x| x.neuron_managers())
+                .and_then(|x| x.neuron_managers().cloned())
             {
                 // Find one ID in the list of manager IDs that is also
                 // in 'caller_neurons'.
@@ -4050,11 +3957,8 @@ impl Governance {
         caller: &PrincipalId,
         req: &ListProposalInfo,
     ) -> ListProposalInfoResponse {
-        let empty = HashSet::<u64>::new();
-        let caller_neurons: &HashSet<u64> = self
-            .principal_to_neuron_ids_index
-            .get(caller)
-            .unwrap_or(&empty);
+        let caller_neurons = self.neuron_store.neuron_ids_by_principal(caller);
+        let caller_neurons = &caller_neurons;
         let exclude_topic: HashSet<i32> = req.exclude_topic.iter().cloned().collect();
         let include_reward_status: HashSet<i32> =
             req.include_reward_status.iter().cloned().collect();
@@ -4166,9 +4070,10 @@ impl Governance {
                         .unwrap_or(false)
                     {
                         if let Some(nid) = &p.proposer {
-                            if let Some(neuron) = self.proto.neurons.get_mut(&nid.id) {
+                            if let Some(mut neuron) = self.neuron_store.get(nid.id) {
                                 if neuron.neuron_fees_e8s >= p.reject_cost_e8s {
                                     neuron.neuron_fees_e8s -= p.reject_cost_e8s;
+                                    upsert_neuron_or_log(&mut self.neuron_store, neuron);
                                 }
                             }
                         }
@@ -4292,6 +4197,8 @@ impl Governance {
                     joined_community_fund_timestamp_seconds: None,
                     known_neuron_data: None,
                     spawn_at_timestamp_seconds: None,
+                    staked_maturity_e8s_equivalent: None,
+                    auto_stake_maturity: None,
                 };
                 self.add_neuron(nid.id, neuron)
             }
@@ -4465,8 +4372,7 @@ impl Governance {
                         if let Some(controller) = self
                             .find_neuron(managed_neuron_id)
                             .ok()
-                            .and_then(|x| x.controller.as_ref())
-                            .copied()
+                            .and_then(|x| x.controller)
                         {
                             let result = self.manage_neuron(&controller, &mgmt).await;
                             match result.command {
@@ -4644,9 +4550,7 @@ impl Governance {
                 self.reward_node_provider(pid, reward).await;
             }
             proposal::Action::SetDefaultFollowees(ref proposal) => {
-                let validate_result = self
-                    .proto
-                    .validate_default_followees(&proposal.default_followees);
+                let validate_result = self.validate_default_followees(&proposal.default_followees);
                 if validate_result.is_err() {
                     self.set_proposal_execution_status(pid, validate_result);
                     return;
@@ -4668,6 +4572,205 @@ impl Governance {
                 self.set_sns_token_swap_open_time_window(pid, set_sns_token_swap_open_time_window)
                     .await
             }
+            proposal::Action::OpenSnsTokenSwap(ref open_sns_token_swap) => {
+                self.open_sns_token_swap(pid, open_sns_token_swap).await
+            }
+        }
+    }
+
+    /// Executes the action in OpenSnsTokenSwap proposals. I.e. draws the
+    /// community fund contribution from the maturity of community fund
+    /// neurons and calls the open Candid method on the target swap canister.
+    ///
+    /// The drawn maturity is recorded in the proposal, so that it can be
+    /// either minted or given back to the neurons once the swap settles (see
+    /// settle_community_fund_participation). If the swap canister rejects the
+    /// call, the maturity is given back right away.
+    async fn open_sns_token_swap(
+        &mut self,
+        proposal_id: u64,
+        open_sns_token_swap: &OpenSnsTokenSwap,
+    ) {
+        // Unpack arguments. As in set_sns_token_swap_open_time_window, the
+        // fields were found to be populated when the proposal was validated.
+        let swap_canister_id = open_sns_token_swap.target_swap_canister_id();
+        let open_time_window = open_sns_token_swap.open_time_window.clone();
+
+        let cf_participants = draw_maturity_from_community_fund(
+            &mut self.neuron_store,
+            open_sns_token_swap
+                .community_fund_investment_e8s
+                .unwrap_or_default(),
+        );
+        match self.proto.proposals.get_mut(&proposal_id) {
+            Some(proposal_data) => proposal_data.cf_participants = cf_participants.clone(),
+            None => {
+                refund_maturity_to_community_fund(&mut self.neuron_store, &cf_participants);
+                println!(
+                    "{LOG_PREFIX}ERROR: Proposal {proposal_id} not found while opening an SNS token swap."
+                );
+                return;
+            }
+        }
+
+        let request = sns_swap_pb::OpenRequest {
+            open_time_window,
+            cf_participants,
+            open_sns_token_swap_proposal_id: Some(proposal_id),
+        };
+
+        // Call the swap canister.
+        let result = self
+            .env
+            .call_canister_method(
+                swap_canister_id,
+                "open",
+                Encode!(&request).expect("Candid encoding open argument failed."),
+            )
+            .await;
+
+        // Convert result.
+        let result = result
+            .map(
+                |response| match Decode!(&response, sns_swap_pb::OpenResponse) {
+                    Ok(response) => println!("{LOG_PREFIX}INFO: {response:#?}"),
+                    Err(err) => println!(
+                        "{LOG_PREFIX}ERROR: Unable to decode open response because of {err:#?}. \
+                     response bytes: {response:?}"
+                    ),
+                },
+            )
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Swap rejected the open method call: {err:#?}"),
+                )
+            });
+
+        // The swap did not take the contribution, give it back.
+        if result.is_err() {
+            if let Some(proposal_data) = self.proto.proposals.get_mut(&proposal_id) {
+                let cf_participants = std::mem::take(&mut proposal_data.cf_participants);
+                refund_maturity_to_community_fund(&mut self.neuron_store, &cf_participants);
+            }
+        }
+
+        // Finally, record the result.
+        self.set_proposal_execution_status(proposal_id, result);
+    }
+
+    /// Settles the community fund contribution drawn by an OpenSnsTokenSwap
+    /// proposal. Called by the swap canister once the swap is finalized.
+    ///
+    /// If the swap committed, the ICP drawn from the maturity of community
+    /// fund neurons is minted to the SNS governance canister. If it aborted,
+    /// the maturity is given back to the neurons.
+    ///
+    /// This is idempotent: once the participation has been settled, further
+    /// calls succeed without doing anything.
+    pub async fn settle_community_fund_participation(
+        &mut self,
+        caller: PrincipalId,
+        request: &sns_swap_pb::SettleCommunityFundParticipation,
+    ) -> Result<(), GovernanceError> {
+        let proposal_id = request.open_sns_token_swap_proposal_id.ok_or_else(|| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidCommand,
+                "The open_sns_token_swap_proposal_id field was not populated.",
+            )
+        })?;
+        let proposal_data = self.proto.proposals.get(&proposal_id).ok_or_else(|| {
+            GovernanceError::new_with_message(
+                ErrorType::NotFound,
+                format!("Proposal {} not found.", proposal_id),
+            )
+        })?;
+        let target_swap_canister_id = match proposal_data
+            .proposal
+            .as_ref()
+            .and_then(|proposal| proposal.action.as_ref())
+        {
+            Some(proposal::Action::OpenSnsTokenSwap(action)) => action.target_swap_canister_id,
+            _ => {
+                return Err(GovernanceError::new_with_message(
+                    ErrorType::InvalidCommand,
+                    format!(
+                        "Proposal {} is not an OpenSnsTokenSwap proposal.",
+                        proposal_id
+                    ),
+                ))
+            }
+        };
+        if target_swap_canister_id != Some(caller) {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::NotAuthorized,
+                format!(
+                    "Caller {} is not the target swap canister of proposal {}.",
+                    caller, proposal_id
+                ),
+            ));
+        }
+        if proposal_data.cf_participants.is_empty() {
+            // Already settled (or nothing was drawn).
+            return Ok(());
+        }
+
+        use sns_swap_pb::settle_community_fund_participation::Result as SettleResult;
+        match &request.result {
+            Some(SettleResult::Committed(committed)) => {
+                let sns_governance_canister_id =
+                    committed.sns_governance_canister_id.ok_or_else(|| {
+                        GovernanceError::new_with_message(
+                            ErrorType::InvalidCommand,
+                            "The sns_governance_canister_id field was not populated.",
+                        )
+                    })?;
+                let amount_e8s =
+                    total_community_fund_participation_e8s(&proposal_data.cf_participants);
+                // Clear the participants before minting, so that a concurrent
+                // call cannot mint twice. They are restored if minting fails.
+                let cf_participants = std::mem::take(
+                    &mut self
+                        .proto
+                        .proposals
+                        .get_mut(&proposal_id)
+                        .expect("Proposal disappeared.")
+                        .cf_participants,
+                );
+                let result = self
+                    .ledger
+                    .transfer_funds(
+                        amount_e8s,
+                        0, // Minting transfers don't pay transaction fees.
+                        None,
+                        AccountIdentifier::new(sns_governance_canister_id, None),
+                        self.env.now(),
+                    )
+                    .await;
+                if let Err(err) = result {
+                    if let Some(proposal_data) = self.proto.proposals.get_mut(&proposal_id) {
+                        proposal_data.cf_participants = cf_participants;
+                    }
+                    return Err(GovernanceError::from(err));
+                }
+                Ok(())
+            }
+            Some(SettleResult::Aborted(_)) => {
+                let cf_participants = std::mem::take(
+                    &mut self
+                        .proto
+                        .proposals
+                        .get_mut(&proposal_id)
+                        .expect("Proposal disappeared.")
+                        .cf_participants,
+                );
+                refund_maturity_to_community_fund(&mut self.neuron_store, &cf_participants);
+                Ok(())
+            }
+            None => Err(GovernanceError::new_with_message(
+                ErrorType::InvalidCommand,
+                "The result field was not populated.",
+            )),
         }
     }
 
@@ -4726,9 +4829,10 @@ impl Governance {
 
         for principal in principal_set {
             for neuron_id in self.get_neuron_ids_by_principal(principal) {
-                if let Some(neuron) = self.proto.neurons.get_mut(&neuron_id) {
+                if let Some(mut neuron) = self.neuron_store.get(neuron_id) {
                     if neuron.controller.as_ref() == Some(principal) {
                         neuron.kyc_verified = true;
+                        upsert_neuron_or_log(&mut self.neuron_store, neuron);
                     }
                 }
             }
@@ -4754,7 +4858,7 @@ impl Governance {
         let neuron_management_fee_per_proposal_e8s =
             self.economics().neuron_management_fee_per_proposal_e8s;
         // Find the proposing neuron.
-        let proposer = self.proto.neurons.get(&proposer_id.id).ok_or_else(|| {
+        let proposer = self.neuron_store.get(proposer_id.id).ok_or_else(|| {
             GovernanceError::new_with_message(
                 ErrorType::NotFound,
                 &format!("Proposer neuron not found: {}", proposer_id.id),
@@ -4910,8 +5014,9 @@ impl Governance {
         };
 
         // Charge fee.
-        if let Some(proposer_mut) = self.proto.neurons.get_mut(&proposer_id.id) {
-            proposer_mut.neuron_fees_e8s += neuron_management_fee_per_proposal_e8s
+        if let Some(mut proposer_mut) = self.neuron_store.get(proposer_id.id) {
+            proposer_mut.neuron_fees_e8s += neuron_management_fee_per_proposal_e8s;
+            self.neuron_store.upsert(proposer_mut)?;
         }
 
         // Add this proposal as an open proposal.
@@ -5073,6 +5178,8 @@ impl Governance {
             &proposal.action
         {
             return action.validate(self.env.now());
+        } else if let Some(proposal::Action::OpenSnsTokenSwap(action)) = &proposal.action {
+            return action.validate(self.env.now());
         } else if proposal.topic() == Topic::Unspecified {
             "The topic of the proposal is unspecified.".to_string()
         } else {
@@ -5097,6 +5204,25 @@ impl Governance {
         // Validate proposal
         self.validate_proposal(proposal)?;
 
+        // Decode the payload of an ExecuteNnsFunction proposal, so that
+        // neuron holders can see what they are voting on. Proposals whose
+        // payload cannot be decoded are rejected.
+        let payload_text_rendering = match &proposal.action {
+            Some(proposal::Action::ExecuteNnsFunction(update)) => {
+                let nns_function =
+                    NnsFunction::from_i32(update.nns_function).unwrap_or(NnsFunction::Unspecified);
+                let rendering = render_nns_function_payload(nns_function, &update.payload)
+                    .map_err(|e| {
+                        GovernanceError::new_with_message(
+                            ErrorType::InvalidProposal,
+                            &format!("Invalid payload for {:?}: {}", nns_function, e),
+                        )
+                    })?;
+                Some(rendering)
+            }
+            _ => None,
+        };
+
         if let Some(proposal::Action::ManageNeuron(m)) = &proposal.action {
             assert_eq!(topic, Topic::NeuronManagement);
             return self.make_manage_neuron_proposal(
@@ -5114,7 +5240,7 @@ impl Governance {
         // electoral roll.
         //
         // Find the proposing neuron.
-        let proposer = self.proto.neurons.get(&proposer_id.id).ok_or_else(|| {
+        let proposer = self.neuron_store.get(proposer_id.id).ok_or_else(|| {
             GovernanceError::new_with_message(
                 ErrorType::NotFound,
                 &format!("Proposer neuron not found: {}", proposer_id.id),
@@ -5192,7 +5318,7 @@ impl Governance {
         );
         let mut electoral_roll = HashMap::<u64, Ballot>::new();
         let mut total_power: u128 = 0;
-        for (k, v) in self.proto.neurons.iter() {
+        for v in self.neuron_store.values() {
             // If this neuron is eligible to vote, record its
             // voting power at the time of making the
             // proposal.
@@ -5205,7 +5331,7 @@ impl Governance {
             let power = v.voting_power(now_seconds);
             total_power += power as u128;
             electoral_roll.insert(
-                *k,
+                v.id.as_ref().expect("Neuron must have an id").id,
                 Ballot {
                     vote: Vote::Unspecified as i32,
                     voting_power: power,
@@ -5240,6 +5366,7 @@ impl Governance {
             proposal: Some(proposal.clone()),
             proposal_timestamp_seconds: now_seconds,
             ballots: electoral_roll,
+            payload_text_rendering,
             ..Default::default()
         };
 
@@ -5253,11 +5380,12 @@ impl Governance {
         // - It prevents a neuron from having too many proposals outstanding.
         // - It reduces the voting power of the submitter so that for every proposal
         //   outstanding the submitter will have less voting power to get it approved.
-        self.proto
-            .neurons
-            .get_mut(&proposer_id.id)
-            .expect("Proposer not found.")
-            .neuron_fees_e8s += info.reject_cost_e8s;
+        let mut proposer = self
+            .neuron_store
+            .get(proposer_id.id)
+            .expect("Proposer not found.");
+        proposer.neuron_fees_e8s += info.reject_cost_e8s;
+        self.neuron_store.upsert(proposer)?;
 
         // Cast self-vote, including following.
         Governance::cast_vote_and_cascade_follow(
@@ -5266,8 +5394,7 @@ impl Governance {
             proposer_id,
             Vote::Yes,
             topic,
-            &self.topic_followee_index,
-            &mut self.proto.neurons,
+            &mut self.neuron_store,
         );
         // Finally, add this proposal as an open proposal.
         self.insert_proposal(proposal_num, info);
@@ -5278,17 +5405,16 @@ impl Governance {
     // Register `voting_neuron_id` voting according to
     // `vote_of_neuron` (which must be `yes` or `no`) in 'ballots' and
     // cascade voting according to the following relationships
-    // specified in 'followee_index' (mapping followees to followers for
-    // the topic) and 'neurons' (which contains a mapping of followers
-    // to followees).
+    // specified in the followees index of 'neuron_store' (mapping
+    // followees to followers for the topic) and the neurons themselves
+    // (which contain a mapping of followers to followees).
     fn cast_vote_and_cascade_follow(
         proposal_id: &ProposalId,
         ballots: &mut HashMap<u64, Ballot>,
         voting_neuron_id: &NeuronId,
         vote_of_neuron: Vote,
         topic: Topic,
-        topic_followee_index: &BTreeMap<Topic, BTreeMap<u64, BTreeSet<u64>>>,
-        neurons: &mut HashMap<u64, Neuron>,
+        neuron_store: &mut NeuronStore,
     ) {
         assert!(topic != Topic::NeuronManagement && topic != Topic::Unspecified);
         // This is the induction variable of the loop: a map from
@@ -5296,8 +5422,6 @@ impl Governance {
         // values not allowed).
         let mut induction_votes = BTreeMap::new();
         induction_votes.insert(voting_neuron_id.id, vote_of_neuron);
-        let topic_cache = topic_followee_index.get(&topic);
-        let unspecified_cache = topic_followee_index.get(&Topic::Unspecified);
         loop {
             // First, we cast the specified votes (in the first round,
             // this will be a single vote) and collect all neurons
@@ -5309,7 +5433,7 @@ impl Governance {
                 if let Some(k_ballot) = ballots.get_mut(k) {
                     // Neuron with ID k is eligible to vote.
                     if k_ballot.vote == (Vote::Unspecified as i32) {
-                        if let Some(k_neuron) = neurons.get_mut(k) {
+                        if let Some(mut k_neuron) = neuron_store.get(*k) {
                             // Only update a vote if it was previously
                             // unspecified. Following can trigger votes
                             // for neurons that have already voted
@@ -5318,22 +5442,18 @@ impl Governance {
                             // Register the neuron's ballot in the
                             // neuron itself.
                             k_neuron.register_recent_ballot(topic, proposal_id, *v);
+                            upsert_neuron_or_log(neuron_store, k_neuron);
                             // Here k is the followee, i.e., the neuron
                             // that has just cast a vote that may be
                             // followed by other neurons.
                             //
                             // Insert followers from 'topic'
-                            if let Some(more_followers) = topic_cache.and_then(|x| x.get(k)) {
-                                all_followers.append(&mut more_followers.clone());
-                            }
+                            all_followers.extend(neuron_store.followers(topic, *k));
                             // Default following doesn't apply to governance proposals.
                             if topic != Topic::Governance {
                                 // Insert followers from 'Unspecified' (default followers)
-                                if let Some(more_followers) =
-                                    unspecified_cache.and_then(|x| x.get(k))
-                                {
-                                    all_followers.append(&mut more_followers.clone());
-                                }
+                                all_followers
+                                    .extend(neuron_store.followers(Topic::Unspecified, *k));
                             }
                         } else {
                             // The voting neuron not found in the
@@ -5354,7 +5474,7 @@ impl Governance {
             // new set now.
             induction_votes.clear();
             for f in all_followers.iter() {
-                if let Some(f_neuron) = neurons.get(f) {
+                if let Some(f_neuron) = neuron_store.get(*f) {
                     let f_vote = f_neuron.would_follow_ballots(topic, ballots);
                     if f_vote != Vote::Unspecified {
                         // f_vote is yes or no, i.e., f_neuron's
@@ -5413,7 +5533,7 @@ impl Governance {
         caller: &PrincipalId,
         pb: &manage_neuron::RegisterVote,
     ) -> Result<(), GovernanceError> {
-        let neuron = self.proto.neurons.get_mut(&neuron_id.id).ok_or_else(||
+        let neuron = self.neuron_store.get(neuron_id.id).ok_or_else(||
             // The specified neuron is not present.
             GovernanceError::new_with_message(ErrorType::NotFound, "Neuron not found"))?;
         // Check that the caller is authorized, i.e., either the
@@ -5464,8 +5584,7 @@ impl Governance {
                 neuron_id,
                 vote,
                 topic,
-                &self.topic_followee_index,
-                &mut self.proto.neurons,
+                &mut self.neuron_store,
             );
         }
 
@@ -5486,12 +5605,8 @@ impl Governance {
         caller: &PrincipalId,
         f: &manage_neuron::Follow,
     ) -> Result<(), GovernanceError> {
-        // The implementation of this method is complicated by the
-        // fact that we have to maintain a reverse index of all follow
-        // relationships, i.e., the `topic_followee_index`.
-
         // Find the neuron to modify.
-        let neuron = self.proto.neurons.get_mut(&id.id).ok_or_else(||
+        let mut neuron = self.neuron_store.get(id.id).ok_or_else(||
             // The specified neuron is not present.
             GovernanceError::new_with_message(ErrorType::NotFound, &format!("Leader neuron not found: {}", id.id)))?;
 
@@ -5523,50 +5638,19 @@ impl Governance {
                 "Too many followees.",
             ));
         }
-        // First, remove the current followees for this neuron and
-        // this topic from the follower cache.
-        if let Some(neuron_followees) = neuron.followees.get(&f.topic) {
-            if let Some(topic) = Topic::from_i32(f.topic) {
-                // If this topic is not represented in the
-                // follower cache, there cannot be anything to remove.
-                if let Some(followee_index) = self.topic_followee_index.get_mut(&topic) {
-                    // We need to remove this neuron as a follower
-                    // for all followees.
-                    for followee in &neuron_followees.followees {
-                        if let Some(all_followers) = followee_index.get_mut(&followee.id) {
-                            all_followers.remove(&id.id);
-                        }
-                        // Note: we don't check that the
-                        // topic_followee_index actually contains this
-                        // neuron's ID as a follower for all the
-                        // followees. This could be a warning, but
-                        // it is not actionable.
-                    }
-                }
-            }
-        }
         if !f.followees.is_empty() {
             // If this topic is valid, perform the operation.
-            if let Some(topic) = Topic::from_i32(f.topic) {
-                // Insert the new list of followees for this topic in
-                // the neuron, removing the old list, which has
-                // already been removed from the follower cache above.
+            if Topic::from_i32(f.topic).is_some() {
+                // Replace the list of followees for this topic in the
+                // neuron. Writing the neuron back updates the followees
+                // index.
                 neuron.followees.insert(
                     f.topic,
                     Followees {
                         followees: f.followees.clone(),
                     },
                 );
-                let cache = self
-                    .topic_followee_index
-                    .entry(topic)
-                    .or_insert_with(BTreeMap::new);
-                // We need to to add this neuron as a follower for
-                // all followees.
-                for followee in &f.followees {
-                    let all_followers = cache.entry(followee.id).or_insert_with(BTreeSet::new);
-                    all_followers.insert(id.id);
-                }
+                self.neuron_store.upsert(neuron)?;
                 Ok(())
             } else {
                 // Attempt to follow for an invalid topic: the set
@@ -5580,6 +5664,7 @@ impl Governance {
         } else {
             // This operation clears the followees for the given topic.
             neuron.followees.remove(&f.topic);
+            self.neuron_store.upsert(neuron)?;
             Ok(())
         }
     }
@@ -5598,33 +5683,11 @@ impl Governance {
         };
         let _lock = self.lock_neuron_for_command(id.id, lock_command)?;
 
-        if let Some(neuron) = self.proto.neurons.get_mut(&id.id) {
+        if let Some(mut neuron) = self.neuron_store.get(id.id) {
             neuron.configure(caller, now_seconds, c)?;
-
-            let op = c
-                .operation
-                .as_ref()
-                .expect("Configure must have an operation");
-
-            match op {
-                manage_neuron::configure::Operation::AddHotKey(k) => {
-                    let hot_key = k.new_hot_key.as_ref().expect("Must have a hot key");
-                    GovernanceProto::add_neuron_to_principal_in_principal_to_neuron_ids_index(
-                        &mut self.principal_to_neuron_ids_index,
-                        id.id,
-                        hot_key,
-                    );
-                }
-                manage_neuron::configure::Operation::RemoveHotKey(k) => {
-                    let hot_key = k.hot_key_to_remove.as_ref().expect("Must have a hot key");
-                    GovernanceProto::remove_neuron_from_principal_in_principal_to_neuron_ids_index(
-                        &mut self.principal_to_neuron_ids_index,
-                        id.id,
-                        hot_key,
-                    );
-                }
-                _ => (),
-            }
+            // Writing the neuron back also updates the principals index,
+            // e.g., when a hot key was added or removed.
+            self.neuron_store.upsert(neuron)?;
             Ok(())
         } else {
             Err(GovernanceError::new_with_message(
@@ -5727,7 +5790,7 @@ impl Governance {
                 ),
             ));
         }
-        let neuron = self.get_neuron_mut(&nid)?;
+        let mut neuron = self.get_neuron(&nid)?;
         match neuron.cached_neuron_stake_e8s.cmp(&balance.get_e8s()) {
             Ordering::Greater => {
                 println!(
@@ -5750,6 +5813,7 @@ impl Governance {
             // neuron id based on the memo and the controller).
             Ordering::Equal => (),
         };
+        self.neuron_store.upsert(neuron)?;
 
         Ok(nid)
     }
@@ -5802,10 +5866,12 @@ impl Governance {
             joined_community_fund_timestamp_seconds: None,
             known_neuron_data: None,
             spawn_at_timestamp_seconds: None,
+            staked_maturity_e8s_equivalent: None,
+            auto_stake_maturity: None,
         };
 
         // This also verifies that there are not too many neurons already.
-        self.add_neuron(nid.id, neuron.clone())?;
+        self.add_neuron(nid.id, neuron)?;
 
         let _neuron_lock = self.lock_neuron_for_command(
             nid.id,
@@ -5825,7 +5891,7 @@ impl Governance {
             // To prevent this method from creating non-staked
             // neurons, we must also remove the neuron that was
             // previously created.
-            self.remove_neuron(nid.id, neuron)?;
+            self.remove_neuron(nid.id)?;
             return Err(GovernanceError::new_with_message(
                 ErrorType::InsufficientFunds,
                 format!(
@@ -5838,10 +5904,11 @@ impl Governance {
         }
 
         // Ok, we are able to stake the neuron.
-        match self.get_neuron_mut(&nid) {
-            Ok(neuron) => {
+        match self.get_neuron(&nid) {
+            Ok(mut neuron) => {
                 // Adjust the stake.
                 neuron.update_stake(balance.get_e8s(), now);
+                self.neuron_store.upsert(neuron)?;
                 Ok(nid)
             }
             Err(err) => {
@@ -5906,7 +5973,10 @@ impl Governance {
                 ),
             ));
         }
-        if self.known_neuron_name_set.contains(&known_neuron_data.name) {
+        if self
+            .neuron_store
+            .contains_known_neuron_name(&known_neuron_data.name)
+        {
             return Err(GovernanceError::new_with_message(
                 ErrorType::PreconditionFailed,
                 format!(
@@ -5916,15 +5986,13 @@ impl Governance {
             ));
         }
 
-        let neuron = self.proto.neurons.get_mut(&neuron_id.id).ok_or_else(||
+        let mut neuron = self.neuron_store.get(neuron_id.id).ok_or_else(||
             // The specified neuron is not present.
             GovernanceError::new_with_message(ErrorType::NotFound, "Neuron not found"))?;
-        if let Some(KnownNeuronData { name: old_name, .. }) = &neuron.known_neuron_data {
-            self.known_neuron_name_set.remove(old_name);
-        }
+        // Writing the neuron back replaces its old name, if any, with the new
+        // one in the known neuron names index.
         neuron.known_neuron_data = Some(known_neuron_data.clone());
-        self.known_neuron_name_set
-            .insert(known_neuron_data.name.clone());
+        self.neuron_store.upsert(neuron)?;
 
         Ok(())
     }
@@ -6024,6 +6092,9 @@ impl Governance {
                 .merge_maturity_of_neuron(&id, caller, m)
                 .await
                 .map(ManageNeuronResponse::merge_maturity_response),
+            Some(manage_neuron::Command::StakeMaturity(s)) => self
+                .stake_maturity_of_neuron(&id, caller, s)
+                .map(ManageNeuronResponse::stake_maturity_response),
             Some(manage_neuron::Command::Split(s)) => self
                 .split_neuron(&id, caller, s)
                 .await
@@ -6116,6 +6187,11 @@ impl Governance {
     /// the last one. This is intended to be called by a cron
     /// process.
     pub async fn run_periodic_tasks(&mut self) {
+        if self.neuron_store.has_neurons_to_migrate() {
+            self.neuron_store
+                .migrate_pending_neurons(MAX_NEURONS_TO_MIGRATE_PER_CALL);
+        }
+
         self.process_proposals();
 
         // First try to mint node provider rewards (once per month).
@@ -6150,7 +6226,11 @@ impl Governance {
                 Ok(supply) => {
                     if self.should_compute_cached_metrics() {
                         let now = self.env.now();
-                        let metrics = self.proto.compute_cached_metrics(now, supply);
+                        let metrics = self.proto.compute_cached_metrics(
+                            self.neuron_store.values(),
+                            now,
+                            supply,
+                        );
                         self.proto.metrics = Some(metrics);
                     }
                 }
@@ -6168,9 +6248,41 @@ impl Governance {
             self.spawn_neurons().await;
         }
 
+        self.unstake_maturity_of_dissolved_neurons();
         self.maybe_gc();
     }
 
+    /// Moves the staked maturity of dissolved neurons back to their maturity,
+    /// from where it can be spawned or merged.
+    ///
+    /// The neurons are found through an index of the neurons with staked
+    /// maturity by the time they dissolve, and at most
+    /// `MAX_NEURONS_TO_UNSTAKE_MATURITY_PER_CALL` of them are processed per
+    /// call, so the cost does not depend on the total number of neurons.
+    fn unstake_maturity_of_dissolved_neurons(&mut self) {
+        let now_seconds = self.env.now();
+        let neuron_ids = self.neuron_store.dissolved_neuron_ids_with_staked_maturity(
+            now_seconds,
+            MAX_NEURONS_TO_UNSTAKE_MATURITY_PER_CALL,
+        );
+
+        for id in neuron_ids {
+            // Don't change the maturity while a ledger update is ongoing.
+            if self.proto.in_flight_commands.contains_key(&id) {
+                continue;
+            }
+            let mut neuron = match self.neuron_store.get(id) {
+                Some(neuron) if neuron.state(now_seconds) == NeuronState::Dissolved => neuron,
+                _ => continue,
+            };
+            let staked_maturity = neuron.staked_maturity_e8s_equivalent.take().unwrap_or(0);
+            neuron.maturity_e8s_equivalent = neuron
+                .maturity_e8s_equivalent
+                .saturating_add(staked_maturity);
+            upsert_neuron_or_log(&mut self.neuron_store, neuron);
+        }
+    }
+
     fn should_update_maturity_modulation(&self) -> bool {
         // Check if we're already updating the neuron maturity modulation.
         let now_seconds = self.env.now();
@@ -6241,10 +6353,8 @@ impl Governance {
         // Filter all the neurons that are currently in "spawning" state.
         // Do this here to avoid having to borrow *self while we perform changes below.
         let spawning_neurons = self
-            .proto
-            .neurons
+            .neuron_store
             .values()
-            .cloned()
             .filter(|n| n.state(now_seconds) == NeuronState::Spawning)
             .collect::<Vec<Neuron>>();
 
@@ -6281,7 +6391,7 @@ impl Governance {
                             LOG_PREFIX, neuron
                         );
 
-                        let mut neuron = self.get_neuron_mut(&id).unwrap();
+                        let mut neuron = self.get_neuron(&id).unwrap();
 
                         // Reset the neuron's maturity and set that it's spawning before we actually mint
                         // the stake. This is conservative to prevent a neuron having _both_ the stake and
@@ -6291,6 +6401,13 @@ impl Governance {
                         neuron.cached_neuron_stake_e8s = neuron_stake;
 
                         let neuron_clone = neuron.clone();
+                        if let Err(err) = self.neuron_store.upsert(neuron) {
+                            println!(
+                                "{}Cannot spawn neuron {}, as it cannot be written: {}",
+                                LOG_PREFIX, id.id, err
+                            );
+                            continue;
+                        }
 
                         // Do the transfer, this is a minting transfer, from the governance canister's
                         // (which is also the minting canister) main account into the neuron's
@@ -6426,7 +6543,7 @@ impl Governance {
         };
 
         for (neuron_id, used_voting_rights) in voters_to_used_voting_right {
-            match self.get_neuron_mut(&neuron_id) {
+            match self.get_neuron(&neuron_id) {
                 Ok(mut neuron) => {
                     // Note that "as" rounds toward zero; this is the desired
                     // behavior here. Also note that `total_voting_rights` has
@@ -6436,7 +6553,17 @@ impl Governance {
                     // positive (non-zero).
                     let reward = (used_voting_rights * distributed_e8s_equivalent_float
                         / total_voting_rights) as u64;
-                    neuron.maturity_e8s_equivalent += reward;
+                    if neuron.is_auto_stake_maturity_enabled() {
+                        neuron.staked_maturity_e8s_equivalent = Some(
+                            neuron
+                                .staked_maturity_e8s_equivalent
+                                .unwrap_or(0)
+                                .saturating_add(reward),
+                        );
+                    } else {
+                        neuron.maturity_e8s_equivalent += reward;
+                    }
+                    upsert_neuron_or_log(&mut self.neuron_store, neuron);
                     actually_distributed_e8s_equivalent += reward;
                 }
                 Err(e) => println!(
@@ -6700,6 +6827,173 @@ impl SetSnsTokenSwapOpenTimeWindow {
     }
 }
 
+impl OpenSnsTokenSwap {
+    /// Precondition: the target_swap_canister_id field must be populated
+    /// (i.e. be Some(_)). This is necessary for self to be considered valid
+    /// (see the validate and defects methods)
+    pub fn target_swap_canister_id(&self) -> CanisterId {
+        self.target_swap_canister_id
+            .expect("Invalid OpenSnsTokenSwap: target_swap_canister_id field not populated.")
+            .try_into()
+            .expect("Unable to convert target_swap_canister_id into a CanisterId")
+    }
+
+    /// Requirements:
+    ///
+    ///   1. All fields must be populated.
+    ///   2. target_swap_canister_id must convert to CanisterId.
+    ///   3. open_time_window must be valid. See SetOpenTimeWindowRequest::defects.
+    pub fn validate(&self, now_timestamp_seconds: u64) -> Result<(), GovernanceError> {
+        let defects = self.defects(now_timestamp_seconds);
+
+        if defects.is_empty() {
+            return Ok(());
+        }
+
+        Err(GovernanceError::new_with_message(
+            ErrorType::InvalidProposal,
+            format!(
+                "Invalid OpenSnsTokenSwap. Defect(s) were:\n  * {}",
+                defects.join("\n  * ")
+            ),
+        ))
+    }
+
+    /// An alternative to validate.
+    ///
+    /// Returns a list of strings, each describing a defect in self (i.e. reason
+    /// that validate should return Err).
+    pub fn defects(&self, now_timestamp_seconds: u64) -> Vec<String> {
+        let mut result = vec![];
+
+        match self.target_swap_canister_id {
+            None => result.push("The target_swap_canister_id field was not populated.".to_string()),
+            Some(id) => {
+                if let Err(err) = CanisterId::try_from(id) {
+                    result.push(format!(
+                        "Unable to parse target_swap_canister_id as a CanisterId: {err:#?}"
+                    ));
+                }
+            }
+        }
+
+        // The window has the same requirements as in SetSnsTokenSwapOpenTimeWindow.
+        let mut window_defects = sns_swap_pb::SetOpenTimeWindowRequest {
+            open_time_window: self.open_time_window,
+        }
+        .defects(now_timestamp_seconds);
+        result.append(&mut window_defects);
+
+        if self.community_fund_investment_e8s.is_none() {
+            result.push("The community_fund_investment_e8s field was not populated.".to_string());
+        }
+
+        result
+    }
+}
+
+/// Draws a total of (up to) `amount_e8s` from the maturity of community fund
+/// neurons, each neuron contributing in proportion to its maturity. If the
+/// community fund neurons hold less maturity than that, all of it is drawn.
+///
+/// Returns the contributions, grouped by controller.
+/// Writes back a neuron in a context where the error cannot be returned,
+/// logging it instead. Used for updates that do not grow the neuron (or grow
+/// it by a bounded amount, such as a recent ballot), which cannot fail.
+fn upsert_neuron_or_log(neuron_store: &mut NeuronStore, neuron: Neuron) {
+    let neuron_id = neuron.id.as_ref().map(|id| id.id);
+    if let Err(err) = neuron_store.upsert(neuron) {
+        println!(
+            "{}ERROR: Cannot write neuron {:?}: {}",
+            LOG_PREFIX, neuron_id, err
+        );
+    }
+}
+
+fn draw_maturity_from_community_fund(
+    neuron_store: &mut NeuronStore,
+    amount_e8s: u64,
+) -> Vec<sns_swap_pb::CfParticipant> {
+    let cf_neurons: Vec<Neuron> = neuron_store
+        .values()
+        .filter(|neuron| neuron.is_community_fund_neuron())
+        .collect();
+    let total_maturity_e8s: u64 = cf_neurons
+        .iter()
+        .map(|neuron| neuron.maturity_e8s_equivalent)
+        .sum();
+    let amount_e8s = std::cmp::min(amount_e8s, total_maturity_e8s);
+    if amount_e8s == 0 {
+        return vec![];
+    }
+
+    let mut controller_to_cf_neurons: BTreeMap<PrincipalId, Vec<sns_swap_pb::CfNeuron>> =
+        BTreeMap::new();
+    for mut neuron in cf_neurons {
+        let id = neuron.id.as_ref().expect("Neuron must have an id").id;
+        let controller = match neuron.controller {
+            Some(controller) => controller,
+            None => continue,
+        };
+        let neuron_amount_e8s = (neuron.maturity_e8s_equivalent as u128 * amount_e8s as u128
+            / total_maturity_e8s as u128) as u64;
+        if neuron_amount_e8s == 0 {
+            continue;
+        }
+        neuron.maturity_e8s_equivalent -= neuron_amount_e8s;
+        upsert_neuron_or_log(neuron_store, neuron);
+        controller_to_cf_neurons
+            .entry(controller)
+            .or_default()
+            .push(sns_swap_pb::CfNeuron {
+                nns_neuron_id: id,
+                amount_icp_e8s: neuron_amount_e8s,
+                amount_sns_e8s: 0,
+                sns_disbursing: false,
+            });
+    }
+
+    controller_to_cf_neurons
+        .into_iter()
+        .map(|(controller, mut cf_neurons)| {
+            cf_neurons.sort_by_key(|cf_neuron| cf_neuron.nns_neuron_id);
+            sns_swap_pb::CfParticipant {
+                controller_principal_id: controller.to_string(),
+                cf_neurons,
+            }
+        })
+        .collect()
+}
+
+/// Gives back the maturity drawn by draw_maturity_from_community_fund. Neurons
+/// that no longer exist are skipped.
+fn refund_maturity_to_community_fund(
+    neuron_store: &mut NeuronStore,
+    cf_participants: &[sns_swap_pb::CfParticipant],
+) {
+    for cf_neuron in cf_participants.iter().flat_map(|p| p.cf_neurons.iter()) {
+        match neuron_store.get(cf_neuron.nns_neuron_id) {
+            Some(mut neuron) => {
+                neuron.maturity_e8s_equivalent += cf_neuron.amount_icp_e8s;
+                upsert_neuron_or_log(neuron_store, neuron);
+            }
+            None => println!(
+                "{LOG_PREFIX}WARNING: Unable to refund {} e8s of maturity to neuron {}, \
+                 which no longer exists.",
+                cf_neuron.amount_icp_e8s, cf_neuron.nns_neuron_id
+            ),
+        }
+    }
+}
+
+fn total_community_fund_participation_e8s(cf_participants: &[sns_swap_pb::CfParticipant]) -> u64 {
+    cf_participants
+        .iter()
+        .flat_map(|p| p.cf_neurons.iter())
+        .map(|cf_neuron| cf_neuron.amount_icp_e8s)
+        .sum()
+}
+
 // Returns whether the following requirements are met:
 //   1. proposal must have a title.
 //   2. title len (bytes, not characters) is between min and max.
@@ -6962,4 +7256,157 @@ mod tests {
 
         assert_invalid_set_sns_token_swap_open_time_window(&action, vec!["duration", "day"]);
     }
+
+    fn ok_open_sns_token_swap() -> OpenSnsTokenSwap {
+        let action = OpenSnsTokenSwap {
+            target_swap_canister_id: Some(PrincipalId::new_user_test_id(42)),
+            open_time_window: Some(sns_swap_pb::TimeWindow {
+                start_timestamp_seconds: START_OF_2022_TIMESTAMP_SECONDS + 42 * SECONDS_PER_DAY,
+                end_timestamp_seconds: START_OF_2022_TIMESTAMP_SECONDS + 44 * SECONDS_PER_DAY,
+            }),
+            community_fund_investment_e8s: Some(100 * E8S_PER_ICP),
+        };
+
+        let defects = action.defects(START_OF_2022_TIMESTAMP_SECONDS);
+        assert!(defects.is_empty(), "{:#?}", defects);
+
+        action
+    }
+
+    fn assert_invalid_open_sns_token_swap(action: &OpenSnsTokenSwap, error_key_word: &str) {
+        let err = action
+            .validate(START_OF_2022_TIMESTAMP_SECONDS)
+            .expect_err("Validation passed, but it shouldn't have.");
+        assert_eq!(err.error_type, ErrorType::InvalidProposal as i32);
+        assert!(
+            err.error_message.contains(error_key_word),
+            "error_key_word={}. err={:#?}",
+            error_key_word,
+            err
+        );
+    }
+
+    #[test]
+    fn validate_open_sns_token_swap_no_canister_id() {
+        let mut action = ok_open_sns_token_swap();
+
+        action.target_swap_canister_id = None;
+        assert_invalid_open_sns_token_swap(&action, "target_swap_canister_id");
+    }
+
+    #[test]
+    fn validate_open_sns_token_swap_defective_window() {
+        let mut action = ok_open_sns_token_swap();
+
+        let mut open_time_window = action.open_time_window.as_mut().unwrap();
+        open_time_window.end_timestamp_seconds = open_time_window.start_timestamp_seconds + 1;
+        assert_invalid_open_sns_token_swap(&action, "Duration");
+    }
+
+    #[test]
+    fn validate_open_sns_token_swap_no_investment() {
+        let mut action = ok_open_sns_token_swap();
+
+        action.community_fund_investment_e8s = None;
+        assert_invalid_open_sns_token_swap(&action, "community_fund_investment_e8s");
+    }
+
+    fn community_fund_neurons() -> HashMap<u64, Neuron> {
+        let neuron = |id: u64, controller: u64, maturity_e8s_equivalent: u64, cf: bool| {
+            (
+                id,
+                Neuron {
+                    id: Some(NeuronId { id }),
+                    controller: Some(PrincipalId::new_user_test_id(controller)),
+                    maturity_e8s_equivalent,
+                    joined_community_fund_timestamp_seconds: if cf { Some(1) } else { None },
+                    ..Default::default()
+                },
+            )
+        };
+        vec![
+            neuron(1, 1001, 100 * E8S_PER_ICP, true),
+            neuron(2, 1001, 300 * E8S_PER_ICP, true),
+            neuron(3, 1002, 600 * E8S_PER_ICP, true),
+            neuron(4, 1002, 1000 * E8S_PER_ICP, false),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn community_fund_neuron_store() -> NeuronStore {
+        let mut neuron_store = NeuronStore::init();
+        for (_, neuron) in community_fund_neurons() {
+            neuron_store.upsert(neuron).unwrap();
+        }
+        neuron_store
+    }
+
+    fn maturity_e8s(neuron_store: &NeuronStore, id: u64) -> u64 {
+        neuron_store.get(id).unwrap().maturity_e8s_equivalent
+    }
+
+    #[test]
+    fn draw_maturity_from_community_fund_is_proportional() {
+        let mut neurons = community_fund_neuron_store();
+
+        let cf_participants = draw_maturity_from_community_fund(&mut neurons, 100 * E8S_PER_ICP);
+
+        assert_eq!(
+            cf_participants,
+            vec![
+                sns_swap_pb::CfParticipant {
+                    controller_principal_id: PrincipalId::new_user_test_id(1001).to_string(),
+                    cf_neurons: vec![
+                        sns_swap_pb::CfNeuron {
+                            nns_neuron_id: 1,
+                            amount_icp_e8s: 10 * E8S_PER_ICP,
+                            ..Default::default()
+                        },
+                        sns_swap_pb::CfNeuron {
+                            nns_neuron_id: 2,
+                            amount_icp_e8s: 30 * E8S_PER_ICP,
+                            ..Default::default()
+                        },
+                    ],
+                },
+                sns_swap_pb::CfParticipant {
+                    controller_principal_id: PrincipalId::new_user_test_id(1002).to_string(),
+                    cf_neurons: vec![sns_swap_pb::CfNeuron {
+                        nns_neuron_id: 3,
+                        amount_icp_e8s: 60 * E8S_PER_ICP,
+                        ..Default::default()
+                    }],
+                },
+            ]
+        );
+        assert_eq!(
+            total_community_fund_participation_e8s(&cf_participants),
+            100 * E8S_PER_ICP
+        );
+        assert_eq!(maturity_e8s(&neurons, 1), 90 * E8S_PER_ICP);
+        assert_eq!(maturity_e8s(&neurons, 2), 270 * E8S_PER_ICP);
+        assert_eq!(maturity_e8s(&neurons, 3), 540 * E8S_PER_ICP);
+        // Not a community fund neuron.
+        assert_eq!(maturity_e8s(&neurons, 4), 1000 * E8S_PER_ICP);
+
+        refund_maturity_to_community_fund(&mut neurons, &cf_participants);
+        assert_eq!(neurons.clone_neurons(), community_fund_neurons());
+    }
+
+    #[test]
+    fn draw_maturity_from_community_fund_is_capped_by_available_maturity() {
+        let mut neurons = community_fund_neuron_store();
+
+        let cf_participants = draw_maturity_from_community_fund(&mut neurons, 5_000 * E8S_PER_ICP);
+
+        assert_eq!(
+            total_community_fund_participation_e8s(&cf_participants),
+            1_000 * E8S_PER_ICP
+        );
+        for id in 1..=3 {
+            assert_eq!(maturity_e8s(&neurons, id), 0);
+        }
+        assert_eq!(maturity_e8s(&neurons, 4), 1000 * E8S_PER_ICP);
+    }
 }
@@ -131,6 +131,9 @@
 /// subnetworks that participate in the Internet Computer (IC).
 pub mod governance;
 pub mod init;
+pub mod neuron_store;
+pub mod nns_function_payload;
 pub mod pb;
 pub mod proposal_submission;
 mod reward;
+pub mod storage;
@@ -0,0 +1,698 @@
+//! The neurons of the governance canister and the indexes over them.
+//!
+//! Neurons are kept in a `StableBTreeMap` in stable memory rather than on the
+//! heap, so that they do not need to be serialized in `canister_pre_upgrade`
+//! and deserialized in `canister_post_upgrade`. The indexes over the neurons
+//! (followers per followee, neurons per principal, neuron per subaccount,
+//! known neuron names and neurons with staked maturity by the time they
+//! dissolve) are kept in a second `StableBTreeMap` and updated
+//! whenever a neuron is written, so they do not have to be rebuilt on upgrade
+//! either.
+//!
+//! Neurons are read and written by value: callers get a copy of a neuron,
+//! modify it, and write it back with `upsert`.
+//!
+//! Neurons that come from a `Governance` proto (the initial state, or the
+//! state saved by versions that kept all neurons on the heap) are first added
+//! as pending neurons, which are kept on the heap and moved to stable memory
+//! in batches by `migrate_pending_neurons`, so that no single message has to
+//! move all of them. Pending neurons are indexed on the heap, with the same
+//! keys as in stable memory, and lookups consult both indexes.
+
+use crate::governance::LOG_PREFIX;
+use crate::pb::v1::{
+    governance_error::ErrorType, neuron::DissolveState, GovernanceError, Neuron, Topic,
+};
+use crate::storage::{self, StorageMemory};
+use ic_base_types::PrincipalId;
+use ic_crypto_sha::Sha256;
+use ledger_canister::Subaccount;
+use prost::Message;
+use stable_structures::{StableBTreeMap, Storable};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::{TryFrom, TryInto};
+use std::ops::Bound;
+
+/// The maximum size of an encoded neuron.
+///
+/// This comfortably fits a known neuron with the longest allowed
+/// description, the maximum number of followees on every topic and a full
+/// list of recent ballots.
+pub const MAX_NEURON_SIZE_BYTES: u32 = 8 * 1024;
+
+/// The maximum size of a key in the indexes map. The longest key is that of
+/// the principals index: a tag, the length of the principal, the principal
+/// (at most 29 bytes) and a neuron ID.
+const MAX_INDEX_KEY_SIZE_BYTES: u32 = 40;
+
+/// The maximum size of a value in the indexes map, which is either empty or
+/// a neuron ID.
+const MAX_INDEX_VALUE_SIZE_BYTES: u32 = 8;
+
+// The tags that prefix the keys of each index in the indexes map.
+
+/// `[tag][topic][followee ID][follower ID] -> ()`
+const FOLLOWEES_INDEX: u8 = 0;
+/// `[tag][principal length][principal][neuron ID] -> ()`
+const PRINCIPALS_INDEX: u8 = 1;
+/// `[tag][subaccount] -> neuron ID`
+const SUBACCOUNTS_INDEX: u8 = 2;
+/// `[tag][sha256(name)] -> neuron ID`
+const KNOWN_NEURON_NAMES_INDEX: u8 = 3;
+/// `[tag][timestamp at which the neuron is dissolved][neuron ID] -> ()`, for
+/// the neurons that have staked maturity and are dissolved or dissolving.
+const STAKED_MATURITY_INDEX: u8 = 4;
+
+impl Storable for Neuron {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(self.encode_to_vec())
+    }
+
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        Neuron::decode(&bytes[..]).expect("Cannot decode a neuron from stable memory")
+    }
+}
+
+/// The neurons, keyed by neuron ID, together with the indexes over them.
+pub struct NeuronStore {
+    neurons: StableBTreeMap<StorageMemory, u64, Neuron>,
+    indexes: StableBTreeMap<StorageMemory, Vec<u8>, Vec<u8>>,
+    /// The neurons that have not been moved to stable memory yet.
+    pending: BTreeMap<u64, Neuron>,
+    /// The index entries of the pending neurons, mapping each key to the ID
+    /// of the neuron it belongs to.
+    pending_indexes: BTreeMap<Vec<u8>, u64>,
+    /// The ID from which `migrate_pending_neurons` resumes. Pending neurons
+    /// below it could not be moved to stable memory.
+    next_pending_id: u64,
+}
+
+impl NeuronStore {
+    /// Loads the neurons and indexes from stable memory, or creates an empty
+    /// store if stable memory does not hold one yet.
+    pub fn init() -> Self {
+        Self {
+            neurons: StableBTreeMap::init(storage::neurons_memory(), 8, MAX_NEURON_SIZE_BYTES),
+            indexes: StableBTreeMap::init(
+                storage::neuron_indexes_memory(),
+                MAX_INDEX_KEY_SIZE_BYTES,
+                MAX_INDEX_VALUE_SIZE_BYTES,
+            ),
+            pending: BTreeMap::new(),
+            pending_indexes: BTreeMap::new(),
+            next_pending_id: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.neurons.len() as usize + self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.neurons.is_empty() && self.pending.is_empty()
+    }
+
+    pub fn contains(&self, neuron_id: u64) -> bool {
+        self.neurons.contains_key(&neuron_id) || self.pending.contains_key(&neuron_id)
+    }
+
+    /// Returns a copy of the neuron with the given ID.
+    pub fn get(&self, neuron_id: u64) -> Option<Neuron> {
+        self.neurons
+            .get(&neuron_id)
+            .or_else(|| self.pending.get(&neuron_id).cloned())
+    }
+
+    /// Inserts `neuron`, or replaces the neuron with the same ID, updating
+    /// the indexes. Returns the replaced neuron, if any.
+    ///
+    /// Fails, leaving the store unchanged, if the neuron is larger than
+    /// `MAX_NEURON_SIZE_BYTES` once encoded. Panics if the neuron has no ID.
+    pub fn upsert(&mut self, neuron: Neuron) -> Result<Option<Neuron>, GovernanceError> {
+        let neuron_id = neuron.id.as_ref().expect("Neuron must have an id").id;
+        let encoded_len = neuron.encoded_len();
+        if encoded_len > MAX_NEURON_SIZE_BYTES as usize {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "Neuron {} is {} bytes long, which exceeds the maximum of {} bytes",
+                    neuron_id, encoded_len, MAX_NEURON_SIZE_BYTES
+                ),
+            ));
+        }
+
+        if let Some(pending_neuron) = self.remove_pending_neuron(neuron_id) {
+            self.add_index_entries(neuron_id, index_entries(neuron_id, &neuron).iter());
+            self.neurons
+                .insert(neuron_id, neuron)
+                .expect("Cannot insert neuron into stable memory");
+            return Ok(Some(pending_neuron));
+        }
+
+        let old_neuron = self.neurons.get(&neuron_id);
+        if old_neuron.as_ref() == Some(&neuron) {
+            return Ok(old_neuron);
+        }
+
+        let old_entries = old_neuron
+            .as_ref()
+            .map(|old_neuron| index_entries(neuron_id, old_neuron))
+            .unwrap_or_default();
+        let new_entries = index_entries(neuron_id, &neuron);
+        self.remove_index_entries(neuron_id, old_entries.difference(&new_entries));
+        self.add_index_entries(neuron_id, new_entries.difference(&old_entries));
+
+        Ok(self
+            .neurons
+            .insert(neuron_id, neuron)
+            .expect("Cannot insert neuron into stable memory"))
+    }
+
+    /// Removes the neuron with the given ID and its index entries. Returns the
+    /// removed neuron, if any.
+    pub fn remove(&mut self, neuron_id: u64) -> Option<Neuron> {
+        if let Some(neuron) = self.remove_pending_neuron(neuron_id) {
+            return Some(neuron);
+        }
+        let neuron = self.neurons.remove(&neuron_id)?;
+        let entries = index_entries(neuron_id, &neuron);
+        self.remove_index_entries(neuron_id, entries.iter());
+        Some(neuron)
+    }
+
+    /// Iterates over copies of all neurons, in no particular order.
+    pub fn values(&self) -> impl Iterator<Item = Neuron> + '_ {
+        self.neurons
+            .iter()
+            .map(|(_, neuron)| neuron)
+            .chain(self.pending.values().cloned())
+    }
+
+    /// Returns the IDs of all neurons.
+    pub fn neuron_ids(&self) -> Vec<u64> {
+        self.neurons
+            .iter()
+            .map(|(id, _)| id)
+            .chain(self.pending.keys().copied())
+            .collect()
+    }
+
+    /// Returns a copy of all neurons keyed by ID, e.g., to compare or to
+    /// snapshot the state of governance in tests.
+    pub fn clone_neurons(&self) -> HashMap<u64, Neuron> {
+        self.neurons
+            .iter()
+            .chain(
+                self.pending
+                    .iter()
+                    .map(|(id, neuron)| (*id, neuron.clone())),
+            )
+            .collect()
+    }
+
+    /// Adds `neurons` as pending neurons, to be moved to stable memory by
+    /// `migrate_pending_neurons`.
+    pub fn add_pending_neurons(&mut self, neurons: impl IntoIterator<Item = (u64, Neuron)>) {
+        for (neuron_id, neuron) in neurons {
+            self.remove_pending_neuron(neuron_id);
+            for key in index_entries(neuron_id, &neuron) {
+                self.pending_indexes.insert(key, neuron_id);
+            }
+            self.pending.insert(neuron_id, neuron);
+        }
+        self.next_pending_id = 0;
+    }
+
+    /// Moves at most `max_neurons` pending neurons to stable memory. Neurons
+    /// that cannot be stored in stable memory remain pending.
+    pub fn migrate_pending_neurons(&mut self, max_neurons: usize) {
+        let neuron_ids: Vec<u64> = self
+            .pending
+            .range(self.next_pending_id..)
+            .map(|(id, _)| *id)
+            .take(max_neurons)
+            .collect();
+        for neuron_id in neuron_ids {
+            let neuron = self.pending[&neuron_id].clone();
+            if let Err(err) = self.upsert(neuron) {
+                println!(
+                    "{}Cannot move neuron {} to stable memory: {}",
+                    LOG_PREFIX, neuron_id, err
+                );
+            }
+            self.next_pending_id = neuron_id.saturating_add(1);
+        }
+    }
+
+    /// Returns whether some pending neurons still have to be moved to stable
+    /// memory.
+    pub fn has_neurons_to_migrate(&self) -> bool {
+        self.pending.range(self.next_pending_id..).next().is_some()
+    }
+
+    /// Removes and returns the pending neurons, e.g., to save them in
+    /// `canister_pre_upgrade`.
+    pub fn take_pending_neurons(&mut self) -> HashMap<u64, Neuron> {
+        self.next_pending_id = 0;
+        self.pending_indexes.clear();
+        std::mem::take(&mut self.pending).into_iter().collect()
+    }
+
+    /// Returns the IDs of the neurons that follow `followee` on `topic`.
+    pub fn followers(&self, topic: Topic, followee: u64) -> Vec<u64> {
+        let mut prefix = vec![FOLLOWEES_INDEX];
+        prefix.extend_from_slice(&(topic as i32 as u32).to_be_bytes());
+        prefix.extend_from_slice(&followee.to_be_bytes());
+        self.indexes
+            .range(prefix.clone(), None)
+            .map(|(key, _)| u64_from_be_bytes(&key[prefix.len()..]))
+            .chain(self.pending_neuron_ids(&prefix))
+            .collect()
+    }
+
+    /// Returns the IDs of the neurons that have `principal` as their
+    /// controller or as one of their hot keys.
+    pub fn neuron_ids_by_principal(&self, principal: &PrincipalId) -> HashSet<u64> {
+        let prefix = principal_prefix(principal);
+        self.indexes
+            .range(prefix.clone(), None)
+            .map(|(key, _)| u64_from_be_bytes(&key[prefix.len()..]))
+            .chain(self.pending_neuron_ids(&prefix))
+            .collect()
+    }
+
+    /// Returns the ID of the neuron whose account is `subaccount`.
+    pub fn neuron_id_by_subaccount(&self, subaccount: &Subaccount) -> Option<u64> {
+        let mut key = vec![SUBACCOUNTS_INDEX];
+        key.extend_from_slice(&subaccount.0);
+        self.indexes
+            .get(&key)
+            .map(|id| u64_from_be_bytes(&id))
+            .or_else(|| self.pending_neuron_ids(&key).next())
+    }
+
+    /// Returns whether a known neuron already has the name `name`.
+    pub fn contains_known_neuron_name(&self, name: &str) -> bool {
+        let key = known_neuron_name_key(name);
+        self.indexes.contains_key(&key) || self.pending_neuron_ids(&key).next().is_some()
+    }
+
+    /// Returns the IDs of all known neurons.
+    pub fn known_neuron_ids(&self) -> Vec<u64> {
+        self.indexes
+            .range(vec![KNOWN_NEURON_NAMES_INDEX], None)
+            .map(|(_, id)| u64_from_be_bytes(&id))
+            .chain(self.pending_neuron_ids(&[KNOWN_NEURON_NAMES_INDEX]))
+            .collect()
+    }
+
+    /// Returns the IDs of at most `limit` neurons that have staked maturity
+    /// and are dissolved at `now_seconds`, in the order in which they
+    /// dissolved.
+    pub fn dissolved_neuron_ids_with_staked_maturity(
+        &self,
+        now_seconds: u64,
+        limit: usize,
+    ) -> Vec<u64> {
+        let prefix = [STAKED_MATURITY_INDEX];
+        let mut keys: Vec<Vec<u8>> = self
+            .indexes
+            .range(prefix.to_vec(), None)
+            .map(|(key, _)| key)
+            .take_while(|key| u64_from_be_bytes(&key[1..9]) <= now_seconds)
+            .take(limit)
+            .collect();
+        keys.extend(
+            self.pending_index_range(&prefix)
+                .map(|(key, _)| key.clone())
+                .take_while(|key| u64_from_be_bytes(&key[1..9]) <= now_seconds)
+                .take(limit),
+        );
+        keys.sort_unstable();
+        keys.into_iter()
+            .take(limit)
+            .map(|key| u64_from_be_bytes(&key[9..]))
+            .collect()
+    }
+
+    /// Returns the IDs of the pending neurons that have an index entry
+    /// starting with `prefix`.
+    fn pending_neuron_ids<'a>(&'a self, prefix: &'a [u8]) -> impl Iterator<Item = u64> + 'a {
+        self.pending_index_range(prefix)
+            .map(|(_, neuron_id)| *neuron_id)
+    }
+
+    /// Iterates over the index entries of the pending neurons whose keys
+    /// start with `prefix`, in the order of their keys.
+    fn pending_index_range<'a>(
+        &'a self,
+        prefix: &'a [u8],
+    ) -> impl Iterator<Item = (&'a Vec<u8>, &'a u64)> + 'a {
+        self.pending_indexes
+            .range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(key, _)| key.starts_with(prefix))
+    }
+
+    /// Removes the pending neuron with the given ID and its index entries.
+    fn remove_pending_neuron(&mut self, neuron_id: u64) -> Option<Neuron> {
+        let neuron = self.pending.remove(&neuron_id)?;
+        for key in index_entries(neuron_id, &neuron) {
+            // As in `remove_index_entries`, entries that map to a neuron ID may
+            // have been overwritten by another neuron.
+            if self.pending_indexes.get(&key) == Some(&neuron_id) {
+                self.pending_indexes.remove(&key);
+            }
+        }
+        Some(neuron)
+    }
+
+    fn add_index_entries<'a>(&mut self, neuron_id: u64, keys: impl Iterator<Item = &'a Vec<u8>>) {
+        for key in keys {
+            let value = if has_neuron_id_value(key) {
+                neuron_id.to_be_bytes().to_vec()
+            } else {
+                vec![]
+            };
+            self.indexes
+                .insert(key.clone(), value)
+                .expect("Cannot insert into the neuron indexes");
+        }
+    }
+
+    fn remove_index_entries<'a>(
+        &mut self,
+        neuron_id: u64,
+        keys: impl Iterator<Item = &'a Vec<u8>>,
+    ) {
+        for key in keys {
+            // Entries that map to a neuron ID could, in principle, have been
+            // overwritten by another neuron with the same subaccount or known
+            // neuron name. Only remove them if they still point to this neuron.
+            if has_neuron_id_value(key)
+                && self.indexes.get(key).map(|id| u64_from_be_bytes(&id)) != Some(neuron_id)
+            {
+                continue;
+            }
+            self.indexes.remove(key);
+        }
+    }
+}
+
+/// Returns the keys of all index entries of `neuron`.
+fn index_entries(neuron_id: u64, neuron: &Neuron) -> BTreeSet<Vec<u8>> {
+    let mut entries = BTreeSet::new();
+
+    for (topic, followees) in neuron.followees.iter() {
+        // Followees on topics that are no longer defined are kept in the
+        // neuron, but they are not indexed.
+        if Topic::from_i32(*topic).is_none() {
+            continue;
+        }
+        for followee in followees.followees.iter() {
+            let mut key = vec![FOLLOWEES_INDEX];
+            key.extend_from_slice(&(*topic as u32).to_be_bytes());
+            key.extend_from_slice(&followee.id.to_be_bytes());
+            key.extend_from_slice(&neuron_id.to_be_bytes());
+            entries.insert(key);
+        }
+    }
+
+    for principal in neuron.controller.iter().chain(neuron.hot_keys.iter()) {
+        let mut key = principal_prefix(principal);
+        key.extend_from_slice(&neuron_id.to_be_bytes());
+        entries.insert(key);
+    }
+
+    if let Ok(subaccount) = Subaccount::try_from(&neuron.account[..]) {
+        let mut key = vec![SUBACCOUNTS_INDEX];
+        key.extend_from_slice(&subaccount.0);
+        entries.insert(key);
+    }
+
+    if let Some(known_neuron_data) = neuron.known_neuron_data.as_ref() {
+        entries.insert(known_neuron_name_key(&known_neuron_data.name));
+    }
+
+    if neuron.staked_maturity_e8s_equivalent.is_some() {
+        let dissolved_timestamp_seconds = match neuron.dissolve_state {
+            Some(DissolveState::DissolveDelaySeconds(0)) | None => Some(0),
+            Some(DissolveState::DissolveDelaySeconds(_)) => None,
+            Some(DissolveState::WhenDissolvedTimestampSeconds(ts)) => Some(ts),
+        };
+        if let Some(dissolved_timestamp_seconds) = dissolved_timestamp_seconds {
+            let mut key = vec![STAKED_MATURITY_INDEX];
+            key.extend_from_slice(&dissolved_timestamp_seconds.to_be_bytes());
+            key.extend_from_slice(&neuron_id.to_be_bytes());
+            entries.insert(key);
+        }
+    }
+
+    entries
+}
+
+fn principal_prefix(principal: &PrincipalId) -> Vec<u8> {
+    let bytes = principal.as_slice();
+    let mut prefix = vec![PRINCIPALS_INDEX, bytes.len() as u8];
+    prefix.extend_from_slice(bytes);
+    prefix
+}
+
+fn known_neuron_name_key(name: &str) -> Vec<u8> {
+    let mut key = vec![KNOWN_NEURON_NAMES_INDEX];
+    key.extend_from_slice(&Sha256::hash(name.as_bytes()));
+    key
+}
+
+/// Returns whether the index entry with the given key maps to a neuron ID, as
+/// opposed to having the neuron ID as the last part of the key.
+fn has_neuron_id_value(key: &[u8]) -> bool {
+    matches!(key[0], SUBACCOUNTS_INDEX | KNOWN_NEURON_NAMES_INDEX)
+}
+
+fn u64_from_be_bytes(bytes: &[u8]) -> u64 {
+    u64::from_be_bytes(bytes.try_into().expect("Expected 8 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pb::v1::{neuron::Followees, KnownNeuronData};
+    use ic_nns_common::pb::v1::NeuronId;
+    use maplit::hashmap;
+
+    fn neuron(id: u64, controller: u64, followees: &[u64]) -> Neuron {
+        Neuron {
+            id: Some(NeuronId { id }),
+            controller: Some(PrincipalId::new_user_test_id(controller)),
+            account: vec![id as u8; 32],
+            followees: hashmap! {
+                Topic::Governance as i32 => Followees {
+                    followees: followees.iter().map(|id| NeuronId { id: *id }).collect(),
+                },
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn upsert_and_remove_maintain_the_indexes() {
+        let mut store = NeuronStore::init();
+        assert!(store.is_empty());
+
+        store.upsert(neuron(1, 100, &[])).unwrap();
+        store.upsert(neuron(2, 200, &[1])).unwrap();
+        store.upsert(neuron(3, 200, &[1])).unwrap();
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.followers(Topic::Governance, 1), vec![2, 3]);
+        assert!(store.followers(Topic::Kyc, 1).is_empty());
+        assert_eq!(
+            store.neuron_ids_by_principal(&PrincipalId::new_user_test_id(200)),
+            [2, 3].iter().copied().collect()
+        );
+        assert_eq!(store.neuron_id_by_subaccount(&Subaccount([3; 32])), Some(3));
+
+        // Neuron 3 changes its followees and controller.
+        let mut changed = store.get(3).unwrap();
+        changed.controller = Some(PrincipalId::new_user_test_id(300));
+        changed.followees.clear();
+        store.upsert(changed.clone()).unwrap();
+        assert_eq!(store.get(3), Some(changed));
+        assert_eq!(store.followers(Topic::Governance, 1), vec![2]);
+        assert_eq!(
+            store.neuron_ids_by_principal(&PrincipalId::new_user_test_id(200)),
+            [2].iter().copied().collect()
+        );
+        assert_eq!(
+            store.neuron_ids_by_principal(&PrincipalId::new_user_test_id(300)),
+            [3].iter().copied().collect()
+        );
+
+        assert!(store.remove(2).is_some());
+        assert!(store.remove(2).is_none());
+        assert!(!store.contains(2));
+        assert!(store.followers(Topic::Governance, 1).is_empty());
+        assert!(store
+            .neuron_ids_by_principal(&PrincipalId::new_user_test_id(200))
+            .is_empty());
+        assert_eq!(store.neuron_id_by_subaccount(&Subaccount([2; 32])), None);
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn known_neuron_names_are_indexed() {
+        let mut store = NeuronStore::init();
+        let mut known = neuron(1, 100, &[]);
+        known.known_neuron_data = Some(KnownNeuronData {
+            name: "known".to_string(),
+            description: None,
+        });
+        store.upsert(known.clone()).unwrap();
+        store.upsert(neuron(2, 100, &[])).unwrap();
+
+        assert!(store.contains_known_neuron_name("known"));
+        assert!(!store.contains_known_neuron_name("unknown"));
+        assert_eq!(store.known_neuron_ids(), vec![1]);
+
+        known.known_neuron_data.as_mut().unwrap().name = "renamed".to_string();
+        store.upsert(known).unwrap();
+        assert!(!store.contains_known_neuron_name("known"));
+        assert!(store.contains_known_neuron_name("renamed"));
+    }
+
+    #[test]
+    fn dissolved_neurons_with_staked_maturity_are_indexed() {
+        let mut store = NeuronStore::init();
+        let with_state = |id: u64, staked: bool, dissolve_state: DissolveState| Neuron {
+            staked_maturity_e8s_equivalent: if staked { Some(10) } else { None },
+            dissolve_state: Some(dissolve_state),
+            ..neuron(id, 100, &[])
+        };
+        store
+            .upsert(with_state(
+                1,
+                true,
+                DissolveState::WhenDissolvedTimestampSeconds(50),
+            ))
+            .unwrap();
+        store
+            .upsert(with_state(2, true, DissolveState::DissolveDelaySeconds(0)))
+            .unwrap();
+        store
+            .upsert(with_state(3, true, DissolveState::DissolveDelaySeconds(10)))
+            .unwrap();
+        store
+            .upsert(with_state(4, false, DissolveState::DissolveDelaySeconds(0)))
+            .unwrap();
+        store.add_pending_neurons(vec![(
+            5,
+            with_state(5, true, DissolveState::WhenDissolvedTimestampSeconds(20)),
+        )]);
+
+        assert_eq!(
+            store.dissolved_neuron_ids_with_staked_maturity(10, 10),
+            vec![2]
+        );
+        assert_eq!(
+            store.dissolved_neuron_ids_with_staked_maturity(100, 10),
+            vec![2, 5, 1]
+        );
+        assert_eq!(
+            store.dissolved_neuron_ids_with_staked_maturity(100, 2),
+            vec![2, 5]
+        );
+
+        // Unstaking the maturity removes the neuron from the index.
+        let mut unstaked = store.get(2).unwrap();
+        unstaked.staked_maturity_e8s_equivalent = None;
+        store.upsert(unstaked).unwrap();
+        store.migrate_pending_neurons(10);
+        assert_eq!(
+            store.dissolved_neuron_ids_with_staked_maturity(100, 10),
+            vec![5, 1]
+        );
+    }
+
+    #[test]
+    fn oversized_neurons_are_rejected() {
+        let mut store = NeuronStore::init();
+        store.upsert(neuron(1, 100, &[])).unwrap();
+        let mut large = neuron(1, 100, &[]);
+        large.hot_keys = (0..1000).map(PrincipalId::new_user_test_id).collect();
+
+        let err = store.upsert(large).unwrap_err();
+
+        assert_eq!(err.error_type, ErrorType::PreconditionFailed as i32);
+        assert!(err.error_message.contains("exceeds the maximum"));
+        assert_eq!(store.get(1), Some(neuron(1, 100, &[])));
+        assert_eq!(
+            store.neuron_ids_by_principal(&PrincipalId::new_user_test_id(100)),
+            [1].iter().copied().collect()
+        );
+    }
+
+    #[test]
+    fn pending_neurons_are_visible_before_and_after_their_migration() {
+        let mut store = NeuronStore::init();
+        store.upsert(neuron(1, 100, &[])).unwrap();
+        let mut oversized = neuron(4, 200, &[1]);
+        oversized.hot_keys = (0..1000).map(PrincipalId::new_user_test_id).collect();
+        store.add_pending_neurons(vec![
+            (2, neuron(2, 200, &[1])),
+            (3, neuron(3, 200, &[1])),
+            (4, oversized.clone()),
+        ]);
+
+        let check = |store: &NeuronStore| {
+            assert_eq!(store.len(), 4);
+            assert_eq!(store.get(4), Some(oversized.clone()));
+            let mut followers = store.followers(Topic::Governance, 1);
+            followers.sort_unstable();
+            assert_eq!(followers, vec![2, 3, 4]);
+            assert!(store
+                .neuron_ids_by_principal(&PrincipalId::new_user_test_id(200))
+                .is_superset(&[2, 3, 4].iter().copied().collect()));
+            assert_eq!(store.neuron_id_by_subaccount(&Subaccount([3; 32])), Some(3));
+        };
+
+        check(&store);
+        store.migrate_pending_neurons(1);
+        assert!(store.has_neurons_to_migrate());
+        check(&store);
+        store.migrate_pending_neurons(10);
+        assert!(!store.has_neurons_to_migrate());
+        check(&store);
+
+        // The oversized neuron stays pending and is saved on upgrade.
+        assert_eq!(
+            store.take_pending_neurons(),
+            hashmap! { 4 => oversized.clone() }
+        );
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn replacing_and_removing_pending_neurons_updates_their_index_entries() {
+        let mut store = NeuronStore::init();
+        store.add_pending_neurons(vec![(2, neuron(2, 200, &[1])), (3, neuron(3, 200, &[1]))]);
+        store.add_pending_neurons(vec![(2, neuron(2, 300, &[]))]);
+
+        assert_eq!(store.followers(Topic::Governance, 1), vec![3]);
+        assert_eq!(
+            store.neuron_ids_by_principal(&PrincipalId::new_user_test_id(200)),
+            [3].iter().copied().collect()
+        );
+        assert_eq!(
+            store.neuron_ids_by_principal(&PrincipalId::new_user_test_id(300)),
+            [2].iter().copied().collect()
+        );
+
+        assert!(store.remove(3).is_some());
+        assert!(store.followers(Topic::Governance, 1).is_empty());
+        assert_eq!(store.neuron_id_by_subaccount(&Subaccount([3; 32])), None);
+        assert_eq!(store.neuron_id_by_subaccount(&Subaccount([2; 32])), Some(2));
+
+        store.take_pending_neurons();
+        assert_eq!(store.neuron_id_by_subaccount(&Subaccount([2; 32])), None);
+    }
+}
@@ -0,0 +1,253 @@
+//! Rendering of the payloads of `ExecuteNnsFunction` proposals.
+//!
+//! The payload of an `ExecuteNnsFunction` proposal is the candid-encoded
+//! argument of the canister method that is called when the proposal is
+//! executed. To let neuron holders see what they are voting on, the payload
+//! is decoded into the argument type of that method when the proposal is
+//! submitted, and stored as text alongside the proposal.
+use crate::pb::v1::NnsFunction;
+use candid::{
+    parser::value::{IDLField, IDLValue, VariantValue},
+    CandidType, Decode, IDLArgs,
+};
+use cycles_minting_canister::SetAuthorizedSubnetworkListArgs;
+use ic_crypto_sha::Sha256;
+use ic_nns_common::types::UpdateIcpXdrConversionRatePayload;
+use ic_protobuf::registry::{
+    dc::v1::AddOrRemoveDataCentersProposalPayload, node_operator::v1::RemoveNodeOperatorsPayload,
+    node_rewards::v2::UpdateNodeRewardsTableProposalPayload,
+};
+use registry_canister::mutations::{
+    complete_canister_migration::CompleteCanisterMigrationPayload,
+    do_add_node_operator::AddNodeOperatorPayload,
+    do_add_nodes_to_subnet::AddNodesToSubnetPayload,
+    do_bless_replica_version::BlessReplicaVersionPayload,
+    do_create_subnet::CreateSubnetPayload,
+    do_recover_subnet::RecoverSubnetPayload,
+    do_remove_nodes_from_subnet::RemoveNodesFromSubnetPayload,
+    do_set_firewall_config::SetFirewallConfigPayload,
+    do_update_node_operator_config::UpdateNodeOperatorConfigPayload,
+    do_update_subnet::UpdateSubnetPayload,
+    do_update_subnet_replica::UpdateSubnetReplicaVersionPayload,
+    do_update_unassigned_nodes_config::UpdateUnassignedNodesConfigPayload,
+    firewall::{AddFirewallRulesPayload, RemoveFirewallRulesPayload, UpdateFirewallRulesPayload},
+    node_management::do_remove_nodes::RemoveNodesPayload,
+    prepare_canister_migration::PrepareCanisterMigrationPayload,
+    reroute_canister_ranges::RerouteCanisterRangesPayload,
+};
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+
+/// Blobs longer than this are rendered as their length and SHA-256 hash
+/// rather than byte by byte, e.g. for Wasm modules.
+const MAX_RENDERED_BLOB_BYTES: usize = 64;
+
+/// Decodes the payload of an `ExecuteNnsFunction` proposal for the given NNS
+/// function and returns a human-readable rendering of it.
+///
+/// Returns an error if the payload cannot be decoded into the argument type
+/// of the canister method that the NNS function calls.
+pub fn render_nns_function_payload(
+    nns_function: NnsFunction,
+    payload: &[u8],
+) -> Result<String, String> {
+    match nns_function {
+        NnsFunction::Unspecified => Err("The NNS function is unspecified.".to_string()),
+        NnsFunction::CreateSubnet => decode_and_render::<CreateSubnetPayload>(payload),
+        NnsFunction::AddNodeToSubnet => decode_and_render::<AddNodesToSubnetPayload>(payload),
+        NnsFunction::BlessReplicaVersion => {
+            decode_and_render::<BlessReplicaVersionPayload>(payload)
+        }
+        NnsFunction::RecoverSubnet => decode_and_render::<RecoverSubnetPayload>(payload),
+        NnsFunction::UpdateConfigOfSubnet => decode_and_render::<UpdateSubnetPayload>(payload),
+        NnsFunction::AssignNoid => decode_and_render::<AddNodeOperatorPayload>(payload),
+        NnsFunction::IcpXdrConversionRate => {
+            decode_and_render::<UpdateIcpXdrConversionRatePayload>(payload)
+        }
+        NnsFunction::UpdateSubnetReplicaVersion => {
+            decode_and_render::<UpdateSubnetReplicaVersionPayload>(payload)
+        }
+        NnsFunction::ClearProvisionalWhitelist => decode_and_render::<()>(payload),
+        NnsFunction::RemoveNodesFromSubnet => {
+            decode_and_render::<RemoveNodesFromSubnetPayload>(payload)
+        }
+        NnsFunction::SetAuthorizedSubnetworks => {
+            decode_and_render::<SetAuthorizedSubnetworkListArgs>(payload)
+        }
+        NnsFunction::SetFirewallConfig => decode_and_render::<SetFirewallConfigPayload>(payload),
+        NnsFunction::UpdateNodeOperatorConfig => {
+            decode_and_render::<UpdateNodeOperatorConfigPayload>(payload)
+        }
+        NnsFunction::RemoveNodes => decode_and_render::<RemoveNodesPayload>(payload),
+        NnsFunction::UpdateNodeRewardsTable => {
+            decode_and_render::<UpdateNodeRewardsTableProposalPayload>(payload)
+        }
+        NnsFunction::AddOrRemoveDataCenters => {
+            decode_and_render::<AddOrRemoveDataCentersProposalPayload>(payload)
+        }
+        NnsFunction::UpdateUnassignedNodesConfig => {
+            decode_and_render::<UpdateUnassignedNodesConfigPayload>(payload)
+        }
+        NnsFunction::RemoveNodeOperators => {
+            decode_and_render::<RemoveNodeOperatorsPayload>(payload)
+        }
+        NnsFunction::RerouteCanisterRanges => {
+            decode_and_render::<RerouteCanisterRangesPayload>(payload)
+        }
+        NnsFunction::AddFirewallRules => decode_and_render::<AddFirewallRulesPayload>(payload),
+        NnsFunction::RemoveFirewallRules => {
+            decode_and_render::<RemoveFirewallRulesPayload>(payload)
+        }
+        NnsFunction::UpdateFirewallRules => {
+            decode_and_render::<UpdateFirewallRulesPayload>(payload)
+        }
+        NnsFunction::PrepareCanisterMigration => {
+            decode_and_render::<PrepareCanisterMigrationPayload>(payload)
+        }
+        NnsFunction::CompleteCanisterMigration => {
+            decode_and_render::<CompleteCanisterMigrationPayload>(payload)
+        }
+        // The argument types of these methods are defined in canisters that
+        // depend on governance, so the payload is rendered without field
+        // names.
+        NnsFunction::NnsCanisterInstall
+        | NnsFunction::NnsCanisterUpgrade
+        | NnsFunction::NnsRootUpgrade
+        | NnsFunction::StopOrStartNnsCanister
+        | NnsFunction::UninstallCode
+        | NnsFunction::AddSnsWasm => render_untyped(payload),
+    }
+}
+
+/// Decodes `payload` as a `T` and renders it using its `Debug`
+/// implementation.
+fn decode_and_render<T>(payload: &[u8]) -> Result<String, String>
+where
+    T: CandidType + DeserializeOwned + Debug,
+{
+    Decode!(payload, T)
+        .map(|decoded| format!("{:#?}", decoded))
+        .map_err(|e| {
+            format!(
+                "The payload could not be decoded into a {}: {}",
+                short_type_name::<T>(),
+                e
+            )
+        })
+}
+
+/// Decodes `payload` as candid values without a type and renders them in the
+/// candid text format, summarizing long blobs.
+fn render_untyped(payload: &[u8]) -> Result<String, String> {
+    let args = IDLArgs::from_bytes(payload)
+        .map_err(|e| format!("The payload is not valid candid: {}", e))?;
+    let args = IDLArgs {
+        args: args.args.into_iter().map(summarize_blobs).collect(),
+    };
+    Ok(args.to_string())
+}
+
+/// Replaces blobs longer than MAX_RENDERED_BLOB_BYTES by a text describing
+/// their length and hash.
+fn summarize_blobs(value: IDLValue) -> IDLValue {
+    match value {
+        IDLValue::Vec(values)
+            if values.len() > MAX_RENDERED_BLOB_BYTES
+                && values.iter().all(|v| matches!(v, IDLValue::Nat8(_))) =>
+        {
+            let bytes: Vec<u8> = values
+                .into_iter()
+                .map(|v| match v {
+                    IDLValue::Nat8(byte) => byte,
+                    _ => unreachable!(),
+                })
+                .collect();
+            IDLValue::Text(format!(
+                "<{} bytes with SHA-256 {}>",
+                bytes.len(),
+                Sha256::hash(&bytes)
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>()
+            ))
+        }
+        IDLValue::Vec(values) => IDLValue::Vec(values.into_iter().map(summarize_blobs).collect()),
+        IDLValue::Opt(value) => IDLValue::Opt(Box::new(summarize_blobs(*value))),
+        IDLValue::Record(fields) => IDLValue::Record(
+            fields
+                .into_iter()
+                .map(|field| IDLField {
+                    id: field.id,
+                    val: summarize_blobs(field.val),
+                })
+                .collect(),
+        ),
+        IDLValue::Variant(VariantValue(field, index)) => IDLValue::Variant(VariantValue(
+            Box::new(IDLField {
+                id: field.id,
+                val: summarize_blobs(field.val),
+            }),
+            index,
+        )),
+        value => value,
+    }
+}
+
+fn short_type_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Encode;
+
+    #[test]
+    fn renders_registry_payloads_with_field_names() {
+        let payload = Encode!(&BlessReplicaVersionPayload {
+            replica_version_id: "new_version".to_string(),
+            release_package_url: "http://release_package.tar.gz".to_string(),
+            release_package_sha256_hex: "".to_string(),
+            binary_url: "".to_string(),
+            sha256_hex: "".to_string(),
+            node_manager_binary_url: "".to_string(),
+            node_manager_sha256_hex: "".to_string(),
+        })
+        .unwrap();
+
+        let rendering =
+            render_nns_function_payload(NnsFunction::BlessReplicaVersion, &payload).unwrap();
+
+        assert!(rendering.starts_with("BlessReplicaVersionPayload {"));
+        assert!(rendering.contains("replica_version_id: \"new_version\""));
+    }
+
+    #[test]
+    fn rejects_payloads_of_the_wrong_type() {
+        let payload = Encode!(&"not a payload").unwrap();
+
+        let error =
+            render_nns_function_payload(NnsFunction::UpdateConfigOfSubnet, &payload).unwrap_err();
+
+        assert!(error.contains("UpdateSubnetPayload"), "{}", error);
+        assert!(render_nns_function_payload(NnsFunction::NnsCanisterUpgrade, &[1, 2, 3]).is_err());
+        assert!(render_nns_function_payload(NnsFunction::Unspecified, &[]).is_err());
+    }
+
+    #[test]
+    fn summarizes_long_blobs_in_untyped_payloads() {
+        let wasm = vec![0_u8; 1000];
+        let payload = Encode!(&true, &serde_bytes::ByteBuf::from(wasm.clone())).unwrap();
+
+        let rendering =
+            render_nns_function_payload(NnsFunction::NnsCanisterUpgrade, &payload).unwrap();
+
+        assert!(
+            rendering.contains("<1000 bytes with SHA-256"),
+            "{}",
+            rendering
+        );
+        assert!(!rendering.contains("0; 0; 0"), "{}", rendering);
+    }
+}
@@ -0,0 +1,296 @@
+//! The layout of the governance canister's stable memory.
+//!
+//! Stable memory is managed by a `MemoryManager`, which splits it into
+//! virtual memories that grow independently, each of which is exclusively
+//! owned by a single data structure:
+//!
+//! - The upgrades memory holds the `Governance` proto, which is written in
+//!   `canister_pre_upgrade` and read back in `canister_post_upgrade`.
+//! - The neuron indexes memory holds the indexes of the `NeuronStore`.
+//! - The neurons memory holds the neurons of the `NeuronStore`.
+//!
+//! Versions that predate the `MemoryManager` wrote the `Governance` proto,
+//! including all neurons, at the beginning of stable memory in the format of
+//! `dfn_core::stable` (a 4-byte little-endian length, followed by the encoded
+//! proto). That state must be read with `load_legacy_governance_proto`
+//! before any virtual memory is used, since initializing the `MemoryManager`
+//! overwrites it. The upgrades memory uses the same format.
+//!
+//! When not running in a canister, every memory is backed by its own
+//! `HeapMemory`.
+
+use crate::pb::v1::Governance as GovernanceProto;
+use prost::{DecodeError, Message};
+use stable_structures::Memory;
+use std::convert::TryFrom;
+
+use stable_structures::memory_manager::MemoryManager;
+#[cfg(target_arch = "wasm32")]
+use stable_structures::{
+    memory_manager::{MemoryId, VirtualMemory},
+    DefaultMemoryImpl,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex};
+
+const WASM_PAGE_SIZE_BYTES: u64 = 65536;
+
+#[cfg(target_arch = "wasm32")]
+const UPGRADES_MEMORY_ID: MemoryId = MemoryId::new(0);
+#[cfg(target_arch = "wasm32")]
+const NEURON_INDEXES_MEMORY_ID: MemoryId = MemoryId::new(1);
+#[cfg(target_arch = "wasm32")]
+const NEURONS_MEMORY_ID: MemoryId = MemoryId::new(2);
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    // Initialized on first use, which must come after the legacy state, if
+    // any, has been read.
+    static MEMORY_MANAGER: MemoryManager<DefaultMemoryImpl> =
+        MemoryManager::init(DefaultMemoryImpl::default());
+}
+
+/// The number of bytes used to store the length of the encoded `Governance`
+/// proto.
+const LENGTH_BYTES: u64 = 4;
+
+#[cfg(target_arch = "wasm32")]
+pub type StorageMemory = VirtualMemory<DefaultMemoryImpl>;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub type StorageMemory = HeapMemory;
+
+/// Returns the memory that holds the `Governance` proto during upgrades.
+#[cfg(target_arch = "wasm32")]
+pub fn upgrades_memory() -> StorageMemory {
+    MEMORY_MANAGER.with(|memory_manager| memory_manager.get(UPGRADES_MEMORY_ID))
+}
+
+/// Returns the memory that holds the indexes of the `NeuronStore`.
+#[cfg(target_arch = "wasm32")]
+pub fn neuron_indexes_memory() -> StorageMemory {
+    MEMORY_MANAGER.with(|memory_manager| memory_manager.get(NEURON_INDEXES_MEMORY_ID))
+}
+
+/// Returns the memory that holds the neurons of the `NeuronStore`.
+#[cfg(target_arch = "wasm32")]
+pub fn neurons_memory() -> StorageMemory {
+    MEMORY_MANAGER.with(|memory_manager| memory_manager.get(NEURONS_MEMORY_ID))
+}
+
+/// Returns the raw stable memory, which only holds legacy state if the
+/// `MemoryManager` has not been initialized.
+#[cfg(target_arch = "wasm32")]
+pub fn raw_memory() -> DefaultMemoryImpl {
+    DefaultMemoryImpl::default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn raw_memory() -> HeapMemory {
+    HeapMemory::default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn upgrades_memory() -> StorageMemory {
+    HeapMemory::default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn neuron_indexes_memory() -> StorageMemory {
+    HeapMemory::default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn neurons_memory() -> StorageMemory {
+    HeapMemory::default()
+}
+
+/// A `Memory` backed by a vector on the heap, used when not running in a
+/// canister.
+///
+/// Unlike `stable_structures::VectorMemory`, it can be sent between threads,
+/// which the interleaving tests rely on.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Default)]
+pub struct HeapMemory(Arc<Mutex<Vec<u8>>>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Memory for HeapMemory {
+    fn size(&self) -> u64 {
+        self.0.lock().unwrap().len() as u64 / WASM_PAGE_SIZE_BYTES
+    }
+
+    fn grow(&self, pages: u64) -> i64 {
+        let mut bytes = self.0.lock().unwrap();
+        let size = bytes.len() as u64 / WASM_PAGE_SIZE_BYTES;
+        bytes.resize(((size + pages) * WASM_PAGE_SIZE_BYTES) as usize, 0);
+        size as i64
+    }
+
+    fn read(&self, offset: u64, dst: &mut [u8]) {
+        let bytes = self.0.lock().unwrap();
+        let offset = offset as usize;
+        dst.copy_from_slice(&bytes[offset..offset + dst.len()]);
+    }
+
+    fn write(&self, offset: u64, src: &[u8]) {
+        let mut bytes = self.0.lock().unwrap();
+        let offset = offset as usize;
+        bytes[offset..offset + src.len()].copy_from_slice(src);
+    }
+}
+
+/// Writes `proto` to `memory`, growing it if needed.
+///
+/// Panics if `memory` cannot grow enough to hold `proto`, i.e., if stable
+/// memory is exhausted. In `canister_pre_upgrade` this aborts the upgrade.
+pub fn save_governance_proto<M: Memory>(memory: &M, proto: &GovernanceProto) {
+    let bytes = proto.encode_to_vec();
+    let length = u32::try_from(bytes.len()).expect("The governance proto is larger than 4 GiB");
+    let required_pages =
+        (LENGTH_BYTES + bytes.len() as u64 + WASM_PAGE_SIZE_BYTES - 1) / WASM_PAGE_SIZE_BYTES;
+    let current_pages = memory.size();
+    if required_pages > current_pages && memory.grow(required_pages - current_pages) == -1 {
+        panic!(
+            "Cannot grow stable memory to {} pages to save the governance proto",
+            required_pages
+        );
+    }
+    memory.write(0, &length.to_le_bytes());
+    memory.write(LENGTH_BYTES, &bytes);
+}
+
+/// Reads the `Governance` proto that versions predating the `MemoryManager`
+/// wrote at the beginning of `memory`, the raw stable memory. Returns `None`
+/// if `memory` is empty or already holds a `MemoryManager`.
+pub fn load_legacy_governance_proto<M: Memory>(
+    memory: &M,
+) -> Option<Result<GovernanceProto, DecodeError>> {
+    if memory.size() == 0 || MemoryManager::is_initialized(memory) {
+        return None;
+    }
+    Some(load_governance_proto(memory))
+}
+
+/// Reads the `Governance` proto written by `save_governance_proto` from
+/// `memory`.
+pub fn load_governance_proto<M: Memory>(memory: &M) -> Result<GovernanceProto, DecodeError> {
+    if memory.size() == 0 {
+        return Ok(GovernanceProto::default());
+    }
+    let mut length_bytes = [0; LENGTH_BYTES as usize];
+    memory.read(0, &mut length_bytes);
+    let mut bytes = vec![0; u32::from_le_bytes(length_bytes) as usize];
+    memory.read(LENGTH_BYTES, &mut bytes);
+    GovernanceProto::decode(&bytes[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pb::v1::{NetworkEconomics, Neuron};
+    use ic_nns_common::pb::v1::NeuronId;
+    use maplit::hashmap;
+    use stable_structures::memory_manager::MemoryId;
+
+    #[test]
+    fn governance_proto_round_trips_through_stable_memory() {
+        let memory = HeapMemory::default();
+        let proto = GovernanceProto {
+            economics: Some(NetworkEconomics::with_default_values()),
+            neurons: hashmap! {
+                1 => Neuron {
+                    id: Some(NeuronId { id: 1 }),
+                    ..Default::default()
+                },
+            },
+            ..Default::default()
+        };
+
+        save_governance_proto(&memory, &proto);
+
+        assert_eq!(load_governance_proto(&memory).unwrap(), proto);
+    }
+
+    #[test]
+    fn saving_a_smaller_proto_replaces_a_larger_one() {
+        let memory = HeapMemory::default();
+        let large = GovernanceProto {
+            short_voting_period_seconds: 1,
+            default_followees: (0..100).map(|topic| (topic, Default::default())).collect(),
+            ..Default::default()
+        };
+        let small = GovernanceProto {
+            short_voting_period_seconds: 2,
+            ..Default::default()
+        };
+
+        save_governance_proto(&memory, &large);
+        save_governance_proto(&memory, &small);
+
+        assert_eq!(load_governance_proto(&memory).unwrap(), small);
+    }
+
+    #[test]
+    fn state_saved_with_the_dfn_core_layout_can_be_loaded() {
+        let memory = HeapMemory::default();
+        let proto = GovernanceProto {
+            short_voting_period_seconds: 3,
+            ..Default::default()
+        };
+        let bytes = proto.encode_to_vec();
+        memory.grow(1);
+        memory.write(0, &(bytes.len() as u32).to_le_bytes());
+        memory.write(4, &bytes);
+
+        assert_eq!(load_governance_proto(&memory).unwrap(), proto);
+    }
+
+    #[test]
+    fn legacy_state_is_read_before_the_memory_manager_takes_over() {
+        let memory = HeapMemory::default();
+        assert_eq!(load_legacy_governance_proto(&memory), None);
+
+        // A legacy image larger than the first bucket of the memory manager.
+        let legacy = GovernanceProto {
+            neurons: (1..=20_000)
+                .map(|id| {
+                    (
+                        id,
+                        Neuron {
+                            id: Some(NeuronId { id }),
+                            account: vec![7; 32],
+                            ..Default::default()
+                        },
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        };
+        save_governance_proto(&memory, &legacy);
+        assert_eq!(load_legacy_governance_proto(&memory), Some(Ok(legacy)));
+
+        let memory_manager = MemoryManager::init(memory.clone());
+        assert_eq!(load_legacy_governance_proto(&memory), None);
+
+        // The regions now grow independently, past any fixed size.
+        let upgrades = memory_manager.get(MemoryId::new(0));
+        let neurons = memory_manager.get(MemoryId::new(2));
+        let proto = GovernanceProto {
+            short_voting_period_seconds: 4,
+            ..Default::default()
+        };
+        save_governance_proto(&upgrades, &proto);
+        neurons.grow(1);
+        neurons.write(0, &[0xff; WASM_PAGE_SIZE_BYTES as usize]);
+        assert_eq!(load_governance_proto(&upgrades).unwrap(), proto);
+    }
+
+    #[test]
+    fn empty_memory_loads_an_empty_proto() {
+        assert_eq!(
+            load_governance_proto(&HeapMemory::default()).unwrap(),
+            GovernanceProto::default()
+        );
+    }
+}
@@ -2,6 +2,7 @@
 //! the heap cannot grow very much.
 use assert_matches::assert_matches;
 use async_trait::async_trait;
+use candid::Encode;
 use futures::future::FutureExt;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_nervous_system_common::{ledger::Ledger, NervousSystemError};
@@ -179,7 +180,7 @@ fn test_can_submit_nns_canister_upgrade_in_degraded_mode() {
                 summary: "proposal 1".to_string(),
                 action: Some(proposal::Action::ExecuteNnsFunction(ExecuteNnsFunction {
                     nns_function: NnsFunction::NnsCanisterUpgrade as i32,
-                    payload: Vec::new(),
+                    payload: Encode!().unwrap(),
                 })),
                 ..Default::default()
             },
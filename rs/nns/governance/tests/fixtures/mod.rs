@@ -83,8 +83,10 @@ use ic_nns_governance::governance::{
 };
 use ic_nns_governance::pb::v1::governance::GovernanceCachedMetrics;
 use ic_nns_governance::pb::v1::governance_error::ErrorType::{NotFound, ResourceExhausted};
-use ic_nns_governance::pb::v1::manage_neuron::MergeMaturity;
-use ic_nns_governance::pb::v1::manage_neuron_response::MergeMaturityResponse;
+use ic_nns_governance::pb::v1::manage_neuron::{MergeMaturity, StakeMaturity};
+use ic_nns_governance::pb::v1::manage_neuron_response::{
+    MergeMaturityResponse, StakeMaturityResponse,
+};
 use ic_nns_governance::pb::v1::proposal::Action;
 use ic_nns_governance::pb::v1::ProposalRewardStatus::{AcceptVotes, ReadyToSettle};
 use ic_nns_governance::pb::v1::ProposalStatus::Rejected;
@@ -668,7 +670,10 @@ impl NNS {
                 .ledger
                 .accounts
                 .clone(),
-            governance_proto: self.governance.proto.clone(),
+            governance_proto: GovernanceProto {
+                neurons: self.governance.neuron_store.clone_neurons(),
+                ..self.governance.proto.clone()
+            },
             latest_gc_num_proposals: self.governance.latest_gc_num_proposals,
         }
     }
@@ -786,6 +791,36 @@ impl NNS {
         }
     }
 
+    pub fn stake_maturity(
+        &mut self,
+        id: &NeuronId,
+        controller: &PrincipalId,
+        percentage_to_stake: Option<u32>,
+    ) -> Result<StakeMaturityResponse, GovernanceError> {
+        let result = self
+            .governance
+            .manage_neuron(
+                controller,
+                &ManageNeuron {
+                    id: None,
+                    neuron_id_or_subaccount: Some(NeuronIdOrSubaccount::NeuronId(id.clone())),
+                    command: Some(Command::StakeMaturity(StakeMaturity {
+                        percentage_to_stake,
+                    })),
+                },
+            )
+            .now_or_never()
+            .unwrap()
+            .command
+            .unwrap();
+
+        match result {
+            manage_neuron_response::Command::Error(e) => Err(e),
+            manage_neuron_response::Command::StakeMaturity(response) => Ok(response),
+            _ => panic!("Stake maturity command returned unexpected response"),
+        }
+    }
+
     pub fn merge_neurons(
         &mut self,
         target: &NeuronId,
@@ -804,7 +839,7 @@ impl NNS {
             .unwrap()
     }
 
-    pub fn get_neuron(&self, ident: &NeuronId) -> &Neuron {
+    pub fn get_neuron(&self, ident: &NeuronId) -> Neuron {
         self.governance.get_neuron(ident).unwrap()
     }
 
@@ -817,7 +852,7 @@ impl NNS {
     }
 
     pub fn get_neuron_account_id(&self, id: u64) -> AccountIdentifier {
-        LedgerBuilder::neuron_account_id(self.get_neuron(&NeuronId { id }))
+        LedgerBuilder::neuron_account_id(&self.get_neuron(&NeuronId { id }))
     }
 
     pub fn get_neuron_stake(&self, neuron: &Neuron) -> u64 {
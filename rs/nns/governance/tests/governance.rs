@@ -42,6 +42,7 @@ use ic_nns_governance::{
         manage_neuron::claim_or_refresh::{By, MemoAndController},
         manage_neuron::configure::Operation,
         manage_neuron::disburse::Amount,
+        manage_neuron::ChangeAutoStakeMaturity,
         manage_neuron::ClaimOrRefresh,
         manage_neuron::Command,
         manage_neuron::Configure,
@@ -67,9 +68,9 @@ use ic_nns_governance::{
         AddOrRemoveNodeProvider, Ballot, BallotInfo, Empty, ExecuteNnsFunction,
         Governance as GovernanceProto, GovernanceError, KnownNeuron, KnownNeuronData, ListNeurons,
         ListNeuronsResponse, ListProposalInfo, ManageNeuron, Motion, NetworkEconomics, Neuron,
-        NeuronState, NnsFunction, NodeProvider, Proposal, ProposalData, ProposalStatus,
-        RewardEvent, RewardNodeProvider, SetDefaultFollowees, SetSnsTokenSwapOpenTimeWindow, Tally,
-        Topic, Vote,
+        NeuronState, NnsFunction, NodeProvider, OpenSnsTokenSwap, Proposal, ProposalData,
+        ProposalStatus, RewardEvent, RewardNodeProvider, SetDefaultFollowees,
+        SetSnsTokenSwapOpenTimeWindow, Tally, Topic, Vote,
     },
 };
 use ic_sns_swap::pb::v1 as sns_swap_pb;
@@ -125,6 +126,7 @@ use ic_nervous_system_common::ledger::Ledger;
 
 const DEFAULT_TEST_START_TIMESTAMP_SECONDS: u64 = 999_111_000_u64;
 const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const E8S: u64 = 100_000_000;
 
 #[cfg(feature = "test")]
 fn check_proposal_status_after_voting_and_after_expiration_new(
@@ -1217,7 +1219,7 @@ fn test_cascade_following() {
 
     // The fee should now be 1 ICP since the fees are charged upfront.
     assert_eq!(
-        gov.proto.neurons.get(&1).unwrap().neuron_fees_e8s,
+        gov.neuron_store.get(1).unwrap().neuron_fees_e8s,
         100_000_000
     );
 
@@ -1228,9 +1230,8 @@ fn test_cascade_following() {
             proposal_id: Some(ProposalId { id: 1 }),
             vote: Vote::Yes as i32
         },
-        gov.proto
-            .neurons
-            .get(&1)
+        gov.neuron_store
+            .get(1)
             .unwrap()
             .recent_ballots
             .get(0)
@@ -1278,9 +1279,8 @@ fn test_cascade_following() {
             proposal_id: Some(ProposalId { id: 1 }),
             vote: Vote::Yes as i32
         },
-        gov.proto
-            .neurons
-            .get(&2)
+        gov.neuron_store
+            .get(2)
             .unwrap()
             .recent_ballots
             .get(0)
@@ -1295,7 +1295,7 @@ fn test_cascade_following() {
     );
 
     // After the proposal is accepted the Neuron 1 should have 0 fees again
-    assert_eq!(gov.proto.neurons.get(&1).unwrap().neuron_fees_e8s, 0);
+    assert_eq!(gov.neuron_store.get(1).unwrap().neuron_fees_e8s, 0);
 }
 
 /// In this scenario, we simply test that you cannot make a proposal
@@ -1418,6 +1418,85 @@ fn test_node_provider_must_be_registered() {
     .unwrap();
 }
 
+#[test]
+fn test_execute_nns_function_payload_is_rendered() {
+    let driver = fake::FakeDriver::default();
+    let mut gov = Governance::new(
+        fixture_for_following(),
+        driver.get_fake_env(),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+    let node_provider_id = PrincipalId::try_from(b"SID2".to_vec()).unwrap();
+    gov.proto.node_providers.push(NodeProvider {
+        id: Some(node_provider_id),
+        reward_account: None,
+    });
+
+    // A payload that cannot be decoded into the argument type of the called
+    // method is rejected.
+    assert_eq!(
+        ErrorType::InvalidProposal as i32,
+        gov.make_proposal(
+            &NeuronId { id: 1 },
+            // Must match neuron 1's serialized_id.
+            &PrincipalId::try_from(b"SID1".to_vec()).unwrap(),
+            &Proposal {
+                title: Some("A Reasonable Title".to_string()),
+                summary: "test".to_string(),
+                action: Some(proposal::Action::ExecuteNnsFunction(ExecuteNnsFunction {
+                    nns_function: NnsFunction::UpdateConfigOfSubnet as i32,
+                    payload: Encode!(&"not an UpdateSubnetPayload").unwrap(),
+                })),
+                ..Default::default()
+            },
+        )
+        .unwrap_err()
+        .error_type
+    );
+
+    let pid = gov
+        .make_proposal(
+            &NeuronId { id: 1 },
+            // Must match neuron 1's serialized_id.
+            &PrincipalId::try_from(b"SID1".to_vec()).unwrap(),
+            &Proposal {
+                title: Some("A Reasonable Title".to_string()),
+                summary: "test".to_string(),
+                action: Some(proposal::Action::ExecuteNnsFunction(ExecuteNnsFunction {
+                    nns_function: NnsFunction::AssignNoid as i32,
+                    payload: Encode!(&AddNodeOperatorPayload {
+                        node_provider_principal_id: Some(node_provider_id),
+                        node_allowance: 42,
+                        ..Default::default()
+                    })
+                    .unwrap(),
+                })),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let rendering = gov
+        .get_proposal_info(&PrincipalId::try_from(b"SID1".to_vec()).unwrap(), pid)
+        .unwrap()
+        .payload_text_rendering
+        .unwrap();
+    assert!(
+        rendering.contains("node_allowance: 42"),
+        "Unexpected rendering: {}",
+        rendering
+    );
+}
+
+/// Applies `update` to the neuron with the given id and writes the result back
+/// to `gov`'s neuron store.
+fn update_neuron(gov: &mut Governance, id: u64, update: impl FnOnce(&mut Neuron)) {
+    let mut neuron = gov.neuron_store.get(id).unwrap();
+    update(&mut neuron);
+    gov.neuron_store.upsert(neuron).unwrap();
+}
+
 /// In this scenario, we simply test that you cannot make a proposal
 /// if you have insufficient stake (less than the reject fee).
 #[test]
@@ -1430,11 +1509,9 @@ fn test_sufficient_stake() {
         driver.get_fake_cmc(),
     );
     // Set stake to 0.5 ICP.
-    gov.proto
-        .neurons
-        .get_mut(&1)
-        .unwrap()
-        .cached_neuron_stake_e8s = 50_000_000;
+    update_neuron(&mut gov, 1, |neuron| {
+        neuron.cached_neuron_stake_e8s = 50_000_000;
+    });
     // This should fail because the reject_cost_e8s is 1 ICP.
     assert_eq!(
         ErrorType::PreconditionFailed as i32,
@@ -1455,11 +1532,9 @@ fn test_sufficient_stake() {
         .error_type
     );
     // Set stake to 1 ICP.
-    gov.proto
-        .neurons
-        .get_mut(&1)
-        .unwrap()
-        .cached_neuron_stake_e8s = 100_000_000;
+    update_neuron(&mut gov, 1, |neuron| {
+        neuron.cached_neuron_stake_e8s = 100_000_000;
+    });
     // This should succeed because the reject_cost_e8s is 1 ICP (same as stake).
     gov.make_proposal(
         &NeuronId { id: 1 },
@@ -1592,7 +1667,9 @@ fn test_follow_negative() {
         Some(manage_neuron_response::Command::Error(err))
             if err.error_type == ErrorType::NotAuthorized as i32
     );
-    gov.proto.neurons.get_mut(&4).unwrap().controller = Some(principal(4));
+    let mut neuron = gov.neuron_store.get(4).unwrap();
+    neuron.controller = Some(principal(4));
+    gov.neuron_store.upsert(neuron).unwrap();
     fake::register_vote_assert_success(
         &mut gov,
         principal(4),
@@ -1626,7 +1703,7 @@ fn test_follow_negative() {
     );
     // Make sure that the neuron has been changed the reject fee.
     assert_eq!(
-        gov.proto.neurons.get(&1).unwrap().neuron_fees_e8s,
+        gov.neuron_store.get(1).unwrap().neuron_fees_e8s,
         gov.proto.economics.unwrap().reject_cost_e8s
     );
 }
@@ -1982,7 +2059,7 @@ fn test_manage_neuron() {
     // Make sure that the neuron has been changed the fee for manage
     // neuron proposals.
     assert_eq!(
-        gov.proto.neurons.get(&2).unwrap().neuron_fees_e8s,
+        gov.neuron_store.get(2).unwrap().neuron_fees_e8s,
         gov.proto
             .economics
             .as_ref()
@@ -1992,9 +2069,8 @@ fn test_manage_neuron() {
     // Now there should be a single followee...
     assert_eq!(
         1,
-        gov.proto
-            .neurons
-            .get_mut(&1)
+        gov.neuron_store
+            .get(1)
             .unwrap()
             .followees
             .get(&(Topic::NeuronManagement as i32))
@@ -2005,9 +2081,8 @@ fn test_manage_neuron() {
     // ... viz., neuron 2.
     assert_eq!(
         2,
-        gov.proto
-            .neurons
-            .get_mut(&1)
+        gov.neuron_store
+            .get(1)
             .unwrap()
             .followees
             .get(&(Topic::NeuronManagement as i32))
@@ -2050,9 +2125,8 @@ fn test_manage_neuron() {
     // Now there should be three followees again.
     assert_eq!(
         3,
-        gov.proto
-            .neurons
-            .get_mut(&1)
+        gov.neuron_store
+            .get(1)
             .unwrap()
             .followees
             .get(&(Topic::NeuronManagement as i32))
@@ -2063,7 +2137,7 @@ fn test_manage_neuron() {
     // Make sure that the neuron has been changed an additional fee
     // for manage neuron proposals.
     assert_eq!(
-        gov.proto.neurons.get(&2).unwrap().neuron_fees_e8s,
+        gov.neuron_store.get(2).unwrap().neuron_fees_e8s,
         2 * gov
             .proto
             .economics
@@ -2086,11 +2160,9 @@ fn test_sufficient_stake_for_manage_neuron() {
     );
     // Set stake to less than 0.01 ICP (same as
     // neuron_management_fee_per_proposal_e8s).
-    gov.proto
-        .neurons
-        .get_mut(&2)
-        .unwrap()
-        .cached_neuron_stake_e8s = 999_999;
+    update_neuron(&mut gov, 2, |neuron| {
+        neuron.cached_neuron_stake_e8s = 999_999;
+    });
     // Try to make a proposal... This should fail because the
     // neuron_management_fee_per_proposal_e8s is 0.01 ICP.
     assert_eq!(
@@ -2118,11 +2190,9 @@ fn test_sufficient_stake_for_manage_neuron() {
         .error_type
     );
     // Set stake to 2 ICP.
-    gov.proto
-        .neurons
-        .get_mut(&2)
-        .unwrap()
-        .cached_neuron_stake_e8s = 200_000_000;
+    update_neuron(&mut gov, 2, |neuron| {
+        neuron.cached_neuron_stake_e8s = 200_000_000;
+    });
     // This should now succeed.
     gov.make_proposal(
         &NeuronId { id: 2 },
@@ -3034,8 +3104,8 @@ fn test_approve_kyc() {
         driver.get_fake_ledger(),
         driver.get_fake_cmc(),
     );
-    let neuron_a = gov.proto.neurons.get(&1).unwrap().clone();
-    let neuron_b = gov.proto.neurons.get(&2).unwrap().clone();
+    let neuron_a = gov.neuron_store.get(1).unwrap();
+    let neuron_b = gov.neuron_store.get(2).unwrap();
 
     let principal1 = *neuron_a.controller.as_ref().unwrap();
     let principal2 = *neuron_b.controller.as_ref().unwrap();
@@ -3077,17 +3147,17 @@ fn test_approve_kyc() {
         "Neuron is not kyc verified: 2"
     );
 
-    assert!(!gov.proto.neurons.get(&1).unwrap().kyc_verified);
-    assert!(!gov.proto.neurons.get(&2).unwrap().kyc_verified);
-    assert!(!gov.proto.neurons.get(&3).unwrap().kyc_verified);
-    assert!(!gov.proto.neurons.get(&4).unwrap().kyc_verified);
+    assert!(!gov.neuron_store.get(1).unwrap().kyc_verified);
+    assert!(!gov.neuron_store.get(2).unwrap().kyc_verified);
+    assert!(!gov.neuron_store.get(3).unwrap().kyc_verified);
+    assert!(!gov.neuron_store.get(4).unwrap().kyc_verified);
 
     gov.approve_genesis_kyc(&[principal1, principal2]);
 
-    assert!(gov.proto.neurons.get(&1).unwrap().kyc_verified);
-    assert!(gov.proto.neurons.get(&2).unwrap().kyc_verified);
-    assert!(gov.proto.neurons.get(&3).unwrap().kyc_verified);
-    assert!(!gov.proto.neurons.get(&4).unwrap().kyc_verified);
+    assert!(gov.neuron_store.get(1).unwrap().kyc_verified);
+    assert!(gov.neuron_store.get(2).unwrap().kyc_verified);
+    assert!(gov.neuron_store.get(3).unwrap().kyc_verified);
+    assert!(!gov.neuron_store.get(4).unwrap().kyc_verified);
 
     // Disbursing should now work.
     let _ = gov
@@ -3262,9 +3332,9 @@ fn governance_with_staked_neuron(
         claim_or_refresh_neuron_by_memo(&mut gov, &from, None, to_subaccount, Memo(nonce), None)
             .unwrap();
 
-    assert_eq!(gov.proto.neurons.len(), 1);
+    assert_eq!(gov.neuron_store.len(), 1);
 
-    let neuron = gov.proto.neurons.get_mut(&nid.id).unwrap();
+    let mut neuron = gov.get_neuron(&nid).unwrap();
     neuron
         .configure(
             &from,
@@ -3276,6 +3346,7 @@ fn governance_with_staked_neuron(
             },
         )
         .unwrap();
+    gov.neuron_store.upsert(neuron).unwrap();
 
     (driver, gov, nid, to_subaccount)
 }
@@ -3303,7 +3374,7 @@ fn create_mature_neuron(dissolved: bool) -> (fake::FakeDriver, Governance, Neuro
 
     // Make sure the neuron was created with the right details.
     assert_eq!(
-        gov.proto.neurons.get(&id.id).unwrap(),
+        gov.neuron_store.get(id.id).unwrap(),
         &Neuron {
             id: Some(id.clone()),
             account: to_subaccount.to_vec(),
@@ -3318,7 +3389,7 @@ fn create_mature_neuron(dissolved: bool) -> (fake::FakeDriver, Governance, Neuro
     );
     assert_eq!(gov.get_neuron_ids_by_principal(&from), vec![id.id]);
 
-    let neuron = gov.proto.neurons.get_mut(&id.id).unwrap();
+    let mut neuron = gov.get_neuron(&id).unwrap();
 
     // Dissolve the neuron if `dissolved` is true
     if dissolved {
@@ -3351,9 +3422,9 @@ fn create_mature_neuron(dissolved: bool) -> (fake::FakeDriver, Governance, Neuro
     // .. and some maturity to collect.
     neuron.maturity_e8s_equivalent = neuron_maturity;
 
-    let n = neuron.clone();
+    gov.neuron_store.upsert(neuron.clone()).unwrap();
 
-    (driver, gov, n)
+    (driver, gov, neuron)
 }
 
 #[test]
@@ -3464,11 +3535,7 @@ fn test_nns1_520() {
     );
 
     assert_eq!(
-        gov.proto
-            .neurons
-            .get(&id.id)
-            .unwrap()
-            .cached_neuron_stake_e8s,
+        gov.neuron_store.get(id.id).unwrap().cached_neuron_stake_e8s,
         0
     );
 }
@@ -3797,7 +3864,7 @@ fn refresh_neuron_by_id_or_subaccount(
     );
 
     // stake shouldn't have changed.
-    let neuron = gov.get_neuron(&nid).unwrap().clone();
+    let neuron = gov.get_neuron(&nid).unwrap();
     assert_eq!(neuron.cached_neuron_stake_e8s, stake.get_e8s());
 
     let neuron_id_or_subaccount = match refresh_by {
@@ -3864,7 +3931,7 @@ fn test_refresh_neuron_by_subaccount_by_proxy() {
 fn test_claim_or_refresh_neuron_does_not_overflow() {
     let (mut driver, mut gov, neuron) = create_mature_neuron(true);
     let nid = neuron.id.unwrap();
-    let neuron = gov.get_neuron_mut(&nid).unwrap();
+    let mut neuron = gov.get_neuron(&nid).unwrap();
     let _account = neuron.account.clone();
     let subaccount = subaccount_from_slice(&neuron.account).unwrap().unwrap();
 
@@ -3891,6 +3958,7 @@ fn test_claim_or_refresh_neuron_does_not_overflow() {
         neuron.aging_since_timestamp_seconds,
         driver.now() - 12 * ic_nns_governance::governance::ONE_MONTH_SECONDS - 1,
     );
+    gov.neuron_store.upsert(neuron).unwrap();
 
     let _block_height = 543212234;
     // Note that the nonce must match the nonce chosen in the original
@@ -3916,7 +3984,7 @@ fn test_claim_or_refresh_neuron_does_not_overflow() {
     .unwrap();
 
     assert_eq!(nid_result, nid);
-    let neuron = gov.get_neuron_mut(&nid).unwrap();
+    let neuron = gov.get_neuron(&nid).unwrap();
     assert_eq!(neuron.cached_neuron_stake_e8s, 100_000_100_000_000);
 }
 
@@ -4062,7 +4130,7 @@ fn test_cant_disburse_without_paying_fees() {
     assert!(result.is_err());
     assert_eq!(result.unwrap_err().error_type(), ErrorType::External);
 
-    assert_eq!(0, gov.proto.neurons.get(&id.id).unwrap().neuron_fees_e8s);
+    assert_eq!(0, gov.neuron_store.get(id.id).unwrap().neuron_fees_e8s);
     driver.assert_account_contains(
         &AccountIdentifier::new(
             GOVERNANCE_CANISTER_ID.get(),
@@ -4146,7 +4214,7 @@ fn test_neuron_split_fails() {
         nonce,
     );
 
-    let neuron = gov.proto.neurons.get_mut(&id.id).unwrap();
+    let neuron = gov.get_neuron(&id).unwrap();
     let transaction_fee = gov.proto.economics.as_ref().unwrap().transaction_fee_e8s;
     let min_neuron_stake = gov
         .proto
@@ -4160,7 +4228,7 @@ fn test_neuron_split_fails() {
         NeuronState::NotDissolving
     );
 
-    let neuron_before = neuron.clone();
+    let neuron_before = neuron;
 
     // 1. Attempt to split a neuron that does not exist
 
@@ -4232,9 +4300,9 @@ fn test_neuron_split_fails() {
            if code == InsufficientFunds as i32 && msg.to_lowercase().contains("at the minimum, one needs the minimum neuron stake"));
 
     // Parent neuron did not change
-    assert_eq!(*gov.get_neuron(&id).unwrap(), neuron_before);
+    assert_eq!(gov.get_neuron(&id).unwrap(), neuron_before);
     // There is still only one neuron
-    assert_eq!(gov.proto.neurons.len(), 1);
+    assert_eq!(gov.neuron_store.len(), 1);
     //  There is still only one ledger account.
     driver.assert_num_neuron_accounts_exist(1);
 }
@@ -4257,7 +4325,7 @@ fn test_neuron_split() {
         nonce,
     );
 
-    let neuron = gov.proto.neurons.get_mut(&id.id).unwrap();
+    let neuron = gov.get_neuron(&id).unwrap();
     let transaction_fee = gov.proto.economics.as_ref().unwrap().transaction_fee_e8s;
 
     assert_eq!(
@@ -4278,7 +4346,7 @@ fn test_neuron_split() {
         .unwrap();
 
     // We should now have 2 neurons.
-    assert_eq!(gov.proto.neurons.len(), 2);
+    assert_eq!(gov.neuron_store.len(), 2);
     // And we should have two ledger accounts.
     driver.assert_num_neuron_accounts_exist(2);
 
@@ -5083,7 +5151,7 @@ fn test_neuron_spawn() {
         nonce,
     );
 
-    let neuron = gov.get_neuron_mut(&id).expect("Neuron did not exist");
+    let mut neuron = gov.get_neuron(&id).expect("Neuron did not exist");
 
     assert_eq!(
         neuron.get_neuron_info(driver.now()).state(),
@@ -5101,6 +5169,7 @@ fn test_neuron_spawn() {
     // An attempt to spawn a neuron should simply return an error and
     // change nothing.
     let neuron_before = neuron.clone();
+    gov.neuron_store.upsert(neuron).unwrap();
     let spawn_res = gov
         .spawn_neuron(
             &id,
@@ -5117,16 +5186,17 @@ fn test_neuron_spawn() {
         spawn_res,
         Err(GovernanceError{error_type: code, error_message: msg})
             if code == InsufficientFunds as i32 && msg.to_lowercase().contains("maturity"));
-    assert_eq!(*gov.get_neuron(&id).unwrap(), neuron_before);
+    assert_eq!(gov.get_neuron(&id).unwrap(), neuron_before);
 
     // Artificially set the neuron's maturity to sufficient value
-    let neuron = gov.get_neuron_mut(&id).expect("Neuron did not exist");
+    let mut neuron = gov.get_neuron(&id).expect("Neuron did not exist");
     let parent_maturity_e8s_equivalent: u64 = 123_456_789;
     assert!(
         parent_maturity_e8s_equivalent
             > NetworkEconomics::with_default_values().neuron_minimum_stake_e8s
     );
     neuron.maturity_e8s_equivalent = parent_maturity_e8s_equivalent;
+    gov.neuron_store.upsert(neuron).unwrap();
 
     // Advance the time so that we can check that the spawned neuron has the age
     // and the right creation timestamp
@@ -5147,14 +5217,13 @@ fn test_neuron_spawn() {
         .unwrap();
 
     // We should now have 2 neurons.
-    assert_eq!(gov.proto.neurons.len(), 2);
+    assert_eq!(gov.neuron_store.len(), 2);
     // .. but only one ledger account since the neuron's maturity hasn't been minted yet.
     driver.assert_num_neuron_accounts_exist(1);
 
     let child_neuron = gov
         .get_neuron(&child_nid)
-        .expect("The child neuron is missing")
-        .clone();
+        .expect("The child neuron is missing");
     let parent_neuron = gov.get_neuron(&id).expect("The parent neuron is missing");
     let child_subaccount = child_neuron.account.clone();
 
@@ -5200,8 +5269,7 @@ fn test_neuron_spawn() {
 
     let child_neuron = gov
         .get_neuron(&child_nid)
-        .expect("The child neuron is missing")
-        .clone();
+        .expect("The child neuron is missing");
     assert_eq!(
         child_neuron,
         Neuron {
@@ -5246,7 +5314,7 @@ fn test_neuron_spawn_with_subaccount() {
         nonce,
     );
 
-    let neuron = gov.get_neuron_mut(&id).expect("Neuron did not exist");
+    let mut neuron = gov.get_neuron(&id).expect("Neuron did not exist");
 
     assert_eq!(
         neuron.get_neuron_info(driver.now()).state(),
@@ -5264,6 +5332,7 @@ fn test_neuron_spawn_with_subaccount() {
     // An attempt to spawn a neuron should simply return an error and
     // change nothing.
     let neuron_before = neuron.clone();
+    gov.neuron_store.upsert(neuron).unwrap();
     let spawn_res = gov
         .spawn_neuron(
             &id,
@@ -5280,16 +5349,17 @@ fn test_neuron_spawn_with_subaccount() {
         spawn_res,
         Err(GovernanceError{error_type: code, error_message: msg})
             if code == InsufficientFunds as i32 && msg.to_lowercase().contains("maturity"));
-    assert_eq!(*gov.get_neuron(&id).unwrap(), neuron_before);
+    assert_eq!(gov.get_neuron(&id).unwrap(), neuron_before);
 
     // Artificially set the neuron's maturity to sufficient value
-    let neuron = gov.get_neuron_mut(&id).expect("Neuron did not exist");
+    let mut neuron = gov.get_neuron(&id).expect("Neuron did not exist");
     let parent_maturity_e8s_equivalent: u64 = 123_456_789;
     assert!(
         parent_maturity_e8s_equivalent
             > NetworkEconomics::with_default_values().neuron_minimum_stake_e8s
     );
     neuron.maturity_e8s_equivalent = parent_maturity_e8s_equivalent;
+    gov.neuron_store.upsert(neuron).unwrap();
 
     // Advance the time so that we can check that the spawned neuron has the age
     // and the right creation timestamp
@@ -5315,7 +5385,7 @@ fn test_neuron_spawn_with_subaccount() {
     let creation_timestamp = driver.now();
 
     // We should now have 2 neurons.
-    assert_eq!(gov.proto.neurons.len(), 2);
+    assert_eq!(gov.neuron_store.len(), 2);
     // And we should have one ledger accounts.
     driver.assert_num_neuron_accounts_exist(1);
 
@@ -5335,8 +5405,7 @@ fn test_neuron_spawn_with_subaccount() {
 
     let child_neuron = gov
         .get_neuron(&child_nid)
-        .expect("The child neuron is missing")
-        .clone();
+        .expect("The child neuron is missing");
     let child_subaccount = child_neuron.account.clone();
 
     // Verify that the sub-account was created according to spawn input.
@@ -5421,7 +5490,7 @@ fn assert_neuron_spawn_partial(
         nonce,
     );
 
-    let neuron = gov.get_neuron_mut(&id).expect("Neuron did not exist");
+    let neuron = gov.get_neuron(&id).expect("Neuron did not exist");
     assert_eq!(
         neuron.get_neuron_info(driver.now()).state(),
         NeuronState::NotDissolving
@@ -5431,17 +5500,18 @@ fn assert_neuron_spawn_partial(
 
     // An attempt to spawn a neuron should simply return an error and
     // change nothing.
-    let neuron_before = neuron.clone();
-    assert_eq!(*gov.get_neuron(&id).unwrap(), neuron_before);
+    let neuron_before = neuron;
+    assert_eq!(gov.get_neuron(&id).unwrap(), neuron_before);
 
     // Artificially set the neuron's maturity to sufficient value
-    let neuron = gov.get_neuron_mut(&id).expect("Neuron did not exist");
+    let mut neuron = gov.get_neuron(&id).expect("Neuron did not exist");
     let parent_maturity_e8s_equivalent: u64 = parent_maturity;
     assert!(
         parent_maturity_e8s_equivalent
             > NetworkEconomics::with_default_values().neuron_minimum_stake_e8s
     );
     neuron.maturity_e8s_equivalent = parent_maturity_e8s_equivalent;
+    gov.neuron_store.upsert(neuron).unwrap();
 
     // Advance the time so that we can check that the spawned neuron has the age
     // and the right creation timestamp
@@ -5465,17 +5535,14 @@ fn assert_neuron_spawn_partial(
     let creation_timestamp = driver.now();
 
     // We should now have 2 neurons.
-    assert_eq!(gov.proto.neurons.len(), 2);
+    assert_eq!(gov.neuron_store.len(), 2);
     // And we should have 1 ledger accounts.
     driver.assert_num_neuron_accounts_exist(1);
 
     let child_neuron = gov
         .get_neuron(&child_nid)
         .expect("The child neuron is missing");
-    let parent_neuron = gov
-        .get_neuron(&id)
-        .expect("The parent neuron is missing")
-        .clone();
+    let parent_neuron = gov.get_neuron(&id).expect("The parent neuron is missing");
     let child_subaccount = child_neuron.account.clone();
 
     // Running periodic tasks shouldn't cause the ICP to be minted.
@@ -5496,8 +5563,7 @@ fn assert_neuron_spawn_partial(
 
     let child_neuron = gov
         .get_neuron(&child_nid)
-        .expect("The child neuron is missing")
-        .clone();
+        .expect("The child neuron is missing");
 
     assert_eq!(
         child_neuron,
@@ -5545,8 +5611,9 @@ fn test_neuron_with_non_self_authenticating_controller_cannot_be_spawned() {
         nonce,
     );
 
-    let neuron = gov.get_neuron_mut(&id).expect("Neuron did not exist");
+    let mut neuron = gov.get_neuron(&id).expect("Neuron did not exist");
     neuron.maturity_e8s_equivalent = 123_456_789;
+    gov.neuron_store.upsert(neuron).unwrap();
 
     let non_self_authenticating_principal_id = PrincipalId::new_user_test_id(144);
 
@@ -5587,7 +5654,7 @@ fn test_disburse_to_neuron() {
         nonce,
     );
 
-    let parent_neuron = gov.proto.neurons.get_mut(&id.id).unwrap();
+    let mut parent_neuron = gov.get_neuron(&id).unwrap();
     let transaction_fee = gov.proto.economics.as_ref().unwrap().transaction_fee_e8s;
 
     // Now Set the neuron to start dissolving
@@ -5624,6 +5691,7 @@ fn test_disburse_to_neuron() {
         parent_neuron.get_neuron_info(driver.now()).state(),
         NeuronState::Dissolved
     );
+    gov.neuron_store.upsert(parent_neuron).unwrap();
 
     let child_controller = *TEST_NEURON_2_OWNER_PRINCIPAL;
 
@@ -5644,7 +5712,7 @@ fn test_disburse_to_neuron() {
         .unwrap();
 
     // We should now have 2 neurons.
-    assert_eq!(gov.proto.neurons.len(), 2);
+    assert_eq!(gov.neuron_store.len(), 2);
     // And we should have two ledger accounts.
     driver.assert_num_neuron_accounts_exist(2);
 
@@ -5719,7 +5787,7 @@ fn governance_with_neurons(neurons: &[Neuron]) -> (fake::FakeDriver, Governance)
         driver.get_fake_ledger(),
         driver.get_fake_cmc(),
     );
-    assert_eq!(gov.proto.neurons.len(), 3);
+    assert_eq!(gov.neuron_store.len(), 3);
     (driver, gov)
 }
 
@@ -5877,9 +5945,8 @@ fn test_add_and_remove_hot_key() {
     let new_controller = init_neurons[&42].controller.unwrap();
 
     assert!(!gov
-        .principal_to_neuron_ids_index
-        .get(&new_controller)
-        .unwrap()
+        .neuron_store
+        .neuron_ids_by_principal(&new_controller)
         .contains(&neuron.id.as_ref().unwrap().id));
     // Add a hot key to the neuron and make sure that gets reflected in the
     // principal to neuron ids index.
@@ -5907,9 +5974,8 @@ fn test_add_and_remove_hot_key() {
 
     assert!(result.is_ok());
     assert!(gov
-        .principal_to_neuron_ids_index
-        .get(&new_controller)
-        .unwrap()
+        .neuron_store
+        .neuron_ids_by_principal(&new_controller)
         .contains(&neuron.id.as_ref().unwrap().id));
 
     // Remove a hot key from that neuron and make sure that gets reflected in
@@ -5938,9 +6004,8 @@ fn test_add_and_remove_hot_key() {
 
     assert!(result.is_ok());
     assert!(!gov
-        .principal_to_neuron_ids_index
-        .get(&new_controller)
-        .unwrap()
+        .neuron_store
+        .neuron_ids_by_principal(&new_controller)
         .contains(&neuron.id.as_ref().unwrap().id));
 }
 
@@ -6233,11 +6298,10 @@ fn test_manage_and_reward_node_providers() {
         ProposalStatus::Executed
     );
     // Find the neuron...
-    let (_, neuron) = gov
-        .proto
-        .neurons
-        .iter()
-        .find(|(_, x)| x.controller == Some(np_pid))
+    let neuron = gov
+        .neuron_store
+        .values()
+        .find(|x| x.controller == Some(np_pid))
         .unwrap();
     assert_eq!(neuron.stake_e8s(), 99_999_999);
     // Find the transaction in the ledger...
@@ -6544,11 +6608,10 @@ fn test_manage_and_reward_multiple_node_providers() {
 
     // Check third reward
     // Find the neuron...
-    let (_, neuron) = gov
-        .proto
-        .neurons
-        .iter()
-        .find(|(_, x)| x.controller == Some(np_pid_2))
+    let neuron = gov
+        .neuron_store
+        .values()
+        .find(|x| x.controller == Some(np_pid_2))
         .unwrap();
     assert_eq!(neuron.stake_e8s(), 99_999_999);
     // Find the transaction in the ledger...
@@ -7824,7 +7887,7 @@ fn test_max_number_of_proposals_with_ballots() {
                 summary: "NnsCanisterUpgrade should go through despite the limit".to_string(),
                 action: Some(proposal::Action::ExecuteNnsFunction(ExecuteNnsFunction {
                     nns_function: NnsFunction::NnsCanisterUpgrade as i32,
-                    payload: Vec::new(),
+                    payload: Encode!().unwrap(),
                 })),
                 ..Default::default()
             },
@@ -8068,7 +8131,7 @@ fn assert_merge_maturity_executes_as_expected_new(
     percentage_to_merge: u32,
     expected_merged_maturity: u64,
 ) {
-    let neuron = nns.get_neuron(id).clone();
+    let neuron = nns.get_neuron(id);
     let response = nns
         .merge_maturity(id, controller, percentage_to_merge)
         .unwrap();
@@ -8228,8 +8291,9 @@ fn test_merge_maturity_of_neuron(
     prop_assert_eq!(neuron_stake_e8s, account_balance);
 
     {
-        let neuron = gov.get_neuron_mut(&id).unwrap();
+        let mut neuron = gov.get_neuron(&id).unwrap();
         neuron.maturity_e8s_equivalent = starting_maturity;
+        gov.neuron_store.upsert(neuron).unwrap();
     }
 
     // Assert that maturity can't be merged by someone who doesn't control the
@@ -8295,7 +8359,7 @@ fn assert_merge_maturity_executes_as_expected(
     expected_merged_maturity: u64,
     driver: &fake::FakeDriver,
 ) -> std::result::Result<(), TestCaseError> {
-    let neuron = gov.get_neuron(&id).unwrap().clone();
+    let neuron = gov.get_neuron(&id).unwrap();
     let account = AccountIdentifier::new(
         ic_base_types::PrincipalId::from(GOVERNANCE_CANISTER_ID),
         Some(Subaccount::try_from(neuron.account.as_slice()).unwrap()),
@@ -8362,6 +8426,160 @@ fn merge_maturity(
     }
 }
 
+#[test]
+fn test_stake_maturity_of_neuron() {
+    let mut nns = NNSBuilder::new()
+        .add_neuron(
+            NeuronBuilder::new(100, 1_000_000_000, principal(1))
+                .set_dissolve_delay(MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS)
+                .set_maturity(200_000_000),
+        )
+        .create();
+
+    let id = NeuronId { id: 100 };
+    let controller = principal(1);
+
+    // Assert that maturity can't be staked by someone who doesn't control
+    // the neuron.
+    assert_matches!(
+        nns.stake_maturity(&id, &principal(2), Some(10)),
+        Err(e) if e.error_type == NotAuthorized as i32
+    );
+
+    // Assert percents outside of (0, 100] are rejected.
+    assert!(nns.stake_maturity(&id, &controller, Some(0)).is_err());
+    assert!(nns.stake_maturity(&id, &controller, Some(101)).is_err());
+
+    // Stake a quarter of the maturity, then all of what remains.
+    let response = nns.stake_maturity(&id, &controller, Some(25)).unwrap();
+    assert_eq!(response.maturity_e8s, 150_000_000);
+    assert_eq!(response.staked_maturity_e8s, 50_000_000);
+
+    let response = nns.stake_maturity(&id, &controller, None).unwrap();
+    assert_eq!(response.maturity_e8s, 0);
+    assert_eq!(response.staked_maturity_e8s, 200_000_000);
+
+    // Staking maturity doesn't change the stake of the neuron, but the
+    // staked maturity counts towards its voting power.
+    let neuron = nns.get_neuron(&id);
+    assert_eq!(neuron.cached_neuron_stake_e8s, 1_000_000_000);
+    assert_eq!(neuron.maturity_e8s_equivalent, 0);
+    assert_eq!(neuron.staked_maturity_e8s_equivalent, Some(200_000_000));
+    let now = nns.now();
+    assert_eq!(
+        neuron.voting_power(now),
+        Neuron {
+            cached_neuron_stake_e8s: 1_200_000_000,
+            staked_maturity_e8s_equivalent: None,
+            ..neuron.clone()
+        }
+        .voting_power(now)
+    );
+}
+
+#[test]
+fn test_staked_maturity_is_unstaked_when_neuron_is_dissolved() {
+    let mut nns = NNSBuilder::new()
+        .add_neuron(
+            NeuronBuilder::new(100, 1_000_000_000, principal(1))
+                .set_dissolve_delay(ONE_DAY_SECONDS)
+                .start_dissolving(DEFAULT_TEST_START_TIMESTAMP_SECONDS)
+                .set_maturity(200_000_000),
+        )
+        .create();
+
+    let id = NeuronId { id: 100 };
+    nns.stake_maturity(&id, &principal(1), None).unwrap();
+
+    // While the neuron is dissolving, its maturity remains staked.
+    nns.run_periodic_tasks();
+    let neuron = nns.get_neuron(&id);
+    assert_eq!(neuron.maturity_e8s_equivalent, 0);
+    assert_eq!(neuron.staked_maturity_e8s_equivalent, Some(200_000_000));
+
+    // Once the neuron is dissolved, its staked maturity is moved back to its
+    // maturity.
+    nns.advance_time_by(ONE_DAY_SECONDS + 1);
+    nns.run_periodic_tasks();
+    let neuron = nns.get_neuron(&id);
+    assert_eq!(neuron.maturity_e8s_equivalent, 200_000_000);
+    assert_eq!(neuron.staked_maturity_e8s_equivalent, None);
+}
+
+#[test]
+fn test_voting_rewards_are_staked_if_auto_stake_maturity_is_enabled() {
+    let mut fake_driver = fake::FakeDriver::default()
+        // The reward supply for the first day is 100 (365_250 * 10% / 365.25 = 100).
+        .with_supply(Tokens::from_e8s(365_250));
+
+    let fixture = GovernanceProto {
+        neurons: [3, 1]
+            .iter()
+            .enumerate()
+            .map(|(i, stake_e8s)| {
+                (
+                    i as u64,
+                    Neuron {
+                        id: Some(NeuronId { id: i as u64 }),
+                        controller: Some(principal(i as u64)),
+                        cached_neuron_stake_e8s: *stake_e8s,
+                        dissolve_state: NOTDISSOLVING_MIN_DISSOLVE_DELAY_TO_VOTE,
+                        account: fake_driver.get_fake_env().random_byte_array().to_vec(),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect(),
+        wait_for_quiet_threshold_seconds: 10,
+        economics: Some(NetworkEconomics::default()),
+        ..Default::default()
+    };
+
+    let mut gov = Governance::new(
+        fixture,
+        fake_driver.get_fake_env(),
+        fake_driver.get_fake_ledger(),
+        fake_driver.get_fake_cmc(),
+    );
+
+    // Enable auto-staking of maturity for the first neuron only.
+    gov.manage_neuron(
+        &principal(0),
+        &ManageNeuron {
+            id: None,
+            neuron_id_or_subaccount: Some(NeuronIdOrSubaccount::NeuronId(NeuronId { id: 0 })),
+            command: Some(Command::Configure(Configure {
+                operation: Some(Operation::ChangeAutoStakeMaturity(
+                    ChangeAutoStakeMaturity {
+                        requested_setting_for_auto_stake_maturity: true,
+                    },
+                )),
+            })),
+        },
+    )
+    .now_or_never()
+    .unwrap()
+    .expect("Configuring auto-stake maturity failed");
+    assert_eq!(
+        gov.get_neuron(&NeuronId { id: 0 })
+            .unwrap()
+            .auto_stake_maturity,
+        Some(true)
+    );
+
+    fake::ProposalNeuronBehavior::from("Py").propose_and_vote(&mut gov, "proposal".to_string());
+    fake_driver.advance_time_by(REWARD_DISTRIBUTION_PERIOD_SECONDS);
+    gov.run_periodic_tasks().now_or_never();
+
+    let neuron_0 = gov.get_neuron(&NeuronId { id: 0 }).unwrap();
+    assert_eq!(neuron_0.maturity_e8s_equivalent, 0);
+    assert_eq!(neuron_0.staked_maturity_e8s_equivalent, Some(75));
+    let neuron_1 = gov.get_neuron(&NeuronId { id: 1 }).unwrap();
+    assert_eq!(neuron_1.maturity_e8s_equivalent, 25);
+    assert_eq!(neuron_1.staked_maturity_e8s_equivalent, None);
+    assert_eq!(gov.latest_reward_event().distributed_e8s_equivalent, 100);
+}
+
 #[test]
 fn test_update_stake() {
     // Assert that doubling a neuron's stake halves its age
@@ -8554,11 +8772,11 @@ fn test_compute_cached_metrics() {
 
     let gov = GovernanceProto {
         economics: Some(economics),
-        neurons,
         ..Default::default()
     };
 
-    let actual_metrics = gov.compute_cached_metrics(now, Tokens::new(147, 0).unwrap());
+    let actual_metrics =
+        gov.compute_cached_metrics(neurons.values().cloned(), now, Tokens::new(147, 0).unwrap());
 
     let expected_metrics = GovernanceCachedMetrics {
         timestamp_seconds: 100,
@@ -8833,7 +9051,7 @@ fn test_stop_dissolving_panics() {
 fn test_update_node_provider() {
     let (_, mut gov, neuron) = create_mature_neuron(false);
     let id = neuron.id.unwrap();
-    let neuron = gov.get_neuron(&id).unwrap().clone();
+    let neuron = gov.get_neuron(&id).unwrap();
     let controller = neuron.controller.unwrap();
     let account = AccountIdentifier::new(
         ic_base_types::PrincipalId::from(GOVERNANCE_CANISTER_ID),
@@ -9110,7 +9328,9 @@ fn test_join_community_fund() {
         driver.get_fake_cmc(),
     );
     {
-        let actual_metrics = gov.proto.compute_cached_metrics(now, total_icp_suppply);
+        let actual_metrics =
+            gov.proto
+                .compute_cached_metrics(gov.neuron_store.values(), now, total_icp_suppply);
         assert_eq!(200, actual_metrics.total_supply_icp);
         assert_eq!(130 * 100_000_000, actual_metrics.total_staked_e8s);
         assert_eq!(0, actual_metrics.community_fund_total_staked_e8s);
@@ -9156,7 +9376,9 @@ fn test_join_community_fund() {
             .now_or_never()
             .unwrap();
         assert!(result.is_ok());
-        let actual_metrics = gov.proto.compute_cached_metrics(now, total_icp_suppply);
+        let actual_metrics =
+            gov.proto
+                .compute_cached_metrics(gov.neuron_store.values(), now, total_icp_suppply);
         assert_eq!(200, actual_metrics.total_supply_icp);
         assert_eq!(130 * 100_000_000, actual_metrics.total_staked_e8s);
         assert_eq!(
@@ -9166,9 +9388,8 @@ fn test_join_community_fund() {
         // 30 days in now
         assert_eq!(
             60 * 60 * 24 * 30,
-            gov.proto
-                .neurons
-                .get(&3)
+            gov.neuron_store
+                .get(3)
                 .unwrap()
                 .joined_community_fund_timestamp_seconds
                 .unwrap_or(0)
@@ -9204,7 +9425,9 @@ fn test_join_community_fund() {
             .now_or_never()
             .unwrap();
         assert!(result.is_ok());
-        let actual_metrics = gov.proto.compute_cached_metrics(now, total_icp_suppply);
+        let actual_metrics =
+            gov.proto
+                .compute_cached_metrics(gov.neuron_store.values(), now, total_icp_suppply);
         assert_eq!(200, actual_metrics.total_supply_icp);
         assert_eq!(130 * 100_000_000, actual_metrics.total_staked_e8s);
         assert_eq!(
@@ -9214,9 +9437,8 @@ fn test_join_community_fund() {
         // 32 days in now
         assert_eq!(
             60 * 60 * 24 * 32,
-            gov.proto
-                .neurons
-                .get(&1)
+            gov.neuron_store
+                .get(1)
                 .unwrap()
                 .joined_community_fund_timestamp_seconds
                 .unwrap_or(0)
@@ -9308,9 +9530,8 @@ fn test_join_community_fund() {
     // Neuron 2 is not in the fund.
     assert_eq!(
         0,
-        gov.proto
-            .neurons
-            .get(&2)
+        gov.neuron_store
+            .get(2)
             .unwrap()
             .joined_community_fund_timestamp_seconds
             .unwrap_or(0)
@@ -10390,96 +10611,94 @@ fn test_known_neurons() {
         "Zwei".to_string()
     );
 
-    let expected_known_neuron_name_set: HashSet<String> = ["One".to_string(), "Zwei".to_string()]
-        .iter()
-        .cloned()
-        .collect();
-    assert_eq!(expected_known_neuron_name_set, gov.known_neuron_name_set);
+    assert!(gov.neuron_store.contains_known_neuron_name("One"));
+    assert!(gov.neuron_store.contains_known_neuron_name("Zwei"));
+    assert!(!gov.neuron_store.contains_known_neuron_name("Two"));
 }
 
-#[test]
-fn test_set_sns_token_swap_open_time_window() {
-    // Step 0: Define helper(s)
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct ExpectedCallCanisterMethodCallArguments<'a> {
+    target: CanisterId,
+    method_name: &'a str,
+    request: Vec<u8>,
+}
+
+/// An Environment that expects a given sequence of call_canister_method calls,
+/// returning the given results.
+#[allow(clippy::type_complexity)]
+struct MockEnvironment<'a> {
+    expected_call_canister_method_calls: VecDeque<(
+        ExpectedCallCanisterMethodCallArguments<'a>,
+        Result<Vec<u8>, (Option<i32>, String)>,
+    )>,
+}
 
-    #[derive(Debug, PartialEq, Eq, Clone)]
-    struct ExpectedCallCanisterMethodCallArguments<'a> {
+#[async_trait]
+impl Environment for MockEnvironment<'_> {
+    async fn call_canister_method(
+        &mut self,
         target: CanisterId,
-        method_name: &'a str,
+        method_name: &str,
         request: Vec<u8>,
-    }
-
-    #[allow(clippy::type_complexity)]
-    struct MockEnvironment<'a> {
-        expected_call_canister_method_calls: VecDeque<(
-            ExpectedCallCanisterMethodCallArguments<'a>,
-            Result<Vec<u8>, (Option<i32>, String)>,
-        )>,
-    }
-
-    #[async_trait]
-    impl Environment for MockEnvironment<'_> {
-        async fn call_canister_method(
-            &mut self,
-            target: CanisterId,
-            method_name: &str,
-            request: Vec<u8>,
-        ) -> Result<Vec<u8>, (Option<i32>, String)> {
-            let (expected_arguments, result) = self
-                .expected_call_canister_method_calls
-                .pop_front()
-                .unwrap();
+    ) -> Result<Vec<u8>, (Option<i32>, String)> {
+        let (expected_arguments, result) = self
+            .expected_call_canister_method_calls
+            .pop_front()
+            .unwrap();
 
-            assert_eq!(
-                ExpectedCallCanisterMethodCallArguments {
-                    target,
-                    method_name,
-                    request
-                },
-                expected_arguments,
-            );
+        assert_eq!(
+            ExpectedCallCanisterMethodCallArguments {
+                target,
+                method_name,
+                request
+            },
+            expected_arguments,
+        );
 
-            result
-        }
+        result
+    }
 
-        // Other methods don't do anything interesting. We implement them mostly
-        // to fulfill the trait requirements.
+    // Other methods don't do anything interesting. We implement them mostly
+    // to fulfill the trait requirements.
 
-        fn now(&self) -> u64 {
-            DEFAULT_TEST_START_TIMESTAMP_SECONDS
-        }
+    fn now(&self) -> u64 {
+        DEFAULT_TEST_START_TIMESTAMP_SECONDS
+    }
 
-        fn random_u64(&mut self) -> u64 {
-            panic!("Unexpected call to Environment::random_u64");
-        }
+    fn random_u64(&mut self) -> u64 {
+        panic!("Unexpected call to Environment::random_u64");
+    }
 
-        fn random_byte_array(&mut self) -> [u8; 32] {
-            panic!("Unexpected call to Environment::random_byte_array");
-        }
+    fn random_byte_array(&mut self) -> [u8; 32] {
+        panic!("Unexpected call to Environment::random_byte_array");
+    }
 
-        fn execute_nns_function(
-            &self,
-            _proposal_id: u64,
-            _update: &ExecuteNnsFunction,
-        ) -> Result<(), GovernanceError> {
-            panic!("Unexpected call to Environment::execute_nns_function");
-        }
+    fn execute_nns_function(
+        &self,
+        _proposal_id: u64,
+        _update: &ExecuteNnsFunction,
+    ) -> Result<(), GovernanceError> {
+        panic!("Unexpected call to Environment::execute_nns_function");
+    }
 
-        fn heap_growth_potential(&self) -> HeapGrowthPotential {
-            HeapGrowthPotential::NoIssue
-        }
+    fn heap_growth_potential(&self) -> HeapGrowthPotential {
+        HeapGrowthPotential::NoIssue
     }
+}
 
-    // Require that all expected calls were made.
-    impl Drop for MockEnvironment<'_> {
-        fn drop(&mut self) {
-            assert!(
-                self.expected_call_canister_method_calls.is_empty(),
-                "{:#?}",
-                self.expected_call_canister_method_calls,
-            );
-        }
+// Require that all expected calls were made.
+impl Drop for MockEnvironment<'_> {
+    fn drop(&mut self) {
+        assert!(
+            self.expected_call_canister_method_calls.is_empty(),
+            "{:#?}",
+            self.expected_call_canister_method_calls,
+        );
     }
+}
 
+#[test]
+fn test_set_sns_token_swap_open_time_window() {
     // Step 1: Prepare the world.
 
     // Parameters which will later be used to construct the swap proposal.
@@ -10576,6 +10795,219 @@ fn test_set_sns_token_swap_open_time_window() {
     assert_eq!(proposal.failure_reason, None, "{:#?}", proposal);
 }
 
+/// Opens an SNS token swap with a contribution of 50 ICP from the community
+/// fund. Neurons 2 and 3 are community fund neurons controlled by
+/// principal(2), with 30 and 70 ICP of maturity.
+fn open_sns_token_swap_with_community_fund() -> (Governance, fake::FakeDriver, PrincipalId, u64) {
+    let swap_canister_id = PrincipalId::new_user_test_id(1);
+    let start_timestamp_seconds = DEFAULT_TEST_START_TIMESTAMP_SECONDS;
+    let open_time_window = Some(sns_swap_pb::TimeWindow {
+        start_timestamp_seconds,
+        end_timestamp_seconds: start_timestamp_seconds + 2 * SECONDS_PER_DAY,
+    });
+
+    let cf_neuron = |id: u64, maturity_e8s_equivalent: u64| Neuron {
+        id: Some(NeuronId { id }),
+        controller: Some(principal(2)),
+        maturity_e8s_equivalent,
+        joined_community_fund_timestamp_seconds: Some(1),
+        ..Default::default()
+    };
+    let neurons = hashmap! {
+        1 => Neuron {
+            id: Some(NeuronId { id: 1 }),
+            controller: Some(principal(1)),
+            cached_neuron_stake_e8s: 100_000_000,
+            dissolve_state: Some(DissolveState::DissolveDelaySeconds(
+                MAX_DISSOLVE_DELAY_SECONDS,
+            )),
+            ..Default::default()
+        },
+        2 => cf_neuron(2, 30 * E8S),
+        3 => cf_neuron(3, 70 * E8S),
+    };
+
+    let governance_proto = GovernanceProto {
+        economics: Some(NetworkEconomics::with_default_values()),
+        neurons,
+        ..Default::default()
+    };
+
+    let expected_request = sns_swap_pb::OpenRequest {
+        open_time_window,
+        cf_participants: vec![sns_swap_pb::CfParticipant {
+            controller_principal_id: principal(2).to_string(),
+            cf_neurons: vec![
+                sns_swap_pb::CfNeuron {
+                    nns_neuron_id: 2,
+                    amount_icp_e8s: 15 * E8S,
+                    ..Default::default()
+                },
+                sns_swap_pb::CfNeuron {
+                    nns_neuron_id: 3,
+                    amount_icp_e8s: 35 * E8S,
+                    ..Default::default()
+                },
+            ],
+        }],
+        open_sns_token_swap_proposal_id: Some(1),
+    };
+    let expected_call_canister_method_calls = [(
+        ExpectedCallCanisterMethodCallArguments {
+            target: CanisterId::try_from(swap_canister_id).unwrap(),
+            method_name: "open",
+            request: Encode!(&expected_request).unwrap(),
+        },
+        Ok(Encode!(&sns_swap_pb::OpenResponse {}).unwrap()),
+    )]
+    .iter()
+    .cloned()
+    .collect();
+
+    let driver = fake::FakeDriver::default().with_supply(Tokens::from_tokens(1_000).unwrap());
+    let mut gov = Governance::new(
+        governance_proto,
+        Box::new(MockEnvironment {
+            expected_call_canister_method_calls,
+        }),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+
+    let proposal_id = gov
+        .make_proposal(
+            &NeuronId { id: 1 },
+            &principal(1),
+            &Proposal {
+                title: Some("Open SNS Token Swap".to_string()),
+                summary: "".to_string(),
+                action: Some(proposal::Action::OpenSnsTokenSwap(OpenSnsTokenSwap {
+                    target_swap_canister_id: Some(swap_canister_id),
+                    open_time_window,
+                    community_fund_investment_e8s: Some(50 * E8S),
+                })),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .id;
+
+    // The proposal was executed and the contribution was drawn from the
+    // maturity of the community fund neurons.
+    let proposal = gov
+        .get_proposal_data(ProposalId { id: proposal_id })
+        .unwrap();
+    assert_eq!(
+        proposal.executed_timestamp_seconds, DEFAULT_TEST_START_TIMESTAMP_SECONDS,
+        "{:#?}",
+        proposal
+    );
+    assert_eq!(proposal.cf_participants, expected_request.cf_participants);
+    assert_eq!(
+        gov.neuron_store.get(2).unwrap().maturity_e8s_equivalent,
+        15 * E8S
+    );
+    assert_eq!(
+        gov.neuron_store.get(3).unwrap().maturity_e8s_equivalent,
+        35 * E8S
+    );
+
+    (gov, driver, swap_canister_id, proposal_id)
+}
+
+#[tokio::test]
+async fn test_open_sns_token_swap_and_commit() {
+    let (mut gov, driver, swap_canister_id, proposal_id) =
+        open_sns_token_swap_with_community_fund();
+    let sns_governance_canister_id = PrincipalId::new_user_test_id(2);
+    let request = sns_swap_pb::SettleCommunityFundParticipation {
+        open_sns_token_swap_proposal_id: Some(proposal_id),
+        result: Some(
+            sns_swap_pb::settle_community_fund_participation::Result::Committed(
+                sns_swap_pb::settle_community_fund_participation::Committed {
+                    sns_governance_canister_id: Some(sns_governance_canister_id),
+                },
+            ),
+        ),
+    };
+
+    // Only the swap canister can settle.
+    let err = gov
+        .settle_community_fund_participation(principal(1), &request)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.error_type,
+        ErrorType::NotAuthorized as i32,
+        "{:#?}",
+        err
+    );
+
+    gov.settle_community_fund_participation(swap_canister_id, &request)
+        .await
+        .unwrap();
+    let sns_governance_account = AccountIdentifier::new(sns_governance_canister_id, None);
+    driver.assert_account_contains(&sns_governance_account, 50 * E8S);
+    assert!(gov
+        .get_proposal_data(ProposalId { id: proposal_id })
+        .unwrap()
+        .cf_participants
+        .is_empty());
+
+    // Settling again does nothing.
+    gov.settle_community_fund_participation(swap_canister_id, &request)
+        .await
+        .unwrap();
+    driver.assert_account_contains(&sns_governance_account, 50 * E8S);
+    assert_eq!(
+        gov.neuron_store.get(2).unwrap().maturity_e8s_equivalent,
+        15 * E8S
+    );
+    assert_eq!(
+        gov.neuron_store.get(3).unwrap().maturity_e8s_equivalent,
+        35 * E8S
+    );
+}
+
+#[tokio::test]
+async fn test_open_sns_token_swap_and_abort() {
+    let (mut gov, _driver, swap_canister_id, proposal_id) =
+        open_sns_token_swap_with_community_fund();
+    let request = sns_swap_pb::SettleCommunityFundParticipation {
+        open_sns_token_swap_proposal_id: Some(proposal_id),
+        result: Some(
+            sns_swap_pb::settle_community_fund_participation::Result::Aborted(
+                sns_swap_pb::settle_community_fund_participation::Aborted {},
+            ),
+        ),
+    };
+
+    gov.settle_community_fund_participation(swap_canister_id, &request)
+        .await
+        .unwrap();
+
+    // The maturity was given back, exactly once.
+    assert_eq!(
+        gov.neuron_store.get(2).unwrap().maturity_e8s_equivalent,
+        30 * E8S
+    );
+    assert_eq!(
+        gov.neuron_store.get(3).unwrap().maturity_e8s_equivalent,
+        70 * E8S
+    );
+    gov.settle_community_fund_participation(swap_canister_id, &request)
+        .await
+        .unwrap();
+    assert_eq!(
+        gov.neuron_store.get(2).unwrap().maturity_e8s_equivalent,
+        30 * E8S
+    );
+    assert_eq!(
+        gov.neuron_store.get(3).unwrap().maturity_e8s_equivalent,
+        70 * E8S
+    );
+}
+
 #[tokio::test]
 async fn distribute_rewards_load_test() {
     // Step 1: Prepare the world.
@@ -10705,7 +11137,7 @@ async fn distribute_rewards_load_test() {
     );
 
     // Step 3.1: Inspect neurons to make sure they have been rewarded for voting.
-    for (_, neuron) in governance.proto.neurons {
+    for neuron in governance.neuron_store.values() {
         assert_ne!(
             neuron.maturity_e8s_equivalent, maturity_e8s_equivalent,
             "neuron: {:#?}",
@@ -270,6 +270,8 @@ fn allocate_neuron(id: u64) -> Neuron {
         joined_community_fund_timestamp_seconds: None,
         known_neuron_data: None,
         spawn_at_timestamp_seconds: None,
+        staked_maturity_e8s_equivalent: None,
+        auto_stake_maturity: None,
     }
 }
 
@@ -108,6 +108,7 @@ fn run_upgrade_test(canister_type: SnsCanisterType) {
                     airdrop_neurons: vec![NeuronDistribution {
                         controller: Some(user),
                         stake_e8s: 2_000_000_000,
+                        vesting_period_seconds: None,
                     }],
                 }),
             },
@@ -284,6 +285,7 @@ pub fn claim_staked_neuron(
                 by: Some(By::MemoAndController(MemoAndController {
                     memo: DEFAULT_NEURON_STAKING_NONCE,
                     controller: None,
+                    dissolve_delay_seconds: None,
                 })),
             })),
         },
@@ -102,6 +102,7 @@ use ic_types::{
     chunkable::{ArtifactErrorCode, ChunkId},
     crypto::CryptoHash,
     p2p::GossipAdvert,
+    state_sync::decode_chunk_id,
     NodeId, RegistryVersion, SubnetId,
 };
 use lru::LruCache;
@@ -1081,7 +1082,7 @@ impl DownloadManagerImpl {
                     peers,
                     &advert_tracker.advert.artifact_id,
                     &advert_tracker.advert.integrity_hash,
-                    chunk_id,
+                    advert_tracker.chunk_request_id(advertiser, chunk_id),
                 )
             })
             .count();
@@ -1091,11 +1092,12 @@ impl DownloadManagerImpl {
         }
 
         // Since the peer has not attempted a chunk download in this round and will not
-        // violate duplicity constraints, a gossip chunk request is returned.
+        // violate duplicity constraints, a gossip chunk request is returned. The
+        // chunk is requested in a form the peer understands.
         Some(GossipChunkRequest {
             artifact_id: advert_tracker.advert.artifact_id.clone(),
             integrity_hash: advert_tracker.advert.integrity_hash.clone(),
-            chunk_id,
+            chunk_id: advert_tracker.chunk_request_id(&peer_id, chunk_id),
         })
     }
 
@@ -1289,6 +1291,9 @@ impl DownloadManagerImpl {
         integrity_hash: CryptoHash,
         chunk_id: ChunkId,
     ) {
+        // Download attempts are tracked by chunk, independently of the form
+        // in which the chunk was requested from the peer.
+        let chunk_id = decode_chunk_id(chunk_id).0;
         // Drop it and switch the preferred primary so that the next node that
         // advertised the chunk picks it up.
         let _ = self
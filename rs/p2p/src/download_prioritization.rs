@@ -191,6 +191,10 @@ pub(crate) struct AdvertTracker {
     pub advert: GossipAdvert,
     /// Peers that have advertised this advert
     pub peers: Vec<NodeId>,
+    /// Attributes of the peers that advertised the artifact with another
+    /// attribute than `advert`, e.g. because they run an older replica
+    /// version with fewer capabilities.
+    peer_attributes: HashMap<NodeId, ArtifactAttribute>,
     /// Per chunk download attempt history map
     download_attempt_map: DownloadAttemptMap,
     /// Priority as computed by the last priority function
@@ -309,8 +313,14 @@ impl DownloadAttemptTracker for AdvertTracker {
 
 /// Implementation for the AdvertTracker data structure
 impl AdvertTracker {
-    /// Adds a peer. If the peer already exists, it is ignored.
-    fn add_peer(&mut self, node_id: NodeId) {
+    /// Adds a peer along with the attribute of its advert. If the peer already
+    /// exists, only its attribute is updated.
+    fn add_peer(&mut self, node_id: NodeId, attribute: ArtifactAttribute) {
+        if attribute == self.advert.attribute {
+            self.peer_attributes.remove(&node_id);
+        } else {
+            self.peer_attributes.insert(node_id, attribute);
+        }
         for x in &self.peers {
             if (*x).get() == node_id.get() {
                 return;
@@ -322,6 +332,16 @@ impl AdvertTracker {
     /// Removes a peer
     fn remove_peer(&mut self, node_id: NodeId) {
         self.peers.retain(|x| (*x).get() != node_id.get());
+        self.peer_attributes.remove(&node_id);
+    }
+
+    /// Returns the id under which chunk `chunk_id` is requested from the given
+    /// peer, according to the attribute of the peer's advert.
+    pub fn chunk_request_id(&self, peer_id: &NodeId, chunk_id: ChunkId) -> ChunkId {
+        self.peer_attributes
+            .get(peer_id)
+            .unwrap_or(&self.advert.attribute)
+            .chunk_request_id(chunk_id)
     }
 
     /// Returns the DownloadAttemptTracker for a chunk
@@ -534,6 +554,7 @@ impl DownloadPrioritizer for DownloadPrioritizerImpl {
         }
         let integrity_hash = advert.integrity_hash.clone();
         let integrity_hash_peer_index = advert.integrity_hash.clone();
+        let attribute = advert.attribute.clone();
 
         // Insert into the client advert map
         let advert_tracker = client.advert_map.entry(integrity_hash).or_insert_with(|| {
@@ -541,6 +562,7 @@ impl DownloadPrioritizer for DownloadPrioritizerImpl {
                 advert,
                 priority,
                 peers: Default::default(),
+                peer_attributes: Default::default(),
                 download_attempt_map: Default::default(),
             }))
         });
@@ -566,7 +588,7 @@ impl DownloadPrioritizer for DownloadPrioritizerImpl {
 
         // Track the peer in the advert
         let mut advert_tracker = advert_tracker.write().unwrap();
-        advert_tracker.add_peer(peer_id);
+        advert_tracker.add_peer(peer_id, attribute);
         Ok(())
     }
 
@@ -925,7 +947,12 @@ pub(crate) mod test {
     use ic_metrics::MetricsRegistry;
     use ic_test_utilities::{types::ids::node_test_id, FastForwardTimeSource};
     use ic_test_utilities_metrics::fetch_histogram_stats;
-    use ic_types::crypto::CryptoHash;
+    use ic_types::{
+        artifact::{StateSyncArtifactId, StateSyncAttribute},
+        crypto::{CryptoHash, CryptoHashOf},
+        state_sync::compressed_chunk_id,
+        Height,
+    };
     use std::time::Duration;
 
     /// Returns a priority for a given artifact based on its content.
@@ -1085,6 +1112,63 @@ pub(crate) mod test {
         }
     }
 
+    /// Checks that chunks of an artifact are requested from each peer in the
+    /// form announced by that peer's advert, so that peers with and without
+    /// support for compressed state sync chunks can serve the same download.
+    #[test]
+    fn chunk_request_ids_follow_each_peers_advert() {
+        let time_source = FastForwardTimeSource::new();
+        let artifact_manager = ArtifactManagerImpl::new(time_source);
+        let download_prioritizer: DownloadPrioritizerImpl = DownloadPrioritizerImpl::new(
+            &artifact_manager,
+            DownloadPrioritizerMetrics::new(&MetricsRegistry::new()),
+        );
+
+        let state_sync_advert = |supports_compressed_chunks| {
+            let hash = CryptoHashOf::from(CryptoHash(vec![1; 32]));
+            GossipAdvert {
+                artifact_id: ArtifactId::StateSync(StateSyncArtifactId {
+                    height: Height::from(10),
+                    hash: hash.clone(),
+                }),
+                attribute: ArtifactAttribute::StateSync(StateSyncAttribute {
+                    height: Height::from(10),
+                    root_hash: hash,
+                    supports_compressed_chunks,
+                }),
+                size: 0,
+                integrity_hash: CryptoHash(vec![2; 32]),
+            }
+        };
+        let (new_peer, old_peer) = (node_test_id(1), node_test_id(2));
+        download_prioritizer
+            .add_advert(state_sync_advert(true), new_peer)
+            .unwrap();
+        download_prioritizer
+            .add_advert(state_sync_advert(false), old_peer)
+            .unwrap();
+
+        let advert = state_sync_advert(true);
+        let advert_tracker = download_prioritizer
+            .get_advert_tracker(&advert.artifact_id, &advert.integrity_hash)
+            .unwrap();
+        let mut advert_tracker = advert_tracker.write().unwrap();
+        assert_eq!(advert_tracker.peers, vec![new_peer, old_peer]);
+
+        let chunk_id = ChunkId::new(7);
+        assert_eq!(
+            advert_tracker.chunk_request_id(&new_peer, chunk_id),
+            compressed_chunk_id(chunk_id)
+        );
+        assert_eq!(
+            advert_tracker.chunk_request_id(&old_peer, chunk_id),
+            chunk_id
+        );
+
+        advert_tracker.remove_peer(old_peer);
+        assert!(advert_tracker.peer_attributes.is_empty());
+    }
+
     /// A test to verify reported metrics
     #[test]
     fn validate_timing_metric() {
@@ -306,6 +306,7 @@ impl SubnetConfig {
             ssh_readonly_access: self.ssh_readonly_access,
             ssh_backup_access: self.ssh_backup_access,
             ecdsa_config: self.ecdsa_config,
+            max_wasm64_memory_size: 0,
         };
 
         let dkg_dealing_encryption_pubkeys: BTreeMap<_, _> = initialized_nodes
@@ -269,6 +269,17 @@ pub mod execution_task {
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TotalQueryStats {
+    #[prost(uint64, tag = "1")]
+    pub num_calls: u64,
+    #[prost(uint64, tag = "2")]
+    pub num_instructions: u64,
+    #[prost(uint64, tag = "3")]
+    pub ingress_payload_size: u64,
+    #[prost(uint64, tag = "4")]
+    pub egress_payload_size: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterStateBits {
     #[prost(uint64, tag = "2")]
     pub last_full_execution_round: u64,
@@ -317,6 +328,13 @@ pub struct CanisterStateBits {
     /// canister.
     #[prost(message, repeated, tag = "30")]
     pub task_queue: ::prost::alloc::vec::Vec<ExecutionTask>,
+    /// The query statistics of the canister reported by all nodes.
+    #[prost(message, optional, tag = "31")]
+    pub total_query_stats: ::core::option::Option<TotalQueryStats>,
+    /// Incremented whenever the canister executes messages, its code is
+    /// installed or uninstalled, or its settings are updated.
+    #[prost(uint64, tag = "32")]
+    pub canister_version: u64,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -31,6 +31,8 @@ pub struct SubnetTopology {
     #[prost(message, repeated, tag = "5")]
     pub ecdsa_keys_held:
         ::prost::alloc::vec::Vec<super::super::super::registry::crypto::v1::EcdsaKeyId>,
+    #[prost(uint64, tag = "6")]
+    pub max_wasm64_memory_size: u64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SubnetsEntry {
@@ -178,6 +180,13 @@ pub struct SubnetMetrics {
         ::core::option::Option<super::super::super::types::v1::NominalCycles>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QueryStatsEpochEntry {
+    #[prost(message, optional, tag = "1")]
+    pub node_id: ::core::option::Option<super::super::super::types::v1::NodeId>,
+    #[prost(uint64, tag = "2")]
+    pub epoch: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SystemMetadata {
     #[prost(uint64, tag = "1")]
     pub generated_id_counter: u64,
@@ -225,6 +234,13 @@ pub struct SystemMetadata {
     pub time_of_last_allocation_charge_nanos: ::core::option::Option<TimeOfLastAllocationCharge>,
     #[prost(message, optional, tag = "15")]
     pub subnet_metrics: ::core::option::Option<SubnetMetrics>,
+    /// The last query stats epoch reported by each node.
+    #[prost(message, repeated, tag = "18")]
+    pub last_reported_query_stats_epochs: ::prost::alloc::vec::Vec<QueryStatsEpochEntry>,
+    /// The query stats reports of the epochs that are not aggregated yet.
+    #[prost(message, repeated, tag = "19")]
+    pub query_stats_reports:
+        ::prost::alloc::vec::Vec<super::super::super::types::v1::QueryStatsPayload>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct StableMemory {
@@ -75,6 +75,13 @@ pub struct SubnetRecord {
     /// If a removed key is not held by another subnet, it will be lost.
     #[prost(message, optional, tag = "27")]
     pub ecdsa_config: ::core::option::Option<EcdsaConfig>,
+    /// The maximum size in bytes of the memory of a canister using 64-bit memory.
+    ///
+    /// A value of 0 means that the replica falls back to its built-in default. This
+    /// also provides an easy way to maintain compatibility of different versions of
+    /// replica and registry.
+    #[prost(uint64, tag = "28")]
+    pub max_wasm64_memory_size: u64,
 }
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, ::prost::Message)]
 pub struct EcdsaInitialization {
@@ -1504,6 +1504,11 @@ struct ProposeToUpdateSubnetCmd {
     /// of this field.
     #[clap(long)]
     pub max_number_of_canisters: Option<u64>,
+
+    /// If set, the created proposal will contain a desired override of the
+    /// maximum size in bytes of the memory of canisters using 64-bit memory.
+    #[clap(long)]
+    pub max_wasm64_memory_size: Option<u64>,
 }
 
 fn parse_ecdsa_keys_option(maybe_value: &Option<Vec<String>>) -> Vec<EcdsaKeyId> {
@@ -1635,6 +1640,7 @@ impl ProposalTitleAndPayload<UpdateSubnetPayload> for ProposeToUpdateSubnetCmd {
             ssh_readonly_access: self.ssh_readonly_access.clone(),
             ssh_backup_access: self.ssh_backup_access.clone(),
             max_number_of_canisters: self.max_number_of_canisters,
+            max_wasm64_memory_size: self.max_wasm64_memory_size,
         }
     }
 }
@@ -237,9 +237,12 @@ fn get_certified_changes_since() {
             let latest_version = registry().latest_version();
             let from_version = EncodedVersion::from(req.version.saturating_add(1));
 
-            let max_versions = registry()
+            let mut max_versions = registry()
                 .count_fitting_deltas(req.version, MAX_REGISTRY_DELTAS_SIZE)
                 .min(MAX_VERSIONS_PER_QUERY);
+            if req.max_versions > 0 {
+                max_versions = max_versions.min(req.max_versions as usize);
+            }
 
             let to_version = EncodedVersion::from(req.version.saturating_add(max_versions as u64));
             let delta_tree = registry()
@@ -362,6 +362,7 @@ impl From<CreateSubnetPayload> for SubnetRecord {
             ssh_readonly_access: val.ssh_readonly_access,
             ssh_backup_access: val.ssh_backup_access,
             ecdsa_config: val.ecdsa_config.map(|x| x.into()),
+            max_wasm64_memory_size: 0,
         }
     }
 }
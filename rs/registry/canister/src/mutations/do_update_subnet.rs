@@ -198,6 +198,7 @@ pub struct UpdateSubnetPayload {
     pub ecdsa_key_signing_disable: Option<Vec<EcdsaKeyId>>,
 
     pub max_number_of_canisters: Option<u64>,
+    pub max_wasm64_memory_size: Option<u64>,
 
     pub ssh_readonly_access: Option<Vec<String>>,
     pub ssh_backup_access: Option<Vec<String>>,
@@ -287,6 +288,7 @@ fn merge_subnet_record(
         ecdsa_key_signing_enable: _,
         ecdsa_key_signing_disable: _,
         max_number_of_canisters,
+        max_wasm64_memory_size,
         ssh_readonly_access,
         ssh_backup_access,
     } = payload;
@@ -337,6 +339,7 @@ fn merge_subnet_record(
     maybe_set_option!(subnet_record, ecdsa_config);
 
     maybe_set!(subnet_record, max_number_of_canisters);
+    maybe_set!(subnet_record, max_wasm64_memory_size);
 
     maybe_set!(subnet_record, ssh_readonly_access);
     maybe_set!(subnet_record, ssh_backup_access);
@@ -417,6 +420,7 @@ mod tests {
             max_number_of_canisters: Some(10),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
+            max_wasm64_memory_size: None,
         }
     }
 
@@ -453,6 +457,7 @@ mod tests {
             max_number_of_canisters: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            max_wasm64_memory_size: None,
         }
     }
 
@@ -490,6 +495,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            max_wasm64_memory_size: 0,
         };
 
         let payload = UpdateSubnetPayload {
@@ -537,6 +543,7 @@ mod tests {
             max_number_of_canisters: Some(10),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
+            max_wasm64_memory_size: None,
         };
 
         assert_eq!(
@@ -589,6 +596,7 @@ mod tests {
                 max_number_of_canisters: 10,
                 ssh_readonly_access: vec!["pub_key_0".to_string()],
                 ssh_backup_access: vec!["pub_key_1".to_string()],
+                max_wasm64_memory_size: 0,
             }
         );
     }
@@ -629,6 +637,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            max_wasm64_memory_size: 0,
         };
 
         let payload = UpdateSubnetPayload {
@@ -668,6 +677,7 @@ mod tests {
             max_number_of_canisters: Some(50),
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            max_wasm64_memory_size: None,
         };
 
         assert_eq!(
@@ -706,6 +716,7 @@ mod tests {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                max_wasm64_memory_size: 0,
             }
         );
     }
@@ -758,6 +769,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            max_wasm64_memory_size: 0,
         };
 
         let payload = UpdateSubnetPayload {
@@ -797,6 +809,7 @@ mod tests {
             max_number_of_canisters: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            max_wasm64_memory_size: None,
         };
 
         merge_subnet_record(subnet_record, payload);
@@ -826,6 +839,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            max_wasm64_memory_size: 0,
         };
 
         let payload = UpdateSubnetPayload {
@@ -865,6 +879,7 @@ mod tests {
             max_number_of_canisters: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            max_wasm64_memory_size: None,
         };
 
         assert_eq!(
@@ -903,6 +918,7 @@ mod tests {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                max_wasm64_memory_size: 0,
             }
         );
     }
@@ -943,6 +959,7 @@ mod tests {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            max_wasm64_memory_size: 0,
         };
 
         let payload = UpdateSubnetPayload {
@@ -982,6 +999,7 @@ mod tests {
             max_number_of_canisters: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            max_wasm64_memory_size: None,
         };
 
         assert_eq!(
@@ -1020,6 +1038,7 @@ mod tests {
                 ssh_readonly_access: vec![],
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
+                max_wasm64_memory_size: 0,
             }
         );
     }
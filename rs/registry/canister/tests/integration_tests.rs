@@ -57,12 +57,23 @@ async fn try_to_install_registry_canister(
 async fn query_certified_changes_since(
     canister: &Canister<'_>,
     version: u64,
+) -> (Vec<RegistryTransportRecord>, RegistryVersion) {
+    query_certified_changes_since_with_max_versions(canister, version, 0).await
+}
+
+async fn query_certified_changes_since_with_max_versions(
+    canister: &Canister<'_>,
+    version: u64,
+    max_versions: u64,
 ) -> (Vec<RegistryTransportRecord>, RegistryVersion) {
     let certified_response: CertifiedResponse = canister
         .query_(
             "get_certified_changes_since",
             protobuf,
-            changes_since(version),
+            RegistryGetChangesSinceRequest {
+                version,
+                max_versions,
+            },
         )
         .await
         .expect("failed to query certified changes");
@@ -86,7 +97,10 @@ fn get_value_request(key: impl AsRef<[u8]>, version: Option<u64>) -> RegistryGet
 }
 
 fn changes_since(version: u64) -> RegistryGetChangesSinceRequest {
-    RegistryGetChangesSinceRequest { version }
+    RegistryGetChangesSinceRequest {
+        version,
+        max_versions: 0,
+    }
 }
 
 fn data_part(certified_response: &CertifiedResponse) -> LabeledTree<Vec<u8>> {
@@ -327,6 +341,59 @@ async fn get_changes_since_certified(runtime: &Runtime, upgrade_scenario: Upgrad
     assert!(deltas.is_empty());
 }
 
+#[test]
+fn get_certified_changes_since_respects_max_versions() {
+    local_test_on_nns_subnet(|runtime| async move {
+        let canister = install_registry_canister(
+            &runtime,
+            RegistryCanisterInitPayloadBuilder::new()
+                .push_init_mutate_request(invariant_compliant_mutation_as_atomic_req())
+                .build(),
+        )
+        .await;
+
+        // Sets up a universal canister in lieu of the governance canister so it can
+        // impersonate it.
+        let fake_governance_canister = set_up_universal_canister(&runtime).await;
+        assert_eq!(
+            fake_governance_canister.canister_id(),
+            GOVERNANCE_CANISTER_ID
+        );
+
+        for key in ["key1", "key2"] {
+            let mutation_request = RegistryAtomicMutateRequest {
+                mutations: vec![insert(key, "value")],
+                preconditions: vec![],
+            };
+            assert!(
+                forward_call_via_universal_canister(
+                    &fake_governance_canister,
+                    &canister,
+                    "atomic_mutate",
+                    encode_or_panic(&mutation_request)
+                )
+                .await
+            );
+        }
+
+        // Only the first of the two new versions is returned, but the latest
+        // version is still reported.
+        let (deltas, version) =
+            query_certified_changes_since_with_max_versions(&canister, 1, 1).await;
+        assert_eq!(version, RegistryVersion::from(3));
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].version, RegistryVersion::from(2));
+
+        let (deltas, version) =
+            query_certified_changes_since_with_max_versions(&canister, 2, 1).await;
+        assert_eq!(version, RegistryVersion::from(3));
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].version, RegistryVersion::from(3));
+
+        Ok(())
+    });
+}
+
 #[test]
 fn test_does_not_return_more_than_1000_certified_deltas() {
     fn count_deltas(tree: &LabeledTree<Vec<u8>>) -> usize {
@@ -92,6 +92,7 @@ fn test_the_anonymous_user_cannot_update_a_subnets_configuration() {
             max_number_of_canisters: Some(10),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
+            max_wasm64_memory_size: None,
         };
 
         // The anonymous end-user tries to update a subnet's configuration, bypassing
@@ -161,6 +162,7 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            max_wasm64_memory_size: 0,
         };
 
         // An attacker got a canister that is trying to pass for the governance
@@ -220,6 +222,7 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             max_number_of_canisters: Some(100),
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            max_wasm64_memory_size: None,
         };
 
         // The attacker canister tries to update the subnet's configuration, pretending
@@ -288,6 +291,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                             ssh_readonly_access: vec![],
                             ssh_backup_access: vec![],
                             ecdsa_config: None,
+                            max_wasm64_memory_size: 0,
                         }),
                     )],
                     preconditions: vec![],
@@ -338,6 +342,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
             max_number_of_canisters: Some(42),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
+            max_wasm64_memory_size: None,
         };
 
         // Attempt to update the subnet's configuration. Since the update happens from
@@ -394,6 +399,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                 ssh_readonly_access: vec!["pub_key_0".to_string()],
                 ssh_backup_access: vec!["pub_key_1".to_string()],
                 ecdsa_config: None,
+                max_wasm64_memory_size: 0,
             }
         );
 
@@ -447,6 +453,7 @@ fn test_subnets_configuration_ecdsa_fields_are_updated_correctly() {
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
             ecdsa_config: None,
+            max_wasm64_memory_size: 0,
         };
 
         // Just create the registry canister and wait until the subnet_handler ID is
@@ -649,5 +656,6 @@ fn empty_update_subnet_payload(subnet_id: SubnetId) -> UpdateSubnetPayload {
         ecdsa_config: None,
         ecdsa_key_signing_enable: None,
         ecdsa_key_signing_disable: None,
+        max_wasm64_memory_size: None,
     }
 }
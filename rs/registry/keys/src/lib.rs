@@ -93,7 +93,7 @@ pub fn make_firewall_config_record_key() -> String {
     "firewall_config".to_string()
 }
 
-const FIREWALL_RULES_RECORD_KEY_PREFIX: &str = "firewall_rules_";
+pub const FIREWALL_RULES_RECORD_KEY_PREFIX: &str = "firewall_rules_";
 const FIREWALL_RULES_SCOPE_GLOBAL: &str = "global";
 const FIREWALL_RULES_SCOPE_REPLICA_NODES: &str = "replica_nodes";
 const FIREWALL_RULES_SCOPE_SUBNET_PREFIX: &str = "subnet";
@@ -76,8 +76,11 @@ fn embed_certificate_error(err: CertificateValidationError) -> CertificationErro
 ///      delta.  Note that It is fine for the registry canister to not return
 ///      all entries up until the current version.  This can happen, e.g., if
 ///      the list of updates is too long for a single request.
+///
+///   4. If `max_versions` is set, the range contains at most that many deltas.
 fn validate_version_range(
     since_version: u64,
+    max_versions: Option<u64>,
     p: &CertifiedPayload,
 ) -> Result<u64, CertificationError> {
     let last_version = p
@@ -101,6 +104,16 @@ fn validate_version_range(
         )));
     }
 
+    if let Some(max_versions) = max_versions {
+        if last_version - since_version > max_versions {
+            return Err(CertificationError::InvalidDeltas(format!(
+                "payload contains {} versions, but at most {} were requested",
+                last_version - since_version,
+                max_versions
+            )));
+        }
+    }
+
     Ok(p.current_version.0)
 }
 
@@ -108,6 +121,14 @@ fn validate_version_range(
 pub fn decode_hash_tree(
     since_version: u64,
     hash_tree: MixedHashTree,
+) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion), CertificationError> {
+    decode_hash_tree_with_max_versions(since_version, None, hash_tree)
+}
+
+fn decode_hash_tree_with_max_versions(
+    since_version: u64,
+    max_versions: Option<u64>,
+    hash_tree: MixedHashTree,
 ) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion), CertificationError> {
     // Extract structured deltas from their tree representation.
     let labeled_tree = LabeledTree::<Vec<u8>>::try_from(hash_tree).map_err(|err| {
@@ -129,7 +150,7 @@ pub fn decode_hash_tree(
 
     // Validate that the deltas form a proper range and convert them to the
     // format that RegistryClient wants.
-    let current_version = validate_version_range(since_version, &certified_payload)?;
+    let current_version = validate_version_range(since_version, max_versions, &certified_payload)?;
 
     let changes = certified_payload
         .delta
@@ -164,6 +185,18 @@ pub fn decode_certified_deltas(
     canister_id: &CanisterId,
     nns_pk: &ThresholdSigPublicKey,
     payload: &[u8],
+) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion, Time), CertificationError> {
+    decode_certified_deltas_with_max_versions(since_version, None, canister_id, nns_pk, payload)
+}
+
+/// Same as `decode_certified_deltas`, but additionally validates that the
+/// response contains at most `max_versions` versions, if set.
+pub fn decode_certified_deltas_with_max_versions(
+    since_version: u64,
+    max_versions: Option<u64>,
+    canister_id: &CanisterId,
+    nns_pk: &ThresholdSigPublicKey,
+    payload: &[u8],
 ) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion, Time), CertificationError> {
     let certified_response = CertifiedResponse::decode(payload).map_err(|err| {
         CertificationError::DeserError(format!(
@@ -195,7 +228,8 @@ pub fn decode_certified_deltas(
     )
     .map_err(embed_certificate_error)?;
 
-    let (changes, current_version) = decode_hash_tree(since_version, mixed_hash_tree)?;
+    let (changes, current_version) =
+        decode_hash_tree_with_max_versions(since_version, max_versions, mixed_hash_tree)?;
 
     Ok((changes, current_version, time))
 }
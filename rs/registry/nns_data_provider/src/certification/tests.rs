@@ -1,4 +1,6 @@
-use super::{decode_certified_deltas, CertificationError};
+use super::{
+    decode_certified_deltas, decode_certified_deltas_with_max_versions, CertificationError,
+};
 use ic_certification_test_utils::{CertificateBuilder, CertificateData};
 use ic_crypto_tree_hash::{
     flatmap, Digest, FlatMap, HashTreeBuilder, HashTreeBuilderImpl, Label, LabeledTree,
@@ -310,3 +312,41 @@ fn test_decode_empty_prefix() {
         other => panic!("Expected InvalidDeltas error, got {:?}", other),
     }
 }
+
+#[test]
+fn test_decode_bounded_delta() {
+    let (cid, pk, payload) = make_certified_delta(
+        vec![
+            make_change(vec![upsert("key1", "value1")]),
+            make_change(vec![upsert("key2", "value2")]),
+            make_change(vec![upsert("key3", "value3")]),
+        ],
+        1..=2,
+        GarbleResponse::LeaveAsIs,
+    );
+    assert_eq!(
+        decode_certified_deltas_with_max_versions(0, Some(2), &cid, &pk, &payload[..]).unwrap(),
+        (
+            vec![set_key(1, "key1", "value1"), set_key(2, "key2", "value2")],
+            RegistryVersion::from(3u64),
+            Time::from_nanos_since_unix_epoch(REPLICA_TIME),
+        ),
+    )
+}
+
+#[test]
+fn test_decode_more_versions_than_requested() {
+    let (cid, pk, payload) = make_certified_delta(
+        vec![
+            make_change(vec![upsert("key1", "value1")]),
+            make_change(vec![upsert("key2", "value2")]),
+            make_change(vec![upsert("key3", "value3")]),
+        ],
+        1..=3,
+        GarbleResponse::LeaveAsIs,
+    );
+    match decode_certified_deltas_with_max_versions(0, Some(2), &cid, &pk, &payload[..]) {
+        Err(CertificationError::InvalidDeltas(_)) => (),
+        other => panic!("Expected InvalidDeltas error, got {:?}", other),
+    }
+}
@@ -12,6 +12,11 @@ pub struct NnsDataProvider {
     rt_handle: tokio::runtime::Handle,
 }
 
+/// The maximum number of registry versions that `CertifiedNnsDataProvider`
+/// fetches per call to `get_updates_since`. Callers poll until they are up to
+/// date, so a large delta is applied in several steps.
+const MAX_VERSIONS_PER_UPDATE: u64 = 1000;
+
 pub struct CertifiedNnsDataProvider {
     registry_canister: Arc<RegistryCanister>,
     nns_public_key: Arc<ThresholdSigPublicKey>,
@@ -100,7 +105,11 @@ impl RegistryDataProvider for CertifiedNnsDataProvider {
                 let nns_public_key = Arc::clone(&self.nns_public_key);
                 async move {
                     registry_canister
-                        .get_certified_changes_since(version.get(), &nns_public_key)
+                        .get_certified_changes_since_with_max_versions(
+                            version.get(),
+                            Some(MAX_VERSIONS_PER_UPDATE),
+                            &nns_public_key,
+                        )
                         .await
                         .map_err(|source| RegistryDataProviderError::Transfer { source })
                 }
@@ -9,7 +9,8 @@ use ic_interfaces::registry::RegistryTransportRecord;
 use ic_registry_transport::{
     deserialize_atomic_mutate_response, deserialize_get_changes_since_response,
     deserialize_get_value_response, serialize_atomic_mutate_request,
-    serialize_get_changes_since_request, serialize_get_value_request,
+    serialize_get_changes_since_request, serialize_get_changes_since_request_with_max_versions,
+    serialize_get_value_request,
 };
 use ic_registry_transport::{
     pb::v1::{Precondition, RegistryDelta, RegistryMutation},
@@ -113,7 +114,25 @@ impl RegistryCanister {
         version: u64,
         nns_public_key: &ThresholdSigPublicKey,
     ) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion, Time), Error> {
-        let payload = serialize_get_changes_since_request(version).unwrap();
+        self.get_certified_changes_since_with_max_versions(version, None, nns_public_key)
+            .await
+    }
+
+    /// Same as `get_certified_changes_since`, but asks for at most
+    /// `max_versions` versions, e.g. to apply a large registry delta in
+    /// several steps.
+    ///
+    /// Every returned record is verified against the registry's certified
+    /// data, so the caller does not need to trust the replica that answered.
+    /// Responses with more than `max_versions` versions are rejected.
+    pub async fn get_certified_changes_since_with_max_versions(
+        &self,
+        version: u64,
+        max_versions: Option<u64>,
+        nns_public_key: &ThresholdSigPublicKey,
+    ) -> Result<(Vec<RegistryTransportRecord>, RegistryVersion, Time), Error> {
+        let payload =
+            serialize_get_changes_since_request_with_max_versions(version, max_versions).unwrap();
         let response = self
             .choose_random_agent()
             .execute_query(&self.canister_id, "get_certified_changes_since", payload)
@@ -131,8 +150,9 @@ impl RegistryCanister {
                 ))
             })?;
 
-        crate::certification::decode_certified_deltas(
+        crate::certification::decode_certified_deltas_with_max_versions(
             version,
+            max_versions,
             &self.canister_id,
             nns_public_key,
             &response[..],
@@ -67,6 +67,27 @@ pub enum CommandArg {
         #[clap(parse(from_os_str))]
         snapshot_file: PathBuf,
     },
+    /// Show which records changed between two registry versions, decoding
+    /// the records and listing the changed fields.
+    Diff {
+        /// The registry version to compare against. Non-positive values are
+        /// relative to the latest available version.
+        #[clap(long, allow_hyphen_values = true)]
+        from: i64,
+
+        /// The registry version to compare. (default: latest available
+        /// version.)
+        #[clap(long, allow_hyphen_values = true)]
+        to: Option<i64>,
+
+        /// Print the diff as json instead of text.
+        #[clap(long)]
+        json: bool,
+
+        /// Path to the local store.
+        #[clap(parse(from_os_str))]
+        local_store_path: PathBuf,
+    },
     CanisterSnapshot {
         /// Url to a node hosting the registry canister (may not be specified
         /// together with --local-store).
@@ -114,6 +135,31 @@ pub enum CommandArg {
         #[clap(parse(from_os_str))]
         snapshot_file: PathBuf,
     },
+    CanisterDiff {
+        /// Url to a node hosting the registry canister.
+        #[clap(long, parse(try_from_str = url::Url::parse))]
+        url: Url,
+
+        /// Optional path to the threshold public key of the root subnet
+        /// (a.k.a. NNS public key). One way to get this key is via
+        /// "ic-admin --nns-url https://nns.ic0.app  get-subnet-public-key"
+        #[clap(long, parse(from_os_str))]
+        nns_public_key: Option<PathBuf>,
+
+        /// The registry version to compare against. Non-positive values are
+        /// relative to the latest available version.
+        #[clap(long, allow_hyphen_values = true)]
+        from: i64,
+
+        /// The registry version to compare. (default: latest available
+        /// version.)
+        #[clap(long, allow_hyphen_values = true)]
+        to: Option<i64>,
+
+        /// Print the diff as json instead of text.
+        #[clap(long)]
+        json: bool,
+    },
 }
 
 impl CliArgs {
@@ -191,6 +237,32 @@ impl CliArgs {
                     snapshot,
                 }
             }
+            CommandArg::Diff {
+                from,
+                to,
+                json,
+                local_store_path,
+            } => Command::Diff {
+                source: SourceSpec::LocalStore(Self::is_dir(local_store_path)?),
+                from: Some(from).into(),
+                to: to.into(),
+                output_format: OutputFormat::from_json_flag(json),
+            },
+            CommandArg::CanisterDiff {
+                url,
+                nns_public_key,
+                from,
+                to,
+                json,
+            } => {
+                let nns_key_material = get_key_material(nns_public_key)?;
+                Command::Diff {
+                    source: SourceSpec::Canister(url, nns_key_material),
+                    from: Some(from).into(),
+                    to: to.into(),
+                    output_format: OutputFormat::from_json_flag(json),
+                }
+            }
         };
         Ok(res)
     }
@@ -281,6 +353,38 @@ pub enum Command {
         snapshot: Value,
         amend: bool,
     },
+    Diff {
+        source: SourceSpec,
+        from: VersionSpec,
+        to: VersionSpec,
+        output_format: OutputFormat,
+    },
+}
+
+impl Command {
+    /// The format in which the result of the command should be printed.
+    pub fn output_format(&self) -> OutputFormat {
+        match self {
+            Command::Diff { output_format, .. } => *output_format,
+            _ => OutputFormat::Json,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Text,
+}
+
+impl OutputFormat {
+    fn from_json_flag(json: bool) -> Self {
+        if json {
+            Self::Json
+        } else {
+            Self::Text
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -4,6 +4,7 @@ mod json;
 mod normalization;
 mod projection;
 mod protobuf;
+mod semantic_diff;
 mod snapshot;
 mod source;
 mod tests;
@@ -67,10 +68,30 @@ pub fn execute_command(cmd: Command) -> Result<Value> {
             local_store.store(v, changelog_entry)?;
             diff.0
         }
+        Command::Diff {
+            source,
+            from,
+            to,
+            output_format: _,
+        } => {
+            let changelog = source::get_changelog(source)?;
+            let from_snapshot = snapshot::changelog_to_snapshot(changelog.clone(), from)?;
+            let to_snapshot = snapshot::changelog_to_snapshot(changelog, to)?;
+            let (from_snapshot, _) = normalization::normalize(from_snapshot.0);
+            let (to_snapshot, _) = normalization::normalize(to_snapshot.0);
+            let diff = semantic_diff::make_semantic_diff(&from_snapshot, &to_snapshot)?;
+            json::assert_to_value(diff)
+        }
     };
     Ok(res)
 }
 
+/// Renders the output of a [Command::Diff] as human-readable text.
+pub fn render_diff_as_text(value: Value) -> Result<String> {
+    let diff: semantic_diff::SemanticDiff = serde_json::from_value(value)?;
+    Ok(diff.to_string())
+}
+
 pub fn load_registry_local_store(local_store_path: PathBuf) -> Result<Value> {
     execute_command(args::Command::Snapshot {
         registry_spec: args::RegistrySpec {
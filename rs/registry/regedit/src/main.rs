@@ -2,12 +2,19 @@
 
 use anyhow::Result;
 use clap::Parser;
+use ic_regedit::args::OutputFormat;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cmd = ic_regedit::args::CliArgs::parse().validate()?;
+    let output_format = cmd.output_format();
     let out = ic_regedit::execute_command(cmd)?;
-    let out = serde_json::to_string_pretty(&out).expect("Could not pretty print value.");
-    println!("{}", out);
+    match output_format {
+        OutputFormat::Json => {
+            let out = serde_json::to_string_pretty(&out).expect("Could not pretty print value.");
+            println!("{}", out);
+        }
+        OutputFormat::Text => print!("{}", ic_regedit::render_diff_as_text(out)?),
+    }
     Ok(())
 }
@@ -4,32 +4,39 @@ use serde_json::Value;
 
 use ic_protobuf::{
     registry::{
-        crypto::v1::{PublicKey, X509PublicKeyCert},
-        firewall::v1::FirewallConfig,
+        crypto::v1::{EcdsaSigningSubnetList, PublicKey, X509PublicKeyCert},
+        dc::v1::DataCenterRecord,
+        firewall::v1::{FirewallConfig, FirewallRuleSet},
         nns::v1::NnsCanisterRecords,
         node_operator::v1::NodeOperatorRecord,
+        node_rewards::v2::NodeRewardsTable,
         provisional_whitelist::v1::ProvisionalWhitelist,
         replica_version::v1::{BlessedReplicaVersions, ReplicaVersionRecord},
-        routing_table::v1::RoutingTable,
+        routing_table::v1::{CanisterMigrations, RoutingTable},
         subnet::v1::{CatchUpPackageContents, SubnetListRecord, SubnetRecord},
+        unassigned_nodes_config::v1::UnassignedNodesConfigRecord,
     },
     types::v1::SubnetId as SubnetIdProto,
 };
 use ic_registry_client_helpers::node::NodeRecord;
 use ic_registry_keys::{
-    make_blessed_replica_version_key, make_firewall_config_record_key,
-    make_nns_canister_records_key, make_provisional_whitelist_record_key,
-    make_routing_table_record_key, make_subnet_list_record_key, CRYPTO_RECORD_KEY_PREFIX,
-    CRYPTO_THRESHOLD_SIGNING_KEY_PREFIX, CRYPTO_TLS_CERT_KEY_PREFIX,
-    NODE_OPERATOR_RECORD_KEY_PREFIX, NODE_RECORD_KEY_PREFIX, REPLICA_VERSION_KEY_PREFIX,
-    ROOT_SUBNET_ID_KEY, SUBNET_RECORD_KEY_PREFIX,
+    make_blessed_replica_version_key, make_canister_migrations_record_key,
+    make_firewall_config_record_key, make_nns_canister_records_key,
+    make_provisional_whitelist_record_key, make_routing_table_record_key,
+    make_subnet_list_record_key, make_unassigned_nodes_config_record_key, CRYPTO_RECORD_KEY_PREFIX,
+    CRYPTO_THRESHOLD_SIGNING_KEY_PREFIX, CRYPTO_TLS_CERT_KEY_PREFIX, DATA_CENTER_KEY_PREFIX,
+    ECDSA_SIGNING_SUBNET_LIST_KEY_PREFIX, FIREWALL_RULES_RECORD_KEY_PREFIX,
+    NODE_OPERATOR_RECORD_KEY_PREFIX, NODE_RECORD_KEY_PREFIX, NODE_REWARDS_TABLE_KEY,
+    REPLICA_VERSION_KEY_PREFIX, ROOT_SUBNET_ID_KEY, SUBNET_RECORD_KEY_PREFIX,
 };
 pub(crate) trait Transformable {
     fn pb_to_value(data: &[u8]) -> Value;
     fn value_to_pb(value: Value) -> Vec<u8>;
 
     fn transformers() -> Transformers {
+        let type_name = std::any::type_name::<Self>();
         Transformers {
+            name: type_name.rsplit("::").next().unwrap_or(type_name),
             d: Self::pb_to_value,
             s: Self::value_to_pb,
         }
@@ -53,6 +60,8 @@ where
     }
 }
 pub(crate) struct Transformers {
+    /// The name of the protobuf message stored under a key.
+    pub name: &'static str,
     pub d: fn(&[u8]) -> Value,
     pub s: fn(Value) -> Vec<u8>,
 }
@@ -65,6 +74,12 @@ pub(crate) fn value_to_raw_data(key: &str, value: Value) -> Vec<u8> {
     (get_transformer(key).s)(value)
 }
 
+/// Returns the name of the type of the record stored under `key`, or
+/// "unknown" if the record is not decoded.
+pub(crate) fn record_type_name(key: &str) -> &'static str {
+    get_transformer(key).name
+}
+
 /// Translates the protobuf encoded values (of a key/value pair) into a
 /// self-describing structure. The semantics of JSON are used for the latter.
 fn get_transformer(key: &str) -> Transformers {
@@ -98,8 +113,21 @@ fn get_transformer(key: &str) -> Transformers {
         CatchUpPackageContents::transformers()
     } else if key.starts_with(&make_nns_canister_records_key()) {
         NnsCanisterRecords::transformers()
+    } else if key.starts_with(FIREWALL_RULES_RECORD_KEY_PREFIX) {
+        FirewallRuleSet::transformers()
+    } else if key.starts_with(ECDSA_SIGNING_SUBNET_LIST_KEY_PREFIX) {
+        EcdsaSigningSubnetList::transformers()
+    } else if key.starts_with(DATA_CENTER_KEY_PREFIX) {
+        DataCenterRecord::transformers()
+    } else if key == make_canister_migrations_record_key() {
+        CanisterMigrations::transformers()
+    } else if key == make_unassigned_nodes_config_record_key() {
+        UnassignedNodesConfigRecord::transformers()
+    } else if key == NODE_REWARDS_TABLE_KEY {
+        NodeRewardsTable::transformers()
     } else {
         Transformers {
+            name: "unknown",
             d: unknown_message_to_value,
             s: value_to_bytes,
         }
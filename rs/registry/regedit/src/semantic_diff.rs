@@ -0,0 +1,192 @@
+//! Human-readable diffs between two versions of the registry.
+//!
+//! In contrast to the diffs in [crate::diff], which are meant to be applied
+//! to a local store, a semantic diff describes, for each record that changed,
+//! which fields of the decoded record changed and how.
+use crate::{
+    normalization::NormalizedSnapshot,
+    protobuf::record_type_name,
+    snapshot::{SPECIAL_FIELD_PREFIX, VERSION_FIELD},
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SemanticDiff {
+    pub from_version: u64,
+    pub to_version: u64,
+    /// The changed records, sorted by key.
+    pub changes: Vec<RecordChange>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordChange {
+    pub key: String,
+    /// The name of the protobuf message stored under `key`.
+    pub record_type: String,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    Added { value: Value },
+    Removed { value: Value },
+    Modified { fields: Vec<FieldChange> },
+}
+
+/// A change of a single field of a record. `path` is a JSON pointer into the
+/// decoded record, e.g. `/membership`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub path: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+/// Computes the semantic diff between two normalized snapshots.
+pub fn make_semantic_diff(
+    from: &NormalizedSnapshot,
+    to: &NormalizedSnapshot,
+) -> Result<SemanticDiff> {
+    let (from_version, from_records) = split_snapshot(from)?;
+    let (to_version, to_records) = split_snapshot(to)?;
+
+    let mut changes = vec![];
+    for (key, from_value) in from_records.iter() {
+        let change = match to_records.get(key) {
+            None => Change::Removed {
+                value: from_value.clone(),
+            },
+            Some(to_value) if to_value != from_value => {
+                let mut fields = vec![];
+                diff_values("", from_value, to_value, &mut fields);
+                Change::Modified { fields }
+            }
+            Some(_) => continue,
+        };
+        changes.push(record_change(key, change));
+    }
+    for (key, to_value) in to_records.iter() {
+        if !from_records.contains_key(key) {
+            let change = Change::Added {
+                value: to_value.clone(),
+            };
+            changes.push(record_change(key, change));
+        }
+    }
+    changes.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(SemanticDiff {
+        from_version,
+        to_version,
+        changes,
+    })
+}
+
+fn record_change(key: &str, change: Change) -> RecordChange {
+    RecordChange {
+        key: key.to_string(),
+        record_type: record_type_name(key).to_string(),
+        change,
+    }
+}
+
+/// Splits a snapshot into its version and its records.
+fn split_snapshot(snapshot: &NormalizedSnapshot) -> Result<(u64, Map<String, Value>)> {
+    let obj = snapshot
+        .0
+        .as_object()
+        .ok_or_else(|| anyhow!("Snapshot is not an object."))?;
+    let version = obj
+        .get(VERSION_FIELD)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow!("Snapshot does not have a version."))?;
+    let records = obj
+        .iter()
+        .filter(|(k, _)| !k.starts_with(SPECIAL_FIELD_PREFIX))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    Ok((version, records))
+}
+
+/// Records the differences between `from` and `to` in `fields`. Objects are
+/// compared field by field and arrays of the same length element by element;
+/// any other difference is recorded as a change of the whole value.
+fn diff_values(path: &str, from: &Value, to: &Value, fields: &mut Vec<FieldChange>) {
+    match (from, to) {
+        _ if from == to => {}
+        (Value::Object(from), Value::Object(to)) => {
+            for (k, from_value) in from.iter() {
+                let path = format!("{}/{}", path, escape_pointer_token(k));
+                diff_values(&path, from_value, to.get(k).unwrap_or(&Value::Null), fields);
+            }
+            for (k, to_value) in to.iter().filter(|(k, _)| !from.contains_key(*k)) {
+                let path = format!("{}/{}", path, escape_pointer_token(k));
+                diff_values(&path, &Value::Null, to_value, fields);
+            }
+        }
+        (Value::Array(from), Value::Array(to)) if from.len() == to.len() => {
+            for (i, (from_value, to_value)) in from.iter().zip(to.iter()).enumerate() {
+                diff_values(&format!("{}/{}", path, i), from_value, to_value, fields);
+            }
+        }
+        _ => fields.push(FieldChange {
+            path: if path.is_empty() {
+                "/".to_string()
+            } else {
+                path.to_string()
+            },
+            from: from.clone(),
+            to: to.clone(),
+        }),
+    }
+}
+
+/// Escapes a key for use in a JSON pointer, see RFC 6901.
+fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+impl fmt::Display for SemanticDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Registry diff from version {} to version {}: {} record(s) changed",
+            self.from_version,
+            self.to_version,
+            self.changes.len()
+        )?;
+        for change in self.changes.iter() {
+            writeln!(f)?;
+            match &change.change {
+                Change::Added { value } => {
+                    writeln!(f, "+ {} ({})", change.key, change.record_type)?;
+                    write_indented(f, value)?;
+                }
+                Change::Removed { value } => {
+                    writeln!(f, "- {} ({})", change.key, change.record_type)?;
+                    write_indented(f, value)?;
+                }
+                Change::Modified { fields } => {
+                    writeln!(f, "~ {} ({})", change.key, change.record_type)?;
+                    for field in fields {
+                        writeln!(f, "    {}: {} -> {}", field.path, field.from, field.to)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_indented(f: &mut fmt::Formatter<'_>, value: &Value) -> fmt::Result {
+    let pretty = serde_json::to_string_pretty(value).map_err(|_| fmt::Error)?;
+    for line in pretty.lines() {
+        writeln!(f, "    {}", line)?;
+    }
+    Ok(())
+}
@@ -1,6 +1,6 @@
 #![cfg(test)]
 use crate::{
-    args::{universal_projection, Command, RegistrySpec, SourceSpec, VersionSpec},
+    args::{universal_projection, Command, OutputFormat, RegistrySpec, SourceSpec, VersionSpec},
     diff::DELETED_MARKER,
     execute_command, normalization,
     snapshot::SPECIAL_FIELD_PREFIX,
@@ -11,6 +11,7 @@ use ic_prep_lib::{
     prep_state_directory::IcPrepStateDir,
     subnet_configuration::SubnetConfig,
 };
+use ic_registry_keys::SUBNET_RECORD_KEY_PREFIX;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use std::{
@@ -108,6 +109,134 @@ fn adding_deleting_values_shows_up_in_diff() {
     assert_eq!(expected_snapshot.0, final_snapshot);
 }
 
+#[test]
+fn diff_lists_added_and_removed_records() {
+    let (_guard, ic_prep_dir) = run_ic_prep();
+    let registry_spec = local_store_latest_snapshot(ic_prep_dir.registry_local_store_path());
+    let mut snapshot = execute_command(Command::Snapshot {
+        registry_spec,
+        projection: universal_projection(),
+    })
+    .unwrap();
+
+    let obj = snapshot.as_object_mut().unwrap();
+    let removed_key = obj.keys().rev().next().unwrap().clone();
+    assert!(obj.remove(&removed_key).is_some());
+    let new_key = "a_key_that_does_not_exist".to_string();
+    obj.insert(
+        new_key.clone(),
+        serde_json::to_value("(binary-data)00").unwrap(),
+    );
+
+    execute_command(Command::ApplyUpdate {
+        local_store_path: ic_prep_dir.registry_local_store_path(),
+        snapshot,
+        amend: false,
+    })
+    .unwrap();
+
+    let out = execute_command(Command::Diff {
+        source: SourceSpec::LocalStore(ic_prep_dir.registry_local_store_path()),
+        from: VersionSpec::RelativeToLatest(1),
+        to: VersionSpec::RelativeToLatest(0),
+        output_format: OutputFormat::Json,
+    })
+    .unwrap();
+
+    assert_eq!(out["from_version"], 1);
+    assert_eq!(out["to_version"], 2);
+    let changes: Vec<_> = out["changes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| (c["key"].as_str().unwrap(), c["kind"].as_str().unwrap()))
+        .collect();
+    let mut expected = vec![
+        (new_key.as_str(), "added"),
+        (removed_key.as_str(), "removed"),
+    ];
+    expected.sort();
+    assert_eq!(changes, expected);
+
+    let text = crate::render_diff_as_text(out).unwrap();
+    assert!(
+        text.contains(&format!("+ {} (unknown)", new_key)),
+        "{}",
+        text
+    );
+    assert!(text.contains(&format!("- {} (", removed_key)), "{}", text);
+}
+
+#[test]
+fn diff_lists_changed_fields_of_decoded_records() {
+    let (_guard, ic_prep_dir) = run_ic_prep();
+    let registry_spec = local_store_latest_snapshot(ic_prep_dir.registry_local_store_path());
+    let mut snapshot = execute_command(Command::Snapshot {
+        registry_spec,
+        projection: universal_projection(),
+    })
+    .unwrap();
+
+    let obj = snapshot.as_object_mut().unwrap();
+    let subnet_key = obj
+        .keys()
+        .find(|k| k.starts_with(SUBNET_RECORD_KEY_PREFIX))
+        .unwrap()
+        .clone();
+    let field = obj
+        .get_mut(&subnet_key)
+        .unwrap()
+        .get_mut("max_ingress_bytes_per_message")
+        .unwrap();
+    let old_value = field.as_u64().unwrap();
+    *field = serde_json::to_value(old_value + 1).unwrap();
+
+    execute_command(Command::ApplyUpdate {
+        local_store_path: ic_prep_dir.registry_local_store_path(),
+        snapshot,
+        amend: false,
+    })
+    .unwrap();
+
+    let out = execute_command(Command::Diff {
+        source: SourceSpec::LocalStore(ic_prep_dir.registry_local_store_path()),
+        from: VersionSpec::RelativeToLatest(1),
+        to: VersionSpec::RelativeToLatest(0),
+        output_format: OutputFormat::Json,
+    })
+    .unwrap();
+
+    assert_eq!(
+        out["changes"],
+        serde_json::json!([{
+            "key": subnet_key,
+            "record_type": "SubnetRecord",
+            "kind": "modified",
+            "fields": [{
+                "path": "/max_ingress_bytes_per_message",
+                "from": old_value,
+                "to": old_value + 1,
+            }],
+        }])
+    );
+
+    let text = crate::render_diff_as_text(out).unwrap();
+    assert!(
+        text.contains(&format!("~ {} (SubnetRecord)", subnet_key)),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains(&format!(
+            "    /max_ingress_bytes_per_message: {} -> {}",
+            old_value,
+            old_value + 1
+        )),
+        "{}",
+        text
+    );
+}
+
 pub fn local_store_latest_snapshot(path: PathBuf) -> RegistrySpec {
     let source = SourceSpec::LocalStore(path);
     let version = VersionSpec::RelativeToLatest(0);
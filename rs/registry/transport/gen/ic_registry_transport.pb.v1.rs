@@ -64,6 +64,12 @@ pub struct RegistryDelta {
 pub struct RegistryGetChangesSinceRequest {
     #[prost(uint64, tag = "1")]
     pub version: u64,
+    /// If non-zero, the maximum number of versions to return. The registry
+    /// may return fewer versions, e.g. to keep the response size bounded.
+    ///
+    /// Only honored by get_certified_changes_since.
+    #[prost(uint64, tag = "2")]
+    pub max_versions: u64,
 }
 /// Message corresponding to the response from the registry
 /// canister to a get_latest_version() request.
@@ -226,7 +226,20 @@ pub fn serialize_get_changes_since_response(
 // be used in the registry canister only and thus there is no problem with
 // leaking the PB structs to the rest of the code base.
 pub fn serialize_get_changes_since_request(version: u64) -> Result<Vec<u8>, Error> {
-    let request = pb::v1::RegistryGetChangesSinceRequest { version };
+    serialize_get_changes_since_request_with_max_versions(version, None)
+}
+
+/// Serializes a get_changes_since() request that asks for at most
+/// `max_versions` versions, or as many versions as the registry canister is
+/// willing to return if `max_versions` is `None`.
+pub fn serialize_get_changes_since_request_with_max_versions(
+    version: u64,
+    max_versions: Option<u64>,
+) -> Result<Vec<u8>, Error> {
+    let request = pb::v1::RegistryGetChangesSinceRequest {
+        version,
+        max_versions: max_versions.unwrap_or_default(),
+    };
     let mut buf = Vec::new();
     match request.encode(&mut buf) {
         Ok(_) => Ok(buf),
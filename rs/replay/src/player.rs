@@ -306,6 +306,7 @@ impl Player {
             cfg.hypervisor.clone(),
             Arc::clone(&cycles_account_manager),
             Arc::clone(&state_manager) as Arc<_>,
+            &replica_version,
         );
         let message_routing = Arc::new(MessageRoutingImpl::new(
             state_manager.clone(),
@@ -680,6 +681,7 @@ impl Player {
                 .map_err(|err| format!("{}", err))?,
             ingress_expiry: ingress_expiry.as_nanos_since_unix_epoch(),
             nonce: None,
+            height: None,
         };
         match self.http_query_handler.query(
             query,
@@ -715,6 +717,7 @@ impl Player {
             method_payload: Vec::new(),
             ingress_expiry: ingress_expiry.as_nanos_since_unix_epoch(),
             nonce: None,
+            height: None,
         };
         match self.http_query_handler.query(
             query,
@@ -751,6 +754,7 @@ impl Player {
             method_payload: payload,
             ingress_expiry: ingress_expiry.as_nanos_since_unix_epoch(),
             nonce: None,
+            height: None,
         };
         match self.http_query_handler.query(
             query,
@@ -781,6 +785,7 @@ impl Player {
             .map_err(|err| format!("{}", err))?,
             ingress_expiry: ingress_expiry.as_nanos_since_unix_epoch(),
             nonce: None,
+            height: None,
         };
         match self.http_query_handler.query(
             query,
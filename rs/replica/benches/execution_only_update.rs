@@ -21,7 +21,7 @@ use ic_types::{
     batch::{Batch, BatchPayload, IngressPayload},
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::SignedIngress,
-    Randomness, RegistryVersion,
+    Randomness, RegistryVersion, ReplicaVersion,
 };
 use ic_types::{messages::MessageId, replica_config::ReplicaConfig, CanisterId};
 use std::time::{Duration, Instant};
@@ -209,6 +209,7 @@ fn criterion_calls(criterion: &mut Criterion) {
             ExecutionConfig::default(),
             Arc::clone(&cycles_account_manager),
             Arc::clone(&state_manager) as Arc<_>,
+            &ReplicaVersion::default(),
         )
         .into_parts();
 
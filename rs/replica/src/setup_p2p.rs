@@ -4,7 +4,7 @@ use ic_config::{artifact_pool::ArtifactPoolConfig, subnet_config::SubnetConfig,
 use ic_consensus::certification::VerifierImpl;
 use ic_crypto::CryptoComponent;
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{ExecutionServices, QueryStatsPayloadBuilderImpl};
 use ic_interfaces::execution_environment::AnonymousQueryService;
 use ic_interfaces::{
     certified_stream_store::CertifiedStreamStore,
@@ -22,7 +22,7 @@ use ic_replica_setup_ic_network::{
 };
 use ic_replicated_state::ReplicatedState;
 use ic_state_manager::StateManagerImpl;
-use ic_types::{consensus::catchup::CUPWithOriginalProtobuf, NodeId, SubnetId};
+use ic_types::{consensus::catchup::CUPWithOriginalProtobuf, NodeId, ReplicaVersion, SubnetId};
 use ic_xnet_endpoint::{XNetEndpoint, XNetEndpointConfig};
 use ic_xnet_payload_builder::XNetPayloadBuilderImpl;
 use std::sync::Arc;
@@ -151,15 +151,22 @@ pub fn construct_ic_stack(
         Some(artifact_pools.consensus_pool_cache.starting_height()),
         config.malicious_behaviour.malicious_flags.clone(),
     ));
+    let mut hypervisor_config = config.hypervisor.clone();
+    if hypervisor_config.compilation_cache_dir.is_none() {
+        hypervisor_config.compilation_cache_dir =
+            Some(state_manager.state_layout().compilation_cache());
+    }
     let execution_services = ExecutionServices::setup_execution(
         replica_logger.clone(),
         &metrics_registry,
         subnet_id,
         subnet_type,
         subnet_config.scheduler_config,
-        config.hypervisor.clone(),
+        hypervisor_config,
         Arc::clone(&cycles_account_manager),
         Arc::clone(&state_manager) as Arc<_>,
+        // Set from the command line arguments on startup.
+        &ReplicaVersion::default(),
     );
 
     let certified_stream_store: Arc<dyn CertifiedStreamStore> =
@@ -240,6 +247,13 @@ pub fn construct_ic_stack(
     );
     let self_validating_payload_builder = Arc::new(self_validating_payload_builder);
 
+    let query_stats_payload_builder = Arc::new(QueryStatsPayloadBuilderImpl::new(
+        Arc::clone(&execution_services.query_stats_collector),
+        Arc::clone(&state_manager) as Arc<_>,
+        node_id,
+        replica_logger.clone(),
+    ));
+
     let canister_http_adapter_client = ic_canister_http_adapter_client::setup_canister_http_client(
         rt_handle.clone(),
         &metrics_registry,
@@ -263,6 +277,7 @@ pub fn construct_ic_stack(
         P2PStateSyncClient::Client(Arc::clone(&state_manager) as Arc<_>),
         xnet_payload_builder as Arc<_>,
         self_validating_payload_builder as Arc<_>,
+        query_stats_payload_builder as Arc<_>,
         message_router as Arc<_>,
         // TODO(SCL-213)
         Arc::clone(&crypto) as Arc<_>,
@@ -698,6 +698,7 @@ impl LocalTestRuntime {
             method_payload: method_payload.into(),
             ingress_expiry: 0,
             nonce: None,
+            height: None,
         };
         let result = self.query_handler.query(
             query,
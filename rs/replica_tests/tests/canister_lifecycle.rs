@@ -4,7 +4,8 @@ use ic_config::Config;
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_ic00_types::{
     self as ic00, CanisterIdRecord, CanisterInstallMode, CanisterStatusResultV2,
-    CanisterStatusType, EmptyBlob, InstallCodeArgs, Method, Payload, SetControllerArgs, IC_00,
+    CanisterStatusType, EmptyBlob, InstallCodeArgs, Method, Payload, QueryStatsResult,
+    SetControllerArgs, IC_00,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_replica_tests as utils;
@@ -679,6 +680,7 @@ fn can_get_canister_information() {
                 None,
                 2592000,
                 0u128,
+                QueryStatsResult::default(),
             )
         );
 
@@ -730,6 +732,7 @@ fn can_get_canister_information() {
                     None,
                     259200,
                     0u128,
+                    QueryStatsResult::default(),
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
                 2 * BALANCE_EPSILON,
@@ -13,6 +13,7 @@ use ic_protobuf::{
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
+    batch::QueryStats,
     messages::{Ingress, Request, RequestOrResponse, Response, StopCanisterContext},
     nominal_cycles::NominalCycles,
     CanisterId, Cycles, MemoryAllocation, NumBytes, PrincipalId, QueueIndex, Time,
@@ -96,6 +97,16 @@ pub struct SystemState {
     /// Tasks to execute before processing input messages.
     /// Currently the task queue is empty outside of execution rounds.
     pub task_queue: VecDeque<ExecutionTask>,
+
+    /// The query statistics of the canister, summed over the reports of all
+    /// nodes delivered through consensus.
+    pub total_query_stats: QueryStats,
+
+    /// Incremented whenever the canister executes messages, its code is
+    /// installed or uninstalled, or its settings are updated, so that
+    /// non-replicated query results computed against one version can be
+    /// reused as long as the version stays the same.
+    pub canister_version: u64,
 }
 
 /// A wrapper around the different canister statuses.
@@ -358,6 +369,8 @@ impl SystemState {
             certified_data: Default::default(),
             canister_metrics: CanisterMetrics::default(),
             task_queue: Default::default(),
+            total_query_stats: QueryStats::default(),
+            canister_version: 0,
         }
     }
 
@@ -389,6 +402,8 @@ impl SystemState {
         canister_metrics: CanisterMetrics,
         cycles_balance: Cycles,
         task_queue: VecDeque<ExecutionTask>,
+        total_query_stats: QueryStats,
+        canister_version: u64,
     ) -> Self {
         Self {
             controllers,
@@ -401,6 +416,8 @@ impl SystemState {
             canister_metrics,
             cycles_balance,
             task_queue,
+            total_query_stats,
+            canister_version,
         }
     }
 
@@ -644,6 +661,15 @@ impl SystemState {
         &self.queues
     }
 
+    /// Replaces the canister queues with a copy of the queues of `other`.
+    ///
+    /// Used to carry over the messages that were inducted into the queues of
+    /// a canister while an `install_code` execution on a copy of it was
+    /// paused.
+    pub fn copy_queues_from(&mut self, other: &SystemState) {
+        self.queues = other.queues.clone();
+    }
+
     /// Returns a boolean whether the system state is ready to be `Stopped`.
     /// Only relevant for a `Stopping` system state.
     pub fn ready_to_stop(&self) -> bool {
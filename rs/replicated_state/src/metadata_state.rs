@@ -25,6 +25,7 @@ use ic_registry_subnet_features::{BitcoinFeature, BitcoinFeatureStatus, SubnetFe
 use ic_registry_subnet_type::SubnetType;
 use ic_types::nominal_cycles::NominalCycles;
 use ic_types::{
+    batch::{QueryStatsEpoch, QueryStatsPayload},
     crypto::CryptoHash,
     ingress::{IngressState, IngressStatus},
     messages::{MessageId, RequestOrResponse},
@@ -33,6 +34,7 @@ use ic_types::{
     time::{Time, UNIX_EPOCH},
     xnet::{StreamHeader, StreamIndex, StreamIndexedQueue, StreamSlice},
     CountBytes, CryptoHashOfPartialState, NodeId, NumBytes, PrincipalId, SubnetId,
+    MAX_WASM64_MEMORY_IN_BYTES,
 };
 use ic_wasm_types::WasmHash;
 use serde::{Deserialize, Serialize};
@@ -139,6 +141,14 @@ pub struct SystemMetadata {
 
     pub subnet_metrics: SubnetMetrics,
 
+    /// The last query stats epoch whose statistics were reported by each node,
+    /// used to reject duplicate reports.
+    pub last_reported_query_stats_epochs: BTreeMap<NodeId, QueryStatsEpoch>,
+
+    /// The query stats reports of the epochs that are not aggregated yet, in
+    /// the order in which they were delivered.
+    pub query_stats_reports: Vec<QueryStatsPayload>,
+
     /// The set of WASM modules we expect to be present in the [`Hypervisor`]'s
     /// compilation cache. This allows us to deterministically decide when we
     /// expect a compilation to be fast and ignore the compilation cost when
@@ -210,6 +220,15 @@ impl NetworkTopology {
             .get(subnet_id)
             .map(|subnet_topology| subnet_topology.nodes.len())
     }
+
+    /// Returns the maximum size of a 64-bit Wasm memory on the given subnet,
+    /// or `MAX_WASM64_MEMORY_IN_BYTES` if the subnet is unknown.
+    pub fn get_max_wasm64_memory_size(&self, subnet_id: &SubnetId) -> NumBytes {
+        self.subnets
+            .get(subnet_id)
+            .map(|subnet_topology| subnet_topology.max_wasm64_memory_size)
+            .unwrap_or_else(|| NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES))
+    }
 }
 
 impl From<&NetworkTopology> for pb_metadata::NetworkTopology {
@@ -310,6 +329,8 @@ pub struct SubnetTopology {
     /// a backup. An additional NNS proposal will be needed to allow the subnet
     /// holding the key as backup to actually produce signatures.
     pub ecdsa_keys_held: BTreeSet<EcdsaKeyId>,
+    /// The maximum size of a 64-bit Wasm memory of canisters on this subnet.
+    pub max_wasm64_memory_size: NumBytes,
 }
 
 impl From<&SubnetTopology> for pb_metadata::SubnetTopology {
@@ -327,6 +348,7 @@ impl From<&SubnetTopology> for pb_metadata::SubnetTopology {
             subnet_type: i32::from(item.subnet_type),
             subnet_features: Some(pb_subnet::SubnetFeatures::from(item.subnet_features)),
             ecdsa_keys_held: item.ecdsa_keys_held.iter().map(|k| k.into()).collect(),
+            max_wasm64_memory_size: item.max_wasm64_memory_size.get(),
         }
     }
 }
@@ -362,6 +384,7 @@ impl TryFrom<pb_metadata::SubnetTopology> for SubnetTopology {
                 .map(SubnetFeatures::from)
                 .unwrap_or_default(),
             ecdsa_keys_held,
+            max_wasm64_memory_size: NumBytes::new(item.max_wasm64_memory_size),
         })
     }
 }
@@ -453,6 +476,15 @@ impl From<&SystemMetadata> for pb_metadata::SystemMetadata {
                     .as_nanos_since_unix_epoch(),
             }),
             subnet_metrics: Some((&item.subnet_metrics).into()),
+            last_reported_query_stats_epochs: item
+                .last_reported_query_stats_epochs
+                .iter()
+                .map(|(node_id, epoch)| pb_metadata::QueryStatsEpochEntry {
+                    node_id: Some(node_id_into_protobuf(*node_id)),
+                    epoch: epoch.get(),
+                })
+                .collect(),
+            query_stats_reports: item.query_stats_reports.iter().map(Into::into).collect(),
         }
     }
 }
@@ -481,6 +513,28 @@ impl TryFrom<pb_metadata::SystemMetadata> for SystemMetadata {
             .last_generated_canister_id
             .map(TryInto::try_into)
             .transpose()?;
+        let mut last_reported_query_stats_epochs = BTreeMap::new();
+        for entry in item.last_reported_query_stats_epochs {
+            last_reported_query_stats_epochs.insert(
+                node_id_try_from_protobuf(try_from_option_field(
+                    entry.node_id,
+                    "SystemMetadata::last_reported_query_stats_epochs::K",
+                )?)?,
+                QueryStatsEpoch::from(entry.epoch),
+            );
+        }
+        let query_stats_reports = item
+            .query_stats_reports
+            .into_iter()
+            .map(|report| {
+                QueryStatsPayload::try_from(report).map_err(|err| {
+                    ProxyDecodeError::ValueOutOfRange {
+                        typ: "QueryStatsPayload",
+                        err,
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
         // Validate that `last_generated_canister_id` (if not `None`) is within the
         // first `canister_allocation_ranges` range.
         if let Some(last_generated_canister_id) = last_generated_canister_id {
@@ -541,6 +595,8 @@ impl TryFrom<pb_metadata::SystemMetadata> for SystemMetadata {
                 Some(subnet_metrics) => subnet_metrics.try_into()?,
                 None => SubnetMetrics::default(),
             },
+            last_reported_query_stats_epochs,
+            query_stats_reports,
             expected_compiled_wasms: BTreeSet::new(),
         })
     }
@@ -569,6 +625,8 @@ impl SystemMetadata {
             heap_delta_estimate: NumBytes::from(0),
             time_of_last_allocation_charge: UNIX_EPOCH,
             subnet_metrics: Default::default(),
+            last_reported_query_stats_epochs: BTreeMap::new(),
+            query_stats_reports: Vec::new(),
             expected_compiled_wasms: BTreeSet::new(),
         }
     }
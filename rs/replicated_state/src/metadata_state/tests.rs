@@ -19,6 +19,7 @@ use ic_types::{
     canister_http::{CanisterHttpMethod, CanisterHttpRequestContext},
     ingress::WasmResult,
     messages::{CallbackId, Payload},
+    MAX_WASM64_MEMORY_IN_BYTES,
 };
 use lazy_static::lazy_static;
 use maplit::btreemap;
@@ -536,6 +537,7 @@ fn network_topology_bitcoin_testnet_subnets() {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::from_str("bitcoin_testnet").unwrap(),
                 ecdsa_keys_held: BTreeSet::new(),
+                max_wasm64_memory_size: NumBytes::from(0),
             },
 
             // A subnet with the bitcoin testnet feature paused.
@@ -545,6 +547,7 @@ fn network_topology_bitcoin_testnet_subnets() {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::from_str("bitcoin_testnet_paused").unwrap(),
                 ecdsa_keys_held: BTreeSet::new(),
+                max_wasm64_memory_size: NumBytes::from(0),
             },
 
             // A subnet without the bitcoin feature enabled.
@@ -554,6 +557,7 @@ fn network_topology_bitcoin_testnet_subnets() {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                max_wasm64_memory_size: NumBytes::from(0),
             }
         ],
         routing_table: Arc::new(RoutingTable::default()),
@@ -587,6 +591,36 @@ fn network_topology_ecdsa_subnets() {
     );
 }
 
+#[test]
+fn network_topology_max_wasm64_memory_size() {
+    let network_topology = NetworkTopology {
+        subnets: btreemap![
+            subnet_test_id(0) => SubnetTopology {
+                max_wasm64_memory_size: NumBytes::from(8 << 30),
+                ..SubnetTopology::default()
+            },
+        ],
+        ..NetworkTopology::default()
+    };
+
+    assert_eq!(
+        network_topology.get_max_wasm64_memory_size(&subnet_test_id(0)),
+        NumBytes::from(8 << 30)
+    );
+    // Unknown subnets fall back to the protocol-wide upper limit.
+    assert_eq!(
+        network_topology.get_max_wasm64_memory_size(&subnet_test_id(1)),
+        NumBytes::from(MAX_WASM64_MEMORY_IN_BYTES)
+    );
+
+    let decoded =
+        NetworkTopology::try_from(pb_metadata::NetworkTopology::from(&network_topology)).unwrap();
+    assert_eq!(
+        decoded.get_max_wasm64_memory_size(&subnet_test_id(0)),
+        NumBytes::from(8 << 30)
+    );
+}
+
 /// Test fixture that will produce an ingress status of type completed or failed,
 /// depending on whether `i % 2 == 0` (completed) or not (failed). Both statuses
 /// will have the same payload size.
@@ -199,6 +199,8 @@ pub enum PersistenceError {
     },
     /// (Slice) size is not equal to page size.
     BadPageSize { expected: usize, actual: usize },
+    /// Overlay file is malformed.
+    InvalidOverlayFile { path: String, reason: String },
 }
 
 impl PersistenceError {
@@ -244,6 +246,9 @@ impl std::fmt::Display for PersistenceError {
                 "Bad slice size: expected {}, actual {}",
                 expected, actual
             ),
+            PersistenceError::InvalidOverlayFile { path, reason } => {
+                write!(f, "Invalid overlay file {}: {}", path, reason)
+            }
         }
     }
 }
@@ -264,8 +269,9 @@ pub type FileOffset = off_t;
 /// pages share the same backing store. There are three possible cases:
 /// - The page is not in the current `PageMap` and it is zero initialized.
 /// - The page maps to the checkpoint file.
-/// - The page is in the page delta of the current `PageMap`. In this case the
-///   range is a singleton and its contents need to be copied out.
+/// - The page is in the page delta of the current `PageMap` or in an overlay
+///   file of the checkpoint. In this case the range is a singleton and its
+///   contents need to be copied out.
 pub enum MemoryRegion<'a> {
     Zeros(Range<PageIndex>),
     BackedByFile(Range<PageIndex>, FileDescriptor),
@@ -325,7 +331,19 @@ impl PageMap {
     ///
     /// Note that the file is assumed to be read-only.
     pub fn open(heap_file: &Path, base_height: Option<Height>) -> Result<Self, PersistenceError> {
-        let checkpoint = Checkpoint::open(heap_file)?;
+        Self::open_with_overlays::<&Path>(heap_file, &[], base_height)
+    }
+
+    /// Creates a page map backed by the provided heap file and the given
+    /// overlay files on top of it, ordered from the oldest to the newest.
+    ///
+    /// Note that all files are assumed to be read-only.
+    pub fn open_with_overlays<P: AsRef<Path>>(
+        heap_file: &Path,
+        overlays: &[P],
+        base_height: Option<Height>,
+    ) -> Result<Self, PersistenceError> {
+        let checkpoint = Checkpoint::open_with_overlays(heap_file, overlays)?;
         Ok(Self {
             checkpoint,
             base_height,
@@ -405,6 +423,31 @@ impl PageMap {
         self.persist_to_file_and_sync(&self.page_delta, dst)
     }
 
+    /// Persists the heap delta contained in this page map as a new overlay
+    /// file at the specified destination and fsyncs it. The destination must
+    /// not exist.
+    pub fn persist_delta_as_overlay(&self, dst: &Path) -> Result<(), PersistenceError> {
+        checkpoint::write_overlay(
+            self.page_delta
+                .iter()
+                .map(|(index, page)| (index, page.contents())),
+            dst,
+        )
+    }
+
+    /// Writes the checkpoint backing this page map, including all its
+    /// overlays, into a single heap file at the specified destination. The
+    /// page delta is not included. The destination must not exist.
+    pub fn merge_checkpoint_to_file(&self, dst: &Path) -> Result<(), PersistenceError> {
+        self.checkpoint.merge_to_file(dst)
+    }
+
+    /// Returns the number of overlay files of the checkpoint backing this
+    /// page map.
+    pub fn num_checkpoint_overlays(&self) -> usize {
+        self.checkpoint.num_overlays()
+    }
+
     /// Persists the round delta contained in this page map to the specified
     /// destination.
     pub fn persist_round_delta(&self, dst: &Path) -> Result<(), PersistenceError> {
@@ -457,12 +500,11 @@ impl PageMap {
         }
     }
 
-    /// Returns the whole checkpoint memory region.
+    /// Returns the whole memory region of the checkpoint file. Pages in
+    /// overlay files of the checkpoint are not part of that file and must be
+    /// looked up with `get_memory_region()`.
     pub fn get_checkpoint_memory_region(&self) -> MemoryRegion {
-        let start = PageIndex::new(0);
-        let end = PageIndex::new(u64::MAX);
-        self.checkpoint
-            .get_memory_region(start, Range { start, end })
+        self.checkpoint.get_base_memory_region()
     }
 
     /// Removes the page delta from this page map.
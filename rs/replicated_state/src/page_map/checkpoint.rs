@@ -4,7 +4,9 @@ use ic_sys::{page_bytes_from_ptr, PageBytes};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::fs::{File, OpenOptions};
+use std::io::Write;
 use std::ops::Range;
+use std::os::unix::fs::FileExt;
 use std::os::unix::io::AsRawFd;
 use std::os::unix::prelude::FromRawFd;
 use std::path::Path;
@@ -16,14 +18,28 @@ lazy_static! {
     static ref ZEROED_PAGE: Box<PageBytes> = Box::new([0; PAGE_SIZE]);
 }
 
+/// The version of the overlay file format.
+const OVERLAY_VERSION: u64 = 0;
+
+/// The size of an entry of the page index of an overlay file.
+const OVERLAY_INDEX_ENTRY_SIZE: usize = std::mem::size_of::<u64>();
+
+/// The size of the trailer of an overlay file: the number of pages followed by
+/// the version.
+const OVERLAY_TRAILER_SIZE: usize = 2 * std::mem::size_of::<u64>();
+
 /// Checkpoint represents a full snapshot of the heap of a single Wasm
 /// module.
 ///
-/// Conceptually it's an immutable byte array backed by a file and
-/// aligned to a page boundary.
+/// Conceptually it's an immutable byte array backed by a base file
+/// aligned to a page boundary, and a stack of overlay files, each of
+/// which replaces some of the pages of the layers below it.
 #[derive(Clone)]
 pub(crate) struct Checkpoint {
     mapping: Option<Arc<Mapping>>,
+    /// The overlays on top of `mapping`, ordered from the oldest to the
+    /// newest one.
+    overlays: Vec<Arc<Overlay>>,
 }
 
 struct Mapping {
@@ -64,22 +80,7 @@ impl Mapping {
     }
 
     fn open(path: &Path) -> Result<Option<Mapping>, PersistenceError> {
-        let file = OpenOptions::new().read(true).open(path).map_err(|err| {
-            PersistenceError::FileSystemError {
-                path: path.display().to_string(),
-                context: "Failed to open file".to_string(),
-                internal_error: err.to_string(),
-            }
-        })?;
-        let metadata = file
-            .metadata()
-            .map_err(|err| PersistenceError::FileSystemError {
-                path: path.display().to_string(),
-                context: "Failed to retrieve file metadata".to_string(),
-                internal_error: err.to_string(),
-            })?;
-
-        let len = metadata.len() as usize;
+        let (file, len) = open_file(path)?;
         if len % PAGE_SIZE != 0 {
             return Err(PersistenceError::InvalidHeapFile {
                 path: path.display().to_string(),
@@ -130,7 +131,7 @@ impl Mapping {
         let num_pages = (self.mmap.len() / PAGE_SIZE) as u64;
         if page_index.get() >= num_pages {
             MemoryRegion::Zeros(Range {
-                start: PageIndex::new(num_pages),
+                start: PageIndex::new(num_pages.max(page_range.start.get())),
                 end: page_range.end,
             })
         } else {
@@ -149,17 +150,215 @@ impl Mapping {
     }
 }
 
+/// Opens the file at the given path for reading and returns it together with
+/// its length.
+fn open_file(path: &Path) -> Result<(File, usize), PersistenceError> {
+    let file = OpenOptions::new().read(true).open(path).map_err(|err| {
+        PersistenceError::FileSystemError {
+            path: path.display().to_string(),
+            context: "Failed to open file".to_string(),
+            internal_error: err.to_string(),
+        }
+    })?;
+    let metadata = file
+        .metadata()
+        .map_err(|err| PersistenceError::FileSystemError {
+            path: path.display().to_string(),
+            context: "Failed to retrieve file metadata".to_string(),
+            internal_error: err.to_string(),
+        })?;
+    Ok((file, metadata.len() as usize))
+}
+
+/// An overlay file contains the pages of a page map that changed between two
+/// checkpoints. Its layout is
+///
+/// ```text
+/// [page contents: n * PAGE_SIZE][page indices: n * u64][n: u64][version: u64]
+/// ```
+///
+/// where all integers are little-endian and the page indices are strictly
+/// increasing. The i-th page index is the index of the i-th page in the file.
+///
+/// Overlay files are written once and never modified afterwards.
+struct Overlay {
+    mapping: Mapping,
+    page_indices: Vec<PageIndex>,
+}
+
+impl Overlay {
+    fn open(path: &Path) -> Result<Overlay, PersistenceError> {
+        let (file, len) = open_file(path)?;
+        let mapping = Mapping::new(file, len, Some(path))?;
+        Self::from_mapping(mapping, &path.display().to_string())
+    }
+
+    fn deserialize(serialized_mapping: MappingSerialization) -> Result<Overlay, PersistenceError> {
+        let fd = serialized_mapping.file_descriptor.fd;
+        let mapping = Mapping::deserialize(serialized_mapping)?;
+        Self::from_mapping(mapping, &format!("/proc/self/fd/{}", fd))
+    }
+
+    /// Validates the trailer and reads the page index of the given mapping.
+    fn from_mapping(mapping: Option<Mapping>, path: &str) -> Result<Overlay, PersistenceError> {
+        let invalid = |reason: String| PersistenceError::InvalidOverlayFile {
+            path: path.to_string(),
+            reason,
+        };
+        let mapping = mapping.ok_or_else(|| invalid("the file is empty".to_string()))?;
+        let bytes = mapping.mmap.as_slice();
+        if bytes.len() < OVERLAY_TRAILER_SIZE {
+            return Err(invalid(format!("the file has only {} bytes", bytes.len())));
+        }
+        let trailer = &bytes[bytes.len() - OVERLAY_TRAILER_SIZE..];
+        let num_pages = read_u64(&trailer[0..8]) as usize;
+        let version = read_u64(&trailer[8..16]);
+        if version != OVERLAY_VERSION {
+            return Err(invalid(format!("unsupported version {}", version)));
+        }
+        let expected_len = num_pages
+            .checked_mul(PAGE_SIZE + OVERLAY_INDEX_ENTRY_SIZE)
+            .and_then(|len| len.checked_add(OVERLAY_TRAILER_SIZE));
+        if expected_len != Some(bytes.len()) {
+            return Err(invalid(format!(
+                "the file has {} bytes but contains {} pages",
+                bytes.len(),
+                num_pages
+            )));
+        }
+        let index_start = num_pages * PAGE_SIZE;
+        let page_indices: Vec<PageIndex> = bytes
+            [index_start..index_start + num_pages * OVERLAY_INDEX_ENTRY_SIZE]
+            .chunks_exact(OVERLAY_INDEX_ENTRY_SIZE)
+            .map(|entry| PageIndex::new(read_u64(entry)))
+            .collect();
+        if page_indices.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(invalid("the page indices are not sorted".to_string()));
+        }
+        Ok(Overlay {
+            mapping,
+            page_indices,
+        })
+    }
+
+    /// Returns the page with the given index if it is contained in this
+    /// overlay.
+    fn get_page(&self, page_index: PageIndex) -> Option<&PageBytes> {
+        let position = self.page_indices.binary_search(&page_index).ok()?;
+        let page_start = (position * PAGE_SIZE) as isize;
+        // SAFETY: The memory from `page_start` to `page_start + PAGE_SIZE` is mapped
+        // and will remain valid for the lifetime of `self`. The memory is read-only
+        // and does not have any mutable references to it.
+        Some(unsafe { page_bytes_from_ptr(self, self.mapping.mmap.addr().offset(page_start)) })
+    }
+
+    /// Returns the largest page index in this overlay that is smaller than
+    /// `page_index` and the smallest one that is larger than `page_index`.
+    fn bounds(&self, page_index: PageIndex) -> (Option<PageIndex>, Option<PageIndex>) {
+        let position = match self.page_indices.binary_search(&page_index) {
+            Ok(position) | Err(position) => position,
+        };
+        let lower = position
+            .checked_sub(1)
+            .map(|position| self.page_indices[position]);
+        let upper = self
+            .page_indices
+            .iter()
+            .skip(position)
+            .find(|index| **index > page_index)
+            .copied();
+        (lower, upper)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (PageIndex, &PageBytes)> {
+        self.page_indices
+            .iter()
+            .map(move |index| (*index, self.get_page(*index).unwrap()))
+    }
+
+    fn num_pages(&self) -> usize {
+        self.page_indices
+            .last()
+            .map(|index| index.get() as usize + 1)
+            .unwrap_or(0)
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+/// Writes the given pages to `dst` in the overlay file format and syncs the
+/// file. The pages must be sorted by their indices.
+pub(crate) fn write_overlay<'a>(
+    pages: impl Iterator<Item = (PageIndex, &'a PageBytes)>,
+    dst: &Path,
+) -> Result<(), PersistenceError> {
+    let fs_error = |context: &str, err: std::io::Error| PersistenceError::FileSystemError {
+        path: dst.display().to_string(),
+        context: context.to_string(),
+        internal_error: err.to_string(),
+    };
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dst)
+        .map_err(|err| fs_error("Failed to create overlay file", err))?;
+    let mut page_indices: Vec<u8> = vec![];
+    let mut num_pages: u64 = 0;
+    {
+        let mut writer = std::io::BufWriter::new(&mut file);
+        for (index, contents) in pages {
+            writer
+                .write_all(contents)
+                .map_err(|err| fs_error("Failed to write page", err))?;
+            page_indices.extend_from_slice(&index.get().to_le_bytes());
+            num_pages += 1;
+        }
+        page_indices.extend_from_slice(&num_pages.to_le_bytes());
+        page_indices.extend_from_slice(&OVERLAY_VERSION.to_le_bytes());
+        writer
+            .write_all(&page_indices)
+            .map_err(|err| fs_error("Failed to write page index", err))?;
+        writer
+            .flush()
+            .map_err(|err| fs_error("Failed to flush overlay file", err))?;
+    }
+    file.sync_all()
+        .map_err(|err| fs_error("Failed to sync overlay file", err))
+}
+
 impl Checkpoint {
     /// Returns an empty checkpoint, not backed by any file. It serves
     /// zeroed pages.
     pub fn empty() -> Checkpoint {
-        Checkpoint { mapping: None }
+        Checkpoint {
+            mapping: None,
+            overlays: vec![],
+        }
     }
 
     /// Opens an existing heap file located at the specified path.
     pub fn open(path: &Path) -> Result<Checkpoint, PersistenceError> {
-        Mapping::open(path).map(|mapping| Checkpoint {
+        Self::open_with_overlays(path, &[])
+    }
+
+    /// Opens an existing heap file located at the specified path together
+    /// with the given overlay files, ordered from the oldest to the newest.
+    pub fn open_with_overlays<P: AsRef<Path>>(
+        path: &Path,
+        overlays: &[P],
+    ) -> Result<Checkpoint, PersistenceError> {
+        let mapping = Mapping::open(path)?;
+        let overlays = overlays
+            .iter()
+            .map(|overlay| Overlay::open(overlay.as_ref()).map(Arc::new))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Checkpoint {
             mapping: mapping.map(Arc::new),
+            overlays,
         })
     }
 
@@ -167,6 +366,11 @@ impl Checkpoint {
     pub fn serialize(&self) -> CheckpointSerialization {
         CheckpointSerialization {
             mapping: self.mapping.as_ref().map(|mapping| mapping.serialize()),
+            overlays: self
+                .overlays
+                .iter()
+                .map(|overlay| overlay.mapping.serialize())
+                .collect(),
         }
     }
 
@@ -179,13 +383,31 @@ impl Checkpoint {
             None => None,
             Some(mapping) => Mapping::deserialize(mapping)?,
         };
+        let overlays = serialized_checkpoint
+            .overlays
+            .into_iter()
+            .map(|overlay| Overlay::deserialize(overlay).map(Arc::new))
+            .collect::<Result<Vec<_>, _>>()?;
         Ok(Checkpoint {
             mapping: mapping.map(Arc::new),
+            overlays,
         })
     }
 
     /// Returns the page with the specified `page_number`.
     pub fn get_page(&self, page_index: PageIndex) -> &PageBytes {
+        if let Some(page) = self
+            .overlays
+            .iter()
+            .rev()
+            .find_map(|overlay| overlay.get_page(page_index))
+        {
+            return page;
+        }
+        self.get_base_page(page_index)
+    }
+
+    fn get_base_page(&self, page_index: PageIndex) -> &PageBytes {
         match self.mapping {
             Some(ref mapping) => mapping.get_page(page_index),
             None => &ZEROED_PAGE,
@@ -199,22 +421,98 @@ impl Checkpoint {
         page_range: Range<PageIndex>,
     ) -> MemoryRegion {
         assert!(page_range.contains(&page_index));
+        let mut page_range = page_range;
+        for overlay in self.overlays.iter().rev() {
+            if let Some(page) = overlay.get_page(page_index) {
+                return MemoryRegion::BackedByPage(page);
+            }
+            // Pages of overlays must not be part of a region backed by the
+            // base file.
+            let (lower, upper) = overlay.bounds(page_index);
+            if let Some(lower) = lower {
+                page_range.start = page_range.start.max(PageIndex::new(lower.get() + 1));
+            }
+            if let Some(upper) = upper {
+                page_range.end = page_range.end.min(upper);
+            }
+        }
         match self.mapping {
             Some(ref mapping) => mapping.get_memory_region(page_index, page_range),
             None => MemoryRegion::Zeros(page_range),
         }
     }
 
+    /// Returns the whole memory region backed by the base file, ignoring
+    /// the overlays.
+    pub fn get_base_memory_region(&self) -> MemoryRegion {
+        let start = PageIndex::new(0);
+        let end = PageIndex::new(u64::MAX);
+        match self.mapping {
+            Some(ref mapping) => mapping.get_memory_region(start, Range { start, end }),
+            None => MemoryRegion::Zeros(Range { start, end }),
+        }
+    }
+
     /// Returns the max number of (possibly) non-zero pages in this
     /// checkpoint.
     pub fn num_pages(&self) -> usize {
-        match self.mapping {
+        let base_pages = match self.mapping {
             Some(ref mapping) => mapping.num_pages(),
             None => 0,
+        };
+        self.overlays
+            .iter()
+            .map(|overlay| overlay.num_pages())
+            .fold(base_pages, usize::max)
+    }
+
+    /// Returns the number of overlay files of this checkpoint.
+    pub fn num_overlays(&self) -> usize {
+        self.overlays.len()
+    }
+
+    /// Writes the contents of this checkpoint into a single file at `dst`
+    /// that can be opened without any overlays, and syncs it.
+    pub fn merge_to_file(&self, dst: &Path) -> Result<(), PersistenceError> {
+        let fs_error = |context: &str, err: std::io::Error| PersistenceError::FileSystemError {
+            path: dst.display().to_string(),
+            context: context.to_string(),
+            internal_error: err.to_string(),
+        };
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dst)
+            .map_err(|err| fs_error("Failed to create merged file", err))?;
+        file.set_len((self.num_pages() * PAGE_SIZE) as u64)
+            .map_err(|err| fs_error("Failed to set the length of merged file", err))?;
+        // The file is sparse, so only the non-zero pages of the base need to be
+        // written before applying the overlays from the oldest to the newest.
+        if let Some(ref mapping) = self.mapping {
+            for i in 0..mapping.num_pages() {
+                let index = PageIndex::new(i as u64);
+                let contents = mapping.get_page(index);
+                if contents.iter().any(|b| *b != 0) {
+                    write_page(&file, index, contents)
+                        .map_err(|err| fs_error("Failed to write page", err))?;
+                }
+            }
         }
+        for overlay in self.overlays.iter() {
+            for (index, contents) in overlay.iter() {
+                write_page(&file, index, contents)
+                    .map_err(|err| fs_error("Failed to write page", err))?;
+            }
+        }
+        file.sync_all()
+            .map_err(|err| fs_error("Failed to sync merged file", err))
     }
 }
 
+fn write_page(file: &File, index: PageIndex, contents: &PageBytes) -> std::io::Result<()> {
+    file.write_all_at(contents, index.get() * PAGE_SIZE as u64)
+}
+
 impl Default for Checkpoint {
     fn default() -> Self {
         Self::empty()
@@ -238,4 +536,5 @@ pub struct MappingSerialization {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CheckpointSerialization {
     pub mapping: Option<MappingSerialization>,
+    pub overlays: Vec<MappingSerialization>,
 }
@@ -1,7 +1,8 @@
 use super::{
     checkpoint::{Checkpoint, MappingSerialization},
     page_allocator::PageAllocatorSerialization,
-    Buffer, FileDescriptor, PageAllocator, PageDelta, PageIndex, PageMap, PageMapSerialization,
+    Buffer, FileDescriptor, MemoryRegion, PageAllocator, PageDelta, PageIndex, PageMap,
+    PageMapSerialization, PersistenceError,
 };
 use ic_sys::PAGE_SIZE;
 use nix::unistd::dup;
@@ -17,21 +18,30 @@ fn assert_equal_page_maps(page_map1: &PageMap, page_map2: &PageMap) {
     }
 }
 
+fn duplicate_mapping(mapping: MappingSerialization) -> MappingSerialization {
+    MappingSerialization {
+        file_descriptor: FileDescriptor {
+            fd: dup(mapping.file_descriptor.fd).unwrap(),
+        },
+        ..mapping
+    }
+}
+
 // Since tests run in the same process, we need to duplicate all file
 // descriptors so that both page maps can close them.
 fn duplicate_file_descriptors(
     mut serialized_page_map: PageMapSerialization,
 ) -> PageMapSerialization {
-    serialized_page_map.checkpoint.mapping =
-        serialized_page_map
-            .checkpoint
-            .mapping
-            .map(|mapping| MappingSerialization {
-                file_descriptor: FileDescriptor {
-                    fd: dup(mapping.file_descriptor.fd).unwrap(),
-                },
-                ..mapping
-            });
+    serialized_page_map.checkpoint.mapping = serialized_page_map
+        .checkpoint
+        .mapping
+        .map(duplicate_mapping);
+    serialized_page_map.checkpoint.overlays = serialized_page_map
+        .checkpoint
+        .overlays
+        .into_iter()
+        .map(duplicate_mapping)
+        .collect();
     serialized_page_map.page_allocator = match serialized_page_map.page_allocator {
         PageAllocatorSerialization::Mmap(file_descriptor) => {
             PageAllocatorSerialization::Mmap(FileDescriptor {
@@ -190,6 +200,135 @@ fn returns_an_error_if_file_size_is_not_a_multiple_of_page_size() {
     }
 }
 
+/// Creates a page map with 50 pages persisted to `heap_file` and two overlays
+/// on top of it, and returns the equivalent in-memory page map.
+fn page_map_with_overlays(heap_file: &std::path::Path, overlays: &[std::path::PathBuf]) -> PageMap {
+    let base_page = [42u8; PAGE_SIZE];
+    let base_pages: Vec<(PageIndex, &[u8; PAGE_SIZE])> = (0..50)
+        .map(|i| (PageIndex::new(i as u64), &base_page))
+        .collect();
+    let mut expected = PageMap::default();
+    expected.update(base_pages.as_slice());
+    expected.persist_delta(heap_file).unwrap();
+
+    let page_1 = [1u8; PAGE_SIZE];
+    let page_3 = [3u8; PAGE_SIZE];
+    let page_60 = [60u8; PAGE_SIZE];
+    let page_3_again = [33u8; PAGE_SIZE];
+    let page_4 = [4u8; PAGE_SIZE];
+    let deltas: [&[(PageIndex, &[u8; PAGE_SIZE])]; 2] = [
+        &[
+            (PageIndex::new(1), &page_1),
+            (PageIndex::new(3), &page_3),
+            (PageIndex::new(60), &page_60),
+        ],
+        &[
+            (PageIndex::new(3), &page_3_again),
+            (PageIndex::new(4), &page_4),
+        ],
+    ];
+    for (delta, overlay) in deltas.iter().zip(overlays.iter()) {
+        let mut page_map = PageMap::open(heap_file, None).unwrap();
+        page_map.update(delta);
+        page_map.persist_delta_as_overlay(overlay).unwrap();
+        expected.update(delta);
+    }
+    expected
+}
+
+#[test]
+fn page_map_with_overlays_is_equivalent_to_the_original() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let overlays = vec![tmp.path().join("overlay_1"), tmp.path().join("overlay_2")];
+    let expected = page_map_with_overlays(&heap_file, &overlays);
+
+    let page_map = PageMap::open_with_overlays(&heap_file, &overlays, None).unwrap();
+
+    assert_eq!(page_map.num_checkpoint_overlays(), 2);
+    assert_eq!(page_map.num_host_pages(), 61);
+    assert_eq!(page_map, expected);
+    assert_eq!(page_map.get_page(PageIndex::new(3)), &[33u8; PAGE_SIZE]);
+
+    // Pages of overlays are copied out and are never part of a region backed
+    // by the heap file.
+    match page_map.get_memory_region(PageIndex::new(4)) {
+        MemoryRegion::BackedByPage(page) => assert_eq!(page, &[4u8; PAGE_SIZE]),
+        _ => panic!("Expected the page to be backed by an overlay page"),
+    }
+    match page_map.get_memory_region(PageIndex::new(10)) {
+        MemoryRegion::BackedByFile(range, _) => {
+            assert_eq!(range, PageIndex::new(5)..PageIndex::new(50))
+        }
+        _ => panic!("Expected the page to be backed by the heap file"),
+    }
+    match page_map.get_memory_region(PageIndex::new(55)) {
+        MemoryRegion::Zeros(range) => assert_eq!(range, PageIndex::new(50)..PageIndex::new(60)),
+        _ => panic!("Expected the page to be zero"),
+    }
+}
+
+#[test]
+fn merged_checkpoint_is_equivalent_to_page_map_with_overlays() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let overlays = vec![tmp.path().join("overlay_1"), tmp.path().join("overlay_2")];
+    let expected = page_map_with_overlays(&heap_file, &overlays);
+    let merged_file = tmp.path().join("merged");
+
+    PageMap::open_with_overlays(&heap_file, &overlays, None)
+        .unwrap()
+        .merge_checkpoint_to_file(&merged_file)
+        .unwrap();
+    let merged = PageMap::open(&merged_file, None).unwrap();
+
+    assert_eq!(merged.num_checkpoint_overlays(), 0);
+    assert_eq!(merged, expected);
+}
+
+#[test]
+fn serialize_page_map_with_overlays() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let overlays = vec![tmp.path().join("overlay_1"), tmp.path().join("overlay_2")];
+    let expected = page_map_with_overlays(&heap_file, &overlays);
+    let page_map = PageMap::open_with_overlays(&heap_file, &overlays, None).unwrap();
+
+    let serialized_page_map = duplicate_file_descriptors(page_map.serialize());
+    let deserialized_page_map = PageMap::deserialize(serialized_page_map).unwrap();
+
+    assert_equal_page_maps(&expected, &deserialized_page_map);
+}
+
+#[test]
+fn returns_an_error_if_overlay_file_is_truncated() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let overlays = vec![tmp.path().join("overlay_1"), tmp.path().join("overlay_2")];
+    page_map_with_overlays(&heap_file, &overlays);
+    let file = OpenOptions::new().write(true).open(&overlays[1]).unwrap();
+    let len = file.metadata().unwrap().len();
+    file.set_len(len - 1).unwrap();
+
+    match PageMap::open_with_overlays(&heap_file, &overlays, None) {
+        Err(PersistenceError::InvalidOverlayFile { .. }) => {}
+        Err(err) => panic!("Expected an invalid overlay file error, got {:?}", err),
+        Ok(_) => panic!("Expected an invalid overlay file error, got Ok(_)"),
+    }
+}
+
 #[test]
 fn can_use_buffer_to_modify_page_map() {
     let page_1 = [1u8; PAGE_SIZE];
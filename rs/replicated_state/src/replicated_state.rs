@@ -20,15 +20,16 @@ use ic_registry_subnet_features::BitcoinFeatureStatus;
 use ic_registry_subnet_type::SubnetType;
 use ic_types::messages::Ingress;
 use ic_types::{
+    batch::{QueryStats, QueryStatsEpoch, QueryStatsPayload},
     ingress::IngressStatus,
     messages::{CallbackId, MessageId, RequestOrResponse, Response},
     xnet::QueueId,
-    CanisterId, MemoryAllocation, NumBytes, QueueIndex, SubnetId, Time,
+    CanisterId, MemoryAllocation, NodeId, NumBytes, QueueIndex, SubnetId, Time,
 };
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -820,6 +821,98 @@ impl ReplicatedState {
         }
     }
 
+    /// Records the query statistics that a node collected during an epoch as
+    /// reported by that node, and aggregates the reports of every epoch that
+    /// is complete into the totals of the respective canisters.
+    ///
+    /// Reports for epochs that the node has already reported (or older ones)
+    /// are ignored. An epoch is complete once all nodes of the subnet have
+    /// reported it or once a later epoch is reported. Nodes that left the
+    /// subnet are forgotten.
+    pub fn apply_query_stats(&mut self, payload: &QueryStatsPayload) {
+        let last_reported_epochs = &mut self.metadata.last_reported_query_stats_epochs;
+        if let Some(last_reported_epoch) = last_reported_epochs.get(&payload.proposer) {
+            if *last_reported_epoch >= payload.epoch {
+                return;
+            }
+        }
+        last_reported_epochs.insert(payload.proposer, payload.epoch);
+
+        let subnet_nodes: Option<BTreeSet<NodeId>> = self
+            .metadata
+            .network_topology
+            .subnets
+            .get(&self.metadata.own_subnet_id)
+            .map(|subnet| subnet.nodes.keys().copied().collect());
+        if let Some(subnet_nodes) = &subnet_nodes {
+            last_reported_epochs.retain(|node_id, _| {
+                *node_id == payload.proposer || subnet_nodes.contains(node_id)
+            });
+        }
+
+        let mut reports_by_epoch: BTreeMap<QueryStatsEpoch, Vec<QueryStatsPayload>> =
+            BTreeMap::new();
+        for report in std::mem::take(&mut self.metadata.query_stats_reports)
+            .into_iter()
+            .chain(std::iter::once(payload.clone()))
+        {
+            reports_by_epoch
+                .entry(report.epoch)
+                .or_default()
+                .push(report);
+        }
+        for (epoch, reports) in reports_by_epoch {
+            let all_nodes_reported = subnet_nodes.as_ref().map_or(false, |subnet_nodes| {
+                subnet_nodes
+                    .iter()
+                    .all(|node_id| reports.iter().any(|report| report.proposer == *node_id))
+            });
+            if epoch < payload.epoch || all_nodes_reported {
+                self.aggregate_query_stats(&reports);
+            } else {
+                self.metadata.query_stats_reports.extend(reports);
+            }
+        }
+    }
+
+    /// Adds the query statistics reported by several nodes for the same epoch
+    /// to the totals of the respective canisters.
+    ///
+    /// Each statistic of a canister is estimated as the lower median of the
+    /// values reported by the nodes, counting nodes that didn't report the
+    /// canister as zero, times the number of reports. Unlike a plain sum, this
+    /// cannot be inflated by less than half of the reporting nodes. The
+    /// statistics of canisters that no longer exist are ignored.
+    fn aggregate_query_stats(&mut self, reports: &[QueryStatsPayload]) {
+        let mut reported_stats: BTreeMap<CanisterId, Vec<&QueryStats>> = BTreeMap::new();
+        for report in reports {
+            for entry in &report.canister_stats {
+                reported_stats
+                    .entry(entry.canister_id)
+                    .or_default()
+                    .push(&entry.stats);
+            }
+        }
+
+        let num_reports = reports.len();
+        for (canister_id, stats) in reported_stats {
+            if let Some(canister) = self.canister_states.get_mut(&canister_id) {
+                let estimate = |field: fn(&QueryStats) -> u64| {
+                    estimate_total(num_reports, stats.iter().copied().map(field))
+                };
+                canister
+                    .system_state
+                    .total_query_stats
+                    .saturating_accumulate(&QueryStats {
+                        num_calls: estimate(|stats| stats.num_calls),
+                        num_instructions: estimate(|stats| stats.num_instructions),
+                        ingress_payload_size: estimate(|stats| stats.ingress_payload_size),
+                        egress_payload_size: estimate(|stats| stats.egress_payload_size),
+                    });
+            }
+        }
+    }
+
     pub fn take_bitcoin_state(&mut self) -> BitcoinState {
         std::mem::take(&mut self.bitcoin)
     }
@@ -829,6 +922,16 @@ impl ReplicatedState {
     }
 }
 
+/// Estimates the sum of a statistic over `num_reports` reports as the lower
+/// median of the reported `values` times `num_reports`, where reports missing
+/// from `values` count as zero.
+fn estimate_total(num_reports: usize, values: impl Iterator<Item = u64>) -> u64 {
+    let mut values: Vec<u64> = values.collect();
+    values.resize(num_reports, 0);
+    values.sort_unstable();
+    values[(num_reports - 1) / 2].saturating_mul(num_reports as u64)
+}
+
 /// A trait exposing `ReplicatedState` functionality for the exclusive use of
 /// Message Routing.
 pub trait ReplicatedStateMessageRouting {
@@ -9,8 +9,8 @@ use ic_replicated_state::replicated_state::testing::ReplicatedStateTesting;
 use ic_replicated_state::testing::{CanisterQueuesTesting, SystemStateTesting};
 use ic_replicated_state::{
     replicated_state::PeekableOutputIterator, replicated_state::ReplicatedStateMessageRouting,
-    BitcoinStateError, CanisterState, InputQueueType, ReplicatedState, SchedulerState, StateError,
-    SystemState,
+    BitcoinStateError, CanisterState, InputQueueType, NodeTopology, ReplicatedState,
+    SchedulerState, StateError, SubnetTopology, SystemState,
 };
 use ic_test_utilities::mock_time;
 use ic_test_utilities::state::{
@@ -18,10 +18,11 @@ use ic_test_utilities::state::{
 };
 use ic_test_utilities::types::ids::canister_test_id;
 use ic_test_utilities::types::{
-    ids::{subnet_test_id, user_test_id},
+    ids::{node_test_id, subnet_test_id, user_test_id},
     messages::{RequestBuilder, ResponseBuilder},
 };
 use ic_types::{
+    batch::{CanisterQueryStats, QueryStats, QueryStatsEpoch, QueryStatsPayload},
     messages::{CallbackId, RequestOrResponse, MAX_RESPONSE_COUNT_BYTES},
     CountBytes, Cycles, QueueIndex,
 };
@@ -779,6 +780,122 @@ fn state_equality_with_bitcoin() {
     assert_ne!(original_state, state);
 }
 
+fn query_stats(num_calls: u64) -> QueryStats {
+    QueryStats {
+        num_calls,
+        num_instructions: 1_000 * num_calls,
+        ingress_payload_size: 10 * num_calls,
+        egress_payload_size: 20 * num_calls,
+    }
+}
+
+fn query_stats_payload(node: u64, epoch: u64, num_calls: u64) -> QueryStatsPayload {
+    QueryStatsPayload {
+        epoch: QueryStatsEpoch::from(epoch),
+        proposer: node_test_id(node),
+        canister_stats: vec![CanisterQueryStats {
+            canister_id: CANISTER_ID,
+            stats: query_stats(num_calls),
+        }],
+    }
+}
+
+fn with_subnet_nodes(state: &mut ReplicatedState, nodes: &[u64]) {
+    state.metadata.network_topology.subnets.insert(
+        SUBNET_ID,
+        SubnetTopology {
+            nodes: nodes
+                .iter()
+                .map(|node| (node_test_id(*node), NodeTopology::default()))
+                .collect(),
+            ..Default::default()
+        },
+    );
+}
+
+fn total_query_stats(state: &ReplicatedState) -> QueryStats {
+    state
+        .canister_state(&CANISTER_ID)
+        .unwrap()
+        .system_state
+        .total_query_stats
+        .clone()
+}
+
+#[test]
+fn apply_query_stats_aggregates_median_once_all_nodes_reported() {
+    replicated_state_test(|mut state| {
+        with_subnet_nodes(&mut state, &[1, 2, 3]);
+
+        state.apply_query_stats(&query_stats_payload(1, 3, 2));
+        state.apply_query_stats(&query_stats_payload(2, 3, 4));
+        // Already reported by node 1.
+        state.apply_query_stats(&query_stats_payload(1, 3, 1_000));
+        state.apply_query_stats(&query_stats_payload(1, 2, 1_000));
+        assert_eq!(total_query_stats(&state), QueryStats::default());
+        assert_eq!(state.metadata.query_stats_reports.len(), 2);
+
+        // Node 3 inflates its report, which doesn't affect the median.
+        state.apply_query_stats(&query_stats_payload(3, 3, 1_000_000));
+        assert_eq!(total_query_stats(&state), query_stats(3 * 4));
+        assert!(state.metadata.query_stats_reports.is_empty());
+        assert_eq!(
+            state.metadata.last_reported_query_stats_epochs,
+            vec![
+                (node_test_id(1), QueryStatsEpoch::from(3)),
+                (node_test_id(2), QueryStatsEpoch::from(3)),
+                (node_test_id(3), QueryStatsEpoch::from(3)),
+            ]
+            .into_iter()
+            .collect()
+        );
+    });
+}
+
+#[test]
+fn apply_query_stats_aggregates_epoch_once_later_epoch_is_reported() {
+    replicated_state_test(|mut state| {
+        with_subnet_nodes(&mut state, &[1, 2, 3]);
+
+        state.apply_query_stats(&query_stats_payload(1, 3, 2));
+        state.apply_query_stats(&query_stats_payload(2, 3, 4));
+        assert_eq!(total_query_stats(&state), QueryStats::default());
+
+        // Node 3 never reports epoch 3. Once epoch 4 is reported, epoch 3 is
+        // aggregated from the two reports it got: the lower median of 2 and
+        // 4, times 2.
+        state.apply_query_stats(&query_stats_payload(1, 4, 5));
+        assert_eq!(total_query_stats(&state), query_stats(2 * 2));
+        assert_eq!(
+            state.metadata.query_stats_reports,
+            vec![query_stats_payload(1, 4, 5)]
+        );
+    });
+}
+
+#[test]
+fn apply_query_stats_forgets_nodes_that_left_the_subnet() {
+    replicated_state_test(|mut state| {
+        with_subnet_nodes(&mut state, &[1, 2, 3]);
+        state.apply_query_stats(&query_stats_payload(1, 3, 2));
+        state.apply_query_stats(&query_stats_payload(3, 3, 2));
+
+        with_subnet_nodes(&mut state, &[1, 2]);
+        state.apply_query_stats(&query_stats_payload(2, 3, 2));
+
+        assert_eq!(total_query_stats(&state), query_stats(3 * 2));
+        assert_eq!(
+            state.metadata.last_reported_query_stats_epochs,
+            vec![
+                (node_test_id(1), QueryStatsEpoch::from(3)),
+                (node_test_id(2), QueryStatsEpoch::from(3)),
+            ]
+            .into_iter()
+            .collect()
+        );
+    });
+}
+
 proptest! {
     #[test]
     fn peek_and_next_consistent(
@@ -0,0 +1,213 @@
+use crate::{BlockHeight, EncodedBlock, HashOf, Icrc1Agent, Icrc1AgentError};
+use futures::stream::Stream;
+use garcon::Waiter;
+use ic_icrc1::{endpoints::ArchivedBlocks, Block};
+use ic_ledger_core::block::BlockType;
+use num_traits::ToPrimitive;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The maximum number of blocks requested from the ledger at once.
+const DEFAULT_BATCH_SIZE: u64 = 2000;
+
+/// How long the stream waits before polling the ledger again once it
+/// reached the tip of the chain.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Follows the blocks of an ICRC-1 ledger, fetching them from the ledger and
+/// its archives.
+///
+/// Every batch starts with reading the certified tip of the ledger and ends
+/// at that tip: every block is checked to be the child of the previous one
+/// and the hash of the last block is checked against the certified tip hash.
+/// A batch is only returned, and the follower only advances, once the whole
+/// batch is verified, so every block returned by the follower is certified.
+pub struct BlockFollower {
+    agent: Icrc1Agent,
+    position: Position,
+    batch_size: u64,
+}
+
+/// The index of the next block to verify and the hash of its parent.
+#[derive(Clone, Copy)]
+struct Position {
+    next_index: BlockHeight,
+    last_hash: Option<HashOf<EncodedBlock>>,
+}
+
+impl Position {
+    fn verify_next(
+        &mut self,
+        encoded_block: EncodedBlock,
+    ) -> Result<(BlockHeight, Block), Icrc1AgentError> {
+        let index = self.next_index;
+        let hash = Block::block_hash(&encoded_block);
+        let block = Block::decode(encoded_block).map_err(|e| {
+            Icrc1AgentError::VerificationFailed(format!("failed to decode block {}: {}", index, e))
+        })?;
+        if block.parent_hash != self.last_hash {
+            return Err(Icrc1AgentError::VerificationFailed(format!(
+                "block {}: parent hash mismatch, expected: {:?}, got: {:?}",
+                index, self.last_hash, block.parent_hash
+            )));
+        }
+        self.last_hash = Some(hash);
+        self.next_index += 1;
+        Ok((index, block))
+    }
+}
+
+impl BlockFollower {
+    /// Creates a follower starting from the genesis block.
+    pub fn new(agent: Icrc1Agent) -> Self {
+        Self::resume(agent, 0, None)
+    }
+
+    /// Creates a follower starting from the block at `next_index`, whose
+    /// parent hash must be `last_hash`.
+    pub fn resume(
+        agent: Icrc1Agent,
+        next_index: BlockHeight,
+        last_hash: Option<HashOf<EncodedBlock>>,
+    ) -> Self {
+        Self {
+            agent,
+            position: Position {
+                next_index,
+                last_hash,
+            },
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Sets the maximum number of blocks requested from the ledger at once.
+    /// A batch returned by [BlockFollower::next_batch] always extends to the
+    /// certified tip and may therefore be made of several requests.
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// The index of the next block the follower will return.
+    pub fn next_index(&self) -> BlockHeight {
+        self.position.next_index
+    }
+
+    /// The hash of the last block the follower returned.
+    pub fn last_hash(&self) -> Option<HashOf<EncodedBlock>> {
+        self.position.last_hash
+    }
+
+    /// Fetches and verifies the blocks following the last returned one up to
+    /// the certified tip of the ledger. Returns an empty batch if the
+    /// follower is at the tip of the chain.
+    ///
+    /// If the verification fails, the follower stays at its current position.
+    pub async fn next_batch(&mut self) -> Result<Vec<(BlockHeight, Block)>, Icrc1AgentError> {
+        let (tip_hash, tip_index) = match self.agent.get_certified_chain_tip().await? {
+            Some(tip) => tip,
+            None => return Ok(vec![]),
+        };
+        if tip_index < self.position.next_index {
+            return Ok(vec![]);
+        }
+
+        // The blocks are verified against a copy of the position, which is
+        // only committed once the tip checks out.
+        let mut position = self.position;
+        let mut batch = vec![];
+        while position.next_index <= tip_index {
+            let length = (tip_index + 1 - position.next_index).min(self.batch_size);
+            if self.fetch(&mut position, length, &mut batch).await? == 0 {
+                return Err(Icrc1AgentError::VerificationFailed(format!(
+                    "the ledger returned no blocks from {} although its certified tip is {}",
+                    position.next_index, tip_index
+                )));
+            }
+        }
+        if position.last_hash != Some(tip_hash) {
+            return Err(Icrc1AgentError::VerificationFailed(format!(
+                "block {}: hash {:?} differs from the certified tip hash {}",
+                tip_index, position.last_hash, tip_hash
+            )));
+        }
+
+        self.position = position;
+        Ok(batch)
+    }
+
+    /// Returns a stream of all the blocks of the ledger starting from the
+    /// next index, polling the ledger for new blocks once it reached the tip.
+    pub fn into_stream(self) -> impl Stream<Item = Result<(BlockHeight, Block), Icrc1AgentError>> {
+        let mut waiter = garcon::Delay::builder()
+            .throttle(DEFAULT_POLL_INTERVAL)
+            .build();
+        waiter.start();
+        futures::stream::unfold(
+            (self, VecDeque::new(), waiter),
+            |(mut follower, mut buffer, mut waiter)| async move {
+                loop {
+                    if let Some(block) = buffer.pop_front() {
+                        return Some((Ok(block), (follower, buffer, waiter)));
+                    }
+                    match follower.next_batch().await {
+                        Ok(batch) if batch.is_empty() => {
+                            let _ = waiter.async_wait().await;
+                        }
+                        Ok(batch) => buffer.extend(batch),
+                        Err(e) => return Some((Err(e), (follower, buffer, waiter))),
+                    }
+                }
+            },
+        )
+    }
+
+    /// Fetches at most `length` blocks following `position` from the ledger
+    /// and its archives, verifies that they extend the chain and appends
+    /// them to `batch`. Returns the number of blocks appended.
+    async fn fetch(
+        &self,
+        position: &mut Position,
+        length: u64,
+        batch: &mut Vec<(BlockHeight, Block)>,
+    ) -> Result<u64, Icrc1AgentError> {
+        let start_index = position.next_index;
+        let response = self.agent.get_blocks(start_index, length).await?;
+
+        for ArchivedBlocks {
+            start,
+            length: archived_length,
+            canister_id,
+        } in response.archived_blocks
+        {
+            let start = to_u64(&start)?;
+            if start != position.next_index {
+                // A previous archive returned fewer blocks than requested.
+                return Ok(position.next_index - start_index);
+            }
+            let range = self
+                .agent
+                .get_archive_blocks(canister_id.get().0, start, to_u64(&archived_length)?)
+                .await?;
+            for encoded_block in range.blocks {
+                batch.push(position.verify_next(encoded_block)?);
+            }
+        }
+
+        if to_u64(&response.first_index)? == position.next_index {
+            // The chain may have grown since the tip was read, the blocks
+            // past it are verified by the next batch.
+            let remaining = (start_index + length).saturating_sub(position.next_index);
+            for encoded_block in response.blocks.into_iter().take(remaining as usize) {
+                batch.push(position.verify_next(encoded_block)?);
+            }
+        }
+        Ok(position.next_index - start_index)
+    }
+}
+
+fn to_u64(n: &candid::Nat) -> Result<u64, Icrc1AgentError> {
+    n.0.to_u64().ok_or_else(|| {
+        Icrc1AgentError::VerificationFailed(format!("block index {} does not fit into u64", n))
+    })
+}
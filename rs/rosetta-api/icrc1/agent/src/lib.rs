@@ -1,15 +1,28 @@
+pub mod block_follower;
+
 use candid::{Decode, Encode, Nat, Principal};
-use ic_agent::Agent;
+use garcon::Waiter;
+use ic_agent::agent::{Replied, RequestStatusResponse};
+use ic_agent::hash_tree::{HashTree, Label, LookupResult};
+use ic_agent::{lookup_value, Agent, AgentError};
+use ic_icrc1::Block;
 pub use ic_icrc1::{
-    endpoints::{TransferArg, TransferError, Value},
+    endpoints::{
+        BlockRange, DataCertificate, GetBlocksRequest, GetBlocksResponse, TransferArg,
+        TransferError, Value,
+    },
     Account,
 };
-pub use ic_ledger_core::block::BlockHeight;
+use ic_ledger_core::block::BlockType;
+pub use ic_ledger_core::block::{BlockHeight, EncodedBlock, HashOf};
+use std::convert::TryInto;
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug)]
 pub enum Icrc1AgentError {
     AgentError(ic_agent::AgentError),
     CandidError(candid::Error),
+    VerificationFailed(String),
 }
 
 impl From<ic_agent::AgentError> for Icrc1AgentError {
@@ -72,6 +85,9 @@ impl Icrc1Agent {
     }
 
     /// Returns the balance of the account given as argument.
+    ///
+    /// Use [CallMode::Update] or [Icrc1Agent::certified_balance_of] if the
+    /// result must be certified.
     pub async fn balance_of(
         &self,
         account: Account,
@@ -171,4 +187,312 @@ impl Icrc1Agent {
             Decode!(&self.update("icrc1_transfer", &Encode!(&args)?).await?, Result<Nat, TransferError>)?,
         )
     }
+
+    /// Returns the balance of the account given as argument, certified by the
+    /// subnet.
+    ///
+    /// The balance is computed by an update call. Its reply is read from the
+    /// `request_status` subtree of the state tree with `read_state`, and the
+    /// certificate returned by `read_state` is verified against the root key
+    /// before the reply is decoded.
+    pub async fn certified_balance_of(&self, account: Account) -> Result<Nat, Icrc1AgentError> {
+        let request_id = self
+            .agent
+            .update(&self.ledger_canister_id, "icrc1_balance_of")
+            .with_arg(&Encode!(&account)?)
+            .call()
+            .await?;
+
+        let mut waiter = garcon::Delay::builder()
+            .throttle(Duration::from_millis(500))
+            .timeout(Duration::from_secs(60 * 5))
+            .build();
+        waiter.start();
+        loop {
+            // `request_status_raw` reads the request status with `read_state`
+            // and verifies the certificate it gets back.
+            match self
+                .agent
+                .request_status_raw(&request_id, self.ledger_canister_id, false)
+                .await?
+            {
+                RequestStatusResponse::Replied {
+                    reply: Replied::CallReplied(reply),
+                } => return Ok(Decode!(&reply, Nat)?),
+                RequestStatusResponse::Rejected {
+                    reject_code,
+                    reject_message,
+                } => {
+                    return Err(Icrc1AgentError::AgentError(AgentError::ReplicaError {
+                        reject_code,
+                        reject_message,
+                    }))
+                }
+                RequestStatusResponse::Done => {
+                    return Err(Icrc1AgentError::AgentError(
+                        AgentError::RequestStatusDoneNoReply(String::from(request_id)),
+                    ))
+                }
+                RequestStatusResponse::Unknown
+                | RequestStatusResponse::Received
+                | RequestStatusResponse::Processing => waiter.async_wait().await.map_err(|_| {
+                    Icrc1AgentError::AgentError(AgentError::TimeoutWaitingForResponse())
+                })?,
+            }
+        }
+    }
+
+    /// Transfers tokens like [Icrc1Agent::transfer], retrying the call at most
+    /// `max_attempts` times on transient errors: transport failures, timeouts,
+    /// overloaded replicas and a temporarily unavailable ledger. Any other
+    /// error is returned immediately.
+    ///
+    /// If `created_at_time` is not set, it is set to the current time, so
+    /// that the ledger deduplicates the retries: a transfer that was applied
+    /// by a failed attempt is reported with its original block index.
+    pub async fn transfer_with_retries(
+        &self,
+        mut args: TransferArg,
+        max_attempts: usize,
+    ) -> Result<Result<Nat, TransferError>, Icrc1AgentError> {
+        if args.created_at_time.is_none() {
+            args.created_at_time = Some(
+                SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .expect("system time is before the UNIX epoch")
+                    .as_nanos() as u64,
+            );
+        }
+
+        let mut waiter = garcon::Delay::builder()
+            .exponential_backoff(Duration::from_millis(500), 2.0)
+            .build();
+        waiter.start();
+
+        let mut attempt = 1;
+        loop {
+            match self.transfer(args.clone()).await {
+                Ok(Err(TransferError::Duplicate { duplicate_of })) if attempt > 1 => {
+                    return Ok(Ok(duplicate_of))
+                }
+                Ok(Err(TransferError::TemporarilyUnavailable)) if attempt < max_attempts => {}
+                Err(Icrc1AgentError::AgentError(ref err))
+                    if is_transient(err) && attempt < max_attempts => {}
+                result => return result,
+            }
+            attempt += 1;
+            waiter
+                .async_wait()
+                .await
+                .map_err(|_| ic_agent::AgentError::TimeoutWaitingForResponse())?;
+        }
+    }
+
+    /// Executes the given transfers concurrently, each one with
+    /// [Icrc1Agent::transfer_with_retries]. The results are in the same
+    /// order as the arguments.
+    pub async fn transfer_batch(
+        &self,
+        args: Vec<TransferArg>,
+        max_attempts: usize,
+    ) -> Vec<Result<Result<Nat, TransferError>, Icrc1AgentError>> {
+        futures::future::join_all(
+            args.into_iter()
+                .map(|arg| self.transfer_with_retries(arg, max_attempts)),
+        )
+        .await
+    }
+
+    /// Returns at most `length` blocks starting from `start`, together with
+    /// the ranges of blocks to fetch from the archives.
+    pub async fn get_blocks(
+        &self,
+        start: BlockHeight,
+        length: u64,
+    ) -> Result<GetBlocksResponse, Icrc1AgentError> {
+        let req = GetBlocksRequest {
+            start: Nat::from(start),
+            length: Nat::from(length),
+        };
+        Ok(Decode!(
+            &self.query("get_blocks", &Encode!(&req)?).await?,
+            GetBlocksResponse
+        )?)
+    }
+
+    /// Returns at most `length` blocks starting from `start` from the given
+    /// archive canister.
+    pub async fn get_archive_blocks(
+        &self,
+        archive_canister_id: Principal,
+        start: BlockHeight,
+        length: u64,
+    ) -> Result<BlockRange, Icrc1AgentError> {
+        let req = GetBlocksRequest {
+            start: Nat::from(start),
+            length: Nat::from(length),
+        };
+        let bytes = self
+            .agent
+            .query(&archive_canister_id, "get_blocks")
+            .with_arg(&Encode!(&req)?)
+            .call()
+            .await?;
+        Ok(Decode!(&bytes, BlockRange)?)
+    }
+
+    /// Returns the certificate of the ledger state and the hash tree it covers.
+    pub async fn get_data_certificate(&self) -> Result<DataCertificate, Icrc1AgentError> {
+        Ok(Decode!(
+            &self.query("get_data_certificate", &Encode!()?).await?,
+            DataCertificate
+        )?)
+    }
+
+    /// Returns the hash and the index of the last block of the ledger,
+    /// verified against the root key of the agent. Returns `None` if the
+    /// ledger has no blocks.
+    ///
+    /// Only the hash is certified, so the index is obtained with a query and
+    /// verified by checking that the block at that index has the certified
+    /// hash. This fails if a new block was added in between.
+    pub async fn get_certified_chain_tip(
+        &self,
+    ) -> Result<Option<(HashOf<EncodedBlock>, BlockHeight)>, Icrc1AgentError> {
+        let DataCertificate {
+            certificate,
+            hash_tree,
+        } = self.get_data_certificate().await?;
+        let certificate = certificate.ok_or_else(|| {
+            Icrc1AgentError::VerificationFailed("the ledger returned no certificate".to_string())
+        })?;
+        let hash_tree: HashTree = serde_cbor::from_slice(&hash_tree).map_err(|e| {
+            Icrc1AgentError::VerificationFailed(format!("failed to decode hash tree: {}", e))
+        })?;
+        self.verify_certified_data(&certificate, &hash_tree.digest())?;
+
+        let tip_hash = match lookup_leaf(&hash_tree, "tip_hash")? {
+            Some(tip_hash) => tip_hash,
+            None => return Ok(None),
+        };
+        let tip_hash: [u8; 32] = tip_hash.try_into().map_err(|_| {
+            Icrc1AgentError::VerificationFailed(format!(
+                "expected a 32 bytes tip hash, got {}",
+                tip_hash.len()
+            ))
+        })?;
+        let tip_hash = HashOf::new(tip_hash);
+
+        let chain_length = self.get_blocks(0, 0).await?.chain_length;
+        let tip_index = chain_length.checked_sub(1).ok_or_else(|| {
+            Icrc1AgentError::VerificationFailed(
+                "the ledger has a certified tip hash but no blocks".to_string(),
+            )
+        })?;
+        let tip_block = self
+            .get_blocks(tip_index, 1)
+            .await?
+            .blocks
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                Icrc1AgentError::VerificationFailed(format!(
+                    "the ledger returned no block at its tip index {}",
+                    tip_index
+                ))
+            })?;
+        if Block::block_hash(&tip_block) != tip_hash {
+            return Err(Icrc1AgentError::VerificationFailed(format!(
+                "block {} does not have the certified tip hash {}",
+                tip_index, tip_hash
+            )));
+        }
+        Ok(Some((tip_hash, tip_index)))
+    }
+
+    /// Checks that `certificate` is valid and certifies `root_hash` as the
+    /// certified data of the ledger.
+    fn verify_certified_data(
+        &self,
+        certificate: &[u8],
+        root_hash: &[u8],
+    ) -> Result<(), Icrc1AgentError> {
+        let certificate = serde_cbor::from_slice(certificate).map_err(|e| {
+            Icrc1AgentError::VerificationFailed(format!("failed to decode certificate: {}", e))
+        })?;
+        self.agent
+            .verify(&certificate, self.ledger_canister_id, false)
+            .map_err(|e| {
+                Icrc1AgentError::VerificationFailed(format!("invalid certificate: {}", e))
+            })?;
+        let certified_data_path = [
+            Label::from("canister"),
+            Label::from(self.ledger_canister_id.as_slice()),
+            Label::from("certified_data"),
+        ];
+        let certified_data = lookup_value(&certificate, certified_data_path).map_err(|e| {
+            Icrc1AgentError::VerificationFailed(format!(
+                "certified data not found in the certificate: {}",
+                e
+            ))
+        })?;
+        if certified_data != root_hash {
+            return Err(Icrc1AgentError::VerificationFailed(
+                "the certified data does not match the hash tree root".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if `err` may go away when the call is retried, i.e. if the
+/// replica couldn't be reached, didn't answer in time or was overloaded.
+fn is_transient(err: &AgentError) -> bool {
+    match err {
+        AgentError::TransportError(_) | AgentError::TimeoutWaitingForResponse() => true,
+        AgentError::HttpError(payload) => payload.status == 429 || payload.status >= 500,
+        _ => false,
+    }
+}
+
+fn lookup_leaf<'a>(
+    hash_tree: &'a HashTree,
+    label: &str,
+) -> Result<Option<&'a [u8]>, Icrc1AgentError> {
+    match hash_tree.lookup_path(&[Label::from(label)]) {
+        LookupResult::Found(value) => Ok(Some(value)),
+        LookupResult::Absent => Ok(None),
+        _ => Err(Icrc1AgentError::VerificationFailed(format!(
+            "the hash tree does not prove the presence or absence of {}",
+            label
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_agent::agent::agent_error::HttpErrorPayload;
+
+    fn http_error(status: u16) -> AgentError {
+        AgentError::HttpError(HttpErrorPayload {
+            status,
+            content_type: None,
+            content: vec![],
+        })
+    }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        assert!(is_transient(&AgentError::TimeoutWaitingForResponse()));
+        assert!(is_transient(&AgentError::TransportError("reset".into())));
+        assert!(is_transient(&http_error(503)));
+        assert!(is_transient(&http_error(429)));
+
+        assert!(!is_transient(&http_error(400)));
+        assert!(!is_transient(&AgentError::ReplicaError {
+            reject_code: 5,
+            reject_message: "canister trapped".to_string(),
+        }));
+    }
 }
@@ -1,7 +1,11 @@
 use candid::{candid_method, Principal};
 use ic_cdk_macros::{init, post_upgrade, query, update};
-use ic_icrc1::{Block, CandidBlock};
+use ic_icrc1::{
+    endpoints::{BlockRange, GetBlocksRequest},
+    Block, CandidBlock,
+};
 use ic_ledger_core::block::{BlockHeight, BlockType, EncodedBlock};
+use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use stable_structures::{
     cell::Cell as StableCell, log::Log as StableLog, DefaultMemoryImpl, RestrictedMemory, Storable,
@@ -11,6 +15,9 @@ use std::cell::RefCell;
 
 const GIB: usize = 1024 * 1024 * 1024;
 
+/// The maximum number of blocks returned by a single `get_blocks` call.
+const MAX_BLOCKS_PER_RESPONSE: u64 = 2000;
+
 /// How much memory do we want to allocate for raw blocks.
 const DEFAULT_MEMORY_LIMIT: usize = 3 * GIB;
 
@@ -186,6 +193,39 @@ fn get_block(index: BlockHeight) -> Option<CandidBlock> {
     )
 }
 
+#[query]
+#[candid_method(query)]
+fn get_blocks(req: GetBlocksRequest) -> BlockRange {
+    let idx_offset = with_archive_opts(|opts| opts.block_index_offset);
+    let start = req.start.0.to_u64().unwrap_or(u64::MAX);
+    let length = req
+        .length
+        .0
+        .to_u64()
+        .unwrap_or(u64::MAX)
+        .min(MAX_BLOCKS_PER_RESPONSE);
+
+    let blocks = with_blocks(|blocks| {
+        if start < idx_offset {
+            return vec![];
+        }
+        let relative_start = start - idx_offset;
+        let relative_end = relative_start
+            .saturating_add(length)
+            .min(blocks.len() as u64);
+        (relative_start..relative_end)
+            .map(|i| {
+                EncodedBlock::from(
+                    blocks
+                        .get(i as usize)
+                        .expect("bug: block index within the log bounds"),
+                )
+            })
+            .collect()
+    });
+    BlockRange { blocks }
+}
+
 fn main() {}
 
 #[test]
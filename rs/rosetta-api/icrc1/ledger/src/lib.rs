@@ -5,6 +5,7 @@ use candid::{
     types::number::{Int, Nat},
     CandidType,
 };
+use ic_crypto_tree_hash::{Label, MixedHashTree};
 use ic_icrc1::endpoints::Value;
 use ic_icrc1::{Account, Block, LedgerBalances, Transaction};
 use ic_ledger_canister_core::{
@@ -254,14 +255,20 @@ impl Ledger {
     /// The canister code must call set_certified_data with the value this function returns after
     /// each successful modification of the ledger.
     pub fn root_hash(&self) -> [u8; 32] {
-        use ic_crypto_tree_hash::{Label, MixedHashTree as T};
-        let tree = match self.blockchain().last_hash {
-            Some(hash) => T::Labeled(
+        self.construct_hash_tree().digest().0
+    }
+
+    /// Returns the hash tree certifying the hash of the last block.
+    ///
+    /// The tree consists of a single `tip_hash` leaf. Clients verify the
+    /// certified data against this layout, so it must not change.
+    pub fn construct_hash_tree(&self) -> MixedHashTree {
+        match self.blockchain().last_hash {
+            Some(hash) => MixedHashTree::Labeled(
                 Label::from("tip_hash"),
-                Box::new(T::Leaf(hash.as_slice().to_vec())),
+                Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
             ),
-            None => T::Empty,
-        };
-        tree.digest().0
+            None => MixedHashTree::Empty,
+        }
     }
 }
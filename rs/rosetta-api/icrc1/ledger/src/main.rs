@@ -4,7 +4,10 @@ use ic_base_types::PrincipalId;
 use ic_cdk::api::stable::{StableReader, StableWriter};
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 use ic_icrc1::{
-    endpoints::{ArchiveInfo, StandardRecord, TransferArg, TransferError, Value},
+    endpoints::{
+        ArchiveInfo, ArchivedBlocks, DataCertificate, GetBlocksRequest, GetBlocksResponse,
+        StandardRecord, TransferArg, TransferError, Value,
+    },
     Account, Operation, Transaction,
 };
 use ic_icrc1_ledger::{InitArgs, Ledger};
@@ -13,10 +16,14 @@ use ic_ledger_canister_core::ledger::{
 };
 use ic_ledger_core::{timestamp::TimeStamp, tokens::Tokens};
 use num_traits::ToPrimitive;
+use serde_bytes::ByteBuf;
 use std::cell::RefCell;
 
 const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
 
+/// The maximum number of blocks returned by a single `get_blocks` call.
+const MAX_BLOCKS_PER_RESPONSE: u64 = 2000;
+
 thread_local! {
     static LEDGER: RefCell<Option<Ledger>> = RefCell::new(None);
 }
@@ -63,7 +70,8 @@ fn post_upgrade() {
             ciborium::de::from_reader(StableReader::default())
                 .expect("failed to decode ledger state"),
         );
-    })
+    });
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
 }
 
 #[query]
@@ -224,6 +232,67 @@ fn archives() -> Vec<ArchiveInfo> {
     })
 }
 
+#[query]
+#[candid_method(query)]
+fn get_blocks(req: GetBlocksRequest) -> GetBlocksResponse {
+    let start = req.start.0.to_u64().unwrap_or(u64::MAX);
+    let length = req
+        .length
+        .0
+        .to_u64()
+        .unwrap_or(u64::MAX)
+        .min(MAX_BLOCKS_PER_RESPONSE);
+    let end = start.saturating_add(length);
+
+    Access::with_ledger(|ledger| {
+        let blockchain = ledger.blockchain();
+        let local_range = blockchain.local_block_range();
+        let local_start = start.clamp(local_range.start, local_range.end);
+        let local_end = end.clamp(local_start, local_range.end);
+        let blocks = blockchain.blocks
+            [(local_start - local_range.start) as usize..(local_end - local_range.start) as usize]
+            .to_vec();
+
+        let archived_blocks = blockchain
+            .archive
+            .read()
+            .unwrap()
+            .as_ref()
+            .iter()
+            .flat_map(|archive| archive.index())
+            .filter_map(|((first, last), canister_id)| {
+                // The archive index ranges are inclusive.
+                let range_start = first.max(start);
+                let range_end = (last + 1).min(end).min(local_range.start);
+                (range_start < range_end).then(|| ArchivedBlocks {
+                    start: Nat::from(range_start),
+                    length: Nat::from(range_end - range_start),
+                    canister_id,
+                })
+            })
+            .collect();
+
+        GetBlocksResponse {
+            first_index: Nat::from(local_start),
+            chain_length: blockchain.chain_length(),
+            blocks,
+            archived_blocks,
+        }
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_data_certificate() -> DataCertificate {
+    let hash_tree = Access::with_ledger(Ledger::construct_hash_tree);
+    let mut tree_buf = vec![];
+    ciborium::ser::into_writer(&hash_tree, &mut tree_buf).expect("failed to encode hash tree");
+    DataCertificate {
+        certificate: ic_cdk::api::data_certificate().map(ByteBuf::from),
+        hash_tree: ByteBuf::from(tree_buf),
+    }
+}
+
 #[query(name = "icrc1_supported_standards")]
 #[candid_method(query, rename = "icrc1_supported_standards")]
 fn supported_standards() -> Vec<StandardRecord> {
@@ -1,8 +1,12 @@
 use candid::types::number::Nat;
 use candid::{CandidType, Decode, Encode};
 use ic_base_types::PrincipalId;
+use ic_crypto_tree_hash::{Label, MixedHashTree};
 use ic_icrc1::{
-    endpoints::{ArchiveInfo, StandardRecord, TransferArg, TransferError, Value},
+    endpoints::{
+        ArchiveInfo, BlockRange, DataCertificate, GetBlocksRequest, GetBlocksResponse,
+        StandardRecord, TransferArg, TransferError, Value,
+    },
     Account, Block, CandidBlock, CandidOperation, Memo, Operation, Transaction,
 };
 use ic_icrc1_ledger::InitArgs;
@@ -200,6 +204,54 @@ fn get_archive_block(
     .expect("failed to decode get_block response")
 }
 
+fn get_blocks(
+    env: &StateMachine,
+    canister: CanisterId,
+    start: u64,
+    length: u64,
+) -> GetBlocksResponse {
+    let req = GetBlocksRequest {
+        start: Nat::from(start),
+        length: Nat::from(length),
+    };
+    Decode!(
+        &env.query(canister, "get_blocks", Encode!(&req).unwrap())
+            .expect("failed to query blocks")
+            .bytes(),
+        GetBlocksResponse
+    )
+    .expect("failed to decode get_blocks response")
+}
+
+fn get_archive_blocks(
+    env: &StateMachine,
+    archive: CanisterId,
+    start: u64,
+    length: u64,
+) -> BlockRange {
+    let req = GetBlocksRequest {
+        start: Nat::from(start),
+        length: Nat::from(length),
+    };
+    Decode!(
+        &env.query(archive, "get_blocks", Encode!(&req).unwrap())
+            .expect("failed to query archive blocks")
+            .bytes(),
+        BlockRange
+    )
+    .expect("failed to decode archive get_blocks response")
+}
+
+fn get_data_certificate(env: &StateMachine, ledger: CanisterId) -> DataCertificate {
+    Decode!(
+        &env.query(ledger, "get_data_certificate", Encode!().unwrap())
+            .expect("failed to query data certificate")
+            .bytes(),
+        DataCertificate
+    )
+    .expect("failed to decode get_data_certificate response")
+}
+
 fn system_time_to_nanos(t: SystemTime) -> u64 {
     t.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_nanos() as u64
 }
@@ -724,6 +776,86 @@ fn test_archiving() {
     );
 }
 
+#[test]
+fn test_get_blocks() {
+    let env = StateMachine::new();
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+
+    let canister_id = install_ledger(&env, vec![(Account::from(p1), 10_000_000)]);
+
+    for i in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        transfer(&env, canister_id, p1.into(), p2.into(), 10_000 + i).expect("transfer failed");
+    }
+
+    env.run_until_completion(/*max_ticks=*/ 10);
+
+    let resp = get_blocks(&env, canister_id, 0, u64::MAX);
+    assert_eq!(resp.chain_length, ARCHIVE_TRIGGER_THRESHOLD + 1);
+    assert_eq!(resp.first_index, NUM_BLOCKS_TO_ARCHIVE);
+    assert_eq!(resp.archived_blocks.len(), 1);
+
+    let archived = &resp.archived_blocks[0];
+    assert_eq!(archived.start, 0);
+    assert_eq!(archived.length, NUM_BLOCKS_TO_ARCHIVE);
+
+    let mut blocks =
+        get_archive_blocks(&env, archived.canister_id, 0, NUM_BLOCKS_TO_ARCHIVE).blocks;
+    blocks.extend(resp.blocks);
+    assert_eq!(blocks.len() as u64, resp.chain_length);
+
+    let mut parent_hash = None;
+    for encoded_block in blocks {
+        let block = Block::decode(encoded_block.clone()).expect("failed to decode block");
+        assert_eq!(block.parent_hash, parent_hash);
+        parent_hash = Some(Block::block_hash(&encoded_block));
+    }
+
+    // A request for the local blocks only does not mention the archive.
+    let resp = get_blocks(&env, canister_id, NUM_BLOCKS_TO_ARCHIVE + 1, 2);
+    assert_eq!(resp.first_index, NUM_BLOCKS_TO_ARCHIVE + 1);
+    assert_eq!(resp.blocks.len(), 2);
+    assert!(resp.archived_blocks.is_empty());
+
+    let DataCertificate { hash_tree, .. } = get_data_certificate(&env, canister_id);
+    let hash_tree: MixedHashTree =
+        ciborium::de::from_reader(&hash_tree[..]).expect("failed to decode hash tree");
+    assert_eq!(
+        hash_tree,
+        MixedHashTree::Labeled(
+            Label::from("tip_hash"),
+            Box::new(MixedHashTree::Leaf(
+                parent_hash.unwrap().as_slice().to_vec()
+            )),
+        )
+    );
+}
+
+#[test]
+fn test_certified_data_is_the_tip_hash_leaf() {
+    let env = StateMachine::new();
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+
+    let canister_id = install_ledger(&env, vec![(Account::from(p1), 10_000_000)]);
+    transfer(&env, canister_id, p1.into(), p2.into(), 10_000).expect("transfer failed");
+
+    let resp = get_blocks(&env, canister_id, 1, 1);
+    let tip_hash = Block::block_hash(&resp.blocks[0]);
+
+    // The certified data has been a single labeled leaf with the tip hash
+    // since the first release of the ledger, and clients rely on that.
+    let DataCertificate { hash_tree, .. } = get_data_certificate(&env, canister_id);
+    let hash_tree: MixedHashTree =
+        ciborium::de::from_reader(&hash_tree[..]).expect("failed to decode hash tree");
+    let expected = MixedHashTree::Labeled(
+        Label::from("tip_hash"),
+        Box::new(MixedHashTree::Leaf(tip_hash.as_slice().to_vec())),
+    );
+    assert_eq!(hash_tree, expected);
+    assert_eq!(hash_tree.digest(), expected.digest());
+}
+
 fn arb_amount() -> impl Strategy<Value = u64> {
     any::<u64>()
 }
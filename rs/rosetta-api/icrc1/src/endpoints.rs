@@ -3,6 +3,7 @@ use candid::types::number::{Int, Nat};
 use candid::CandidType;
 use ic_base_types::CanisterId;
 use ic_ledger_canister_core::ledger::TransferError as CoreTransferError;
+use ic_ledger_core::block::EncodedBlock;
 use serde::Deserialize;
 use serde_bytes::ByteBuf;
 
@@ -136,3 +137,50 @@ pub struct StandardRecord {
     pub name: String,
     pub url: String,
 }
+
+/// The arguments of the `get_blocks` endpoint of the ledger and the archive.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct GetBlocksRequest {
+    pub start: BlockIndex,
+    pub length: Nat,
+}
+
+/// A range of blocks that the ledger moved to an archive canister.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct ArchivedBlocks {
+    pub start: BlockIndex,
+    pub length: Nat,
+    pub canister_id: CanisterId,
+}
+
+/// The response of the ledger `get_blocks` endpoint.
+///
+/// The blocks are CBOR-encoded, the hash of a block is the representation-independent hash of
+/// its encoding (see [crate::hash]).
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct GetBlocksResponse {
+    /// The index of the first block in `blocks`.
+    pub first_index: BlockIndex,
+    /// The total number of blocks in the chain.
+    pub chain_length: u64,
+    pub blocks: Vec<EncodedBlock>,
+    /// The ranges of the requested blocks that must be fetched from the archives.
+    pub archived_blocks: Vec<ArchivedBlocks>,
+}
+
+/// The response of the archive `get_blocks` endpoint.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct BlockRange {
+    pub blocks: Vec<EncodedBlock>,
+}
+
+/// The response of the ledger `get_data_certificate` endpoint.
+///
+/// `hash_tree` is the CBOR-encoded hash tree whose root hash is the certified data of the ledger.
+/// It consists of a single `tip_hash` leaf with the hash of the last block, or is empty if the
+/// ledger has no blocks.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct DataCertificate {
+    pub certificate: Option<ByteBuf>,
+    pub hash_tree: ByteBuf,
+}
@@ -1,5 +1,6 @@
 use crate::balance_book::BalanceBook;
 use crate::errors::Error;
+use crate::snapshot::LedgerSnapshot;
 use crate::store::{BlockStoreError, HashedBlock, SQLiteStore};
 use ic_ledger_canister_core::ledger::LedgerTransaction;
 use ic_ledger_core::block::{BlockHeight, BlockType, EncodedBlock, HashOf};
@@ -96,6 +97,38 @@ impl Blocks {
         Ok(n)
     }
 
+    /// Initializes an empty store from `snapshot`. The snapshot is expected
+    /// to have been verified by the caller. `load_from_store` must be called
+    /// afterwards.
+    pub fn bootstrap_from_snapshot(&mut self, snapshot: &LedgerSnapshot) -> Result<(), Error> {
+        let balance_book = snapshot.balance_book().map_err(Error::InternalError)?;
+        Ok(self
+            .block_store
+            .bootstrap(&snapshot.genesis, &snapshot.tip, &balance_book)?)
+    }
+
+    /// Creates a snapshot of the state at the last verified block.
+    /// `certification` must be the ledger certificate for that block.
+    pub fn create_snapshot(&self, certification: Option<Vec<u8>>) -> Result<LedgerSnapshot, Error> {
+        let tip = self
+            .last_verified()?
+            .ok_or_else(|| Error::InternalError("No verified block to snapshot".to_string()))?;
+        let genesis = self.get_at(0)?;
+        let mut balances = Vec::with_capacity(self.balance_book.store.acc_to_hist.len());
+        for acc in self.balance_book.store.acc_to_hist.keys() {
+            let amount = self.balance_book.store.get_at(*acc, tip.index)?;
+            if amount != Tokens::ZERO {
+                balances.push((*acc, amount));
+            }
+        }
+        Ok(LedgerSnapshot {
+            genesis,
+            tip,
+            certification,
+            balances,
+        })
+    }
+
     fn get_at(&self, index: BlockHeight) -> Result<HashedBlock, Error> {
         Ok(self.block_store.get_at(index)?)
     }
@@ -13,6 +13,7 @@ use crate::blocks::Blocks;
 use crate::blocks_access::BlocksAccess;
 use crate::certification::{verify_block_hash, VerificationInfo};
 use crate::errors::Error;
+use crate::snapshot::LedgerSnapshot;
 use crate::store::{BlockStoreError, HashedBlock};
 
 // If pruning is enabled, instead of pruning after each new block
@@ -24,6 +25,11 @@ const PRINT_SYNC_PROGRESS_THRESHOLD: u64 = 1000;
 // Max number of retry in case of query failure while retrieving blocks.
 const MAX_RETRY: u8 = 5;
 
+// Max number of blocks fetched by a single call to `backfill_history`, so that
+// filling in the history of a bootstrapped store doesn't hold back the sync of
+// new blocks for long.
+const BACKFILL_BATCH_LEN: u64 = 10000;
+
 /// The LedgerBlocksSynchronizer will use this to output the metrics while
 /// synchronizing with the Ledger
 pub trait LedgerBlocksSynchronizerMetrics {
@@ -60,6 +66,7 @@ impl<B: BlocksAccess> LedgerBlocksSynchronizer<B> {
         store_location: Option<&std::path::Path>,
         store_max_blocks: Option<u64>,
         verification_info: Option<VerificationInfo>,
+        bootstrap_snapshot: Option<LedgerSnapshot>,
         metrics: Box<dyn LedgerBlocksSynchronizerMetrics + Send + Sync>,
     ) -> Result<LedgerBlocksSynchronizer<B>, Error> {
         let mut blocks = match store_location {
@@ -67,6 +74,10 @@ impl<B: BlocksAccess> LedgerBlocksSynchronizer<B> {
             None => Blocks::new_in_memory(),
         };
 
+        if let Some(snapshot) = bootstrap_snapshot {
+            Self::bootstrap(&mut blocks, &snapshot, verification_info.as_ref())?;
+        }
+
         if let Some(blocks_access) = &blocks_access {
             Self::verify_store(&blocks, blocks_access).await?;
             if let Some(verification_info) = &verification_info {
@@ -109,6 +120,130 @@ impl<B: BlocksAccess> LedgerBlocksSynchronizer<B> {
         })
     }
 
+    /// Initializes an empty store from a certified snapshot. A store that
+    /// already contains blocks is left untouched.
+    ///
+    /// The certificate only covers the tip of the snapshot. The blocks before
+    /// it are filled in by `backfill_history`.
+    fn bootstrap(
+        blocks: &mut Blocks,
+        snapshot: &LedgerSnapshot,
+        verification_info: Option<&VerificationInfo>,
+    ) -> Result<(), Error> {
+        if blocks.block_store.first()?.is_some() {
+            info!("Store is not empty, ignoring the bootstrap snapshot");
+            return Ok(());
+        }
+        let verification_info = verification_info.ok_or_else(|| {
+            Error::InternalError(
+                "Bootstrapping from a snapshot requires a root key to verify it".to_string(),
+            )
+        })?;
+        snapshot
+            .verify(Some(verification_info))
+            .map_err(Error::InternalError)?;
+        info!(
+            "Bootstrapping the store from the snapshot at block {}",
+            snapshot.tip.index
+        );
+        blocks.bootstrap_from_snapshot(snapshot)
+    }
+
+    /// Fills in the blocks missing before the oldest available block of a
+    /// store bootstrapped from a snapshot, fetching at most
+    /// `BACKFILL_BATCH_LEN` blocks per call going backwards.
+    ///
+    /// The hash of every fetched block has to be the parent hash of its
+    /// successor, starting from the certified snapshot tip, and the first
+    /// block has to be a child of the genesis block.
+    pub async fn backfill_history(&self, stopped: Arc<AtomicBool>) -> Result<(), Error> {
+        // A pruned store would drop the history again.
+        if self.store_max_blocks.is_some() {
+            return Ok(());
+        }
+        let canister = match self.blocks_access.as_ref() {
+            Some(canister) => canister,
+            None => return Ok(()),
+        };
+        let (start, end, mut expected_hash, genesis_hash) = {
+            let blockchain = self.blockchain.read().await;
+            let end = blockchain.block_store.first_available_index();
+            if end <= 1 {
+                return Ok(());
+            }
+            let oldest = blockchain.block_store.get_at(end)?;
+            let genesis = blockchain.block_store.get_at(0)?;
+            (
+                end.saturating_sub(BACKFILL_BATCH_LEN).max(1),
+                end,
+                oldest.parent_hash,
+                genesis.hash,
+            )
+        };
+
+        debug!("Filling in the history [{},{})", start, end);
+        let mut raw_blocks = Vec::with_capacity((end - start) as usize);
+        while start + (raw_blocks.len() as u64) < end {
+            if stopped.load(Relaxed) {
+                return Err(Error::InternalError("Interrupted".to_string()));
+            }
+            let next = start + raw_blocks.len() as u64;
+            let batch = canister
+                .clone()
+                .multi_query_blocks(Range { start: next, end })
+                .await
+                .map_err(Error::InternalError)?;
+            if batch.is_empty() {
+                return Err(Error::InternalError(format!(
+                    "Couldn't fetch blocks [{},{}) (batch result empty)",
+                    next, end
+                )));
+            }
+            raw_blocks.extend(batch);
+        }
+
+        let mut hashed_batch = Vec::with_capacity(raw_blocks.len());
+        for (i, raw_block) in raw_blocks.into_iter().enumerate().rev() {
+            let index = start + i as u64;
+            let hash = Block::block_hash(&raw_block);
+            if Some(hash) != expected_hash {
+                let err_msg = format!(
+                    "Block at {}: hash mismatch with the parent hash of its successor. Expected: {:?}, got: {}",
+                    index, expected_hash, hash
+                );
+                error!("{}", err_msg);
+                return Err(Error::InternalError(err_msg));
+            }
+            let block = Block::decode(raw_block.clone())
+                .map_err(|err| Error::InternalError(format!("Cannot decode block: {}", err)))?;
+            expected_hash = block.parent_hash;
+            hashed_batch.push(HashedBlock {
+                block: raw_block,
+                hash,
+                parent_hash: block.parent_hash,
+                index,
+            });
+        }
+        if start == 1 && expected_hash != Some(genesis_hash) {
+            let err_msg = format!(
+                "Block at 1: parent hash mismatch with the genesis block. Expected: {}, got: {:?}",
+                genesis_hash, expected_hash
+            );
+            error!("{}", err_msg);
+            return Err(Error::InternalError(err_msg));
+        }
+        hashed_batch.reverse();
+
+        let mut blockchain = self.blockchain.write().await;
+        blockchain.block_store.push_history(hashed_batch)?;
+        if start == 1 {
+            info!("The history before the snapshot is complete");
+        } else {
+            debug!("Filled in the history down to block {}", start);
+        }
+        Ok(())
+    }
+
     async fn verify_store(blocks: &Blocks, canister_access: &B) -> Result<(), Error> {
         debug!("Verifying store...");
         let first_block = blocks.block_store.first()?;
@@ -203,6 +338,51 @@ impl<B: BlocksAccess> LedgerBlocksSynchronizer<B> {
         Ok(())
     }
 
+    /// Syncs the local copy to the current tip of the ledger and creates a
+    /// snapshot of it, certified by the ledger.
+    pub async fn create_snapshot(&self) -> Result<LedgerSnapshot, Error> {
+        let canister = self.blocks_access.as_ref().ok_or_else(|| {
+            Error::InternalError("Cannot create a snapshot in offline mode".to_string())
+        })?;
+        let TipOfChainRes {
+            tip_index,
+            certification,
+        } = canister.query_tip().await.map_err(Error::InternalError)?;
+
+        let mut blockchain = self.blockchain.write().await;
+        let (last_block_hash, next_block_index) = match blockchain.synced_to() {
+            Some((hash, index)) => (Some(hash), index + 1),
+            None => (None, 0),
+        };
+        // The certificate only covers the tip, so the snapshot has to be taken
+        // exactly there.
+        if next_block_index <= tip_index {
+            self.sync_range_of_blocks(
+                Range {
+                    start: next_block_index,
+                    end: tip_index + 1,
+                },
+                last_block_hash,
+                Arc::new(AtomicBool::new(false)),
+                certification.clone(),
+                &mut *blockchain,
+            )
+            .await?;
+        }
+        let last_verified = blockchain.block_store.last_verified();
+        if last_verified != Some(tip_index) {
+            return Err(Error::InternalError(format!(
+                "Local copy is not synced to the ledger tip. Ledger tip index: {}, last verified index: {:?}",
+                tip_index, last_verified
+            )));
+        }
+        let snapshot = blockchain.create_snapshot(certification)?;
+        snapshot
+            .verify(self.verification_info.as_ref())
+            .map_err(Error::InternalError)?;
+        Ok(snapshot)
+    }
+
     pub async fn read_blocks(&self) -> Box<dyn Deref<Target = Blocks> + '_> {
         Box::new(self.blockchain.read().await)
     }
@@ -382,8 +562,11 @@ mod test {
     use ic_types::PrincipalId;
     use ledger_canister::{AccountIdentifier, Block, BlockHeight, Memo, TipOfChainRes};
 
+    use crate::blocks::Blocks;
     use crate::blocks_access::BlocksAccess;
     use crate::ledger_blocks_sync::LedgerBlocksSynchronizer;
+    use crate::snapshot::LedgerSnapshot;
+    use crate::store::HashedBlock;
 
     use super::NopMetrics;
 
@@ -433,6 +616,7 @@ mod test {
             /* store_location = */ None,
             /* store_max_blocks = */ None,
             /* verification_info = */ None,
+            /* bootstrap_snapshot = */ None,
             Box::new(NopMetrics {}),
         )
         .await
@@ -537,4 +721,175 @@ mod test {
             );
         }
     }
+
+    #[tokio::test]
+    async fn bootstrap_from_snapshot() {
+        let blocks = dummy_blocks(10);
+        let blocks_sync = new_ledger_blocks_synchronizer(blocks[..6].to_vec()).await;
+        blocks_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), None)
+            .await
+            .unwrap();
+        let snapshot = blocks_sync.create_snapshot().await.unwrap();
+        assert_eq!(5, snapshot.tip.index);
+        assert_eq!(
+            snapshot,
+            LedgerSnapshot::decode(&snapshot.encode().unwrap()).unwrap()
+        );
+
+        let mut bootstrapped = Blocks::new_in_memory();
+        bootstrapped.bootstrap_from_snapshot(&snapshot).unwrap();
+        bootstrapped.load_from_store().unwrap();
+        assert!(bootstrapped.get_verified_at(1).is_err());
+        assert_eq!(blocks[5], bootstrapped.get_verified_at(5).unwrap().block);
+
+        // the bootstrapped copy can keep syncing from the snapshot tip
+        let mut parent_hash = Some(snapshot.tip.hash);
+        let mut batch = vec![];
+        for (i, block) in blocks.iter().enumerate().skip(6) {
+            let hb = HashedBlock::hash_block(block.clone(), parent_hash, i as u64);
+            parent_hash = Some(hb.hash);
+            batch.push(hb);
+        }
+        bootstrapped.add_blocks_batch(batch).unwrap();
+        bootstrapped.block_store.mark_last_verified(9).unwrap();
+
+        let full_sync = new_ledger_blocks_synchronizer(blocks.clone()).await;
+        full_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), None)
+            .await
+            .unwrap();
+        let full = full_sync.read_blocks().await;
+        for account in full.balance_book.store.acc_to_hist.keys() {
+            assert_eq!(
+                full.get_balance(account, 9).unwrap(),
+                bootstrapped.get_balance(account, 9).unwrap()
+            );
+        }
+        assert_eq!(
+            full.balance_book.token_pool,
+            bootstrapped.balance_book.token_pool
+        );
+    }
+
+    #[tokio::test]
+    async fn bootstrap_requires_verification_info() {
+        let blocks = dummy_blocks(3);
+        let blocks_sync = new_ledger_blocks_synchronizer(blocks.clone()).await;
+        blocks_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), None)
+            .await
+            .unwrap();
+        let snapshot = blocks_sync.create_snapshot().await.unwrap();
+
+        let res = LedgerBlocksSynchronizer::new(
+            Some(Arc::new(RangeOfBlocks::new(blocks))),
+            /* store_location = */ None,
+            /* store_max_blocks = */ None,
+            /* verification_info = */ None,
+            Some(snapshot),
+            Box::new(NopMetrics {}),
+        )
+        .await;
+        assert!(res.is_err());
+    }
+
+    async fn bootstrapped_synchronizer(
+        snapshot: &LedgerSnapshot,
+        ledger_blocks: Vec<EncodedBlock>,
+    ) -> LedgerBlocksSynchronizer<RangeOfBlocks> {
+        let blocks_sync = new_ledger_blocks_synchronizer(ledger_blocks).await;
+        let mut bootstrapped = Blocks::new_in_memory();
+        bootstrapped.bootstrap_from_snapshot(snapshot).unwrap();
+        bootstrapped.load_from_store().unwrap();
+        *blocks_sync.blockchain.write().await = bootstrapped;
+        blocks_sync
+    }
+
+    #[tokio::test]
+    async fn history_is_filled_in_after_bootstrap() {
+        let blocks = dummy_blocks(6);
+        let blocks_sync = new_ledger_blocks_synchronizer(blocks.clone()).await;
+        blocks_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), None)
+            .await
+            .unwrap();
+        let snapshot = blocks_sync.create_snapshot().await.unwrap();
+
+        let bootstrapped = bootstrapped_synchronizer(&snapshot, blocks.clone()).await;
+        assert!(bootstrapped.read_blocks().await.get_verified_at(1).is_err());
+
+        bootstrapped
+            .backfill_history(Arc::new(AtomicBool::new(false)))
+            .await
+            .unwrap();
+        {
+            let actual_blocks = bootstrapped.read_blocks().await;
+            assert_eq!(1, actual_blocks.block_store.first_available_index());
+            for (i, block) in blocks.iter().enumerate() {
+                assert_eq!(
+                    *block,
+                    actual_blocks.get_verified_at(i as u64).unwrap().block
+                );
+            }
+        }
+
+        // once the history is complete there is nothing left to do
+        bootstrapped
+            .backfill_history(Arc::new(AtomicBool::new(false)))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn history_from_another_chain_is_rejected() {
+        let blocks = dummy_blocks(6);
+        let blocks_sync = new_ledger_blocks_synchronizer(blocks.clone()).await;
+        blocks_sync
+            .sync_blocks(Arc::new(AtomicBool::new(false)), None)
+            .await
+            .unwrap();
+        let snapshot = blocks_sync.create_snapshot().await.unwrap();
+
+        // the blocks served by the ledger don't end at the snapshot tip
+        let mut other_blocks = blocks;
+        other_blocks.swap(2, 3);
+        let bootstrapped = bootstrapped_synchronizer(&snapshot, other_blocks).await;
+        assert!(bootstrapped
+            .backfill_history(Arc::new(AtomicBool::new(false)))
+            .await
+            .is_err());
+        assert_eq!(
+            5,
+            bootstrapped
+                .read_blocks()
+                .await
+                .block_store
+                .first_available_index()
+        );
+    }
+
+    #[test]
+    fn snapshot_with_tampered_tip_is_rejected() {
+        let blocks = dummy_blocks(3);
+        let genesis = HashedBlock::hash_block(blocks[0].clone(), None, 0);
+        let first = HashedBlock::hash_block(blocks[1].clone(), Some(genesis.hash), 1);
+        let mut tip = HashedBlock::hash_block(blocks[2].clone(), Some(first.hash), 2);
+        let snapshot = LedgerSnapshot {
+            genesis: genesis.clone(),
+            tip: tip.clone(),
+            certification: None,
+            balances: vec![],
+        };
+        assert_eq!(Ok(()), snapshot.verify(None));
+
+        tip.block = blocks[1].clone();
+        let snapshot = LedgerSnapshot {
+            genesis,
+            tip,
+            certification: None,
+            balances: vec![],
+        };
+        assert!(snapshot.verify(None).is_err());
+    }
 }
@@ -5,4 +5,5 @@ pub mod canister_access;
 pub mod certification;
 pub mod errors;
 pub mod ledger_blocks_sync;
+pub mod snapshot;
 pub mod store;
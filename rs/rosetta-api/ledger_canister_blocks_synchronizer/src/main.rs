@@ -29,6 +29,10 @@ struct Args {
     /// Sync the chain up to this block. This block will be available in the local copy, the next one won't.
     #[clap(short = 'b', long)]
     pub up_to_block: Option<BlockHeight>,
+
+    /// Write a snapshot of the synced state to this file, to be used to bootstrap other stores.
+    #[clap(long)]
+    pub write_snapshot: Option<PathBuf>,
 }
 
 struct PrintMetrics {}
@@ -53,6 +57,7 @@ async fn main() {
         Some(args.store_location.as_ref()),
         /* store_max_blocks = */ None,
         /* verification_info = */ None,
+        /* bootstrap_snapshot = */ None,
         Box::new(PrintMetrics {}),
     )
     .await
@@ -66,4 +71,13 @@ async fn main() {
         .await
         .expect("Failed to sync blocks");
     println!("Synchronization done");
+    if let Some(path) = args.write_snapshot {
+        synchronizer
+            .create_snapshot()
+            .await
+            .expect("Failed to create snapshot")
+            .write_to_file(&path)
+            .expect("Failed to write snapshot");
+        println!("Snapshot written to {}", path.display());
+    }
 }
@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use candid::CandidType;
+use ic_ledger_core::block::BlockType;
+use ledger_canister::{AccountIdentifier, Block, Tokens};
+use serde::{Deserialize, Serialize};
+
+use crate::balance_book::BalanceBook;
+use crate::certification::{verify_block_hash, VerificationInfo};
+use crate::store::HashedBlock;
+
+/// The state of the ledger at a given block, sufficient to start a local
+/// store without syncing the chain from genesis.
+///
+/// Only the `tip` block is certified by the ledger: `certification` is the
+/// certificate returned by `tip_of_chain` for `tip.index`. The balances are
+/// taken from the snapshot as they are, so a snapshot should only be obtained
+/// from a trusted source. The blocks between `genesis` and `tip` are filled in
+/// afterwards, and checked against the hash chain ending at the certified tip.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LedgerSnapshot {
+    pub genesis: HashedBlock,
+    pub tip: HashedBlock,
+    pub certification: Option<Vec<u8>>,
+    pub balances: Vec<(AccountIdentifier, Tokens)>,
+}
+
+impl LedgerSnapshot {
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        candid::encode_one(self).map_err(|e| format!("Failed to encode snapshot: {}", e))
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        candid::decode_one(bytes).map_err(|e| format!("Failed to decode snapshot: {}", e))
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.encode()?)
+            .map_err(|e| format!("Failed to write snapshot to {}: {}", path.display(), e))
+    }
+
+    pub fn read_from_file(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| format!("Failed to read snapshot from {}: {}", path.display(), e))?;
+        Self::decode(&bytes)
+    }
+
+    /// Checks that the snapshot is well formed and, if `verification_info` is
+    /// provided, that the tip block is certified by the ledger canister.
+    pub fn verify(&self, verification_info: Option<&VerificationInfo>) -> Result<(), String> {
+        if self.genesis.index != 0 || self.genesis.parent_hash.is_some() {
+            return Err(format!(
+                "Snapshot genesis block has index {} and parent hash {:?}",
+                self.genesis.index, self.genesis.parent_hash
+            ));
+        }
+        if self.tip.index == 0 {
+            return Err("Snapshot tip must be past the genesis block".to_string());
+        }
+        for hb in [&self.genesis, &self.tip] {
+            if hb.hash != Block::block_hash(&hb.block) {
+                return Err(format!("Snapshot block {} has an invalid hash", hb.index));
+            }
+            let block = Block::decode(hb.block.clone())
+                .map_err(|e| format!("Cannot decode snapshot block {}: {}", hb.index, e))?;
+            if block.parent_hash != hb.parent_hash {
+                return Err(format!(
+                    "Snapshot block {}: parent hash mismatch. Expected: {:?}, got: {:?}",
+                    hb.index, hb.parent_hash, block.parent_hash
+                ));
+            }
+        }
+
+        self.balance_book()?;
+
+        if let Some(verification_info) = verification_info {
+            verify_block_hash(&self.certification, self.tip.hash, verification_info)?;
+        }
+        Ok(())
+    }
+
+    /// Builds the balance book at the tip of the snapshot.
+    pub fn balance_book(&self) -> Result<BalanceBook, String> {
+        let mut balance_book = BalanceBook::default();
+        let mut seen = HashSet::new();
+        for (acc, amount) in &self.balances {
+            if !seen.insert(*acc) {
+                return Err(format!("Duplicate account in snapshot: {}", acc));
+            }
+            balance_book.token_pool = (balance_book.token_pool - *amount)
+                .map_err(|_| "Snapshot balances exceed the total token supply".to_string())?;
+            balance_book.store.insert(*acc, self.tip.index, *amount);
+            // The history before the tip is unknown, so the only entry is
+            // treated the same way as the oldest entry of a pruned history.
+            balance_book
+                .store
+                .acc_to_hist
+                .get_mut(acc)
+                .expect("Expected history for account.")
+                .num_pruned_transactions = 1;
+        }
+        Ok(balance_book)
+    }
+}
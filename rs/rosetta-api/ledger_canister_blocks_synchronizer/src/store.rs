@@ -103,6 +103,19 @@ impl SQLiteStore {
                     Ok(())
                 }
             })?;
+
+            // Blocks before the oldest block snapshot are available if the
+            // history was filled in after bootstrapping from a snapshot.
+            let connection = store.connection.lock().unwrap();
+            let mut stmt = connection
+                .prepare("SELECT MIN(idx) FROM blocks WHERE idx > 0")
+                .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+            let min_idx: Option<u64> = stmt
+                .query_row([], |row| row.get(0))
+                .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+            if let Some(min_idx) = min_idx {
+                store.base_idx = store.base_idx.min(min_idx);
+            }
         }
 
         // Read last verified index (if any).
@@ -424,6 +437,64 @@ impl SQLiteStore {
         Ok(())
     }
 
+    /// Initializes an empty store with the genesis block and a verified
+    /// oldest block snapshot at `tip`. Blocks in between can be added later
+    /// with `push_history`.
+    pub fn bootstrap(
+        &mut self,
+        genesis: &HashedBlock,
+        tip: &HashedBlock,
+        balances: &BalanceBook,
+    ) -> Result<(), BlockStoreError> {
+        if self.first()?.is_some() {
+            return Err(BlockStoreError::Other(
+                "Cannot bootstrap a store that is not empty".to_string(),
+            ));
+        }
+        self.push_batch(vec![genesis.clone(), tip.clone()])?;
+        self.write_oldest_block_snapshot(tip, balances)
+            .map_err(BlockStoreError::Other)?;
+        self.base_idx = tip.index;
+        self.mark_last_verified(tip.index)
+    }
+
+    /// The index of the oldest block other than the genesis block available
+    /// in the store. The blocks in between were either pruned or, for a store
+    /// bootstrapped from a snapshot, not filled in yet.
+    pub fn first_available_index(&self) -> BlockHeight {
+        self.base_idx
+    }
+
+    /// Adds blocks preceding the oldest available block, to fill in the history
+    /// of a store bootstrapped from a snapshot. The blocks must have been
+    /// verified against the hash chain by the caller.
+    pub fn push_history(&mut self, batch: Vec<HashedBlock>) -> Result<(), BlockStoreError> {
+        let first_index = match batch.first() {
+            Some(hb) => hb.index,
+            None => return Ok(()),
+        };
+        if first_index == 0 || first_index + batch.len() as u64 != self.base_idx {
+            return Err(BlockStoreError::Other(format!(
+                "History blocks [{},{}) do not end at the first available block {}",
+                first_index,
+                first_index + batch.len() as u64,
+                self.base_idx
+            )));
+        }
+        self.push_batch(batch)?;
+        {
+            let connection = self.connection.lock().unwrap();
+            connection
+                .execute(
+                    "UPDATE blocks SET verified = TRUE WHERE idx >= ? AND idx < ?",
+                    params![first_index, self.base_idx],
+                )
+                .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+        }
+        self.base_idx = first_index;
+        Ok(())
+    }
+
     pub fn first_snapshot(&self) -> Option<(HashedBlock, BalanceBook)> {
         self.read_oldest_block_snapshot()
             .expect("Error while retrieving first snapshot.")
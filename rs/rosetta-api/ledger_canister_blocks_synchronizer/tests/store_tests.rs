@@ -1,5 +1,6 @@
 use ic_ledger_canister_blocks_synchronizer::{
     balance_book::BalanceBook,
+    snapshot::LedgerSnapshot,
     store::{BlockStoreError, SQLiteStore},
 };
 use ic_ledger_canister_blocks_synchronizer_test_utils::{
@@ -133,6 +134,52 @@ async fn store_prune_and_load_test() {
     verify_balance_snapshot(&scribe, &mut store, 30);
 }
 
+#[actix_rt::test]
+async fn store_bootstrap_and_load_test() {
+    init_test_logger();
+    let tmpdir = create_tmp_dir();
+    let mut store = sqlite_on_disk_store(tmpdir.path());
+
+    let scribe = Scribe::new_with_sample_data(10, 100);
+    let snapshot = LedgerSnapshot {
+        genesis: scribe.blockchain[0].clone(),
+        tip: scribe.blockchain[20].clone(),
+        certification: None,
+        balances: scribe.balance_history[20].clone().into_iter().collect(),
+    };
+    snapshot.verify(None).unwrap();
+
+    store
+        .bootstrap(
+            &snapshot.genesis,
+            &snapshot.tip,
+            &snapshot.balance_book().unwrap(),
+        )
+        .unwrap();
+    assert_eq!(store.last_verified(), Some(20));
+    for hb in scribe.blockchain.iter().skip(21) {
+        store.push(hb.clone()).unwrap();
+    }
+    verify_pruned(&scribe, &mut store, 20);
+    verify_balance_snapshot(&scribe, &mut store, 20);
+
+    // A store that is not empty cannot be bootstrapped
+    assert!(store
+        .bootstrap(
+            &snapshot.genesis,
+            &snapshot.tip,
+            &snapshot.balance_book().unwrap(),
+        )
+        .is_err());
+
+    drop(store);
+    // Now reload from disk
+    let mut store = sqlite_on_disk_store(tmpdir.path());
+    assert_eq!(store.last_verified(), Some(20));
+    verify_pruned(&scribe, &mut store, 20);
+    verify_balance_snapshot(&scribe, &mut store, 20);
+}
+
 pub(crate) fn to_balances(
     balances: BTreeMap<AccountIdentifier, Tokens>,
     index: BlockHeight,
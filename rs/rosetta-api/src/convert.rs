@@ -10,9 +10,10 @@ use crate::request::transaction_operation_results::TransactionOperationResults;
 use crate::request::transaction_results::TransactionResults;
 use crate::request::Request;
 use crate::request_types::{
-    DisburseMetadata, FollowMetadata, KeyMetadata, MergeMaturityMetadata, NeuronIdentifierMetadata,
-    NeuronInfoMetadata, PublicKeyOrPrincipal, RequestResultMetadata, SetDissolveTimestampMetadata,
-    SpawnMetadata, Status, STATUS_COMPLETED,
+    DisburseMetadata, DisburseToNeuronMetadata, FollowMetadata, KeyMetadata, MergeMaturityMetadata,
+    MergeMetadata, NeuronIdentifierMetadata, NeuronInfoMetadata, PublicKeyOrPrincipal,
+    RegisterVoteMetadata, RequestResultMetadata, SetDissolveTimestampMetadata, SpawnMetadata,
+    Status, STATUS_COMPLETED,
 };
 use crate::transaction_id::TransactionIdentifier;
 use crate::{convert, errors};
@@ -91,7 +92,14 @@ pub fn operations_to_requests(
             .map_err(|e| op_error(o, e))?;
 
         let validate_neuron_management_op = || {
-            if o.amount.is_some() && o._type != OperationType::Disburse {
+            if o.amount.is_some()
+                && !matches!(
+                    o._type,
+                    OperationType::Disburse
+                        | OperationType::Split
+                        | OperationType::DisburseToNeuron
+                )
+            {
                 Err(op_error(
                     o,
                     format!(
@@ -236,6 +244,80 @@ pub fn operations_to_requests(
                 };
                 state.follow(account, pid, neuron_index, topic, followees)?;
             }
+            OperationType::Split => {
+                let NeuronIdentifierMetadata { neuron_index } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                let amount = o
+                    .amount
+                    .as_ref()
+                    .ok_or_else(|| op_error(o, "Amount must be populated".into()))?;
+                let amount = ledgeramount_from_amount(amount, token_name).map_err(|e| {
+                    ApiError::internal_error(format!("Could not convert Amount {:?}", e))
+                })?;
+                state.split(account, neuron_index, amount)?;
+            }
+            OperationType::Merge => {
+                let MergeMetadata {
+                    source_neuron_id,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.merge(account, neuron_index, source_neuron_id)?;
+            }
+            OperationType::DisburseToNeuron => {
+                let DisburseToNeuronMetadata {
+                    controller,
+                    dissolve_delay_seconds,
+                    kyc_verified,
+                    child_neuron_index,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                let amount = o
+                    .amount
+                    .as_ref()
+                    .ok_or_else(|| op_error(o, "Amount must be populated".into()))?;
+                let amount = ledgeramount_from_amount(amount, token_name).map_err(|e| {
+                    ApiError::internal_error(format!("Could not convert Amount {:?}", e))
+                })?;
+                state.disburse_to_neuron(
+                    account,
+                    neuron_index,
+                    amount,
+                    principal_id_from_public_key_or_principal(controller)?,
+                    dissolve_delay_seconds,
+                    kyc_verified,
+                    child_neuron_index,
+                )?;
+            }
+            OperationType::JoinCommunityFund => {
+                validate_neuron_management_op()?;
+                let NeuronIdentifierMetadata { neuron_index } = o.metadata.clone().try_into()?;
+                state.join_community_fund(account, neuron_index)?;
+            }
+            OperationType::LeaveCommunityFund => {
+                validate_neuron_management_op()?;
+                let NeuronIdentifierMetadata { neuron_index } = o.metadata.clone().try_into()?;
+                state.leave_community_fund(account, neuron_index)?;
+            }
+            OperationType::RegisterVote => {
+                let RegisterVoteMetadata {
+                    proposal,
+                    vote,
+                    controller,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                let pid = match controller {
+                    None => None,
+                    Some(p) => Some(principal_id_from_public_key_or_principal(p)?),
+                };
+                state.register_vote(account, pid, neuron_index, proposal, vote)?;
+            }
+            OperationType::ListNeurons => {
+                validate_neuron_management_op()?;
+                state.list_neurons(account)?;
+            }
         }
     }
 
@@ -2,9 +2,11 @@ use crate::errors::ApiError;
 use crate::models::seconds::Seconds;
 use crate::request::Request;
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RemoveHotKey,
-    SetDissolveTimestamp, Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, DisburseToNeuron, Follow, JoinCommunityFund, LeaveCommunityFund,
+    ListNeurons, Merge, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RegisterVote,
+    RemoveHotKey, SetDissolveTimestamp, Spawn, Split, Stake, StartDissolve, StopDissolve,
 };
+use ic_nns_governance::pb::v1::Vote;
 use ic_types::PrincipalId;
 use ledger_canister::{Operation, Tokens, DEFAULT_TRANSFER_FEE};
 
@@ -320,6 +322,123 @@ impl State {
         }));
         Ok(())
     }
+
+    pub fn split(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+        amount: Tokens,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::Split(Split {
+            account,
+            amount,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn merge(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+        source_neuron_id: u64,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::Merge(Merge {
+            account,
+            source_neuron_id,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn disburse_to_neuron(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+        amount: Tokens,
+        controller: PrincipalId,
+        dissolve_delay_seconds: u64,
+        kyc_verified: bool,
+        child_neuron_index: u64,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions
+            .push(Request::DisburseToNeuron(DisburseToNeuron {
+                account,
+                amount,
+                controller,
+                dissolve_delay_seconds,
+                kyc_verified,
+                child_neuron_index,
+                neuron_index,
+            }));
+        Ok(())
+    }
+
+    pub fn join_community_fund(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions
+            .push(Request::JoinCommunityFund(JoinCommunityFund {
+                account,
+                neuron_index,
+            }));
+        Ok(())
+    }
+
+    pub fn leave_community_fund(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions
+            .push(Request::LeaveCommunityFund(LeaveCommunityFund {
+                account,
+                neuron_index,
+            }));
+        Ok(())
+    }
+
+    pub fn register_vote(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        controller: Option<PrincipalId>,
+        neuron_index: u64,
+        proposal: u64,
+        vote: i32,
+    ) -> Result<(), ApiError> {
+        if !matches!(Vote::from_i32(vote), Some(Vote::Yes) | Some(Vote::No)) {
+            let msg = format!("Invalid vote: {}", vote);
+            let err = ApiError::InvalidTransaction(false, msg.into());
+            return Err(err);
+        }
+        self.flush()?;
+        self.actions.push(Request::RegisterVote(RegisterVote {
+            account,
+            proposal,
+            vote,
+            controller,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn list_neurons(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions
+            .push(Request::ListNeurons(ListNeurons { account }));
+        Ok(())
+    }
 }
 
 /// Structure for manipulating tokens in relation to account, for example during transfers.
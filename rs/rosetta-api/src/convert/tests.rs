@@ -1,7 +1,10 @@
 use super::*;
 use crate::models::amount::signed_amount;
 use crate::models::operation::{OperationIdentifier, OperationType};
-use crate::request_types::Stake;
+use crate::request_types::{
+    DisburseToNeuron, JoinCommunityFund, LeaveCommunityFund, ListNeurons, Merge, RegisterVote,
+    Split, Stake,
+};
 use crate::DEFAULT_TOKEN_SYMBOL;
 use ledger_canister::AccountIdentifier;
 use ledger_canister::Operation as LedgerOperation;
@@ -175,6 +178,70 @@ fn test_can_handle_multiple_transfers() {
     );
 }
 
+#[test]
+fn test_neuron_lifecycle_requests_round_trip() {
+    let requests = vec![
+        Request::Split(Split {
+            account: test_account(1),
+            amount: Tokens::from_e8s(500_000_000),
+            neuron_index: 1,
+        }),
+        Request::Merge(Merge {
+            account: test_account(1),
+            source_neuron_id: 42,
+            neuron_index: 1,
+        }),
+        Request::DisburseToNeuron(DisburseToNeuron {
+            account: test_account(1),
+            amount: Tokens::from_e8s(300_000_000),
+            controller: PrincipalId::new_user_test_id(7),
+            dissolve_delay_seconds: 86_400,
+            kyc_verified: true,
+            child_neuron_index: 2,
+            neuron_index: 1,
+        }),
+        Request::JoinCommunityFund(JoinCommunityFund {
+            account: test_account(1),
+            neuron_index: 1,
+        }),
+        Request::LeaveCommunityFund(LeaveCommunityFund {
+            account: test_account(1),
+            neuron_index: 1,
+        }),
+        Request::RegisterVote(RegisterVote {
+            account: test_account(1),
+            proposal: 1234,
+            vote: 1,
+            controller: None,
+            neuron_index: 1,
+        }),
+        Request::ListNeurons(ListNeurons {
+            account: test_account(1),
+        }),
+    ];
+    let operations = Request::requests_to_operations(&requests, DEFAULT_TOKEN_SYMBOL).unwrap();
+    assert_eq!(
+        operations_to_requests(&operations, false, DEFAULT_TOKEN_SYMBOL),
+        Ok(requests)
+    );
+}
+
+#[test]
+fn test_register_vote_rejects_unspecified_vote() {
+    let operations = Request::requests_to_operations(
+        &[Request::RegisterVote(RegisterVote {
+            account: test_account(1),
+            proposal: 1234,
+            vote: 0,
+            controller: None,
+            neuron_index: 1,
+        })],
+        DEFAULT_TOKEN_SYMBOL,
+    )
+    .unwrap();
+    assert!(operations_to_requests(&operations, false, DEFAULT_TOKEN_SYMBOL).is_err());
+}
+
 #[test]
 fn account_identifier_decode_test() {
     // a good address
@@ -1,15 +1,23 @@
 mod handle_add_hotkey;
 mod handle_disburse;
+mod handle_disburse_to_neuron;
 mod handle_follow;
+mod handle_join_community_fund;
+mod handle_leave_community_fund;
+mod handle_list_neurons;
+mod handle_merge;
 mod handle_merge_maturity;
 mod handle_neuron_info;
+mod handle_register_vote;
 mod handle_remove_hotkey;
 mod handle_send;
 mod handle_set_dissolve_timestamp;
 mod handle_spawn;
+mod handle_split;
 mod handle_stake;
 mod handle_start_dissolve;
 mod handle_stop_dissolve;
+mod list_neurons_response;
 mod neuron_response;
 
 use core::ops::Deref;
@@ -30,6 +38,7 @@ use ic_ledger_canister_blocks_synchronizer::certification::VerificationInfo;
 use ic_ledger_canister_blocks_synchronizer::ledger_blocks_sync::{
     LedgerBlocksSynchronizer, LedgerBlocksSynchronizerMetrics,
 };
+use ic_ledger_canister_blocks_synchronizer::snapshot::LedgerSnapshot;
 use ic_nns_governance::pb::v1::{manage_neuron::NeuronIdOrSubaccount, GovernanceError, NeuronInfo};
 use ic_types::messages::{HttpCallContent, MessageId};
 use ic_types::CanisterId;
@@ -39,13 +48,18 @@ use on_wire::{FromWire, IntoWire};
 
 use crate::convert;
 use crate::errors::{ApiError, Details, ICError};
+use crate::ledger_client::list_neurons_response::ListNeuronsResponse;
 use crate::ledger_client::neuron_response::NeuronResponse;
 use crate::ledger_client::{
     handle_add_hotkey::handle_add_hotkey, handle_disburse::handle_disburse,
-    handle_follow::handle_follow, handle_merge_maturity::handle_merge_maturity,
-    handle_neuron_info::handle_neuron_info, handle_remove_hotkey::handle_remove_hotkey,
+    handle_disburse_to_neuron::handle_disburse_to_neuron, handle_follow::handle_follow,
+    handle_join_community_fund::handle_join_community_fund,
+    handle_leave_community_fund::handle_leave_community_fund,
+    handle_list_neurons::handle_list_neurons, handle_merge::handle_merge,
+    handle_merge_maturity::handle_merge_maturity, handle_neuron_info::handle_neuron_info,
+    handle_register_vote::handle_register_vote, handle_remove_hotkey::handle_remove_hotkey,
     handle_send::handle_send, handle_set_dissolve_timestamp::handle_set_dissolve_timestamp,
-    handle_spawn::handle_spawn, handle_stake::handle_stake,
+    handle_spawn::handle_spawn, handle_split::handle_split, handle_stake::handle_stake,
     handle_start_dissolve::handle_start_dissolve, handle_stop_dissolve::handle_stop_dissolve,
 };
 use crate::models::{EnvelopePair, Object, SignedTransaction};
@@ -103,6 +117,7 @@ pub enum OperationOutput {
     BlockIndex(BlockHeight),
     NeuronId(u64),
     NeuronResponse(NeuronResponse),
+    ListNeuronsResponse(ListNeuronsResponse),
 }
 
 impl LedgerClient {
@@ -114,6 +129,7 @@ impl LedgerClient {
         governance_canister_id: CanisterId,
         store_location: Option<&std::path::Path>,
         store_max_blocks: Option<u64>,
+        bootstrap_snapshot: Option<&std::path::Path>,
         offline: bool,
         root_key: Option<ThresholdSigPublicKey>,
     ) -> Result<LedgerClient, ApiError> {
@@ -128,11 +144,16 @@ impl LedgerClient {
             root_key,
             canister_id,
         });
+        let bootstrap_snapshot = bootstrap_snapshot
+            .map(LedgerSnapshot::read_from_file)
+            .transpose()
+            .map_err(ApiError::internal_error)?;
         let ledger_blocks_synchronizer = LedgerBlocksSynchronizer::new(
             canister_access.clone(),
             store_location,
             store_max_blocks,
             verification_info,
+            bootstrap_snapshot,
             Box::new(LedgerBlocksSynchronizerMetricsImpl {}),
         )
         .await?;
@@ -202,7 +223,11 @@ impl LedgerAccess for LedgerClient {
             return Err(ApiError::NotAvailableOffline(false, Details::default()));
         }
         self.ledger_blocks_synchronizer
-            .sync_blocks(stopped, None)
+            .sync_blocks(stopped.clone(), None)
+            .await
+            .map_err(ApiError::from)?;
+        self.ledger_blocks_synchronizer
+            .backfill_history(stopped)
             .await
             .map_err(ApiError::from)
     }
@@ -513,6 +538,9 @@ impl LedgerClient {
                     OperationOutput::NeuronResponse(response) => {
                         result.response = Some(Object::from(response));
                     }
+                    OperationOutput::ListNeuronsResponse(response) => {
+                        result.response = Some(Object::from(response));
+                    }
                 }
                 result.status = Status::Completed;
                 Ok(())
@@ -665,6 +693,13 @@ impl LedgerClient {
             RequestType::Stake { .. } => handle_stake(bytes),
             RequestType::StartDissolve { .. } => handle_start_dissolve(bytes, request_type),
             RequestType::StopDissolve { .. } => handle_stop_dissolve(bytes, request_type),
+            RequestType::Split { .. } => handle_split(bytes),
+            RequestType::Merge { .. } => handle_merge(bytes),
+            RequestType::DisburseToNeuron { .. } => handle_disburse_to_neuron(bytes),
+            RequestType::JoinCommunityFund { .. } => handle_join_community_fund(bytes),
+            RequestType::LeaveCommunityFund { .. } => handle_leave_community_fund(bytes),
+            RequestType::RegisterVote { .. } => handle_register_vote(bytes),
+            RequestType::ListNeurons => handle_list_neurons(bytes),
         }
     }
 }
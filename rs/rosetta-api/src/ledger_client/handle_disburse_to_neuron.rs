@@ -0,0 +1,24 @@
+use crate::errors::ApiError;
+use crate::ledger_client::OperationOutput;
+use ic_nns_governance::pb::v1::manage_neuron_response::{Command, DisburseToNeuronResponse};
+use ic_nns_governance::pb::v1::ManageNeuronResponse;
+
+pub fn handle_disburse_to_neuron(
+    bytes: Vec<u8>,
+) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: ManageNeuronResponse = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode DISBURSE_TO_NEURON response: {}", err))?;
+    match &response.command {
+        Some(Command::DisburseToNeuron(DisburseToNeuronResponse {
+            created_neuron_id: Some(neuron_id),
+        })) => Ok(Ok(Some(OperationOutput::NeuronId(neuron_id.id)))),
+        Some(Command::Error(err)) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not disburse to neuron: {}", err).into(),
+        ))),
+        _ => panic!(
+            "Unexpected disburse to neuron result: {:?}",
+            response.command
+        ),
+    }
+}
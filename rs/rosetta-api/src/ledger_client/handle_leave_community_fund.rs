@@ -0,0 +1,29 @@
+use crate::errors::ApiError;
+use crate::ledger_client::OperationOutput;
+use ic_nns_governance::pb::v1::governance_error;
+use ic_nns_governance::pb::v1::manage_neuron_response::Command;
+use ic_nns_governance::pb::v1::ManageNeuronResponse;
+
+pub fn handle_leave_community_fund(
+    bytes: Vec<u8>,
+) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: ManageNeuronResponse = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode LEAVE_COMMUNITY_FUND response: {}", err))?;
+    match &response.command {
+        Some(Command::Configure(_)) => Ok(Ok(None)),
+        // Leaving is idempotent from the point of view of the client.
+        Some(Command::Error(err))
+            if err.error_type == governance_error::ErrorType::NotInTheCommunityFund as i32 =>
+        {
+            Ok(Ok(None))
+        }
+        Some(Command::Error(err)) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not leave the community fund: {}", err).into(),
+        ))),
+        _ => panic!(
+            "Unexpected leave community fund result: {:?}",
+            response.command
+        ),
+    }
+}
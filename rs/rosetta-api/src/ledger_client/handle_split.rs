@@ -0,0 +1,19 @@
+use crate::errors::ApiError;
+use crate::ledger_client::OperationOutput;
+use ic_nns_governance::pb::v1::manage_neuron_response::{Command, SplitResponse};
+use ic_nns_governance::pb::v1::ManageNeuronResponse;
+
+pub fn handle_split(bytes: Vec<u8>) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: ManageNeuronResponse = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode SPLIT response: {}", err))?;
+    match &response.command {
+        Some(Command::Split(SplitResponse {
+            created_neuron_id: Some(neuron_id),
+        })) => Ok(Ok(Some(OperationOutput::NeuronId(neuron_id.id)))),
+        Some(Command::Error(err)) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not split neuron: {}", err).into(),
+        ))),
+        _ => panic!("Unexpected split result: {:?}", response.command),
+    }
+}
@@ -0,0 +1,17 @@
+use crate::ledger_client::neuron_response::NeuronResponse;
+use crate::models::Object;
+use serde_json::Value;
+
+#[derive(serde::Serialize)]
+pub struct ListNeuronsResponse {
+    pub(crate) neurons: Vec<NeuronResponse>,
+}
+
+impl From<ListNeuronsResponse> for Object {
+    fn from(r: ListNeuronsResponse) -> Self {
+        match serde_json::to_value(r) {
+            Ok(Value::Object(o)) => o,
+            _ => Object::default(),
+        }
+    }
+}
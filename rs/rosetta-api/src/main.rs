@@ -40,6 +40,10 @@ struct Opt {
     store_location: PathBuf,
     #[clap(long = "store-max-blocks")]
     store_max_blocks: Option<u64>,
+    /// Initialize an empty store from a ledger snapshot instead of syncing
+    /// from genesis. The snapshot is verified against the root key.
+    #[clap(long = "bootstrap-snapshot")]
+    bootstrap_snapshot: Option<PathBuf>,
     #[clap(long = "exit-on-sync")]
     exit_on_sync: bool,
     #[clap(long = "offline")]
@@ -155,6 +159,7 @@ async fn main() -> std::io::Result<()> {
 
     let Opt {
         store_max_blocks,
+        bootstrap_snapshot,
         offline,
         exit_on_sync,
         mainnet,
@@ -170,6 +175,7 @@ async fn main() -> std::io::Result<()> {
         governance_canister_id,
         store_location,
         store_max_blocks,
+        bootstrap_snapshot.as_deref(),
         offline,
         root_key,
     )
@@ -161,4 +161,25 @@ pub enum OperationType {
     #[serde(rename = "FOLLOW")]
     #[strum(serialize = "FOLLOW")]
     Follow,
+    #[serde(rename = "SPLIT")]
+    #[strum(serialize = "SPLIT")]
+    Split,
+    #[serde(rename = "MERGE")]
+    #[strum(serialize = "MERGE")]
+    Merge,
+    #[serde(rename = "DISBURSE_TO_NEURON")]
+    #[strum(serialize = "DISBURSE_TO_NEURON")]
+    DisburseToNeuron,
+    #[serde(rename = "JOIN_COMMUNITY_FUND")]
+    #[strum(serialize = "JOIN_COMMUNITY_FUND")]
+    JoinCommunityFund,
+    #[serde(rename = "LEAVE_COMMUNITY_FUND")]
+    #[strum(serialize = "LEAVE_COMMUNITY_FUND")]
+    LeaveCommunityFund,
+    #[serde(rename = "REGISTER_VOTE")]
+    #[strum(serialize = "REGISTER_VOTE")]
+    RegisterVote,
+    #[serde(rename = "LIST_NEURONS")]
+    #[strum(serialize = "LIST_NEURONS")]
+    ListNeurons,
 }
@@ -5,6 +5,7 @@ use crate::request_types::*;
 use crate::{convert, models};
 use dfn_candid::CandidOne;
 use ic_nns_governance::pb::v1::manage_neuron::{self, configure, Command, Configure};
+use ic_nns_governance::pb::v1::Vote;
 use ic_types::PrincipalId;
 use ledger_canister::Tokens;
 use on_wire::FromWire;
@@ -52,6 +53,20 @@ pub enum Request {
     NeuronInfo(NeuronInfo),
     #[serde(rename = "FOLLOW")]
     Follow(Follow),
+    #[serde(rename = "SPLIT")]
+    Split(Split),
+    #[serde(rename = "MERGE")]
+    Merge(Merge),
+    #[serde(rename = "DISBURSE_TO_NEURON")]
+    DisburseToNeuron(DisburseToNeuron),
+    #[serde(rename = "JOIN_COMMUNITY_FUND")]
+    JoinCommunityFund(JoinCommunityFund),
+    #[serde(rename = "LEAVE_COMMUNITY_FUND")]
+    LeaveCommunityFund(LeaveCommunityFund),
+    #[serde(rename = "REGISTER_VOTE")]
+    RegisterVote(RegisterVote),
+    #[serde(rename = "LIST_NEURONS")]
+    ListNeurons(ListNeurons),
 }
 
 impl Request {
@@ -118,6 +133,36 @@ impl Request {
                 neuron_index: *neuron_index,
                 controller: controller.map(PublicKeyOrPrincipal::Principal),
             }),
+            Request::Split(Split { neuron_index, .. }) => Ok(RequestType::Split {
+                neuron_index: *neuron_index,
+            }),
+            Request::Merge(Merge { neuron_index, .. }) => Ok(RequestType::Merge {
+                neuron_index: *neuron_index,
+            }),
+            Request::DisburseToNeuron(DisburseToNeuron { neuron_index, .. }) => {
+                Ok(RequestType::DisburseToNeuron {
+                    neuron_index: *neuron_index,
+                })
+            }
+            Request::JoinCommunityFund(JoinCommunityFund { neuron_index, .. }) => {
+                Ok(RequestType::JoinCommunityFund {
+                    neuron_index: *neuron_index,
+                })
+            }
+            Request::LeaveCommunityFund(LeaveCommunityFund { neuron_index, .. }) => {
+                Ok(RequestType::LeaveCommunityFund {
+                    neuron_index: *neuron_index,
+                })
+            }
+            Request::RegisterVote(RegisterVote {
+                neuron_index,
+                controller,
+                ..
+            }) => Ok(RequestType::RegisterVote {
+                neuron_index: *neuron_index,
+                controller: controller.map(PublicKeyOrPrincipal::Principal),
+            }),
+            Request::ListNeurons(_) => Ok(RequestType::ListNeurons),
         }
     }
 
@@ -144,6 +189,13 @@ impl Request {
                 Request::MergeMaturity(o) => builder.merge_maturity(o),
                 Request::NeuronInfo(o) => builder.neuron_info(o),
                 Request::Follow(o) => builder.follow(o),
+                Request::Split(o) => builder.split(o, token_name),
+                Request::Merge(o) => builder.merge(o),
+                Request::DisburseToNeuron(o) => builder.disburse_to_neuron(o, token_name),
+                Request::JoinCommunityFund(o) => builder.join_community_fund(o),
+                Request::LeaveCommunityFund(o) => builder.leave_community_fund(o),
+                Request::RegisterVote(o) => builder.register_vote(o),
+                Request::ListNeurons(o) => builder.list_neurons(o),
             };
         }
         Ok(builder.build())
@@ -167,6 +219,13 @@ impl Request {
                 | Request::MergeMaturity(_)
                 | Request::NeuronInfo(_) // not neuron management but we need it signed.
                 | Request::Follow(_)
+                | Request::Split(_)
+                | Request::Merge(_)
+                | Request::DisburseToNeuron(_)
+                | Request::JoinCommunityFund(_)
+                | Request::LeaveCommunityFund(_)
+                | Request::RegisterVote(_)
+                | Request::ListNeurons(_) // not neuron management but we need it signed.
         )
     }
 }
@@ -408,6 +467,114 @@ impl TryFrom<&models::Request> for Request {
                     Err(ApiError::invalid_request("Invalid follow request."))
                 }
             }
+            RequestType::Split { neuron_index } => {
+                if let Some(Command::Split(manage_neuron::Split { amount_e8s })) = manage_neuron()?
+                {
+                    Ok(Request::Split(Split {
+                        account,
+                        amount: Tokens::from_e8s(amount_e8s),
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid split request."))
+                }
+            }
+            RequestType::Merge { neuron_index } => {
+                if let Some(Command::Merge(manage_neuron::Merge {
+                    source_neuron_id: Some(source_neuron_id),
+                })) = manage_neuron()?
+                {
+                    Ok(Request::Merge(Merge {
+                        account,
+                        source_neuron_id: source_neuron_id.id,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid merge request."))
+                }
+            }
+            RequestType::DisburseToNeuron { neuron_index } => {
+                if let Some(Command::DisburseToNeuron(manage_neuron::DisburseToNeuron {
+                    new_controller: Some(controller),
+                    amount_e8s,
+                    dissolve_delay_seconds,
+                    kyc_verified,
+                    nonce,
+                })) = manage_neuron()?
+                {
+                    Ok(Request::DisburseToNeuron(DisburseToNeuron {
+                        account,
+                        amount: Tokens::from_e8s(amount_e8s),
+                        controller,
+                        dissolve_delay_seconds,
+                        kyc_verified,
+                        child_neuron_index: nonce,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request(
+                        "Invalid disburse to neuron request.",
+                    ))
+                }
+            }
+            RequestType::JoinCommunityFund { neuron_index } => {
+                if let Some(Command::Configure(Configure {
+                    operation: Some(configure::Operation::JoinCommunityFund(_)),
+                })) = manage_neuron()?
+                {
+                    Ok(Request::JoinCommunityFund(JoinCommunityFund {
+                        account,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request(
+                        "Invalid join community fund request.",
+                    ))
+                }
+            }
+            RequestType::LeaveCommunityFund { neuron_index } => {
+                if let Some(Command::Configure(Configure {
+                    operation: Some(configure::Operation::LeaveCommunityFund(_)),
+                })) = manage_neuron()?
+                {
+                    Ok(Request::LeaveCommunityFund(LeaveCommunityFund {
+                        account,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request(
+                        "Invalid leave community fund request.",
+                    ))
+                }
+            }
+            RequestType::RegisterVote {
+                neuron_index,
+                controller,
+            } => {
+                if let Some(Command::RegisterVote(manage_neuron::RegisterVote {
+                    proposal: Some(proposal),
+                    vote,
+                })) = manage_neuron()?
+                {
+                    if !matches!(Vote::from_i32(vote), Some(Vote::Yes) | Some(Vote::No)) {
+                        return Err(ApiError::invalid_request(format!("Invalid vote: {}", vote)));
+                    }
+                    let controller = controller
+                        .clone()
+                        .map(principal_id_from_public_key_or_principal)
+                        .transpose()?;
+                    Ok(Request::RegisterVote(RegisterVote {
+                        account,
+                        proposal: proposal.id,
+                        vote,
+                        controller,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid register vote request."))
+                }
+            }
+            RequestType::ListNeurons => Ok(Request::ListNeurons(ListNeurons { account })),
         }
     }
 }
@@ -3,13 +3,15 @@ use crate::errors::ApiError;
 use crate::models::{ConstructionParseRequest, ConstructionParseResponse, ParsedTransaction};
 use crate::request_handler::{verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RemoveHotKey,
-    RequestType, SetDissolveTimestamp, Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, DisburseToNeuron, Follow, JoinCommunityFund, LeaveCommunityFund,
+    ListNeurons, Merge, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RegisterVote,
+    RemoveHotKey, RequestType, SetDissolveTimestamp, Spawn, Split, Stake, StartDissolve,
+    StopDissolve,
 };
 
 use ic_nns_governance::pb::v1::{
     manage_neuron::{self, Command, NeuronIdOrSubaccount},
-    ClaimOrRefreshNeuronFromAccount, ManageNeuron,
+    ClaimOrRefreshNeuronFromAccount, ListNeurons as ListNeuronsArgs, ManageNeuron, Vote,
 };
 
 use crate::models::seconds::Seconds;
@@ -88,6 +90,26 @@ impl RosettaRequestHandler {
                     neuron_index,
                     controller,
                 } => follow(&mut requests, arg, from, neuron_index, controller)?,
+                RequestType::Split { neuron_index } => {
+                    split(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::Merge { neuron_index } => {
+                    merge(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::DisburseToNeuron { neuron_index } => {
+                    disburse_to_neuron(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::JoinCommunityFund { neuron_index } => {
+                    join_community_fund(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::LeaveCommunityFund { neuron_index } => {
+                    leave_community_fund(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::RegisterVote {
+                    neuron_index,
+                    controller,
+                } => register_vote(&mut requests, arg, from, neuron_index, controller)?,
+                RequestType::ListNeurons => list_neurons(&mut requests, arg, from)?,
             }
         }
 
@@ -465,3 +487,204 @@ fn follow(
     }
     Ok(())
 }
+
+/// Handle SPLIT.
+fn split(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::Split(manage_neuron::Split { amount_e8s })) = manage.command {
+        requests.push(Request::Split(Split {
+            account: from,
+            amount: ledger_canister::Tokens::from_e8s(amount_e8s),
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle MERGE.
+fn merge(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::Merge(manage_neuron::Merge {
+        source_neuron_id: Some(source_neuron_id),
+    })) = manage.command
+    {
+        requests.push(Request::Merge(Merge {
+            account: from,
+            source_neuron_id: source_neuron_id.id,
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle DISBURSE_TO_NEURON.
+fn disburse_to_neuron(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::DisburseToNeuron(manage_neuron::DisburseToNeuron {
+        new_controller: Some(controller),
+        amount_e8s,
+        dissolve_delay_seconds,
+        kyc_verified,
+        nonce,
+    })) = manage.command
+    {
+        requests.push(Request::DisburseToNeuron(DisburseToNeuron {
+            account: from,
+            amount: ledger_canister::Tokens::from_e8s(amount_e8s),
+            controller,
+            dissolve_delay_seconds,
+            kyc_verified,
+            child_neuron_index: nonce,
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle JOIN_COMMUNITY_FUND.
+fn join_community_fund(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!(
+            "Could not decode Join Community Fund argument: {:?}",
+            e
+        ))
+    })?;
+    if !matches!(
+        manage.command,
+        Some(Command::Configure(manage_neuron::Configure {
+            operation: Some(manage_neuron::configure::Operation::JoinCommunityFund(
+                manage_neuron::JoinCommunityFund {},
+            )),
+        }))
+    ) {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    };
+    requests.push(Request::JoinCommunityFund(JoinCommunityFund {
+        account: from,
+        neuron_index,
+    }));
+    Ok(())
+}
+
+/// Handle LEAVE_COMMUNITY_FUND.
+fn leave_community_fund(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!(
+            "Could not decode Leave Community Fund argument: {:?}",
+            e
+        ))
+    })?;
+    if !matches!(
+        manage.command,
+        Some(Command::Configure(manage_neuron::Configure {
+            operation: Some(manage_neuron::configure::Operation::LeaveCommunityFund(
+                manage_neuron::LeaveCommunityFund {},
+            )),
+        }))
+    ) {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    };
+    requests.push(Request::LeaveCommunityFund(LeaveCommunityFund {
+        account: from,
+        neuron_index,
+    }));
+    Ok(())
+}
+
+/// Handle REGISTER_VOTE.
+fn register_vote(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+    controller: Option<PublicKeyOrPrincipal>,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::RegisterVote(manage_neuron::RegisterVote {
+        proposal: Some(proposal),
+        vote,
+    })) = manage.command
+    {
+        if !matches!(Vote::from_i32(vote), Some(Vote::Yes) | Some(Vote::No)) {
+            return Err(ApiError::invalid_request(format!("Invalid vote: {}", vote)));
+        }
+        let controller = controller
+            .map(convert::principal_id_from_public_key_or_principal)
+            .transpose()
+            .map_err(|_| ApiError::invalid_request("Invalid register vote request."))?;
+        requests.push(Request::RegisterVote(RegisterVote {
+            account: from,
+            proposal: proposal.id,
+            vote,
+            controller,
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle LIST_NEURONS.
+fn list_neurons(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+) -> Result<(), ApiError> {
+    let _: ListNeuronsArgs = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode list neurons argument: {:?}", e))
+    })?;
+    requests.push(Request::ListNeurons(ListNeurons { account: from }));
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use dfn_candid::CandidOne;
-use ic_nns_common::pb::v1::NeuronId;
+use ic_nns_common::pb::v1::{NeuronId, ProposalId};
 use ic_types::messages::{Blob, HttpCanisterUpdate, MessageId};
 use ic_types::PrincipalId;
 use ledger_canister::{Memo, Operation, SendArgs, Tokens};
@@ -11,7 +11,7 @@ use std::time::Duration;
 
 use ic_nns_governance::pb::v1::{
     manage_neuron::{self, configure, Command, NeuronIdOrSubaccount},
-    ClaimOrRefreshNeuronFromAccount, ManageNeuron,
+    ClaimOrRefreshNeuronFromAccount, ListNeurons as ListNeuronsArgs, ManageNeuron,
 };
 
 use crate::convert::{make_read_state_from_update, to_arg, to_model_account_identifier};
@@ -24,8 +24,10 @@ use crate::models::{
 use crate::request::Request;
 use crate::request_handler::{make_sig_data, verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RemoveHotKey,
-    RequestType, SetDissolveTimestamp, Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, DisburseToNeuron, Follow, JoinCommunityFund, LeaveCommunityFund,
+    ListNeurons, Merge, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RegisterVote,
+    RemoveHotKey, RequestType, SetDissolveTimestamp, Spawn, Split, Stake, StartDissolve,
+    StopDissolve,
 };
 use crate::{convert, models};
 
@@ -187,6 +189,55 @@ impl RosettaRequestHandler {
                     &pks_map,
                     &ingress_expiries,
                 )?,
+                Request::Split(req) => handle_split(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::Merge(req) => handle_merge(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::DisburseToNeuron(req) => handle_disburse_to_neuron(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::JoinCommunityFund(req) => handle_join_community_fund(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::LeaveCommunityFund(req) => handle_leave_community_fund(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::RegisterVote(req) => handle_register_vote(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::ListNeurons(req) => handle_list_neurons(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
             }
         }
 
@@ -681,6 +732,223 @@ fn handle_follow(
     Ok(())
 }
 
+/// Handle SPLIT.
+fn handle_split(
+    req: Split,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let neuron_index = req.neuron_index;
+    let command = Command::Split(manage_neuron::Split {
+        amount_e8s: req.amount.get_e8s(),
+    });
+    add_neuron_management_payload(
+        RequestType::Split { neuron_index },
+        account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle MERGE.
+fn handle_merge(
+    req: Merge,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let neuron_index = req.neuron_index;
+    let command = Command::Merge(manage_neuron::Merge {
+        source_neuron_id: Some(NeuronId {
+            id: req.source_neuron_id,
+        }),
+    });
+    add_neuron_management_payload(
+        RequestType::Merge { neuron_index },
+        account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle DISBURSE_TO_NEURON.
+fn handle_disburse_to_neuron(
+    req: DisburseToNeuron,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let neuron_index = req.neuron_index;
+    let command = Command::DisburseToNeuron(manage_neuron::DisburseToNeuron {
+        new_controller: Some(req.controller),
+        amount_e8s: req.amount.get_e8s(),
+        dissolve_delay_seconds: req.dissolve_delay_seconds,
+        kyc_verified: req.kyc_verified,
+        nonce: req.child_neuron_index,
+    });
+    add_neuron_management_payload(
+        RequestType::DisburseToNeuron { neuron_index },
+        account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle JOIN_COMMUNITY_FUND.
+fn handle_join_community_fund(
+    req: JoinCommunityFund,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let neuron_index = req.neuron_index;
+    let command = Command::Configure(manage_neuron::Configure {
+        operation: Some(configure::Operation::JoinCommunityFund(
+            manage_neuron::JoinCommunityFund {},
+        )),
+    });
+    add_neuron_management_payload(
+        RequestType::JoinCommunityFund { neuron_index },
+        account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle LEAVE_COMMUNITY_FUND.
+fn handle_leave_community_fund(
+    req: LeaveCommunityFund,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let neuron_index = req.neuron_index;
+    let command = Command::Configure(manage_neuron::Configure {
+        operation: Some(configure::Operation::LeaveCommunityFund(
+            manage_neuron::LeaveCommunityFund {},
+        )),
+    });
+    add_neuron_management_payload(
+        RequestType::LeaveCommunityFund { neuron_index },
+        account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle REGISTER_VOTE.
+fn handle_register_vote(
+    req: RegisterVote,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let controller = req.controller;
+    let neuron_index = req.neuron_index;
+    let command = Command::RegisterVote(manage_neuron::RegisterVote {
+        proposal: Some(ProposalId { id: req.proposal }),
+        vote: req.vote,
+    });
+    add_neuron_management_payload(
+        RequestType::RegisterVote {
+            neuron_index,
+            controller: controller.map(PublicKeyOrPrincipal::Principal),
+        },
+        account,
+        controller,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle LIST_NEURONS.
+fn handle_list_neurons(
+    req: ListNeurons,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let pk = pks_map.get(&account).ok_or_else(|| {
+        ApiError::internal_error(format!(
+            "ListNeurons - Cannot find public key for account {}",
+            account,
+        ))
+    })?;
+
+    // List all the neurons the sender is allowed to read, i.e. the neurons it
+    // controls or is a hotkey of.
+    let args = ListNeuronsArgs {
+        neuron_ids: vec![],
+        include_neurons_readable_by_caller: true,
+    };
+    let update = HttpCanisterUpdate {
+        canister_id: Blob(ic_nns_constants::GOVERNANCE_CANISTER_ID.get().to_vec()),
+        method_name: "list_neurons".to_string(),
+        arg: Blob(CandidOne(args).into_bytes().expect("Serialization failed")),
+        nonce: None,
+        sender: Blob(convert::principal_id_from_public_key(pk)?.into_vec()),
+        ingress_expiry: 0,
+    };
+    add_payloads(
+        payloads,
+        ingress_expiries,
+        &convert::to_model_account_identifier(&account),
+        &update,
+    );
+    updates.push((RequestType::ListNeurons, update));
+    Ok(())
+}
+
 fn add_neuron_management_payload(
     request_type: RequestType,
     account: ledger_canister::AccountIdentifier,
@@ -33,6 +33,13 @@ pub const SPAWN: &str = "SPAWN";
 pub const MERGE_MATURITY: &str = "MERGE_MATURITY";
 pub const NEURON_INFO: &str = "NEURON_INFO";
 pub const FOLLOW: &str = "FOLLOW";
+pub const SPLIT: &str = "SPLIT";
+pub const MERGE: &str = "MERGE";
+pub const DISBURSE_TO_NEURON: &str = "DISBURSE_TO_NEURON";
+pub const JOIN_COMMUNITY_FUND: &str = "JOIN_COMMUNITY_FUND";
+pub const LEAVE_COMMUNITY_FUND: &str = "LEAVE_COMMUNITY_FUND";
+pub const REGISTER_VOTE: &str = "REGISTER_VOTE";
+pub const LIST_NEURONS: &str = "LIST_NEURONS";
 
 /// `RequestType` contains all supported values of `Operation.type`.
 /// Extra information, such as `neuron_index` should only be included
@@ -82,6 +89,30 @@ pub enum RequestType {
         neuron_index: u64,
         controller: Option<PublicKeyOrPrincipal>,
     },
+    #[serde(rename = "SPLIT")]
+    #[serde(alias = "Split")]
+    Split { neuron_index: u64 },
+    #[serde(rename = "MERGE")]
+    #[serde(alias = "Merge")]
+    Merge { neuron_index: u64 },
+    #[serde(rename = "DISBURSE_TO_NEURON")]
+    #[serde(alias = "DisburseToNeuron")]
+    DisburseToNeuron { neuron_index: u64 },
+    #[serde(rename = "JOIN_COMMUNITY_FUND")]
+    #[serde(alias = "JoinCommunityFund")]
+    JoinCommunityFund { neuron_index: u64 },
+    #[serde(rename = "LEAVE_COMMUNITY_FUND")]
+    #[serde(alias = "LeaveCommunityFund")]
+    LeaveCommunityFund { neuron_index: u64 },
+    #[serde(rename = "REGISTER_VOTE")]
+    #[serde(alias = "RegisterVote")]
+    RegisterVote {
+        neuron_index: u64,
+        controller: Option<PublicKeyOrPrincipal>,
+    },
+    #[serde(rename = "LIST_NEURONS")]
+    #[serde(alias = "ListNeurons")]
+    ListNeurons,
 }
 
 impl RequestType {
@@ -99,6 +130,13 @@ impl RequestType {
             RequestType::MergeMaturity { .. } => MERGE_MATURITY,
             RequestType::NeuronInfo { .. } => NEURON_INFO,
             RequestType::Follow { .. } => FOLLOW,
+            RequestType::Split { .. } => SPLIT,
+            RequestType::Merge { .. } => MERGE,
+            RequestType::DisburseToNeuron { .. } => DISBURSE_TO_NEURON,
+            RequestType::JoinCommunityFund { .. } => JOIN_COMMUNITY_FUND,
+            RequestType::LeaveCommunityFund { .. } => LEAVE_COMMUNITY_FUND,
+            RequestType::RegisterVote { .. } => REGISTER_VOTE,
+            RequestType::ListNeurons => LIST_NEURONS,
         }
     }
 
@@ -120,6 +158,13 @@ impl RequestType {
                 | RequestType::MergeMaturity { .. }
                 | RequestType::NeuronInfo { .. }
                 | RequestType::Follow { .. }
+                | RequestType::Split { .. }
+                | RequestType::Merge { .. }
+                | RequestType::DisburseToNeuron { .. }
+                | RequestType::JoinCommunityFund { .. }
+                | RequestType::LeaveCommunityFund { .. }
+                | RequestType::RegisterVote { .. }
+                | RequestType::ListNeurons
         )
     }
 }
@@ -288,6 +333,66 @@ pub struct Follow {
     pub neuron_index: u64,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Split {
+    pub account: ledger_canister::AccountIdentifier,
+    pub amount: Tokens,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Merge {
+    pub account: ledger_canister::AccountIdentifier,
+    /// The id of the neuron whose stake and maturity are merged into the
+    /// neuron identified by `neuron_index`.
+    pub source_neuron_id: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DisburseToNeuron {
+    pub account: ledger_canister::AccountIdentifier,
+    pub amount: Tokens,
+    pub controller: PrincipalId,
+    pub dissolve_delay_seconds: u64,
+    pub kyc_verified: bool,
+    /// The nonce used by governance to derive the subaccount of the new neuron.
+    pub child_neuron_index: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct JoinCommunityFund {
+    pub account: ledger_canister::AccountIdentifier,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LeaveCommunityFund {
+    pub account: ledger_canister::AccountIdentifier,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegisterVote {
+    pub account: ledger_canister::AccountIdentifier,
+    pub proposal: u64,
+    pub vote: i32,
+    pub controller: Option<PrincipalId>,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ListNeurons {
+    pub account: ledger_canister::AccountIdentifier,
+}
+
 #[derive(Debug, Clone, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 // Externally tagged by default.
@@ -673,6 +778,113 @@ impl From<FollowMetadata> for Object {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct MergeMetadata {
+    pub source_neuron_id: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for MergeMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse a MERGE operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<MergeMetadata> for Object {
+    fn from(m: MergeMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct DisburseToNeuronMetadata {
+    pub controller: PublicKeyOrPrincipal,
+    pub dissolve_delay_seconds: u64,
+    #[serde(default)]
+    pub kyc_verified: bool,
+    pub child_neuron_index: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for DisburseToNeuronMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse a DISBURSE_TO_NEURON operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<DisburseToNeuronMetadata> for Object {
+    fn from(m: DisburseToNeuronMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct RegisterVoteMetadata {
+    pub proposal: u64,
+    pub vote: i32,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub controller: Option<PublicKeyOrPrincipal>,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for RegisterVoteMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse a REGISTER_VOTE operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<RegisterVoteMetadata> for Object {
+    fn from(m: RegisterVoteMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn test_parse_register_vote_metadata() {
+    let m: RegisterVoteMetadata =
+        serde_json::from_str(r#"{ "proposal": 42, "vote": 1, "neuron_index": 3 }"#).unwrap();
+    assert_eq!(
+        m,
+        RegisterVoteMetadata {
+            proposal: 42,
+            vote: 1,
+            controller: None,
+            neuron_index: 3,
+        }
+    );
+}
+
 /// Transaction is a bit of a misnomer, since operations can succeed or fail
 /// independently from a Transaction.
 #[derive(Default)]
@@ -1054,6 +1266,177 @@ impl TransactionBuilder {
             ),
         });
     }
+
+    pub fn split(&mut self, split: &Split, token_name: &str) {
+        let Split {
+            account,
+            amount,
+            neuron_index,
+        } = split;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Split,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: Some(tokens_to_amount(*amount, token_name).expect("failed to convert amount")),
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                NeuronIdentifierMetadata {
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn merge(&mut self, merge: &Merge) {
+        let Merge {
+            account,
+            source_neuron_id,
+            neuron_index,
+        } = merge;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Merge,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                MergeMetadata {
+                    source_neuron_id: *source_neuron_id,
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn disburse_to_neuron(&mut self, disburse: &DisburseToNeuron, token_name: &str) {
+        let DisburseToNeuron {
+            account,
+            amount,
+            controller,
+            dissolve_delay_seconds,
+            kyc_verified,
+            child_neuron_index,
+            neuron_index,
+        } = disburse;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::DisburseToNeuron,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: Some(tokens_to_amount(*amount, token_name).expect("failed to convert amount")),
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                DisburseToNeuronMetadata {
+                    controller: PublicKeyOrPrincipal::Principal(*controller),
+                    dissolve_delay_seconds: *dissolve_delay_seconds,
+                    kyc_verified: *kyc_verified,
+                    child_neuron_index: *child_neuron_index,
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn join_community_fund(&mut self, join: &JoinCommunityFund) {
+        let JoinCommunityFund {
+            account,
+            neuron_index,
+        } = join;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::JoinCommunityFund,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                NeuronIdentifierMetadata {
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn leave_community_fund(&mut self, leave: &LeaveCommunityFund) {
+        let LeaveCommunityFund {
+            account,
+            neuron_index,
+        } = leave;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::LeaveCommunityFund,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                NeuronIdentifierMetadata {
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn register_vote(&mut self, vote: &RegisterVote) {
+        let RegisterVote {
+            account,
+            proposal,
+            vote,
+            controller,
+            neuron_index,
+        } = vote;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::RegisterVote,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                RegisterVoteMetadata {
+                    proposal: *proposal,
+                    vote: *vote,
+                    controller: pkp_from_principal(controller),
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn list_neurons(&mut self, req: &ListNeurons) {
+        let ListNeurons { account } = req;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::ListNeurons,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: None,
+        });
+    }
 }
 
 /// Converts an optional PrincipalId to an optional PublicKeyOrPrincipal.
@@ -3,20 +3,26 @@ use super::*;
 use ic_ledger_canister_blocks_synchronizer_test_utils::sample_data::Scribe;
 use ic_ledger_canister_blocks_synchronizer_test_utils::{create_tmp_dir, init_test_logger};
 use ic_ledger_core::block::BlockType;
-use ic_rosetta_api::convert::{block_id, from_hash, to_hash};
+use ic_rosetta_api::convert::{
+    block_id, from_hash, operations_to_requests, principal_id_from_public_key, to_hash,
+};
 use ic_rosetta_api::ledger_client::LedgerAccess;
 use ic_rosetta_api::models::amount::{tokens_to_amount, Amount};
 use ic_rosetta_api::request_handler::RosettaRequestHandler;
+use ic_rosetta_api::request_types::{
+    DisburseToNeuron, JoinCommunityFund, LeaveCommunityFund, ListNeurons, Merge, RegisterVote,
+    Split,
+};
 use ic_rosetta_api::transaction_id::TransactionIdentifier;
 use ic_rosetta_api::{models, API_VERSION, NODE_VERSION};
 
 use ic_rosetta_api::models::{
     AccountBalanceResponse, BlockIdentifier, BlockRequest, BlockTransaction,
     BlockTransactionRequest, ConstructionDeriveRequest, ConstructionDeriveResponse,
-    ConstructionMetadataRequest, ConstructionMetadataResponse, Currency, CurveType,
-    MempoolResponse, MempoolTransactionRequest, MetadataRequest, NetworkListResponse,
-    NetworkRequest, NetworkStatusResponse, SearchTransactionsRequest, SearchTransactionsResponse,
-    SyncStatus,
+    ConstructionMetadataRequest, ConstructionMetadataResponse, ConstructionParseRequest,
+    ConstructionPayloadsRequest, Currency, CurveType, MempoolResponse, MempoolTransactionRequest,
+    MetadataRequest, NetworkListResponse, NetworkRequest, NetworkStatusResponse, PublicKey,
+    SearchTransactionsRequest, SearchTransactionsResponse, SyncStatus,
 };
 use std::sync::Arc;
 
@@ -779,3 +785,71 @@ async fn store_batch_test() {
     blocks.block_store.mark_last_verified(last_idx).unwrap();
     verify_balances(&scribe, &blocks, 0);
 }
+
+#[actix_rt::test]
+async fn neuron_lifecycle_requests_round_trip_through_construction() {
+    let ledger = Arc::new(TestLedger::default());
+    let req_handler = RosettaRequestHandler::new_with_default_blockchain(ledger);
+
+    let public_key = PublicKey::new(hex::encode([1u8; 32]), CurveType::Edwards25519);
+    let account: AccountIdentifier = principal_id_from_public_key(&public_key).unwrap().into();
+    let requests = vec![
+        Request::Split(Split {
+            account,
+            amount: Tokens::from_e8s(500_000_000),
+            neuron_index: 1,
+        }),
+        Request::Merge(Merge {
+            account,
+            source_neuron_id: 42,
+            neuron_index: 1,
+        }),
+        Request::DisburseToNeuron(DisburseToNeuron {
+            account,
+            amount: Tokens::from_e8s(300_000_000),
+            controller: PrincipalId::new_user_test_id(7),
+            dissolve_delay_seconds: 86_400,
+            kyc_verified: true,
+            child_neuron_index: 2,
+            neuron_index: 1,
+        }),
+        Request::JoinCommunityFund(JoinCommunityFund {
+            account,
+            neuron_index: 1,
+        }),
+        Request::LeaveCommunityFund(LeaveCommunityFund {
+            account,
+            neuron_index: 1,
+        }),
+        Request::RegisterVote(RegisterVote {
+            account,
+            proposal: 1234,
+            vote: 1,
+            controller: None,
+            neuron_index: 1,
+        }),
+        Request::ListNeurons(ListNeurons { account }),
+    ];
+    let operations = Request::requests_to_operations(&requests, DEFAULT_TOKEN_SYMBOL).unwrap();
+
+    let payloads = req_handler
+        .construction_payloads(ConstructionPayloadsRequest {
+            network_identifier: req_handler.network_id(),
+            operations,
+            metadata: None,
+            public_keys: Some(vec![public_key]),
+        })
+        .unwrap();
+    let parsed = req_handler
+        .construction_parse(ConstructionParseRequest::new(
+            req_handler.network_id(),
+            false,
+            payloads.unsigned_transaction,
+        ))
+        .unwrap();
+
+    assert_eq!(
+        operations_to_requests(&parsed.operations, false, DEFAULT_TOKEN_SYMBOL),
+        Ok(requests)
+    );
+}
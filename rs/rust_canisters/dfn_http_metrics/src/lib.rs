@@ -1,10 +1,21 @@
-use dfn_http::types::{HttpRequest, HttpResponse};
+pub use dfn_http::types::{HttpRequest, HttpResponse};
 use serde_bytes::ByteBuf;
 
 /// Implements an HTTP endpoint that handles /metrics requests and return 404
 /// for all other paths.
 pub fn serve_metrics(
     encode_metrics: impl FnOnce(&mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()>,
+) {
+    serve_metrics_and(encode_metrics, |_path, _req| None)
+}
+
+/// Implements an HTTP endpoint that handles /metrics requests and delegates
+/// all other requests to `serve_other`, which is given the request path
+/// (without the query string). If `serve_other` returns `None`, a 404 is
+/// returned.
+pub fn serve_metrics_and(
+    encode_metrics: impl FnOnce(&mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()>,
+    serve_other: impl FnOnce(&str, &HttpRequest) -> Option<HttpResponse>,
 ) {
     dfn_core::over(
         dfn_candid::candid,
@@ -43,12 +54,12 @@ pub fn serve_metrics(
                     },
                 }
             } else {
-                HttpResponse {
+                serve_other(path, &req).unwrap_or_else(|| HttpResponse {
                     status_code: 404,
                     headers: vec![],
                     body: ByteBuf::from("not found"),
                     streaming_strategy: None,
-                }
+                })
             }
         },
     );
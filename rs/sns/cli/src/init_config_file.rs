@@ -2,7 +2,10 @@ use anyhow::anyhow;
 use clap::Parser;
 use ic_sns_governance::pb::v1::{governance::SnsMetadata, NervousSystemParameters};
 use ic_sns_init::{
-    pb::v1::{sns_init_payload::InitialTokenDistribution, SnsInitPayload},
+    pb::v1::{
+        sns_init_payload::InitialTokenDistribution, NeuronBasketConstructionParameters,
+        SnsInitPayload,
+    },
     MAX_TOKEN_NAME_LENGTH, MAX_TOKEN_SYMBOL_LENGTH, MIN_PARTICIPANT_ICP_E8S_DEFAULT,
     MIN_TOKEN_NAME_LENGTH, MIN_TOKEN_SYMBOL_LENGTH,
 };
@@ -98,6 +101,10 @@ pub struct SnsCliInitConfig {
     /// to the strategy and configuration picked via the initial_token_distribution
     /// parameter.
     initial_token_distribution: Option<InitialTokenDistribution>,
+
+    /// How the SNS tokens bought by each participant of the swap are split into a
+    /// basket of neurons with staggered dissolve delays.
+    neuron_basket_construction_parameters: Option<NeuronBasketConstructionParameters>,
 }
 
 impl Default for SnsCliInitConfig {
@@ -121,6 +128,7 @@ impl Default for SnsCliInitConfig {
             name: None,
             description: None,
             initial_token_distribution: None,
+            neuron_basket_construction_parameters: None,
         }
     }
 }
@@ -227,6 +235,8 @@ impl TryFrom<SnsCliInitConfig> for SnsInitPayload {
             name: sns_cli_init_config.name,
             description: sns_cli_init_config.description,
             initial_token_distribution: sns_cli_init_config.initial_token_distribution,
+            neuron_basket_construction_parameters: sns_cli_init_config
+                .neuron_basket_construction_parameters,
         })
     }
 }
@@ -316,7 +326,9 @@ fn get_config_file_contents(sns_cli_init_config: SnsCliInitConfig) -> String {
 # - developer_distribution has one field:
 #    - developer_neurons: A list of NeuronDistributions that specify the neuron's stake and
 #      controlling principal. These neurons will be available at genesis in PreInitializationSwap
-#      mode. The voting power mutliplier will be applied to these neurons.
+#      mode. The voting power mutliplier will be applied to these neurons. A NeuronDistribution
+#      may also specify a vesting_period_seconds, during which the dissolve state of the
+#      neuron cannot be changed and the neuron cannot be split.
 #
 # - treasury_distribution has one field:
 #    - total_e8s: The total amount of tokens in the treasury bucket.
@@ -341,6 +353,7 @@ fn get_config_file_contents(sns_cli_init_config: SnsCliInitConfig) -> String {
 #           stake_e8s: 1500000000
 #         - controller: fod6j-klqsi-ljm4t-7v54x-2wd6s-6yduy-spdkk-d2vd4-iet7k-nakfi-qqe
 #           stake_e8s: 1500000000
+#           vesting_period_seconds: 31557600
 #     treasury_distribution:
 #       total_e8s: 5000000000
 #     swap_distribution:
@@ -435,6 +448,22 @@ fn get_config_file_contents(sns_cli_init_config: SnsCliInitConfig) -> String {
 # If the swap fails, control of the dapp canister(s) will be set to these
 # principal IDs. In most use-cases, this would be the same as the original set
 # of controller(s). Must not be empty.
+#"##
+            .to_string(),
+        ),
+        (
+            Regex::new(r"neuron_basket_construction_parameters.*").unwrap(),
+            r##"#
+# The basket of neurons that each participant of the swap receives. The SNS
+# tokens bought by a participant are split evenly between `count` neurons, the
+# i-th of which (counting from zero) has a dissolve delay of
+# `i * dissolve_delay_interval_seconds`. If unset, each participant receives a
+# single neuron with zero dissolve delay.
+#
+# Example:
+# neuron_basket_construction_parameters:
+#   count: 3
+#   dissolve_delay_interval_seconds: 7889400
 #"##
             .to_string(),
         ),
@@ -525,7 +554,9 @@ fn validate(init_config_file: PathBuf) {
 mod test {
     use crate::init_config_file::{get_config_file_contents, SnsCliInitConfig};
     use ic_sns_init::pb::v1::sns_init_payload::InitialTokenDistribution::FractionalDeveloperVotingPower as FDVP;
-    use ic_sns_init::pb::v1::{FractionalDeveloperVotingPower, SnsInitPayload};
+    use ic_sns_init::pb::v1::{
+        FractionalDeveloperVotingPower, NeuronBasketConstructionParameters, SnsInitPayload,
+    };
     use std::convert::TryFrom;
     use std::fs::File;
     use std::io::{BufReader, Read};
@@ -614,6 +645,10 @@ url: https://internetcomputer.org/
             initial_token_distribution: Some(FDVP(FractionalDeveloperVotingPower {
                 ..Default::default()
             })),
+            neuron_basket_construction_parameters: Some(NeuronBasketConstructionParameters {
+                count: 9,
+                dissolve_delay_interval_seconds: 10,
+            }),
         };
 
         let sns_init_payload = SnsInitPayload::try_from(create_sns_cli_init_config())
@@ -670,6 +705,10 @@ url: https://internetcomputer.org/
             sns_cli_init_config.initial_token_distribution,
             sns_init_payload.initial_token_distribution
         );
+        assert_eq!(
+            sns_cli_init_config.neuron_basket_construction_parameters,
+            sns_init_payload.neuron_basket_construction_parameters
+        );
 
         // Read the test.png file into memory
         let logo_path = sns_cli_init_config.logo.unwrap();
@@ -85,6 +85,12 @@ pub struct Neuron {
     /// that is created at SNS initialization.
     #[prost(uint64, tag = "13")]
     pub voting_power_percentage_multiplier: u64,
+    /// The duration, counted from `created_timestamp_seconds`, during which
+    /// the neuron is vesting. While a neuron is vesting, its dissolve state
+    /// cannot be changed and it cannot be split. Only set for developer
+    /// neurons created at SNS initialization.
+    #[prost(uint64, optional, tag = "14")]
+    pub vesting_period_seconds: ::core::option::Option<u64>,
     /// The neuron's dissolve state, specifying whether the neuron is dissolving,
     /// non-dissolving, or dissolved.
     ///
@@ -207,6 +213,10 @@ pub mod nervous_system_function {
         /// <method_name>(proposal_data: ProposalData) -> Result<String, String>
         #[prost(string, optional, tag = "5")]
         pub validator_method_name: ::core::option::Option<::prost::alloc::string::String>,
+        /// The topic of the proposals that execute this function. If not
+        /// specified, the topic is TOPIC_APPLICATION_BUSINESS_LOGIC.
+        #[prost(enumeration = "super::Topic", optional, tag = "6")]
+        pub topic: ::core::option::Option<i32>,
     }
     #[derive(candid::CandidType, candid::Deserialize)]
     #[cfg_attr(feature = "test", derive(comparable::Comparable))]
@@ -272,6 +282,90 @@ pub struct UpgradeSnsControlledCanister {
 #[cfg_attr(feature = "test", derive(comparable::Comparable))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UpgradeSnsToNextVersion {}
+/// A proposal function that transfers funds held by the SNS governance canister,
+/// i.e. the SNS's treasury, on either the ICP ledger or the SNS ledger.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TransferSnsTreasuryFunds {
+    /// The treasury the funds are transferred from.
+    #[prost(enumeration = "transfer_sns_treasury_funds::TransferFrom", tag = "1")]
+    pub from_treasury: i32,
+    /// The amount to transfer, in e8s. The transfer fee is paid on top of this
+    /// amount by the treasury.
+    #[prost(uint64, tag = "2")]
+    pub amount_e8s: u64,
+    /// An optional memo to use for the transfer.
+    #[prost(uint64, optional, tag = "3")]
+    pub memo: ::core::option::Option<u64>,
+    /// The principal owning the recipient account.
+    #[prost(message, optional, tag = "4")]
+    pub to_principal: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The subaccount of the recipient account. If not set, the default
+    /// subaccount of `to_principal` is used.
+    #[prost(message, optional, tag = "5")]
+    pub to_subaccount: ::core::option::Option<Subaccount>,
+}
+/// Nested message and enum types in `TransferSnsTreasuryFunds`.
+pub mod transfer_sns_treasury_funds {
+    /// Whether to make the transfer from the ICP treasury or from the SNS token
+    /// treasury.
+    #[derive(candid::CandidType, candid::Deserialize)]
+    #[cfg_attr(feature = "test", derive(comparable::Comparable))]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum TransferFrom {
+        Unspecified = 0,
+        IcpTreasury = 1,
+        SnsTokenTreasury = 2,
+    }
+}
+/// A proposal function that makes SNS root the sole controller of a set of
+/// dapp canisters, and registers them with SNS root, so that they can be
+/// upgraded by proposal and are listed among the SNS's canisters.
+///
+/// SNS root must already be one of the controllers of each canister.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegisterDappCanisters {
+    /// The canister IDs to be registered.
+    #[prost(message, repeated, tag = "1")]
+    pub canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+}
+/// A proposal function that hands control of a set of registered dapp
+/// canisters over to a set of principals, and deregisters them from SNS root.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeregisterDappCanisters {
+    /// The canister IDs to be deregistered.
+    #[prost(message, repeated, tag = "1")]
+    pub canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    /// The new controllers of the deregistered canisters.
+    #[prost(message, repeated, tag = "2")]
+    pub new_controllers: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+}
+/// A proposal function that changes the SNS's metadata, i.e., the values
+/// returned by `get_metadata`. Only the populated fields are changed; each of
+/// them must satisfy the same limits as at initialization.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ManageSnsMetadata {
+    /// Url to the dapp controlled by the SNS project.
+    #[prost(string, optional, tag = "1")]
+    pub url: ::core::option::Option<::prost::alloc::string::String>,
+    /// The logo for the SNS project represented as a base64 encoded string.
+    #[prost(string, optional, tag = "2")]
+    pub logo: ::core::option::Option<::prost::alloc::string::String>,
+    /// Name of the SNS project. This may differ from the name of the associated token.
+    #[prost(string, optional, tag = "3")]
+    pub name: ::core::option::Option<::prost::alloc::string::String>,
+    /// Description of the SNS project.
+    #[prost(string, optional, tag = "4")]
+    pub description: ::core::option::Option<::prost::alloc::string::String>,
+}
 /// A proposal is the immutable input of a proposal submission.
 #[derive(candid::CandidType, candid::Deserialize)]
 #[cfg_attr(feature = "test", derive(comparable::Comparable), compare_default)]
@@ -298,7 +392,10 @@ pub struct Proposal {
     ///
     /// See `impl From<&Action> for u64` in src/types.rs for the implementation
     /// of this mapping.
-    #[prost(oneof = "proposal::Action", tags = "4, 5, 6, 7, 8, 9, 10, 11")]
+    #[prost(
+        oneof = "proposal::Action",
+        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15"
+    )]
     pub action: ::core::option::Option<proposal::Action>,
 }
 /// Nested message and enum types in `Proposal`.
@@ -367,6 +464,27 @@ pub mod proposal {
         /// Id = 7.
         #[prost(message, tag = "11")]
         UpgradeSnsToNextVersion(super::UpgradeSnsToNextVersion),
+        /// Transfer funds from the SNS's treasury to an account.
+        ///
+        /// Id = 8.
+        #[prost(message, tag = "12")]
+        TransferSnsTreasuryFunds(super::TransferSnsTreasuryFunds),
+        /// Register dapp canisters with SNS root.
+        ///
+        /// Id = 9.
+        #[prost(message, tag = "13")]
+        RegisterDappCanisters(super::RegisterDappCanisters),
+        /// Deregister dapp canisters from SNS root, handing their control over to
+        /// other principals.
+        ///
+        /// Id = 10.
+        #[prost(message, tag = "14")]
+        DeregisterDappCanisters(super::DeregisterDappCanisters),
+        /// Change the metadata of the SNS.
+        ///
+        /// Id = 11.
+        #[prost(message, tag = "15")]
+        ManageSnsMetadata(super::ManageSnsMetadata),
     }
 }
 #[derive(candid::CandidType, candid::Deserialize)]
@@ -590,6 +708,10 @@ pub struct ProposalData {
     /// parameters can be changed without affecting existing proposals.
     #[prost(uint64, tag = "18")]
     pub wait_for_quiet_deadline_increase_seconds: u64,
+    /// The topic of the proposal, determined by its action when the proposal
+    /// was made.
+    #[prost(enumeration = "Topic", tag = "19")]
+    pub topic: i32,
 }
 /// The nervous system's parameters, which are parameters that can be changed, via proposals,
 /// by each nervous system community.
@@ -711,6 +833,13 @@ pub struct NervousSystemParameters {
     /// probably be pretty confusing.
     #[prost(message, optional, tag = "19")]
     pub voting_rewards_parameters: ::core::option::Option<VotingRewardsParameters>,
+    /// The maximum amount, in e8s, that a single TransferSnsTreasuryFunds proposal
+    /// can transfer, from either treasury.
+    ///
+    /// When this field is not populated, TransferSnsTreasuryFunds proposals are
+    /// rejected.
+    #[prost(uint64, optional, tag = "20")]
+    pub max_treasury_transfer_amount_e8s: ::core::option::Option<u64>,
 }
 #[derive(candid::CandidType, candid::Deserialize)]
 #[cfg_attr(feature = "test", derive(comparable::Comparable))]
@@ -1302,6 +1431,11 @@ pub mod manage_neuron {
             /// The principal for which the neuron should be claimed.
             #[prost(message, optional, tag = "2")]
             pub controller: ::core::option::Option<::ic_base_types::PrincipalId>,
+            /// The dissolve delay of the neuron when it is claimed. Can only be
+            /// set by the swap canister, when it claims the neurons of the
+            /// participants of the decentralization swap.
+            #[prost(uint64, optional, tag = "3")]
+            pub dissolve_delay_seconds: ::core::option::Option<u64>,
         }
         #[derive(candid::CandidType, candid::Deserialize)]
         #[cfg_attr(feature = "test", derive(comparable::Comparable))]
@@ -1624,6 +1758,16 @@ pub struct ListProposals {
     /// If this list is empty, no restriction is applied.
     #[prost(enumeration = "ProposalDecisionStatus", repeated, tag = "5")]
     pub include_status: ::prost::alloc::vec::Vec<i32>,
+    /// A list of topics, specifying that only proposals that belong to one
+    /// of the given topics should be included in the list.
+    /// If this list is empty, no restriction is applied.
+    #[prost(enumeration = "Topic", repeated, tag = "6")]
+    pub include_topics: ::prost::alloc::vec::Vec<i32>,
+    /// A list of neuron IDs, specifying that only proposals that were made
+    /// by one of the given neurons should be included in the list.
+    /// If this list is empty, no restriction is applied.
+    #[prost(message, repeated, tag = "7")]
+    pub include_proposers: ::prost::alloc::vec::Vec<NeuronId>,
 }
 /// A response to the ListProposals command.
 #[derive(candid::CandidType, candid::Deserialize, Clone, PartialEq, ::prost::Message)]
@@ -1824,3 +1968,40 @@ pub enum ProposalRewardStatus {
     /// the associated rewards have been settled.
     Settled = 3,
 }
+/// The topic of a proposal. Each proposal action belongs to exactly one
+/// topic, which allows, e.g., frontends to filter proposals by topic.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum Topic {
+    /// This exists because proto3 defaults to the 0 value on enums.
+    Unspecified = 0,
+    /// Proposals that set the direction of the SNS but have no direct
+    /// effect, i.e., motions.
+    Governance = 1,
+    /// Proposals that change the settings of the SNS, such as its nervous
+    /// system parameters and its metadata.
+    DaoCommunitySettings = 2,
+    /// Proposals that upgrade the SNS canisters.
+    SnsFrameworkManagement = 3,
+    /// Proposals that upgrade, register or deregister the dapp canisters
+    /// controlled by the SNS.
+    DappCanisterManagement = 4,
+    /// Proposals that execute generic nervous system functions.
+    ApplicationBusinessLogic = 5,
+    /// Proposals that transfer funds out of the SNS treasury.
+    TreasuryAssetManagement = 6,
+    /// Proposals that add or remove generic nervous system functions.
+    CriticalDappOperations = 7,
+}
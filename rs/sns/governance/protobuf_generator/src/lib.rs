@@ -166,6 +166,46 @@ pub fn generate_prost_files(proto: ProtoPaths<'_>, out: &Path) {
         ]
         .join(" "),
     );
+    config.type_attribute(
+        "ic_sns_governance.pb.v1.TransferSnsTreasuryFunds",
+        [
+            "#[derive(candid::CandidType, candid::Deserialize)]",
+            "#[cfg_attr(feature = \"test\", derive(comparable::Comparable))]",
+        ]
+        .join(" "),
+    );
+    config.type_attribute(
+        "ic_sns_governance.pb.v1.TransferSnsTreasuryFunds.TransferFrom",
+        [
+            "#[derive(candid::CandidType, candid::Deserialize)]",
+            "#[cfg_attr(feature = \"test\", derive(comparable::Comparable))]",
+        ]
+        .join(" "),
+    );
+    config.type_attribute(
+        "ic_sns_governance.pb.v1.RegisterDappCanisters",
+        [
+            "#[derive(candid::CandidType, candid::Deserialize)]",
+            "#[cfg_attr(feature = \"test\", derive(comparable::Comparable))]",
+        ]
+        .join(" "),
+    );
+    config.type_attribute(
+        "ic_sns_governance.pb.v1.DeregisterDappCanisters",
+        [
+            "#[derive(candid::CandidType, candid::Deserialize)]",
+            "#[cfg_attr(feature = \"test\", derive(comparable::Comparable))]",
+        ]
+        .join(" "),
+    );
+    config.type_attribute(
+        "ic_sns_governance.pb.v1.ManageSnsMetadata",
+        [
+            "#[derive(candid::CandidType, candid::Deserialize)]",
+            "#[cfg_attr(feature = \"test\", derive(comparable::Comparable))]",
+        ]
+        .join(" "),
+    );
     config.type_attribute(
         "ic_sns_governance.pb.v1.Proposal",
         [
@@ -207,6 +247,10 @@ pub fn generate_prost_files(proto: ProtoPaths<'_>, out: &Path) {
         "ic_sns_governance.pb.v1.ProposalRewardStatus",
         "#[derive(candid::CandidType, candid::Deserialize)]",
     );
+    config.type_attribute(
+        "ic_sns_governance.pb.v1.Topic",
+        "#[derive(candid::CandidType, candid::Deserialize)]",
+    );
     config.type_attribute(
         "ic_sns_governance.pb.v1.Tally",
         [
@@ -17,6 +17,7 @@ use crate::pb::v1::{
     get_neuron_response, get_proposal_response,
     governance::{
         self, neuron_in_flight_command::Command as InFlightCommand, NeuronInFlightCommand,
+        SnsMetadata,
     },
     governance_error::ErrorType,
     manage_neuron::{
@@ -25,14 +26,15 @@ use crate::pb::v1::{
         ClaimOrRefresh,
     },
     neuron::{DissolveState, Followees},
-    proposal, Ballot, DefaultFollowees, Empty, GetMetadataRequest, GetMetadataResponse, GetNeuron,
-    GetNeuronResponse, GetProposal, GetProposalResponse, Governance as GovernanceProto,
-    GovernanceError, ListNervousSystemFunctionsResponse, ListNeurons, ListNeuronsResponse,
-    ListProposals, ListProposalsResponse, ManageNeuron, ManageNeuronResponse,
-    NervousSystemParameters, Neuron, NeuronId, NeuronPermission, NeuronPermissionList,
-    NeuronPermissionType, Proposal, ProposalData, ProposalDecisionStatus, ProposalId,
-    ProposalRewardStatus, RewardEvent, Tally, UpgradeSnsControlledCanister,
-    UpgradeSnsToNextVersion, Vote,
+    proposal, Ballot, DefaultFollowees, DeregisterDappCanisters, Empty, GetMetadataRequest,
+    GetMetadataResponse, GetNeuron, GetNeuronResponse, GetProposal, GetProposalResponse,
+    Governance as GovernanceProto, GovernanceError, ListNervousSystemFunctionsResponse,
+    ListNeurons, ListNeuronsResponse, ListProposals, ListProposalsResponse, ManageNeuron,
+    ManageNeuronResponse, ManageSnsMetadata, NervousSystemParameters, Neuron, NeuronId,
+    NeuronPermission, NeuronPermissionList, NeuronPermissionType, Proposal, ProposalData,
+    ProposalDecisionStatus, ProposalId, ProposalRewardStatus, RegisterDappCanisters, RewardEvent,
+    Tally, Topic, TransferSnsTreasuryFunds, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion,
+    Vote,
 };
 use ic_base_types::PrincipalId;
 use ic_icrc1::{Account, Subaccount};
@@ -56,6 +58,7 @@ use crate::pb::v1::{
     manage_neuron::{AddNeuronPermissions, RemoveNeuronPermissions},
     manage_neuron_response::{DisburseMaturityResponse, MergeMaturityResponse},
     proposal::Action,
+    transfer_sns_treasury_funds::TransferFrom,
     ExecuteGenericNervousSystemFunction, NervousSystemFunction, WaitForQuietState,
 };
 use crate::proposal::{
@@ -65,10 +68,13 @@ use crate::proposal::{
 
 use crate::sns_upgrade::{
     get_all_sns_canisters, get_current_version, get_next_version,
-    get_upgrade_target_canister_id_and_wasm,
+    get_upgrade_target_canister_id_and_wasm, CanisterIds, RegisterDappCanistersRequest,
+    SetDappControllersRequest, SetDappControllersResponse,
 };
-use crate::types::{is_registered_function_id, Environment, HeapGrowthPotential, LedgerUpdateLock};
-use candid::Encode;
+use crate::types::{
+    is_registered_function_id, topic_of_action, Environment, HeapGrowthPotential, LedgerUpdateLock,
+};
+use candid::{Decode, Encode};
 use dfn_core::api::{id, spawn, CanisterId};
 use ic_nervous_system_common::{ledger, NervousSystemError};
 use ic_nervous_system_root::ChangeCanisterProposal;
@@ -87,6 +93,10 @@ lazy_static! {
 /// this limit, the payload will not be returned in the reply.
 pub const EXECUTE_NERVOUS_SYSTEM_FUNCTION_PAYLOAD_LISTING_BYTES_MAX: usize = 1000; // 1 KB
 
+/// The nonce of the governance subaccount holding the SNS token treasury on the
+/// SNS ledger. `sns init` uses it when creating the treasury distribution.
+pub const TREASURY_SUBACCOUNT_NONCE: u64 = 0;
+
 const MAX_HEAP_SIZE_IN_KIB: usize = 4 * 1024 * 1024;
 const WASM32_PAGE_SIZE_IN_KIB: usize = 64;
 
@@ -523,6 +533,9 @@ pub struct Governance {
     /// Implementation of the interface with the SNS ledger canister.
     ledger: Box<dyn Ledger>,
 
+    /// Implementation of the interface with the NNS (ICP) ledger canister.
+    nns_ledger: Box<dyn Ledger>,
+
     /// Cached data structure that (for each proposal function_id) maps a followee to
     /// the set of its followers. It is the inverse of the mapping from follower
     /// to followees that is stored in each (follower) neuron.
@@ -578,6 +591,7 @@ impl Governance {
         proto: ValidGovernanceProto,
         env: Box<dyn Environment>,
         ledger: Box<dyn Ledger>,
+        nns_ledger: Box<dyn Ledger>,
     ) -> Self {
         let mut proto = proto.into_inner();
 
@@ -609,6 +623,7 @@ impl Governance {
             proto,
             env,
             ledger,
+            nns_ledger,
             function_followee_index: BTreeMap::new(),
             principal_to_neuron_ids_index: BTreeMap::new(),
             closest_proposal_deadline_timestamp_seconds: 0,
@@ -980,6 +995,9 @@ impl Governance {
         let neuron = self.get_neuron_result(id)?;
 
         neuron.check_authorized(caller, NeuronPermissionType::Disburse)?;
+        // A vesting neuron may have no dissolve delay, but its stake is
+        // locked until the end of the vesting period.
+        neuron.check_not_vesting(self.env.now())?;
 
         let state = neuron.state(self.env.now());
         if state != NeuronState::Dissolved {
@@ -1127,6 +1145,9 @@ impl Governance {
         let parent_nid = parent_neuron.id.as_ref().expect("Neurons must have an id");
 
         parent_neuron.check_authorized(caller, NeuronPermissionType::Split)?;
+        // The child neuron does not inherit the vesting period, so
+        // vesting neurons cannot be split.
+        parent_neuron.check_not_vesting(self.env.now())?;
 
         if split.amount_e8s < min_stake + transaction_fee_e8s {
             return Err(GovernanceError::new_with_message(
@@ -1185,6 +1206,7 @@ impl Governance {
             maturity_e8s_equivalent: 0,
             dissolve_state: parent_neuron.dissolve_state.clone(),
             voting_power_percentage_multiplier: parent_neuron.voting_power_percentage_multiplier,
+            vesting_period_seconds: None,
         };
 
         // Add the child neuron's id to the set of neurons with ongoing operations.
@@ -1528,16 +1550,33 @@ impl Governance {
             proposal: new_proposal,
             proposal_creation_timestamp_seconds: data.proposal_creation_timestamp_seconds,
             ballots: BTreeMap::new(), // To reduce size of payload, exclude ballots
+            topic: self.proposal_topic(data) as i32,
             ..data.clone()
         }
     }
 
+    /// Returns the topic of the given proposal.
+    ///
+    /// Proposals made before topics were introduced do not record their
+    /// topic, in which case it is derived from the proposal's action.
+    fn proposal_topic(&self, data: &ProposalData) -> Topic {
+        match data.topic() {
+            Topic::Unspecified => {
+                topic_of_action(data.action, &self.proto.id_to_nervous_system_functions)
+            }
+            topic => topic,
+        }
+    }
+
     /// Returns proposal data of proposals with proposal ID less
     /// than `before_proposal` (exclusive), returning at most `limit` proposal
     /// data. If `before_proposal` is not provided, list_proposals() starts from the highest
     /// available proposal ID (inclusive). If `limit` is not provided, the
     /// system max MAX_LIST_PROPOSAL_RESULTS is used.
     ///
+    /// Proposals can additionally be filtered by action type, status, reward
+    /// status, topic and proposer; an empty filter does not exclude anything.
+    ///
     /// As proposal IDs are assigned sequentially, this retrieves up to
     /// `limit` proposals older (in terms of creation) than a specific
     /// proposal. This can be used to paginate through proposals, as follows:
@@ -1564,6 +1603,12 @@ impl Governance {
         let include_reward_status: HashSet<i32> =
             req.include_reward_status.iter().cloned().collect();
         let include_status: HashSet<i32> = req.include_status.iter().cloned().collect();
+        let include_topics: HashSet<i32> = req.include_topics.iter().cloned().collect();
+        let include_proposers: HashSet<String> = req
+            .include_proposers
+            .iter()
+            .map(|neuron_id| neuron_id.to_string())
+            .collect();
         let now = self.env.now();
         let filter_all = |data: &ProposalData| -> bool {
             let action = data.action;
@@ -1581,6 +1626,20 @@ impl Governance {
             if !(include_status.is_empty() || include_status.contains(&(data.status() as i32))) {
                 return false;
             }
+            // Filter out proposals by topic.
+            if !(include_topics.is_empty()
+                || include_topics.contains(&(self.proposal_topic(data) as i32)))
+            {
+                return false;
+            }
+            // Filter out proposals by proposer.
+            if !(include_proposers.is_empty()
+                || data.proposer.as_ref().map_or(false, |proposer| {
+                    include_proposers.contains(&proposer.to_string())
+                }))
+            {
+                return false;
+            }
 
             true
         };
@@ -1811,6 +1870,18 @@ impl Governance {
             proposal::Action::RemoveGenericNervousSystemFunction(id) => {
                 self.perform_remove_generic_nervous_system_function(id)
             }
+            proposal::Action::TransferSnsTreasuryFunds(transfer) => {
+                self.perform_transfer_sns_treasury_funds(&transfer).await
+            }
+            proposal::Action::RegisterDappCanisters(register) => {
+                self.perform_register_dapp_canisters(register).await
+            }
+            proposal::Action::DeregisterDappCanisters(deregister) => {
+                self.perform_deregister_dapp_canisters(deregister).await
+            }
+            proposal::Action::ManageSnsMetadata(manage_sns_metadata) => {
+                self.perform_manage_sns_metadata(manage_sns_metadata)
+            }
             // This should not be possible, because Proposal validation is performed when
             // a proposal is first made.
             proposal::Action::Unspecified(_) => Err(GovernanceError::new_with_message(
@@ -1948,6 +2019,204 @@ impl Governance {
         }
     }
 
+    /// Executes a TransferSnsTreasuryFunds proposal by transferring funds from the
+    /// ICP treasury (the governance canister's default account on the ICP ledger)
+    /// or from the SNS token treasury (the treasury subaccount of the governance
+    /// canister on the SNS ledger) to the target account.
+    async fn perform_transfer_sns_treasury_funds(
+        &mut self,
+        transfer: &TransferSnsTreasuryFunds,
+    ) -> Result<(), GovernanceError> {
+        // The cap is checked again, as the parameters might have changed since the
+        // proposal was made.
+        let max_amount_e8s = self
+            .nervous_system_parameters()
+            .max_treasury_transfer_amount_e8s
+            .unwrap_or(0);
+        if transfer.amount_e8s > max_amount_e8s {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "Cannot transfer {} e8s from a treasury: the maximum amount a single \
+                     proposal can transfer is {} e8s.",
+                    transfer.amount_e8s, max_amount_e8s
+                ),
+            ));
+        }
+
+        let to = Account {
+            owner: transfer.to_principal.ok_or_else(|| {
+                GovernanceError::new_with_message(
+                    ErrorType::InvalidProposal,
+                    "TransferSnsTreasuryFunds must have a to_principal.",
+                )
+            })?,
+            subaccount: transfer
+                .to_subaccount
+                .as_ref()
+                .map(|s| {
+                    Subaccount::try_from(&s.subaccount[..]).map_err(|_| {
+                        GovernanceError::new_with_message(
+                            ErrorType::InvalidProposal,
+                            "TransferSnsTreasuryFunds.to_subaccount must be 32 bytes long.",
+                        )
+                    })
+                })
+                .transpose()?,
+        };
+        let memo = transfer.memo.unwrap_or(0);
+
+        match TransferFrom::from_i32(transfer.from_treasury) {
+            Some(TransferFrom::IcpTreasury) => self
+                .nns_ledger
+                .transfer_funds(
+                    transfer.amount_e8s,
+                    ledger_canister::DEFAULT_TRANSFER_FEE.get_e8s(),
+                    None,
+                    to,
+                    memo,
+                )
+                .await
+                .map(|_| ())
+                .map_err(GovernanceError::from),
+            Some(TransferFrom::SnsTokenTreasury) => {
+                let treasury_subaccount = ledger::compute_distribution_subaccount_bytes(
+                    self.env.canister_id().get(),
+                    TREASURY_SUBACCOUNT_NONCE,
+                );
+                self.ledger
+                    .transfer_funds(
+                        transfer.amount_e8s,
+                        self.transaction_fee_e8s(),
+                        Some(treasury_subaccount),
+                        to,
+                        memo,
+                    )
+                    .await
+                    .map(|_| ())
+                    .map_err(GovernanceError::from)
+            }
+            Some(TransferFrom::Unspecified) | None => Err(GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                format!(
+                    "TransferSnsTreasuryFunds has an invalid from_treasury: {}",
+                    transfer.from_treasury
+                ),
+            )),
+        }
+    }
+
+    /// Executes a ManageSnsMetadata proposal by updating the populated fields of
+    /// the SNS metadata. The new values were validated when the proposal was made.
+    fn perform_manage_sns_metadata(
+        &mut self,
+        manage_sns_metadata: ManageSnsMetadata,
+    ) -> Result<(), GovernanceError> {
+        let sns_metadata = self
+            .proto
+            .sns_metadata
+            .get_or_insert_with(SnsMetadata::default);
+
+        let ManageSnsMetadata {
+            url,
+            logo,
+            name,
+            description,
+        } = manage_sns_metadata;
+        if url.is_some() {
+            sns_metadata.url = url;
+        }
+        if logo.is_some() {
+            sns_metadata.logo = logo;
+        }
+        if name.is_some() {
+            sns_metadata.name = name;
+        }
+        if description.is_some() {
+            sns_metadata.description = description;
+        }
+
+        Ok(())
+    }
+
+    /// Executes a RegisterDappCanisters proposal by calling the root canister to
+    /// register the dapp canisters. SNS root verifies that it is a controller of
+    /// each canister, and then becomes their sole controller.
+    async fn perform_register_dapp_canisters(
+        &self,
+        register: RegisterDappCanisters,
+    ) -> Result<(), GovernanceError> {
+        let payload = Encode!(&RegisterDappCanistersRequest {
+            canister_ids: register.canister_ids,
+        })
+        .unwrap();
+
+        self.env
+            .call_canister(
+                self.proto.root_canister_id_or_panic(),
+                "register_dapp_canisters",
+                payload,
+            )
+            .await
+            // Convert to return type.
+            .map(|_reply| ())
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Canister method call failed: {:?}", err),
+                )
+            })
+    }
+
+    /// Executes a DeregisterDappCanisters proposal by calling the root canister to
+    /// set the controllers of the dapp canisters to the new controllers, which
+    /// deregisters them (unless SNS root is one of the new controllers).
+    async fn perform_deregister_dapp_canisters(
+        &self,
+        deregister: DeregisterDappCanisters,
+    ) -> Result<(), GovernanceError> {
+        let payload = Encode!(&SetDappControllersRequest {
+            canister_ids: Some(CanisterIds {
+                canister_ids: deregister.canister_ids,
+            }),
+            controller_principal_ids: deregister.new_controllers,
+        })
+        .unwrap();
+
+        let reply = self
+            .env
+            .call_canister(
+                self.proto.root_canister_id_or_panic(),
+                "set_dapp_controllers",
+                payload,
+            )
+            .await
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Canister method call failed: {:?}", err),
+                )
+            })?;
+
+        let response = Decode!(&reply, SetDappControllersResponse).map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Could not decode SetDappControllersResponse: {:?}", err),
+            )
+        })?;
+        if !response.failed_updates.is_empty() {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::External,
+                format!(
+                    "Unable to set the controllers of some dapp canisters: {:?}",
+                    response.failed_updates
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Executes a UpgradeSnsControlledCanister proposal by calling the root canister
     /// to upgrade an SNS controlled canister.  This does not upgrade "core" SNS canisters
     /// (i.e. Root, Governance, Ledger, Ledger Archives, or Sale)
@@ -2335,6 +2604,10 @@ impl Governance {
             is_eligible_for_rewards,
             initial_voting_period_seconds,
             wait_for_quiet_deadline_increase_seconds,
+            topic: topic_of_action(
+                u64::from(action),
+                &self.proto.id_to_nervous_system_functions,
+            ) as i32,
             // Writing these explicitly so that we have to make a consious decision
             // about what to do when adding a new field to `ProposalData`.
             latest_tally: ProposalData::default().latest_tally,
@@ -2744,6 +3017,9 @@ impl Governance {
     /// and the given memo.
     /// If the neuron id exists, the neuron is refreshed and if the neuron id
     /// does not yet exist, the neuron is claimed.
+    ///
+    /// Only the swap canister may specify the dissolve delay of a neuron
+    /// being claimed; it is capped at the maximum dissolve delay.
     async fn claim_or_refresh_neuron_by_memo_and_controller(
         &mut self,
         caller: &PrincipalId,
@@ -2751,6 +3027,23 @@ impl Governance {
     ) -> Result<(), GovernanceError> {
         let controller = memo_and_controller.controller.unwrap_or(*caller);
         let memo = memo_and_controller.memo;
+        let dissolve_delay_seconds = match memo_and_controller.dissolve_delay_seconds {
+            None => 0,
+            Some(dissolve_delay_seconds) => {
+                if !self.is_swap_canister(*caller) {
+                    return Err(GovernanceError::new_with_message(
+                        ErrorType::NotAuthorized,
+                        "Only the swap canister may claim a neuron with a dissolve delay.",
+                    ));
+                }
+                std::cmp::min(
+                    dissolve_delay_seconds,
+                    self.nervous_system_parameters()
+                        .max_dissolve_delay_seconds
+                        .expect("NervousSystemParameters must have max_dissolve_delay_seconds"),
+                )
+            }
+        };
         let nid = NeuronId::from(ledger::compute_neuron_staking_subaccount_bytes(
             controller, memo,
         ));
@@ -2759,7 +3052,10 @@ impl Governance {
                 let nid = neuron.id.as_ref().expect("Neuron must have an id").clone();
                 self.refresh_neuron(&nid).await
             }
-            Err(_) => self.claim_neuron(nid, &controller).await,
+            Err(_) => {
+                self.claim_neuron(nid, &controller, dissolve_delay_seconds)
+                    .await
+            }
         }
     }
 
@@ -2837,10 +3133,12 @@ impl Governance {
     /// * `neuron_id` ID of the neuron being claimed/created.
     /// * `principal_id` ID to whom default permissions will be granted for the new neuron
     ///   being claimed/created.
+    /// * `dissolve_delay_seconds` The dissolve delay of the new neuron.
     async fn claim_neuron(
         &mut self,
         neuron_id: NeuronId,
         principal_id: &PrincipalId,
+        dissolve_delay_seconds: u64,
     ) -> Result<(), GovernanceError> {
         let now = self.env.now();
 
@@ -2862,10 +3160,11 @@ impl Governance {
             aging_since_timestamp_seconds: now,
             followees: self.default_followees().followees,
             maturity_e8s_equivalent: 0,
-            dissolve_state: Some(DissolveState::DissolveDelaySeconds(0)),
+            dissolve_state: Some(DissolveState::DissolveDelaySeconds(dissolve_delay_seconds)),
             // A neuron created through the `claim_or_refresh` ManageNeuron command will
             // have the default voting power multiplier applied.
             voting_power_percentage_multiplier: DEFAULT_VOTING_POWER_PERCENTAGE_MULTIPLIER,
+            vesting_period_seconds: None,
         };
 
         // This also verifies that there are not too many neurons already.
@@ -3774,10 +4073,10 @@ fn get_neuron_id_from_memo_and_controller(
 mod tests {
     use super::*;
     use crate::sns_upgrade::{
-        CanisterSummary, GetNextSnsVersionRequest, GetNextSnsVersionResponse,
-        GetSnsCanistersSummaryRequest, GetSnsCanistersSummaryResponse, GetWasmRequest,
-        GetWasmResponse, ListSnsCanistersRequest, ListSnsCanistersResponse, SnsCanisterType,
-        SnsVersion, SnsWasm,
+        CanisterCallError, CanisterSummary, FailedUpdate, GetNextSnsVersionRequest,
+        GetNextSnsVersionResponse, GetSnsCanistersSummaryRequest, GetSnsCanistersSummaryResponse,
+        GetWasmRequest, GetWasmResponse, ListSnsCanistersRequest, ListSnsCanistersResponse,
+        RegisterDappCanistersResponse, SnsCanisterType, SnsVersion, SnsWasm,
     };
     use crate::{
         pb::v1::{
@@ -3789,13 +4088,14 @@ mod tests {
             WaitForQuietState,
         },
         tests::assert_is_ok,
-        types::test_helpers::NativeEnvironment,
+        types::{test_helpers::NativeEnvironment, ONE_MONTH_SECONDS},
     };
     use async_trait::async_trait;
     use ic_base_types::NumBytes;
     use ic_canister_client_sender::Sender;
     use ic_ic00_types::{
         CanisterIdRecord, CanisterInstallMode, CanisterStatusResultV2, CanisterStatusType,
+        QueryStatsResult,
     };
     use ic_nervous_system_common_test_keys::TEST_USER1_KEYPAIR;
     use ic_nns_constants::SNS_WASM_CANISTER_ID;
@@ -3999,6 +4299,7 @@ mod tests {
                         transfer_funds_arrived: transfer_funds_arrived.clone(),
                         transfer_funds_continue: transfer_funds_continue.clone(),
                     }),
+                    Box::new(DoNothingLedger {}),
                 );
 
                 // Step 2: Execute code under test.
@@ -4154,6 +4455,7 @@ mod tests {
                         target_method_name: Some("test_method".to_string()),
                         validator_canister_id: Some(CanisterId::from_u64(1).get()),
                         validator_method_name: Some("test_validator_method".to_string()),
+                        topic: None,
                     },
                 )),
             },
@@ -4188,6 +4490,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_vesting_neuron_cannot_change_dissolve_state() {
+        let created_timestamp_seconds = 1_000;
+        let vesting_period_seconds = 100;
+        let mut neuron = Neuron {
+            id: Some(NeuronId { id: vec![1] }),
+            created_timestamp_seconds,
+            aging_since_timestamp_seconds: created_timestamp_seconds,
+            dissolve_state: Some(DissolveState::DissolveDelaySeconds(ONE_MONTH_SECONDS)),
+            vesting_period_seconds: Some(vesting_period_seconds),
+            ..Default::default()
+        };
+        let start_dissolving = manage_neuron::Configure {
+            operation: Some(manage_neuron::configure::Operation::StartDissolving(
+                manage_neuron::StartDissolving {},
+            )),
+        };
+        let increase_dissolve_delay = manage_neuron::Configure {
+            operation: Some(manage_neuron::configure::Operation::IncreaseDissolveDelay(
+                manage_neuron::IncreaseDissolveDelay {
+                    additional_dissolve_delay_seconds: 1,
+                },
+            )),
+        };
+
+        // While vesting, the dissolve state cannot be changed.
+        let vesting_end_seconds = created_timestamp_seconds + vesting_period_seconds;
+        assert!(neuron.is_vesting(vesting_end_seconds - 1));
+        for configure in [&start_dissolving, &increase_dissolve_delay] {
+            let err = neuron
+                .configure(vesting_end_seconds - 1, configure, u64::MAX)
+                .unwrap_err();
+            assert_eq!(err.error_type, ErrorType::PreconditionFailed as i32);
+        }
+        assert_eq!(
+            neuron.dissolve_state,
+            Some(DissolveState::DissolveDelaySeconds(ONE_MONTH_SECONDS))
+        );
+
+        // Once the vesting period is over, the neuron behaves normally.
+        assert!(!neuron.is_vesting(vesting_end_seconds));
+        neuron
+            .configure(vesting_end_seconds, &start_dissolving, u64::MAX)
+            .unwrap();
+        assert_eq!(
+            neuron.dissolve_state,
+            Some(DissolveState::WhenDissolvedTimestampSeconds(
+                vesting_end_seconds + ONE_MONTH_SECONDS
+            ))
+        );
+    }
+
     #[test]
     fn test_governance_proto_neurons_voting_power_multiplier_in_expected_range() {
         let mut proto = basic_governance_proto();
@@ -4428,6 +4782,7 @@ mod tests {
             Some(0),
             0,
             0,
+            QueryStatsResult::default(),
         )
     }
 
@@ -4472,6 +4827,7 @@ mod tests {
             .unwrap(),
             Box::new(NativeEnvironment::default()),
             Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
         );
         let swap_canister_id = governance.proto.swap_canister_id_or_panic();
 
@@ -4516,6 +4872,7 @@ mod tests {
             .unwrap(),
             Box::new(NativeEnvironment::default()),
             Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
         );
 
         // Step 2: Run code under test.
@@ -4604,6 +4961,7 @@ mod tests {
             .unwrap(),
             Box::new(NativeEnvironment::default()),
             Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
         );
 
         // Step 2: Execute code under test.
@@ -4928,6 +5286,7 @@ mod tests {
             .unwrap(),
             Box::new(env),
             Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
         );
 
         // When we execute the proposal
@@ -5027,6 +5386,7 @@ mod tests {
             .unwrap(),
             Box::new(env),
             Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
         );
 
         // Helper function to assert failures.
@@ -5064,6 +5424,453 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_manage_sns_metadata_only_changes_populated_fields() {
+        let mut governance = Governance::new(
+            basic_governance_proto().try_into().unwrap(),
+            Box::new(NativeEnvironment::default()),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+        );
+        let original_metadata = governance.get_metadata(&GetMetadataRequest {});
+
+        governance
+            .perform_manage_sns_metadata(ManageSnsMetadata {
+                url: Some("https://www.example.com".to_string()),
+                name: Some("Rebranded Project".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(
+            governance.get_metadata(&GetMetadataRequest {}),
+            GetMetadataResponse {
+                url: Some("https://www.example.com".to_string()),
+                name: Some("Rebranded Project".to_string()),
+                ..original_metadata
+            }
+        );
+    }
+
+    #[test]
+    fn test_register_and_deregister_dapp_canisters_call_root() {
+        use ProposalDecisionStatus as Status;
+
+        let root_canister_id = canister_test_id(500);
+        let governance_canister_id = canister_test_id(501);
+        let dapp_canister_id = canister_test_id(600);
+        let new_controller = PrincipalId::new_user_test_id(700);
+
+        let create_proposal = |id: u64, action: Action| {
+            let proposal = ProposalData {
+                action: (&action).into(),
+                id: Some(id.into()),
+                ballots: btreemap! {
+                    "neuron 1".to_string() => Ballot {
+                        vote: Vote::Yes as i32,
+                        voting_power: 9001,
+                        cast_timestamp_seconds: 1,
+                    },
+                },
+                wait_for_quiet_state: Some(WaitForQuietState::default()),
+                proposal: Some(Proposal {
+                    title: "Dapp Canisters Proposal".to_string(),
+                    action: Some(action),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            assert_eq!(proposal.status(), Status::Open);
+
+            proposal
+        };
+        let register_proposal = create_proposal(
+            1,
+            Action::RegisterDappCanisters(RegisterDappCanisters {
+                canister_ids: vec![dapp_canister_id.get()],
+            }),
+        );
+        let deregister_proposal = create_proposal(
+            2,
+            Action::DeregisterDappCanisters(DeregisterDappCanisters {
+                canister_ids: vec![dapp_canister_id.get()],
+                new_controllers: vec![new_controller],
+            }),
+        );
+
+        // Root is called with the canisters referenced by the proposals. The
+        // deregistration partially fails.
+        let mut env = NativeEnvironment::new(Some(governance_canister_id));
+        env.require_call_canister_invocation(
+            root_canister_id,
+            "register_dapp_canisters",
+            Encode!(&RegisterDappCanistersRequest {
+                canister_ids: vec![dapp_canister_id.get()],
+            })
+            .unwrap(),
+            Some(Ok(Encode!(&RegisterDappCanistersResponse {}).unwrap())),
+        );
+        env.require_call_canister_invocation(
+            root_canister_id,
+            "set_dapp_controllers",
+            Encode!(&SetDappControllersRequest {
+                canister_ids: Some(CanisterIds {
+                    canister_ids: vec![dapp_canister_id.get()],
+                }),
+                controller_principal_ids: vec![new_controller],
+            })
+            .unwrap(),
+            Some(Ok(Encode!(&SetDappControllersResponse {
+                failed_updates: vec![FailedUpdate {
+                    dapp_canister_id: Some(dapp_canister_id.get()),
+                    err: Some(CanisterCallError {
+                        code: Some(1),
+                        description: "Out of cycles".to_string(),
+                    }),
+                }],
+            })
+            .unwrap())),
+        );
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                proposals: btreemap! {
+                    1 => register_proposal,
+                    2 => deregister_proposal,
+                },
+                root_canister_id: Some(root_canister_id.get()),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+        );
+
+        assert_eq!(
+            execute_proposal(&mut governance, 1).status(),
+            Status::Executed
+        );
+        let deregister_result = execute_proposal(&mut governance, 2);
+        assert_eq!(deregister_result.status(), Status::Failed);
+        assert_eq!(
+            deregister_result.failure_reason.unwrap().error_type,
+            ErrorType::External as i32,
+        );
+    }
+
+    /// A transfer made through the `Ledger` trait.
+    #[derive(Clone, Debug, PartialEq)]
+    struct LedgerTransfer {
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to: Account,
+        memo: u64,
+    }
+
+    /// A ledger that records the transfers made through it.
+    #[derive(Default)]
+    struct RecordingLedger {
+        transfers: Arc<std::sync::Mutex<Vec<LedgerTransfer>>>,
+    }
+
+    #[async_trait]
+    impl Ledger for RecordingLedger {
+        async fn transfer_funds(
+            &self,
+            amount_e8s: u64,
+            fee_e8s: u64,
+            from_subaccount: Option<Subaccount>,
+            to: Account,
+            memo: u64,
+        ) -> Result<u64, NervousSystemError> {
+            let mut transfers = self.transfers.lock().unwrap();
+            transfers.push(LedgerTransfer {
+                amount_e8s,
+                fee_e8s,
+                from_subaccount,
+                to,
+                memo,
+            });
+            Ok(transfers.len() as u64)
+        }
+
+        async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
+            unimplemented!()
+        }
+
+        async fn account_balance(&self, _account: Account) -> Result<Tokens, NervousSystemError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_sns_treasury_funds_transfers_from_the_treasury() {
+        let governance_canister_id = canister_test_id(501);
+        let recipient = PrincipalId::new_user_test_id(700);
+        let recipient_subaccount = [7; 32];
+
+        let ledger = RecordingLedger::default();
+        let ledger_transfers = Arc::clone(&ledger.transfers);
+        let nns_ledger = RecordingLedger::default();
+        let nns_ledger_transfers = Arc::clone(&nns_ledger.transfers);
+
+        let mut governance_proto = basic_governance_proto();
+        let parameters = governance_proto.parameters.as_mut().unwrap();
+        parameters.max_treasury_transfer_amount_e8s = Some(1_000 * E8);
+        let sns_transaction_fee_e8s = parameters.transaction_fee_e8s.unwrap();
+        let mut governance = Governance::new(
+            governance_proto.try_into().unwrap(),
+            Box::new(NativeEnvironment::new(Some(governance_canister_id))),
+            Box::new(ledger),
+            Box::new(nns_ledger),
+        );
+
+        // Transfer SNS tokens from the treasury subaccount.
+        governance
+            .perform_transfer_sns_treasury_funds(&TransferSnsTreasuryFunds {
+                from_treasury: TransferFrom::SnsTokenTreasury as i32,
+                amount_e8s: 500 * E8,
+                memo: Some(42),
+                to_principal: Some(recipient),
+                to_subaccount: Some(crate::pb::v1::Subaccount {
+                    subaccount: recipient_subaccount.to_vec(),
+                }),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            *ledger_transfers.lock().unwrap(),
+            vec![LedgerTransfer {
+                amount_e8s: 500 * E8,
+                fee_e8s: sns_transaction_fee_e8s,
+                from_subaccount: Some(ledger::compute_distribution_subaccount_bytes(
+                    governance_canister_id.get(),
+                    TREASURY_SUBACCOUNT_NONCE,
+                )),
+                to: Account {
+                    owner: recipient,
+                    subaccount: Some(recipient_subaccount),
+                },
+                memo: 42,
+            }]
+        );
+        assert!(nns_ledger_transfers.lock().unwrap().is_empty());
+
+        // Transfer ICP from the default account of governance.
+        governance
+            .perform_transfer_sns_treasury_funds(&TransferSnsTreasuryFunds {
+                from_treasury: TransferFrom::IcpTreasury as i32,
+                amount_e8s: 3 * E8,
+                memo: None,
+                to_principal: Some(recipient),
+                to_subaccount: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            *nns_ledger_transfers.lock().unwrap(),
+            vec![LedgerTransfer {
+                amount_e8s: 3 * E8,
+                fee_e8s: ledger_canister::DEFAULT_TRANSFER_FEE.get_e8s(),
+                from_subaccount: None,
+                to: Account {
+                    owner: recipient,
+                    subaccount: None,
+                },
+                memo: 0,
+            }]
+        );
+        assert_eq!(ledger_transfers.lock().unwrap().len(), 1);
+
+        // Amounts above the cap are refused without calling the ledgers.
+        assert!(governance
+            .perform_transfer_sns_treasury_funds(&TransferSnsTreasuryFunds {
+                from_treasury: TransferFrom::SnsTokenTreasury as i32,
+                amount_e8s: 1_001 * E8,
+                memo: None,
+                to_principal: Some(recipient),
+                to_subaccount: None,
+            })
+            .await
+            .is_err());
+        assert_eq!(ledger_transfers.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_list_proposals_filters_by_topic_and_proposer() {
+        use crate::types::native_action_ids::{
+            MOTION, REGISTER_DAPP_CANISTERS, TRANSFER_SNS_TREASURY_FUNDS,
+        };
+
+        let proposer_a = NeuronId { id: vec![1; 32] };
+        let proposer_b = NeuronId { id: vec![2; 32] };
+        let proposal =
+            |id: u64, action: u64, topic: Topic, proposer: &NeuronId, decided: bool| ProposalData {
+                id: Some(ProposalId { id }),
+                action,
+                topic: topic as i32,
+                proposer: Some(proposer.clone()),
+                decided_timestamp_seconds: if decided { 1 } else { 0 },
+                ..Default::default()
+            };
+        let governance = Governance::new(
+            GovernanceProto {
+                proposals: btreemap! {
+                    1 => proposal(1, MOTION, Topic::Governance, &proposer_a, false),
+                    2 => proposal(
+                        2,
+                        TRANSFER_SNS_TREASURY_FUNDS,
+                        Topic::TreasuryAssetManagement,
+                        &proposer_b,
+                        false,
+                    ),
+                    // Proposals made before topics were recorded get the
+                    // topic of their action.
+                    3 => proposal(3, MOTION, Topic::Unspecified, &proposer_b, true),
+                    4 => proposal(
+                        4,
+                        REGISTER_DAPP_CANISTERS,
+                        Topic::DappCanisterManagement,
+                        &proposer_a,
+                        false,
+                    ),
+                },
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(NativeEnvironment::default()),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+        );
+        let list = |request: ListProposals| -> Vec<u64> {
+            governance
+                .list_proposals(&request)
+                .proposals
+                .iter()
+                .map(|proposal| proposal.id.as_ref().unwrap().id)
+                .collect()
+        };
+
+        assert_eq!(list(ListProposals::default()), vec![4, 3, 2, 1]);
+        assert_eq!(
+            list(ListProposals {
+                include_topics: vec![Topic::Governance as i32],
+                ..Default::default()
+            }),
+            vec![3, 1]
+        );
+        assert_eq!(
+            list(ListProposals {
+                include_proposers: vec![proposer_a.clone()],
+                ..Default::default()
+            }),
+            vec![4, 1]
+        );
+        // Both filters must match.
+        assert_eq!(
+            list(ListProposals {
+                include_topics: vec![Topic::Governance as i32],
+                include_proposers: vec![proposer_b.clone()],
+                ..Default::default()
+            }),
+            vec![3]
+        );
+        // The filters combine with the other ones.
+        assert_eq!(
+            list(ListProposals {
+                include_topics: vec![Topic::Governance as i32],
+                include_proposers: vec![proposer_b.clone()],
+                include_status: vec![ProposalDecisionStatus::Open as i32],
+                ..Default::default()
+            }),
+            Vec::<u64>::new()
+        );
+        assert_eq!(
+            list(ListProposals {
+                include_topics: vec![
+                    Topic::Governance as i32,
+                    Topic::TreasuryAssetManagement as i32
+                ],
+                exclude_type: vec![MOTION],
+                ..Default::default()
+            }),
+            vec![2]
+        );
+        assert_eq!(
+            list(ListProposals {
+                include_proposers: vec![proposer_a, proposer_b],
+                before_proposal: Some(ProposalId { id: 4 }),
+                limit: 1,
+                ..Default::default()
+            }),
+            vec![3]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_vesting_neuron_cannot_be_disbursed() {
+        let controller = PrincipalId::new_user_test_id(1);
+        let now = NativeEnvironment::default().now();
+        let dissolved_neuron = |id: u8, vesting_period_seconds: u64| Neuron {
+            id: Some(NeuronId { id: vec![id; 32] }),
+            permissions: vec![NeuronPermission::all(&controller)],
+            cached_neuron_stake_e8s: 10 * E8,
+            created_timestamp_seconds: now - 10,
+            dissolve_state: Some(DissolveState::WhenDissolvedTimestampSeconds(0)),
+            vesting_period_seconds: Some(vesting_period_seconds),
+            ..Default::default()
+        };
+        let vesting = dissolved_neuron(1, ONE_MONTH_SECONDS);
+        let vested = dissolved_neuron(2, 1);
+
+        let ledger = RecordingLedger::default();
+        let ledger_transfers = Arc::clone(&ledger.transfers);
+        let mut governance = Governance::new(
+            GovernanceProto {
+                neurons: btreemap! {
+                    vesting.id.as_ref().unwrap().to_string() => vesting.clone(),
+                    vested.id.as_ref().unwrap().to_string() => vested.clone(),
+                },
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(NativeEnvironment::default()),
+            Box::new(ledger),
+            Box::new(DoNothingLedger {}),
+        );
+        let disburse = manage_neuron::Disburse {
+            amount: None,
+            to_account: None,
+        };
+
+        let err = governance
+            .disburse_neuron(vesting.id.as_ref().unwrap(), &controller, &disburse)
+            .await
+            .unwrap_err();
+        assert_eq!(err.error_type, ErrorType::PreconditionFailed as i32);
+        assert!(err.error_message.contains("vesting"), "{:?}", err);
+        assert!(ledger_transfers.lock().unwrap().is_empty());
+        assert_eq!(
+            governance
+                .get_neuron_result(vesting.id.as_ref().unwrap())
+                .unwrap()
+                .cached_neuron_stake_e8s,
+            10 * E8
+        );
+
+        // Once the vesting period is over, the neuron can be disbursed.
+        governance
+            .disburse_neuron(vested.id.as_ref().unwrap(), &controller, &disburse)
+            .await
+            .unwrap();
+        assert_eq!(ledger_transfers.lock().unwrap().len(), 1);
+    }
+
     #[test]
     fn test_allow_canister_upgrades_while_motion_proposal_execution_is_in_progress() {
         // Step 1: Prepare the world.
@@ -416,15 +416,55 @@ impl Neuron {
         }
     }
 
+    /// Returns true if the neuron is still in its vesting period, i.e.,
+    /// if `vesting_period_seconds` is set and has not yet elapsed since
+    /// the neuron was created.
+    ///
+    /// Vesting locks the neuron's stake: a vesting neuron cannot change its
+    /// dissolve state, be split or be disbursed. Its maturity is not locked.
+    pub fn is_vesting(&self, now_seconds: u64) -> bool {
+        match self.vesting_period_seconds {
+            Some(vesting_period_seconds) => {
+                self.created_timestamp_seconds
+                    .saturating_add(vesting_period_seconds)
+                    > now_seconds
+            }
+            None => false,
+        }
+    }
+
+    /// Returns an error if the neuron is vesting.
+    pub fn check_not_vesting(&self, now_seconds: u64) -> Result<(), GovernanceError> {
+        if self.is_vesting(now_seconds) {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "Neuron {} is vesting until {}.",
+                    self.id
+                        .as_ref()
+                        .map(|id| id.to_string())
+                        .unwrap_or_default(),
+                    self.created_timestamp_seconds
+                        .saturating_add(self.vesting_period_seconds.unwrap_or_default())
+                ),
+            ));
+        }
+        Ok(())
+    }
+
     /// Apply the specified neuron configuration operation on this neuron.
     ///
     /// See [manage_neuron::Configure] for details.
+    ///
+    /// The dissolve state of a vesting neuron cannot be changed.
     pub fn configure(
         &mut self,
         now_seconds: u64,
         cmd: &manage_neuron::Configure,
         max_dissolve_delay_seconds: u64,
     ) -> Result<(), GovernanceError> {
+        self.check_not_vesting(now_seconds)?;
+
         let op = &cmd.operation.as_ref().ok_or_else(|| {
             GovernanceError::new_with_message(
                 ErrorType::InvalidCommand,
@@ -2,10 +2,13 @@ use crate::canister_control::perform_execute_generic_nervous_system_function_val
 use crate::governance::{log_prefix, NERVOUS_SYSTEM_FUNCTION_DELETION_MARKER};
 use crate::pb::v1::nervous_system_function::{FunctionType, GenericNervousSystemFunction};
 use crate::pb::v1::proposal::Action;
+use crate::pb::v1::transfer_sns_treasury_funds::TransferFrom;
 use crate::pb::v1::{
-    governance, proposal, ExecuteGenericNervousSystemFunction, Motion, NervousSystemFunction,
-    NervousSystemParameters, Proposal, ProposalData, ProposalDecisionStatus, ProposalRewardStatus,
-    Tally, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
+    governance::{self, SnsMetadata},
+    proposal, DeregisterDappCanisters, ExecuteGenericNervousSystemFunction, ManageSnsMetadata,
+    Motion, NervousSystemFunction, NervousSystemParameters, Proposal, ProposalData,
+    ProposalDecisionStatus, ProposalRewardStatus, RegisterDappCanisters, Tally, Topic,
+    TransferSnsTreasuryFunds, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
 };
 use crate::sns_upgrade::{
     canister_type_and_wasm_hash_for_upgrade, get_all_sns_canisters, get_canister_to_upgrade,
@@ -13,10 +16,12 @@ use crate::sns_upgrade::{
 };
 use crate::types::Environment;
 use crate::{validate_chars_count, validate_len, validate_required_field};
+use candid::Encode;
 use dfn_core::api::CanisterId;
 use ic_base_types::PrincipalId;
 use ic_crypto_sha::Sha256;
-use std::collections::BTreeMap;
+use ic_nervous_system_root::CanisterIdRecord;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 
 /// The maximum number of bytes in an SNS proposal's title.
@@ -27,6 +32,9 @@ pub const PROPOSAL_SUMMARY_BYTES_MAX: usize = 15000;
 pub const PROPOSAL_URL_CHAR_MAX: usize = 2048;
 /// The maximum number of bytes in an SNS motion proposal's motion_text.
 pub const PROPOSAL_MOTION_TEXT_BYTES_MAX: usize = 10000;
+/// The maximum number of canisters that a single RegisterDappCanisters or
+/// DeregisterDappCanisters proposal can reference.
+pub const MAX_NUMBER_OF_DAPP_CANISTERS_PER_PROPOSAL: usize = 100;
 
 /// The minimum number of votes a proposal must have at the end of the voting period to be
 /// adopted with a plurality of the voting power submitted rather than a majority of the
@@ -189,6 +197,18 @@ pub async fn validate_and_render_action(
             validate_and_render_execute_nervous_system_function(env, execute, existing_functions)
                 .await
         }
+        proposal::Action::TransferSnsTreasuryFunds(transfer) => {
+            validate_and_render_transfer_sns_treasury_funds(transfer, current_parameters)
+        }
+        proposal::Action::RegisterDappCanisters(register) => {
+            validate_and_render_register_dapp_canisters(register, env, root_canister_id).await
+        }
+        proposal::Action::DeregisterDappCanisters(deregister) => {
+            validate_and_render_deregister_dapp_canisters(deregister, root_canister_id)
+        }
+        proposal::Action::ManageSnsMetadata(manage_sns_metadata) => {
+            validate_and_render_manage_sns_metadata(manage_sns_metadata)
+        }
     }
 }
 
@@ -233,6 +253,299 @@ fn validate_and_render_manage_nervous_system_parameters(
     ))
 }
 
+/// Validates and renders a proposal with action TransferSnsTreasuryFunds.
+fn validate_and_render_transfer_sns_treasury_funds(
+    transfer: &TransferSnsTreasuryFunds,
+    current_parameters: &NervousSystemParameters,
+) -> Result<String, String> {
+    let mut defects = vec![];
+
+    let from_treasury = match TransferFrom::from_i32(transfer.from_treasury) {
+        Some(TransferFrom::IcpTreasury) => "ICP",
+        Some(TransferFrom::SnsTokenTreasury) => "SNS token",
+        Some(TransferFrom::Unspecified) | None => {
+            defects.push(format!(
+                "Must specify a treasury to transfer from, but from_treasury was {}.",
+                transfer.from_treasury
+            ));
+            ""
+        }
+    };
+
+    if transfer.amount_e8s == 0 {
+        defects.push("amount_e8s must be greater than 0.".to_string());
+    }
+    match current_parameters.max_treasury_transfer_amount_e8s {
+        None => defects.push(
+            "Treasury transfers are disabled because the nervous system parameter \
+             max_treasury_transfer_amount_e8s is not set."
+                .to_string(),
+        ),
+        Some(max_amount_e8s) => {
+            if transfer.amount_e8s > max_amount_e8s {
+                defects.push(format!(
+                    "amount_e8s ({}) exceeds the maximum amount a single proposal can transfer \
+                     from a treasury ({}).",
+                    transfer.amount_e8s, max_amount_e8s
+                ));
+            }
+        }
+    }
+
+    if let Err(err) = validate_required_field("to_principal", &transfer.to_principal) {
+        defects.push(err);
+    }
+    if let Some(subaccount) = &transfer.to_subaccount {
+        if subaccount.subaccount.len() != 32 {
+            defects.push(format!(
+                "to_subaccount must be 32 bytes long, but was {} bytes long.",
+                subaccount.subaccount.len()
+            ));
+        }
+    }
+
+    if !defects.is_empty() {
+        return Err(format!(
+            "TransferSnsTreasuryFunds was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    let to_subaccount = match &transfer.to_subaccount {
+        Some(subaccount) => hex::encode(&subaccount.subaccount),
+        None => "(default)".to_string(),
+    };
+    let memo = match transfer.memo {
+        Some(memo) => memo.to_string(),
+        None => "(none)".to_string(),
+    };
+
+    Ok(format!(
+        r"# Proposal to transfer SNS treasury funds:
+
+## Source treasury: {}
+
+## Amount (e8s): {}
+
+## Target principal: {}
+
+## Target subaccount: {}
+
+## Memo: {}",
+        from_treasury,
+        transfer.amount_e8s,
+        transfer.to_principal.unwrap(),
+        to_subaccount,
+        memo
+    ))
+}
+
+/// Returns the defects of a list of dapp canister IDs referenced by a
+/// RegisterDappCanisters or DeregisterDappCanisters proposal.
+///
+/// Whether SNS root controls (or has registered) the canisters is checked
+/// separately, as it requires calling SNS root.
+fn dapp_canister_ids_defects(
+    canister_ids: &[PrincipalId],
+    root_canister_id: CanisterId,
+) -> Vec<String> {
+    let mut defects = vec![];
+
+    if canister_ids.is_empty() {
+        defects.push("canister_ids must not be empty.".to_string());
+    }
+    if canister_ids.len() > MAX_NUMBER_OF_DAPP_CANISTERS_PER_PROPOSAL {
+        defects.push(format!(
+            "canister_ids must not contain more than {} canisters, but contained {}.",
+            MAX_NUMBER_OF_DAPP_CANISTERS_PER_PROPOSAL,
+            canister_ids.len()
+        ));
+    }
+
+    let mut seen = BTreeSet::new();
+    for canister_id in canister_ids {
+        if *canister_id == root_canister_id.get() {
+            defects.push(format!("{} is the SNS root canister.", canister_id));
+        }
+        if !seen.insert(*canister_id) {
+            defects.push(format!("{} is listed more than once.", canister_id));
+        }
+    }
+
+    defects
+}
+
+fn render_principal_ids(principal_ids: &[PrincipalId]) -> String {
+    principal_ids
+        .iter()
+        .map(|principal_id| format!("- {}", principal_id))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the canisters among `canister_ids` that SNS root does not
+/// control, as determined by asking SNS root for their status, which the
+/// management canister only reveals to their controllers.
+async fn canisters_not_controlled_by_root_defects(
+    canister_ids: &[PrincipalId],
+    env: &dyn Environment,
+    root_canister_id: CanisterId,
+) -> Vec<String> {
+    let mut defects = vec![];
+    for canister_id in canister_ids {
+        let canister_id = match CanisterId::new(*canister_id) {
+            Ok(canister_id) => canister_id,
+            Err(err) => {
+                defects.push(format!("{} is not a canister ID: {:?}", canister_id, err));
+                continue;
+            }
+        };
+        let arg = Encode!(&CanisterIdRecord::from(canister_id)).unwrap();
+        if let Err(err) = env
+            .call_canister(root_canister_id, "canister_status", arg)
+            .await
+        {
+            defects.push(format!(
+                "SNS root is not a controller of {}: {:?}",
+                canister_id, err
+            ));
+        }
+    }
+    defects
+}
+
+/// Validates and renders a proposal with action RegisterDappCanisters.
+///
+/// SNS root must already control the canisters, which is also checked by SNS
+/// root when the proposal is executed.
+async fn validate_and_render_register_dapp_canisters(
+    register: &RegisterDappCanisters,
+    env: &dyn Environment,
+    root_canister_id: CanisterId,
+) -> Result<String, String> {
+    let mut defects = dapp_canister_ids_defects(&register.canister_ids, root_canister_id);
+    // The canisters are only queried if the proposal is otherwise valid, so
+    // that a proposal cannot make governance send an unbounded number of
+    // calls.
+    if defects.is_empty() {
+        defects.extend(
+            canisters_not_controlled_by_root_defects(&register.canister_ids, env, root_canister_id)
+                .await,
+        );
+    }
+
+    if !defects.is_empty() {
+        return Err(format!(
+            "RegisterDappCanisters was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    Ok(format!(
+        r"# Proposal to register dapp canisters:
+
+SNS root ({}) must already be a controller of each canister, and will become its sole controller.
+
+## Canisters to be registered:
+{}",
+        root_canister_id,
+        render_principal_ids(&register.canister_ids),
+    ))
+}
+
+/// Validates and renders a proposal with action DeregisterDappCanisters.
+fn validate_and_render_deregister_dapp_canisters(
+    deregister: &DeregisterDappCanisters,
+    root_canister_id: CanisterId,
+) -> Result<String, String> {
+    let mut defects = dapp_canister_ids_defects(&deregister.canister_ids, root_canister_id);
+
+    if deregister.new_controllers.is_empty() {
+        defects.push("new_controllers must not be empty.".to_string());
+    }
+
+    if !defects.is_empty() {
+        return Err(format!(
+            "DeregisterDappCanisters was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    Ok(format!(
+        r"# Proposal to deregister dapp canisters:
+
+## Canisters to be deregistered:
+{}
+
+## New controllers:
+{}",
+        render_principal_ids(&deregister.canister_ids),
+        render_principal_ids(&deregister.new_controllers),
+    ))
+}
+
+/// Validates and renders a proposal with action ManageSnsMetadata.
+///
+/// Each populated field is validated the same way as the corresponding field
+/// of the SnsMetadata set at initialization.
+fn validate_and_render_manage_sns_metadata(
+    manage_sns_metadata: &ManageSnsMetadata,
+) -> Result<String, String> {
+    let mut defects = vec![];
+    let mut render = "# Proposal to change the SNS metadata:\n".to_string();
+    let mut no_change = true;
+
+    if let Some(url) = &manage_sns_metadata.url {
+        no_change = false;
+        match SnsMetadata::validate_url(url) {
+            Ok(_) => render += &format!("## New url: {}\n", url),
+            Err(err) => defects.push(err),
+        }
+    }
+    if let Some(logo) = &manage_sns_metadata.logo {
+        no_change = false;
+        match SnsMetadata::validate_logo(logo) {
+            // The logo can be large, so only its hash is rendered.
+            Ok(_) => {
+                render += &format!(
+                    "## New logo (SHA-256): {}\n",
+                    hex::encode(Sha256::hash(logo.as_bytes()))
+                )
+            }
+            Err(err) => defects.push(err),
+        }
+    }
+    if let Some(name) = &manage_sns_metadata.name {
+        no_change = false;
+        match SnsMetadata::validate_name(name) {
+            Ok(_) => render += &format!("## New name: {}\n", name),
+            Err(err) => defects.push(err),
+        }
+    }
+    if let Some(description) = &manage_sns_metadata.description {
+        no_change = false;
+        match SnsMetadata::validate_description(description) {
+            Ok(_) => render += &format!("## New description: {}\n", description),
+            Err(err) => defects.push(err),
+        }
+    }
+
+    if no_change {
+        defects.push(
+            "ManageSnsMetadata must change at least one value, all values are None".to_string(),
+        );
+    }
+
+    if !defects.is_empty() {
+        return Err(format!(
+            "ManageSnsMetadata was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    Ok(render)
+}
+
 /// Validates and renders a proposal with action UpgradeSnsControlledCanister.
 fn validate_and_render_upgrade_sns_controlled_canister(
     upgrade: &UpgradeSnsControlledCanister,
@@ -445,6 +758,7 @@ impl TryFrom<&NervousSystemFunction> for ValidGenericNervousSystemFunction {
                 target_method_name,
                 validator_canister_id,
                 validator_method_name,
+                topic,
             })) => {
                 // Validate the target_canister_id field.
                 let target_canister_id =
@@ -468,6 +782,16 @@ impl TryFrom<&NervousSystemFunction> for ValidGenericNervousSystemFunction {
                     defects.push("validator_method_name was empty.".to_string());
                 }
 
+                // Validate the topic field, if it was given.
+                if let Some(topic) = topic {
+                    match Topic::from_i32(*topic) {
+                        None | Some(Topic::Unspecified) => {
+                            defects.push(format!("topic {} is not a valid topic.", topic));
+                        }
+                        Some(_) => (),
+                    }
+                }
+
                 if !defects.is_empty() {
                     return Err(format!(
                         "ExecuteNervousSystemFunction was invalid for the following reason(s):\n{}",
@@ -882,7 +1206,7 @@ mod tests {
             SnsWasm,
         },
         tests::{assert_is_err, assert_is_ok},
-        types::test_helpers::NativeEnvironment,
+        types::{test_helpers::NativeEnvironment, E8S_PER_TOKEN},
     };
     use candid::Encode;
     use futures::FutureExt;
@@ -891,6 +1215,7 @@ mod tests {
     use ic_crypto_sha::Sha256;
     use ic_ic00_types::CanisterStatusResultV2;
     use ic_ic00_types::CanisterStatusType;
+    use ic_ic00_types::QueryStatsResult;
     use ic_nns_constants::SNS_WASM_CANISTER_ID;
     use ic_test_utilities::types::ids::canister_test_id;
     use lazy_static::lazy_static;
@@ -1131,6 +1456,295 @@ mod tests {
         assert_validate_upgrade_sns_controlled_canister_is_err(&proposal);
     }
 
+    fn basic_transfer_sns_treasury_funds() -> TransferSnsTreasuryFunds {
+        let transfer = TransferSnsTreasuryFunds {
+            from_treasury: TransferFrom::SnsTokenTreasury as i32,
+            amount_e8s: 1_000 * E8S_PER_TOKEN,
+            memo: Some(42),
+            to_principal: Some(basic_principal_id()),
+            to_subaccount: None,
+        };
+        assert_is_ok(validate_and_render_transfer_sns_treasury_funds(
+            &transfer,
+            &DEFAULT_PARAMS,
+        ));
+        transfer
+    }
+
+    #[test]
+    fn transfer_sns_treasury_funds_renders_correctly() {
+        let mut transfer = basic_transfer_sns_treasury_funds();
+        transfer.from_treasury = TransferFrom::IcpTreasury as i32;
+        transfer.to_subaccount = Some(crate::pb::v1::Subaccount {
+            subaccount: vec![1; 32],
+        });
+
+        let rendered =
+            validate_and_render_transfer_sns_treasury_funds(&transfer, &DEFAULT_PARAMS).unwrap();
+
+        assert_eq!(
+            rendered,
+            format!(
+                r"# Proposal to transfer SNS treasury funds:
+
+## Source treasury: ICP
+
+## Amount (e8s): {}
+
+## Target principal: {}
+
+## Target subaccount: {}
+
+## Memo: 42",
+                1_000 * E8S_PER_TOKEN,
+                basic_principal_id(),
+                hex::encode(vec![1; 32]),
+            )
+        );
+        assert_is_ok(validate_default_action(&Some(
+            proposal::Action::TransferSnsTreasuryFunds(transfer),
+        )));
+    }
+
+    #[test]
+    fn transfer_sns_treasury_funds_must_be_well_formed() {
+        let defects: Vec<fn(&mut TransferSnsTreasuryFunds)> = vec![
+            |transfer| transfer.from_treasury = TransferFrom::Unspecified as i32,
+            |transfer| transfer.from_treasury = 1_000,
+            |transfer| transfer.amount_e8s = 0,
+            |transfer| transfer.to_principal = None,
+            |transfer| {
+                transfer.to_subaccount = Some(crate::pb::v1::Subaccount {
+                    subaccount: vec![1; 31],
+                })
+            },
+        ];
+
+        for create_defect in defects {
+            let mut transfer = basic_transfer_sns_treasury_funds();
+            create_defect(&mut transfer);
+
+            assert_is_err(validate_and_render_transfer_sns_treasury_funds(
+                &transfer,
+                &DEFAULT_PARAMS,
+            ));
+            assert_is_err(validate_default_action(&Some(
+                proposal::Action::TransferSnsTreasuryFunds(transfer),
+            )));
+        }
+    }
+
+    #[test]
+    fn transfer_sns_treasury_funds_cannot_exceed_max_amount() {
+        let mut transfer = basic_transfer_sns_treasury_funds();
+        let max_amount_e8s = DEFAULT_PARAMS.max_treasury_transfer_amount_e8s.unwrap();
+
+        transfer.amount_e8s = max_amount_e8s;
+        assert_is_ok(validate_and_render_transfer_sns_treasury_funds(
+            &transfer,
+            &DEFAULT_PARAMS,
+        ));
+
+        transfer.amount_e8s = max_amount_e8s + 1;
+        let err = validate_and_render_transfer_sns_treasury_funds(&transfer, &DEFAULT_PARAMS)
+            .unwrap_err();
+        assert!(err.contains("exceeds the maximum amount"), "{}", err);
+    }
+
+    #[test]
+    fn transfer_sns_treasury_funds_is_rejected_when_max_amount_is_not_set() {
+        let transfer = basic_transfer_sns_treasury_funds();
+        let parameters = NervousSystemParameters {
+            max_treasury_transfer_amount_e8s: None,
+            ..DEFAULT_PARAMS.clone()
+        };
+
+        let err =
+            validate_and_render_transfer_sns_treasury_funds(&transfer, &parameters).unwrap_err();
+        assert!(err.contains("max_treasury_transfer_amount_e8s"), "{}", err);
+    }
+
+    #[test]
+    fn manage_sns_metadata_renders_the_changed_values() {
+        let manage_sns_metadata = ManageSnsMetadata {
+            url: Some("https://www.example.com".to_string()),
+            logo: None,
+            name: Some("Example Project".to_string()),
+            description: None,
+        };
+
+        let rendering = validate_and_render_manage_sns_metadata(&manage_sns_metadata).unwrap();
+        assert_eq!(
+            rendering,
+            "# Proposal to change the SNS metadata:\n\
+             ## New url: https://www.example.com\n\
+             ## New name: Example Project\n"
+        );
+        assert_is_ok(validate_default_action(&Some(
+            proposal::Action::ManageSnsMetadata(manage_sns_metadata),
+        )));
+    }
+
+    #[test]
+    fn manage_sns_metadata_must_be_well_formed() {
+        let defects = vec![
+            // Nothing to change.
+            ManageSnsMetadata::default(),
+            ManageSnsMetadata {
+                url: Some("X".repeat(SnsMetadata::MAX_URL_LENGTH + 1)),
+                ..Default::default()
+            },
+            ManageSnsMetadata {
+                url: Some("X".repeat(SnsMetadata::MIN_URL_LENGTH - 1)),
+                ..Default::default()
+            },
+            ManageSnsMetadata {
+                logo: Some("X".repeat(SnsMetadata::MAX_LOGO_LENGTH + 1)),
+                ..Default::default()
+            },
+            ManageSnsMetadata {
+                name: Some("X".repeat(SnsMetadata::MIN_NAME_LENGTH - 1)),
+                ..Default::default()
+            },
+            ManageSnsMetadata {
+                description: Some("X".repeat(SnsMetadata::MAX_DESCRIPTION_LENGTH + 1)),
+                ..Default::default()
+            },
+        ];
+
+        for manage_sns_metadata in defects {
+            assert_is_err(validate_and_render_manage_sns_metadata(
+                &manage_sns_metadata,
+            ));
+            assert_is_err(validate_default_action(&Some(
+                proposal::Action::ManageSnsMetadata(manage_sns_metadata),
+            )));
+        }
+    }
+
+    #[test]
+    fn register_dapp_canisters_renders_the_canisters() {
+        let register = RegisterDappCanisters {
+            canister_ids: vec![CanisterId::from_u64(1).get(), CanisterId::from_u64(2).get()],
+        };
+
+        let rendering = validate_and_render_register_dapp_canisters(
+            &register,
+            &**FAKE_ENV,
+            CanisterId::ic_00(),
+        )
+        .now_or_never()
+        .unwrap()
+        .unwrap();
+        assert!(
+            rendering.contains(&format!("- {}", CanisterId::from_u64(1))),
+            "{}",
+            rendering
+        );
+        assert!(
+            rendering.contains(&format!("- {}", CanisterId::from_u64(2))),
+            "{}",
+            rendering
+        );
+        assert_is_ok(validate_default_action(&Some(
+            proposal::Action::RegisterDappCanisters(register),
+        )));
+    }
+
+    #[test]
+    fn register_dapp_canisters_must_be_well_formed() {
+        let root_canister_id = CanisterId::from_u64(9);
+        let defects: Vec<Vec<PrincipalId>> = vec![
+            vec![],
+            vec![CanisterId::from_u64(1).get(), CanisterId::from_u64(1).get()],
+            vec![root_canister_id.get()],
+            (0..=MAX_NUMBER_OF_DAPP_CANISTERS_PER_PROPOSAL as u64)
+                .map(|i| CanisterId::from_u64(i).get())
+                .collect(),
+        ];
+
+        for canister_ids in defects {
+            let register = RegisterDappCanisters { canister_ids };
+            assert_is_err(
+                validate_and_render_register_dapp_canisters(
+                    &register,
+                    &**FAKE_ENV,
+                    root_canister_id,
+                )
+                .now_or_never()
+                .unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn register_dapp_canisters_must_be_controlled_by_root() {
+        let root_canister_id = CanisterId::from_u64(9);
+        let controlled = CanisterId::from_u64(1);
+        let uncontrolled = CanisterId::from_u64(2);
+        let mut env = NativeEnvironment::default();
+        env.require_call_canister_invocation(
+            root_canister_id,
+            "canister_status",
+            Encode!(&CanisterIdRecord::from(controlled)).unwrap(),
+            Some(Ok(vec![])),
+        );
+        env.require_call_canister_invocation(
+            root_canister_id,
+            "canister_status",
+            Encode!(&CanisterIdRecord::from(uncontrolled)).unwrap(),
+            Some(Err((
+                Some(5),
+                "Only the controllers of the canister can get its status".to_string(),
+            ))),
+        );
+        let register = RegisterDappCanisters {
+            canister_ids: vec![controlled.get(), uncontrolled.get()],
+        };
+
+        let err = validate_and_render_register_dapp_canisters(&register, &env, root_canister_id)
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+
+        assert!(
+            err.contains(&format!("SNS root is not a controller of {}", uncontrolled)),
+            "{}",
+            err
+        );
+        assert!(
+            !err.contains(&format!("controller of {}", controlled)),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn deregister_dapp_canisters_must_be_well_formed() {
+        let basic = DeregisterDappCanisters {
+            canister_ids: vec![CanisterId::from_u64(1).get()],
+            new_controllers: vec![basic_principal_id()],
+        };
+        assert_is_ok(validate_default_action(&Some(
+            proposal::Action::DeregisterDappCanisters(basic.clone()),
+        )));
+
+        let defects: Vec<fn(&mut DeregisterDappCanisters)> = vec![
+            |deregister| deregister.canister_ids = vec![],
+            |deregister| deregister.new_controllers = vec![],
+            |deregister| deregister.canister_ids.push(deregister.canister_ids[0]),
+        ];
+
+        for create_defect in defects {
+            let mut deregister = basic.clone();
+            create_defect(&mut deregister);
+
+            assert_is_err(validate_default_action(&Some(
+                proposal::Action::DeregisterDappCanisters(deregister),
+            )));
+        }
+    }
+
     fn basic_add_nervous_system_function_proposal() -> Proposal {
         let nervous_system_function = NervousSystemFunction {
             id: 1000,
@@ -1142,6 +1756,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from_u64(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -1289,6 +1904,30 @@ mod tests {
             _ => panic!("Proposal.action is not AddGenericNervousSystemFunction"),
         }
 
+        // Make sure setting an unspecified or unknown topic is invalid.
+        for topic in [Topic::Unspecified as i32, 1000] {
+            match proposal.clone().action.as_mut().unwrap() {
+                proposal::Action::AddGenericNervousSystemFunction(nervous_system_function) => {
+                    match nervous_system_function.function_type.as_mut() {
+                        Some(FunctionType::GenericNervousSystemFunction(
+                            GenericNervousSystemFunction {
+                                topic: function_topic,
+                                ..
+                            },
+                        )) => {
+                            *function_topic = Some(topic);
+                        }
+                        _ => panic!("FunctionType is not GenericNervousSystemFunction"),
+                    }
+                    assert_is_err(validate_and_render_add_generic_nervous_system_function(
+                        nervous_system_function,
+                        &EMPTY_FUNCTIONS,
+                    ));
+                }
+                _ => panic!("Proposal.action is not AddGenericNervousSystemFunction"),
+            }
+        }
+
         // Make sure not setting the validator method name is invalid.
         match proposal.action.as_mut().unwrap() {
             proposal::Action::AddGenericNervousSystemFunction(nervous_system_function) => {
@@ -1324,6 +1963,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from_u64(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -1365,6 +2005,7 @@ mod tests {
                         target_method_name: Some("test_method".to_string()),
                         validator_canister_id: Some(CanisterId::from_u64(i as u64).get()),
                         validator_method_name: Some("test_validator_method".to_string()),
+                        topic: None,
                     },
                 )),
             };
@@ -1381,6 +2022,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from_u64(u64::MAX).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -1408,6 +2050,7 @@ mod tests {
             Some(0),
             0,
             0,
+            QueryStatsResult::default(),
         )
     }
 
@@ -298,6 +298,49 @@ pub(crate) struct CanisterSummary {
     pub status: Option<CanisterStatusResultV2>,
 }
 
+/// Copied from ic-sns-root
+#[derive(PartialEq, Eq, Debug, candid::CandidType, candid::Deserialize)]
+pub(crate) struct RegisterDappCanistersRequest {
+    pub canister_ids: Vec<PrincipalId>,
+}
+
+/// Copied from ic-sns-root
+#[derive(PartialEq, Eq, Debug, candid::CandidType, candid::Deserialize)]
+pub(crate) struct RegisterDappCanistersResponse {}
+
+/// Copied from ic-sns-root
+#[derive(PartialEq, Eq, Debug, candid::CandidType, candid::Deserialize)]
+pub(crate) struct SetDappControllersRequest {
+    pub canister_ids: Option<CanisterIds>,
+    pub controller_principal_ids: Vec<PrincipalId>,
+}
+
+/// Copied from ic-sns-root
+#[derive(PartialEq, Eq, Debug, candid::CandidType, candid::Deserialize)]
+pub(crate) struct CanisterIds {
+    pub canister_ids: Vec<PrincipalId>,
+}
+
+/// Copied from ic-sns-root
+#[derive(PartialEq, Eq, Debug, candid::CandidType, candid::Deserialize)]
+pub(crate) struct SetDappControllersResponse {
+    pub failed_updates: Vec<FailedUpdate>,
+}
+
+/// Copied from ic-sns-root
+#[derive(PartialEq, Eq, Debug, candid::CandidType, candid::Deserialize)]
+pub(crate) struct FailedUpdate {
+    pub dapp_canister_id: Option<PrincipalId>,
+    pub err: Option<CanisterCallError>,
+}
+
+/// Copied from ic-sns-root
+#[derive(PartialEq, Eq, Debug, candid::CandidType, candid::Deserialize)]
+pub(crate) struct CanisterCallError {
+    pub code: Option<i32>,
+    pub description: String,
+}
+
 ///Copied from ic-sns-wasm.
 /// The argument for get_wasm, which consists of the WASM hash to be retrieved.
 #[derive(candid::CandidType, candid::Deserialize, Clone, PartialEq, ::prost::Message)]
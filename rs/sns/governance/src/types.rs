@@ -9,7 +9,7 @@ use crate::{
         proposal::Action,
         DefaultFollowees, Empty, ExecuteGenericNervousSystemFunction, GovernanceError,
         ManageNeuronResponse, NervousSystemFunction, NervousSystemParameters, NeuronId,
-        NeuronPermissionList, NeuronPermissionType, ProposalId, RewardEvent, Vote,
+        NeuronPermissionList, NeuronPermissionType, ProposalId, RewardEvent, Topic, Vote,
     },
     proposal::ValidGenericNervousSystemFunction,
 };
@@ -66,6 +66,18 @@ pub mod native_action_ids {
 
     /// UpgradeSnsToNextVersion Action.
     pub const UPGRADE_SNS_TO_NEXT_VERSION: u64 = 7;
+
+    /// TransferSnsTreasuryFunds Action.
+    pub const TRANSFER_SNS_TREASURY_FUNDS: u64 = 8;
+
+    /// RegisterDappCanisters Action.
+    pub const REGISTER_DAPP_CANISTERS: u64 = 9;
+
+    /// DeregisterDappCanisters Action.
+    pub const DEREGISTER_DAPP_CANISTERS: u64 = 10;
+
+    /// ManageSnsMetadata Action.
+    pub const MANAGE_SNS_METADATA: u64 = 11;
 }
 
 impl governance::Mode {
@@ -182,6 +194,33 @@ impl governance::Mode {
                 ),
             )),
 
+            Action::TransferSnsTreasuryFunds(_) => Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "TransferSnsTreasuryFunds proposals are not allowed while \
+                         governance is in PreInitializationSwap mode: {:#?}",
+                    action,
+                ),
+            )),
+
+            Action::DeregisterDappCanisters(_) => Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "DeregisterDappCanisters proposals are not allowed while \
+                         governance is in PreInitializationSwap mode: {:#?}",
+                    action,
+                ),
+            )),
+
+            Action::ManageSnsMetadata(_) => Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "ManageSnsMetadata proposals are not allowed while \
+                         governance is in PreInitializationSwap mode: {:#?}",
+                    action,
+                ),
+            )),
+
             _ => Ok(()),
         }
     }
@@ -291,6 +330,9 @@ impl NervousSystemParameters {
     /// hosting the SNS.
     pub const MAX_NUMBER_OF_PRINCIPALS_PER_NEURON_CEILING: u64 = 15;
 
+    /// The default for `max_treasury_transfer_amount_e8s`.
+    pub const DEFAULT_MAX_TREASURY_TRANSFER_AMOUNT_E8S: u64 = 100_000 * E8S_PER_TOKEN;
+
     pub fn with_default_values() -> Self {
         Self {
             reject_cost_e8s: Some(E8S_PER_TOKEN), // 1 governance token
@@ -310,6 +352,7 @@ impl NervousSystemParameters {
             neuron_grantable_permissions: Some(NeuronPermissionList::default()),
             max_number_of_principals_per_neuron: Some(5),
             voting_rewards_parameters: None,
+            max_treasury_transfer_amount_e8s: Some(Self::DEFAULT_MAX_TREASURY_TRANSFER_AMOUNT_E8S),
         }
     }
 
@@ -365,6 +408,9 @@ impl NervousSystemParameters {
             .or(base.max_number_of_principals_per_neuron);
         // No need to manipulate voting_rewards_parameters, because the default
         // is None anyway.
+        new_params.max_treasury_transfer_amount_e8s = self
+            .max_treasury_transfer_amount_e8s
+            .or(base.max_treasury_transfer_amount_e8s);
 
         new_params
     }
@@ -388,6 +434,7 @@ impl NervousSystemParameters {
         self.validate_neuron_grantable_permissions()?;
         self.validate_max_number_of_principals_per_neuron()?;
         self.validate_voting_rewards_parameters(mode)?;
+        self.validate_max_treasury_transfer_amount_e8s()?;
 
         Ok(())
     }
@@ -736,6 +783,19 @@ impl NervousSystemParameters {
             Some(p) => p.is_valid_and_in_normal_mode(mode),
         }
     }
+
+    /// The max_treasury_transfer_amount_e8s is considered valid if it is either
+    /// unpopulated (in which case treasury transfers are disabled), or if it is
+    /// greater than 0.
+    fn validate_max_treasury_transfer_amount_e8s(&self) -> Result<(), String> {
+        match self.max_treasury_transfer_amount_e8s {
+            Some(0) => Err(
+                "NervousSystemParameters.max_treasury_transfer_amount_e8s must be greater than 0"
+                    .to_string(),
+            ),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl GovernanceError {
@@ -964,6 +1024,26 @@ impl SnsMetadata {
     /// Validate the SnsMetadata values
     pub fn validate(&self) -> Result<(), String> {
         let url = self.url.as_ref().ok_or("SnsMetadata.url must be set")?;
+        Self::validate_url(url)?;
+
+        let logo = self.logo.as_ref().ok_or("SnsMetadata.logo must be set")?;
+        Self::validate_logo(logo)?;
+
+        let name = self.name.as_ref().ok_or("SnsMetadata.name must be set")?;
+        Self::validate_name(name)?;
+
+        let description = self
+            .description
+            .as_ref()
+            .ok_or("SnsMetadata.description must be set")?;
+        Self::validate_description(description)?;
+
+        Ok(())
+    }
+
+    /// Validate the SnsMetadata url. Also used to validate ManageSnsMetadata
+    /// proposals.
+    pub fn validate_url(url: &str) -> Result<(), String> {
         if url.len() > Self::MAX_URL_LENGTH {
             return Err(format!(
                 "SnsMetadata.url must be less than {} characters",
@@ -975,16 +1055,24 @@ impl SnsMetadata {
                 Self::MIN_URL_LENGTH
             ));
         }
+        Ok(())
+    }
 
-        let logo = self.logo.as_ref().ok_or("SnsMetadata.logo must be set")?;
+    /// Validate the SnsMetadata logo. Also used to validate ManageSnsMetadata
+    /// proposals.
+    pub fn validate_logo(logo: &str) -> Result<(), String> {
         if logo.len() > Self::MAX_LOGO_LENGTH {
             return Err(format!(
                 "SnsMetadata.logo must be less than {} characters, roughly 256 Kb",
                 Self::MAX_LOGO_LENGTH
             ));
         }
+        Ok(())
+    }
 
-        let name = self.name.as_ref().ok_or("SnsMetadata.name must be set")?;
+    /// Validate the SnsMetadata name. Also used to validate ManageSnsMetadata
+    /// proposals.
+    pub fn validate_name(name: &str) -> Result<(), String> {
         if name.len() > Self::MAX_NAME_LENGTH {
             return Err(format!(
                 "SnsMetadata.name must be less than {} characters",
@@ -996,11 +1084,12 @@ impl SnsMetadata {
                 Self::MIN_NAME_LENGTH
             ));
         }
+        Ok(())
+    }
 
-        let description = self
-            .description
-            .as_ref()
-            .ok_or("SnsMetadata.description must be set")?;
+    /// Validate the SnsMetadata description. Also used to validate
+    /// ManageSnsMetadata proposals.
+    pub fn validate_description(description: &str) -> Result<(), String> {
         if description.len() > Self::MAX_DESCRIPTION_LENGTH {
             return Err(format!(
                 "SnsMetadata.description must be less than {} characters",
@@ -1085,6 +1174,41 @@ impl Action {
                 ),
                 function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
             },
+            NervousSystemFunction {
+                id: native_action_ids::TRANSFER_SNS_TREASURY_FUNDS,
+                name: "Transfer SNS treasury funds".to_string(),
+                description: Some(
+                    "Proposal to transfer funds from an SNS treasury (ICP or SNS token) \
+                     to an account."
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            NervousSystemFunction {
+                id: native_action_ids::REGISTER_DAPP_CANISTERS,
+                name: "Register dapp canisters".to_string(),
+                description: Some("Proposal to register a dapp canister with the SNS.".to_string()),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            NervousSystemFunction {
+                id: native_action_ids::DEREGISTER_DAPP_CANISTERS,
+                name: "Deregister dapp canisters".to_string(),
+                description: Some(
+                    "Proposal to deregister a previously-registered dapp canister from the SNS, \
+                     handing its control over to other principals."
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            NervousSystemFunction {
+                id: native_action_ids::MANAGE_SNS_METADATA,
+                name: "Manage SNS metadata".to_string(),
+                description: Some(
+                    "Proposal to change the metadata of the SNS (logo, url, name, description)."
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
         ]
     }
 
@@ -1102,7 +1226,7 @@ impl Action {
 // Mapping of action to the unique function id of that action.
 //
 // When adding/removing an action here, also add/remove from
-// `Action::native_actions_metadata()`.
+// `Action::native_actions_metadata()` and `topic_of_action`.
 impl From<&Action> for u64 {
     fn from(action: &Action) -> Self {
         match action {
@@ -1115,6 +1239,10 @@ impl From<&Action> for u64 {
                 native_action_ids::UPGRADE_SNS_CONTROLLER_CANISTER
             }
             Action::UpgradeSnsToNextVersion(_) => native_action_ids::UPGRADE_SNS_TO_NEXT_VERSION,
+            Action::TransferSnsTreasuryFunds(_) => native_action_ids::TRANSFER_SNS_TREASURY_FUNDS,
+            Action::RegisterDappCanisters(_) => native_action_ids::REGISTER_DAPP_CANISTERS,
+            Action::DeregisterDappCanisters(_) => native_action_ids::DEREGISTER_DAPP_CANISTERS,
+            Action::ManageSnsMetadata(_) => native_action_ids::MANAGE_SNS_METADATA,
             Action::AddGenericNervousSystemFunction(_) => {
                 native_action_ids::ADD_GENERIC_NERVOUS_SYSTEM_FUNCTION
             }
@@ -1141,6 +1269,43 @@ pub fn is_registered_function_id(
     }
 }
 
+/// Returns the topic of the proposals whose action has the id `action_id`.
+///
+/// Proposals that execute a generic nervous system function belong to the
+/// topic of that function, or to `Topic::ApplicationBusinessLogic` if the
+/// function has no topic or does not exist (anymore).
+pub fn topic_of_action(
+    action_id: u64,
+    nervous_system_functions: &BTreeMap<u64, NervousSystemFunction>,
+) -> Topic {
+    match action_id {
+        native_action_ids::UNSPECIFIED => Topic::Unspecified,
+        native_action_ids::MOTION => Topic::Governance,
+        native_action_ids::MANAGE_NERVOUS_SYSTEM_PARAMETERS
+        | native_action_ids::MANAGE_SNS_METADATA => Topic::DaoCommunitySettings,
+        native_action_ids::UPGRADE_SNS_TO_NEXT_VERSION => Topic::SnsFrameworkManagement,
+        native_action_ids::UPGRADE_SNS_CONTROLLER_CANISTER
+        | native_action_ids::REGISTER_DAPP_CANISTERS
+        | native_action_ids::DEREGISTER_DAPP_CANISTERS => Topic::DappCanisterManagement,
+        native_action_ids::TRANSFER_SNS_TREASURY_FUNDS => Topic::TreasuryAssetManagement,
+        native_action_ids::ADD_GENERIC_NERVOUS_SYSTEM_FUNCTION
+        | native_action_ids::REMOVE_GENERIC_NERVOUS_SYSTEM_FUNCTION => {
+            Topic::CriticalDappOperations
+        }
+        function_id => match nervous_system_functions
+            .get(&function_id)
+            .and_then(|function| function.function_type.as_ref())
+        {
+            Some(FunctionType::GenericNervousSystemFunction(function)) => function
+                .topic
+                .and_then(Topic::from_i32)
+                .filter(|topic| *topic != Topic::Unspecified)
+                .unwrap_or(Topic::ApplicationBusinessLogic),
+            _ => Topic::ApplicationBusinessLogic,
+        },
+    }
+}
+
 /// Summarizes a RewardEvent. Suitable for logging, because the string is
 /// bounded in size.
 impl fmt::Display for RewardEvent {
@@ -1782,10 +1947,14 @@ pub(crate) mod tests {
                 Action::UpgradeSnsControlledCanister       (Default::default()),
                 Action::AddGenericNervousSystemFunction    (Default::default()),
                 Action::RemoveGenericNervousSystemFunction (Default::default()),
+                Action::RegisterDappCanisters              (Default::default()),
             ];
 
             let disallowed_in_pre_initialization_swap = vec! [
                 Action::ManageNervousSystemParameters(Default::default()),
+                Action::TransferSnsTreasuryFunds(Default::default()),
+                Action::DeregisterDappCanisters(Default::default()),
+                Action::ManageSnsMetadata(Default::default()),
             ];
 
             // Conditionally allow: No targetting SNS canisters.
@@ -1823,6 +1992,7 @@ pub(crate) mod tests {
                         target_method_name: Some("Foo".to_string()),
                         validator_canister_id: Some(*target_canister_id),
                         validator_method_name: Some("Bar".to_string()),
+                        topic: None,
                     })),
                 }
             }
@@ -2072,4 +2242,43 @@ pub(crate) mod tests {
 
         assert!(default.validate().is_ok());
     }
+
+    #[test]
+    fn test_topic_of_action() {
+        assert_eq!(
+            topic_of_action(native_action_ids::MOTION, &ID_TO_NERVOUS_SYSTEM_FUNCTION),
+            Topic::Governance
+        );
+        assert_eq!(
+            topic_of_action(
+                native_action_ids::ADD_GENERIC_NERVOUS_SYSTEM_FUNCTION,
+                &ID_TO_NERVOUS_SYSTEM_FUNCTION
+            ),
+            Topic::CriticalDappOperations
+        );
+
+        // Generic functions without a topic fall back to ApplicationBusinessLogic.
+        assert_eq!(
+            topic_of_action(ROOT_TARGETING_FUNCTION_ID, &ID_TO_NERVOUS_SYSTEM_FUNCTION),
+            Topic::ApplicationBusinessLogic
+        );
+
+        // Generic functions can opt into a different topic.
+        let mut functions = ID_TO_NERVOUS_SYSTEM_FUNCTION.clone();
+        match functions
+            .get_mut(&RANDOM_CANISTER_TARGETING_FUNCTION_ID)
+            .unwrap()
+            .function_type
+            .as_mut()
+        {
+            Some(FunctionType::GenericNervousSystemFunction(function)) => {
+                function.topic = Some(Topic::TreasuryAssetManagement as i32)
+            }
+            _ => panic!("Expected a generic nervous system function."),
+        }
+        assert_eq!(
+            topic_of_action(RANDOM_CANISTER_TARGETING_FUNCTION_ID, &functions),
+            Topic::TreasuryAssetManagement
+        );
+    }
 }
@@ -130,6 +130,7 @@ pub struct NeuronBuilder {
     dissolve_state: Option<DissolveState>,
     followees: BTreeMap<u64, Followees>,
     voting_power_percentage_multiplier: u64,
+    vesting_period_seconds: Option<u64>,
 }
 
 impl From<Neuron> for NeuronBuilder {
@@ -149,6 +150,7 @@ impl From<Neuron> for NeuronBuilder {
             dissolve_state: neuron.dissolve_state,
             followees: neuron.followees,
             voting_power_percentage_multiplier: neuron.voting_power_percentage_multiplier,
+            vesting_period_seconds: neuron.vesting_period_seconds,
         }
     }
 }
@@ -166,6 +168,7 @@ impl NeuronBuilder {
             dissolve_state: None,
             followees: BTreeMap::new(),
             voting_power_percentage_multiplier: 100,
+            vesting_period_seconds: None,
         }
     }
 
@@ -181,6 +184,7 @@ impl NeuronBuilder {
             dissolve_state: None,
             followees: BTreeMap::new(),
             voting_power_percentage_multiplier: 100,
+            vesting_period_seconds: None,
         }
     }
 
@@ -242,6 +246,11 @@ impl NeuronBuilder {
         self
     }
 
+    pub fn set_vesting_period(mut self, seconds: u64) -> Self {
+        self.vesting_period_seconds = Some(seconds);
+        self
+    }
+
     pub fn create(self, now: u64, ledger: &mut LedgerBuilder) -> Neuron {
         if let Some(id) = self.id.as_ref() {
             let subaccount = id.subaccount().unwrap();
@@ -265,6 +274,7 @@ impl NeuronBuilder {
             dissolve_state: self.dissolve_state,
             followees: self.followees,
             voting_power_percentage_multiplier: self.voting_power_percentage_multiplier,
+            vesting_period_seconds: self.vesting_period_seconds,
         }
     }
 
@@ -730,7 +740,12 @@ impl SNSBuilder {
         let valid_governance = ValidGovernanceProto::try_from(self.governance).unwrap();
         let mut sns = SNS {
             fixture: fixture.clone(),
-            governance: Governance::new(valid_governance, Box::new(fixture), ledger),
+            governance: Governance::new(
+                valid_governance,
+                Box::new(fixture.clone()),
+                ledger,
+                Box::new(fixture),
+            ),
             initial_state: None,
         };
         sns.capture_state();
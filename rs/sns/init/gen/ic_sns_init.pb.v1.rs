@@ -74,6 +74,13 @@ pub struct SnsInitPayload {
     /// Description of the SNS project.
     #[prost(string, optional, tag = "16")]
     pub description: ::core::option::Option<::prost::alloc::string::String>,
+    /// How the SNS tokens bought by each direct participant of the
+    /// decentralization swap are split into a basket of neurons with
+    /// staggered dissolve delays. If unset, each participant receives a
+    /// single neuron with zero dissolve delay.
+    #[prost(message, optional, tag = "17")]
+    pub neuron_basket_construction_parameters:
+        ::core::option::Option<NeuronBasketConstructionParameters>,
     /// The initial tokens and neurons available at genesis will be distributed according
     /// to the strategy and configuration picked via the initial_token_distribution
     /// parameter.
@@ -101,6 +108,28 @@ pub mod sns_init_payload {
         FractionalDeveloperVotingPower(super::FractionalDeveloperVotingPower),
     }
 }
+/// The parameters of the basket of neurons that each direct participant of
+/// the decentralization swap receives. The SNS tokens of the participant are
+/// split evenly between the neurons of the basket.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    serde::Serialize,
+    Eq,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct NeuronBasketConstructionParameters {
+    /// The number of neurons in each basket. Must be greater than zero.
+    #[prost(uint64, tag = "1")]
+    pub count: u64,
+    /// The dissolve delay of the i-th neuron (counting from zero) of the
+    /// basket is `i * dissolve_delay_interval_seconds`. The largest dissolve
+    /// delay must not exceed the SNS' maximum dissolve delay.
+    #[prost(uint64, tag = "2")]
+    pub dissolve_delay_interval_seconds: u64,
+}
 /// The FractionalDeveloperVotingPower token distribution strategy configures
 /// how tokens and neurons are distributed via four "buckets": developers,
 /// treasury, swap, and airdrops. This strategy will distribute all developer tokens
@@ -236,4 +265,9 @@ pub struct NeuronDistribution {
     /// Neuron's account in the SNS Ledger will have this value.
     #[prost(uint64, tag = "2")]
     pub stake_e8s: u64,
+    /// The duration, counted from SNS genesis, during which the neuron is
+    /// vesting. While vesting, the dissolve state of the neuron cannot be
+    /// changed and the neuron cannot be split. Intended for developer neurons.
+    #[prost(uint64, optional, tag = "3")]
+    pub vesting_period_seconds: ::core::option::Option<u64>,
 }
@@ -22,7 +22,7 @@ use std::collections::BTreeMap;
 pub const DEFAULT_NEURON_STAKING_NONCE: u64 = 0;
 
 /// The static MEMO used when calculating the SNS Treasury subaccount.
-pub const TREASURY_SUBACCOUNT_NONCE: u64 = 0;
+pub use ic_sns_governance::governance::TREASURY_SUBACCOUNT_NONCE;
 
 /// The static MEMO used when calculating the subaccount of future token swaps.
 pub const SWAP_SUBACCOUNT_NONCE: u64 = 1;
@@ -185,6 +185,7 @@ impl FractionalDeveloperVotingPower {
             followees: default_followees,
             dissolve_state: Some(DissolveState::DissolveDelaySeconds(dissolve_delay_seconds)),
             voting_power_percentage_multiplier,
+            vesting_period_seconds: neuron_distribution.vesting_period_seconds,
             ..Default::default()
         })
     }
@@ -531,10 +532,12 @@ mod test {
                     NeuronDistribution {
                         controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                         stake_e8s: neuron_stake,
+                        vesting_period_seconds: None,
                     },
                     NeuronDistribution {
                         controller: Some(*TEST_NEURON_2_OWNER_PRINCIPAL),
                         stake_e8s: neuron_stake,
+                        vesting_period_seconds: None,
                     },
                 ],
             }),
@@ -549,6 +552,7 @@ mod test {
                 airdrop_neurons: vec![NeuronDistribution {
                     controller: Some(*TEST_NEURON_3_OWNER_PRINCIPAL),
                     stake_e8s: neuron_stake,
+                    vesting_period_seconds: None,
                 }],
             }),
         };
@@ -627,6 +631,7 @@ mod test {
     fn test_initial_neurons() {
         let developer_neuron_stake = 100_000_000;
         let airdrop_neuron_stake = 50_000;
+        let vesting_period_seconds = 365 * 24 * 60 * 60;
         let swap_total = 1_000_000_000;
         let swap_initial_round = 400_000_000;
         let treasury_total = 1_000_000_000;
@@ -637,10 +642,12 @@ mod test {
                     NeuronDistribution {
                         controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                         stake_e8s: developer_neuron_stake,
+                        vesting_period_seconds: None,
                     },
                     NeuronDistribution {
                         controller: Some(*TEST_NEURON_2_OWNER_PRINCIPAL),
                         stake_e8s: developer_neuron_stake,
+                        vesting_period_seconds: Some(vesting_period_seconds),
                     },
                 ],
             }),
@@ -655,6 +662,7 @@ mod test {
                 airdrop_neurons: vec![NeuronDistribution {
                     controller: Some(*TEST_NEURON_3_OWNER_PRINCIPAL),
                     stake_e8s: airdrop_neuron_stake,
+                    vesting_period_seconds: None,
                 }],
             }),
         };
@@ -753,6 +761,14 @@ mod test {
             neuron_3.voting_power_percentage_multiplier,
             DEFAULT_VOTING_POWER_PERCENTAGE_MULTIPLIER
         );
+
+        // That only the neuron configured with a vesting period is vesting.
+        assert_eq!(neuron_1.vesting_period_seconds, None);
+        assert_eq!(
+            neuron_2.vesting_period_seconds,
+            Some(vesting_period_seconds)
+        );
+        assert_eq!(neuron_3.vesting_period_seconds, None);
     }
 
     #[test]
@@ -790,10 +806,12 @@ mod test {
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                     stake_e8s: 1,
+                    vesting_period_seconds: None,
                 },
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                     stake_e8s: 1,
+                    vesting_period_seconds: None,
                 },
             ],
         });
@@ -805,10 +823,12 @@ mod test {
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                     stake_e8s: 1,
+                    vesting_period_seconds: None,
                 },
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_2_OWNER_PRINCIPAL),
                     stake_e8s: 1,
+                    vesting_period_seconds: None,
                 },
             ],
         });
@@ -820,10 +840,12 @@ mod test {
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                     stake_e8s: u64::MAX,
+                    vesting_period_seconds: None,
                 },
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_2_OWNER_PRINCIPAL),
                     stake_e8s: u64::MAX,
+                    vesting_period_seconds: None,
                 },
             ],
         });
@@ -836,10 +858,12 @@ mod test {
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                     stake_e8s: 50,
+                    vesting_period_seconds: None,
                 },
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_2_OWNER_PRINCIPAL),
                     stake_e8s: 50,
+                    vesting_period_seconds: None,
                 },
             ],
         });
@@ -852,10 +876,12 @@ mod test {
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                     stake_e8s: 50,
+                    vesting_period_seconds: None,
                 },
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_2_OWNER_PRINCIPAL),
                     stake_e8s: 51,
+                    vesting_period_seconds: None,
                 },
             ],
         });
@@ -866,6 +892,7 @@ mod test {
             developer_neurons: vec![NeuronDistribution {
                 controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                 stake_e8s: 50,
+                vesting_period_seconds: None,
             }],
         });
         assert!(initial_token_distribution.validate().is_ok());
@@ -876,6 +903,7 @@ mod test {
             airdrop_neurons: vec![NeuronDistribution {
                 controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                 stake_e8s: 50,
+                vesting_period_seconds: None,
             }],
         });
         assert!(initial_token_distribution.validate().is_err());
@@ -1001,10 +1029,12 @@ mod test {
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                     stake_e8s: 1,
+                    vesting_period_seconds: None,
                 },
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                     stake_e8s: 1,
+                    vesting_period_seconds: None,
                 },
             ],
         });
@@ -1016,10 +1046,12 @@ mod test {
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                     stake_e8s: 1,
+                    vesting_period_seconds: None,
                 },
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_2_OWNER_PRINCIPAL),
                     stake_e8s: 1,
+                    vesting_period_seconds: None,
                 },
             ],
         });
@@ -1031,10 +1063,12 @@ mod test {
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                     stake_e8s: u64::MAX,
+                    vesting_period_seconds: None,
                 },
                 NeuronDistribution {
                     controller: Some(*TEST_NEURON_2_OWNER_PRINCIPAL),
                     stake_e8s: u64::MAX,
+                    vesting_period_seconds: None,
                 },
             ],
         });
@@ -1045,6 +1079,7 @@ mod test {
             airdrop_neurons: vec![NeuronDistribution {
                 controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                 stake_e8s: 50,
+                vesting_period_seconds: None,
             }],
         });
         assert!(initial_token_distribution.validate().is_ok());
@@ -1055,6 +1090,7 @@ mod test {
             developer_neurons: vec![NeuronDistribution {
                 controller: Some(*TEST_NEURON_1_OWNER_PRINCIPAL),
                 stake_e8s: 50,
+                vesting_period_seconds: None,
             }],
         });
         assert!(initial_token_distribution.validate().is_err());
@@ -4,7 +4,7 @@ pub mod pb;
 use crate::pb::v1::{
     sns_init_payload::InitialTokenDistribution::FractionalDeveloperVotingPower,
     AirdropDistribution, DeveloperDistribution, FractionalDeveloperVotingPower as FractionalDVP,
-    SnsInitPayload, SwapDistribution, TreasuryDistribution,
+    NeuronBasketConstructionParameters, SnsInitPayload, SwapDistribution, TreasuryDistribution,
 };
 use anyhow::anyhow;
 use ic_base_types::{CanisterId, PrincipalId};
@@ -23,7 +23,9 @@ use ic_sns_governance::pb::v1::{
 };
 use ic_sns_governance::types::DEFAULT_TRANSFER_FEE;
 use ic_sns_root::pb::v1::SnsRootCanister;
-use ic_sns_swap::pb::v1::Init;
+use ic_sns_swap::pb::v1::{
+    Init, NeuronBasketConstructionParameters as SwapNeuronBasketConstructionParameters,
+};
 use lazy_static::lazy_static;
 use maplit::{btreemap, hashset};
 use std::collections::{BTreeMap, HashSet};
@@ -98,6 +100,7 @@ impl SnsInitPayload {
             url: None,
             name: None,
             description: None,
+            neuron_basket_construction_parameters: None,
         }
     }
 
@@ -274,6 +277,13 @@ impl SnsInitPayload {
                 .expect("Field max_participants_icp_e8s cannot be None"),
             min_icp_e8s: self.min_icp_e8s.expect("Field min_icp_e8s cannot be None"),
             fallback_controller_principal_ids: self.fallback_controller_principal_ids.clone(),
+            neuron_basket_construction_parameters: self
+                .neuron_basket_construction_parameters
+                .as_ref()
+                .map(|x| SwapNeuronBasketConstructionParameters {
+                    count: x.count,
+                    dissolve_delay_interval_seconds: x.dissolve_delay_interval_seconds,
+                }),
         }
     }
 
@@ -324,6 +334,7 @@ impl SnsInitPayload {
             self.validate_logo(),
             self.validate_description(),
             self.validate_name(),
+            self.validate_neuron_basket_construction_parameters(),
         ];
 
         let defect_msg = validation_fns
@@ -576,7 +587,13 @@ impl SnsInitPayload {
         let neuron_minimum_stake_e8s = self
             .neuron_minimum_stake_e8s
             .ok_or_else(|| "Error: neuron_minimum_stake_e8s must be specified.".to_string())?;
-        let min_participant_token = min_participant_icp_e8s * sale_tokens / max_icp_e8s;
+        // The tokens of each participant are split between the neurons of its basket.
+        let neurons_per_participant = self
+            .neuron_basket_construction_parameters
+            .as_ref()
+            .map_or(1, |x| x.count.max(1));
+        let min_participant_token =
+            min_participant_icp_e8s * sale_tokens / max_icp_e8s / neurons_per_participant;
         if min_participant_token < neuron_minimum_stake_e8s {
             Err("Error: min_participant_icp_e8s is too small. If max_icp are obtained, a contribution \
 of min_participant_icp would result in a neuron with a stake smaller than \
@@ -700,23 +717,57 @@ neuron_minimum_stake".to_string())
 
         Ok(())
     }
+
+    /// Optional. If specified, the basket must not be empty and the largest dissolve
+    /// delay must not exceed the maximum dissolve delay of SNS neurons.
+    fn validate_neuron_basket_construction_parameters(&self) -> Result<(), String> {
+        let neuron_basket_construction_parameters = match &self
+            .neuron_basket_construction_parameters
+        {
+            None => return Ok(()),
+            Some(neuron_basket_construction_parameters) => neuron_basket_construction_parameters,
+        };
+
+        let count = neuron_basket_construction_parameters.count;
+        if count < 1 {
+            return Err(
+                "Error: neuron_basket_construction_parameters.count must be larger than 0"
+                    .to_string(),
+            );
+        }
+
+        let max_dissolve_delay_seconds = NervousSystemParameters::with_default_values()
+            .max_dissolve_delay_seconds
+            .expect("NervousSystemParameters must have max_dissolve_delay_seconds");
+        let largest_dissolve_delay_seconds = (count - 1)
+            .checked_mul(neuron_basket_construction_parameters.dissolve_delay_interval_seconds);
+        match largest_dissolve_delay_seconds {
+            Some(d) if d <= max_dissolve_delay_seconds => Ok(()),
+            _ => Err(format!(
+                "Error: the largest dissolve delay of the neuron basket must not exceed {} seconds",
+                max_dissolve_delay_seconds
+            )),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::pb::v1::{
         sns_init_payload::InitialTokenDistribution, DeveloperDistribution,
-        FractionalDeveloperVotingPower as FractionalDVP, SwapDistribution, TreasuryDistribution,
+        FractionalDeveloperVotingPower as FractionalDVP, NeuronBasketConstructionParameters,
+        SwapDistribution, TreasuryDistribution,
     };
     use crate::{
         AirdropDistribution, FractionalDeveloperVotingPower, SnsCanisterIds, SnsInitPayload,
-        MAX_TOKEN_NAME_LENGTH, MAX_TOKEN_SYMBOL_LENGTH,
+        SwapNeuronBasketConstructionParameters, MAX_TOKEN_NAME_LENGTH, MAX_TOKEN_SYMBOL_LENGTH,
     };
     use ic_base_types::ic_types::Principal;
     use ic_base_types::{CanisterId, PrincipalId};
     use ic_icrc1::Account;
     use ic_sns_governance::governance::ValidGovernanceProto;
     use ic_sns_governance::pb::v1::governance::SnsMetadata;
+    use ic_sns_governance::types::{ONE_MONTH_SECONDS, ONE_YEAR_SECONDS};
 
     fn create_valid_initial_token_distribution() -> InitialTokenDistribution {
         FractionalDeveloperVotingPower(FractionalDVP {
@@ -757,6 +808,7 @@ mod test {
             description: Some("A project that decentralizes a dapp".to_string()),
             url: Some("https://internetcomputer.org/".to_string()),
             min_participant_icp_e8s: Some(100_000_000),
+            neuron_basket_construction_parameters: None,
         }
     }
 
@@ -1009,6 +1061,62 @@ mod test {
         assert!(swap.is_valid());
     }
 
+    #[test]
+    fn test_neuron_basket_construction_parameters() {
+        // With this minimum stake, a contribution of min_participant_icp_e8s
+        // can be split into (at most) two neurons.
+        let sns_init_payload = SnsInitPayload {
+            neuron_minimum_stake_e8s: Some(50_000_000),
+            neuron_basket_construction_parameters: Some(NeuronBasketConstructionParameters {
+                count: 2,
+                dissolve_delay_interval_seconds: ONE_MONTH_SECONDS,
+            }),
+            ..get_test_sns_init_payload()
+        };
+        assert!(sns_init_payload.validate().is_ok());
+
+        // The parameters are passed on to the swap canister.
+        let swap = sns_init_payload
+            .build_canister_payloads(&create_canister_ids())
+            .expect("Expected SnsInitPayload to be a valid payload")
+            .swap;
+        assert!(swap.is_valid());
+        assert_eq!(
+            swap.neuron_basket_construction_parameters,
+            Some(SwapNeuronBasketConstructionParameters {
+                count: 2,
+                dissolve_delay_interval_seconds: ONE_MONTH_SECONDS,
+            })
+        );
+
+        // An empty basket is invalid.
+        let mut invalid_payload = sns_init_payload.clone();
+        invalid_payload.neuron_basket_construction_parameters =
+            Some(NeuronBasketConstructionParameters {
+                count: 0,
+                dissolve_delay_interval_seconds: ONE_MONTH_SECONDS,
+            });
+        assert!(invalid_payload.validate().is_err());
+
+        // Splitting into three neurons would result in neurons below the minimum stake.
+        let mut invalid_payload = sns_init_payload.clone();
+        invalid_payload.neuron_basket_construction_parameters =
+            Some(NeuronBasketConstructionParameters {
+                count: 3,
+                dissolve_delay_interval_seconds: ONE_MONTH_SECONDS,
+            });
+        assert!(invalid_payload.validate().is_err());
+
+        // The dissolve delays must not exceed the maximum dissolve delay.
+        let mut invalid_payload = sns_init_payload;
+        invalid_payload.neuron_basket_construction_parameters =
+            Some(NeuronBasketConstructionParameters {
+                count: 2,
+                dissolve_delay_interval_seconds: 9 * ONE_YEAR_SECONDS,
+            });
+        assert!(invalid_payload.validate().is_err());
+    }
+
     #[test]
     fn test_ledger_init_args_is_valid() {
         // Build an sns_init_payload with defaults for non-ledger related configuration.
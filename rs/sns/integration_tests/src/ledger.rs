@@ -102,6 +102,7 @@ fn test_stake_and_disburse_neuron_with_notification() {
                             by: Some(By::MemoAndController(MemoAndController {
                                 memo: nonce,
                                 controller: None,
+                                dissolve_delay_seconds: None,
                             })),
                         })),
                     },
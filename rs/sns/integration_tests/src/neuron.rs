@@ -236,6 +236,7 @@ fn test_claim_neuron() {
                 by: Some(By::MemoAndController(MemoAndController {
                     memo: NONCE,
                     controller: None,
+                    dissolve_delay_seconds: None,
                 })),
             })),
         };
@@ -341,6 +342,7 @@ fn test_claim_neuron_fails_when_max_number_of_neurons_is_reached() {
                 by: Some(By::MemoAndController(MemoAndController {
                     memo: NONCE,
                     controller: None,
+                    dissolve_delay_seconds: None,
                 })),
             })),
         };
@@ -457,6 +459,7 @@ fn test_refresh_neuron() {
                         by: Some(By::MemoAndController(MemoAndController {
                             memo: NONCE,
                             controller: Some(user.sender.get_principal_id()),
+                            dissolve_delay_seconds: None,
                         })),
                     })),
                 },
@@ -963,6 +966,7 @@ async fn zero_total_reward_shares() {
         proto.try_into().unwrap(),
         Box::new(environment),
         Box::new(EmptyLedger {}),
+        Box::new(EmptyLedger {}),
     );
     // Prevent gc.
     governance.latest_gc_timestamp_seconds = now;
@@ -1164,6 +1168,7 @@ async fn couple_of_neurons_who_voted_get_rewards() {
         proto.try_into().unwrap(),
         Box::new(environment),
         Box::new(StubLedger {}),
+        Box::new(StubLedger {}),
     );
     // Prevent gc.
     governance.latest_gc_timestamp_seconds = now;
@@ -1346,6 +1351,7 @@ fn test_one_user_cannot_claim_other_users_neuron() {
                         by: Some(By::MemoAndController(MemoAndController {
                             memo: nonce,
                             controller: Some(user1.get_principal_id()),
+                            dissolve_delay_seconds: None,
                         })),
                     })),
                 },
@@ -523,6 +523,7 @@ fn swap_lifecycle_happy() {
                 possibility: None
             }),
             set_dapp_controllers_result: None,
+            settle_community_fund_participation_result: None,
         }
     );
 
@@ -679,6 +680,7 @@ fn swap_lifecycle_sad() {
                     }
                 )),
             }),
+            settle_community_fund_participation_result: None,
         }
     );
 
@@ -13,8 +13,9 @@ use ic_nervous_system_root::{ChangeCanisterProposal, LOG_PREFIX};
 use ic_sns_root::{
     pb::v1::{
         CanisterCallError, ListSnsCanistersRequest, ListSnsCanistersResponse,
-        RegisterDappCanisterRequest, RegisterDappCanisterResponse, SetDappControllersRequest,
-        SetDappControllersResponse, SnsRootCanister,
+        RegisterDappCanisterRequest, RegisterDappCanisterResponse, RegisterDappCanistersRequest,
+        RegisterDappCanistersResponse, SetDappControllersRequest, SetDappControllersResponse,
+        SnsRootCanister,
     },
     CanisterIdRecord, CanisterStatusResultV2, EmptyBlob, GetSnsCanistersSummaryRequest,
     GetSnsCanistersSummaryResponse, LedgerCanisterClient, ManagementCanisterClient,
@@ -293,12 +294,38 @@ async fn register_dapp_canister_(
     .await
 }
 
+/// Registers a batch of dapp canisters, and makes this canister (SNS root)
+/// their sole controller.
+///
+/// Caller must be the governance canister. Otherwise, the request will be
+/// rejected. This canister must already be one of the controllers of each
+/// canister.
+#[export_name = "canister_update register_dapp_canisters"]
+fn register_dapp_canisters() {
+    println!("{}register_dapp_canisters", LOG_PREFIX);
+    over_async(candid_one, register_dapp_canisters_);
+}
+
+#[candid_method(update, rename = "register_dapp_canisters")]
+async fn register_dapp_canisters_(
+    request: RegisterDappCanistersRequest,
+) -> RegisterDappCanistersResponse {
+    SnsRootCanister::register_dapp_canisters(
+        &STATE,
+        &mut RealManagementCanisterClient::new(),
+        dfn_core::api::id(),
+        dfn_core::api::caller(),
+        request,
+    )
+    .await
+}
+
 /// Sets the controllers of registered dapp canisters.
 ///
 /// Dapp canisters can be registered via the register_dapp_canister method.
 ///
-/// Caller must be the swap canister. Otherwise, the request will be
-/// rejected.
+/// Caller must be the swap canister or the governance canister. Otherwise,
+/// the request will be rejected.
 ///
 /// Registered dapp canisters must not have disappeared prior to this being
 /// called. Otherwise, request will be rejected. Some precautions are taken
@@ -49,10 +49,37 @@ pub struct RegisterDappCanisterResponse {}
 #[derive(candid::CandidType, candid::Deserialize)]
 #[cfg_attr(feature = "test", derive(comparable::Comparable))]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegisterDappCanistersRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+}
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegisterDappCanistersResponse {}
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SetDappControllersRequest {
+    /// If populated, only the controllers of these (registered) dapp canisters
+    /// are set. Otherwise, the controllers of all registered dapp canisters are
+    /// set.
+    #[prost(message, optional, tag = "2")]
+    pub canister_ids: ::core::option::Option<set_dapp_controllers_request::CanisterIds>,
     #[prost(message, repeated, tag = "1")]
     pub controller_principal_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
 }
+/// Nested message and enum types in `SetDappControllersRequest`.
+pub mod set_dapp_controllers_request {
+    /// A list of canister IDs.
+    #[derive(candid::CandidType, candid::Deserialize)]
+    #[cfg_attr(feature = "test", derive(comparable::Comparable))]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct CanisterIds {
+        #[prost(message, repeated, tag = "1")]
+        pub canister_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    }
+}
 #[derive(candid::CandidType, candid::Deserialize)]
 #[cfg_attr(feature = "test", derive(comparable::Comparable))]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -3,8 +3,9 @@ pub mod types;
 
 use crate::pb::v1::{
     set_dapp_controllers_response, CanisterCallError, ListSnsCanistersResponse,
-    RegisterDappCanisterRequest, RegisterDappCanisterResponse, SetDappControllersRequest,
-    SetDappControllersResponse, SnsRootCanister,
+    RegisterDappCanisterRequest, RegisterDappCanisterResponse, RegisterDappCanistersRequest,
+    RegisterDappCanistersResponse, SetDappControllersRequest, SetDappControllersResponse,
+    SnsRootCanister,
 };
 use async_trait::async_trait;
 use candid::{CandidType, Decode, Deserialize, Encode};
@@ -462,6 +463,9 @@ impl SnsRootCanister {
     /// Registered dapp canisters are used by at least two methods:
     ///   1. get_sns_canisters_summary
     ///   2. set_dapp_controllers (currently in review).
+    ///
+    /// See also register_dapp_canisters, which is how SNS governance registers
+    /// dapp canisters (as a result of RegisterDappCanisters proposals).
     pub async fn register_dapp_canister(
         self_ref: &'static LocalKey<RefCell<Self>>,
         management_canister_client: &mut impl ManagementCanisterClient,
@@ -528,12 +532,154 @@ impl SnsRootCanister {
         RegisterDappCanisterResponse {}
     }
 
+    /// Registers a batch of dapp canisters.
+    ///
+    /// Caller must be the governance canister (this is how RegisterDappCanisters
+    /// proposals are executed). Otherwise, the request will be rejected.
+    ///
+    /// Unlike register_dapp_canister, the canisters need not be exclusively
+    /// controlled by this canister (i.e. SNS root); it suffices that this
+    /// canister is one of their controllers. Once all canisters have been
+    /// validated, this canister becomes their sole controller.
+    ///
+    /// The operation is all-or-nothing: if this canister fails to become the
+    /// sole controller of one of the canisters, the controllers of the
+    /// canisters changed so far are restored and no canister is registered.
+    ///
+    /// None of the canisters may be one of the distinguished SNS canisters.
+    /// Canisters that are already registered are left alone.
+    pub async fn register_dapp_canisters(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        management_canister_client: &mut impl ManagementCanisterClient,
+        own_canister_id: CanisterId,
+        caller: PrincipalId,
+        request: RegisterDappCanistersRequest,
+    ) -> RegisterDappCanistersResponse {
+        let is_authorized =
+            self_ref.with(|self_ref| caller == self_ref.borrow().governance_canister_id());
+        assert!(is_authorized, "Caller ({caller}) is not authorized.");
+
+        // Reject if any of the canisters is one of the distinguished canisters
+        // in the SNS.
+        let sns_canister_ids: Vec<PrincipalId> = self_ref.with(|s| {
+            let s = s.borrow();
+            vec![
+                s.governance_canister_id.unwrap(),
+                s.ledger_canister_id.unwrap(),
+                s.swap_canister_id.unwrap(),
+                own_canister_id.into(),
+            ]
+            .into_iter()
+            .chain(s.archive_canister_ids.clone())
+            .collect()
+        });
+        let mut dapp_canister_ids = vec![];
+        for canister_id in request.canister_ids {
+            if sns_canister_ids.contains(&canister_id) {
+                panic!(
+                    "Invalid RegisterDappCanistersRequest: \
+                     The requested canister ({canister_id}) is an SNS canister."
+                );
+            }
+            let canister_id = CanisterId::new(canister_id).unwrap_or_else(|err| {
+                panic!(
+                    "Invalid RegisterDappCanistersRequest: \
+                     contained an invalid canister ID ({canister_id}): {err:#?}"
+                )
+            });
+            if !dapp_canister_ids.contains(&canister_id) {
+                dapp_canister_ids.push(canister_id);
+            }
+        }
+
+        // A pre-flight check: Make sure we are a controller of all canisters
+        // by querying the management canister, so that we either register all
+        // canisters or none of them. The original controllers are kept to be
+        // restored if taking control of a canister fails.
+        let mut original_controllers = vec![];
+        for dapp_canister_id in &dapp_canister_ids {
+            let canister_status = management_canister_client
+                .canister_status(&(*dapp_canister_id).into())
+                .await
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "Operation aborted due to an error; no changes have been made: \
+                         Unable to get the status of a canister referenced in the request \
+                         ({dapp_canister_id}). This canister (SNS root) must be one of its \
+                         controllers: {err:#?}"
+                    )
+                });
+            assert!(
+                canister_status
+                    .controllers()
+                    .contains(&own_canister_id.into()),
+                "Operation aborted; no changes have been made: The canister referenced \
+                 by the request ({dapp_canister_id}) is not controlled by this SNS root \
+                 canister.",
+            );
+            original_controllers.push(canister_status.controllers());
+        }
+
+        // Take exclusive control of the canisters.
+        for (i, dapp_canister_id) in dapp_canister_ids.iter().enumerate() {
+            let update_result = set_controllers(
+                management_canister_client,
+                (*dapp_canister_id).into(),
+                vec![own_canister_id.into()],
+            )
+            .await;
+            if let Err(err) = update_result {
+                // Roll back the canisters changed so far. This can only fail if
+                // they changed since the pre-flight check, which is reported.
+                let mut rollback_errors = vec![];
+                for (changed_canister_id, controllers) in
+                    dapp_canister_ids.iter().zip(original_controllers).take(i)
+                {
+                    if let Err(err) = set_controllers(
+                        management_canister_client,
+                        (*changed_canister_id).into(),
+                        controllers,
+                    )
+                    .await
+                    {
+                        rollback_errors.push(format!("{changed_canister_id}: {err:#?}"));
+                    }
+                }
+                panic!(
+                    "Operation aborted; no canister has been registered: Unable to set this \
+                     canister (SNS root) as the sole controller of {dapp_canister_id}: {err:#?}. \
+                     The controllers of the canisters changed so far have been restored, \
+                     except for: {rollback_errors:?}"
+                );
+            }
+        }
+
+        // Register the canisters.
+        self_ref.with(|s| {
+            let mut s = s.borrow_mut();
+            for dapp_canister_id in dapp_canister_ids {
+                let dapp_canister_id = PrincipalId::from(dapp_canister_id);
+                if !s.dapp_canister_ids.contains(&dapp_canister_id) {
+                    s.dapp_canister_ids.push(dapp_canister_id);
+                }
+            }
+        });
+
+        // Report success.
+        RegisterDappCanistersResponse {}
+    }
+
     /// Sets the controllers of registered dapp canisters.
     ///
     /// Dapp canisters can be registered via the register_dapp_canister method.
     ///
-    /// Caller must be the swap canister. Otherwise, the request will be
-    /// rejected.
+    /// Caller must be the swap canister or the governance canister (the latter
+    /// as a result of DeregisterDappCanisters proposals). Otherwise, the request
+    /// will be rejected.
+    ///
+    /// If request.canister_ids is populated, only the controllers of those
+    /// canisters are set, and they must all be registered dapp canisters.
+    /// Otherwise, the controllers of all registered dapp canisters are set.
     ///
     /// Registered dapp canisters must not have disappeared prior to this being
     /// called. Otherwise, request will be rejected. Some precautions are taken
@@ -545,13 +691,29 @@ impl SnsRootCanister {
         caller: PrincipalId,
         request: &'a SetDappControllersRequest,
     ) -> SetDappControllersResponse {
-        let is_authorized =
-            self_ref.with(|self_ref| caller == self_ref.borrow().swap_canister_id());
+        let is_authorized = self_ref.with(|self_ref| {
+            let self_ref = self_ref.borrow();
+            caller == self_ref.swap_canister_id() || caller == self_ref.governance_canister_id()
+        });
         assert!(is_authorized, "Caller ({caller}) is not authorized.");
 
         // Grab a snapshot of canisters to operate on.
-        let dapp_canister_ids =
+        let registered_dapp_canister_ids =
             self_ref.with(|self_ref| self_ref.borrow().dapp_canister_ids.clone());
+        let dapp_canister_ids = match &request.canister_ids {
+            None => registered_dapp_canister_ids,
+            Some(canister_ids) => {
+                for canister_id in &canister_ids.canister_ids {
+                    assert!(
+                        registered_dapp_canister_ids.contains(canister_id),
+                        "Operation aborted; no changes have been made: The canister \
+                         referenced by the request ({canister_id}) is not a registered \
+                         dapp canister."
+                    );
+                }
+                canister_ids.canister_ids.clone()
+            }
+        };
 
         // A pre-flight check: Assert that we still control all canisters
         // referenced in dapp_canister_ids. This way, we minimize that chance of
@@ -808,6 +970,25 @@ async fn get_swap_status(
     }
 }
 
+/// Sets the controllers of `canister_id`, leaving its other settings alone.
+async fn set_controllers(
+    management_canister_client: &mut impl ManagementCanisterClient,
+    canister_id: PrincipalId,
+    controllers: Vec<PrincipalId>,
+) -> Result<EmptyBlob, CanisterCallError> {
+    let request = UpdateSettingsArgs {
+        canister_id,
+        settings: CanisterSettingsArgs {
+            controllers: Some(controllers),
+            controller: None,
+            compute_allocation: None,
+            memory_allocation: None,
+            freezing_threshold: None,
+        },
+    };
+    management_canister_client.update_settings(&request).await
+}
+
 async fn get_owned_canister_summary(
     management_canister_client: &mut impl ManagementCanisterClient,
     canister_id: PrincipalId,
@@ -848,7 +1029,7 @@ async fn get_owned_canister_summary(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::pb::v1::ListSnsCanistersResponse;
+    use crate::pb::v1::{set_dapp_controllers_request::CanisterIds, ListSnsCanistersResponse};
     use dfn_core::api::now;
     use std::collections::VecDeque;
     use std::sync::{Arc, Mutex};
@@ -1307,6 +1488,7 @@ mod tests {
             sns_root_canister_id,
             STATE.with(|state| state.borrow().swap_canister_id.unwrap()),
             &SetDappControllersRequest {
+                canister_ids: None,
                 controller_principal_ids: vec![new_controller_principal_id],
             },
         )
@@ -1358,6 +1540,7 @@ mod tests {
             sns_root_canister_id,
             not_swap,
             &SetDappControllersRequest {
+                canister_ids: None,
                 controller_principal_ids: vec![new_controller_principal_id],
             },
         )
@@ -1419,6 +1602,7 @@ mod tests {
             sns_root_canister_id,
             STATE.with(|state| state.borrow().swap_canister_id.unwrap()),
             &SetDappControllersRequest {
+                canister_ids: None,
                 controller_principal_ids: vec![
                     new_controller_principal_id,
                     sns_root_canister_id.into(),
@@ -1444,6 +1628,334 @@ mod tests {
         assert_eq!(state, original_state, "{state:#?}");
     }
 
+    #[tokio::test]
+    async fn test_set_dapp_controllers_of_some_dapp_canisters() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
+                ledger_canister_id: Some(PrincipalId::new_user_test_id(2)),
+                swap_canister_id: Some(PrincipalId::new_user_test_id(99)),
+                dapp_canister_ids: vec![
+                    PrincipalId::new_user_test_id(3),
+                    PrincipalId::new_user_test_id(6),
+                ],
+                archive_canister_ids: vec![],
+                ..Default::default()
+            });
+        }
+        let sns_root_canister_id = CanisterId::try_from(PrincipalId::new_user_test_id(4)).unwrap();
+        let new_controller_principal_id = PrincipalId::new_user_test_id(5);
+
+        // Step 1.1: Prepare helpers. Only the canister referenced by the
+        // request is touched.
+        let mut management_canister_client = MockManagementCanisterClient {
+            calls: vec![
+                ManagementCanisterClientCall::CanisterStatus {
+                    expected_canister_id: PrincipalId::new_user_test_id(6),
+                    result: Ok(canister_status_result_v2_for_test(
+                        sns_root_canister_id.get(),
+                    )),
+                },
+                ManagementCanisterClientCall::UpdateSettings {
+                    update_settings_args: UpdateSettingsArgs {
+                        canister_id: PrincipalId::new_user_test_id(6),
+                        settings: CanisterSettingsArgs {
+                            controllers: Some(vec![new_controller_principal_id]),
+                            controller: None,
+                            compute_allocation: None,
+                            memory_allocation: None,
+                            freezing_threshold: None,
+                        },
+                    },
+                    result: Ok(EmptyBlob {}),
+                },
+            ]
+            .into(),
+        };
+
+        // Step 2: Run code under test. The caller is governance, as it is
+        // when executing a DeregisterDappCanisters proposal.
+        let response = SnsRootCanister::set_dapp_controllers(
+            &STATE,
+            &mut management_canister_client,
+            sns_root_canister_id,
+            STATE.with(|state| state.borrow().governance_canister_id()),
+            &SetDappControllersRequest {
+                canister_ids: Some(CanisterIds {
+                    canister_ids: vec![PrincipalId::new_user_test_id(6)],
+                }),
+                controller_principal_ids: vec![new_controller_principal_id],
+            },
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        assert_eq!(
+            response,
+            SetDappControllersResponse {
+                failed_updates: vec![]
+            }
+        );
+        let state = STATE.with(|state| state.borrow().clone());
+        assert_eq!(
+            state.dapp_canister_ids,
+            vec![PrincipalId::new_user_test_id(3)],
+            "{state:#?}"
+        );
+    }
+
+    #[should_panic(expected = "not a registered dapp canister")]
+    #[tokio::test]
+    async fn test_set_dapp_controllers_rejects_unregistered_canister() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
+                ledger_canister_id: Some(PrincipalId::new_user_test_id(2)),
+                swap_canister_id: Some(PrincipalId::new_user_test_id(99)),
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
+                archive_canister_ids: vec![],
+                ..Default::default()
+            });
+        }
+        let sns_root_canister_id = CanisterId::try_from(PrincipalId::new_user_test_id(4)).unwrap();
+
+        // Step 1.1: Prepare helpers.
+        let mut management_canister_client = MockManagementCanisterClient {
+            calls: vec![].into(),
+        };
+
+        // Step 2: Run code under test.
+        SnsRootCanister::set_dapp_controllers(
+            &STATE,
+            &mut management_canister_client,
+            sns_root_canister_id,
+            STATE.with(|state| state.borrow().governance_canister_id()),
+            &SetDappControllersRequest {
+                canister_ids: Some(CanisterIds {
+                    canister_ids: vec![PrincipalId::new_user_test_id(6)],
+                }),
+                controller_principal_ids: vec![PrincipalId::new_user_test_id(5)],
+            },
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_register_dapp_canisters() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister());
+        }
+        let sns_root_canister_id = CanisterId::try_from(PrincipalId::new_user_test_id(4)).unwrap();
+        let dapp_canister_id = PrincipalId::new_user_test_id(6);
+        let dapp_developer = PrincipalId::new_user_test_id(7);
+
+        // Step 1.1: Prepare helpers. SNS root is one of (but not the only)
+        // controller of the dapp canister.
+        let canister_status = CanisterStatusResultV2::new(
+            CanisterStatusType::Running,
+            None,                                             // module_hash
+            dapp_developer,                                   // controller
+            vec![dapp_developer, sns_root_canister_id.get()], // controllers
+            NumBytes::new(42),                                // memory_size
+            43,                                               // cycles
+            44,                                               // compute_allocation
+            None,                                             // memory_allocation
+            45,                                               // freezing_threshold
+            46,                                               // idle_cycles_burned_per_day
+        );
+        let mut management_canister_client = MockManagementCanisterClient {
+            calls: vec![
+                ManagementCanisterClientCall::CanisterStatus {
+                    expected_canister_id: dapp_canister_id,
+                    result: Ok(canister_status),
+                },
+                ManagementCanisterClientCall::UpdateSettings {
+                    update_settings_args: UpdateSettingsArgs {
+                        canister_id: dapp_canister_id,
+                        settings: CanisterSettingsArgs {
+                            controllers: Some(vec![sns_root_canister_id.get()]),
+                            controller: None,
+                            compute_allocation: None,
+                            memory_allocation: None,
+                            freezing_threshold: None,
+                        },
+                    },
+                    result: Ok(EmptyBlob {}),
+                },
+            ]
+            .into(),
+        };
+
+        // Step 2: Run code under test.
+        let response = SnsRootCanister::register_dapp_canisters(
+            &STATE,
+            &mut management_canister_client,
+            sns_root_canister_id,
+            STATE.with(|state| state.borrow().governance_canister_id()),
+            RegisterDappCanistersRequest {
+                canister_ids: vec![dapp_canister_id],
+            },
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        assert_eq!(response, RegisterDappCanistersResponse {});
+        let state = STATE.with(|state| state.borrow().clone());
+        assert_eq!(
+            state.dapp_canister_ids,
+            vec![dapp_canister_id],
+            "{state:#?}"
+        );
+    }
+
+    #[should_panic(expected = "no canister has been registered")]
+    #[tokio::test]
+    async fn test_register_dapp_canisters_restores_controllers_on_failure() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister());
+        }
+        let sns_root_canister_id = CanisterId::try_from(PrincipalId::new_user_test_id(4)).unwrap();
+        let dapp_canister_ids = [
+            PrincipalId::new_user_test_id(6),
+            PrincipalId::new_user_test_id(7),
+        ];
+        let dapp_developer = PrincipalId::new_user_test_id(8);
+        let original_controllers = vec![dapp_developer, sns_root_canister_id.get()];
+        let update_settings = |canister_id, controllers| UpdateSettingsArgs {
+            canister_id,
+            settings: CanisterSettingsArgs {
+                controllers: Some(controllers),
+                controller: None,
+                compute_allocation: None,
+                memory_allocation: None,
+                freezing_threshold: None,
+            },
+        };
+
+        // Step 1.1: Prepare helpers. Taking control of the second canister
+        // fails, so control of the first one is given back.
+        let mut calls = VecDeque::new();
+        for canister_id in dapp_canister_ids {
+            let canister_status = CanisterStatusResultV2::new(
+                CanisterStatusType::Running,
+                None,                         // module_hash
+                dapp_developer,               // controller
+                original_controllers.clone(), // controllers
+                NumBytes::new(42),            // memory_size
+                43,                           // cycles
+                44,                           // compute_allocation
+                None,                         // memory_allocation
+                45,                           // freezing_threshold
+                46,                           // idle_cycles_burned_per_day
+            );
+            calls.push_back(ManagementCanisterClientCall::CanisterStatus {
+                expected_canister_id: canister_id,
+                result: Ok(canister_status),
+            });
+        }
+        calls.push_back(ManagementCanisterClientCall::UpdateSettings {
+            update_settings_args: update_settings(
+                dapp_canister_ids[0],
+                vec![sns_root_canister_id.get()],
+            ),
+            result: Ok(EmptyBlob {}),
+        });
+        calls.push_back(ManagementCanisterClientCall::UpdateSettings {
+            update_settings_args: update_settings(
+                dapp_canister_ids[1],
+                vec![sns_root_canister_id.get()],
+            ),
+            result: Err(CanisterCallError {
+                code: None,
+                description: "Out of cycles.".to_string(),
+            }),
+        });
+        calls.push_back(ManagementCanisterClientCall::UpdateSettings {
+            update_settings_args: update_settings(dapp_canister_ids[0], original_controllers),
+            result: Ok(EmptyBlob {}),
+        });
+        let mut management_canister_client = MockManagementCanisterClient { calls };
+
+        // Step 2: Run code under test. Step 3 (inspecting the results) is
+        // taken care of by #[should_panic] and by the mock, which checks that
+        // all the expected calls were made when it is dropped.
+        SnsRootCanister::register_dapp_canisters(
+            &STATE,
+            &mut management_canister_client,
+            sns_root_canister_id,
+            STATE.with(|state| state.borrow().governance_canister_id()),
+            RegisterDappCanistersRequest {
+                canister_ids: dapp_canister_ids.to_vec(),
+            },
+        )
+        .await;
+    }
+
+    #[should_panic(expected = "is not controlled by this SNS root")]
+    #[tokio::test]
+    async fn test_register_dapp_canisters_rejects_uncontrolled_canister() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister());
+        }
+        let sns_root_canister_id = CanisterId::try_from(PrincipalId::new_user_test_id(4)).unwrap();
+        let dapp_canister_id = PrincipalId::new_user_test_id(6);
+
+        // Step 1.1: Prepare helpers.
+        let mut management_canister_client = MockManagementCanisterClient {
+            calls: vec![ManagementCanisterClientCall::CanisterStatus {
+                expected_canister_id: dapp_canister_id,
+                result: Ok(canister_status_result_v2_for_test(
+                    PrincipalId::new_user_test_id(7),
+                )),
+            }]
+            .into(),
+        };
+
+        // Step 2: Run code under test.
+        SnsRootCanister::register_dapp_canisters(
+            &STATE,
+            &mut management_canister_client,
+            sns_root_canister_id,
+            STATE.with(|state| state.borrow().governance_canister_id()),
+            RegisterDappCanistersRequest {
+                canister_ids: vec![dapp_canister_id],
+            },
+        )
+        .await;
+    }
+
+    #[should_panic(expected = "authorize")]
+    #[tokio::test]
+    async fn test_register_dapp_canisters_rejects_non_governance_caller() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(build_test_sns_root_canister());
+        }
+        let sns_root_canister_id = CanisterId::try_from(PrincipalId::new_user_test_id(4)).unwrap();
+
+        // Step 1.1: Prepare helpers.
+        let mut management_canister_client = MockManagementCanisterClient {
+            calls: vec![].into(),
+        };
+
+        // Step 2: Run code under test.
+        SnsRootCanister::register_dapp_canisters(
+            &STATE,
+            &mut management_canister_client,
+            sns_root_canister_id,
+            PrincipalId::new_user_test_id(9001),
+            RegisterDappCanistersRequest {
+                canister_ids: vec![PrincipalId::new_user_test_id(6)],
+            },
+        )
+        .await;
+    }
+
     #[test]
     fn test_list_sns_canisters() {
         let state = SnsRootCanister {
@@ -38,11 +38,12 @@ use ic_sns_swap::pb::v1::{SetDappControllersRequest, SetDappControllersResponse}
 use ic_sns_swap::pb::v1::{
     CanisterCallError, ErrorRefundIcpRequest, ErrorRefundIcpResponse, FinalizeSwapRequest,
     FinalizeSwapResponse, GetBuyerStateRequest, GetBuyerStateResponse, GetBuyersTotalRequest,
-    GetBuyersTotalResponse, GetCanisterStatusRequest, GetStateRequest, GetStateResponse, Init,
-    Lifecycle, RefreshBuyerTokensRequest, RefreshBuyerTokensResponse, RefreshSnsTokensRequest,
-    RefreshSnsTokensResponse, SetOpenTimeWindowRequest, SetOpenTimeWindowResponse, Swap,
+    GetBuyersTotalResponse, GetCanisterStatusRequest, GetStateRequest, GetStateResponse,
+    GovernanceError, Init, Lifecycle, OpenRequest, OpenResponse, RefreshBuyerTokensRequest,
+    RefreshBuyerTokensResponse, RefreshSnsTokensRequest, RefreshSnsTokensResponse,
+    SetOpenTimeWindowRequest, SetOpenTimeWindowResponse, SettleCommunityFundParticipation, Swap,
 };
-use ic_sns_swap::swap::{SnsGovernanceClient, SnsRootClient, LOG_PREFIX};
+use ic_sns_swap::swap::{NnsGovernanceClient, SnsGovernanceClient, SnsRootClient, LOG_PREFIX};
 use prost::Message;
 use std::str::FromStr;
 use std::time::{Duration, SystemTime};
@@ -124,6 +125,24 @@ fn set_open_time_window_(request: SetOpenTimeWindowRequest) -> SetOpenTimeWindow
     swap_mut().set_open_time_window(caller(), now_seconds(), &request)
 }
 
+/// Sets the open time window and the participation of the community fund.
+///
+/// See Swap.open_with_community_fund.
+#[export_name = "canister_update open"]
+fn open() {
+    over(candid_one, open_)
+}
+
+/// See `open`.
+#[candid_method(update, rename = "open")]
+fn open_(request: OpenRequest) -> OpenResponse {
+    println!("{}open", LOG_PREFIX);
+    match swap_mut().open_with_community_fund(caller(), now_seconds(), &request) {
+        Ok(response) => response,
+        Err(msg) => panic!("{}ERROR: {}", LOG_PREFIX, msg),
+    }
+}
+
 /// See `Swap.refresh_sns_token_e8s`.
 #[export_name = "canister_update refresh_sns_tokens"]
 fn refresh_sns_tokens() {
@@ -234,6 +253,33 @@ impl SnsGovernanceClient for RealSnsGovernanceClient {
     }
 }
 
+struct RealNnsGovernanceClient {
+    canister_id: CanisterId,
+}
+
+impl RealNnsGovernanceClient {
+    fn new(canister_id: CanisterId) -> Self {
+        Self { canister_id }
+    }
+}
+
+#[async_trait]
+impl NnsGovernanceClient for RealNnsGovernanceClient {
+    async fn settle_community_fund_participation(
+        &mut self,
+        request: SettleCommunityFundParticipation,
+    ) -> Result<Result<(), GovernanceError>, CanisterCallError> {
+        dfn_core::api::call(
+            self.canister_id,
+            "settle_community_fund_participation",
+            dfn_candid::candid_one,
+            request,
+        )
+        .await
+        .map_err(CanisterCallError::from)
+    }
+}
+
 /// See Swap.finalize.
 #[export_name = "canister_update finalize_swap"]
 fn finalize_swap() {
@@ -246,6 +292,7 @@ async fn finalize_swap_(_arg: FinalizeSwapRequest) -> FinalizeSwapResponse {
     // Helpers.
     let mut sns_root_client = RealSnsRootClient::new(swap().init().sns_root());
     let mut sns_governance_client = RealSnsGovernanceClient::new(swap().init().sns_governance());
+    let mut nns_governance_client = RealNnsGovernanceClient::new(swap().init().nns_governance());
     let icp_ledger_factory = create_real_icp_ledger;
     let icrc1_ledger_factory = create_real_icrc1_ledger;
 
@@ -253,6 +300,7 @@ async fn finalize_swap_(_arg: FinalizeSwapRequest) -> FinalizeSwapResponse {
         .finalize(
             &mut sns_root_client,
             &mut sns_governance_client,
+            &mut nns_governance_client,
             icp_ledger_factory,
             icrc1_ledger_factory,
         )
@@ -490,7 +538,7 @@ fn main() {}
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ic_ic00_types::CanisterStatusType;
+    use ic_ic00_types::{CanisterStatusType, QueryStatsResult};
 
     /// A test that fails if the API was updated but the candid definition was not.
     #[test]
@@ -523,6 +571,7 @@ mod tests {
             None,
             0,
             0,
+            QueryStatsResult::default(),
         )
     }
 
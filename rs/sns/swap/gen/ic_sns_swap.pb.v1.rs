@@ -60,6 +60,47 @@ pub struct Init {
     /// root, rather than SNS governance.
     #[prost(string, tag = "12")]
     pub sns_root_canister_id: ::prost::alloc::string::String,
+    /// How the SNS tokens bought by a direct participant are split into a
+    /// basket of neurons. If unset, each direct participant receives a
+    /// single neuron with zero dissolve delay.
+    #[prost(message, optional, tag = "13")]
+    pub neuron_basket_construction_parameters: ::core::option::Option<NeuronBasketConstructionParameters>,
+}
+/// The parameters of the basket of neurons that each direct participant
+/// receives when the swap is committed.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NeuronBasketConstructionParameters {
+    /// The number of neurons in each basket. Must be greater than zero.
+    #[prost(uint64, tag = "1")]
+    pub count: u64,
+    /// The dissolve delay of the i-th neuron (counting from zero) of the
+    /// basket is `i * dissolve_delay_interval_seconds`.
+    #[prost(uint64, tag = "2")]
+    pub dissolve_delay_interval_seconds: u64,
+}
+/// One neuron of the basket of a direct participant. Created when the
+/// world lifecycle changes to Committed.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NeuronBasketEntry {
+    /// The memo of the neuron. The SNS tokens are transferred to the
+    /// neuron staking subaccount of the buyer and this memo.
+    #[prost(uint64, tag = "1")]
+    pub memo: u64,
+    /// The dissolve delay of the neuron when it is claimed.
+    #[prost(uint64, tag = "2")]
+    pub dissolve_delay_seconds: u64,
+    /// The amount of SNS tokens to transfer to the neuron. Zeroed once
+    /// the tokens have been transferred.
+    #[prost(uint64, tag = "3")]
+    pub amount_sns_e8s: u64,
+    /// Only used in state Committed, when a transfer of
+    /// `amount_sns_e8s` is in progress.
+    #[prost(bool, tag = "4")]
+    pub sns_disbursing: bool,
 }
 #[derive(candid::CandidType, candid::Deserialize)]
 #[cfg_attr(feature = "test", derive(comparable::Comparable))]
@@ -89,7 +130,9 @@ pub struct BuyerState {
     /// * aborted - owned by the buyer, can be transferred out
     #[prost(uint64, tag = "1")]
     pub amount_icp_e8s: u64,
-    /// Computed when world lifecycle changes to Committed.
+    /// Computed when world lifecycle changes to Committed. Once
+    /// committed, this is the sum of the `amount_sns_e8s` of the entries
+    /// of `neuron_basket` that are yet to be transferred.
     ///
     /// ownership:
     /// * pending - a `BuyerState` cannot exists
@@ -107,6 +150,49 @@ pub struct BuyerState {
     /// `amount_sns_e8s` is in progress.
     #[prost(bool, tag = "4")]
     pub sns_disbursing: bool,
+    /// The neurons that the buyer receives. Computed when world lifecycle
+    /// changes to Committed, following
+    /// `init.neuron_basket_construction_parameters`. Must be empty
+    /// before that.
+    #[prost(message, repeated, tag = "5")]
+    pub neuron_basket: ::prost::alloc::vec::Vec<NeuronBasketEntry>,
+}
+/// A community fund neuron taking part in the swap. The ICP of the
+/// neuron is drawn from its maturity by NNS governance when the swap
+/// is opened; it is never held by the swap canister.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CfNeuron {
+    /// The ID of the NNS neuron.
+    #[prost(fixed64, tag = "1")]
+    pub nns_neuron_id: u64,
+    /// The amount of ICP (drawn from the maturity of the neuron) that
+    /// the neuron contributes to the swap. Must be greater than zero.
+    #[prost(uint64, tag = "2")]
+    pub amount_icp_e8s: u64,
+    /// Computed when world lifecycle changes to Committed. Must be zero
+    /// when the neuron is passed to the swap canister.
+    ///
+    /// ownership: same as `BuyerState.amount_sns_e8s`.
+    #[prost(uint64, tag = "3")]
+    pub amount_sns_e8s: u64,
+    /// Only used in state Committed, when a transfer of
+    /// `amount_sns_e8s` is in progress.
+    #[prost(bool, tag = "4")]
+    pub sns_disbursing: bool,
+}
+/// The community fund neurons controlled by the same principal.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CfParticipant {
+    /// The controller of the NNS neurons. The SNS neurons created for
+    /// the community fund neurons are controlled by this principal.
+    #[prost(string, tag = "1")]
+    pub controller_principal_id: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub cf_neurons: ::prost::alloc::vec::Vec<CfNeuron>,
 }
 /// Mutable state of the swap canister.
 #[derive(candid::CandidType, candid::Deserialize)]
@@ -141,6 +227,21 @@ pub struct State {
     /// canister to enter the Open state.
     #[prost(message, optional, tag = "4")]
     pub open_time_window: ::core::option::Option<TimeWindow>,
+    /// Initially, empty. Later, set by the open Candid method, while the
+    /// canister is in the Pending state.
+    ///
+    /// Invariant:
+    /// ```text
+    /// state.cf_total_icp_e8s <= init.max_icp_e8s
+    /// ```
+    #[prost(message, repeated, tag = "5")]
+    pub cf_participants: ::prost::alloc::vec::Vec<CfParticipant>,
+    /// The ID of the NNS proposal that opened the swap, if it was opened
+    /// by the open Candid method. The participation of the community
+    /// fund is settled with NNS governance using this ID when the swap
+    /// is finalized.
+    #[prost(uint64, optional, tag = "6")]
+    pub open_sns_token_swap_proposal_id: ::core::option::Option<u64>,
 }
 #[derive(candid::CandidType, candid::Deserialize)]
 #[cfg_attr(feature = "test", derive(comparable::Comparable))]
@@ -236,6 +337,28 @@ pub struct SetOpenTimeWindowRequest {
 #[cfg_attr(feature = "test", derive(comparable::Comparable))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SetOpenTimeWindowResponse {}
+/// See `open` for details.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenRequest {
+    /// Same requirements as in SetOpenTimeWindowRequest.
+    #[prost(message, optional, tag = "1")]
+    pub open_time_window: ::core::option::Option<TimeWindow>,
+    /// The community fund neurons taking part in the swap, grouped by
+    /// controller.
+    #[prost(message, repeated, tag = "2")]
+    pub cf_participants: ::prost::alloc::vec::Vec<CfParticipant>,
+    /// The ID of the NNS proposal that is opening the swap. Must be
+    /// populated.
+    #[prost(uint64, optional, tag = "3")]
+    pub open_sns_token_swap_proposal_id: ::core::option::Option<u64>,
+}
+/// Response if opening the swap succeeded.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenResponse {}
 /// Informs the swap canister that the swap has been funded. That is, the initial
 /// pot of tokens being offered has been transferred to the swap canister.
 ///
@@ -284,6 +407,9 @@ pub struct FinalizeSwapResponse {
     pub sns_governance_normal_mode_enabled: ::core::option::Option<SetModeCallResult>,
     #[prost(message, optional, tag = "5")]
     pub set_dapp_controllers_result: ::core::option::Option<SetDappControllersCallResult>,
+    #[prost(message, optional, tag = "6")]
+    pub settle_community_fund_participation_result:
+        ::core::option::Option<SettleCommunityFundParticipationResult>,
 }
 #[derive(candid::CandidType, candid::Deserialize)]
 #[cfg_attr(feature = "test", derive(comparable::Comparable))]
@@ -373,6 +499,88 @@ pub struct CanisterCallError {
     #[prost(string, tag = "2")]
     pub description: ::prost::alloc::string::String,
 }
+/// Sent by the swap canister to the settle_community_fund_participation
+/// Candid method of NNS governance once the swap is committed or
+/// aborted.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SettleCommunityFundParticipation {
+    /// The ID of the proposal that opened the swap.
+    #[prost(uint64, optional, tag = "1")]
+    pub open_sns_token_swap_proposal_id: ::core::option::Option<u64>,
+    #[prost(oneof = "settle_community_fund_participation::Result", tags = "2, 3")]
+    pub result: ::core::option::Option<settle_community_fund_participation::Result>,
+}
+/// Nested message and enum types in `SettleCommunityFundParticipation`.
+pub mod settle_community_fund_participation {
+    /// The ICP drawn from the maturity of the community fund neurons is
+    /// minted to the SNS governance canister.
+    #[derive(candid::CandidType, candid::Deserialize)]
+    #[cfg_attr(feature = "test", derive(comparable::Comparable))]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Committed {
+        #[prost(message, optional, tag = "1")]
+        pub sns_governance_canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    }
+    /// The maturity drawn from the community fund neurons is restored.
+    #[derive(candid::CandidType, candid::Deserialize)]
+    #[cfg_attr(feature = "test", derive(comparable::Comparable))]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Aborted {}
+    #[derive(candid::CandidType, candid::Deserialize)]
+    #[cfg_attr(feature = "test", derive(comparable::Comparable))]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Result {
+        #[prost(message, tag = "2")]
+        Committed(Committed),
+        #[prost(message, tag = "3")]
+        Aborted(Aborted),
+    }
+}
+/// Same shape as the GovernanceError of NNS governance, so that it can
+/// be decoded from replies of NNS governance.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GovernanceError {
+    #[prost(int32, tag = "1")]
+    pub error_type: i32,
+    #[prost(string, tag = "2")]
+    pub error_message: ::prost::alloc::string::String,
+}
+/// Analogous to Rust type Result<Result<(), GovernanceError>, CanisterCallError>.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[cfg_attr(feature = "test", derive(comparable::Comparable))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SettleCommunityFundParticipationResult {
+    #[prost(
+        oneof = "settle_community_fund_participation_result::Possibility",
+        tags = "1, 2"
+    )]
+    pub possibility:
+        ::core::option::Option<settle_community_fund_participation_result::Possibility>,
+}
+/// Nested message and enum types in `SettleCommunityFundParticipationResult`.
+pub mod settle_community_fund_participation_result {
+    #[derive(candid::CandidType, candid::Deserialize)]
+    #[cfg_attr(feature = "test", derive(comparable::Comparable))]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Response {
+        /// Not populated if the participation was settled.
+        #[prost(message, optional, tag = "1")]
+        pub governance_error: ::core::option::Option<super::GovernanceError>,
+    }
+    #[derive(candid::CandidType, candid::Deserialize)]
+    #[cfg_attr(feature = "test", derive(comparable::Comparable))]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Possibility {
+        #[prost(message, tag = "1")]
+        Ok(Response),
+        #[prost(message, tag = "2")]
+        Err(super::CanisterCallError),
+    }
+}
 /// Request a refund of tokens that were sent to the canister in
 /// error. The refund is always on the ICP ledger, from this canister's
 /// subaccount of the caller to the account of the caller.
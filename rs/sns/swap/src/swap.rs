@@ -1,8 +1,11 @@
 use crate::pb::v1::{
-    set_dapp_controllers_call_result, set_mode_call_result, BuyerState, CanisterCallError,
-    DerivedState, FinalizeSwapResponse, GetBuyerStateRequest, GetBuyerStateResponse,
-    GetBuyersTotalResponse, Init, Lifecycle, SetDappControllersCallResult, SetModeCallResult,
-    SetOpenTimeWindowRequest, SetOpenTimeWindowResponse, State, Swap, SweepResult, TimeWindow,
+    set_dapp_controllers_call_result, set_mode_call_result, settle_community_fund_participation,
+    settle_community_fund_participation_result, BuyerState, CanisterCallError, CfNeuron,
+    CfParticipant, DerivedState, FinalizeSwapResponse, GetBuyerStateRequest, GetBuyerStateResponse,
+    GetBuyersTotalResponse, GovernanceError, Init, Lifecycle, NeuronBasketConstructionParameters,
+    NeuronBasketEntry, OpenRequest, OpenResponse, SetDappControllersCallResult, SetModeCallResult,
+    SetOpenTimeWindowRequest, SetOpenTimeWindowResponse, SettleCommunityFundParticipation,
+    SettleCommunityFundParticipationResult, State, Swap, SweepResult, TimeWindow,
 };
 use async_trait::async_trait;
 #[cfg(target_arch = "wasm32")]
@@ -84,6 +87,22 @@ impl From<Result<SetDappControllersResponse, CanisterCallError>> for SetDappCont
     }
 }
 
+impl From<Result<Result<(), GovernanceError>, CanisterCallError>>
+    for SettleCommunityFundParticipationResult
+{
+    fn from(native_result: Result<Result<(), GovernanceError>, CanisterCallError>) -> Self {
+        use settle_community_fund_participation_result::{Possibility as P, Response};
+        let possibility = Some(match native_result {
+            Ok(result) => P::Ok(Response {
+                governance_error: result.err(),
+            }),
+            Err(err) => P::Err(err),
+        });
+
+        Self { possibility }
+    }
+}
+
 #[async_trait]
 pub trait SnsGovernanceClient {
     async fn manage_neuron(
@@ -102,6 +121,14 @@ pub trait SnsRootClient {
     ) -> Result<SetDappControllersResponse, CanisterCallError>;
 }
 
+#[async_trait]
+pub trait NnsGovernanceClient {
+    async fn settle_community_fund_participation(
+        &mut self,
+        request: SettleCommunityFundParticipation,
+    ) -> Result<Result<(), GovernanceError>, CanisterCallError>;
+}
+
 /**
 
 State diagram for the swap canister's state.
@@ -196,6 +223,8 @@ impl Swap {
                 buyers: Default::default(),
                 lifecycle,
                 open_time_window: None,
+                cf_participants: vec![],
+                open_sns_token_swap_proposal_id: None,
             }),
         }
     }
@@ -225,9 +254,11 @@ impl Swap {
             return true;
         }
         let lifecycle = self.state().lifecycle();
-        if (lifecycle == Lifecycle::Open || lifecycle == Lifecycle::Pending)
-            && self.swap_due(now_seconds)
-            && !self.sufficient_participation()
+        // A swap that never opened is aborted even if the community fund
+        // alone would have been sufficient participation.
+        if self.swap_due(now_seconds)
+            && (lifecycle == Lifecycle::Pending
+                || (lifecycle == Lifecycle::Open && !self.sufficient_participation()))
         {
             self.abort(now_seconds);
             return true;
@@ -272,6 +303,72 @@ impl Swap {
         SetOpenTimeWindowResponse {}
     }
 
+    /// Sets the open time window and the participation of the community
+    /// fund, and opens the swap if the start time has arrived and the
+    /// tokens being offered have been received. Otherwise, the swap is
+    /// opened later by the heartbeat.
+    ///
+    /// Can only be called once, while the swap is pending.
+    ///
+    /// Caller must be authorized. To wit, nns_governance.
+    pub fn open_with_community_fund(
+        &mut self,
+        caller: PrincipalId,
+        now_timestamp_seconds: u64,
+        request: &OpenRequest,
+    ) -> Result<OpenResponse, String> {
+        // Require authorization.
+        let allowed_canister = self.init().nns_governance();
+        if caller != PrincipalId::from(allowed_canister) {
+            return Err(format!(
+                "This method can only be called by canister {}",
+                allowed_canister
+            ));
+        }
+
+        if self.state().lifecycle() != Lifecycle::Pending {
+            return Err(
+                "Invalid lifecycle state to 'open' the swap; must be 'pending'".to_string(),
+            );
+        }
+        if self.state().open_sns_token_swap_proposal_id.is_some() {
+            return Err(format!(
+                "The swap has already been opened by proposal {:?}",
+                self.state().open_sns_token_swap_proposal_id
+            ));
+        }
+
+        // Inspect the request.
+        let defects = request.defects(now_timestamp_seconds, self.init().max_icp_e8s);
+        if !defects.is_empty() {
+            return Err(format!(
+                "Received an invalid OpenRequest. defects:\n  * {}",
+                defects.join("\n  * "),
+            ));
+        }
+
+        // Modify self.
+        let state = self.state_mut();
+        state.open_time_window = request.open_time_window;
+        state.cf_participants = request.cf_participants.clone();
+        state.open_sns_token_swap_proposal_id = request.open_sns_token_swap_proposal_id;
+        println!(
+            "{}INFO: proposal {:?} set the open time window to {:?}; {} community fund participants contribute {} ICP (e8s)",
+            LOG_PREFIX,
+            state.open_sns_token_swap_proposal_id,
+            state.open_time_window,
+            state.cf_participants.len(),
+            state.cf_total_icp_e8s()
+        );
+
+        // The start time may not have arrived yet, in which case the
+        // heartbeat opens the swap later.
+        if let Err(msg) = self.open(now_timestamp_seconds) {
+            println!("{}INFO: swap not opened yet: {}", LOG_PREFIX, msg);
+        }
+        Ok(OpenResponse {})
+    }
+
     /// Precondition: lifecycle == Pending && sns_amount_available &&
     /// open time window is set && now in open time window
     ///
@@ -321,8 +418,11 @@ impl Swap {
         assert!(sns_being_offered_e8s > 0);
         // Note that this value has to be > 0 as we have > 0
         // participants each with > 0 ICP contributed.
-        let total_buyer_icp_e8s = self.state().buyer_total_icp_e8s() as u128;
-        assert!(total_buyer_icp_e8s > 0);
+        let total_participant_icp_e8s = self.state().participant_total_icp_e8s() as u128;
+        assert!(total_participant_icp_e8s > 0);
+        let neuron_basket_construction_parameters = self
+            .init()
+            .neuron_basket_construction_parameters_or_default();
         let state_mut = self.state_mut();
         // Keep track of SNS tokens sold just to check that the amount
         // is correct at the end.
@@ -330,9 +430,18 @@ impl Swap {
         // =====================================================================
         // ===            This is where the actual swap happens              ===
         // =====================================================================
-        for (_, state) in state_mut.buyers.iter_mut() {
+        let buyer_amounts = state_mut
+            .buyers
+            .values_mut()
+            .map(|x| (x.amount_icp_e8s, &mut x.amount_sns_e8s));
+        let cf_neuron_amounts = state_mut
+            .cf_participants
+            .iter_mut()
+            .flat_map(|x| x.cf_neurons.iter_mut())
+            .map(|x| (x.amount_icp_e8s, &mut x.amount_sns_e8s));
+        for (amount_icp_e8s, amount_sns_e8s) in buyer_amounts.chain(cf_neuron_amounts) {
             // If we divide SNS (sns_being_offered_e8s) with ICP
-            // (total_buyer_icp_e8s), we get the price of SNS tokens in
+            // (total_participant_icp_e8s), we get the price of SNS tokens in
             // ICP tokens for the swap, i.e., the fractional number of
             // SNS token the buyer get for one ICP token.
             //
@@ -344,20 +453,27 @@ impl Swap {
             // then divide, to avoid loss of precision. Also, we
             // perform the operation in u128 to prevent loss of precision.
             let amount_sns_e8s_u128 = sns_being_offered_e8s
-                .saturating_mul(state.amount_icp_e8s as u128)
-                .saturating_div(total_buyer_icp_e8s as u128);
-            // Note that state.amount_icp_e8s <= total_buyer_icp_e8s,
+                .saturating_mul(amount_icp_e8s as u128)
+                .saturating_div(total_participant_icp_e8s as u128);
+            // Note that amount_icp_e8s <= total_participant_icp_e8s,
             // whence amount_sns_e8s <= sns_being_offered_e8s <=
             // u64::MAX.
             assert!(amount_sns_e8s_u128 <= u64::MAX as u128);
             let x = amount_sns_e8s_u128 as u64;
-            state.amount_sns_e8s = x;
+            *amount_sns_e8s = x;
             total_sns_tokens_sold = total_sns_tokens_sold.saturating_add(x);
         }
         assert!(total_sns_tokens_sold <= sns_being_offered_e8s as u64);
+        // Split the SNS tokens of each direct participant into a
+        // basket of neurons. Community fund neurons are not split:
+        // each of them becomes exactly one SNS neuron.
+        for buyer_state in state_mut.buyers.values_mut() {
+            buyer_state.neuron_basket =
+                neuron_basket_construction_parameters.construct_basket(buyer_state.amount_sns_e8s);
+        }
         println!("{}INFO: token swap committed; {} participants receive a total of {} out of {} (change {});",
 		 LOG_PREFIX,
-		 state_mut.buyers.len(),
+		 state_mut.buyers.len() + state_mut.cf_participants.len(),
 		 total_sns_tokens_sold,
 		 state_mut.sns_token_e8s,
 		 state_mut.sns_token_e8s - total_sns_tokens_sold);
@@ -368,16 +484,16 @@ impl Swap {
         state_mut.set_lifecycle(Lifecycle::Committed);
     }
 
-    /// Precondition: lifecycle IN {Open, Pending} && swap_due && not sufficient_participation
+    /// Precondition: swap_due && (lifecycle == Pending || (lifecycle == Open && not sufficient_participation))
     ///
     /// Postcondition: lifecycle == Aborted
     fn abort(&mut self, now_seconds: u64) {
+        let lifecycle = self.state().lifecycle();
         assert!(
-            self.state().lifecycle() == Lifecycle::Open
-                || self.state().lifecycle() == Lifecycle::Pending
+            lifecycle == Lifecycle::Pending
+                || (lifecycle == Lifecycle::Open && !self.sufficient_participation())
         );
         assert!(self.swap_due(now_seconds));
-        assert!(!self.sufficient_participation());
         self.state_mut().sns_token_e8s = 0;
         self.state_mut().set_lifecycle(Lifecycle::Aborted);
     }
@@ -485,20 +601,20 @@ impl Swap {
         }
 
         // Recheck total amount of ICP bought after async call.
-        let buyer_total_icp_e8s = self.state().buyer_total_icp_e8s();
+        let participant_total_icp_e8s = self.state().participant_total_icp_e8s();
         let max_icp_e8s = self.init().max_icp_e8s;
-        if buyer_total_icp_e8s >= max_icp_e8s {
-            if buyer_total_icp_e8s > max_icp_e8s {
+        if participant_total_icp_e8s >= max_icp_e8s {
+            if participant_total_icp_e8s > max_icp_e8s {
                 println!(
                     "{}WARNING: total amount of ICP bought {} already exceeds the target {}!",
-                    LOG_PREFIX, buyer_total_icp_e8s, max_icp_e8s
+                    LOG_PREFIX, participant_total_icp_e8s, max_icp_e8s
                 );
             }
             // Nothing we can do for this buyer.
             return Ok(());
         }
         // Subtraction safe because of the preceding if-statement.
-        let max_increment_e8s = max_icp_e8s - buyer_total_icp_e8s;
+        let max_increment_e8s = max_icp_e8s - participant_total_icp_e8s;
 
         // Check that the minimum amount has been transferred before
         // actually creating an entry for the buyer.
@@ -520,6 +636,7 @@ impl Swap {
                 amount_sns_e8s: 0,
                 icp_disbursing: false,
                 sns_disbursing: false,
+                neuron_basket: vec![],
             });
         let old_amount_icp_e8s = buyer_state.amount_icp_e8s;
         if old_amount_icp_e8s >= e8s {
@@ -575,15 +692,24 @@ impl Swap {
         // TODO: get rid of logically unneccessary clone
         let init = self.init().clone();
         if let Some(buyer_state) = self.state_mut().buyers.get_mut(&principal.to_string()) {
-            // Observe: memo == 0. Could be specified as an argument instead.
-            let dst_subaccount = compute_neuron_staking_subaccount_bytes(principal, 0);
-            let dst = Account {
-                owner: init.sns_governance().get(),
-                subaccount: Some(dst_subaccount),
-            };
-            let result = buyer_state
-                .sns_transfer_helper(&init, fee, dst, &ledger_stub)
+            let results = buyer_state
+                .sns_transfer_basket_helper(principal, &init, fee, &ledger_stub)
                 .await;
+            // Report the first failure, if any; otherwise the last
+            // successful transfer, if any.
+            let mut result = TransferResult::AmountTooSmall;
+            for r in results {
+                match r {
+                    TransferResult::Failure(_) => return r,
+                    TransferResult::Success(_) => result = r,
+                    TransferResult::AlreadyInProgress => {
+                        if !matches!(result, TransferResult::Success(_)) {
+                            result = r;
+                        }
+                    }
+                    TransferResult::AmountTooSmall => (),
+                }
+            }
             result
         } else {
             TransferResult::Failure(format!("Principal {} not found", principal))
@@ -632,10 +758,16 @@ impl Swap {
     ///
     /// If the swap ended unsuccessfully (i.e. it is in the Lifecycle::Aborted
     /// phase), then ICP is send back to the buyers.
+    ///
+    /// In both cases, if the swap was opened by an NNS proposal, NNS governance
+    /// is asked to settle the participation of the community fund: the ICP of
+    /// the community fund neurons is minted to the SNS governance canister if
+    /// the swap was committed, and their maturity is restored otherwise.
     pub async fn finalize(
         &mut self,
         sns_root_client: &mut impl SnsRootClient,
         sns_governance_client: &mut impl SnsGovernanceClient,
+        nns_governance_client: &mut impl NnsGovernanceClient,
         icp_ledger_factory: impl Fn(CanisterId) -> Box<dyn Ledger>,
         icrc1_ledger_factory: impl Fn(CanisterId) -> Box<dyn Ledger>,
     ) -> FinalizeSwapResponse {
@@ -649,6 +781,9 @@ impl Swap {
         let sweep_icp = self
             .sweep_icp(DEFAULT_TRANSFER_FEE, &icp_ledger_factory)
             .await;
+        let settle_community_fund_participation_result = self
+            .settle_community_fund_participation(nns_governance_client)
+            .await;
         if lifecycle != Lifecycle::Committed {
             // Restore controllers of dapp canisters to their original owners (i.e. self.init.fallback_controller_principal_ids).
             let set_dapp_controllers_result = sns_root_client.set_dapp_controllers(
@@ -671,6 +806,7 @@ impl Swap {
                 create_neuron: None,
                 sns_governance_normal_mode_enabled: None,
                 set_dapp_controllers_result: Some(set_dapp_controllers_result.into()),
+                settle_community_fund_participation_result,
             };
         }
 
@@ -693,21 +829,63 @@ impl Swap {
             create_neuron: Some(create_neuron),
             sns_governance_normal_mode_enabled,
             set_dapp_controllers_result: None,
+            settle_community_fund_participation_result,
         }
     }
 
+    /// Asks NNS governance to settle the participation of the community fund
+    /// according to the outcome of the swap. Returns None if the swap was not
+    /// opened by an NNS proposal.
+    ///
+    /// Settling is idempotent on the NNS governance side, so this can be
+    /// retried by finalizing the swap again.
+    async fn settle_community_fund_participation(
+        &self,
+        nns_governance_client: &mut impl NnsGovernanceClient,
+    ) -> Option<SettleCommunityFundParticipationResult> {
+        let open_sns_token_swap_proposal_id = self.state().open_sns_token_swap_proposal_id?;
+        let result = if self.state().lifecycle() == Lifecycle::Committed {
+            settle_community_fund_participation::Result::Committed(
+                settle_community_fund_participation::Committed {
+                    sns_governance_canister_id: Some(self.init().sns_governance().get()),
+                },
+            )
+        } else {
+            settle_community_fund_participation::Result::Aborted(
+                settle_community_fund_participation::Aborted {},
+            )
+        };
+        let response = nns_governance_client
+            .settle_community_fund_participation(SettleCommunityFundParticipation {
+                open_sns_token_swap_proposal_id: Some(open_sns_token_swap_proposal_id),
+                result: Some(result),
+            })
+            .await;
+        match &response {
+            Ok(Ok(())) => println!(
+                "{}INFO: community fund participation settled for proposal {}",
+                LOG_PREFIX, open_sns_token_swap_proposal_id
+            ),
+            err => println!(
+                "{}ERROR: unable to settle community fund participation for proposal {}: {:#?}",
+                LOG_PREFIX, open_sns_token_swap_proposal_id, err
+            ),
+        }
+        Some(response.into())
+    }
+
     async fn claim_neurons(
         &self,
         sns_governance_client: &mut impl SnsGovernanceClient,
     ) -> SweepResult {
-        let (skipped, princpal_ids) = self.principals_for_create_neuron();
+        let (skipped, neurons) = self.principals_for_create_neuron();
         let mut result = SweepResult {
             success: 0,
             failure: 0,
             skipped,
         };
 
-        for p in princpal_ids {
+        for (p, memo, dissolve_delay_seconds) in neurons {
             // Claim SNS neuron that we just funded (or at least tried to).
             let request = ManageNeuron {
                 subaccount: vec![],
@@ -716,7 +894,8 @@ impl Swap {
                         by: Some(manage_neuron::claim_or_refresh::By::MemoAndController(
                             manage_neuron::claim_or_refresh::MemoAndController {
                                 controller: Some(p),
-                                memo: 0,
+                                memo,
+                                dissolve_delay_seconds: Some(dissolve_delay_seconds),
                             },
                         )),
                     },
@@ -790,6 +969,11 @@ impl Swap {
     /// principal's tokens by multiple fees, and, second, so a third
     /// party cannot return the buyer's token that they intended to
     /// use to join the swap.
+    ///
+    /// The ICP of community fund neurons is never held by this
+    /// canister, so it cannot be refunded here; it is returned to the
+    /// neurons' maturity when the participation of the community fund
+    /// is settled in `finalize`.
     pub async fn error_refund_icp(
         &self,
         principal: PrincipalId,
@@ -918,7 +1102,11 @@ impl Swap {
     }
 
     /// In state 'committed'. Transfer SNS tokens from the swap
-    /// canister to each buyer.
+    /// canister to each buyer and to each community fund neuron.
+    ///
+    /// The SNS tokens of a community fund neuron are transferred to
+    /// the neuron staking subaccount of its controller, using the ID
+    /// of the NNS neuron as memo.
     ///
     /// Returns the following values:
     /// - the number of skipped buyers due balance less than fee or operation already in progress
@@ -948,24 +1136,55 @@ impl Swap {
                     continue;
                 }
             };
-            // Observe: memo == 0. Could be specified as an argument instead.
-            let dst_subaccount = compute_neuron_staking_subaccount_bytes(principal, 0);
-            let dst = Account {
-                owner: sns_governance.get(),
-                subaccount: Some(dst_subaccount),
-            };
-            let result = buyer_state
-                .sns_transfer_helper(&init, fee, dst, &ledger_stub)
+            let results = buyer_state
+                .sns_transfer_basket_helper(principal, &init, fee, &ledger_stub)
                 .await;
-            match result {
-                TransferResult::AmountTooSmall | TransferResult::AlreadyInProgress => {
-                    skipped += 1;
+            for result in results {
+                match result {
+                    TransferResult::AmountTooSmall | TransferResult::AlreadyInProgress => {
+                        skipped += 1;
+                    }
+                    TransferResult::Success(_) => {
+                        success += 1;
+                    }
+                    TransferResult::Failure(_) => {
+                        failure += 1;
+                    }
                 }
-                TransferResult::Success(_) => {
-                    success += 1;
+            }
+        }
+        for cf_participant in self.state_mut().cf_participants.iter_mut() {
+            let controller = match PrincipalId::from_str(&cf_participant.controller_principal_id) {
+                Ok(p) => p,
+                Err(msg) => {
+                    println!(
+                        "{}ERROR: cannot parse principal {} for disbursal: {}",
+                        LOG_PREFIX, cf_participant.controller_principal_id, msg
+                    );
+                    failure += cf_participant.cf_neurons.len() as u32;
+                    continue;
                 }
-                TransferResult::Failure(_) => {
-                    failure += 1;
+            };
+            for cf_neuron in cf_participant.cf_neurons.iter_mut() {
+                let dst_subaccount =
+                    compute_neuron_staking_subaccount_bytes(controller, cf_neuron.nns_neuron_id);
+                let dst = Account {
+                    owner: sns_governance.get(),
+                    subaccount: Some(dst_subaccount),
+                };
+                let result = cf_neuron
+                    .sns_transfer_helper(&init, fee, dst, &ledger_stub)
+                    .await;
+                match result {
+                    TransferResult::AmountTooSmall | TransferResult::AlreadyInProgress => {
+                        skipped += 1;
+                    }
+                    TransferResult::Success(_) => {
+                        success += 1;
+                    }
+                    TransferResult::Failure(_) => {
+                        failure += 1;
+                    }
                 }
             }
         }
@@ -976,36 +1195,65 @@ impl Swap {
         }
     }
 
-    /// Returns the set of principals for which a neuron may need to be
-    /// created together with the number of principals skipped.
+    /// Returns the set of (principal, memo, dissolve delay) triples
+    /// for which a neuron may need to be created together with the
+    /// number of neurons skipped. For buyers, there is one triple per
+    /// entry of the neuron basket; for community fund neurons, the
+    /// memo is the ID of the NNS neuron and the dissolve delay is
+    /// zero.
     ///
     /// If the swap is not committed, this results in an empty vector,
-    /// i.e., all principals are skipped. If the swap is committed, it
-    /// returns all principals for which the SNS tokens have been
+    /// i.e., all neurons are skipped. If the swap is committed, it
+    /// returns all neurons for which the SNS tokens have been
     /// disbursed.
     ///
     /// The swap does not keep track of which neurons that actually
     /// have been created; instead it relies on neuron creation being
     /// idempotent.
-    pub fn principals_for_create_neuron(&self) -> (u32, Vec<PrincipalId>) {
+    pub fn principals_for_create_neuron(&self) -> (u32, Vec<(PrincipalId, u64, u64)>) {
+        let cf_neurons = self.state().cf_participants.iter().flat_map(|x| {
+            x.cf_neurons.iter().map(move |y| {
+                (
+                    &x.controller_principal_id,
+                    y.nns_neuron_id,
+                    0,
+                    y.amount_sns_e8s,
+                )
+            })
+        });
         if self.state().lifecycle() != Lifecycle::Committed {
-            return (self.state().buyers.len() as u32, vec![]);
+            return (
+                (self.state().buyers.len() + cf_neurons.count()) as u32,
+                vec![],
+            );
         }
-        let mut principal_ids = Vec::new();
+        let buyers = self.state().buyers.iter().flat_map(|(x, y)| {
+            if y.neuron_basket.is_empty() {
+                // Committed before neuron baskets were introduced:
+                // a single neuron with memo zero.
+                vec![(x, 0, 0, y.amount_sns_e8s)]
+            } else {
+                y.neuron_basket
+                    .iter()
+                    .map(|z| (x, z.memo, z.dissolve_delay_seconds, z.amount_sns_e8s))
+                    .collect()
+            }
+        });
+        let mut neurons = Vec::new();
         let mut skipped = 0;
-        for (x, y) in self.state().buyers.iter() {
-            if y.amount_sns_e8s == 0 {
+        for (x, memo, dissolve_delay_seconds, amount_sns_e8s) in buyers.chain(cf_neurons) {
+            if amount_sns_e8s == 0 {
                 match PrincipalId::from_str(x).ok() {
                     None => {
                         skipped += 1;
                     }
-                    Some(xx) => principal_ids.push(xx),
+                    Some(xx) => neurons.push((xx, memo, dissolve_delay_seconds)),
                 }
             } else {
                 skipped += 1;
             }
         }
-        (skipped, principal_ids)
+        (skipped, neurons)
     }
 
     //
@@ -1044,23 +1292,25 @@ impl Swap {
     }
 
     /// The minimum number of participants have been achieved, and the
-    /// minimal total amount has been reached.
+    /// minimal total amount has been reached. Each community fund
+    /// participant counts as a single participant.
     pub fn sufficient_participation(&self) -> bool {
         if let Some(init) = &self.init {
             if let Some(state) = &self.state {
-                return state.buyers.len() >= (init.min_participants as usize)
-                    && state.buyer_total_icp_e8s() >= init.min_icp_e8s;
+                return state.buyers.len() + state.cf_participants.len()
+                    >= (init.min_participants as usize)
+                    && state.participant_total_icp_e8s() >= init.min_icp_e8s;
             }
         }
         false
     }
 
-    /// The total number of ICP contributed by all buyers is at least
-    /// the target ICP of the swap.
+    /// The total number of ICP contributed by all buyers and the
+    /// community fund is at least the target ICP of the swap.
     pub fn icp_target_reached(&self) -> bool {
         if let Some(init) = &self.init {
             if let Some(state) = &self.state {
-                return state.buyer_total_icp_e8s() >= init.max_icp_e8s;
+                return state.participant_total_icp_e8s() >= init.max_icp_e8s;
             }
         }
         false
@@ -1073,7 +1323,7 @@ impl Swap {
             return false;
         }
         // Possible optimization: both 'sufficient_participation' and
-        // 'icp_target_reached' compute 'buyer_total_icp_e8s', and
+        // 'icp_target_reached' compute 'participant_total_icp_e8s', and
         // this computation could be shared (or cached).
         if !self.sufficient_participation() {
             return false;
@@ -1090,10 +1340,11 @@ impl Swap {
 
     pub fn derived_state(&self) -> DerivedState {
         let buyer_total_icp_e8s = self.state().buyer_total_icp_e8s();
+        let participant_total_icp_e8s = self.state().participant_total_icp_e8s();
         DerivedState {
             buyer_total_icp_e8s,
-            sns_tokens_per_icp: ((self.state().sns_token_e8s as f64) / (buyer_total_icp_e8s as f64))
-                as f32,
+            sns_tokens_per_icp: ((self.state().sns_token_e8s as f64)
+                / (participant_total_icp_e8s as f64)) as f32,
         }
     }
 
@@ -1145,6 +1396,19 @@ impl Init {
         CanisterId::new(PrincipalId::from_str(&self.icp_ledger_canister_id).unwrap()).unwrap()
     }
 
+    /// Returns the neuron basket construction parameters, defaulting
+    /// to a basket of a single neuron with zero dissolve delay.
+    pub fn neuron_basket_construction_parameters_or_default(
+        &self,
+    ) -> NeuronBasketConstructionParameters {
+        self.neuron_basket_construction_parameters
+            .clone()
+            .unwrap_or(NeuronBasketConstructionParameters {
+                count: 1,
+                dissolve_delay_interval_seconds: 0,
+            })
+    }
+
     #[rustfmt::skip]
     pub fn is_valid(&self) -> bool {
         fn is_canister_id(role: &str, s: &str) -> bool {
@@ -1187,6 +1451,49 @@ impl Init {
 	    && (self.min_participants as u64).checked_mul(self.max_participant_icp_e8s).is_some()
             && self.max_icp_e8s >= (self.min_participants as u64).saturating_mul(self.min_participant_icp_e8s)
 	    && self.min_icp_e8s <= self.max_icp_e8s
+            && self.neuron_basket_construction_parameters_or_default().is_valid()
+    }
+}
+
+impl NeuronBasketConstructionParameters {
+    pub fn is_valid(&self) -> bool {
+        if self.count == 0 {
+            println!("{LOG_PREFIX}ERROR: the neuron basket must not be empty");
+            return false;
+        }
+        // The largest dissolve delay must not overflow.
+        if (self.count - 1)
+            .checked_mul(self.dissolve_delay_interval_seconds)
+            .is_none()
+        {
+            println!("{LOG_PREFIX}ERROR: the neuron basket dissolve delays overflow: {self:?}");
+            return false;
+        }
+        true
+    }
+
+    /// Splits `amount_sns_e8s` evenly into a basket of `count`
+    /// neurons. The i-th neuron has memo `i` and dissolve delay
+    /// `i * dissolve_delay_interval_seconds`; the first neuron also
+    /// receives the remainder of the division.
+    ///
+    /// Precondition: `is_valid()`
+    pub fn construct_basket(&self, amount_sns_e8s: u64) -> Vec<NeuronBasketEntry> {
+        assert!(self.count > 0);
+        let amount_per_neuron_e8s = amount_sns_e8s / self.count;
+        let remainder_e8s = amount_sns_e8s % self.count;
+        (0..self.count)
+            .map(|i| NeuronBasketEntry {
+                memo: i,
+                dissolve_delay_seconds: i * self.dissolve_delay_interval_seconds,
+                amount_sns_e8s: if i == 0 {
+                    amount_per_neuron_e8s + remainder_e8s
+                } else {
+                    amount_per_neuron_e8s
+                },
+                sns_disbursing: false,
+            })
+            .collect()
     }
 }
 
@@ -1194,8 +1501,25 @@ impl State {
     pub fn buyer_total_icp_e8s(&self) -> u64 {
         self.buyers.values().map(|x| x.amount_icp_e8s).sum()
     }
+    pub fn cf_total_icp_e8s(&self) -> u64 {
+        self.cf_participants
+            .iter()
+            .flat_map(|x| x.cf_neurons.iter())
+            .map(|x| x.amount_icp_e8s)
+            .sum()
+    }
+    /// The ICP contributed by the buyers and by the community fund.
+    pub fn participant_total_icp_e8s(&self) -> u64 {
+        self.buyer_total_icp_e8s()
+            .saturating_add(self.cf_total_icp_e8s())
+    }
     pub fn all_zeroed(&self) -> bool {
         self.buyers.values().all(|x| x.zeroed())
+            && self
+                .cf_participants
+                .iter()
+                .flat_map(|x| x.cf_neurons.iter())
+                .all(|x| x.amount_sns_e8s == 0 && !x.sns_disbursing)
     }
     pub fn is_valid(&self) -> bool {
         true
@@ -1212,6 +1536,7 @@ impl BuyerState {
             && self.amount_sns_e8s == 0
             && !self.icp_disbursing
             && !self.sns_disbursing
+            && self.neuron_basket.iter().all(|x| !x.sns_disbursing)
     }
 
     async fn icp_transfer_helper(
@@ -1264,50 +1589,123 @@ impl BuyerState {
         }
     }
 
-    async fn sns_transfer_helper(
+    /// Transfers the SNS tokens of each entry of the neuron basket to
+    /// the neuron staking subaccount of `principal` (the buyer) and the
+    /// entry's memo, returning the result of each transfer.
+    ///
+    /// `amount_sns_e8s` is kept equal to the amount yet to be
+    /// transferred.
+    async fn sns_transfer_basket_helper(
         &mut self,
+        principal: PrincipalId,
         init: &Init,
         fee: Tokens,
-        dst: Account,
         ledger_stub: &'_ dyn Fn(CanisterId) -> Box<dyn Ledger>,
-    ) -> TransferResult {
-        let sns_ledger = init.sns_ledger();
-        let amount = Tokens::from_e8s(self.amount_sns_e8s);
-        if amount <= fee {
-            // Skip: amount too small...
-            return TransferResult::AmountTooSmall;
-        }
-        if self.sns_disbursing {
-            // Operation in progress...
-            return TransferResult::AlreadyInProgress;
+    ) -> Vec<TransferResult> {
+        if self.neuron_basket.is_empty() && !self.sns_disbursing {
+            // Committed before neuron baskets were introduced: the
+            // buyer receives a single neuron with memo zero.
+            self.neuron_basket = vec![NeuronBasketEntry {
+                memo: 0,
+                dissolve_delay_seconds: 0,
+                amount_sns_e8s: self.amount_sns_e8s,
+                sns_disbursing: false,
+            }];
         }
-        self.sns_disbursing = true;
-        let result = ledger_stub(sns_ledger)
-            .transfer_funds(
-                amount.get_e8s().saturating_sub(fee.get_e8s()),
-                fee.get_e8s(),
-                None,
-                dst.clone(),
-                0,
+        let mut results = Vec::with_capacity(self.neuron_basket.len());
+        for entry in self.neuron_basket.iter_mut() {
+            let old_amount_sns_e8s = entry.amount_sns_e8s;
+            let dst = Account {
+                owner: init.sns_governance().get(),
+                subaccount: Some(compute_neuron_staking_subaccount_bytes(
+                    principal, entry.memo,
+                )),
+            };
+            let result = sns_transfer_helper(
+                &mut entry.amount_sns_e8s,
+                &mut entry.sns_disbursing,
+                init,
+                fee,
+                dst,
+                ledger_stub,
             )
             .await;
-        if !self.sns_disbursing {
-            println!("{}ERROR: SNS disburse logic error", LOG_PREFIX);
+            // Zero on success, unchanged otherwise.
+            let transferred_e8s = old_amount_sns_e8s - entry.amount_sns_e8s;
+            self.amount_sns_e8s = self.amount_sns_e8s.saturating_sub(transferred_e8s);
+            results.push(result);
         }
-        self.sns_disbursing = false;
-        match result {
-            Ok(h) => {
-                self.amount_sns_e8s = 0;
-                println!(
-                    "{}INFO: transferred {} SNS tokens to {} at height {}",
-                    LOG_PREFIX, amount, dst, h
-                );
-                TransferResult::Success(h)
-            }
-            Err(e) => {
-                println!("{}ERROR: failed to transfer {}: {}", LOG_PREFIX, amount, e);
-                TransferResult::Failure(e.to_string())
-            }
+        results
+    }
+}
+
+impl CfNeuron {
+    async fn sns_transfer_helper(
+        &mut self,
+        init: &Init,
+        fee: Tokens,
+        dst: Account,
+        ledger_stub: &'_ dyn Fn(CanisterId) -> Box<dyn Ledger>,
+    ) -> TransferResult {
+        sns_transfer_helper(
+            &mut self.amount_sns_e8s,
+            &mut self.sns_disbursing,
+            init,
+            fee,
+            dst,
+            ledger_stub,
+        )
+        .await
+    }
+}
+
+/// Transfers `amount_sns_e8s` (minus the fee) from the swap canister to
+/// `dst` on the SNS ledger, unless a transfer is already in progress as
+/// indicated by `sns_disbursing`. Zeroes `amount_sns_e8s` on success.
+async fn sns_transfer_helper(
+    amount_sns_e8s: &mut u64,
+    sns_disbursing: &mut bool,
+    init: &Init,
+    fee: Tokens,
+    dst: Account,
+    ledger_stub: &'_ dyn Fn(CanisterId) -> Box<dyn Ledger>,
+) -> TransferResult {
+    let sns_ledger = init.sns_ledger();
+    let amount = Tokens::from_e8s(*amount_sns_e8s);
+    if amount <= fee {
+        // Skip: amount too small...
+        return TransferResult::AmountTooSmall;
+    }
+    if *sns_disbursing {
+        // Operation in progress...
+        return TransferResult::AlreadyInProgress;
+    }
+    *sns_disbursing = true;
+    let result = ledger_stub(sns_ledger)
+        .transfer_funds(
+            amount.get_e8s().saturating_sub(fee.get_e8s()),
+            fee.get_e8s(),
+            None,
+            dst.clone(),
+            0,
+        )
+        .await;
+    if !*sns_disbursing {
+        println!("{}ERROR: SNS disburse logic error", LOG_PREFIX);
+    }
+    *sns_disbursing = false;
+    match result {
+        Ok(h) => {
+            *amount_sns_e8s = 0;
+            println!(
+                "{}INFO: transferred {} SNS tokens to {} at height {}",
+                LOG_PREFIX, amount, dst, h
+            );
+            TransferResult::Success(h)
+        }
+        Err(e) => {
+            println!("{}ERROR: failed to transfer {}: {}", LOG_PREFIX, amount, e);
+            TransferResult::Failure(e.to_string())
         }
     }
 }
@@ -1372,6 +1770,65 @@ impl SetOpenTimeWindowRequest {
     }
 }
 
+impl OpenRequest {
+    /// Returns a list of strings, each describing a defect in self.
+    ///
+    /// The open time window must be valid (see
+    /// SetOpenTimeWindowRequest::defects), the proposal ID must be
+    /// populated, every community fund neuron must contribute a
+    /// positive amount of ICP, and the community fund must not
+    /// contribute more than `max_icp_e8s` in total.
+    pub fn defects(&self, now_timestamp_seconds: u64, max_icp_e8s: u64) -> Vec<String> {
+        let mut result = SetOpenTimeWindowRequest {
+            open_time_window: self.open_time_window,
+        }
+        .defects(now_timestamp_seconds);
+
+        if self.open_sns_token_swap_proposal_id.is_none() {
+            result.push("The open_sns_token_swap_proposal_id field was not populated.".to_string());
+        }
+
+        let mut cf_total_icp_e8s: u64 = 0;
+        for cf_participant in &self.cf_participants {
+            if PrincipalId::from_str(&cf_participant.controller_principal_id).is_err() {
+                result.push(format!(
+                    "Unable to parse {} as the principal of a community fund participant",
+                    cf_participant.controller_principal_id
+                ));
+            }
+            if cf_participant.cf_neurons.is_empty() {
+                result.push(format!(
+                    "Community fund participant {} has no neurons",
+                    cf_participant.controller_principal_id
+                ));
+            }
+            for cf_neuron in &cf_participant.cf_neurons {
+                if cf_neuron.amount_icp_e8s == 0 {
+                    result.push(format!(
+                        "Community fund neuron {} contributes no ICP",
+                        cf_neuron.nns_neuron_id
+                    ));
+                }
+                if cf_neuron.amount_sns_e8s != 0 || cf_neuron.sns_disbursing {
+                    result.push(format!(
+                        "Community fund neuron {} must not have any SNS tokens",
+                        cf_neuron.nns_neuron_id
+                    ));
+                }
+                cf_total_icp_e8s = cf_total_icp_e8s.saturating_add(cf_neuron.amount_icp_e8s);
+            }
+        }
+        if cf_total_icp_e8s > max_icp_e8s {
+            result.push(format!(
+                "The community fund contributes {} ICP (e8s), more than the maximum of {}",
+                cf_total_icp_e8s, max_icp_e8s
+            ));
+        }
+
+        result
+    }
+}
+
 pub fn principal_to_subaccount(principal_id: &PrincipalId) -> Subaccount {
     let mut subaccount = [0; std::mem::size_of::<Subaccount>()];
     let principal_id = principal_id.as_slice();
@@ -28,8 +28,8 @@ use ic_sns_swap::{
         *,
     },
     swap::{
-        principal_to_subaccount, SnsGovernanceClient, SnsRootClient, TransferResult,
-        SECONDS_PER_DAY, START_OF_2022_TIMESTAMP_SECONDS,
+        principal_to_subaccount, NnsGovernanceClient, SnsGovernanceClient, SnsRootClient,
+        TransferResult, SECONDS_PER_DAY, START_OF_2022_TIMESTAMP_SECONDS,
     },
 };
 use ledger_canister::DEFAULT_TRANSFER_FEE;
@@ -68,6 +68,7 @@ fn init() -> Init {
         min_participant_icp_e8s: 100 * E8,
         max_participant_icp_e8s: 1000000 * E8,
         fallback_controller_principal_ids: vec![i2principal_id_string(1230578)],
+        neuron_basket_construction_parameters: None,
     };
 
     assert!(result.is_valid(), "{result:#?}");
@@ -82,6 +83,16 @@ fn fallback_controller_principal_ids_must_not_be_empty() {
     assert!(!init.is_valid(), "{init:#?}");
 }
 
+#[test]
+fn neuron_basket_must_not_be_empty() {
+    let mut init = init();
+    init.neuron_basket_construction_parameters = Some(NeuronBasketConstructionParameters {
+        count: 0,
+        dissolve_delay_interval_seconds: SECONDS_PER_DAY,
+    });
+    assert!(!init.is_valid(), "{init:#?}");
+}
+
 /// Expectation of one call on the mock Ledger.
 #[derive(Debug, Clone)]
 enum LedgerExpect {
@@ -877,6 +888,23 @@ fn i2principal_id_string(i: u64) -> String {
     Principal::from(PrincipalId::new_user_test_id(i)).to_text()
 }
 
+/// Records the calls made to NNS governance, all of which succeed.
+#[derive(Default, Debug)]
+struct SpyNnsGovernanceClient {
+    calls: Vec<SettleCommunityFundParticipation>,
+}
+
+#[async_trait]
+impl NnsGovernanceClient for SpyNnsGovernanceClient {
+    async fn settle_community_fund_participation(
+        &mut self,
+        request: SettleCommunityFundParticipation,
+    ) -> Result<Result<(), GovernanceError>, CanisterCallError> {
+        self.calls.push(request);
+        Ok(Ok(()))
+    }
+}
+
 #[tokio::test]
 async fn test_finalize_swap_ok() {
     // Step 0: Define helper types.
@@ -1010,6 +1038,7 @@ async fn test_finalize_swap_ok() {
         max_participant_icp_e8s: 100,
         min_participants: 1,
         fallback_controller_principal_ids: vec![i2principal_id_string(4242)],
+        neuron_basket_construction_parameters: None,
     });
     let nns_governance = PrincipalId::from(init.as_ref().unwrap().nns_governance());
     let mut swap = Swap {
@@ -1021,6 +1050,7 @@ async fn test_finalize_swap_ok() {
                     amount_sns_e8s: 0,
                     icp_disbursing: false,
                     sns_disbursing: false,
+                    neuron_basket: vec![],
                 },
 
                 i2principal_id_string(1002) => BuyerState {
@@ -1028,6 +1058,7 @@ async fn test_finalize_swap_ok() {
                     amount_sns_e8s: 0,
                     icp_disbursing: false,
                     sns_disbursing: false,
+                    neuron_basket: vec![],
                 },
 
                 i2principal_id_string(1003) => BuyerState {
@@ -1035,11 +1066,14 @@ async fn test_finalize_swap_ok() {
                     amount_sns_e8s: 0,
                     icp_disbursing: false,
                     sns_disbursing: false,
+                    neuron_basket: vec![],
                 },
             },
             lifecycle: Pending as i32,
             sns_token_e8s: 0,
             open_time_window: None,
+            cf_participants: vec![],
+            open_sns_token_swap_proposal_id: None,
         }),
     };
     swap.set_open_time_window(
@@ -1066,12 +1100,14 @@ async fn test_finalize_swap_ok() {
 
     let mut sns_root_client = ExplodingSnsRootClient::default();
     let mut sns_governance_client = SpySnsGovernanceClient::default();
+    let mut nns_governance_client = SpyNnsGovernanceClient::default();
 
     // Step 2: Run the code under test. To wit, finalize_swap.
     let result = swap
         .finalize(
             &mut sns_root_client,
             &mut sns_governance_client,
+            &mut nns_governance_client,
             ledger_factory,
             ledger_factory,
         )
@@ -1098,6 +1134,7 @@ async fn test_finalize_swap_ok() {
             }),
             sns_governance_normal_mode_enabled: Some(SetModeCallResult { possibility: None }),
             set_dapp_controllers_result: None,
+            settle_community_fund_participation_result: None,
         },
     );
 
@@ -1382,6 +1419,7 @@ async fn test_finalize_swap_abort() {
         min_participants: 2,
 
         fallback_controller_principal_ids: vec![i2principal_id_string(4242)],
+        neuron_basket_construction_parameters: None,
     };
     let nns_governance = PrincipalId::from(init.nns_governance());
     let mut swap = Swap {
@@ -1391,6 +1429,8 @@ async fn test_finalize_swap_abort() {
             lifecycle: Pending as i32,
             sns_token_e8s: 0,
             open_time_window: None,
+            cf_participants: vec![],
+            open_sns_token_swap_proposal_id: None,
         }),
     };
     swap.set_open_time_window(
@@ -1428,12 +1468,14 @@ async fn test_finalize_swap_abort() {
 
     let mut sns_root_client = SpySnsRootClient::default();
     let mut sns_governance_client = SpySnsGovernanceClient::default();
+    let mut nns_governance_client = SpyNnsGovernanceClient::default();
 
     // Step 2: Run the code under test. To wit, finalize_swap.
     let result = swap
         .finalize(
             &mut sns_root_client,
             &mut sns_governance_client,
+            &mut nns_governance_client,
             ledger_factory,
             ledger_factory,
         )
@@ -1459,6 +1501,7 @@ async fn test_finalize_swap_abort() {
                     }
                 )),
             }),
+            settle_community_fund_participation_result: None,
         },
     );
 
@@ -1863,3 +1906,570 @@ fn test_get_buyer_state() {
         .buyer_state
         .is_none());
 }
+
+/// Returns community fund participants 1001 and 1002, with two and one
+/// neurons, contributing 20, 30 and 50 ICP respectively.
+fn cf_participants() -> Vec<CfParticipant> {
+    vec![
+        CfParticipant {
+            controller_principal_id: i2principal_id_string(1001),
+            cf_neurons: vec![
+                CfNeuron {
+                    nns_neuron_id: 1,
+                    amount_icp_e8s: 20 * E8,
+                    ..Default::default()
+                },
+                CfNeuron {
+                    nns_neuron_id: 2,
+                    amount_icp_e8s: 30 * E8,
+                    ..Default::default()
+                },
+            ],
+        },
+        CfParticipant {
+            controller_principal_id: i2principal_id_string(1002),
+            cf_neurons: vec![CfNeuron {
+                nns_neuron_id: 3,
+                amount_icp_e8s: 50 * E8,
+                ..Default::default()
+            }],
+        },
+    ]
+}
+
+fn open_request(cf_participants: Vec<CfParticipant>) -> OpenRequest {
+    OpenRequest {
+        open_time_window: Some(OPEN_TIME_WINDOW),
+        cf_participants,
+        open_sns_token_swap_proposal_id: Some(4711),
+    }
+}
+
+/// Returns a pending swap that has received 100k SNS tokens.
+fn funded_swap(init: Init) -> Swap {
+    let mut swap = Swap::new(init);
+    swap.refresh_sns_token_e8s(
+        SWAP_CANISTER_ID,
+        &mock_stub(vec![LedgerExpect::AccountBalance(
+            Account {
+                owner: SWAP_CANISTER_ID.get(),
+                subaccount: None,
+            },
+            Ok(Tokens::from_e8s(100000 * E8)),
+        )]),
+    )
+    .now_or_never()
+    .unwrap()
+    .unwrap();
+    swap
+}
+
+#[test]
+fn test_open_with_community_fund() {
+    let nns_governance = PrincipalId::from(init().nns_governance());
+    let mut swap = funded_swap(init());
+
+    // Only NNS governance can open the swap.
+    let wrong_canister = PrincipalId::from(init().icp_ledger());
+    assert!(swap
+        .open_with_community_fund(
+            wrong_canister,
+            START_TIMESTAMP_SECONDS,
+            &open_request(cf_participants())
+        )
+        .is_err());
+
+    // The community fund cannot contribute more than the maximum.
+    let mut request = open_request(cf_participants());
+    request.cf_participants[1].cf_neurons[0].amount_icp_e8s = init().max_icp_e8s;
+    assert!(swap
+        .open_with_community_fund(nns_governance, START_TIMESTAMP_SECONDS, &request)
+        .is_err());
+
+    // The proposal ID is required.
+    let mut request = open_request(cf_participants());
+    request.open_sns_token_swap_proposal_id = None;
+    assert!(swap
+        .open_with_community_fund(nns_governance, START_TIMESTAMP_SECONDS, &request)
+        .is_err());
+    assert_eq!(swap.state().lifecycle(), Pending);
+
+    // Before the start time, the swap stays pending.
+    assert!(swap
+        .open_with_community_fund(
+            nns_governance,
+            START_TIMESTAMP_SECONDS - 1,
+            &open_request(cf_participants())
+        )
+        .is_ok());
+    assert_eq!(swap.state().lifecycle(), Pending);
+    assert_eq!(swap.state().cf_total_icp_e8s(), 100 * E8);
+    assert_eq!(swap.state().participant_total_icp_e8s(), 100 * E8);
+    assert_eq!(swap.state().open_sns_token_swap_proposal_id, Some(4711));
+
+    // The swap cannot be opened twice.
+    assert!(swap
+        .open_with_community_fund(
+            nns_governance,
+            START_TIMESTAMP_SECONDS,
+            &open_request(cf_participants())
+        )
+        .is_err());
+
+    assert!(open_at_start(&mut swap).is_ok());
+    assert_eq!(swap.state().lifecycle(), Lifecycle::Open);
+}
+
+#[test]
+fn test_community_fund_counts_towards_participation() {
+    let nns_governance = PrincipalId::from(init().nns_governance());
+    let mut swap = funded_swap(Init {
+        max_icp_e8s: 1000 * E8,
+        min_icp_e8s: 150 * E8,
+        max_participant_icp_e8s: 1000 * E8,
+        ..init()
+    });
+    swap.open_with_community_fund(
+        nns_governance,
+        START_TIMESTAMP_SECONDS,
+        &open_request(cf_participants()),
+    )
+    .unwrap();
+    assert_eq!(swap.state().lifecycle(), Lifecycle::Open);
+    // 100 ICP from the community fund is not enough.
+    assert!(!swap.sufficient_participation());
+
+    // One buyer brings the total to 200 ICP and the number of
+    // participants to 3.
+    swap.refresh_buyer_token_e8s(
+        *TEST_USER1_PRINCIPAL,
+        SWAP_CANISTER_ID,
+        &mock_stub(vec![LedgerExpect::AccountBalance(
+            Account {
+                owner: SWAP_CANISTER_ID.get(),
+                subaccount: Some(principal_to_subaccount(&*TEST_USER1_PRINCIPAL)),
+            },
+            Ok(Tokens::from_e8s(100 * E8)),
+        )]),
+    )
+    .now_or_never()
+    .unwrap()
+    .unwrap();
+    assert!(swap.sufficient_participation());
+    assert!(swap.try_commit_or_abort(END_TIMESTAMP_SECONDS));
+    assert_eq!(swap.state().lifecycle(), Committed);
+
+    // The SNS tokens are split between the buyer and the community fund
+    // neurons in proportion to their ICP.
+    assert_eq!(
+        swap.state()
+            .get_buyer_state(&TEST_USER1_PRINCIPAL)
+            .unwrap()
+            .amount_sns_e8s,
+        50000 * E8
+    );
+    let cf_amounts_sns_e8s = swap
+        .state()
+        .cf_participants
+        .iter()
+        .flat_map(|x| x.cf_neurons.iter())
+        .map(|x| (x.nns_neuron_id, x.amount_sns_e8s))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        cf_amounts_sns_e8s,
+        vec![(1, 10000 * E8), (2, 15000 * E8), (3, 25000 * E8)]
+    );
+}
+
+#[tokio::test]
+async fn test_finalize_swap_with_community_fund_committed() {
+    #[derive(Default, Debug)]
+    struct SpySnsGovernanceClient {
+        claimed: Vec<(PrincipalId, u64)>,
+    }
+    #[async_trait]
+    impl SnsGovernanceClient for SpySnsGovernanceClient {
+        async fn manage_neuron(
+            &mut self,
+            request: ManageNeuron,
+        ) -> Result<ManageNeuronResponse, CanisterCallError> {
+            if let Some(manage_neuron::Command::ClaimOrRefresh(manage_neuron::ClaimOrRefresh {
+                by: Some(manage_neuron::claim_or_refresh::By::MemoAndController(m)),
+            })) = request.command
+            {
+                self.claimed.push((m.controller.unwrap(), m.memo));
+            }
+            Ok(ManageNeuronResponse {
+                command: Some(manage_neuron_response::Command::ClaimOrRefresh(
+                    ClaimOrRefreshResponse::default(),
+                )),
+            })
+        }
+        async fn set_mode(
+            &mut self,
+            _request: SetMode,
+        ) -> Result<SetModeResponse, CanisterCallError> {
+            Ok(SetModeResponse {})
+        }
+    }
+    #[derive(Default, Debug)]
+    struct ExplodingSnsRootClient;
+    #[async_trait]
+    impl SnsRootClient for ExplodingSnsRootClient {
+        async fn set_dapp_controllers(
+            &mut self,
+            _request: SetDappControllersRequest,
+        ) -> Result<SetDappControllersResponse, CanisterCallError> {
+            unimplemented!();
+        }
+    }
+
+    // The community fund alone satisfies the participation requirements.
+    let nns_governance = PrincipalId::from(init().nns_governance());
+    let mut swap = funded_swap(Init {
+        max_icp_e8s: 100 * E8,
+        min_participants: 2,
+        min_participant_icp_e8s: E8,
+        max_participant_icp_e8s: 100 * E8,
+        ..init()
+    });
+    swap.open_with_community_fund(
+        nns_governance,
+        START_TIMESTAMP_SECONDS,
+        &open_request(cf_participants()),
+    )
+    .unwrap();
+    assert!(swap.icp_target_reached());
+    assert!(swap.try_commit_or_abort(START_TIMESTAMP_SECONDS));
+    assert_eq!(swap.state().lifecycle(), Committed);
+
+    let fee = DEFAULT_TRANSFER_FEE.get_e8s();
+    let dst = |principal: u64, memo: u64| Account {
+        owner: SNS_GOVERNANCE_CANISTER_ID.get(),
+        subaccount: Some(compute_neuron_staking_subaccount_bytes(
+            PrincipalId::from_str(&i2principal_id_string(principal)).unwrap(),
+            memo,
+        )),
+    };
+    let ledger_factory = mock_stub(vec![
+        LedgerExpect::TransferFunds(20000 * E8 - fee, fee, None, dst(1001, 1), 0, Ok(1)),
+        LedgerExpect::TransferFunds(30000 * E8 - fee, fee, None, dst(1001, 2), 0, Ok(2)),
+        LedgerExpect::TransferFunds(50000 * E8 - fee, fee, None, dst(1002, 3), 0, Ok(3)),
+    ]);
+    let mut sns_governance_client = SpySnsGovernanceClient::default();
+    let mut nns_governance_client = SpyNnsGovernanceClient::default();
+    let result = swap
+        .finalize(
+            &mut ExplodingSnsRootClient,
+            &mut sns_governance_client,
+            &mut nns_governance_client,
+            &ledger_factory,
+            &ledger_factory,
+        )
+        .await;
+
+    assert_eq!(
+        result.sweep_sns,
+        Some(SweepResult {
+            success: 3,
+            failure: 0,
+            skipped: 0,
+        })
+    );
+    assert_eq!(
+        result.settle_community_fund_participation_result,
+        Some(SettleCommunityFundParticipationResult {
+            possibility: Some(settle_community_fund_participation_result::Possibility::Ok(
+                settle_community_fund_participation_result::Response {
+                    governance_error: None,
+                }
+            )),
+        })
+    );
+    assert!(swap.state().all_zeroed());
+
+    // One SNS neuron is claimed per community fund neuron, with the NNS
+    // neuron ID as memo.
+    let principal = |i| PrincipalId::from_str(&i2principal_id_string(i)).unwrap();
+    assert_eq!(
+        sns_governance_client.claimed,
+        vec![
+            (principal(1001), 1),
+            (principal(1001), 2),
+            (principal(1002), 3)
+        ]
+    );
+
+    // NNS governance is asked to mint the ICP to SNS governance.
+    assert_eq!(
+        nns_governance_client.calls,
+        vec![SettleCommunityFundParticipation {
+            open_sns_token_swap_proposal_id: Some(4711),
+            result: Some(settle_community_fund_participation::Result::Committed(
+                settle_community_fund_participation::Committed {
+                    sns_governance_canister_id: Some(SNS_GOVERNANCE_CANISTER_ID.get()),
+                }
+            )),
+        }]
+    );
+}
+
+#[test]
+fn test_neuron_basket() {
+    let nns_governance = PrincipalId::from(init().nns_governance());
+    let mut swap = funded_swap(Init {
+        max_icp_e8s: 1000 * E8,
+        min_icp_e8s: 150 * E8,
+        max_participant_icp_e8s: 1000 * E8,
+        neuron_basket_construction_parameters: Some(NeuronBasketConstructionParameters {
+            count: 3,
+            dissolve_delay_interval_seconds: 30 * SECONDS_PER_DAY,
+        }),
+        ..init()
+    });
+    swap.open_with_community_fund(
+        nns_governance,
+        START_TIMESTAMP_SECONDS,
+        &open_request(cf_participants()),
+    )
+    .unwrap();
+    swap.refresh_buyer_token_e8s(
+        *TEST_USER1_PRINCIPAL,
+        SWAP_CANISTER_ID,
+        &mock_stub(vec![LedgerExpect::AccountBalance(
+            Account {
+                owner: SWAP_CANISTER_ID.get(),
+                subaccount: Some(principal_to_subaccount(&*TEST_USER1_PRINCIPAL)),
+            },
+            Ok(Tokens::from_e8s(100 * E8)),
+        )]),
+    )
+    .now_or_never()
+    .unwrap()
+    .unwrap();
+    assert!(swap.try_commit_or_abort(END_TIMESTAMP_SECONDS));
+    assert_eq!(swap.state().lifecycle(), Committed);
+
+    // The 50k SNS tokens of the buyer are split into three neurons with
+    // staggered dissolve delays; the first one gets the remainder.
+    let buyer_state = swap.state().get_buyer_state(&TEST_USER1_PRINCIPAL).unwrap();
+    assert_eq!(buyer_state.amount_sns_e8s, 50000 * E8);
+    let third_e8s = 50000 * E8 / 3;
+    assert_eq!(
+        buyer_state.neuron_basket,
+        vec![
+            NeuronBasketEntry {
+                memo: 0,
+                dissolve_delay_seconds: 0,
+                amount_sns_e8s: third_e8s + 2,
+                sns_disbursing: false,
+            },
+            NeuronBasketEntry {
+                memo: 1,
+                dissolve_delay_seconds: 30 * SECONDS_PER_DAY,
+                amount_sns_e8s: third_e8s,
+                sns_disbursing: false,
+            },
+            NeuronBasketEntry {
+                memo: 2,
+                dissolve_delay_seconds: 60 * SECONDS_PER_DAY,
+                amount_sns_e8s: third_e8s,
+                sns_disbursing: false,
+            },
+        ]
+    );
+    // Nothing can be claimed before the tokens have been transferred.
+    assert_eq!(swap.principals_for_create_neuron(), (6, vec![]));
+
+    // Each neuron of the basket is funded in its own subaccount. The
+    // transfer to the last neuron fails.
+    let fee = DEFAULT_TRANSFER_FEE.get_e8s();
+    let dst = |principal: PrincipalId, memo: u64| Account {
+        owner: SNS_GOVERNANCE_CANISTER_ID.get(),
+        subaccount: Some(compute_neuron_staking_subaccount_bytes(principal, memo)),
+    };
+    let buyer = *TEST_USER1_PRINCIPAL;
+    let cf = |i| PrincipalId::from_str(&i2principal_id_string(i)).unwrap();
+    let result = swap
+        .sweep_sns(
+            DEFAULT_TRANSFER_FEE,
+            &mock_stub(vec![
+                LedgerExpect::TransferFunds(
+                    third_e8s + 2 - fee,
+                    fee,
+                    None,
+                    dst(buyer, 0),
+                    0,
+                    Ok(1),
+                ),
+                LedgerExpect::TransferFunds(third_e8s - fee, fee, None, dst(buyer, 1), 0, Ok(2)),
+                LedgerExpect::TransferFunds(third_e8s - fee, fee, None, dst(buyer, 2), 0, Err(1)),
+                LedgerExpect::TransferFunds(
+                    10000 * E8 - fee,
+                    fee,
+                    None,
+                    dst(cf(1001), 1),
+                    0,
+                    Ok(3),
+                ),
+                LedgerExpect::TransferFunds(
+                    15000 * E8 - fee,
+                    fee,
+                    None,
+                    dst(cf(1001), 2),
+                    0,
+                    Ok(4),
+                ),
+                LedgerExpect::TransferFunds(
+                    25000 * E8 - fee,
+                    fee,
+                    None,
+                    dst(cf(1002), 3),
+                    0,
+                    Ok(5),
+                ),
+            ]),
+        )
+        .now_or_never()
+        .unwrap();
+    assert_eq!(
+        result,
+        SweepResult {
+            success: 5,
+            failure: 1,
+            skipped: 0,
+        }
+    );
+    assert_eq!(
+        swap.state()
+            .get_buyer_state(&TEST_USER1_PRINCIPAL)
+            .unwrap()
+            .amount_sns_e8s,
+        third_e8s
+    );
+
+    // The funded neurons can be claimed with their dissolve delays;
+    // the last neuron of the basket is skipped until it is funded.
+    assert_eq!(
+        swap.principals_for_create_neuron(),
+        (
+            1,
+            vec![
+                (buyer, 0, 0),
+                (buyer, 1, 30 * SECONDS_PER_DAY),
+                (cf(1001), 1, 0),
+                (cf(1001), 2, 0),
+                (cf(1002), 3, 0),
+            ]
+        )
+    );
+
+    // Retrying only transfers the remaining neuron.
+    let result = swap
+        .sweep_sns(
+            DEFAULT_TRANSFER_FEE,
+            &mock_stub(vec![LedgerExpect::TransferFunds(
+                third_e8s - fee,
+                fee,
+                None,
+                dst(buyer, 2),
+                0,
+                Ok(6),
+            )]),
+        )
+        .now_or_never()
+        .unwrap();
+    assert_eq!(
+        result,
+        SweepResult {
+            success: 1,
+            failure: 0,
+            skipped: 5,
+        }
+    );
+    assert!(swap.state().all_zeroed());
+    assert_eq!(swap.principals_for_create_neuron().0, 0);
+}
+
+#[tokio::test]
+async fn test_finalize_swap_with_community_fund_aborted() {
+    #[derive(Default, Debug)]
+    struct StubSnsRootClient;
+    #[async_trait]
+    impl SnsRootClient for StubSnsRootClient {
+        async fn set_dapp_controllers(
+            &mut self,
+            _request: SetDappControllersRequest,
+        ) -> Result<SetDappControllersResponse, CanisterCallError> {
+            Ok(SetDappControllersResponse {
+                failed_updates: vec![],
+            })
+        }
+    }
+    #[derive(Default, Debug)]
+    struct ExplodingSnsGovernanceClient;
+    #[async_trait]
+    impl SnsGovernanceClient for ExplodingSnsGovernanceClient {
+        async fn manage_neuron(
+            &mut self,
+            _request: ManageNeuron,
+        ) -> Result<ManageNeuronResponse, CanisterCallError> {
+            unimplemented!();
+        }
+        async fn set_mode(
+            &mut self,
+            _request: SetMode,
+        ) -> Result<SetModeResponse, CanisterCallError> {
+            unimplemented!();
+        }
+    }
+
+    // The swap is never funded, hence it never opens, even though the
+    // community fund would satisfy the participation requirements.
+    let nns_governance = PrincipalId::from(init().nns_governance());
+    let mut swap = Swap::new(Init {
+        min_participants: 2,
+        ..init()
+    });
+    swap.open_with_community_fund(
+        nns_governance,
+        START_TIMESTAMP_SECONDS,
+        &open_request(cf_participants()),
+    )
+    .unwrap();
+    assert_eq!(swap.state().lifecycle(), Pending);
+    assert!(swap.sufficient_participation());
+    assert!(swap.try_commit_or_abort(END_TIMESTAMP_SECONDS));
+    assert_eq!(swap.state().lifecycle(), Aborted);
+
+    let mut nns_governance_client = SpyNnsGovernanceClient::default();
+    let result = swap
+        .finalize(
+            &mut StubSnsRootClient,
+            &mut ExplodingSnsGovernanceClient,
+            &mut nns_governance_client,
+            mock_stub(vec![]),
+            mock_stub(vec![]),
+        )
+        .await;
+
+    // No ICP is refunded by the swap; NNS governance restores the maturity
+    // of the community fund neurons instead.
+    assert_eq!(
+        result.sweep_icp,
+        Some(SweepResult {
+            success: 0,
+            failure: 0,
+            skipped: 0,
+        })
+    );
+    assert_eq!(
+        nns_governance_client.calls,
+        vec![SettleCommunityFundParticipation {
+            open_sns_token_swap_proposal_id: Some(4711),
+            result: Some(settle_community_fund_participation::Result::Aborted(
+                settle_community_fund_participation::Aborted {}
+            )),
+        }]
+    );
+}
@@ -489,6 +489,7 @@ impl SnsCanisters<'_> {
                         by: Some(By::MemoAndController(MemoAndController {
                             memo: NONCE,
                             controller: None,
+                            dissolve_delay_seconds: None,
                         })),
                     })),
                 },
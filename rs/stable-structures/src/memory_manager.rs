@@ -0,0 +1,322 @@
+//! A memory manager that splits a single memory into up to 255 virtual
+//! memories, each of which can grow independently.
+//!
+//! The underlying memory is divided into buckets of `bucket_size_in_pages`
+//! pages. Whenever a virtual memory grows beyond the buckets it owns, the
+//! next free bucket is assigned to it. A virtual memory is thus made of a
+//! list of buckets that are not necessarily contiguous, and no virtual
+//! memory needs to reserve space upfront.
+//!
+//! # V1 layout
+//!
+//! ```text
+//! -------------------------------------------------- <- Address 0
+//! Magic "MGR"                           ↕ 3 bytes
+//! --------------------------------------------------
+//! Layout version                        ↕ 1 byte
+//! --------------------------------------------------
+//! Number of allocated buckets           ↕ 2 bytes
+//! --------------------------------------------------
+//! Bucket size (in pages) = N            ↕ 2 bytes
+//! --------------------------------------------------
+//! Reserved space                        ↕ 32 bytes
+//! --------------------------------------------------
+//! Size of memory 0 (in pages)           ↕ 8 bytes
+//! --------------------------------------------------
+//! ...
+//! --------------------------------------------------
+//! Size of memory 254 (in pages)         ↕ 8 bytes
+//! -------------------------------------------------- <- Bucket allocations
+//! Memory owning bucket 0                ↕ 1 byte
+//! --------------------------------------------------
+//! ...
+//! --------------------------------------------------
+//! Memory owning bucket 32767            ↕ 1 byte
+//! --------------------------------------------------
+//! Unused space
+//! -------------------------------------------------- <- Page 1
+//! Bucket 0                              ↕ N pages
+//! --------------------------------------------------
+//! Bucket 1                              ↕ N pages
+//! --------------------------------------------------
+//! ...
+//! ```
+use crate::{write, Memory, WASM_PAGE_SIZE};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: &[u8; 3] = b"MGR"; // short for "manager"
+const LAYOUT_VERSION: u8 = 1;
+
+/// The maximum number of virtual memories.
+const MAX_NUM_MEMORIES: u8 = 255;
+
+/// The maximum number of buckets the underlying memory can be divided into.
+const MAX_NUM_BUCKETS: u64 = 32768;
+
+/// With 128 pages per bucket, the virtual memories can use up to 256 GiB.
+const DEFAULT_BUCKET_SIZE_IN_PAGES: u16 = 128;
+
+/// Marks buckets that are not assigned to any virtual memory.
+const UNALLOCATED_BUCKET_MARKER: u8 = MAX_NUM_MEMORIES;
+
+const HEADER_RESERVED_BYTES: u64 = 32;
+const MEMORY_SIZES_ADDRESS: u64 = 3 + 1 + 2 + 2 + HEADER_RESERVED_BYTES;
+const BUCKET_ALLOCATIONS_ADDRESS: u64 = MEMORY_SIZES_ADDRESS + 8 * MAX_NUM_MEMORIES as u64;
+
+/// The buckets start after the first page, which holds the header.
+const BUCKETS_OFFSET_IN_PAGES: u64 = 1;
+
+/// The ID of a virtual memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MemoryId(u8);
+
+impl MemoryId {
+    pub const fn new(id: u8) -> Self {
+        assert!(id != UNALLOCATED_BUCKET_MARKER);
+        Self(id)
+    }
+}
+
+/// Manages the virtual memories stored in a single memory.
+pub struct MemoryManager<M: Memory> {
+    inner: Rc<RefCell<MemoryManagerInner<M>>>,
+}
+
+impl<M: Memory> MemoryManager<M> {
+    /// Loads the memory manager from `memory`, or initializes a new one if
+    /// `memory` does not hold one.
+    ///
+    /// Initializing a new memory manager overwrites the beginning of
+    /// `memory`, so anything `memory` held before must have been read out
+    /// already; see [MemoryManager::is_initialized].
+    pub fn init(memory: M) -> Self {
+        Self::init_with_bucket_size(memory, DEFAULT_BUCKET_SIZE_IN_PAGES)
+    }
+
+    /// Like [MemoryManager::init], but with a custom bucket size. The bucket
+    /// size of an existing memory manager is kept.
+    pub fn init_with_bucket_size(memory: M, bucket_size_in_pages: u16) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(MemoryManagerInner::init(
+                memory,
+                bucket_size_in_pages,
+            ))),
+        }
+    }
+
+    /// Returns true if `memory` holds a memory manager.
+    pub fn is_initialized(memory: &M) -> bool {
+        if memory.size() == 0 {
+            return false;
+        }
+        let mut magic = [0; 3];
+        memory.read(0, &mut magic);
+        &magic == MAGIC
+    }
+
+    /// Returns the virtual memory with the given ID.
+    pub fn get(&self, id: MemoryId) -> VirtualMemory<M> {
+        VirtualMemory {
+            id,
+            memory_manager: Rc::clone(&self.inner),
+        }
+    }
+}
+
+/// A virtual memory handed out by a [MemoryManager].
+#[derive(Clone)]
+pub struct VirtualMemory<M: Memory> {
+    id: MemoryId,
+    memory_manager: Rc<RefCell<MemoryManagerInner<M>>>,
+}
+
+impl<M: Memory> Memory for VirtualMemory<M> {
+    fn size(&self) -> u64 {
+        self.memory_manager.borrow().memory_size(self.id)
+    }
+
+    fn grow(&self, pages: u64) -> i64 {
+        self.memory_manager.borrow_mut().grow(self.id, pages)
+    }
+
+    fn read(&self, offset: u64, dst: &mut [u8]) {
+        self.memory_manager.borrow().read(self.id, offset, dst)
+    }
+
+    fn write(&self, offset: u64, src: &[u8]) {
+        self.memory_manager.borrow().write(self.id, offset, src)
+    }
+}
+
+struct MemoryManagerInner<M: Memory> {
+    memory: M,
+    bucket_size_in_pages: u16,
+    allocated_buckets: u16,
+    memory_sizes_in_pages: [u64; MAX_NUM_MEMORIES as usize],
+    // The buckets owned by each memory, in the order of the memory.
+    memory_buckets: Vec<Vec<u16>>,
+}
+
+impl<M: Memory> MemoryManagerInner<M> {
+    fn init(memory: M, bucket_size_in_pages: u16) -> Self {
+        if MemoryManager::is_initialized(&memory) {
+            Self::load(memory)
+        } else {
+            Self::new(memory, bucket_size_in_pages)
+        }
+    }
+
+    fn new(memory: M, bucket_size_in_pages: u16) -> Self {
+        assert!(bucket_size_in_pages > 0);
+        let manager = Self {
+            memory,
+            bucket_size_in_pages,
+            allocated_buckets: 0,
+            memory_sizes_in_pages: [0; MAX_NUM_MEMORIES as usize],
+            memory_buckets: vec![vec![]; MAX_NUM_MEMORIES as usize],
+        };
+        let mut header = vec![];
+        header.extend_from_slice(MAGIC);
+        header.push(LAYOUT_VERSION);
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&bucket_size_in_pages.to_le_bytes());
+        header.extend_from_slice(&[0; HEADER_RESERVED_BYTES as usize]);
+        header.extend_from_slice(&[0; 8 * MAX_NUM_MEMORIES as usize]);
+        header.extend_from_slice(&[UNALLOCATED_BUCKET_MARKER; MAX_NUM_BUCKETS as usize]);
+        write(&manager.memory, 0, &header);
+        manager
+    }
+
+    fn load(memory: M) -> Self {
+        let mut header = [0; BUCKET_ALLOCATIONS_ADDRESS as usize];
+        memory.read(0, &mut header);
+        assert_eq!(&header[0..3], MAGIC, "Bad magic.");
+        assert_eq!(header[3], LAYOUT_VERSION, "Unsupported layout version.");
+        let allocated_buckets = u16::from_le_bytes([header[4], header[5]]);
+        let bucket_size_in_pages = u16::from_le_bytes([header[6], header[7]]);
+
+        let mut memory_sizes_in_pages = [0; MAX_NUM_MEMORIES as usize];
+        for (i, size) in memory_sizes_in_pages.iter_mut().enumerate() {
+            let start = MEMORY_SIZES_ADDRESS as usize + 8 * i;
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&header[start..start + 8]);
+            *size = u64::from_le_bytes(bytes);
+        }
+
+        let mut allocations = vec![0; allocated_buckets as usize];
+        memory.read(BUCKET_ALLOCATIONS_ADDRESS, &mut allocations);
+        let mut memory_buckets = vec![vec![]; MAX_NUM_MEMORIES as usize];
+        for (bucket, memory_id) in allocations.into_iter().enumerate() {
+            memory_buckets[memory_id as usize].push(bucket as u16);
+        }
+
+        Self {
+            memory,
+            bucket_size_in_pages,
+            allocated_buckets,
+            memory_sizes_in_pages,
+            memory_buckets,
+        }
+    }
+
+    fn memory_size(&self, id: MemoryId) -> u64 {
+        self.memory_sizes_in_pages[id.0 as usize]
+    }
+
+    fn grow(&mut self, id: MemoryId, pages: u64) -> i64 {
+        let old_size = self.memory_size(id);
+        let new_size = match old_size.checked_add(pages) {
+            Some(new_size) => new_size,
+            None => return -1,
+        };
+        let bucket_size = self.bucket_size_in_pages as u64;
+        let required_buckets = (new_size + bucket_size - 1) / bucket_size;
+        let owned_buckets = self.memory_buckets[id.0 as usize].len() as u64;
+        let new_buckets = required_buckets.saturating_sub(owned_buckets);
+        let total_buckets = self.allocated_buckets as u64 + new_buckets;
+        if total_buckets > MAX_NUM_BUCKETS {
+            return -1;
+        }
+
+        // Make sure the underlying memory covers the new buckets.
+        let required_pages = BUCKETS_OFFSET_IN_PAGES + total_buckets * bucket_size;
+        let current_pages = self.memory.size();
+        if required_pages > current_pages && self.memory.grow(required_pages - current_pages) == -1
+        {
+            return -1;
+        }
+
+        for _ in 0..new_buckets {
+            let bucket = self.allocated_buckets;
+            write(
+                &self.memory,
+                BUCKET_ALLOCATIONS_ADDRESS + bucket as u64,
+                &[id.0],
+            );
+            self.memory_buckets[id.0 as usize].push(bucket);
+            self.allocated_buckets += 1;
+        }
+        write(&self.memory, 4, &self.allocated_buckets.to_le_bytes());
+
+        self.memory_sizes_in_pages[id.0 as usize] = new_size;
+        write(
+            &self.memory,
+            MEMORY_SIZES_ADDRESS + 8 * id.0 as u64,
+            &new_size.to_le_bytes(),
+        );
+        old_size as i64
+    }
+
+    fn read(&self, id: MemoryId, offset: u64, dst: &mut [u8]) {
+        let mut done = 0;
+        self.for_each_segment(id, offset, dst.len(), |address, len| {
+            self.memory.read(address, &mut dst[done..done + len]);
+            done += len;
+        });
+    }
+
+    fn write(&self, id: MemoryId, offset: u64, src: &[u8]) {
+        let mut done = 0;
+        self.for_each_segment(id, offset, src.len(), |address, len| {
+            self.memory.write(address, &src[done..done + len]);
+            done += len;
+        });
+    }
+
+    /// Splits the range of `len` bytes at `offset` of memory `id` into the
+    /// contiguous ranges of the underlying memory that hold them, and calls
+    /// `f` with the address and the length of each one in order.
+    fn for_each_segment(
+        &self,
+        id: MemoryId,
+        offset: u64,
+        len: usize,
+        mut f: impl FnMut(u64, usize),
+    ) {
+        let end = offset
+            .checked_add(len as u64)
+            .expect("Address space overflow");
+        assert!(
+            end <= self.memory_size(id) * WASM_PAGE_SIZE,
+            "{:?}: access out of bounds",
+            id
+        );
+        let bucket_size_in_bytes = self.bucket_size_in_pages as u64 * WASM_PAGE_SIZE;
+        let buckets = &self.memory_buckets[id.0 as usize];
+        let mut offset = offset;
+        while offset < end {
+            let bucket = buckets[(offset / bucket_size_in_bytes) as usize] as u64;
+            let offset_in_bucket = offset % bucket_size_in_bytes;
+            let segment_len = (bucket_size_in_bytes - offset_in_bucket).min(end - offset);
+            let address = BUCKETS_OFFSET_IN_PAGES * WASM_PAGE_SIZE
+                + bucket * bucket_size_in_bytes
+                + offset_in_bucket;
+            f(address, segment_len as usize);
+            offset += segment_len;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use super::{MemoryId, MemoryManager, BUCKETS_OFFSET_IN_PAGES};
+use crate::vec_mem::VectorMemory;
+use crate::{Memory, RestrictedMemory, WASM_PAGE_SIZE};
+
+#[test]
+fn test_memories_grow_independently() {
+    let mem = VectorMemory::default();
+    let mm = MemoryManager::init_with_bucket_size(mem.clone(), 1);
+    let m0 = mm.get(MemoryId::new(0));
+    let m1 = mm.get(MemoryId::new(1));
+
+    assert_eq!(m0.size(), 0);
+    assert_eq!(m0.grow(1), 0);
+    assert_eq!(m1.grow(2), 0);
+    assert_eq!(m0.grow(1), 1);
+    assert_eq!((m0.size(), m1.size()), (2, 2));
+    assert_eq!(mem.size(), BUCKETS_OFFSET_IN_PAGES + 4);
+
+    m0.write(0, &[1; 2 * WASM_PAGE_SIZE as usize]);
+    m1.write(0, &[2; 2 * WASM_PAGE_SIZE as usize]);
+    let mut buf = vec![0; 2 * WASM_PAGE_SIZE as usize];
+    m0.read(0, &mut buf);
+    assert!(buf.iter().all(|b| *b == 1));
+    m1.read(0, &mut buf);
+    assert!(buf.iter().all(|b| *b == 2));
+}
+
+#[test]
+fn test_access_across_buckets() {
+    let mm = MemoryManager::init_with_bucket_size(VectorMemory::default(), 1);
+    let m0 = mm.get(MemoryId::new(0));
+    let m1 = mm.get(MemoryId::new(1));
+    // Interleave the buckets of both memories.
+    for _ in 0..3 {
+        assert_ne!(m0.grow(1), -1);
+        assert_ne!(m1.grow(1), -1);
+    }
+
+    let data: Vec<u8> = (0..3 * WASM_PAGE_SIZE).map(|i| (i % 251) as u8).collect();
+    m0.write(0, &data);
+    m1.write(10, &[7; 100]);
+
+    let offset = WASM_PAGE_SIZE - 10;
+    let mut buf = vec![0; WASM_PAGE_SIZE as usize + 20];
+    m0.read(offset, &mut buf);
+    assert_eq!(
+        &buf[..],
+        &data[offset as usize..offset as usize + buf.len()]
+    );
+}
+
+#[test]
+fn test_reload_keeps_memories() {
+    let mem = VectorMemory::default();
+    let mm = MemoryManager::init_with_bucket_size(mem.clone(), 1);
+    let m0 = mm.get(MemoryId::new(0));
+    let m1 = mm.get(MemoryId::new(1));
+    m1.grow(1);
+    m0.grow(2);
+    m0.write(WASM_PAGE_SIZE, b"hello");
+    m1.write(0, b"world");
+    drop(mm);
+
+    assert!(MemoryManager::is_initialized(&mem));
+    // The bucket size of the existing manager wins.
+    let mm = MemoryManager::init(mem);
+    let m0 = mm.get(MemoryId::new(0));
+    let m1 = mm.get(MemoryId::new(1));
+    assert_eq!((m0.size(), m1.size()), (2, 1));
+    let mut buf = [0; 5];
+    m0.read(WASM_PAGE_SIZE, &mut buf);
+    assert_eq!(&buf, b"hello");
+    m1.read(0, &mut buf);
+    assert_eq!(&buf, b"world");
+}
+
+#[test]
+fn test_is_initialized() {
+    let mem = VectorMemory::default();
+    assert!(!MemoryManager::is_initialized(&mem));
+    mem.grow(1);
+    mem.write(0, b"legacy data");
+    assert!(!MemoryManager::is_initialized(&mem));
+    MemoryManager::init(mem.clone());
+    assert!(MemoryManager::is_initialized(&mem));
+}
+
+#[test]
+fn test_grow_fails_when_underlying_memory_is_full() {
+    let mem = RestrictedMemory::new(VectorMemory::default(), 0..3);
+    let mm = MemoryManager::init_with_bucket_size(mem, 1);
+    let m0 = mm.get(MemoryId::new(0));
+    assert_eq!(m0.grow(2), 0);
+    assert_eq!(m0.grow(1), -1);
+    assert_eq!(m0.size(), 2);
+}
+
+#[test]
+#[should_panic(expected = "access out of bounds")]
+fn test_out_of_bounds_write_panics() {
+    let mm = MemoryManager::init_with_bucket_size(VectorMemory::default(), 2);
+    let m0 = mm.get(MemoryId::new(0));
+    m0.grow(1);
+    // The bucket holds two pages but the memory only one.
+    m0.write(WASM_PAGE_SIZE, &[1]);
+}
@@ -1,4 +1,4 @@
-use crate::state_layout::CheckpointManager;
+use crate::state_layout::{is_overlay_file, CheckpointManager};
 use crate::utils::do_copy;
 use ic_logger::{error, ReplicaLogger};
 use ic_utils::fs::{sync_and_mark_files_readonly, sync_path};
@@ -435,12 +435,25 @@ fn copy_recursively_respecting_tombstones(
 
 /// Copies the given file and ensures that the `read/write` permission of the
 /// target file match the given permission.
+///
+/// Overlay files are immutable, so they are hard-linked instead of copied if
+/// possible and always stay read-only.
 fn copy_and_sync_file(
     log: &ReplicaLogger,
     src: &Path,
     dst: &Path,
     dst_permissions: FilePermissions,
 ) -> std::io::Result<()> {
+    if is_overlay_file(src) {
+        if fs::hard_link(src, dst).is_err() {
+            do_copy(log, src, dst)?;
+        }
+        let mut permissions = dst.metadata()?.permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&dst, permissions)?;
+        return sync_path(&dst);
+    }
+
     do_copy(log, src, dst)?;
 
     // We keep the directory writable though to make sure we can rename
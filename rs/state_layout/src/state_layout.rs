@@ -18,8 +18,9 @@ use ic_replicated_state::{
 };
 use ic_sys::mmap::ScopedMmap;
 use ic_types::{
-    nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId, ComputeAllocation, Cycles,
-    ExecutionRound, Height, MemoryAllocation, NumInstructions, PrincipalId,
+    batch::QueryStats, nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId,
+    ComputeAllocation, Cycles, ExecutionRound, Height, MemoryAllocation, NumInstructions,
+    PrincipalId,
 };
 use ic_wasm_types::{CanisterModule, WasmHash};
 use std::convert::{From, TryFrom, TryInto};
@@ -189,6 +190,8 @@ pub struct CanisterStateBits {
     pub heap_delta_debit: NumBytes,
     pub install_code_debit: NumInstructions,
     pub task_queue: Vec<ExecutionTask>,
+    pub total_query_stats: QueryStats,
+    pub canister_version: u64,
 }
 
 /// This struct contains bits of the `BitcoinState` that are not already
@@ -240,6 +243,7 @@ impl Default for BitcoinStateBits {
 /// │       └── <hex(canister_id)>
 /// │           ├── queues.pbuf
 /// │           ├── vmemory_0.bin
+/// │           ├── vmemory_0.<hex(round)>.overlay
 /// │           ├── canister.pbuf
 /// │           ├── stable_memory.(pbuf|bin)
 /// │           └── software.wasm
@@ -258,13 +262,24 @@ impl Default for BitcoinStateBits {
 /// │          └── <hex(canister_id)>
 /// │              ├── queues.pbuf
 /// │              ├── vmemory_0.bin
+/// │              ├── vmemory_0.<hex(round)>.overlay
 /// │              ├── canister.pbuf
 /// │              ├── stable_memory.(pbuf|bin)
 /// │              └── software.wasm
 /// │
+/// ├── compilation_cache
+/// │   └── <hex(fingerprint)>
+/// │       └── <hex(wasm_hash)>
+/// │
 /// └── tmp
 /// ```
 ///
+/// Each page map file (`*.bin`) may be accompanied by overlay files
+/// (`*.<hex(round)>.overlay`) that contain the pages that changed at the
+/// checkpoint of the given round. The page map consists of the pages of the
+/// `.bin` file with the pages of the overlays applied in the order of their
+/// rounds. See [overlay_path].
+///
 /// Needs to be pub for criterion performance regression tests.
 #[derive(Clone)]
 pub struct StateLayout {
@@ -314,6 +329,12 @@ impl StateLayout {
         CheckpointLayout::new(self.tip_path(), height)
     }
 
+    /// Returns the path to the persistent cache of compiled Wasm modules.
+    /// Unlike the tmp directory, it is preserved across restarts of a node.
+    pub fn compilation_cache(&self) -> PathBuf {
+        self.cp_manager.raw_path().join("compilation_cache")
+    }
+
     /// Returns the path to the serialized states metadata.
     pub fn states_metadata(&self) -> PathBuf {
         self.cp_manager.raw_path().join("states_metadata.pbuf")
@@ -684,6 +705,65 @@ fn is_dir_already_exists_err(err: &std::io::Error) -> bool {
         || err.raw_os_error() == Some(libc::ENOTEMPTY as i32)
 }
 
+const OVERLAY_EXTENSION: &str = "overlay";
+
+/// Returns the path of the overlay file with the pages that changed at the
+/// checkpoint at `height` of the page map stored in `base`. For example, the
+/// overlay at height 300 of `vmemory_0.bin` is
+/// `vmemory_0.000000000000012c.overlay`.
+pub fn overlay_path(base: &Path, height: Height) -> PathBuf {
+    let stem = base
+        .file_stem()
+        .expect("page map files have a name")
+        .to_string_lossy();
+    base.with_file_name(format!(
+        "{}.{:016x}.{}",
+        stem,
+        height.get(),
+        OVERLAY_EXTENSION
+    ))
+}
+
+/// Returns true if `path` is an overlay file. Overlay files are never
+/// modified after they were written, so they can be shared between
+/// checkpoints.
+pub fn is_overlay_file(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |ext| ext == OVERLAY_EXTENSION)
+}
+
+/// Returns the heights and paths of the overlay files of the page map stored
+/// in `base`, ordered by height.
+pub fn overlays_of(base: &Path) -> Result<Vec<(Height, PathBuf)>, LayoutError> {
+    let dir = match base.parent() {
+        Some(dir) if dir.exists() => dir,
+        _ => return Ok(vec![]),
+    };
+    let stem = base
+        .file_stem()
+        .expect("page map files have a name")
+        .to_string_lossy();
+    let mut overlays = vec![];
+    for name in collect_subdirs(dir, |name| name.to_string())? {
+        let height = match name
+            .strip_suffix(OVERLAY_EXTENSION)
+            .and_then(|name| name.strip_suffix('.'))
+            .and_then(|name| name.rsplit_once('.'))
+        {
+            Some((overlay_stem, height)) if overlay_stem == stem => height,
+            _ => continue,
+        };
+        let height =
+            u64::from_str_radix(height, 16).map_err(|err| LayoutError::CorruptedLayout {
+                path: dir.join(&name),
+                message: format!("failed to parse the height of overlay {}: {}", name, err),
+            })?;
+        overlays.push((Height::new(height), dir.join(&name)));
+    }
+    overlays.sort();
+    Ok(overlays)
+}
+
 pub struct CheckpointLayout<Permissions: AccessPolicy> {
     root: PathBuf,
     height: Height,
@@ -1073,6 +1153,13 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             heap_delta_debit: item.heap_delta_debit.get(),
             install_code_debit: item.install_code_debit.get(),
             task_queue: item.task_queue.iter().map(|v| v.into()).collect(),
+            total_query_stats: Some(pb_canister_state_bits::TotalQueryStats {
+                num_calls: item.total_query_stats.num_calls,
+                num_instructions: item.total_query_stats.num_instructions,
+                ingress_payload_size: item.total_query_stats.ingress_payload_size,
+                egress_payload_size: item.total_query_stats.egress_payload_size,
+            }),
+            canister_version: item.canister_version,
         }
     }
 }
@@ -1145,6 +1232,16 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             heap_delta_debit: NumBytes::from(value.heap_delta_debit),
             install_code_debit: NumInstructions::from(value.install_code_debit),
             task_queue,
+            total_query_stats: value
+                .total_query_stats
+                .map(|stats| QueryStats {
+                    num_calls: stats.num_calls,
+                    num_instructions: stats.num_instructions,
+                    ingress_payload_size: stats.ingress_payload_size,
+                    egress_payload_size: stats.egress_payload_size,
+                })
+                .unwrap_or_default(),
+            canister_version: value.canister_version,
         })
     }
 }
@@ -1333,9 +1430,42 @@ mod test {
             heap_delta_debit: NumBytes::from(0),
             install_code_debit: NumInstructions::from(0),
             task_queue: vec![],
+            total_query_stats: QueryStats::default(),
+            canister_version: 0,
         }
     }
 
+    #[test]
+    fn overlays_are_listed_in_order_of_height() {
+        let tmp = tempfile::Builder::new().prefix("test").tempdir().unwrap();
+        let base = tmp.path().join("vmemory_0.bin");
+        for height in [300, 7, 42] {
+            let path = overlay_path(&base, Height::new(height));
+            assert!(is_overlay_file(&path));
+            std::fs::File::create(path).unwrap();
+        }
+        std::fs::File::create(&base).unwrap();
+        std::fs::File::create(overlay_path(
+            &tmp.path().join("stable_memory.bin"),
+            Height::new(1),
+        ))
+        .unwrap();
+
+        assert!(!is_overlay_file(&base));
+        assert_eq!(
+            overlay_path(&base, Height::new(300)),
+            tmp.path().join("vmemory_0.000000000000012c.overlay")
+        );
+        assert_eq!(
+            overlays_of(&base).unwrap(),
+            vec![
+                (Height::new(7), overlay_path(&base, Height::new(7))),
+                (Height::new(42), overlay_path(&base, Height::new(42))),
+                (Height::new(300), overlay_path(&base, Height::new(300))),
+            ]
+        );
+    }
+
     #[test]
     fn test_encode_decode_empty_controllers() {
         // A canister state with empty controllers.
@@ -1394,4 +1524,22 @@ mod test {
         let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
         assert_eq!(canister_state_bits.task_queue, task_queue);
     }
+
+    #[test]
+    fn test_encode_decode_total_query_stats() {
+        let total_query_stats = QueryStats {
+            num_calls: 3,
+            num_instructions: 5_000,
+            ingress_payload_size: 70,
+            egress_payload_size: 110,
+        };
+        let canister_state_bits = CanisterStateBits {
+            total_query_stats: total_query_stats.clone(),
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(canister_state_bits.total_query_stats, total_query_stats);
+    }
 }
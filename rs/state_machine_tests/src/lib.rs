@@ -67,6 +67,7 @@ use ic_types::{
     },
     time::current_time_and_expiry_time,
     CryptoHashOfPartialState, Height, NodeId, NumberOfNodes, Randomness, RegistryVersion,
+    ReplicaVersion,
 };
 pub use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
@@ -324,6 +325,7 @@ impl StateMachine {
                 hypervisor_config.clone(),
                 Arc::clone(&cycles_account_manager),
                 Arc::clone(&state_manager) as Arc<_>,
+                &ReplicaVersion::default(),
             )
         });
 
@@ -685,6 +687,36 @@ impl StateMachine {
             .commit_and_certify(state, h.increment(), CertificationScope::Full);
     }
 
+    /// Imports a canister archive written by `state_tool canister archive`
+    /// into the state machine and returns the id of the imported canister.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the archive cannot be read or loading the
+    /// canister state fails.
+    pub fn import_canister_archive<P: AsRef<Path>>(&self, archive_path: P) -> CanisterId {
+        let archive_path = archive_path.as_ref();
+        let archive = std::fs::File::open(archive_path)
+            .unwrap_or_else(|e| panic!("failed to open {}: {}", archive_path.display(), e));
+        let canister_directory = tempfile::Builder::new()
+            .prefix("canister_archive")
+            .tempdir()
+            .expect("failed to create a temporary directory");
+        let canister_id = ic_state_manager::canister_archive::unpack_canister_archive(
+            std::io::BufReader::new(archive),
+            canister_directory.path(),
+        )
+        .unwrap_or_else(|e| {
+            panic!(
+                "failed to unpack canister archive {}: {}",
+                archive_path.display(),
+                e
+            )
+        });
+        self.import_canister_state(canister_directory.path(), canister_id);
+        canister_id
+    }
+
     pub fn install_wasm_in_mode(
         &self,
         canister_id: CanisterId,
@@ -873,6 +905,7 @@ impl StateMachine {
                 method_payload,
                 ingress_expiry: 0,
                 nonce: None,
+                height: None,
             },
             state,
             data_certificate,
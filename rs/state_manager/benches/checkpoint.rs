@@ -6,7 +6,8 @@ use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::ReplicatedState;
 use ic_state_layout::StateLayout;
 use ic_state_manager::{
-    checkpoint::make_checkpoint, CheckpointMetrics, NUMBER_OF_CHECKPOINT_THREADS,
+    checkpoint::{make_checkpoint, PageMapPersistence},
+    CheckpointMetrics, NUMBER_OF_CHECKPOINT_THREADS,
 };
 use ic_test_utilities::{
     state::new_canister_state,
@@ -80,6 +81,7 @@ fn criterion_make_checkpoint(c: &mut Criterion) {
                         &log,
                         &data.metrics,
                         Rc::get_mut(&mut data.thread_pool).unwrap(),
+                        PageMapPersistence::InPlace,
                     );
                 });
             },
@@ -0,0 +1,321 @@
+//! A portable archive format for the state of a single canister.
+//!
+//! A canister archive is a tar file with the following entries:
+//!
+//! ```text
+//! VERSION            <- the archive format version, in decimal
+//! CANISTER_ID        <- the textual representation of the canister id
+//! canister.pbuf      <- the system state of the canister
+//! queues.pbuf        <- the canister queues (optional)
+//! software.wasm      <- the wasm module (optional)
+//! vmemory_0.bin      <- the wasm heap (optional)
+//! stable_memory.bin  <- the stable memory (optional)
+//! ```
+//!
+//! `VERSION` is always the first entry and `CANISTER_ID` the second one. The
+//! files have the same names and format as in a canister directory of a
+//! checkpoint, except that page map overlays are merged into the page files,
+//! so an unpacked archive can be loaded as a canister directory of any
+//! checkpoint.
+
+use crate::manifest::FileContents;
+use ic_state_layout::{AccessPolicy, CanisterLayout};
+use ic_types::CanisterId;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The current version of the canister archive format.
+pub const CANISTER_ARCHIVE_VERSION: u32 = 1;
+
+const VERSION_ENTRY: &str = "VERSION";
+const CANISTER_ID_ENTRY: &str = "CANISTER_ID";
+const CANISTER_STATE_FILE: &str = "canister.pbuf";
+
+/// The canister files that may be stored in an archive, in the order they are
+/// written.
+const CANISTER_FILES: [&str; 5] = [
+    CANISTER_STATE_FILE,
+    "queues.pbuf",
+    "software.wasm",
+    "vmemory_0.bin",
+    "stable_memory.bin",
+];
+
+/// Writes the state of canister `canister_id`, stored in `canister_layout`,
+/// as a canister archive to `writer`.
+pub fn write_canister_archive<P: AccessPolicy, W: Write>(
+    canister_layout: &CanisterLayout<P>,
+    canister_id: &CanisterId,
+    writer: W,
+) -> Result<(), String> {
+    let canister_root = canister_layout.raw_path();
+    if !canister_root.join(CANISTER_STATE_FILE).exists() {
+        return Err(format!(
+            "canister {} has no {} at {}",
+            canister_id,
+            CANISTER_STATE_FILE,
+            canister_root.display()
+        ));
+    }
+
+    let mut builder = tar::Builder::new(writer);
+    append_entry(
+        &mut builder,
+        VERSION_ENTRY,
+        CANISTER_ARCHIVE_VERSION.to_string().as_bytes(),
+    )?;
+    append_entry(
+        &mut builder,
+        CANISTER_ID_ENTRY,
+        canister_id.to_string().as_bytes(),
+    )?;
+
+    for name in CANISTER_FILES {
+        let path = canister_root.join(name);
+        if !path.exists() {
+            continue;
+        }
+        // Page files are read through their overlays, if any.
+        let contents = FileContents::open(&path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let mut header = new_header(contents.len() as u64);
+        builder
+            .append_data(&mut header, name, contents.reader())
+            .map_err(|e| format!("failed to append {} to archive: {}", name, e))?;
+    }
+
+    builder
+        .into_inner()
+        .and_then(|mut writer| writer.flush())
+        .map_err(|e| format!("failed to finish archive: {}", e))
+}
+
+/// Unpacks the canister archive read from `reader` into the directory `dst`,
+/// which must exist, and returns the id of the archived canister.
+///
+/// Fails if the archive was written by a newer version of the format or
+/// contains entries other than the ones listed in the module documentation.
+pub fn unpack_canister_archive<R: Read>(reader: R, dst: &Path) -> Result<CanisterId, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = archive
+        .entries()
+        .map_err(|e| format!("failed to read archive: {}", e))?;
+
+    let version: u32 = match next_entry(&mut entries)? {
+        Some((name, entry)) if name == VERSION_ENTRY => read_text(entry, VERSION_ENTRY)?
+            .parse()
+            .map_err(|e| format!("failed to parse archive version: {}", e))?,
+        _ => return Err(format!("archive must start with a {} entry", VERSION_ENTRY)),
+    };
+    if version > CANISTER_ARCHIVE_VERSION {
+        return Err(format!(
+            "unsupported archive version {}, expected at most {}",
+            version, CANISTER_ARCHIVE_VERSION
+        ));
+    }
+
+    let canister_id = match next_entry(&mut entries)? {
+        Some((name, entry)) if name == CANISTER_ID_ENTRY => {
+            let text = read_text(entry, CANISTER_ID_ENTRY)?;
+            CanisterId::from_str(&text)
+                .map_err(|e| format!("failed to parse canister id {}: {}", text, e))?
+        }
+        _ => {
+            return Err(format!(
+                "missing {} entry after {}",
+                CANISTER_ID_ENTRY, VERSION_ENTRY
+            ))
+        }
+    };
+
+    let mut has_canister_state = false;
+    while let Some((name, mut entry)) = next_entry(&mut entries)? {
+        if !CANISTER_FILES.contains(&name.as_str()) {
+            return Err(format!("unexpected archive entry {}", name));
+        }
+        has_canister_state |= name == CANISTER_STATE_FILE;
+        let path = dst.join(&name);
+        entry
+            .unpack(&path)
+            .map_err(|e| format!("failed to unpack {}: {}", path.display(), e))?;
+    }
+    if !has_canister_state {
+        return Err(format!("archive has no {} entry", CANISTER_STATE_FILE));
+    }
+
+    Ok(canister_id)
+}
+
+/// Returns the next entry of the archive along with its name, rejecting
+/// anything that is not a regular file, such as links or directories.
+fn next_entry<'a, R: Read>(
+    entries: &mut tar::Entries<'a, R>,
+) -> Result<Option<(String, tar::Entry<'a, R>)>, String> {
+    match entries.next() {
+        None => Ok(None),
+        Some(entry) => {
+            let entry = entry.map_err(|e| format!("failed to read archive entry: {}", e))?;
+            let name = entry_name(&entry)?;
+            let entry_type = entry.header().entry_type();
+            if entry_type != tar::EntryType::Regular {
+                return Err(format!(
+                    "archive entry {} is not a regular file: {:?}",
+                    name, entry_type
+                ));
+            }
+            Ok(Some((name, entry)))
+        }
+    }
+}
+
+fn new_header(size: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_cksum();
+    header
+}
+
+fn append_entry<W: Write>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    data: &[u8],
+) -> Result<(), String> {
+    builder
+        .append_data(&mut new_header(data.len() as u64), name, data)
+        .map_err(|e| format!("failed to append {} to archive: {}", name, e))
+}
+
+/// Returns the name of an archive entry, rejecting anything that is not a
+/// plain file name.
+fn entry_name<R: Read>(entry: &tar::Entry<'_, R>) -> Result<String, String> {
+    let path: PathBuf = entry
+        .path()
+        .map_err(|e| format!("failed to read archive entry path: {}", e))?
+        .into_owned();
+    match (path.to_str(), path.components().count()) {
+        (Some(name), 1) if path.file_name().is_some() => Ok(name.to_string()),
+        _ => Err(format!("unexpected archive entry {}", path.display())),
+    }
+}
+
+fn read_text<R: Read>(mut entry: tar::Entry<'_, R>, name: &str) -> Result<String, String> {
+    let mut text = String::new();
+    entry
+        .read_to_string(&mut text)
+        .map_err(|e| format!("failed to read {} entry: {}", name, e))?;
+    Ok(text.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::READ_BUFFER_SIZE;
+    use ic_state_layout::{CheckpointLayout, RwPolicy};
+    use ic_test_utilities::types::ids::canister_test_id;
+    use ic_types::Height;
+
+    fn canister_layout(root: &Path, canister_id: &CanisterId) -> CanisterLayout<RwPolicy> {
+        let layout = CheckpointLayout::<RwPolicy>::new(root.to_path_buf(), Height::new(0))
+            .unwrap()
+            .canister(canister_id)
+            .unwrap();
+        std::fs::create_dir_all(layout.raw_path()).unwrap();
+        layout
+    }
+
+    #[test]
+    fn canister_archive_roundtrip() {
+        let tmp = tempfile::Builder::new().prefix("test").tempdir().unwrap();
+        let canister_id = canister_test_id(42);
+        let layout = canister_layout(&tmp.path().join("src"), &canister_id);
+        std::fs::write(layout.raw_path().join(CANISTER_STATE_FILE), b"state").unwrap();
+        std::fs::write(layout.wasm().raw_path(), b"\0asm").unwrap();
+        std::fs::write(layout.vmemory_0(), vec![7; 3 * READ_BUFFER_SIZE + 1]).unwrap();
+
+        let mut archive = vec![];
+        write_canister_archive(&layout, &canister_id, &mut archive).unwrap();
+
+        let dst = tmp.path().join("dst");
+        std::fs::create_dir_all(&dst).unwrap();
+        assert_eq!(
+            unpack_canister_archive(&archive[..], &dst).unwrap(),
+            canister_id
+        );
+
+        let mut unpacked: Vec<_> = std::fs::read_dir(&dst)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        unpacked.sort();
+        assert_eq!(
+            unpacked,
+            vec!["canister.pbuf", "software.wasm", "vmemory_0.bin"]
+        );
+        for path in [
+            layout.raw_path().join(CANISTER_STATE_FILE),
+            layout.wasm().raw_path().to_path_buf(),
+            layout.vmemory_0(),
+        ] {
+            assert_eq!(
+                std::fs::read(&path).unwrap(),
+                std::fs::read(dst.join(path.file_name().unwrap())).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn unpack_rejects_newer_versions() {
+        let mut archive = vec![];
+        {
+            let mut builder = tar::Builder::new(&mut archive);
+            let version = (CANISTER_ARCHIVE_VERSION + 1).to_string();
+            append_entry(&mut builder, VERSION_ENTRY, version.as_bytes()).unwrap();
+            builder.finish().unwrap();
+        }
+        let tmp = tempfile::Builder::new().prefix("test").tempdir().unwrap();
+        let err = unpack_canister_archive(&archive[..], tmp.path()).unwrap_err();
+        assert!(err.contains("unsupported archive version"), "{}", err);
+    }
+
+    #[test]
+    fn unpack_rejects_symlinks() {
+        let tmp = tempfile::Builder::new().prefix("test").tempdir().unwrap();
+        let target = tmp.path().join("target");
+        std::fs::write(&target, b"outside").unwrap();
+
+        let mut archive = vec![];
+        {
+            let mut builder = tar::Builder::new(&mut archive);
+            append_entry(
+                &mut builder,
+                VERSION_ENTRY,
+                CANISTER_ARCHIVE_VERSION.to_string().as_bytes(),
+            )
+            .unwrap();
+            append_entry(
+                &mut builder,
+                CANISTER_ID_ENTRY,
+                canister_test_id(42).to_string().as_bytes(),
+            )
+            .unwrap();
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            header.set_mode(0o644);
+            header.set_link_name(&target).unwrap();
+            builder
+                .append_data(&mut header, CANISTER_STATE_FILE, std::io::empty())
+                .unwrap();
+            builder.finish().unwrap();
+        }
+
+        let dst = tmp.path().join("dst");
+        std::fs::create_dir_all(&dst).unwrap();
+        let err = unpack_canister_archive(&archive[..], &dst).unwrap_err();
+        assert!(err.contains("is not a regular file"), "{}", err);
+        assert!(!dst.join(CANISTER_STATE_FILE).exists());
+        assert_eq!(std::fs::read(&target).unwrap(), b"outside");
+    }
+}
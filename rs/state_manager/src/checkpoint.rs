@@ -1,6 +1,5 @@
 use crate::{
-    BitcoinPageMap, CheckpointError, CheckpointMetrics, PageMapType, PersistenceError,
-    NUMBER_OF_CHECKPOINT_THREADS,
+    BitcoinPageMap, CheckpointError, CheckpointMetrics, PageMapType, NUMBER_OF_CHECKPOINT_THREADS,
 };
 use ic_base_types::CanisterId;
 use ic_logger::ReplicaLogger;
@@ -14,8 +13,8 @@ use ic_replicated_state::{
     SystemState,
 };
 use ic_state_layout::{
-    BitcoinStateBits, BitcoinStateLayout, CanisterLayout, CanisterStateBits, CheckpointLayout,
-    ExecutionStateBits, ReadPolicy, RwPolicy, StateLayout,
+    overlay_path, overlays_of, BitcoinStateBits, BitcoinStateLayout, CanisterLayout,
+    CanisterStateBits, CheckpointLayout, ExecutionStateBits, ReadPolicy, RwPolicy, StateLayout,
 };
 use ic_types::Height;
 use ic_utils::fs::defrag_file_partially;
@@ -33,6 +32,31 @@ use std::{
 
 const DEFRAG_SIZE: u64 = 1 << 29; // 500 MB
 
+/// The number of overlays of a page map file in a checkpoint starting from
+/// which the overlays are merged into the file in the background.
+pub const MAX_OVERLAYS_PER_PAGE_MAP: usize = 8;
+
+/// Describes how the page maps of the tip are persisted in checkpoints.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PageMapPersistence {
+    /// The page deltas are applied to the page map files of the tip after
+    /// every round, and the files are copied to the checkpoint.
+    InPlace,
+    /// The page deltas accumulated since the last checkpoint are written to
+    /// a new overlay file on top of the files of the last checkpoint.
+    Overlays,
+}
+
+/// A page map file of a checkpoint with all its overlays merged into a single
+/// file outside of the tip. See [merge_overlays].
+#[derive(Debug)]
+pub struct MergedPageMap {
+    page_map_type: PageMapType,
+    /// The height of the checkpoint the page map was loaded from.
+    height: Height,
+    path: PathBuf,
+}
+
 /// Creates a checkpoint of the node state using specified directory
 /// layout. Returns a new state that is equivalent to the given one
 /// and a result of the operation.
@@ -50,6 +74,7 @@ pub fn make_checkpoint(
     log: &ReplicaLogger,
     metrics: &CheckpointMetrics,
     thread_pool: &mut scoped_threadpool::Pool,
+    persistence: PageMapPersistence,
 ) -> Result<ReplicatedState, CheckpointError> {
     let tip = layout.tip(height)?;
 
@@ -58,10 +83,12 @@ pub fn make_checkpoint(
             .make_checkpoint_step_duration
             .with_label_values(&["serialize_to_tip"])
             .start_timer();
-        serialize_to_tip(log, state, &tip, thread_pool)?;
+        serialize_to_tip(log, state, &tip, thread_pool, persistence)?;
     }
 
-    {
+    // Page map files are never written to in place when using overlays, so
+    // they do not get fragmented.
+    if persistence == PageMapPersistence::InPlace {
         let _timer = metrics
             .make_checkpoint_step_duration
             .with_label_values(&["defrag_tip"])
@@ -98,6 +125,7 @@ fn serialize_to_tip(
     state: &ReplicatedState,
     tip: &CheckpointLayout<RwPolicy>,
     thread_pool: &mut scoped_threadpool::Pool,
+    persistence: PageMapPersistence,
 ) -> Result<(), CheckpointError> {
     tip.system_metadata()
         .serialize(state.system_metadata().into())?;
@@ -106,14 +134,14 @@ fn serialize_to_tip(
         .serialize((state.subnet_queues()).into())?;
 
     let results = parallel_map(thread_pool, state.canisters_iter(), |canister_state| {
-        serialize_canister_to_tip(log, canister_state, tip)
+        serialize_canister_to_tip(log, canister_state, tip, persistence)
     });
 
     for result in results.into_iter() {
         result?;
     }
 
-    serialize_bitcoin_state_to_tip(state.bitcoin(), &tip.bitcoin()?)?;
+    serialize_bitcoin_state_to_tip(state.bitcoin(), &tip.bitcoin()?, tip.height(), persistence)?;
 
     Ok(())
 }
@@ -122,6 +150,7 @@ fn serialize_canister_to_tip(
     log: &ReplicaLogger,
     canister_state: &CanisterState,
     tip: &CheckpointLayout<RwPolicy>,
+    persistence: PageMapPersistence,
 ) -> Result<(), CheckpointError> {
     let canister_layout = tip.canister(&canister_state.canister_id())?;
     canister_layout
@@ -151,14 +180,18 @@ fn serialize_canister_to_tip(
                         .serialize(&execution_state.wasm_binary.binary)?;
                 }
             }
-            execution_state
-                .wasm_memory
-                .page_map
-                .persist_and_sync_delta(&canister_layout.vmemory_0())?;
-            execution_state
-                .stable_memory
-                .page_map
-                .persist_and_sync_delta(&canister_layout.stable_memory_blob())?;
+            persist_page_map(
+                &execution_state.wasm_memory.page_map,
+                &canister_layout.vmemory_0(),
+                tip.height(),
+                persistence,
+            )?;
+            persist_page_map(
+                &execution_state.stable_memory.page_map,
+                &canister_layout.stable_memory_blob(),
+                tip.height(),
+                persistence,
+            )?;
 
             Some(ExecutionStateBits {
                 exported_globals: execution_state.exported_globals.clone(),
@@ -223,6 +256,8 @@ fn serialize_canister_to_tip(
                     .clone()
                     .into_iter()
                     .collect(),
+                total_query_stats: canister_state.system_state.total_query_stats.clone(),
+                canister_version: canister_state.system_state.canister_version,
             }
             .into(),
         )
@@ -232,21 +267,29 @@ fn serialize_canister_to_tip(
 fn serialize_bitcoin_state_to_tip(
     state: &BitcoinState,
     layout: &BitcoinStateLayout<RwPolicy>,
+    height: Height,
+    persistence: PageMapPersistence,
 ) -> Result<(), CheckpointError> {
-    state
-        .utxo_set
-        .utxos_small
-        .persist_and_sync_delta(&layout.utxos_small())?;
-
-    state
-        .utxo_set
-        .utxos_medium
-        .persist_and_sync_delta(&layout.utxos_medium())?;
-
-    state
-        .utxo_set
-        .address_outpoints
-        .persist_and_sync_delta(&layout.address_outpoints())?;
+    persist_page_map(
+        &state.utxo_set.utxos_small,
+        &layout.utxos_small(),
+        height,
+        persistence,
+    )?;
+
+    persist_page_map(
+        &state.utxo_set.utxos_medium,
+        &layout.utxos_medium(),
+        height,
+        persistence,
+    )?;
+
+    persist_page_map(
+        &state.utxo_set.address_outpoints,
+        &layout.address_outpoints(),
+        height,
+        persistence,
+    )?;
 
     layout
         .bitcoin_state()
@@ -264,6 +307,172 @@ fn serialize_bitcoin_state_to_tip(
         .map_err(CheckpointError::from)
 }
 
+/// Persists the page delta of `page_map` to the page map file `path` in the
+/// tip at `height`.
+///
+/// With [PageMapPersistence::Overlays], the delta is written to a new overlay
+/// file next to `path`. The files of a page map that does not come from a
+/// checkpoint, e.g. because the canister was reinstalled, are stale, so they
+/// are replaced by an empty file first.
+fn persist_page_map(
+    page_map: &PageMap,
+    path: &Path,
+    height: Height,
+    persistence: PageMapPersistence,
+) -> Result<(), CheckpointError> {
+    match persistence {
+        PageMapPersistence::InPlace => page_map.persist_and_sync_delta(path)?,
+        PageMapPersistence::Overlays => {
+            if page_map.base_height.is_none() {
+                remove_overlays(path)?;
+                std::fs::File::create(path).map_err(|err| CheckpointError::IoError {
+                    path: path.to_path_buf(),
+                    message: "failed to create page map file".to_string(),
+                    io_err: err.to_string(),
+                })?;
+            }
+            if !page_map.page_delta_is_empty() {
+                page_map.persist_delta_as_overlay(&overlay_path(path, height))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Removes all overlay files of the page map file `path`.
+fn remove_overlays(path: &Path) -> Result<(), CheckpointError> {
+    for (_, overlay) in overlays_of(path)? {
+        std::fs::remove_file(&overlay).map_err(|err| CheckpointError::IoError {
+            path: overlay,
+            message: "failed to remove overlay file".to_string(),
+            io_err: err.to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+/// Returns the page maps of `state`, a state loaded from a checkpoint, whose
+/// files have at least [MAX_OVERLAYS_PER_PAGE_MAP] overlays.
+pub fn page_maps_to_merge(state: &ReplicatedState) -> Vec<(PageMapType, PageMap)> {
+    PageMapType::list_all(state)
+        .into_iter()
+        .filter_map(|entry| {
+            let page_map = entry.get(state)?;
+            (page_map.base_height.is_some()
+                && page_map.num_checkpoint_overlays() >= MAX_OVERLAYS_PER_PAGE_MAP)
+                .then(|| (entry, page_map.clone()))
+        })
+        .collect()
+}
+
+/// Merges the checkpoint files of each of the given page maps into a single
+/// file in `dir`.
+///
+/// This only reads the files of checkpoints, which are immutable, so it is
+/// safe to run in the background while the tip is being modified. The merged
+/// files are moved to the tip by [install_merged_page_maps].
+pub fn merge_overlays(
+    page_maps: Vec<(PageMapType, PageMap)>,
+    dir: &Path,
+) -> Result<Vec<MergedPageMap>, CheckpointError> {
+    let mut merged = Vec::with_capacity(page_maps.len());
+    for (i, (page_map_type, page_map)) in page_maps.into_iter().enumerate() {
+        let height = match page_map.base_height {
+            Some(height) => height,
+            None => continue,
+        };
+        let path = dir.join(format!("merged_page_map_{}_{}", height, i));
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|err| CheckpointError::IoError {
+                path: path.clone(),
+                message: "failed to remove stale merged page map".to_string(),
+                io_err: err.to_string(),
+            })?;
+        }
+        page_map.merge_checkpoint_to_file(&path)?;
+        merged.push(MergedPageMap {
+            page_map_type,
+            height,
+            path,
+        });
+    }
+    Ok(merged)
+}
+
+/// Replaces the page map files of the tip by the merged files produced by
+/// [merge_overlays] and removes their overlays.
+///
+/// A merged file is only used if the corresponding page map of `state`, the
+/// state that is about to be written to the tip, is still based on the
+/// checkpoint the file was merged from. Otherwise it is discarded.
+pub fn install_merged_page_maps(
+    state: &ReplicatedState,
+    tip: &CheckpointLayout<RwPolicy>,
+    merged: Vec<MergedPageMap>,
+) -> Result<(), CheckpointError> {
+    for MergedPageMap {
+        page_map_type,
+        height,
+        path,
+    } in merged
+    {
+        let base_height = page_map_type
+            .get(state)
+            .and_then(|page_map| page_map.base_height);
+        if base_height != Some(height) {
+            std::fs::remove_file(&path).map_err(|err| CheckpointError::IoError {
+                path: path.clone(),
+                message: "failed to remove outdated merged page map".to_string(),
+                io_err: err.to_string(),
+            })?;
+            continue;
+        }
+        let dst = page_map_type.path(tip)?;
+        std::fs::rename(&path, &dst).map_err(|err| CheckpointError::IoError {
+            path: path.clone(),
+            message: format!("failed to move merged page map to {}", dst.display()),
+            io_err: err.to_string(),
+        })?;
+        remove_overlays(&dst)?;
+    }
+    Ok(())
+}
+
+/// Merges the overlays of all page maps of `tip_state`, a state loaded from the
+/// tip, into their page map files in the tip.
+///
+/// This is required before the tip is written to in place, see
+/// [PageMapPersistence::InPlace], e.g. if the checkpoint the tip was reset to
+/// was created with overlays.
+pub fn flatten_overlays(
+    tip_state: &ReplicatedState,
+    tip: &CheckpointLayout<RwPolicy>,
+) -> Result<(), CheckpointError> {
+    for entry in PageMapType::list_all(tip_state) {
+        let page_map = match entry.get(tip_state) {
+            Some(page_map) if page_map.num_checkpoint_overlays() > 0 => page_map,
+            _ => continue,
+        };
+        let path = entry.path(tip)?;
+        let merged = path.with_extension("merged");
+        if merged.exists() {
+            std::fs::remove_file(&merged).map_err(|err| CheckpointError::IoError {
+                path: merged.clone(),
+                message: "failed to remove stale merged page map".to_string(),
+                io_err: err.to_string(),
+            })?;
+        }
+        page_map.merge_checkpoint_to_file(&merged)?;
+        std::fs::rename(&merged, &path).map_err(|err| CheckpointError::IoError {
+            path: merged.clone(),
+            message: format!("failed to move merged page map to {}", path.display()),
+            io_err: err.to_string(),
+        })?;
+        remove_overlays(&path)?;
+    }
+    Ok(())
+}
+
 /// Defragments part of the tip directory.
 ///
 /// The way we use PageMap files in the tip, namely by having a
@@ -486,14 +695,14 @@ pub fn load_canister_state<P: ReadPolicy>(
         Some(execution_state_bits) => {
             let starting_time = Instant::now();
             let wasm_memory = Memory::new(
-                PageMap::open(&canister_layout.vmemory_0(), Some(height))?,
+                open_page_map(&canister_layout.vmemory_0(), Some(height))?,
                 execution_state_bits.heap_size,
             );
             durations.insert("wasm_memory", starting_time.elapsed());
 
             let starting_time = Instant::now();
             let stable_memory = Memory::new(
-                PageMap::open(&canister_layout.stable_memory_blob(), Some(height))?,
+                open_page_map(&canister_layout.stable_memory_blob(), Some(height))?,
                 canister_state_bits.stable_memory_size,
             );
             durations.insert("stable_memory", starting_time.elapsed());
@@ -552,6 +761,8 @@ pub fn load_canister_state<P: ReadPolicy>(
         canister_metrics,
         canister_state_bits.cycles_balance,
         canister_state_bits.task_queue.into_iter().collect(),
+        canister_state_bits.total_query_stats,
+        canister_state_bits.canister_version,
     );
 
     let canister_state = CanisterState {
@@ -621,17 +832,23 @@ fn load_bitcoin_state<P: ReadPolicy>(
     })
 }
 
-fn load_or_create_pagemap(
-    path: &Path,
-    height: Option<Height>,
-) -> Result<PageMap, PersistenceError> {
+fn load_or_create_pagemap(path: &Path, height: Option<Height>) -> Result<PageMap, CheckpointError> {
     if path.exists() {
-        PageMap::open(path, height)
+        open_page_map(path, height)
     } else {
         Ok(PageMap::default())
     }
 }
 
+/// Opens the page map file `path` together with its overlays, if any.
+fn open_page_map(path: &Path, height: Option<Height>) -> Result<PageMap, CheckpointError> {
+    let overlays: Vec<PathBuf> = overlays_of(path)?
+        .into_iter()
+        .map(|(_, overlay)| overlay)
+        .collect();
+    Ok(PageMap::open_with_overlays(path, &overlays, height)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -704,6 +921,7 @@ mod tests {
             log,
             &checkpoint_metrics(),
             &mut thread_pool(),
+            PageMapPersistence::InPlace,
         )
         .unwrap_or_else(|err| panic!("Expected make_checkpoint to succeed, got {:?}", err))
     }
@@ -801,6 +1019,7 @@ mod tests {
                 &log,
                 &checkpoint_metrics(),
                 &mut thread_pool(),
+                PageMapPersistence::InPlace,
             ) {
                 Err(_) => assert!(
                     !expected_scratchpad_dir.exists(),
@@ -902,6 +1121,123 @@ mod tests {
         });
     }
 
+    #[test]
+    fn can_recover_from_checkpoints_with_overlays() {
+        with_test_replica_logger(|log| {
+            let tmp = Builder::new().prefix("test").tempdir().unwrap();
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::new(log.clone(), root.clone());
+            let canister_id: CanisterId = canister_test_id(10);
+
+            let make_checkpoint_with_overlays = |state: &ReplicatedState, height: Height| {
+                make_checkpoint(
+                    state,
+                    height,
+                    &layout,
+                    &log,
+                    &checkpoint_metrics(),
+                    &mut thread_pool(),
+                    PageMapPersistence::Overlays,
+                )
+                .unwrap_or_else(|err| panic!("Expected make_checkpoint to succeed, got {:?}", err))
+            };
+            let wasm_page_map = |state: &ReplicatedState| {
+                state
+                    .canister_state(&canister_id)
+                    .unwrap()
+                    .execution_state
+                    .as_ref()
+                    .unwrap()
+                    .wasm_memory
+                    .page_map
+                    .clone()
+            };
+            let write_page = |state: &mut ReplicatedState, index: u64, byte: u8| {
+                state
+                    .canister_state_mut(&canister_id)
+                    .unwrap()
+                    .execution_state
+                    .as_mut()
+                    .unwrap()
+                    .wasm_memory
+                    .page_map
+                    .update(&[(PageIndex::new(index), &[byte; PAGE_SIZE])]);
+            };
+
+            let mut canister_state = new_canister_state(
+                canister_id,
+                user_test_id(24).get(),
+                INITIAL_CYCLES,
+                NumSeconds::from(100_000),
+            );
+            canister_state.execution_state = Some(ExecutionState {
+                canister_root: root.clone(),
+                session_nonce: None,
+                wasm_binary: WasmBinary::new(empty_wasm()),
+                wasm_memory: one_page_of(1),
+                stable_memory: Memory::default(),
+                exported_globals: vec![],
+                exports: ExportedFunctions::new(BTreeSet::new()),
+                metadata: WasmMetadata::default(),
+                last_executed_round: ExecutionRound::from(0),
+            });
+            let mut state =
+                ReplicatedState::new_rooted_at(subnet_test_id(1), SubnetType::Application, root);
+            state.put_canister_state(canister_state);
+
+            let mut state = make_checkpoint_with_overlays(&state, Height::new(1));
+            write_page(&mut state, 0, 2);
+            write_page(&mut state, 3, 3);
+            let mut state = make_checkpoint_with_overlays(&state, Height::new(2));
+
+            let vmemory_0 = layout
+                .checkpoint(Height::new(2))
+                .unwrap()
+                .canister(&canister_id)
+                .unwrap()
+                .vmemory_0();
+            assert_eq!(std::fs::metadata(&vmemory_0).unwrap().len(), 0);
+            assert_eq!(overlays_of(&vmemory_0).unwrap().len(), 2);
+
+            let page_map = wasm_page_map(&state);
+            assert_eq!(page_map.num_checkpoint_overlays(), 2);
+            assert_eq!(page_map.num_host_pages(), 4);
+            assert_eq!(page_map.get_page(PageIndex::new(0)), &[2; PAGE_SIZE]);
+            assert_eq!(page_map.get_page(PageIndex::new(1)), &[0; PAGE_SIZE]);
+            assert_eq!(page_map.get_page(PageIndex::new(3)), &[3; PAGE_SIZE]);
+
+            // Merging the overlays replaces the page map file of the tip.
+            let merge_dir = tmp.path().join("merge");
+            std::fs::create_dir(&merge_dir).unwrap();
+            let merged = merge_overlays(
+                vec![(PageMapType::WasmMemory(canister_id), page_map)],
+                &merge_dir,
+            )
+            .unwrap();
+            install_merged_page_maps(&state, &layout.tip(Height::new(3)).unwrap(), merged).unwrap();
+            write_page(&mut state, 1, 4);
+            let state = make_checkpoint_with_overlays(&state, Height::new(3));
+
+            let vmemory_0 = layout
+                .checkpoint(Height::new(3))
+                .unwrap()
+                .canister(&canister_id)
+                .unwrap()
+                .vmemory_0();
+            assert_eq!(
+                std::fs::metadata(&vmemory_0).unwrap().len(),
+                4 * PAGE_SIZE as u64
+            );
+            assert_eq!(overlays_of(&vmemory_0).unwrap().len(), 1);
+
+            let page_map = wasm_page_map(&state);
+            assert_eq!(page_map.num_checkpoint_overlays(), 1);
+            assert_eq!(page_map.get_page(PageIndex::new(0)), &[2; PAGE_SIZE]);
+            assert_eq!(page_map.get_page(PageIndex::new(1)), &[4; PAGE_SIZE]);
+            assert_eq!(page_map.get_page(PageIndex::new(3)), &[3; PAGE_SIZE]);
+        });
+    }
+
     #[test]
     fn can_recover_an_empty_state() {
         with_test_replica_logger(|log| {
@@ -993,6 +1329,7 @@ mod tests {
                 &log,
                 &checkpoint_metrics(),
                 &mut thread_pool(),
+                PageMapPersistence::InPlace,
             );
 
             assert!(
@@ -1,5 +1,6 @@
 // Needs to be `pub` so that the benchmarking code in `state_manager/benches`
 // can access it.
+pub mod canister_archive;
 pub mod checkpoint;
 pub mod labeled_tree_visitor;
 pub mod manifest;
@@ -8,6 +9,7 @@ pub mod stream_encoding;
 pub mod tree_diff;
 pub mod tree_hash;
 
+use crate::checkpoint::{MergedPageMap, PageMapPersistence};
 use crate::state_sync::chunkable::cache::StateSyncCache;
 use crossbeam_channel::{unbounded, Sender};
 use ic_base_types::CanisterId;
@@ -15,7 +17,7 @@ use ic_canonical_state::{
     hash_tree::{hash_lazy_tree, HashTree},
     lazy_tree::{materialize::materialize_partial, LazyTree},
 };
-use ic_config::state_manager::Config;
+use ic_config::{flag_status::FlagStatus, state_manager::Config};
 use ic_crypto_tree_hash::{recompute_digest, Digest, LabeledTree, MixedHashTree, Witness};
 use ic_interfaces::{
     certification::Verifier,
@@ -86,6 +88,7 @@ const LABEL_FETCH: &str = "fetch";
 const LABEL_COPY_FILES: &str = "copy_files";
 const LABEL_COPY_CHUNKS: &str = "copy_chunks";
 const LABEL_PREALLOCATE: &str = "preallocate";
+const LABEL_DEDUPLICATE: &str = "deduplicate";
 
 #[derive(Clone)]
 pub struct StateManagerMetrics {
@@ -119,6 +122,7 @@ pub struct StateSyncMetrics {
     remaining: IntGauge,
     corrupted_chunks_critical: IntCounter,
     corrupted_chunks: IntCounterVec,
+    received_bytes: IntCounterVec,
 }
 
 #[derive(Clone)]
@@ -295,7 +299,7 @@ impl StateSyncMetrics {
     pub fn new(metrics_registry: &MetricsRegistry) -> Self {
         let size = metrics_registry.int_counter_vec(
             "state_sync_size_bytes_total",
-            "Size of chunks synchronized by different operations ('fetch', 'copy_files', 'copy_chunks', 'preallocate') during all the state sync in bytes.",
+            "Size of chunks synchronized by different operations ('fetch', 'copy_files', 'copy_chunks', 'preallocate', 'deduplicate') during all the state sync in bytes.",
             &["op"],
         );
 
@@ -305,6 +309,7 @@ impl StateSyncMetrics {
             LABEL_COPY_FILES,
             LABEL_COPY_CHUNKS,
             LABEL_PREALLOCATE,
+            LABEL_DEDUPLICATE,
         ] {
             size.with_label_values(&[*op]);
         }
@@ -361,6 +366,20 @@ impl StateSyncMetrics {
             corrupted_chunks.with_label_values(&[*source]);
         }
 
+        let received_bytes = metrics_registry.int_counter_vec(
+            "state_sync_received_bytes_total",
+            "Size of the chunk payloads received from peers by encoding ('raw', 'deflate') during all the state sync in bytes.",
+            &["encoding"],
+        );
+
+        // Note [Metrics preallocation]
+        for encoding in &[
+            state_sync::chunkable::ENCODING_RAW,
+            state_sync::chunkable::ENCODING_DEFLATE,
+        ] {
+            received_bytes.with_label_values(&[*encoding]);
+        }
+
         Self {
             size,
             duration,
@@ -368,6 +387,7 @@ impl StateSyncMetrics {
             remaining,
             corrupted_chunks_critical,
             corrupted_chunks,
+            received_bytes,
         }
     }
 }
@@ -420,7 +440,8 @@ impl TryFrom<pb::StateMetadata> for StateMetadata {
 #[derive(Debug)]
 struct CertificationMetadata {
     /// Fully materialized hash tree built from the part of the state that is
-    /// certified every round.  Dropped as soon as a higher state is certified.
+    /// certified every round.  Dropped once a state at least
+    /// `CERTIFIED_HASH_TREES_TO_KEEP` heights higher is certified.
     hash_tree: Option<Arc<HashTree>>,
     /// Root hash of the tree above. It's stored even if the hash tree is
     /// dropped.
@@ -457,6 +478,9 @@ impl Drop for CheckpointContext {
         }
 
         let start = Instant::now();
+        if let Ok(checkpoint_layout) = self.state_layout.checkpoint(self.height) {
+            state_sync::chunkable::forget_state_sync_chunk_files(checkpoint_layout.raw_path());
+        }
         if let Err(err) = self.state_layout.remove_checkpoint(self.height) {
             self.metrics
                 .state_manager_error_count
@@ -619,6 +643,13 @@ const MAX_DIVERGED_STATES_TO_KEEP: usize = 2;
 /// The number of extra checkpoints to keep for state sync.
 const EXTRA_CHECKPOINTS_TO_KEEP: usize = 1;
 
+/// The number of most recent heights, up to the latest certified one, whose
+/// hash trees are kept so that queries can read these certified states.
+const CERTIFIED_HASH_TREES_TO_KEEP: u64 = 10;
+
+/// A background thread merging the overlays of page maps.
+type OverlayMerge = JoinOnDrop<Result<Vec<MergedPageMap>, CheckpointError>>;
+
 pub struct StateManagerImpl {
     log: ReplicaLogger,
     metrics: StateManagerMetrics,
@@ -649,6 +680,12 @@ pub struct StateManagerImpl {
     /// The lock should also only be held when no other locks are being held by the
     /// same thread. This applies particularly to the lock for `states`.
     checkpoint_thread_pool: Arc<Mutex<scoped_threadpool::Pool>>,
+    /// How page maps are persisted in checkpoints.
+    page_map_persistence: PageMapPersistence,
+    /// The background merge of the overlays of the page maps of the latest
+    /// checkpoint, see `checkpoint::merge_overlays`. The result is picked up
+    /// when the next checkpoint is created.
+    overlay_merge: Mutex<Option<OverlayMerge>>,
     _state_hasher_handle: JoinOnDrop<()>,
     _deallocation_handle: JoinOnDrop<()>,
     #[cfg(debug_assertions)]
@@ -692,6 +729,7 @@ fn load_checkpoint_as_tip(
     state_layout: &StateLayout,
     snapshot: &Snapshot,
     own_subnet_type: SubnetType,
+    page_map_persistence: PageMapPersistence,
 ) -> ReplicatedState {
     #[cfg(debug_assertions)]
     let _guard = lock
@@ -711,6 +749,13 @@ fn load_checkpoint_as_tip(
     let mut tip = checkpoint::load_checkpoint_parallel(&tip_layout, own_subnet_type, metrics)
         .unwrap_or_else(|err| fatal!(log, "Failed to load checkpoint as tip {:?}", err));
 
+    // Page deltas are written to the tip files in place after every round, so
+    // the tip must not have any overlays.
+    if page_map_persistence == PageMapPersistence::InPlace {
+        checkpoint::flatten_overlays(&tip, &tip_layout)
+            .unwrap_or_else(|err| fatal!(log, "Failed to flatten overlays of tip {:?}", err));
+    }
+
     // Ensure that the `PageMap`s of the tip use the clean read-only checkpoint
     // files similar to how this is done in `commit_and_certify()` after a full
     // checkpoint.
@@ -1129,6 +1174,10 @@ impl StateManagerImpl {
             config.state_root().display()
         );
         let state_layout = StateLayout::new(log.clone(), config.state_root());
+        let page_map_persistence = match config.incremental_checkpoints {
+            FlagStatus::Enabled => PageMapPersistence::Overlays,
+            FlagStatus::Disabled => PageMapPersistence::InPlace,
+        };
 
         state_layout
             .remove_tmp()
@@ -1296,6 +1345,7 @@ impl StateManagerImpl {
                     &state_layout,
                     snapshot,
                     own_subnet_type,
+                    page_map_persistence,
                 );
 
                 info!(
@@ -1405,6 +1455,8 @@ impl StateManagerImpl {
             latest_certified_height,
             state_sync_refs: StateSyncRefs::new(log),
             checkpoint_thread_pool,
+            page_map_persistence,
+            overlay_merge: Mutex::new(None),
             _state_hasher_handle,
             _deallocation_handle,
             #[cfg(debug_assertions)]
@@ -1798,7 +1850,14 @@ impl StateManagerImpl {
 
     /// Flushes to disk all the canister heap deltas accumulated in memory
     /// during one round of execution.
+    ///
+    /// With overlays, the deltas are only written to disk at checkpoints, so
+    /// this does nothing.
     fn flush_page_maps(&self, tip_state: &mut ReplicatedState, height: Height) {
+        if self.page_map_persistence == PageMapPersistence::Overlays {
+            return;
+        }
+
         let tip_layout = self
             .state_layout
             .tip(height)
@@ -1830,6 +1889,68 @@ impl StateManagerImpl {
         }
     }
 
+    /// Starts merging the overlays of the page maps of `checkpointed_state`
+    /// that have too many of them in the background.
+    fn start_overlay_merge(&self, checkpointed_state: &ReplicatedState) {
+        let page_maps = checkpoint::page_maps_to_merge(checkpointed_state);
+        if page_maps.is_empty() {
+            return;
+        }
+        let dir = self.state_layout.tmp().unwrap_or_else(|err| {
+            fatal!(self.log, "Failed to create temporary directory: {}", err)
+        });
+        // The previous merge, if any, was picked up when creating the
+        // checkpoint. Files of merges that are never picked up are removed
+        // together with the tmp directory on restart.
+        *self.overlay_merge.lock().unwrap() = Some(JoinOnDrop::new(
+            std::thread::Builder::new()
+                .name("OverlayMerge".to_string())
+                .spawn(move || checkpoint::merge_overlays(page_maps, &dir))
+                .expect("failed to spawn overlay merge thread"),
+        ));
+    }
+
+    /// Waits for the background merge of overlays, if any, and moves the
+    /// merged page map files to the tip, see
+    /// `checkpoint::install_merged_page_maps`.
+    fn install_merged_page_maps(&self, state: &ReplicatedState, height: Height) {
+        let overlay_merge = match self.overlay_merge.lock().unwrap().take() {
+            Some(overlay_merge) => overlay_merge,
+            None => return,
+        };
+        let _timer = self
+            .metrics
+            .checkpoint_metrics
+            .make_checkpoint_step_duration
+            .with_label_values(&["install_merged_page_maps"])
+            .start_timer();
+        let merged = match overlay_merge.join() {
+            Ok(Ok(merged)) => merged,
+            // Merging is only an optimization, the overlays are merged again
+            // after the next checkpoint.
+            Ok(Err(err)) => {
+                warn!(self.log, "Failed to merge overlays: {}", err);
+                return;
+            }
+            Err(_) => {
+                warn!(self.log, "The overlay merge thread panicked");
+                return;
+            }
+        };
+        let tip_layout = self
+            .state_layout
+            .tip(height)
+            .unwrap_or_else(|err| fatal!(self.log, "Failed to access @TIP: {}", err));
+        checkpoint::install_merged_page_maps(state, &tip_layout, merged).unwrap_or_else(|err| {
+            fatal!(
+                self.log,
+                "Failed to install merged page maps @TIP {}: {}",
+                tip_layout.raw_path().display(),
+                err
+            )
+        });
+    }
+
     fn clone_checkpoint(&self, from: Height, to: Height) -> Result<(), LayoutError> {
         let target_layout = self.state_layout.checkpoint_to_scratchpad(from)?;
         self.state_layout
@@ -2233,6 +2354,7 @@ impl StateManager for StateManagerImpl {
             &self.state_layout,
             &target_snapshot,
             self.own_subnet_type,
+            self.page_map_persistence,
         );
 
         // This might still not be the latest version: there might have been
@@ -2419,9 +2541,14 @@ impl StateManager for StateManagerImpl {
 
             metadata.certification = Some(certification);
 
+            let oldest_hash_tree_height = Height::new(
+                certification_height
+                    .get()
+                    .saturating_sub(CERTIFIED_HASH_TREES_TO_KEEP - 1),
+            );
             for (_, certification_metadata) in states
                 .certifications_metadata
-                .range_mut(Self::INITIAL_STATE_HEIGHT..certification_height)
+                .range_mut(Self::INITIAL_STATE_HEIGHT..oldest_hash_tree_height)
             {
                 if let Some(tree) = certification_metadata.hash_tree.take() {
                     self.deallocation_sender
@@ -2625,9 +2752,13 @@ impl StateManager for StateManagerImpl {
                         })
                 };
 
-                // We don't need to persist the deltas to the tip because we
-                // flush deltas separately every round, see flush_page_maps.
-                strip_page_map_deltas(&mut state);
+                match self.page_map_persistence {
+                    // We don't need to persist the deltas to the tip because we
+                    // flush deltas separately every round, see flush_page_maps.
+                    PageMapPersistence::InPlace => strip_page_map_deltas(&mut state),
+                    // The deltas are written to overlays by make_checkpoint.
+                    PageMapPersistence::Overlays => self.install_merged_page_maps(&state, height),
+                }
                 let result = {
                     let mut thread_pool = self.checkpoint_thread_pool.lock().unwrap();
                     checkpoint::make_checkpoint(
@@ -2637,8 +2768,12 @@ impl StateManager for StateManagerImpl {
                         &self.log,
                         &self.metrics.checkpoint_metrics,
                         &mut thread_pool,
+                        self.page_map_persistence,
                     )
                 };
+                if self.page_map_persistence == PageMapPersistence::Overlays {
+                    strip_page_map_deltas(&mut state);
+                }
 
                 let elapsed = start.elapsed();
                 let checkpointed_state = match result {
@@ -2689,6 +2824,9 @@ impl StateManager for StateManagerImpl {
                     ),
                 };
                 switch_to_checkpoint(&mut state, &checkpointed_state);
+                if self.page_map_persistence == PageMapPersistence::Overlays {
+                    self.start_overlay_merge(&checkpointed_state);
+                }
                 checkpointed_state
             }
             CertificationScope::Metadata => state.clone(),
@@ -2919,6 +3057,52 @@ impl StateReader for StateManagerImpl {
 
         Some((state, mixed_hash_tree, certification))
     }
+
+    fn read_certified_state_at(
+        &self,
+        height: Height,
+        paths: &LabeledTree<()>,
+    ) -> StateManagerResult<Option<(Arc<Self::State>, MixedHashTree, Certification)>> {
+        let _timer = self
+            .metrics
+            .api_call_duration
+            .with_label_values(&["read_certified_state_at"])
+            .start_timer();
+
+        if self.latest_state_height() < height {
+            return Err(StateManagerError::StateNotCommittedYet(height));
+        }
+        let (state, certification, hash_tree) = {
+            let states = self.states.read();
+            let state = states
+                .snapshots
+                .iter()
+                .find_map(|snapshot| {
+                    (snapshot.height == height).then(|| Arc::clone(&snapshot.state))
+                })
+                .ok_or(StateManagerError::StateRemoved(height))?;
+            let metadata = states
+                .certifications_metadata
+                .get(&height)
+                .ok_or(StateManagerError::StateRemoved(height))?;
+            let certification = match metadata.certification.clone() {
+                Some(certification) => certification,
+                None => return Ok(None),
+            };
+            // Rehashing the whole state on demand would be too expensive, so
+            // states whose hash tree was dropped can't be read anymore.
+            let hash_tree = metadata
+                .hash_tree
+                .clone()
+                .ok_or(StateManagerError::StateRemoved(height))?;
+            (state, certification, hash_tree)
+        };
+
+        let lazy_tree = LazyTree::from(&*state);
+        Ok(materialize_partial(&lazy_tree, paths)
+            .map(|partial_tree| hash_tree.witness::<MixedHashTree>(&partial_tree))
+            .map(|mixed_hash_tree| (Arc::clone(&state), mixed_hash_tree, certification)))
+    }
 }
 
 impl CertifiedStreamStore for StateManagerImpl {
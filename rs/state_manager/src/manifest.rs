@@ -12,8 +12,8 @@ use bit_vec::BitVec;
 use hash::{chunk_hasher, file_hasher, manifest_hasher, ManifestHash};
 use ic_crypto_sha::Sha256;
 use ic_logger::{error, fatal, ReplicaLogger};
-use ic_replicated_state::PageIndex;
-use ic_state_layout::{CheckpointLayout, ReadOnly};
+use ic_replicated_state::{page_map::PageMap, PageIndex};
+use ic_state_layout::{is_overlay_file, overlays_of, CheckpointLayout, ReadOnly};
 use ic_sys::{mmap::ScopedMmap, PAGE_SIZE};
 use ic_types::{
     state_sync::{ChunkInfo, FileInfo, Manifest},
@@ -21,8 +21,10 @@ use ic_types::{
 };
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
+use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
+use std::io::Read;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, Weak};
@@ -129,6 +131,100 @@ impl std::error::Error for ChunkValidationError {}
 #[derive(Clone)]
 struct FileWithSize(PathBuf, u64);
 
+/// The contents of a checkpoint file as they are seen by the manifest and by
+/// state sync.
+///
+/// A page map file that has overlays is read through a `PageMap`, so that its
+/// contents are the same as if all the overlays were merged into it. This keeps
+/// the manifest independent of how page maps are stored on disk.
+pub enum FileContents {
+    Mmap(ScopedMmap),
+    PageMap(PageMap),
+}
+
+impl FileContents {
+    /// Opens the file at `path` together with its overlays, if any.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let overlays = overlays_of(path).map_err(|err| err.to_string())?;
+        if overlays.is_empty() {
+            ScopedMmap::from_path(path)
+                .map(Self::Mmap)
+                .map_err(|err| err.to_string())
+        } else {
+            let overlays: Vec<_> = overlays.into_iter().map(|(_, path)| path).collect();
+            PageMap::open_with_overlays(path, &overlays, None)
+                .map(Self::PageMap)
+                .map_err(|err| err.to_string())
+        }
+    }
+
+    /// Returns the size of the contents in bytes.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Mmap(mmap) => mmap.len(),
+            Self::PageMap(page_map) => page_map.num_host_pages() * PAGE_SIZE,
+        }
+    }
+
+    /// Returns true if the file has no contents.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a reader over the contents, which reads them in bounded pieces
+    /// so that page files with overlays don't have to be materialized in
+    /// memory all at once.
+    pub fn reader(&self) -> FileContentsReader<'_> {
+        FileContentsReader {
+            contents: self,
+            offset: 0,
+        }
+    }
+
+    /// Returns the bytes in the given range. Panics if the range is out of
+    /// bounds.
+    pub fn read(&self, range: Range<usize>) -> Cow<'_, [u8]> {
+        match self {
+            Self::Mmap(mmap) => Cow::Borrowed(&mmap.as_slice()[range]),
+            Self::PageMap(page_map) => {
+                assert!(range.end <= self.len());
+                let mut bytes = Vec::with_capacity(range.len());
+                let mut offset = range.start;
+                while offset < range.end {
+                    let page = page_map.get_page(PageIndex::from((offset / PAGE_SIZE) as u64));
+                    let start = offset % PAGE_SIZE;
+                    let end = PAGE_SIZE.min(start + range.end - offset);
+                    bytes.extend_from_slice(&page[start..end]);
+                    offset += end - start;
+                }
+                Cow::Owned(bytes)
+            }
+        }
+    }
+}
+
+/// The maximum number of bytes a `FileContentsReader` reads at once.
+pub(crate) const READ_BUFFER_SIZE: usize = 1 << 20;
+
+/// Reads `FileContents` sequentially, see `FileContents::reader`.
+pub struct FileContentsReader<'a> {
+    contents: &'a FileContents,
+    offset: usize,
+}
+
+impl Read for FileContentsReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let end = self
+            .contents
+            .len()
+            .min(self.offset + buf.len().min(READ_BUFFER_SIZE));
+        let data = self.contents.read(self.offset..end);
+        buf[..data.len()].copy_from_slice(&data);
+        self.offset = end;
+        Ok(data.len())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum ChunkAction {
     /// Recompute the hash of the chunk, as no previously computed hash is
@@ -254,7 +350,7 @@ fn build_chunk_table_parallel(
     // and close the corresponding file.
     // This way we keep the number of files opened at the same time
     // low (it doesn't exceed the number of the threads).
-    let file_cache: Arc<Mutex<HashMap<u32, Weak<ScopedMmap>>>> =
+    let file_cache: Arc<Mutex<HashMap<u32, Weak<FileContents>>>> =
         Arc::new(Mutex::new(HashMap::new()));
 
     // Compute real chunk hashes in parallel.
@@ -268,33 +364,32 @@ fn build_chunk_table_parallel(
             let file_cache = Arc::clone(&file_cache);
             scope.execute(move || {
                 let recompute_chunk_hash = || {
-                    let mmap: Arc<ScopedMmap> = if file_size > max_chunk_size as u64 {
+                    let contents: Arc<FileContents> = if file_size > max_chunk_size as u64 {
                         // We only use the file cache if there is more than one chunk in the file,
                         // otherwise the synchronization cost is unnecessary.
                         let mut cache = file_cache.lock().unwrap();
                         match cache.get(&chunk_info.file_index).and_then(Weak::upgrade) {
-                            Some(mmap) => mmap,
+                            Some(contents) => contents,
                             None => {
-                                let mmap = Arc::new(
-                                    ScopedMmap::from_path(&file_path)
-                                        .unwrap_or_else(|e| fatal!(log, "failed to mmap file {}: {}", file_path.display(), e)),
+                                let contents = Arc::new(
+                                    FileContents::open(&file_path)
+                                        .unwrap_or_else(|e| fatal!(log, "failed to open file {}: {}", file_path.display(), e)),
                                 );
-                                cache.insert(chunk_info.file_index, Arc::downgrade(&mmap));
-                                mmap
+                                cache.insert(chunk_info.file_index, Arc::downgrade(&contents));
+                                contents
                             }
                         }
                     } else {
                         Arc::new(
-                            ScopedMmap::from_path(&file_path)
-                                .unwrap_or_else(|e| fatal!(log, "failed to mmap file {}: {}", file_path.display(), e))
+                            FileContents::open(&file_path)
+                                .unwrap_or_else(|e| fatal!(log, "failed to open file {}: {}", file_path.display(), e))
                         )
                     };
-                    let data = mmap.as_slice();
 
                     let mut hasher = chunk_hasher();
                     let chunk_start = chunk_info.offset as usize;
                     let chunk_end = chunk_start + chunk_info.size_bytes as usize;
-                    hasher.write(&data[chunk_start..chunk_end]);
+                    hasher.write(&contents.read(chunk_start..chunk_end));
                     hasher.finish()
                 };
 
@@ -368,7 +463,7 @@ fn build_chunk_table_sequential(
 
         (num_chunks as u32).update_hash(&mut file_hash);
 
-        let compute_file_chunk_hashes = |data: &[u8]| {
+        let compute_file_chunk_hashes = |contents: &FileContents| {
             // It's OK to not have any chunks for 0-sized files (though it's unlikely that
             // we have any).
             while bytes_left > 0 {
@@ -377,7 +472,7 @@ fn build_chunk_table_sequential(
 
                 let recompute_chunk_hash = || {
                     let mut hasher = chunk_hasher();
-                    hasher.write(&data[offset as usize..(offset + chunk_size) as usize]);
+                    hasher.write(&contents.read(offset as usize..(offset + chunk_size) as usize));
                     hasher.finish()
                 };
 
@@ -446,9 +541,8 @@ fn build_chunk_table_sequential(
             });
         };
 
-        let mmap = ScopedMmap::from_path(root.join(&relative_path)).expect("failed to open file");
-        let data = mmap.as_slice();
-        compute_file_chunk_hashes(data);
+        let contents = FileContents::open(&root.join(&relative_path)).expect("failed to open file");
+        compute_file_chunk_hashes(&contents);
     }
 
     assert_eq!(chunk_table.len(), chunk_actions.len());
@@ -456,6 +550,51 @@ fn build_chunk_table_sequential(
     (file_table, chunk_table)
 }
 
+/// Removes the overlay files from `files` and replaces the sizes of the page
+/// map files that have overlays by the sizes of their contents with the
+/// overlays merged in.
+fn fold_overlays(root: &Path, files: &mut Vec<FileWithSize>) -> Result<(), CheckpointError> {
+    // The paths of the page map files that have overlays, without extension.
+    // The overlays of `vmemory_0.bin` are named `vmemory_0.<height>.overlay`.
+    let mut with_overlays = HashSet::new();
+    files.retain(|FileWithSize(relative_path, _)| {
+        if !is_overlay_file(relative_path) {
+            return true;
+        }
+        with_overlays.insert(relative_path.with_extension("").with_extension(""));
+        false
+    });
+    for FileWithSize(relative_path, size_bytes) in files.iter_mut() {
+        if with_overlays.contains(&relative_path.with_extension("")) {
+            let path = root.join(&relative_path);
+            *size_bytes = FileContents::open(&path)
+                .map_err(|err| CheckpointError::IoError {
+                    path: path.clone(),
+                    message: "failed to open page map with overlays".to_string(),
+                    io_err: err,
+                })?
+                .len() as u64;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the indices of the chunks of all the files in `manifest` that have
+/// overlays in the checkpoint at `root`. These chunks cannot be copied from
+/// the files on disk directly.
+pub(crate) fn chunks_of_files_with_overlays(manifest: &Manifest, root: &Path) -> HashSet<usize> {
+    let mut chunks = HashSet::new();
+    for (file_index, file_info) in manifest.file_table.iter().enumerate() {
+        let has_overlays = overlays_of(&root.join(&file_info.relative_path))
+            .map(|overlays| !overlays.is_empty())
+            .unwrap_or(true);
+        if has_overlays {
+            chunks.extend(file_chunk_range(&manifest.chunk_table, file_index));
+        }
+    }
+    chunks
+}
+
 /// Traverses root recursively and populates the `files` vector with entries of
 /// the form `(relative_file_name, file_len)`.
 fn files_with_sizes(
@@ -745,6 +884,7 @@ pub fn compute_manifest(
     files_with_sizes(checkpoint_root_path, "".into(), &mut files)?;
     // We sort the table to make sure that the table is the same on all replicas
     files.sort_unstable_by(|lhs, rhs| lhs.0.cmp(&rhs.0));
+    fold_overlays(checkpoint_root_path, &mut files)?;
 
     let chunk_actions = match opt_manifest_delta {
         Some(manifest_delta) => {
@@ -1041,3 +1181,30 @@ pub fn filter_out_zero_chunks(manifest: &Manifest) -> HashSet<usize> {
         .collect();
     fetch_chunks
 }
+
+/// Groups the given chunk indices by chunk contents.  Returns a map from the
+/// lowest index of every group to the remaining indices of that group, e.g.
+/// the chunks of identical wasm modules installed on different canisters.
+pub fn group_identical_chunks(
+    manifest: &Manifest,
+    chunks: &HashSet<usize>,
+) -> BTreeMap<usize, Vec<usize>> {
+    let mut sorted_chunks: Vec<usize> = chunks.iter().copied().collect();
+    sorted_chunks.sort_unstable();
+
+    let mut first_with_contents: HashMap<(u32, [u8; 32]), usize> = HashMap::new();
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for index in sorted_chunks {
+        let chunk_info = &manifest.chunk_table[index];
+        match first_with_contents.entry((chunk_info.size_bytes, chunk_info.hash)) {
+            std::collections::hash_map::Entry::Occupied(first) => {
+                groups.entry(*first.get()).or_default().push(index)
+            }
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(index);
+                groups.insert(index, vec![]);
+            }
+        }
+    }
+    groups
+}
@@ -1,16 +1,20 @@
 use super::{
-    compute_manifest, diff_manifest, file_chunk_range, filter_out_zero_chunks, hash::ManifestHash,
-    manifest_hash, validate_chunk, validate_manifest, ChunkValidationError, DiffScript,
-    ManifestValidationError, CURRENT_STATE_SYNC_VERSION, STATE_SYNC_V1,
+    compute_manifest, diff_manifest, file_chunk_range, filter_out_zero_chunks,
+    group_identical_chunks, hash::ManifestHash, manifest_hash, validate_chunk, validate_manifest,
+    ChunkValidationError, DiffScript, ManifestValidationError, CURRENT_STATE_SYNC_VERSION,
+    STATE_SYNC_V1,
 };
 use crate::ManifestMetrics;
 
 use ic_crypto_sha::Sha256;
 use ic_metrics::MetricsRegistry;
+use ic_replicated_state::{page_map::PageMap, PageIndex};
+use ic_state_layout::overlay_path;
+use ic_sys::PAGE_SIZE;
 use ic_types::{
     crypto::CryptoHash,
     state_sync::{decode_manifest, encode_manifest, ChunkInfo, FileInfo, Manifest},
-    CryptoHashOfState,
+    CryptoHashOfState, Height,
 };
 
 use ic_logger::replica_logger::no_op_logger;
@@ -495,6 +499,42 @@ fn test_filter_all_zero_chunks() {
     assert_eq!(filter_out_zero_chunks(&manifest), fetch_chunks);
 }
 
+#[test]
+fn test_group_identical_chunks() {
+    let metrics_registry = MetricsRegistry::new();
+    let manifest_metrics = ManifestMetrics::new(&metrics_registry);
+    let dir = tempfile::TempDir::new().expect("failed to create a temporary directory");
+    let root = dir.path();
+
+    // Chunks 0, 1 and 2 have the same contents, chunk 3 is a prefix of them
+    // and chunk 4 differs.
+    fs::write(root.join("a.bin"), vec![1u8; 2048 * 1024]).expect("failed to create file 'a.bin'");
+    fs::write(root.join("b.bin"), vec![1u8; 1024 * 1024]).expect("failed to create file 'b.bin'");
+    fs::write(root.join("c.bin"), vec![1u8; 512 * 1024]).expect("failed to create file 'c.bin'");
+    fs::write(root.join("d.bin"), vec![2u8; 1024 * 1024]).expect("failed to create file 'd.bin'");
+
+    let mut thread_pool = scoped_threadpool::Pool::new(NUM_THREADS);
+    let manifest = compute_manifest(
+        &mut thread_pool,
+        &manifest_metrics,
+        &no_op_logger(),
+        STATE_SYNC_V1,
+        root,
+        1024 * 1024,
+        None,
+    )
+    .expect("failed to compute manifest");
+
+    assert_eq!(
+        group_identical_chunks(&manifest, &maplit::hashset! {0, 1, 2, 3, 4}),
+        maplit::btreemap! {0 => vec![1, 2], 3 => vec![], 4 => vec![]}
+    );
+    assert_eq!(
+        group_identical_chunks(&manifest, &maplit::hashset! {1, 2, 4}),
+        maplit::btreemap! {1 => vec![2], 4 => vec![]}
+    );
+}
+
 #[test]
 fn test_missing_simple_manifest() {
     let (_, manifest_old) = simple_manifest();
@@ -774,3 +814,47 @@ fn test_file_chunk_range() {
         );
     }
 }
+
+#[test]
+fn manifest_does_not_depend_on_overlays() {
+    let metrics_registry = MetricsRegistry::new();
+    let manifest_metrics = ManifestMetrics::new(&metrics_registry);
+    let mut thread_pool = scoped_threadpool::Pool::new(NUM_THREADS);
+    let mut compute = |root: &std::path::Path| {
+        compute_manifest(
+            &mut thread_pool,
+            &manifest_metrics,
+            &no_op_logger(),
+            STATE_SYNC_V1,
+            root,
+            2 * PAGE_SIZE as u32,
+            None,
+        )
+        .expect("failed to compute manifest")
+    };
+
+    let with_overlays = tempfile::TempDir::new().expect("failed to create a temporary directory");
+    let in_place = tempfile::TempDir::new().expect("failed to create a temporary directory");
+    for dir in [&with_overlays, &in_place] {
+        fs::write(dir.path().join("vmemory_0.bin"), vec![1u8; 3 * PAGE_SIZE])
+            .expect("failed to create file 'vmemory_0.bin'");
+    }
+
+    let base = with_overlays.path().join("vmemory_0.bin");
+    let mut page_map = PageMap::open(&base, Some(Height::new(1))).unwrap();
+    page_map.update(&[
+        (PageIndex::new(1), &[2; PAGE_SIZE]),
+        (PageIndex::new(5), &[3; PAGE_SIZE]),
+    ]);
+    page_map
+        .persist_delta_as_overlay(&overlay_path(&base, Height::new(2)))
+        .unwrap();
+    page_map
+        .persist_and_sync_delta(&in_place.path().join("vmemory_0.bin"))
+        .unwrap();
+
+    let manifest = compute(with_overlays.path());
+    assert_eq!(manifest.file_table.len(), 1);
+    assert_eq!(manifest.file_table[0].size_bytes, 6 * PAGE_SIZE as u64);
+    assert_eq!(manifest, compute(in_place.path()));
+}
@@ -45,6 +45,7 @@ impl ArtifactKind for StateSyncArtifact {
             attribute: StateSyncAttribute {
                 height: msg.height,
                 root_hash: msg.root_hash.clone(),
+                supports_compressed_chunks: true,
             },
             size: size as usize,
             integrity_hash: crypto_hash(msg).get(),
@@ -99,6 +100,9 @@ impl ArtifactClient<StateSyncArtifact> for StateManagerImpl {
                         get_state_sync_chunk: Some(
                             crate::state_sync::chunkable::get_state_sync_chunk,
                         ),
+                        compress_state_sync_chunk: Some(
+                            crate::state_sync::chunkable::compress_state_sync_chunk,
+                        ),
                     })
                 } else {
                     None
@@ -149,6 +153,9 @@ impl ArtifactClient<StateSyncArtifact> for StateManagerImpl {
                         get_state_sync_chunk: Some(
                             crate::state_sync::chunkable::get_state_sync_chunk,
                         ),
+                        compress_state_sync_chunk: Some(
+                            crate::state_sync::chunkable::compress_state_sync_chunk,
+                        ),
                     };
                     Some(StateSyncArtifact::message_to_advert(&msg))
                 } else {
@@ -1,8 +1,8 @@
 use crate::{
-    manifest::{filter_out_zero_chunks, DiffScript},
+    manifest::{filter_out_zero_chunks, DiffScript, FileContents},
     CheckpointRef, StateManagerMetrics, StateSyncMetrics, StateSyncRefs,
-    CRITICAL_ERROR_STATE_SYNC_CORRUPTED_CHUNKS, LABEL_COPY_CHUNKS, LABEL_COPY_FILES, LABEL_FETCH,
-    LABEL_PREALLOCATE,
+    CRITICAL_ERROR_STATE_SYNC_CORRUPTED_CHUNKS, LABEL_COPY_CHUNKS, LABEL_COPY_FILES,
+    LABEL_DEDUPLICATE, LABEL_FETCH, LABEL_PREALLOCATE,
 };
 use ic_logger::{debug, error, fatal, info, trace, warn, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
@@ -17,9 +17,10 @@ use ic_types::{
         ChunkId, Chunkable,
     },
     crypto::CryptoHash,
-    state_sync::{decode_manifest, Manifest, MANIFEST_CHUNK},
+    state_sync::{decode_chunk_id, decode_manifest, Manifest, MANIFEST_CHUNK},
     CryptoHashOfState, Height,
 };
+use std::borrow::Cow;
 use std::os::unix::fs::FileExt;
 use std::path::{Path, PathBuf};
 use std::time::Instant;
@@ -34,6 +35,19 @@ pub mod cache;
 // necessary.
 const ALWAYS_VALIDATE: bool = false;
 
+// The first byte of a chunk requested in its compressed encoding tells how the
+// rest of the payload is encoded.
+const CHUNK_ENCODING_RAW: u8 = 0;
+const CHUNK_ENCODING_DEFLATE: u8 = 1;
+
+/// Metric labels of the chunk encodings.
+pub(crate) const ENCODING_RAW: &str = "raw";
+pub(crate) const ENCODING_DEFLATE: &str = "deflate";
+
+// Upper bound on the size of the decompressed manifest chunk, protecting us
+// against peers sending small payloads that decompress to huge ones.
+const MAX_MANIFEST_CHUNK_SIZE: u64 = 1 << 30;
+
 /// The state of the communication with up-to-date nodes.
 #[derive(Clone)]
 enum DownloadState {
@@ -47,6 +61,10 @@ enum DownloadState {
         /// set chunk 0 is the manifest. To get indices into the manifests's
         /// chunk table subtract 1.
         fetch_chunks: HashSet<usize>,
+        /// Chunks that have the same contents as a chunk in `fetch_chunks`,
+        /// keyed by that chunk. They are not fetched but written when the
+        /// chunk they are keyed by is received.
+        duplicate_chunks: HashMap<usize, Vec<usize>>,
     },
     /// Successfully completed and returned the artifact to P2P, nothing else to
     /// do.
@@ -95,10 +113,7 @@ impl Drop for IncompleteState {
                     .with_label_values(&["aborted_blank"])
                     .observe(elapsed.as_secs_f64());
             }
-            DownloadState::Loading {
-                manifest: _,
-                fetch_chunks: _,
-            } => {
+            DownloadState::Loading { .. } => {
                 self.metrics
                     .state_sync_metrics
                     .duration
@@ -113,12 +128,14 @@ impl Drop for IncompleteState {
         if let DownloadState::Loading {
             manifest: _,
             ref fetch_chunks,
+            ref duplicate_chunks,
         } = self.state
         {
+            let num_duplicates: usize = duplicate_chunks.values().map(Vec::len).sum();
             self.metrics
                 .state_sync_metrics
                 .remaining
-                .sub(fetch_chunks.len() as i64);
+                .sub((fetch_chunks.len() + num_duplicates) as i64);
         }
 
         // We need to record the download state before passing self to the cache, as
@@ -137,17 +154,121 @@ impl Drop for IncompleteState {
     }
 }
 
+// The maximum number of page map files with overlays that are kept open for
+// serving state sync chunks.
+const MAX_OPEN_STATE_SYNC_FILES: usize = 16;
+
+// Page map files with overlays that chunks were recently served from, most
+// recently used last. Opening such a file means opening its base and folding
+// all of its overlays, which is too expensive to repeat for every chunk.
+// Checkpoint files never change, so an entry stays valid until its checkpoint
+// is removed, see `forget_state_sync_chunk_files`.
+static OPEN_STATE_SYNC_FILES: parking_lot::Mutex<Vec<(PathBuf, Arc<FileContents>)>> =
+    parking_lot::const_mutex(Vec::new());
+
+// Returns the contents of the page map file at `file_path` if it has overlays,
+// opening it only if it isn't open already. Returns `None` for plain files.
+fn open_state_sync_file(file_path: &Path) -> std::io::Result<Option<Arc<FileContents>>> {
+    let mut open_files = OPEN_STATE_SYNC_FILES.lock();
+    if let Some(pos) = open_files.iter().position(|(path, _)| path == file_path) {
+        let entry = open_files.remove(pos);
+        let contents = Arc::clone(&entry.1);
+        open_files.push(entry);
+        return Ok(Some(contents));
+    }
+    if !ic_state_layout::overlays_of(file_path)
+        .map(|overlays| !overlays.is_empty())
+        .unwrap_or(false)
+    {
+        return Ok(None);
+    }
+    let contents = FileContents::open(file_path)
+        .map(Arc::new)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+    if open_files.len() >= MAX_OPEN_STATE_SYNC_FILES {
+        open_files.remove(0);
+    }
+    open_files.push((file_path.to_path_buf(), Arc::clone(&contents)));
+    Ok(Some(contents))
+}
+
+/// Closes the files under `checkpoint_root` that were kept open for serving
+/// state sync chunks. Must be called when the checkpoint is removed, so that
+/// its files don't stay mapped.
+pub(crate) fn forget_state_sync_chunk_files(checkpoint_root: &Path) {
+    OPEN_STATE_SYNC_FILES
+        .lock()
+        .retain(|(path, _)| !path.starts_with(checkpoint_root));
+}
+
 pub(crate) fn get_state_sync_chunk(
     file_path: PathBuf,
     offset: u64,
     len: u32,
 ) -> std::io::Result<Vec<u8>> {
+    // The contents of page map files with overlays are spread over several
+    // files.
+    if let Some(contents) = open_state_sync_file(&file_path)? {
+        let range = offset as usize..offset as usize + len as usize;
+        if range.end > contents.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("chunk {:?} is out of range", range),
+            ));
+        }
+        return Ok(contents.read(range).into_owned());
+    }
     let mut buf = vec![0; len as usize];
     let f = std::fs::File::open(&file_path)?;
     f.read_exact_at(&mut buf[..], offset)?;
     Ok(buf)
 }
 
+/// Encodes a chunk for a peer that requested it compressed.  Chunks that do
+/// not shrink under compression are sent as they are.
+pub(crate) fn compress_state_sync_chunk(chunk: Vec<u8>) -> Vec<u8> {
+    use flate2::{write::DeflateEncoder, Compression};
+    use std::io::Write;
+
+    let mut encoder = DeflateEncoder::new(vec![CHUNK_ENCODING_DEFLATE], Compression::fast());
+    match encoder.write_all(&chunk).and_then(|()| encoder.finish()) {
+        Ok(compressed) if compressed.len() <= chunk.len() => compressed,
+        _ => {
+            let mut buf = Vec::with_capacity(chunk.len() + 1);
+            buf.push(CHUNK_ENCODING_RAW);
+            buf.extend_from_slice(&chunk);
+            buf
+        }
+    }
+}
+
+/// Decodes a chunk produced by `compress_state_sync_chunk`, refusing to
+/// produce more than `max_size` bytes.  Returns the chunk and the label of its
+/// encoding.
+pub(crate) fn decompress_state_sync_chunk(
+    payload: &[u8],
+    max_size: u64,
+) -> Result<(Cow<[u8]>, &'static str), String> {
+    use std::io::Read;
+
+    match payload.split_first() {
+        Some((&CHUNK_ENCODING_RAW, chunk)) => Ok((Cow::Borrowed(chunk), ENCODING_RAW)),
+        Some((&CHUNK_ENCODING_DEFLATE, compressed)) => {
+            let mut chunk = vec![];
+            flate2::read::DeflateDecoder::new(compressed)
+                .take(max_size + 1)
+                .read_to_end(&mut chunk)
+                .map_err(|err| format!("failed to decompress chunk: {}", err))?;
+            if chunk.len() as u64 > max_size {
+                return Err(format!("decompressed chunk exceeds {} bytes", max_size));
+            }
+            Ok((Cow::Owned(chunk), ENCODING_DEFLATE))
+        }
+        Some((encoding, _)) => Err(format!("unknown chunk encoding {}", encoding)),
+        None => Err("empty chunk payload".to_string()),
+    }
+}
+
 impl IncompleteState {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -732,6 +853,7 @@ impl IncompleteState {
                 .to_path_buf(),
             manifest: manifest.clone(),
             get_state_sync_chunk: Some(get_state_sync_chunk),
+            compress_state_sync_chunk: Some(compress_state_sync_chunk),
         })
     }
 
@@ -827,8 +949,13 @@ impl IncompleteState {
 
     /// Preallocates the files listed in the manifest and copies the chunks
     /// that we have locally.
-    /// Returns a set of chunks that still need to be fetched
-    fn initialize_state_on_disk(&mut self, manifest_new: &Manifest) -> HashSet<usize> {
+    /// Returns a set of chunks that still need to be fetched, together with
+    /// the chunks that have the same contents as one of them (see
+    /// `DownloadState::Loading`).
+    fn initialize_state_on_disk(
+        &mut self,
+        manifest_new: &Manifest,
+    ) -> (HashSet<usize>, HashMap<usize, Vec<usize>>) {
         Self::preallocate_layout(&self.log, &self.root, manifest_new);
 
         let state_sync_size_fetch = self
@@ -851,6 +978,11 @@ impl IncompleteState {
             .state_sync_metrics
             .size
             .with_label_values(&[LABEL_PREALLOCATE]);
+        let state_sync_size_deduplicate = self
+            .metrics
+            .state_sync_metrics
+            .size
+            .with_label_values(&[LABEL_DEDUPLICATE]);
         let total_bytes: u64 = manifest_new.file_table.iter().map(|f| f.size_bytes).sum();
 
         self.metrics
@@ -955,9 +1087,9 @@ impl IncompleteState {
                 (None, None) => None,
             };
 
-        if let Some(DiffData {
+        let fetch_chunks: HashSet<usize> = if let Some(DiffData {
             manifest_old,
-            missing_chunks,
+            mut missing_chunks,
             root_old,
             height_old,
             validate_data,
@@ -974,6 +1106,12 @@ impl IncompleteState {
                 },
                 height_old
             );
+            // Page map files with overlays cannot be copied from the old
+            // checkpoint as they are, so their chunks are fetched instead.
+            missing_chunks.extend(crate::manifest::chunks_of_files_with_overlays(
+                manifest_old,
+                &root_old,
+            ));
             let diff_script =
                 crate::manifest::diff_manifest(manifest_old, &missing_chunks, manifest_new);
             debug!(
@@ -1004,7 +1142,6 @@ impl IncompleteState {
             let copy_chunks_bytes: u64 =
                 total_bytes - diff_bytes - preallocate_bytes - copy_files_bytes;
 
-            state_sync_size_fetch.inc_by(diff_bytes);
             state_sync_size_preallocate.inc_by(preallocate_bytes);
             state_sync_size_copy_files.inc_by(copy_files_bytes);
             state_sync_size_copy_chunks.inc_by(copy_chunks_bytes);
@@ -1053,7 +1190,6 @@ impl IncompleteState {
                 .iter()
                 .map(|i| manifest_new.chunk_table[*i].size_bytes as u64)
                 .sum();
-            state_sync_size_fetch.inc_by(diff_bytes);
             state_sync_size_preallocate.inc_by(total_bytes - diff_bytes);
 
             let zeros_chunks = manifest_new.chunk_table.len() - non_zero_chunks.len();
@@ -1064,7 +1200,25 @@ impl IncompleteState {
                 .sub(zeros_chunks as i64);
 
             non_zero_chunks.iter().map(|i| *i + 1).collect()
+        };
+
+        // Chunks with identical contents are fetched only once.
+        let chunk_bytes = |i: &usize| manifest_new.chunk_table[*i].size_bytes as u64;
+        let chunk_table_indices = fetch_chunks.iter().map(|i| *i - 1).collect();
+        let mut unique_chunks = HashSet::new();
+        let mut duplicate_chunks = HashMap::new();
+        for (chunk, duplicates) in
+            crate::manifest::group_identical_chunks(manifest_new, &chunk_table_indices)
+        {
+            state_sync_size_fetch.inc_by(chunk_bytes(&chunk));
+            state_sync_size_deduplicate.inc_by(duplicates.iter().map(chunk_bytes).sum());
+
+            unique_chunks.insert(chunk + 1);
+            if !duplicates.is_empty() {
+                duplicate_chunks.insert(chunk + 1, duplicates.iter().map(|i| *i + 1).collect());
+            }
         }
+        (unique_chunks, duplicate_chunks)
     }
 }
 
@@ -1078,8 +1232,7 @@ impl Chunkable for IncompleteState {
             DownloadState::Blank => Box::new(std::iter::once(MANIFEST_CHUNK)),
             DownloadState::Complete(_) => Box::new(std::iter::empty()),
             DownloadState::Loading {
-                manifest: _,
-                ref fetch_chunks,
+                ref fetch_chunks, ..
             } => {
                 #[allow(clippy::needless_collect)]
                 let ids: Vec<_> = fetch_chunks
@@ -1096,7 +1249,8 @@ impl Chunkable for IncompleteState {
     }
 
     fn add_chunk(&mut self, artifact_chunk: ArtifactChunk) -> Result<Artifact, ArtifactErrorCode> {
-        let ix = artifact_chunk.chunk_id.get() as usize;
+        let (chunk_id, compressed) = decode_chunk_id(artifact_chunk.chunk_id);
+        let ix = chunk_id.get() as usize;
 
         let payload = match artifact_chunk.artifact_chunk_data {
             ArtifactChunkData::SemiStructuredChunkData(ref payload) => payload,
@@ -1106,19 +1260,46 @@ impl Chunkable for IncompleteState {
             }
         };
 
+        let log = &self.log;
+        let metrics = &self.metrics;
+        // Returns the contents of the received chunk, expected to be at most
+        // `max_size` bytes long.
+        let decode_payload = |max_size: u64| -> Result<Cow<[u8]>, ArtifactErrorCode> {
+            let (chunk, encoding) = if compressed {
+                decompress_state_sync_chunk(payload, max_size).map_err(|err| {
+                    warn!(log, "Failed to decode chunk {}: {}", ix, err);
+                    metrics
+                        .state_sync_metrics
+                        .corrupted_chunks
+                        .with_label_values(&[LABEL_FETCH])
+                        .inc();
+                    ChunkVerificationFailed
+                })?
+            } else {
+                (Cow::Borrowed(payload.as_slice()), ENCODING_RAW)
+            };
+            metrics
+                .state_sync_metrics
+                .received_bytes
+                .with_label_values(&[encoding])
+                .inc_by(payload.len() as u64);
+            Ok(chunk)
+        };
+
         match &mut self.state {
             DownloadState::Complete(ref artifact) => {
                 debug!(
                     self.log,
-                    "Received chunk {} on completed state {}", artifact_chunk.chunk_id, self.height
+                    "Received chunk {} on completed state {}", chunk_id, self.height
                 );
 
                 Ok(*artifact.clone())
             }
 
             DownloadState::Blank => {
-                if artifact_chunk.chunk_id == MANIFEST_CHUNK {
-                    let manifest = decode_manifest(payload).map_err(|err| {
+                if chunk_id == MANIFEST_CHUNK {
+                    let payload = decode_payload(MAX_MANIFEST_CHUNK_SIZE)?;
+                    let manifest = decode_manifest(&payload).map_err(|err| {
                         warn!(
                             self.log,
                             "Failed to decode manifest chunk for state {}: {}", self.height, err
@@ -1142,7 +1323,7 @@ impl Chunkable for IncompleteState {
 
                     trace!(self.log, "Received manifest:\n{}", manifest);
 
-                    let fetch_chunks = self.initialize_state_on_disk(&manifest);
+                    let (fetch_chunks, duplicate_chunks) = self.initialize_state_on_disk(&manifest);
 
                     if fetch_chunks.is_empty() {
                         debug!(
@@ -1177,6 +1358,7 @@ impl Chunkable for IncompleteState {
                         self.state = DownloadState::Loading {
                             manifest,
                             fetch_chunks,
+                            duplicate_chunks,
                         };
                         Err(ChunksMoreNeeded)
                     }
@@ -1191,8 +1373,9 @@ impl Chunkable for IncompleteState {
             DownloadState::Loading {
                 ref manifest,
                 ref mut fetch_chunks,
+                ref mut duplicate_chunks,
             } => {
-                if artifact_chunk.chunk_id == MANIFEST_CHUNK {
+                if chunk_id == MANIFEST_CHUNK {
                     // Have already seen the manifest chunk
                     return Err(ChunksMoreNeeded);
                 }
@@ -1211,9 +1394,9 @@ impl Chunkable for IncompleteState {
 
                 let chunk_table_index = ix - 1;
 
-                let log = &self.log;
-                let metrics = &self.metrics;
-                crate::manifest::validate_chunk(chunk_table_index, payload, manifest).map_err(
+                let payload =
+                    decode_payload(manifest.chunk_table[chunk_table_index].size_bytes as u64)?;
+                crate::manifest::validate_chunk(chunk_table_index, &payload, manifest).map_err(
                     |err| {
                         warn!(log, "Received invalid chunk: {}", err);
                         metrics
@@ -1230,9 +1413,19 @@ impl Chunkable for IncompleteState {
                     &self.metrics.state_sync_metrics,
                     &self.root,
                     chunk_table_index,
-                    payload,
+                    &payload,
                     manifest,
                 );
+                for duplicate in duplicate_chunks.remove(&ix).unwrap_or_default() {
+                    Self::apply_chunk(
+                        &self.log,
+                        &self.metrics.state_sync_metrics,
+                        &self.root,
+                        duplicate - 1,
+                        &payload,
+                        manifest,
+                    );
+                }
 
                 fetch_chunks.remove(&ix);
 
@@ -1279,7 +1472,7 @@ impl Chunkable for IncompleteState {
     }
 
     fn get_chunk_size(&self, chunk_id: ChunkId) -> usize {
-        let ix = chunk_id.get() as usize;
+        let ix = decode_chunk_id(chunk_id).0.get() as usize;
 
         if ix == 0 {
             // Guestimate of manifest size
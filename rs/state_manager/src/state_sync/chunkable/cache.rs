@@ -164,8 +164,11 @@ impl StateSyncCache {
         match std::mem::replace(&mut sync.state, DownloadState::Blank) {
             DownloadState::Loading {
                 manifest,
-                fetch_chunks,
+                mut fetch_chunks,
+                duplicate_chunks,
             } => {
+                // Duplicates of chunks still to be fetched are missing as well.
+                fetch_chunks.extend(duplicate_chunks.into_values().flatten());
                 if self.entry.is_some() {
                     // The current cache is newer
                     delete_folder(&self.log, &sync.root);
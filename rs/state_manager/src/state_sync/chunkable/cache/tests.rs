@@ -45,6 +45,7 @@ fn fake_loading(seed: u32) -> (DownloadState, Manifest, HashSet<usize>) {
     let state = DownloadState::Loading {
         manifest: manifest.clone(),
         fetch_chunks: fetch_chunks.clone(),
+        duplicate_chunks: Default::default(),
     };
     (state, manifest, fetch_chunks)
 }
@@ -62,6 +63,7 @@ fn fake_complete() -> DownloadState {
         checkpoint_root: PathBuf::new(),
         manifest,
         get_state_sync_chunk: None,
+        compress_state_sync_chunk: None,
     });
     DownloadState::Complete(Box::new(artifact))
 }
@@ -96,11 +98,7 @@ fn incomplete_state_for_tests(
     result.state = state;
     // if Loading, populate the scratchpad with a file named after the seed
     // contained in manifest
-    if let DownloadState::Loading {
-        ref manifest,
-        fetch_chunks: _,
-    } = &result.state
-    {
+    if let DownloadState::Loading { ref manifest, .. } = &result.state {
         std::fs::create_dir(&result.root).unwrap();
         let mut _file = std::fs::File::create(result.root.join(manifest.version.to_string()));
     }
@@ -27,11 +27,12 @@ use ic_test_utilities::{
 };
 use ic_test_utilities_metrics::{fetch_int_counter_vec, fetch_int_gauge, Labels};
 use ic_types::{
-    artifact::{Priority, StateSyncArtifactId, StateSyncAttribute},
-    chunkable::ChunkId,
+    artifact::{Artifact, Priority, StateSyncArtifactId, StateSyncAttribute},
+    chunkable::{ArtifactErrorCode, ChunkId, ChunkableArtifact},
     crypto::CryptoHash,
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::CallbackId,
+    state_sync::compressed_chunk_id,
     xnet::{StreamIndex, StreamIndexedQueue},
     CanisterId, CryptoHashOfPartialState, CryptoHashOfState, Height, PrincipalId,
 };
@@ -1565,6 +1566,7 @@ fn state_sync_priority_fn_respects_states_to_fetch() {
                     &StateSyncAttribute {
                         height: height(*h),
                         root_hash: hash(*h as u8),
+                        supports_compressed_chunks: true,
                     }
                 )
             );
@@ -1586,6 +1588,7 @@ fn state_sync_priority_fn_respects_states_to_fetch() {
                 &StateSyncAttribute {
                     height: height(3),
                     root_hash: hash(3),
+                    supports_compressed_chunks: true,
                 }
             )
         );
@@ -1600,6 +1603,7 @@ fn state_sync_priority_fn_respects_states_to_fetch() {
                 &StateSyncAttribute {
                     height: height(3),
                     root_hash: hash(4),
+                    supports_compressed_chunks: true,
                 }
             )
         );
@@ -1619,6 +1623,7 @@ fn state_sync_priority_fn_respects_states_to_fetch() {
                 &StateSyncAttribute {
                     height: height(3),
                     root_hash: hash(3),
+                    supports_compressed_chunks: true,
                 }
             )
         );
@@ -1632,6 +1637,7 @@ fn state_sync_priority_fn_respects_states_to_fetch() {
                 &StateSyncAttribute {
                     height: height(4),
                     root_hash: hash(4),
+                    supports_compressed_chunks: true,
                 }
             )
         );
@@ -1696,6 +1702,140 @@ fn can_do_simple_state_sync_transfer() {
     })
 }
 
+#[test]
+fn can_state_sync_duplicate_chunks() {
+    state_manager_test(|src_metrics, src_state_manager| {
+        let (_height, mut state) = src_state_manager.take_tip();
+        // Both canisters have the same wasm module.
+        insert_dummy_canister(&mut state, canister_test_id(100));
+        insert_dummy_canister(&mut state, canister_test_id(200));
+
+        src_state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+        let hash = wait_for_checkpoint(&src_state_manager, height(1));
+        let id = StateSyncArtifactId {
+            height: height(1),
+            hash,
+        };
+
+        let state = src_state_manager.get_latest_state().take();
+
+        let msg = src_state_manager
+            .get_validated_by_identifier(&id)
+            .expect("failed to get state sync messages");
+
+        assert_error_counters(src_metrics);
+
+        state_manager_test(|dst_metrics, dst_state_manager| {
+            let chunkable = dst_state_manager.create_chunkable_state(&id);
+
+            let dst_msg = pipe_state_sync(msg, chunkable);
+            dst_state_manager
+                .check_artifact_acceptance(dst_msg, &node_test_id(0))
+                .expect("Failed to process state sync artifact");
+
+            let recovered_state = dst_state_manager
+                .get_state_at(height(1))
+                .expect("Destination state manager didn't receive the state")
+                .take();
+            assert_eq!(state, recovered_state);
+
+            let op = |op: &str| maplit::btreemap! {"op".to_string() => op.to_string()};
+            let size = fetch_int_counter_vec(dst_metrics, "state_sync_size_bytes_total");
+            assert!(size[&op("deduplicate")] > 0);
+
+            let encoding =
+                |encoding: &str| maplit::btreemap! {"encoding".to_string() => encoding.to_string()};
+            let received = fetch_int_counter_vec(dst_metrics, "state_sync_received_bytes_total");
+            assert!(received[&encoding("raw")] > 0);
+            assert_eq!(0, received[&encoding("deflate")]);
+
+            assert_eq!(
+                0,
+                fetch_int_gauge(dst_metrics, "state_sync_remaining_chunks").unwrap()
+            );
+            assert_error_counters(dst_metrics);
+        })
+    })
+}
+
+#[test]
+fn can_state_sync_from_compressed_and_uncompressed_peers() {
+    state_manager_test(|src_metrics, src_state_manager| {
+        let (_height, mut state) = src_state_manager.take_tip();
+        insert_dummy_canister(&mut state, canister_test_id(100));
+
+        src_state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+        let hash = wait_for_checkpoint(&src_state_manager, height(1));
+        let id = StateSyncArtifactId {
+            height: height(1),
+            hash,
+        };
+
+        let state = src_state_manager.get_latest_state().take();
+
+        let msg = src_state_manager
+            .get_validated_by_identifier(&id)
+            .expect("failed to get state sync messages");
+
+        assert_error_counters(src_metrics);
+
+        state_manager_test(|dst_metrics, dst_state_manager| {
+            let mut chunkable = dst_state_manager.create_chunkable_state(&id);
+
+            // Every other chunk is requested from a peer that serves compressed
+            // chunks, the others from a peer that doesn't.
+            let mut dst_msg = None;
+            while dst_msg.is_none() {
+                let ids: Vec<_> = chunkable.chunks_to_download().collect();
+                assert!(!ids.is_empty());
+                for (i, id) in ids.into_iter().enumerate() {
+                    let requested_id = if i % 2 == 0 {
+                        compressed_chunk_id(id)
+                    } else {
+                        id
+                    };
+                    let chunk = Box::new(msg.clone())
+                        .get_chunk(requested_id)
+                        .unwrap_or_else(|| panic!("Requested unknown chunk {}", id));
+                    match chunkable.add_chunk(chunk) {
+                        Ok(Artifact::StateSync(msg)) => {
+                            dst_msg = Some(msg);
+                            break;
+                        }
+                        Ok(artifact) => panic!("Unexpected artifact type: {:?}", artifact),
+                        Err(ArtifactErrorCode::ChunksMoreNeeded) => (),
+                        Err(ArtifactErrorCode::ChunkVerificationFailed) => {
+                            panic!("Encountered invalid chunk {}", id)
+                        }
+                    }
+                }
+            }
+
+            dst_state_manager
+                .check_artifact_acceptance(dst_msg.unwrap(), &node_test_id(0))
+                .expect("Failed to process state sync artifact");
+
+            let recovered_state = dst_state_manager
+                .get_state_at(height(1))
+                .expect("Destination state manager didn't receive the state")
+                .take();
+            assert_eq!(state, recovered_state);
+
+            let encoding =
+                |encoding: &str| maplit::btreemap! {"encoding".to_string() => encoding.to_string()};
+            let received = fetch_int_counter_vec(dst_metrics, "state_sync_received_bytes_total");
+            assert!(received[&encoding("raw")] > 0);
+            assert!(received[&encoding("deflate")] > 0);
+
+            assert_eq!(
+                0,
+                fetch_int_gauge(dst_metrics, "state_sync_remaining_chunks").unwrap()
+            );
+            assert_error_counters(dst_metrics);
+        })
+    })
+}
+
 #[test]
 fn can_state_sync_from_cache() {
     state_manager_test(|src_metrics, src_state_manager| {
@@ -2543,6 +2683,92 @@ fn certified_read_can_certify_time() {
     })
 }
 
+#[test]
+fn certified_read_at_height_reads_older_states() {
+    use std::time::Duration;
+    use LabeledTree::*;
+
+    state_manager_test(|_metrics, state_manager| {
+        let path: LabeledTree<()> = LabeledTree::SubTree(flatmap! {
+            label("time") => Leaf(())
+        });
+
+        let mut certifications = vec![];
+        for h in 1..=2 {
+            let (_, mut state) = state_manager.take_tip();
+            state.metadata.batch_time += Duration::new(0, 10);
+            state_manager.commit_and_certify(state, height(h), CertificationScope::Metadata);
+            assert_eq!(
+                None,
+                state_manager
+                    .read_certified_state_at(height(h), &path)
+                    .expect("failed to read state")
+            );
+            certifications.push(certify_height(&state_manager, height(h)));
+        }
+
+        for (h, delivered_certification) in (1..=2).zip(certifications) {
+            let (_state, mixed_tree, cert) = state_manager
+                .read_certified_state_at(height(h), &path)
+                .expect("failed to read state")
+                .expect("failed to read certified state");
+
+            assert_eq!(cert, delivered_certification);
+            assert_eq!(
+                tree_payload(mixed_tree),
+                SubTree(flatmap!(label("time") => Leaf(vec![10 * h as u8])))
+            );
+        }
+
+        assert!(matches!(
+            state_manager.read_certified_state_at(height(3), &path),
+            Err(StateManagerError::StateNotCommittedYet(_))
+        ));
+
+        state_manager.remove_inmemory_states_below(height(2));
+        assert!(matches!(
+            state_manager.read_certified_state_at(height(1), &path),
+            Err(StateManagerError::StateRemoved(_))
+        ));
+        assert!(matches!(
+            state_manager.read_certified_state_at(height(2), &path),
+            Ok(Some(_))
+        ));
+    })
+}
+
+#[test]
+fn certified_read_at_height_requires_a_retained_hash_tree() {
+    use LabeledTree::*;
+
+    state_manager_test(|_metrics, state_manager| {
+        let path: LabeledTree<()> = LabeledTree::SubTree(flatmap! {
+            label("time") => Leaf(())
+        });
+
+        // Only the hash trees of the 10 most recent heights are kept.
+        for h in 1..=11 {
+            let (_, state) = state_manager.take_tip();
+            state_manager.commit_and_certify(state, height(h), CertificationScope::Metadata);
+            certify_height(&state_manager, height(h));
+        }
+
+        // The state at height 1 is still in memory, but reading it would
+        // require rehashing it.
+        assert!(state_manager.get_state_at(height(1)).is_ok());
+        assert!(matches!(
+            state_manager.read_certified_state_at(height(1), &path),
+            Err(StateManagerError::StateRemoved(_))
+        ));
+        for h in 2..=11 {
+            assert!(matches!(
+                state_manager.read_certified_state_at(height(h), &path),
+                Ok(Some(_))
+            ));
+        }
+    })
+}
+
 #[test]
 fn certified_read_can_certify_canister_data() {
     use LabeledTree::*;
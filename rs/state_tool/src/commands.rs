@@ -1,4 +1,5 @@
 //! Command implementations.
+pub mod canister;
 pub mod cdiff;
 pub mod chash;
 pub mod decode;
@@ -6,3 +7,4 @@ pub mod import_state;
 pub mod list;
 pub mod manifest;
 mod utils;
+pub mod verify_manifest;
@@ -0,0 +1,218 @@
+//! Lists, displays, exports and archives the canisters of a checkpoint.
+
+use ic_protobuf::state::{canister_state_bits::v1 as pb_canister, queues::v1 as pb_queues};
+use ic_replicated_state::canister_state::{
+    system_state::CanisterStatus, CanisterQueues, WASM_PAGE_SIZE_IN_BYTES,
+};
+use ic_state_layout::{CanisterLayout, CanisterStateBits, CheckpointLayout, ReadOnly};
+use ic_state_manager::{canister_archive::write_canister_archive, manifest::FileContents};
+use ic_types::{CanisterId, Height};
+use std::convert::TryFrom;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Lists the canisters of the checkpoint rooted at `path` along with their
+/// status and cycles balance.
+pub fn do_list(path: PathBuf) -> Result<(), String> {
+    let cp_layout = checkpoint_layout(path)?;
+    let canister_ids = cp_layout
+        .canister_ids()
+        .map_err(|e| format!("failed to enumerate canisters: {}", e))?;
+
+    if canister_ids.is_empty() {
+        println!("No canisters to display");
+        return Ok(());
+    }
+
+    println!("{:<30}    {:<10}    {:>30}", "CANISTER", "STATUS", "CYCLES");
+    for canister_id in canister_ids {
+        let bits = load_canister_state_bits(&canister_layout(&cp_layout, &canister_id)?)?;
+        println!(
+            "{:<30}    {:<10}    {:>30}",
+            canister_id.to_string(),
+            status_name(&bits.status),
+            bits.cycles_balance.to_string()
+        );
+    }
+    Ok(())
+}
+
+/// Prints the controllers, cycles, memory usage and queue sizes of canister
+/// `canister_id` in the checkpoint rooted at `path`.
+pub fn do_show(path: PathBuf, canister_id: String) -> Result<(), String> {
+    let cp_layout = checkpoint_layout(path)?;
+    let canister_id = parse_canister_id(&canister_id)?;
+    let layout = canister_layout(&cp_layout, &canister_id)?;
+    let bits = load_canister_state_bits(&layout)?;
+
+    let heap_bytes = bits
+        .execution_state_bits
+        .as_ref()
+        .map(|bits| bits.heap_size.get() * WASM_PAGE_SIZE_IN_BYTES)
+        .unwrap_or(0);
+    let stable_memory_bytes = bits.stable_memory_size.get() * WASM_PAGE_SIZE_IN_BYTES;
+    let wasm_bytes = file_size(layout.wasm().raw_path())?;
+
+    println!("CANISTER:            {}", canister_id);
+    println!("STATUS:              {}", status_name(&bits.status));
+    println!(
+        "CONTROLLERS:         {}",
+        bits.controllers
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!("CYCLES:              {}", bits.cycles_balance);
+    println!("FREEZE THRESHOLD:    {}s", bits.freeze_threshold);
+    println!("COMPUTE ALLOCATION:  {:?}", bits.compute_allocation);
+    println!("MEMORY ALLOCATION:   {:?}", bits.memory_allocation);
+    println!(
+        "MEMORY USAGE:        {} bytes (heap {}, stable memory {}, wasm {})",
+        heap_bytes + stable_memory_bytes + wasm_bytes as usize,
+        heap_bytes,
+        stable_memory_bytes,
+        wasm_bytes
+    );
+    if let Some(execution_state_bits) = bits.execution_state_bits.as_ref() {
+        println!(
+            "MODULE HASH:         {}",
+            execution_state_bits
+                .binary_hash
+                .as_ref()
+                .map(|hash| hex::encode(hash.to_vec()))
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+
+    let queues = layout
+        .queues()
+        .deserialize_opt()
+        .map_err(|e| format!("failed to read queues of canister {}: {}", canister_id, e))?
+        .map(|pb: pb_queues::CanisterQueues| {
+            CanisterQueues::try_from(pb)
+                .map_err(|e| format!("failed to decode queues of canister {}: {}", canister_id, e))
+        })
+        .transpose()?
+        .unwrap_or_default();
+    println!(
+        "INGRESS QUEUE:       {} messages, {} bytes",
+        queues.ingress_queue_message_count(),
+        queues.ingress_queue_size_bytes()
+    );
+    println!(
+        "INPUT QUEUES:        {} messages, {} bytes",
+        queues.input_queues_message_count(),
+        queues.input_queues_size_bytes()
+    );
+    println!(
+        "OUTPUT QUEUES:       {} messages",
+        queues.output_queues_message_count()
+    );
+    Ok(())
+}
+
+/// Writes the wasm module, the heap and the stable memory of canister
+/// `canister_id` in the checkpoint rooted at `path` to files in the `output`
+/// directory.
+pub fn do_export(path: PathBuf, canister_id: String, output: PathBuf) -> Result<(), String> {
+    let cp_layout = checkpoint_layout(path)?;
+    let canister_id = parse_canister_id(&canister_id)?;
+    let layout = canister_layout(&cp_layout, &canister_id)?;
+
+    std::fs::create_dir_all(&output)
+        .map_err(|e| format!("failed to create directory {}: {}", output.display(), e))?;
+
+    for (src, name) in [
+        (layout.wasm().raw_path().to_path_buf(), "software.wasm"),
+        (layout.vmemory_0(), "vmemory_0.bin"),
+        (layout.stable_memory_blob(), "stable_memory.bin"),
+    ] {
+        if !src.exists() {
+            println!("Skipping {}: canister {} has none", name, canister_id);
+            continue;
+        }
+        // Page map files are read through their overlays, if any.
+        let contents = FileContents::open(&src)
+            .map_err(|e| format!("failed to read {}: {}", src.display(), e))?;
+        let dst = output.join(name);
+        std::fs::File::create(&dst)
+            .and_then(|file| {
+                let mut writer = std::io::BufWriter::new(file);
+                std::io::copy(&mut contents.reader(), &mut writer)?;
+                writer.flush()
+            })
+            .map_err(|e| format!("failed to write {}: {}", dst.display(), e))?;
+        println!("Exported {} bytes to {}", contents.len(), dst.display());
+    }
+    Ok(())
+}
+
+/// Writes canister `canister_id` in the checkpoint rooted at `path` as a
+/// canister archive to the `output` file.
+pub fn do_archive(path: PathBuf, canister_id: String, output: PathBuf) -> Result<(), String> {
+    let cp_layout = checkpoint_layout(path)?;
+    let canister_id = parse_canister_id(&canister_id)?;
+    let layout = canister_layout(&cp_layout, &canister_id)?;
+
+    let file = std::fs::File::create(&output)
+        .map_err(|e| format!("failed to create {}: {}", output.display(), e))?;
+    write_canister_archive(&layout, &canister_id, std::io::BufWriter::new(file))?;
+    println!("Archived canister {} to {}", canister_id, output.display());
+    Ok(())
+}
+
+fn checkpoint_layout(path: PathBuf) -> Result<CheckpointLayout<ReadOnly>, String> {
+    CheckpointLayout::<ReadOnly>::new(path, Height::new(0))
+        .map_err(|e| format!("Failed to create checkpoint layout: {}", e))
+}
+
+fn canister_layout(
+    cp_layout: &CheckpointLayout<ReadOnly>,
+    canister_id: &CanisterId,
+) -> Result<CanisterLayout<ReadOnly>, String> {
+    cp_layout
+        .canister(canister_id)
+        .map_err(|e| format!("failed to access canister {}: {}", canister_id, e))
+}
+
+fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
+    CanisterId::from_str(canister_id)
+        .map_err(|e| format!("failed to parse canister id {}: {}", canister_id, e))
+}
+
+fn load_canister_state_bits(
+    layout: &CanisterLayout<ReadOnly>,
+) -> Result<CanisterStateBits, String> {
+    let pb: pb_canister::CanisterStateBits = layout.canister().deserialize().map_err(|e| {
+        format!(
+            "failed to read canister state at {}: {}",
+            layout.raw_path().display(),
+            e
+        )
+    })?;
+    CanisterStateBits::try_from(pb).map_err(|e| {
+        format!(
+            "failed to decode canister state at {}: {}",
+            layout.raw_path().display(),
+            e
+        )
+    })
+}
+
+fn status_name(status: &CanisterStatus) -> &'static str {
+    match status {
+        CanisterStatus::Running { .. } => "running",
+        CanisterStatus::Stopping { .. } => "stopping",
+        CanisterStatus::Stopped => "stopped",
+    }
+}
+
+fn file_size(path: &Path) -> Result<u64, String> {
+    match std::fs::metadata(path) {
+        Ok(metadata) => Ok(metadata.len()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(format!("failed to stat {}: {}", path.display(), e)),
+    }
+}
@@ -2,10 +2,12 @@
 
 use crate::commands::utils;
 use ic_state_layout::{CheckpointLayout, RwPolicy};
+use ic_state_manager::canister_archive::unpack_canister_archive;
 use ic_sys::fs::clone_file;
-use ic_types::Height;
+use ic_types::{CanisterId, Height};
 use ic_utils::fs::copy_file_sparse;
 use std::fs;
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::string::ToString;
 
@@ -68,11 +70,91 @@ fn copy_recursively(src: &Path, dst: &Path) -> Result<(), String> {
     go(src, dst, &mut can_clone)
 }
 
-/// Imports a checkpoint of replicated state into the replica state directory.
+/// Unpacks the canister archive at `archive_path` into the checkpoint
+/// `cp_layout`, replacing the canister with the same id, if any.
+///
+/// The archive is unpacked into a staging directory inside the checkpoint,
+/// which is removed if the import fails so that it doesn't end up in the
+/// checkpoint.
+fn import_canister_archive(
+    cp_layout: &CheckpointLayout<RwPolicy>,
+    archive_path: &Path,
+) -> Result<(), String> {
+    let staging_dir = cp_layout.raw_path().join("canister_archive.tmp");
+    fs::create_dir_all(&staging_dir).map_err(|e| {
+        format!(
+            "failed to create directory {}: {}",
+            staging_dir.display(),
+            e
+        )
+    })?;
+
+    let result = unpack_into_checkpoint(cp_layout, archive_path, &staging_dir);
+    if result.is_err() && staging_dir.exists() {
+        if let Err(e) = fs::remove_dir_all(&staging_dir) {
+            eprintln!(
+                "failed to remove directory {}: {}",
+                staging_dir.display(),
+                e
+            );
+        }
+    }
+    let canister_id = result?;
+
+    println!(
+        "Imported canister {} from {}",
+        canister_id,
+        archive_path.display()
+    );
+    Ok(())
+}
+
+/// Unpacks the canister archive at `archive_path` into `staging_dir` and
+/// moves it to the directory of the archived canister in `cp_layout`.
+fn unpack_into_checkpoint(
+    cp_layout: &CheckpointLayout<RwPolicy>,
+    archive_path: &Path,
+    staging_dir: &Path,
+) -> Result<CanisterId, String> {
+    let archive = fs::File::open(archive_path)
+        .map_err(|e| format!("failed to open {}: {}", archive_path.display(), e))?;
+    let canister_id = unpack_canister_archive(BufReader::new(archive), staging_dir)?;
+
+    let canister_dir = cp_layout
+        .canister(&canister_id)
+        .map_err(|e| format!("failed to access canister {}: {}", canister_id, e))?
+        .raw_path();
+    if canister_dir.exists() {
+        fs::remove_dir_all(&canister_dir).map_err(|e| {
+            format!(
+                "failed to remove directory {}: {}",
+                canister_dir.display(),
+                e
+            )
+        })?;
+    }
+    fs::rename(staging_dir, &canister_dir).map_err(|e| {
+        format!(
+            "failed to rename {} -> {}: {}",
+            staging_dir.display(),
+            canister_dir.display(),
+            e
+        )
+    })?;
+    Ok(canister_id)
+}
+
+/// Imports a checkpoint of replicated state into the replica state directory,
+/// replacing its canisters with the ones from `canister_archives`.
 ///
 /// Function is not crash-safe. Caller is responsible to follow guidelines
 /// regarding crash-safe I/O.
-pub fn do_import(state_path: PathBuf, config_path: PathBuf, height: u64) -> Result<(), String> {
+pub fn do_import(
+    state_path: PathBuf,
+    config_path: PathBuf,
+    height: u64,
+    canister_archives: Vec<PathBuf>,
+) -> Result<(), String> {
     let state_layout = utils::locate_state_root(config_path)?;
     let height = Height::new(height);
 
@@ -93,6 +175,10 @@ pub fn do_import(state_path: PathBuf, config_path: PathBuf, height: u64) -> Resu
     let cp_layout = CheckpointLayout::<RwPolicy>::new(scratchpad_dir, height)
         .map_err(|e| format!("Failed to create scratchpad checkpoint layout: {}", e))?;
 
+    for archive_path in canister_archives.iter() {
+        import_canister_archive(&cp_layout, archive_path)?;
+    }
+
     state_layout
         .scratchpad_to_checkpoint(cp_layout, height)
         .map_err(|e| e.to_string())?;
@@ -7,15 +7,14 @@ use ic_state_manager::{
     manifest::{compute_manifest, manifest_hash, DEFAULT_CHUNK_SIZE},
     ManifestMetrics,
 };
-use ic_types::Height;
+use ic_types::{state_sync::Manifest, Height};
 use std::path::PathBuf;
 
-/// Computes the manifest (chunk hashes, file hashes and root hash) of the
-/// checkpoint rooted at `path`.
-pub fn do_compute_manifest(path: PathBuf) -> Result<(), String> {
-    let cp_layout = CheckpointLayout::<ReadOnly>::new(path, Height::new(0))
-        .map_err(|e| format!("Failed to create checkpoint layout: {}", e))?;
-
+/// Computes the manifest of the checkpoint with the given layout from the
+/// files on disk.
+pub(crate) fn compute_checkpoint_manifest(
+    cp_layout: &CheckpointLayout<ReadOnly>,
+) -> Result<Manifest, String> {
     let metadata = cp_layout.system_metadata().deserialize().map_err(|e| {
         format!(
             "Failed to deserialize system metadata to determine the manifest version: {}",
@@ -27,7 +26,7 @@ pub fn do_compute_manifest(path: PathBuf) -> Result<(), String> {
         scoped_threadpool::Pool::new(ic_state_manager::NUMBER_OF_CHECKPOINT_THREADS);
     let metrics_registry = MetricsRegistry::new();
     let manifest_metrics = ManifestMetrics::new(&metrics_registry);
-    let manifest = compute_manifest(
+    compute_manifest(
         &mut thread_pool,
         &manifest_metrics,
         &no_op_logger(),
@@ -42,7 +41,16 @@ pub fn do_compute_manifest(path: PathBuf) -> Result<(), String> {
             cp_layout.raw_path().display(),
             e
         )
-    })?;
+    })
+}
+
+/// Computes the manifest (chunk hashes, file hashes and root hash) of the
+/// checkpoint rooted at `path`.
+pub fn do_compute_manifest(path: PathBuf) -> Result<(), String> {
+    let cp_layout = CheckpointLayout::<ReadOnly>::new(path, Height::new(0))
+        .map_err(|e| format!("Failed to create checkpoint layout: {}", e))?;
+
+    let manifest = compute_checkpoint_manifest(&cp_layout)?;
 
     println!("{}", manifest);
     println!();
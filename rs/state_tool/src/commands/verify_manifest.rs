@@ -0,0 +1,118 @@
+//! Verifies the manifests stored in the states metadata against checkpoints.
+
+use crate::commands::{manifest::compute_checkpoint_manifest, utils};
+use ic_protobuf::state::v1 as pb;
+use ic_state_layout::{ProtoFileWith, ReadOnly};
+use ic_state_manager::manifest::manifest_hash;
+use ic_types::{state_sync::Manifest, Height};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+/// Recomputes the manifest of every checkpoint (or only the one at `height`)
+/// under the state root location indicated in the given configuration file and
+/// compares it and its root hash against the manifest recorded in the states
+/// metadata.  Fails if any of them doesn't match.
+pub fn do_verify_manifest(config: PathBuf, height: Option<u64>) -> Result<(), String> {
+    let state_layout = utils::locate_state_root(config)?;
+
+    let metadata_file: ProtoFileWith<pb::StatesMetadata, ReadOnly> =
+        state_layout.states_metadata().into();
+    let recorded_manifests = metadata_file
+        .deserialize_opt()
+        .map_err(|e| format!("failed to read states metadata: {}", e))?
+        .unwrap_or_default()
+        .by_height
+        .into_iter()
+        .filter_map(|(h, state_metadata)| Some((Height::new(h), state_metadata.manifest?)))
+        .map(|(h, pb_manifest)| {
+            Manifest::try_from(pb_manifest)
+                .map(|manifest| (h, manifest))
+                .map_err(|e| format!("failed to decode manifest of state @{}: {}", h, e))
+        })
+        .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+    let heights: Vec<Height> = match height {
+        Some(h) => vec![Height::new(h)],
+        None => state_layout
+            .checkpoint_heights()
+            .map_err(|e| format!("failed to enumerate checkpoints: {}", e))?,
+    };
+
+    if heights.is_empty() {
+        println!("No checkpoints to verify");
+        return Ok(());
+    }
+
+    let mut num_mismatches = 0;
+    for h in heights {
+        let cp_layout = state_layout
+            .checkpoint(h)
+            .map_err(|e| format!("failed to access checkpoint @{}: {}", h, e))?;
+        let computed = compute_checkpoint_manifest(&cp_layout)?;
+        let computed_hash = hex::encode(manifest_hash(&computed));
+
+        match recorded_manifests.get(&h) {
+            None => println!(
+                "{:>15}    {:<12}    {}",
+                h.get(),
+                "no metadata",
+                computed_hash
+            ),
+            Some(recorded) if *recorded == computed => {
+                println!("{:>15}    {:<12}    {}", h.get(), "ok", computed_hash)
+            }
+            Some(recorded) => {
+                num_mismatches += 1;
+                println!(
+                    "{:>15}    {:<12}    {} (recorded {})",
+                    h.get(),
+                    "mismatch",
+                    computed_hash,
+                    hex::encode(manifest_hash(recorded))
+                );
+                print_file_differences(recorded, &computed);
+            }
+        }
+    }
+
+    if num_mismatches > 0 {
+        return Err(format!(
+            "{} checkpoint(s) don't match the recorded manifest",
+            num_mismatches
+        ));
+    }
+    Ok(())
+}
+
+/// Prints the files whose size or hash differ between the `recorded` and the
+/// `computed` manifest.
+fn print_file_differences(recorded: &Manifest, computed: &Manifest) {
+    let files = |manifest: &Manifest| -> BTreeMap<PathBuf, (u64, [u8; 32])> {
+        manifest
+            .file_table
+            .iter()
+            .map(|f| (f.relative_path.clone(), (f.size_bytes, f.hash)))
+            .collect()
+    };
+    let recorded = files(recorded);
+    let computed = files(computed);
+
+    for (path, recorded_file) in recorded.iter() {
+        match computed.get(path) {
+            None => println!("    missing on disk: {}", path.display()),
+            Some(computed_file) if computed_file != recorded_file => println!(
+                "    differs: {} (recorded {} bytes {}, on disk {} bytes {})",
+                path.display(),
+                recorded_file.0,
+                hex::encode(recorded_file.1),
+                computed_file.0,
+                hex::encode(computed_file.1)
+            ),
+            Some(_) => {}
+        }
+    }
+    for path in computed.keys().filter(|path| !recorded.contains_key(*path)) {
+        println!("    not in metadata: {}", path.display());
+    }
+}
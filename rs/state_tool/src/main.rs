@@ -2,9 +2,10 @@
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
 //! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! checkpoint manifests, verify recorded manifests, import state trees, inspect
+//! and export individual canisters).
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
 mod commands;
@@ -39,6 +40,11 @@ enum Opt {
         /// The height to label the state with.
         #[clap(long = "height", short = 'h')]
         height: u64,
+
+        /// Canister archives to import into the state, replacing canisters
+        /// with the same id.
+        #[clap(long = "canister-archive")]
+        canister_archives: Vec<PathBuf>,
     },
 
     /// Computes manifest of a checkpoint.
@@ -64,6 +70,69 @@ enum Opt {
         #[clap(long = "file")]
         file: PathBuf,
     },
+
+    /// Inspects the canisters of a checkpoint.
+    #[clap(name = "canister")]
+    Canister {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+
+        #[clap(subcommand)]
+        command: CanisterCommand,
+    },
+
+    /// Recomputes the manifests of checkpoints and compares them against the
+    /// ones recorded in the states metadata.
+    #[clap(name = "verify-manifest")]
+    VerifyManifest {
+        /// Path to the replica configuration (ic.json).
+        #[clap(long = "config")]
+        config: PathBuf,
+
+        /// Height of the checkpoint to verify, all checkpoints if omitted.
+        #[clap(long = "height", short = 'h')]
+        height: Option<u64>,
+    },
+}
+
+/// Supported `state_tool canister` commands and their arguments.
+#[derive(Subcommand, Debug)]
+enum CanisterCommand {
+    /// Enumerates the canisters in the checkpoint.
+    #[clap(name = "list")]
+    List,
+
+    /// Displays the controllers, cycles, memory usage and queues of a
+    /// canister.
+    #[clap(name = "show")]
+    Show {
+        /// Textual representation of the canister id.
+        canister_id: String,
+    },
+
+    /// Writes the wasm module, heap and stable memory of a canister to files.
+    #[clap(name = "export")]
+    Export {
+        /// Textual representation of the canister id.
+        canister_id: String,
+
+        /// Directory to write the files to.
+        #[clap(long = "output")]
+        output: PathBuf,
+    },
+
+    /// Writes a canister archive that can be imported into another state or
+    /// into a `StateMachine`.
+    #[clap(name = "archive")]
+    Archive {
+        /// Textual representation of the canister id.
+        canister_id: String,
+
+        /// Path of the archive to write.
+        #[clap(long = "output")]
+        output: PathBuf,
+    },
 }
 
 fn main() {
@@ -75,10 +144,26 @@ fn main() {
             state,
             config,
             height,
-        } => commands::import_state::do_import(state, config, height),
+            canister_archives,
+        } => commands::import_state::do_import(state, config, height, canister_archives),
         Opt::Manifest { path } => commands::manifest::do_compute_manifest(path),
         Opt::ListStates { config } => commands::list::do_list(config),
         Opt::Decode { file } => commands::decode::do_decode(file),
+        Opt::Canister { path, command } => match command {
+            CanisterCommand::List => commands::canister::do_list(path),
+            CanisterCommand::Show { canister_id } => commands::canister::do_show(path, canister_id),
+            CanisterCommand::Export {
+                canister_id,
+                output,
+            } => commands::canister::do_export(path, canister_id, output),
+            CanisterCommand::Archive {
+                canister_id,
+                output,
+            } => commands::canister::do_archive(path, canister_id, output),
+        },
+        Opt::VerifyManifest { config, height } => {
+            commands::verify_manifest::do_verify_manifest(config, height)
+        }
     };
 
     if let Err(e) = result {
@@ -11,7 +11,7 @@ use ic_interfaces::execution_environment::{
     AvailableMemory, ExecutionComplexity, ExecutionMode,
     HypervisorError::{self, *},
     HypervisorResult, OutOfInstructionsHandler, PerformanceCounterType, SubnetAvailableMemory,
-    SystemApi,
+    SystemApi, SystemApiCallCounters,
     TrapCode::CyclesAmountTooBigFor64Bit,
 };
 use ic_logger::{error, ReplicaLogger};
@@ -146,6 +146,8 @@ pub struct ExecutionParameters {
     pub compute_allocation: ComputeAllocation,
     pub subnet_type: SubnetType,
     pub execution_mode: ExecutionMode,
+    /// The maximum size of a 64-bit Wasm memory on this subnet.
+    pub wasm64_memory_limit: NumBytes,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -716,6 +718,10 @@ pub struct SystemApiImpl {
 
     /// Tracks the total execution complexity.
     total_execution_complexity: ExecutionComplexity,
+
+    /// Counts the calls to the system API functions tracked for the query
+    /// cache.
+    call_counters: SystemApiCallCounters,
 }
 
 impl SystemApiImpl {
@@ -751,9 +757,16 @@ impl SystemApiImpl {
             current_slice_instruction_limit: i64::try_from(slice_limit).unwrap_or(i64::MAX),
             instructions_executed_before_current_slice: 0,
             total_execution_complexity: ExecutionComplexity::new(),
+            call_counters: SystemApiCallCounters::default(),
         }
     }
 
+    /// Returns the number of calls to the system API functions tracked for
+    /// the query cache.
+    pub fn call_counters(&self) -> SystemApiCallCounters {
+        self.call_counters.clone()
+    }
+
     /// Gets the result of execution, assuming there is no error from
     /// running the canister. Returns any cycles used for an outgoing request
     /// that doesn't get sent and returns allocated memory to the subnet if the
@@ -2297,7 +2310,8 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_time(&self) -> HypervisorResult<Time> {
+    fn ic0_time(&mut self) -> HypervisorResult<Time> {
+        self.call_counters.time += 1;
         let result = match &self.api_type {
             ApiType::Start { .. } => Err(self.error_for("ic0_time")),
             ApiType::Init { time, .. }
@@ -2541,12 +2555,13 @@ impl SystemApi for SystemApiImpl {
     }
 
     fn ic0_data_certificate_copy(
-        &self,
+        &mut self,
         dst: u32,
         offset: u32,
         size: u32,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
+        self.call_counters.data_certificate_copy += 1;
         let result = match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
@@ -202,7 +202,7 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_stable64_write(&mut self, _: u64, _: u64, _: u64, _: &[u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
-    fn ic0_time(&self) -> HypervisorResult<Time> {
+    fn ic0_time(&mut self) -> HypervisorResult<Time> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_performance_counter(
@@ -256,7 +256,7 @@ impl SystemApi for SystemApiEmpty {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
     fn ic0_data_certificate_copy(
-        &self,
+        &mut self,
         _: u32,
         _: u32,
         _: u32,
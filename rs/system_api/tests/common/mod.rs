@@ -20,7 +20,7 @@ use ic_test_utilities::{
 use ic_test_utilities::{state::SystemStateBuilder, types::ids::canister_test_id};
 use ic_types::{
     messages::{CallContextId, CallbackId, RejectContext},
-    ComputeAllocation, Cycles, NumInstructions, Time,
+    ComputeAllocation, Cycles, NumInstructions, Time, MAX_WASM64_MEMORY_IN_BYTES,
 };
 use maplit::btreemap;
 
@@ -37,6 +37,7 @@ pub fn execution_parameters() -> ExecutionParameters {
         compute_allocation: ComputeAllocation::default(),
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
+        wasm64_memory_limit: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
     }
 }
 
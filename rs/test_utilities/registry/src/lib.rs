@@ -198,6 +198,7 @@ pub fn test_subnet_record() -> SubnetRecord {
         ssh_readonly_access: vec![],
         ssh_backup_access: vec![],
         ecdsa_config: None,
+        max_wasm64_memory_size: 0,
     }
 }
 
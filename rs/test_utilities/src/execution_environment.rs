@@ -16,7 +16,7 @@ use ic_execution_environment::{
     as_num_instructions, execute_canister, util::process_stopping_canisters,
     CanisterHeartbeatError, CompilationCostHandling, ExecuteMessageResult, ExecutionEnvironment,
     ExecutionResponse, Hypervisor, IngressHistoryWriterImpl, InternalHttpQueryHandler,
-    RoundInstructions, RoundLimits,
+    QueryStatsCollector, RoundInstructions, RoundLimits,
 };
 use ic_ic00_types::{
     CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterStatusType, EcdsaKeyId,
@@ -50,13 +50,14 @@ use ic_types::Time;
 use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{AnonymousQuery, CallbackId, MessageId, RequestOrResponse, Response, UserQuery},
-    CanisterId, Cycles, NumInstructions, UserId,
+    CanisterId, Cycles, NumInstructions, ReplicaVersion, UserId, MAX_WASM64_MEMORY_IN_BYTES,
 };
 use ic_types_test_utils::ids::{node_test_id, subnet_test_id, user_test_id};
 use ic_universal_canister::UNIVERSAL_CANISTER_WASM;
 use ic_wasm_types::BinaryEncodedWasm;
 use maplit::btreemap;
 
+use crate::state_manager::FakeStateManager;
 use crate::types::messages::{RequestBuilder, SignedIngressBuilder};
 use crate::{crypto::mock_random_number_generator, mock_time, types::messages::IngressBuilder};
 
@@ -120,6 +121,7 @@ pub struct ExecutionTest {
     // The actual implementation.
     exec_env: ExecutionEnvironment,
     query_handler: InternalHttpQueryHandler,
+    query_stats_collector: Arc<QueryStatsCollector>,
     cycles_account_manager: Arc<CyclesAccountManager>,
     metrics_registry: MetricsRegistry,
     ingress_history_writer: Arc<dyn IngressHistoryWriter<State = ReplicatedState>>,
@@ -284,6 +286,10 @@ impl ExecutionTest {
         &self.cycles_account_manager
     }
 
+    pub fn query_stats_collector(&self) -> &QueryStatsCollector {
+        &self.query_stats_collector
+    }
+
     pub fn time(&self) -> Time {
         self.time
     }
@@ -1163,6 +1169,7 @@ pub struct ExecutionTestBuilder {
     deterministic_time_slicing: bool,
     allocatable_compute_capacity_in_percent: usize,
     subnet_features: String,
+    query_caching: bool,
 }
 
 impl Default for ExecutionTestBuilder {
@@ -1198,6 +1205,7 @@ impl Default for ExecutionTestBuilder {
             deterministic_time_slicing: false,
             allocatable_compute_capacity_in_percent: 100,
             subnet_features: String::default(),
+            query_caching: false,
         }
     }
 }
@@ -1336,6 +1344,13 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_query_caching(self) -> Self {
+        Self {
+            query_caching: true,
+            ..self
+        }
+    }
+
     pub fn with_allocatable_compute_capacity_in_percent(
         self,
         allocatable_compute_capacity_in_percent: usize,
@@ -1401,6 +1416,7 @@ impl ExecutionTestBuilder {
                     subnet_type,
                     subnet_features: SubnetFeatures::default(),
                     ecdsa_keys_held: BTreeSet::new(),
+                    max_wasm64_memory_size: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
                 },
             );
         }
@@ -1474,6 +1490,7 @@ impl ExecutionTestBuilder {
             self.subnet_type,
             self.log.clone(),
             Arc::clone(&cycles_account_manager),
+            &ReplicaVersion::default(),
         );
         let hypervisor = Arc::new(hypervisor);
         let ingress_history_writer =
@@ -1491,14 +1508,25 @@ impl ExecutionTestBuilder {
             config,
             Arc::clone(&cycles_account_manager),
         );
+        let query_caching = if self.query_caching {
+            FlagStatus::Enabled
+        } else {
+            FlagStatus::Disabled
+        };
+        let query_stats_collector =
+            Arc::new(QueryStatsCollector::new(Arc::new(FakeStateManager::new())));
         let query_handler = InternalHttpQueryHandler::new(
             self.log,
             hypervisor,
             self.subnet_type,
-            Config::default(),
+            Config {
+                query_caching,
+                ..Config::default()
+            },
             &metrics_registry,
             self.instruction_limit,
             Arc::clone(&cycles_account_manager),
+            Arc::clone(&query_stats_collector),
         );
         ExecutionTest {
             state: Some(state),
@@ -1528,6 +1556,7 @@ impl ExecutionTestBuilder {
             caller_canister_id: self.caller_canister_id,
             exec_env,
             query_handler,
+            query_stats_collector,
             cycles_account_manager,
             metrics_registry,
             ingress_history_writer,
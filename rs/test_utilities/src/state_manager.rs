@@ -42,6 +42,12 @@ mock! {
             &self,
             _paths: &LabeledTree<()>
         ) -> Option<(Arc<ReplicatedState>, MixedHashTree, Certification)>;
+
+        fn read_certified_state_at(
+            &self,
+            _height: Height,
+            _paths: &LabeledTree<()>
+        ) -> StateManagerResult<Option<(Arc<ReplicatedState>, MixedHashTree, Certification)>>;
     }
 
     trait StateManager: StateReader {
@@ -363,6 +369,14 @@ impl StateReader for FakeStateManager {
     ) -> Option<(Arc<Self::State>, MixedHashTree, Certification)> {
         None
     }
+
+    fn read_certified_state_at(
+        &self,
+        height: Height,
+        _paths: &LabeledTree<()>,
+    ) -> StateManagerResult<Option<(Arc<Self::State>, MixedHashTree, Certification)>> {
+        self.get_state_at(height).map(|_| None)
+    }
 }
 
 /// Local helper to enable serialization and deserialization of
@@ -654,4 +668,15 @@ impl StateReader for RefMockStateManager {
     ) -> Option<(Arc<Self::State>, MixedHashTree, Certification)> {
         self.mock.read().unwrap().read_certified_state(paths)
     }
+
+    fn read_certified_state_at(
+        &self,
+        height: Height,
+        paths: &LabeledTree<()>,
+    ) -> StateManagerResult<Option<(Arc<Self::State>, MixedHashTree, Certification)>> {
+        self.mock
+            .read()
+            .unwrap()
+            .read_certified_state_at(height, paths)
+    }
 }
@@ -11,7 +11,7 @@ use ic_system_api::{
     sandbox_safe_system_state::SandboxSafeSystemState, ExecutionParameters, InstructionLimits,
     ModificationTracking, SystemApiImpl,
 };
-use ic_types::{ComputeAllocation, NumInstructions};
+use ic_types::{ComputeAllocation, NumBytes, NumInstructions, MAX_WASM64_MEMORY_IN_BYTES};
 use ic_wasm_types::BinaryEncodedWasm;
 
 use crate::{
@@ -109,6 +109,7 @@ impl WasmtimeInstanceBuilder {
                 compute_allocation: ComputeAllocation::default(),
                 subnet_type: self.subnet_type,
                 execution_mode: ExecutionMode::Replicated,
+                wasm64_memory_limit: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
             },
             AvailableMemory::new(i64::MAX / 2, i64::MAX / 2),
             Memory::default(),
@@ -119,6 +119,7 @@ fn test_valid_query_followed_by_garbage(handle: &IcHandle, ctx: &ic_fondue::pot:
                 sender: Blob(vec![4]), // the anonymous user.
                 ingress_expiry: expiry_time().as_nanos() as u64,
                 nonce: None,
+                height: None,
             },
         },
         sender_delegation: None,
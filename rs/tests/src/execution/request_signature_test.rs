@@ -242,6 +242,7 @@ async fn test_request_with_empty_signature_fails<T: Identity + 'static>(
             sender: Blob(identity.sender().unwrap().as_slice().to_vec()),
             ingress_expiry: expiry_time().as_nanos() as u64,
             nonce: None,
+            height: None,
         },
     };
 
@@ -318,6 +319,7 @@ async fn test_request_signed_by_another_identity_fails<
             sender: Blob(identity1.sender().unwrap().as_slice().to_vec()),
             ingress_expiry: expiry_time().as_nanos() as u64,
             nonce: None,
+            height: None,
         },
     };
 
@@ -395,6 +397,7 @@ async fn test_request_with_valid_signature_but_wrong_sender_fails<
             sender: Blob(identity1.sender().unwrap().as_slice().to_vec()),
             ingress_expiry: expiry_time().as_nanos() as u64,
             nonce: None,
+            height: None,
         },
     };
 
@@ -5,7 +5,7 @@ use canister_test::{Canister, PrincipalId, RemoteTestRuntime, Runtime};
 use ic_canister_client::{Agent, Sender};
 use ic_fondue::ic_manager::IcHandle;
 use ic_icrc1::Account;
-use ic_icrc1_agent::{CallMode, Icrc1Agent, TransferArg, Value};
+use ic_icrc1_agent::{block_follower::BlockFollower, CallMode, Icrc1Agent, TransferArg, Value};
 use ic_icrc1_ledger::InitArgs;
 use ic_nns_test_utils::itest_helpers::install_rust_canister;
 use ic_registry_subnet_type::SubnetType;
@@ -219,8 +219,28 @@ fn test(handle: IcHandle, ctx: &ic_fondue::pot::Context) {
         );
         assert_eq!(
             Nat::from(amount),
-            agent.balance_of(account2, CallMode::Query).await.unwrap()
+            agent
+                .balance_of(account2.clone(), CallMode::Query)
+                .await
+                .unwrap()
+        );
+
+        // certified balance_of
+        assert_eq!(
+            Nat::from(amount),
+            agent.certified_balance_of(account2).await.unwrap()
+        );
+
+        // block follower: the mint and the transfer, verified up to the
+        // certified tip
+        let mut follower = BlockFollower::new(agent.clone()).with_batch_size(1);
+        let batch = follower.next_batch().await.unwrap();
+        assert_eq!(
+            vec![0, 1],
+            batch.iter().map(|(index, _)| *index).collect::<Vec<_>>()
         );
+        assert_eq!(2, follower.next_index());
+        assert!(follower.next_batch().await.unwrap().is_empty());
     });
 }
 
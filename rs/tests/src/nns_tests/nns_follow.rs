@@ -27,6 +27,7 @@ NotCovered:
 end::catalog[] */
 
 use crate::util::{get_random_nns_node_endpoint, runtime_from_url};
+use candid::Encode;
 
 use crate::driver::ic::InternetComputer;
 use ic_fondue::ic_manager::IcHandle;
@@ -579,7 +580,7 @@ async fn submit_proposal(
         url: "".to_string(),
         action: Some(proposal::Action::ExecuteNnsFunction(ExecuteNnsFunction {
             nns_function: update_type as i32,
-            payload: Vec::new(),
+            payload: Encode!().unwrap(),
         })),
     };
 
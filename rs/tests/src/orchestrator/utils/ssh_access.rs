@@ -177,6 +177,7 @@ pub(crate) fn get_updatesubnetpayload_with_keys(
         max_number_of_canisters: None,
         ssh_readonly_access: readonly_keys,
         ssh_backup_access: backup_keys,
+        max_wasm64_memory_size: None,
     }
 }
 
@@ -98,6 +98,7 @@ fn empty_subnet_update() -> UpdateSubnetPayload {
         max_number_of_canisters: None,
         ssh_readonly_access: None,
         ssh_backup_access: None,
+        max_wasm64_memory_size: None,
     }
 }
 
@@ -196,6 +196,7 @@ impl Payload<'_> for CanisterStatusResult {}
 ///     memory_size: nat;
 ///     cycles: nat;
 ///     idle_cycles_burned_per_day: nat;
+///     query_stats: query_stats;
 /// })`
 #[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
 pub struct CanisterStatusResultV2 {
@@ -209,6 +210,7 @@ pub struct CanisterStatusResultV2 {
     balance: Vec<(Vec<u8>, candid::Nat)>,
     freezing_threshold: candid::Nat,
     idle_cycles_burned_per_day: candid::Nat,
+    query_stats: QueryStatsResult,
 }
 
 impl CanisterStatusResultV2 {
@@ -224,6 +226,7 @@ impl CanisterStatusResultV2 {
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
         idle_cycles_burned_per_day: u128,
+        query_stats: QueryStatsResult,
     ) -> Self {
         Self {
             status,
@@ -243,6 +246,7 @@ impl CanisterStatusResultV2 {
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
+            query_stats,
         }
     }
 
@@ -277,6 +281,66 @@ impl CanisterStatusResultV2 {
     pub fn idle_cycles_burned_per_day(&self) -> u128 {
         self.idle_cycles_burned_per_day.0.to_u128().unwrap()
     }
+
+    pub fn query_stats(&self) -> &QueryStatsResult {
+        &self.query_stats
+    }
+}
+
+/// The totals of the query calls served by a canister, aggregated over all
+/// the nodes of its subnet.
+///
+/// Struct used for encoding/decoding
+/// `(record {
+///     num_calls_total: nat;
+///     num_instructions_total: nat;
+///     request_payload_bytes_total: nat;
+///     response_payload_bytes_total: nat;
+/// })`
+#[derive(Clone, CandidType, Debug, Deserialize, Eq, PartialEq)]
+pub struct QueryStatsResult {
+    num_calls_total: candid::Nat,
+    num_instructions_total: candid::Nat,
+    request_payload_bytes_total: candid::Nat,
+    response_payload_bytes_total: candid::Nat,
+}
+
+impl QueryStatsResult {
+    pub fn new(
+        num_calls_total: u64,
+        num_instructions_total: u64,
+        request_payload_bytes_total: u64,
+        response_payload_bytes_total: u64,
+    ) -> Self {
+        Self {
+            num_calls_total: candid::Nat::from(num_calls_total),
+            num_instructions_total: candid::Nat::from(num_instructions_total),
+            request_payload_bytes_total: candid::Nat::from(request_payload_bytes_total),
+            response_payload_bytes_total: candid::Nat::from(response_payload_bytes_total),
+        }
+    }
+
+    pub fn num_calls_total(&self) -> u64 {
+        self.num_calls_total.0.to_u64().unwrap()
+    }
+
+    pub fn num_instructions_total(&self) -> u64 {
+        self.num_instructions_total.0.to_u64().unwrap()
+    }
+
+    pub fn request_payload_bytes_total(&self) -> u64 {
+        self.request_payload_bytes_total.0.to_u64().unwrap()
+    }
+
+    pub fn response_payload_bytes_total(&self) -> u64 {
+        self.response_payload_bytes_total.0.to_u64().unwrap()
+    }
+}
+
+impl Default for QueryStatsResult {
+    fn default() -> Self {
+        Self::new(0, 0, 0, 0)
+    }
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
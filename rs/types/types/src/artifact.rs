@@ -15,6 +15,7 @@
 //! defined in the chunkable module.
 use crate::{
     canister_http::{CanisterHttpResponseAttribute, CanisterHttpResponseShare},
+    chunkable::ChunkId,
     consensus::{certification::CertificationMessageHash, ConsensusMessageHash},
     crypto::{CryptoHash, CryptoHashOf},
     filetree_sync::{FileTreeSyncArtifact, FileTreeSyncId},
@@ -68,6 +69,19 @@ pub enum ArtifactAttribute {
     StateSync(StateSyncAttribute),
 }
 
+impl ArtifactAttribute {
+    /// Returns the id under which chunk `chunk_id` of the artifact is requested
+    /// from a peer that advertised it with this attribute.
+    pub fn chunk_request_id(&self, chunk_id: ChunkId) -> ChunkId {
+        match self {
+            ArtifactAttribute::StateSync(attribute) if attribute.supports_compressed_chunks => {
+                crate::state_sync::compressed_chunk_id(chunk_id)
+            }
+            _ => chunk_id,
+        }
+    }
+}
+
 /// Artifact identifier type.
 #[derive(From, TryInto, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[try_into(owned, ref, ref_mut)]
@@ -497,6 +511,8 @@ pub struct StateSyncArtifactId {
 type GetStateSyncChunk =
     fn(file_path: std::path::PathBuf, offset: u64, len: u32) -> std::io::Result<Vec<u8>>;
 
+type CompressStateSyncChunk = fn(chunk: Vec<u8>) -> Vec<u8>;
+
 /// State sync message.
 //
 // NOTE: StateSyncMessage is never persisted or transferred over the wire
@@ -516,6 +532,10 @@ pub struct StateSyncMessage {
 
     #[serde(skip_serializing, skip_deserializing)]
     pub get_state_sync_chunk: Option<GetStateSyncChunk>,
+
+    /// Encodes a chunk for peers that requested it compressed.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub compress_state_sync_chunk: Option<CompressStateSyncChunk>,
 }
 
 // We need a custom Hash instance to skip checkpoint_root in order
@@ -546,6 +566,14 @@ pub struct StateSyncAttribute {
     // Note: the root hash is also an attribute so that we can access it from
     // the priority function.
     pub root_hash: CryptoHashOfState,
+
+    /// Whether the advertising peer serves compressed chunks, see
+    /// `state_sync::COMPRESSED_CHUNK_BIT`.
+    ///
+    /// NOTE: this field must remain the last one. Replicas that don't know it
+    /// ignore it as a trailing byte, and adverts of such replicas, which lack
+    /// it, are decoded with `false`; see `p2p::GossipAdvert`.
+    pub supports_compressed_chunks: bool,
 }
 
 /// State sync filter is by height.
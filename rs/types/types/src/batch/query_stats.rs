@@ -0,0 +1,180 @@
+use crate::{node_id_into_protobuf, node_id_try_from_protobuf, CanisterId, CountBytes, Height};
+use ic_base_types::NodeId;
+use ic_protobuf::types::v1 as pb;
+use phantom_newtype::AmountOf;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+pub struct QueryStatsEpochTag;
+/// An epoch over which each node collects query statistics before reporting
+/// them through consensus.
+pub type QueryStatsEpoch = AmountOf<QueryStatsEpochTag, u64>;
+
+/// The number of block heights in a query stats epoch.
+pub const QUERY_STATS_EPOCH_LENGTH: u64 = 600;
+
+/// Returns the query stats epoch that contains `height`.
+pub fn epoch_from_height(height: Height) -> QueryStatsEpoch {
+    QueryStatsEpoch::from(height.get() / QUERY_STATS_EPOCH_LENGTH)
+}
+
+/// Statistics about the query calls served by a canister.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QueryStats {
+    /// The number of query calls.
+    pub num_calls: u64,
+    /// The number of instructions executed by the query calls.
+    pub num_instructions: u64,
+    /// The total size of the query arguments in bytes.
+    pub ingress_payload_size: u64,
+    /// The total size of the replies and reject messages in bytes.
+    pub egress_payload_size: u64,
+}
+
+impl QueryStats {
+    /// Adds `other` to these statistics, saturating at the numeric bounds.
+    pub fn saturating_accumulate(&mut self, other: &QueryStats) {
+        self.num_calls = self.num_calls.saturating_add(other.num_calls);
+        self.num_instructions = self.num_instructions.saturating_add(other.num_instructions);
+        self.ingress_payload_size = self
+            .ingress_payload_size
+            .saturating_add(other.ingress_payload_size);
+        self.egress_payload_size = self
+            .egress_payload_size
+            .saturating_add(other.egress_payload_size);
+    }
+}
+
+/// The query statistics of a single canister, as reported by a node.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CanisterQueryStats {
+    pub canister_id: CanisterId,
+    pub stats: QueryStats,
+}
+
+impl CountBytes for CanisterQueryStats {
+    fn count_bytes(&self) -> usize {
+        std::mem::size_of::<CanisterQueryStats>()
+    }
+}
+
+/// Payload that contains the query statistics a node collected during a
+/// finished epoch.
+///
+/// Each node reports its statistics for an epoch at most once, in a block it
+/// proposed itself; the canister entries are sorted by canister id.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QueryStatsPayload {
+    pub epoch: QueryStatsEpoch,
+    pub proposer: NodeId,
+    pub canister_stats: Vec<CanisterQueryStats>,
+}
+
+impl CountBytes for QueryStatsPayload {
+    fn count_bytes(&self) -> usize {
+        std::mem::size_of::<QueryStatsEpoch>()
+            + std::mem::size_of::<NodeId>()
+            + self
+                .canister_stats
+                .iter()
+                .map(CountBytes::count_bytes)
+                .sum::<usize>()
+    }
+}
+
+impl From<&QueryStatsPayload> for pb::QueryStatsPayload {
+    fn from(payload: &QueryStatsPayload) -> Self {
+        Self {
+            epoch: payload.epoch.get(),
+            proposer: Some(node_id_into_protobuf(payload.proposer)),
+            canister_stats: payload
+                .canister_stats
+                .iter()
+                .map(|entry| pb::CanisterQueryStats {
+                    canister_id: Some(pb::CanisterId::from(entry.canister_id)),
+                    num_calls: entry.stats.num_calls,
+                    num_instructions: entry.stats.num_instructions,
+                    ingress_payload_size: entry.stats.ingress_payload_size,
+                    egress_payload_size: entry.stats.egress_payload_size,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::QueryStatsPayload> for QueryStatsPayload {
+    type Error = String;
+
+    fn try_from(payload: pb::QueryStatsPayload) -> Result<Self, Self::Error> {
+        let proposer = payload
+            .proposer
+            .ok_or_else(|| "Error: query_stats_payload does not contain a proposer".to_string())
+            .and_then(|proposer| {
+                node_id_try_from_protobuf(proposer).map_err(|e| format!("{:?}", e))
+            })?;
+        Ok(QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(payload.epoch),
+            proposer,
+            canister_stats: payload
+                .canister_stats
+                .into_iter()
+                .map(|entry| {
+                    let canister_id = entry
+                        .canister_id
+                        .ok_or_else(|| "No canister id on canister query stats".to_string())
+                        .and_then(|canister_id| {
+                            CanisterId::try_from(canister_id)
+                                .map_err(|e| format!("Proxy decode error {:?}", e))
+                        })?;
+                    Ok(CanisterQueryStats {
+                        canister_id,
+                        stats: QueryStats {
+                            num_calls: entry.num_calls,
+                            num_instructions: entry.num_instructions,
+                            ingress_payload_size: entry.ingress_payload_size,
+                            egress_payload_size: entry.egress_payload_size,
+                        },
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_base_types::PrincipalId;
+
+    #[test]
+    fn query_stats_payload_proto_roundtrip() {
+        let payload = QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(7),
+            proposer: NodeId::from(PrincipalId::new_node_test_id(1)),
+            canister_stats: vec![CanisterQueryStats {
+                canister_id: CanisterId::from_u64(3),
+                stats: QueryStats {
+                    num_calls: 2,
+                    num_instructions: 1_000,
+                    ingress_payload_size: 10,
+                    egress_payload_size: 20,
+                },
+            }],
+        };
+        let proto = pb::QueryStatsPayload::from(&payload);
+        assert_eq!(QueryStatsPayload::try_from(proto).unwrap(), payload);
+    }
+
+    #[test]
+    fn epoch_from_height_rounds_down() {
+        assert_eq!(epoch_from_height(Height::new(0)).get(), 0);
+        assert_eq!(
+            epoch_from_height(Height::new(QUERY_STATS_EPOCH_LENGTH - 1)).get(),
+            0
+        );
+        assert_eq!(
+            epoch_from_height(Height::new(QUERY_STATS_EPOCH_LENGTH)).get(),
+            1
+        );
+    }
+}
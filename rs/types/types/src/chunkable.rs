@@ -141,7 +141,8 @@ chunkable_artifact_impl! {CanisterHttpResponseShare, |self|
 }
 
 impl ChunkableArtifact for StateSyncMessage {
-    fn get_chunk(self: Box<Self>, chunk_id: ChunkId) -> Option<ArtifactChunk> {
+    fn get_chunk(self: Box<Self>, requested_id: ChunkId) -> Option<ArtifactChunk> {
+        let (chunk_id, compressed) = crate::state_sync::decode_chunk_id(requested_id);
         let buf = if chunk_id == crate::state_sync::MANIFEST_CHUNK {
             crate::state_sync::encode_manifest(&self.manifest)
         } else if let Some(chunk) = self
@@ -158,9 +159,15 @@ impl ChunkableArtifact for StateSyncMessage {
         } else {
             return None;
         };
+        let buf = if compressed {
+            let compress_state_sync_chunk = self.compress_state_sync_chunk?;
+            compress_state_sync_chunk(buf)
+        } else {
+            buf
+        };
 
         Some(ArtifactChunk::new(
-            chunk_id,
+            requested_id,
             ArtifactChunkData::SemiStructuredChunkData(buf),
         ))
     }
@@ -1187,6 +1187,7 @@ impl From<&Block> for pb::Block {
             ingress_payload,
             self_validating_payload,
             canister_http_payload,
+            query_stats_payload,
             ecdsa_summary,
         ) = if payload.is_summary() {
             (
@@ -1195,6 +1196,7 @@ impl From<&Block> for pb::Block {
                 None,
                 None,
                 None,
+                None,
                 payload
                     .as_summary()
                     .ecdsa
@@ -1209,6 +1211,7 @@ impl From<&Block> for pb::Block {
                 Some(pb::IngressPayload::from(&batch.ingress)),
                 Some(pb::SelfValidatingPayload::from(&batch.self_validating)),
                 Some(pb::CanisterHttpPayload::from(&batch.canister_http)),
+                batch.query_stats.as_ref().map(pb::QueryStatsPayload::from),
                 None,
             )
         };
@@ -1225,6 +1228,7 @@ impl From<&Block> for pb::Block {
             ingress_payload,
             self_validating_payload,
             canister_http_payload,
+            query_stats_payload,
             ecdsa_summary,
             payload_hash: block.payload.get_hash().clone().get().0,
         }
@@ -1260,6 +1264,10 @@ impl TryFrom<pb::Block> for Block {
                 .map(crate::batch::CanisterHttpPayload::try_from)
                 .transpose()?
                 .unwrap_or_default(),
+            block
+                .query_stats_payload
+                .map(crate::batch::QueryStatsPayload::try_from)
+                .transpose()?,
         );
         let payload = match dkg_payload {
             dkg::Payload::Summary(summary) => {
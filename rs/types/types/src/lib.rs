@@ -483,6 +483,12 @@ pub const MAX_STABLE_MEMORY_IN_BYTES: u64 = 8 * GB;
 /// it is public and `u64` (`NumBytes` cannot be used in const expressions).
 pub const MAX_WASM_MEMORY_IN_BYTES: u64 = 4 * GB;
 
+/// The upper limit on the size of a 64-bit Wasm memory. Subnets can lower it
+/// through `max_wasm64_memory_size` in their subnet record.
+/// This constant is used by other crates to define other constants, that's why
+/// it is public and `u64` (`NumBytes` cannot be used in const expressions).
+pub const MAX_WASM64_MEMORY_IN_BYTES: u64 = 32 * GB;
+
 const MIN_MEMORY_ALLOCATION: NumBytes = NumBytes::new(0);
 pub const MAX_MEMORY_ALLOCATION: NumBytes =
     NumBytes::new(MAX_STABLE_MEMORY_IN_BYTES + MAX_WASM_MEMORY_IN_BYTES);
@@ -101,6 +101,10 @@ pub struct HttpUserQuery {
     // Do not include omitted fields in MessageId calculation
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nonce: Option<Blob>,
+    /// The height of the certified state to execute the query against. The
+    /// query is executed against the latest certified state if omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
 }
 
 /// Describes the contents of a /api/v2/canister/_/query request.
@@ -177,6 +181,9 @@ impl HttpUserQuery {
         if let Some(nonce) = &self.nonce {
             map.insert("nonce".to_string(), Bytes(nonce.0.clone()));
         }
+        if let Some(height) = self.height {
+            map.insert("height".to_string(), U64(height));
+        }
         hash_of_map(&map)
     }
 }
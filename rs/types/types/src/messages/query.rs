@@ -4,7 +4,7 @@ use crate::{
         message_id::hash_of_map, HasCanisterId, HttpRequestError, HttpUserQuery, MessageId,
         RawHttpRequestVal,
     },
-    CanisterId, PrincipalId, UserId,
+    CanisterId, Height, PrincipalId, UserId,
 };
 use ic_error_types::RejectCode;
 use maplit::btreemap;
@@ -20,6 +20,9 @@ pub struct UserQuery {
     pub method_payload: Vec<u8>,
     pub ingress_expiry: u64,
     pub nonce: Option<Vec<u8>>,
+    /// The height of the certified state to execute the query against, or
+    /// `None` for the latest certified state.
+    pub height: Option<Height>,
 }
 
 impl UserQuery {
@@ -38,6 +41,9 @@ impl UserQuery {
         if let Some(nonce) = &self.nonce {
             map.insert("nonce".to_string(), Bytes(nonce.clone()));
         }
+        if let Some(height) = self.height {
+            map.insert("height".to_string(), U64(height.get()));
+        }
         MessageId::from(hash_of_map(&map))
     }
 }
@@ -63,6 +69,7 @@ impl TryFrom<HttpUserQuery> for UserQuery {
             method_payload: query.arg.0,
             ingress_expiry: query.ingress_expiry,
             nonce: query.nonce.map(|n| n.0),
+            height: query.height.map(Height::from),
         })
     }
 }
@@ -140,6 +147,7 @@ mod test {
                 sender: Blob(vec![0x04]),
                 nonce: None,
                 ingress_expiry: 0,
+                height: None,
             },
             Value::Map(btreemap! {
                 text("arg") => bytes(&[][..]),
@@ -161,6 +169,7 @@ mod test {
                 sender: Blob(vec![0; 33]),
                 nonce: None,
                 ingress_expiry: 0,
+                height: None,
             },
             Value::Map(btreemap! {
                 text("arg") => bytes(b"Hello, World!"),
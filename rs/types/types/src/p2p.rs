@@ -98,10 +98,91 @@ impl TryFrom<pb::GossipAdvert> for GossipAdvert {
     type Error = ProxyDecodeError;
     fn try_from(advert: pb::GossipAdvert) -> Result<Self, Self::Error> {
         Ok(Self {
-            attribute: deserialize(&advert.attribute)?,
+            attribute: deserialize_attribute(&advert.attribute)?,
             size: advert.size as usize,
             artifact_id: deserialize(&advert.artifact_id)?,
             integrity_hash: bincode::deserialize(&advert.integrity_hash)?,
         })
     }
 }
+
+/// Deserializes the attribute of an advert.
+///
+/// Adverts of replicas predating `StateSyncAttribute::supports_compressed_chunks`
+/// lack its trailing byte. These are decoded as if the byte was `false`.
+fn deserialize_attribute(bytes: &[u8]) -> bincode::Result<ArtifactAttribute> {
+    deserialize(bytes).or_else(|err| {
+        let mut legacy_bytes = bytes.to_vec();
+        legacy_bytes.push(0);
+        match deserialize(&legacy_bytes) {
+            Ok(attribute @ ArtifactAttribute::StateSync(_)) => Ok(attribute),
+            _ => Err(err),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact::{StateSyncArtifactId, StateSyncAttribute};
+    use crate::crypto::CryptoHashOf;
+    use crate::Height;
+
+    fn state_sync_advert(supports_compressed_chunks: bool) -> GossipAdvert {
+        let root_hash = CryptoHashOf::from(CryptoHash(vec![1; 32]));
+        GossipAdvert {
+            attribute: ArtifactAttribute::StateSync(StateSyncAttribute {
+                height: Height::from(10),
+                root_hash: root_hash.clone(),
+                supports_compressed_chunks,
+            }),
+            size: 0,
+            artifact_id: ArtifactId::StateSync(StateSyncArtifactId {
+                height: Height::from(10),
+                hash: root_hash,
+            }),
+            integrity_hash: CryptoHash(vec![2; 32]),
+        }
+    }
+
+    #[test]
+    fn state_sync_advert_round_trips() {
+        for supports_compressed_chunks in [false, true] {
+            let advert = state_sync_advert(supports_compressed_chunks);
+            let pb_advert = pb::GossipAdvert::from(advert.clone());
+            assert_eq!(GossipAdvert::try_from(pb_advert).unwrap(), advert);
+        }
+    }
+
+    #[test]
+    fn state_sync_advert_without_compression_flag_is_decoded() {
+        let mut pb_advert = pb::GossipAdvert::from(state_sync_advert(false));
+        // Adverts of older replicas lack the trailing flag.
+        pb_advert.attribute.pop();
+        assert_eq!(
+            GossipAdvert::try_from(pb_advert).unwrap(),
+            state_sync_advert(false)
+        );
+    }
+
+    #[test]
+    fn older_replicas_ignore_the_compression_flag() {
+        #[derive(Deserialize)]
+        struct LegacyStateSyncAttribute {
+            height: Height,
+            root_hash: crate::CryptoHashOfState,
+        }
+
+        let advert = state_sync_advert(true);
+        let bytes = match &advert.attribute {
+            ArtifactAttribute::StateSync(attribute) => serialize(attribute).unwrap(),
+            _ => unreachable!(),
+        };
+        let legacy: LegacyStateSyncAttribute = deserialize(&bytes).unwrap();
+        assert_eq!(legacy.height, Height::from(10));
+        assert_eq!(
+            legacy.root_hash,
+            CryptoHashOf::from(CryptoHash(vec![1; 32]))
+        );
+    }
+}
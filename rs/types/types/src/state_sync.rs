@@ -72,6 +72,26 @@ use std::ops::Range;
 /// Id of the manifest chunk in StateSync artifact.
 pub const MANIFEST_CHUNK: ChunkId = ChunkId::new(0);
 
+/// Bit set in the id of a requested chunk to ask the peer for the compressed
+/// encoding of the chunk.  It is only set for peers that advertised
+/// `StateSyncAttribute::supports_compressed_chunks`.
+pub const COMPRESSED_CHUNK_BIT: u32 = 1 << 31;
+
+/// Returns the id under which the compressed encoding of chunk `id` is
+/// requested.
+pub fn compressed_chunk_id(id: ChunkId) -> ChunkId {
+    ChunkId::new(id.get() | COMPRESSED_CHUNK_BIT)
+}
+
+/// Splits a requested chunk id into the id of the chunk and a flag telling
+/// whether the compressed encoding was requested.
+pub fn decode_chunk_id(id: ChunkId) -> (ChunkId, bool) {
+    (
+        ChunkId::new(id.get() & !COMPRESSED_CHUNK_BIT),
+        id.get() & COMPRESSED_CHUNK_BIT != 0,
+    )
+}
+
 /// An entry of the file table.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct FileInfo {
@@ -42,6 +42,8 @@ pub enum WasmValidationError {
     InvalidDataSection(String),
     /// Module contains an invalid custom section
     InvalidCustomSection(String),
+    /// Module contains an invalid memory section
+    InvalidMemorySection(String),
     /// Module contains too many globals.
     TooManyGlobals { defined: usize, allowed: usize },
     /// Module contains too many functions.
@@ -83,6 +85,9 @@ impl std::fmt::Display for WasmValidationError {
             Self::InvalidCustomSection(err) => {
                 write!(f, "Wasm module has an invalid custom section. {}", err)
             }
+            Self::InvalidMemorySection(err) => {
+                write!(f, "Wasm module has an invalid memory section. {}", err)
+            }
             Self::TooManyGlobals { defined, allowed } => write!(
                 f,
                 "Wasm module defined {} globals which exceeds the maximum number allowed {}.",
@@ -129,6 +134,8 @@ pub enum WasmInstrumentationError {
         len: usize,
     },
     InvalidExport(String),
+    /// Module contains an invalid memory section
+    InvalidMemorySection(String),
 }
 
 impl std::fmt::Display for WasmInstrumentationError {
@@ -153,6 +160,9 @@ impl std::fmt::Display for WasmInstrumentationError {
             Self::InvalidExport(err) => {
                 write!(f, "Failed to export: {}", err)
             }
+            Self::InvalidMemorySection(err) => {
+                write!(f, "Wasm module has an invalid memory section: {}", err)
+            }
         }
     }
 }